@@ -0,0 +1,143 @@
+// Validation results cached by workflow content, so validating a repository
+// again only re-validates the workflows that changed
+use models::ValidationResult;
+use serde_yaml::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use utils::hash::Fnv;
+
+/// Bump whenever a validation rule changes what it reports, so results of
+/// the old rules are never reused
+const RULES_REVISION: u32 = 4;
+
+static DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// Validate every workflow from scratch, e.g. for `wrkflw validate --no-cache`
+pub fn disable() {
+    DISABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    !DISABLED.load(Ordering::Relaxed)
+}
+
+pub struct ValidationCache {
+    dir: PathBuf,
+}
+
+impl ValidationCache {
+    /// The cache in `<root>/.wrkflw/cache`, unless caching is disabled
+    pub fn for_repository(root: &Path) -> Option<Self> {
+        if DISABLED.load(Ordering::Relaxed) {
+            return None;
+        }
+        Some(ValidationCache {
+            dir: root.join(".wrkflw").join("cache").join("validation"),
+        })
+    }
+
+    pub fn get(&self, key: &str) -> Option<ValidationResult> {
+        let cached = fs::read_to_string(self.dir.join(format!("{}.json", key))).ok()?;
+        serde_json::from_str(&cached).ok()
+    }
+
+    /// Store a result; a cache that can't be written only costs speed
+    pub fn put(&self, key: &str, result: &ValidationResult) {
+        let Ok(json) = serde_json::to_string(result) else {
+            return;
+        };
+        if fs::create_dir_all(&self.dir).is_ok() {
+            let _ = fs::write(self.dir.join(format!("{}.json", key)), json);
+        }
+    }
+}
+
+/// Key of a workflow's validation result: a hash of the rule set, its
+/// content and the local actions and reusable workflows it uses from the
+/// repository at `root`, whose existence the rules check
+pub fn cache_key(content: &str, workflow: Option<&Value>, root: &Path) -> String {
+    let mut hash = Fnv::new();
+    hash.write(env!("CARGO_PKG_VERSION").as_bytes());
+    hash.write(&RULES_REVISION.to_le_bytes());
+    hash.write(content.as_bytes());
+
+    for reference in workflow.map(local_references).unwrap_or_default() {
+        hash.write(reference.as_bytes());
+        let path = root.join(reference);
+        let definition = if path.is_dir() {
+            ["action.yml", "action.yaml"]
+                .iter()
+                .find_map(|name| fs::read(path.join(name)).ok())
+        } else {
+            fs::read(&path).ok()
+        };
+        match definition {
+            Some(bytes) => hash.write(&bytes),
+            None => hash.write(&[u8::from(path.exists())]),
+        }
+    }
+    format!("{:016x}", hash.finish())
+}
+
+/// The `uses: ./...` references of jobs and their steps
+fn local_references(workflow: &Value) -> Vec<&str> {
+    let Some(Value::Mapping(jobs)) = workflow.get("jobs") else {
+        return Vec::new();
+    };
+    let steps = jobs
+        .values()
+        .filter_map(|job| job.get("steps").and_then(Value::as_sequence))
+        .flatten();
+    jobs.values()
+        .chain(steps)
+        .filter_map(|node| node.get("uses").and_then(Value::as_str))
+        .filter(|reference| reference.starts_with("./"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_follow_content_and_local_references() {
+        let workflow = "on: push\njobs:\n  build:\n    steps:\n      - uses: ./my-action # local\n      - uses: actions/checkout@v4\n  call:\n    uses: ./.github/workflows/reusable.yml\n";
+        let key = |content: &str, root: &Path| {
+            let value: Value = serde_yaml::from_str(content).unwrap();
+            cache_key(content, Some(&value), root)
+        };
+        let value: Value = serde_yaml::from_str(workflow).unwrap();
+        assert_eq!(
+            local_references(&value),
+            ["./.github/workflows/reusable.yml", "./my-action"]
+        );
+
+        // Local actions are looked up in the repository, wherever wrkflw runs
+        let repo = tempfile::tempdir().unwrap();
+        let missing = key(workflow, repo.path());
+        assert_eq!(missing, key(workflow, repo.path()));
+        assert_ne!(
+            missing,
+            key(&workflow.replace("push", "pull_request"), repo.path())
+        );
+        fs::create_dir(repo.path().join("my-action")).unwrap();
+        fs::write(repo.path().join("my-action/action.yml"), "name: a\n").unwrap();
+        let present = key(workflow, repo.path());
+        assert_ne!(missing, present);
+        fs::write(repo.path().join("my-action/action.yml"), "name: b\n").unwrap();
+        assert_ne!(present, key(workflow, repo.path()));
+
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ValidationCache {
+            dir: dir.path().join("validation"),
+        };
+        let mut result = ValidationResult::new();
+        result.add_issue_at("missing-name", "", "Workflow is missing a name".to_string());
+        cache.put("abc", &result);
+        let cached = cache.get("abc").unwrap();
+        assert!(!cached.is_valid);
+        assert_eq!(cached.issues, result.issues);
+        assert!(cache.get("def").is_none());
+    }
+}
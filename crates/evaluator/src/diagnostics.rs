@@ -0,0 +1,145 @@
+// Positions of diagnostics in a workflow's source, and how they're shown to
+// people by `wrkflw validate`
+use colored::*;
+use models::{Diagnostic, DiagnosticSeverity, ValidationResult};
+use parser::spans::SpanIndex;
+use std::path::Path;
+
+/// Fill in the line and column of a result's diagnostics from their YAML
+/// paths
+pub fn locate(result: &mut ValidationResult, content: &str) {
+    locate_all(&mut result.diagnostics, content);
+}
+
+/// Fill in the line and column of `diagnostics` that don't have one from
+/// their YAML paths. Those about the whole document get no position.
+pub fn locate_all(diagnostics: &mut [Diagnostic], content: &str) {
+    if diagnostics
+        .iter()
+        .all(|d| d.path.is_empty() || d.line.is_some())
+    {
+        return;
+    }
+
+    let spans = SpanIndex::new(content);
+    for diagnostic in diagnostics {
+        if diagnostic.line.is_some() || diagnostic.path.is_empty() {
+            continue;
+        }
+        if let Some((line, column)) = spans.locate(&diagnostic.path) {
+            diagnostic.line = Some(line);
+            diagnostic.column = Some(column);
+        }
+    }
+}
+
+/// A diagnostic as the compiler shows errors: the rule and message, where it
+/// is, and the line of `content` it's on with the column marked
+pub fn render(file: &Path, content: &str, diagnostic: &Diagnostic) -> String {
+    let severity = match diagnostic.severity {
+        DiagnosticSeverity::Error => "error".red().bold(),
+        DiagnosticSeverity::Warning => "warning".yellow().bold(),
+    };
+    let mut out = format!(
+        "{}{}: {}",
+        severity,
+        format!("[{}]", diagnostic.code).bold(),
+        diagnostic.message
+    );
+
+    let Some(line) = diagnostic.line else {
+        out.push_str(&format!("\n {} {}", "-->".blue(), file.display()));
+        return out;
+    };
+    let column = diagnostic.column.unwrap_or(1);
+    let number = line.to_string();
+    let gutter = " ".repeat(number.len());
+    out.push_str(&format!(
+        "\n{}{} {}:{}:{}",
+        gutter,
+        "-->".blue(),
+        file.display(),
+        line,
+        column
+    ));
+
+    let Some(source) = content.lines().nth(line - 1) else {
+        return out;
+    };
+    let bar = "|".blue();
+    let marker: String = source
+        .chars()
+        .take(column.saturating_sub(1))
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    out.push_str(&format!(
+        "\n{} {}\n{} {} {}\n{} {} {}{}",
+        gutter,
+        bar,
+        number.blue(),
+        bar,
+        source,
+        gutter,
+        bar,
+        marker,
+        "^".red().bold()
+    ));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locates_and_renders_diagnostics() {
+        colored::control::set_override(false);
+        let content = "\
+name: CI
+on: push
+jobs:
+  build:
+    steps:
+      - run: make
+        if: success() &&
+";
+        let mut result = ValidationResult::new();
+        result.add_issue_at(
+            "missing-runs-on",
+            "jobs.build",
+            "Job 'build' is missing 'runs-on' field".to_string(),
+        );
+        result.add_issue_at(
+            "invalid-expression",
+            "jobs.build.steps[0].if",
+            "Invalid 'if' expression".to_string(),
+        );
+        result.add_warning_at("odd-workflow", "", "Something odd".to_string());
+        locate(&mut result, content);
+
+        let positions: Vec<_> = result
+            .diagnostics
+            .iter()
+            .map(|d| (d.line, d.column))
+            .collect();
+        assert_eq!(
+            positions,
+            [(Some(4), Some(3)), (Some(7), Some(9)), (None, None)]
+        );
+
+        let file = Path::new("ci.yml");
+        assert_eq!(
+            render(file, content, &result.diagnostics[1]),
+            "\
+error[invalid-expression]: Invalid 'if' expression
+ --> ci.yml:7:9
+  |
+7 |         if: success() &&
+  |         ^"
+        );
+        assert_eq!(
+            render(file, content, &result.diagnostics[2]),
+            "warning[odd-workflow]: Something odd\n --> ci.yml"
+        );
+    }
+}
@@ -1,77 +1,251 @@
 use colored::*;
 use serde_yaml::{self, Value};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use models::ValidationResult;
-use validators::{validate_jobs, validate_triggers};
+use models::github::Workflow;
+use models::{Diagnostic, DiagnosticSeverity, ValidationResult};
+use parser::sniff::{self, Platform};
+use validators::{
+    validate_action_metadata, validate_env_names, validate_expressions, validate_hash_files,
+    validate_jobs, validate_needs_references, validate_runner_labels, validate_shells,
+    validate_triggers, validate_working_directories,
+};
+
+pub mod cache;
+pub mod diagnostics;
+pub mod online;
+pub mod report;
+pub mod rules;
+
+use cache::ValidationCache;
 
 pub fn evaluate_workflow_file(path: &Path, verbose: bool) -> Result<ValidationResult, String> {
     let content = fs::read_to_string(path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let root = repository_root(path);
+    // Parsed once for the cache key, the checks and those made outside the
+    // cache alike
+    let workflow: Result<Value, String> =
+        serde_yaml::from_str(&content).map_err(|e| format!("Invalid YAML: {}", e));
+
+    let cache = ValidationCache::for_repository(&root);
+    let key = cache::cache_key(&content, workflow.as_ref().ok(), &root);
+    let mut result = match cache.as_ref().and_then(|cache| cache.get(&key)) {
+        Some(result) => result,
+        None => {
+            let result = evaluate_content(workflow.as_ref()?, &content, &root)?;
+            if let Some(cache) = &cache {
+                cache.put(&key, &result);
+            }
+            result
+        }
+    };
+
+    // hashFiles() patterns depend on the repository's files rather than the
+    // workflow, so they're checked on every run
+    if let Ok(workflow) = &workflow {
+        if content.contains("hashFiles") {
+            validate_hash_files(workflow, &root, &mut result);
+        }
+        // Tags and branches move, so refs are looked up outside the cache too
+        if online::is_enabled() {
+            online::validate_actions(workflow, &root, &mut result);
+        }
+        // As are labels, which a project's .wrkflw.toml can map
+        if result.is_valid {
+            validate_runner_labels(workflow, &rules::runner_labels(), &mut result);
+        }
+    }
+    rules::apply(&mut result);
+    diagnostics::locate(&mut result, &content);
+
+    if verbose && result.is_valid {
+        println!(
+            "{} Validated structure of workflow: {}",
+            "✓".green(),
+            path.display()
+        );
+    }
 
-    // Parse YAML content
-    let workflow: Value =
+    Ok(result)
+}
+
+/// Validate an action's metadata file, action.yml. These checks are quick
+/// and need nothing online, so results aren't cached.
+pub fn evaluate_action_file(path: &Path) -> Result<ValidationResult, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let action: Value =
         serde_yaml::from_str(&content).map_err(|e| format!("Invalid YAML: {}", e))?;
+    let mut result = validate_action_metadata(&action);
+    rules::apply(&mut result);
+    diagnostics::locate(&mut result, &content);
+    Ok(result)
+}
+
+/// Validate a file as one of `platform`, the way `wrkflw validate` does.
+/// GitLab pipelines, CircleCI and Azure configs get their own checks.
+pub fn evaluate_file(
+    path: &Path,
+    platform: Platform,
+    verbose: bool,
+) -> Result<ValidationResult, String> {
+    match platform {
+        Platform::GitHub => evaluate_workflow_file(path, verbose),
+        Platform::GitLab => evaluate_gitlab_pipeline(path),
+        Platform::CircleCi | Platform::Azure => evaluate_ci_config(path, platform),
+        Platform::Action => evaluate_action_file(path),
+    }
+}
 
+/// Validate a file as what it looks like, refusing to guess when it looks
+/// like both a GitHub workflow and a GitLab pipeline
+pub fn evaluate_detected_file(path: &Path, verbose: bool) -> Result<ValidationResult, String> {
+    let detection = sniff::detect_platform(path);
+    let platform = detection.platform().ok_or_else(|| {
+        format!(
+            "{} looks like both a GitHub workflow and a GitLab pipeline ({})",
+            path.display(),
+            detection.describe()
+        )
+    })?;
+    evaluate_file(path, platform, verbose)
+}
+
+/// Structural checks of a CircleCI or Azure Pipelines config, which wrkflw
+/// doesn't run
+fn evaluate_ci_config(path: &Path, platform: Platform) -> Result<ValidationResult, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let config: Value =
+        serde_yaml::from_str(&content).map_err(|e| format!("Invalid YAML: {}", e))?;
+    let mut result = match platform {
+        Platform::Azure => validators::validate_azure_pipeline(&config),
+        _ => validators::validate_circleci_config(&config),
+    };
+    diagnostics::locate(&mut result, &content);
+    Ok(result)
+}
+
+/// Checks of a GitLab pipeline, with its includes and `extends` resolved.
+/// Findings in included files have no position in this one.
+fn evaluate_gitlab_pipeline(path: &Path) -> Result<ValidationResult, String> {
+    let pipeline = parser::gitlab::parse_pipeline(path).map_err(|e| e.to_string())?;
+    let mut result = validators::validate_gitlab_pipeline(&pipeline);
+    if let Ok(content) = fs::read_to_string(path) {
+        diagnostics::locate(&mut result, &content);
+    }
+    Ok(result)
+}
+
+/// The checks that only depend on a workflow's content and the local
+/// actions it uses
+fn evaluate_content(
+    workflow: &Value,
+    content: &str,
+    root: &Path,
+) -> Result<ValidationResult, String> {
     let mut result = ValidationResult::new();
 
-    // Check for required structure
-    if !workflow.is_mapping() {
-        result.add_issue("Workflow file is not a valid YAML mapping".to_string());
+    // A docker-compose file or Kubernetes manifest would only produce a
+    // list of missing workflow keys, so say what it looks like instead
+    if let Some(kind) = parser::sniff::non_workflow_kind(workflow) {
+        document_issue(
+            &mut result,
+            "not-a-workflow",
+            parser::sniff::non_workflow_message(&kind),
+        );
         return Ok(result);
     }
 
-    // Check if name exists
-    if workflow.get("name").is_none() {
-        // Check if this might be a reusable workflow caller before reporting missing name
-        let has_reusable_workflow_job = if let Some(Value::Mapping(jobs)) = workflow.get("jobs") {
-            jobs.values().any(|job| {
-                if let Some(job_config) = job.as_mapping() {
-                    job_config.contains_key(Value::String("uses".to_string()))
-                } else {
-                    false
-                }
-            })
-        } else {
-            false
-        };
-
-        // Only report missing name if it's not a workflow with reusable workflow jobs
-        if !has_reusable_workflow_job {
-            result.add_issue("Workflow is missing a name".to_string());
-        }
+    // Check for required structure
+    if !workflow.is_mapping() {
+        document_issue(
+            &mut result,
+            "not-a-mapping",
+            "Workflow file is not a valid YAML mapping".to_string(),
+        );
+        return Ok(result);
     }
 
-    // Check if jobs section exists
+    // Check for the required sections
     match workflow.get("jobs") {
-        Some(jobs) if jobs.is_mapping() => {
-            validate_jobs(jobs, &mut result);
-        }
-        Some(_) => {
-            result.add_issue("'jobs' section is not a mapping".to_string());
-        }
-        None => {
-            result.add_issue("Workflow is missing 'jobs' section".to_string());
-        }
+        Some(jobs) if jobs.is_mapping() => {}
+        Some(_) => result.add_issue_at(
+            "invalid-jobs",
+            "jobs",
+            "'jobs' section is not a mapping".to_string(),
+        ),
+        None => document_issue(
+            &mut result,
+            "missing-jobs",
+            "Workflow is missing 'jobs' section".to_string(),
+        ),
+    }
+    if workflow.get("on").is_none() {
+        document_issue(
+            &mut result,
+            "missing-on",
+            "Workflow is missing 'on' section (triggers)".to_string(),
+        );
+    }
+    if !result.is_valid {
+        return Ok(result);
     }
 
-    // Check for valid triggers
-    match workflow.get("on") {
-        Some(on) => {
-            validate_triggers(on, &mut result);
+    // Parsed from the text rather than the value so errors say where
+    let typed: Workflow = match serde_yaml::from_str(content) {
+        Ok(typed) => typed,
+        Err(e) => {
+            let mut diagnostic = Diagnostic::new(
+                DiagnosticSeverity::Error,
+                "invalid-structure",
+                "",
+                format!("Invalid workflow structure: {}", e),
+            );
+            if let Some(location) = e.location() {
+                diagnostic.line = Some(location.line());
+                diagnostic.column = Some(location.column());
+            }
+            result.report(diagnostic);
+            return Ok(result);
         }
-        None => {
-            result.add_issue("Workflow is missing 'on' section (triggers)".to_string());
-        }
-    }
+    };
 
-    if verbose && result.is_valid {
-        println!(
-            "{} Validated structure of workflow: {}",
-            "✓".green(),
-            path.display()
+    // Workflows that only call reusable workflows may go without a name
+    if typed.name.is_none() && !typed.jobs.values().any(|job| job.uses.is_some()) {
+        document_issue(
+            &mut result,
+            "missing-name",
+            "Workflow is missing a name".to_string(),
         );
     }
 
+    validate_jobs(&typed.jobs, root, &mut result);
+    validate_shells(&typed, &mut result);
+    validate_working_directories(&typed, &mut result);
+    validate_env_names(&typed, &mut result);
+    validate_expressions(workflow, &mut result);
+    validate_needs_references(&typed, &mut result);
+
+    // Check for valid triggers
+    validate_triggers(&typed.on, &mut result);
+
     Ok(result)
 }
+
+/// The repository a workflow belongs to: the directory holding its
+/// `.github` folder, or else the workflow's own directory
+fn repository_root(path: &Path) -> PathBuf {
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    path.ancestors()
+        .find(|dir| dir.file_name().is_some_and(|name| name == ".github"))
+        .and_then(Path::parent)
+        .or_else(|| path.parent())
+        .unwrap_or(Path::new("."))
+        .to_path_buf()
+}
+
+/// An issue with the document as a whole rather than one of its nodes
+fn document_issue(result: &mut ValidationResult, code: &str, message: String) {
+    result.add_issue_at(code, "", message);
+}
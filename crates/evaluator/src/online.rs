@@ -0,0 +1,110 @@
+// Checking the `uses:` references of workflows against GitHub, which
+// `wrkflw validate` does unless given `--offline`
+use github::action_refs::{self, ActionRef, RefCache, RefResolution};
+use github::GithubClient;
+use models::ValidationResult;
+use serde_yaml::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use validators::validate_action_reference_online;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Look actions up on GitHub when validating
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Check every step's action against GitHub, using the answers cached in
+/// `<root>/.wrkflw/cache` where they're recent enough
+pub fn validate_actions(workflow: &Value, root: &Path, result: &mut ValidationResult) {
+    let Some(Value::Mapping(jobs)) = workflow.get("jobs") else {
+        return;
+    };
+    let mut uses = Vec::new();
+    for (job_name, job) in jobs {
+        let (Some(job_name), Some(Value::Sequence(steps))) = (job_name.as_str(), job.get("steps"))
+        else {
+            continue;
+        };
+        for (i, step) in steps.iter().enumerate() {
+            if let Some(action) = step.get("uses").and_then(Value::as_str) {
+                uses.push((job_name, i, action));
+            }
+        }
+    }
+
+    let mut cache = RefCache::load(&root.join(".wrkflw").join("cache").join("actions.json"));
+    let mut missing: Vec<ActionRef> = Vec::new();
+    for (_, _, action) in &uses {
+        if let Some(action) = ActionRef::parse(action) {
+            if cache.get(&action).is_none() && !missing.contains(&action) {
+                missing.push(action);
+            }
+        }
+    }
+    if !missing.is_empty() {
+        for (action, resolution) in resolve_all(missing) {
+            cache.insert(&action, resolution);
+        }
+        if crate::cache::is_enabled() {
+            cache.save();
+        }
+    }
+
+    let lookup = |action: &ActionRef| cache.get(action);
+    for (job_name, i, action) in uses {
+        validate_action_reference_online(action, job_name, i, &lookup, result);
+    }
+}
+
+/// Ask GitHub about each action. The first failed request, e.g. with no
+/// network or once the rate limit is hit, turns online checks off for the
+/// rest of the run rather than failing every lookup after it.
+fn resolve_all(actions: Vec<ActionRef>) -> HashMap<ActionRef, RefResolution> {
+    let token = std::env::var("GITHUB_TOKEN")
+        .ok()
+        .filter(|token| !token.is_empty());
+
+    // A runtime of its own, on a thread of its own, since validation may
+    // already be running inside one
+    let lookups = std::thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| (HashMap::new(), e.to_string()))?;
+        // No retries: a GitHub that can't be reached turns the checks off
+        let client = GithubClient::new(token.as_deref())
+            .map_err(|e| (HashMap::new(), e.to_string()))?
+            .with_max_attempts(1);
+        runtime.block_on(async {
+            let mut resolved = HashMap::new();
+            for action in actions {
+                match action_refs::resolve(&client, &action).await {
+                    Ok(resolution) => {
+                        resolved.insert(action, resolution);
+                    }
+                    Err(e) => return Err((resolved, e.to_string())),
+                }
+            }
+            Ok(resolved)
+        })
+    })
+    .join()
+    .unwrap_or_else(|_| Err((HashMap::new(), "lookup thread panicked".to_string())));
+
+    lookups.unwrap_or_else(|(resolved, e)| {
+        if ENABLED.swap(false, Ordering::Relaxed) {
+            eprintln!(
+                "⚠️  Could not check actions on GitHub ({}), skipping online checks; use --offline to turn them off",
+                e
+            );
+        }
+        resolved
+    })
+}
@@ -0,0 +1,269 @@
+// Machine-readable validation results, for `wrkflw validate --format json`
+// and `--format sarif`
+use models::{Diagnostic, DiagnosticSeverity, ValidationResult};
+use parser::sniff::Platform;
+use serde::Serialize;
+use serde_json::{json, Value};
+use serde_yaml::Value as Yaml;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// The validation result of one file
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FileReport {
+    pub file: PathBuf,
+    pub platform: Platform,
+    pub valid: bool,
+    pub issues: Vec<Finding>,
+    pub warnings: Vec<Finding>,
+}
+
+/// An issue or warning, with the rule that found it and where it is when
+/// that can be told
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Finding {
+    pub code: String,
+    pub message: String,
+    /// YAML path of the node it's about, e.g. `jobs.build.runs-on`, or
+    /// empty for the file as a whole
+    pub path: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+impl From<Diagnostic> for Finding {
+    fn from(diagnostic: Diagnostic) -> Self {
+        Finding {
+            code: diagnostic.code,
+            message: diagnostic.message,
+            path: diagnostic.path,
+            line: diagnostic.line,
+            column: diagnostic.column,
+        }
+    }
+}
+
+impl FileReport {
+    /// Report a validation result, or the error that kept the file from
+    /// being validated at all
+    pub fn new(
+        file: &Path,
+        platform: Platform,
+        result: Result<ValidationResult, String>,
+        extra_warnings: Vec<Diagnostic>,
+    ) -> Self {
+        let content = fs::read_to_string(file).unwrap_or_default();
+        let (valid, mut diagnostics) = match result {
+            Ok(result) => (result.is_valid, result.diagnostics),
+            Err(e) => (false, vec![file_error(&content, e)]),
+        };
+        diagnostics.extend(extra_warnings);
+        crate::diagnostics::locate_all(&mut diagnostics, &content);
+
+        let (mut issues, mut warnings) = (Vec::new(), Vec::new());
+        for diagnostic in diagnostics {
+            match diagnostic.severity {
+                DiagnosticSeverity::Error => issues.push(diagnostic.into()),
+                DiagnosticSeverity::Warning => warnings.push(diagnostic.into()),
+            }
+        }
+        FileReport {
+            file: file.to_path_buf(),
+            platform,
+            valid,
+            issues,
+            warnings,
+        }
+    }
+}
+
+/// The error that kept a file from being validated, at the YAML syntax
+/// error in it if there is one
+fn file_error(content: &str, message: String) -> Diagnostic {
+    let mut diagnostic =
+        Diagnostic::new(DiagnosticSeverity::Error, "invalid-workflow", "", message);
+    let location = serde_yaml::from_str::<Yaml>(content)
+        .err()
+        .and_then(|e| e.location());
+    if let Some(location) = location {
+        diagnostic.line = Some(location.line());
+        diagnostic.column = Some(location.column());
+    }
+    diagnostic
+}
+
+/// A SARIF 2.1.0 log of the issues and warnings in `reports`, as code
+/// scanning tools read it
+pub fn sarif(reports: &[FileReport]) -> Value {
+    let result = |report: &FileReport, finding: &Finding, level: &str| {
+        let mut location = json!({ "artifactLocation": { "uri": sarif_uri(&report.file) } });
+        if let Some(line) = finding.line {
+            location["region"] = json!({ "startLine": line });
+            if let Some(column) = finding.column {
+                location["region"]["startColumn"] = json!(column);
+            }
+        }
+        json!({
+            "ruleId": finding.code,
+            "level": level,
+            "message": { "text": finding.message },
+            "locations": [{ "physicalLocation": location }],
+        })
+    };
+    let results: Vec<Value> = reports
+        .iter()
+        .flat_map(|report| {
+            let issues = report
+                .issues
+                .iter()
+                .map(|issue| result(report, issue, "error"));
+            let warnings = report
+                .warnings
+                .iter()
+                .map(|warning| result(report, warning, "warning"));
+            issues.chain(warnings).collect::<Vec<_>>()
+        })
+        .collect();
+
+    let mut codes: Vec<&str> = reports
+        .iter()
+        .flat_map(|report| report.issues.iter().chain(&report.warnings))
+        .map(|finding| finding.code.as_str())
+        .collect();
+    codes.sort_unstable();
+    codes.dedup();
+    let rules: Vec<Value> = codes
+        .into_iter()
+        .map(|code| json!({ "id": code }))
+        .collect();
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "wrkflw",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/bahdotsh/wrkflw",
+                    "rules": rules,
+                },
+            },
+            "results": results,
+        }],
+    })
+}
+
+/// Relative paths with forward slashes, as SARIF URIs need
+fn sarif_uri(path: &Path) -> String {
+    let path = path.strip_prefix("./").unwrap_or(path);
+    path.to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions_findings_and_writes_sarif() {
+        let content = "\
+name: CI
+on: push
+jobs:
+  lint:
+    runs-on: ubuntu-latest
+    steps:
+      - run: cargo clippy
+  build:
+    needs: deploy
+    steps:
+      - uses: actions/checkout@v4
+      - name: Test
+        if: success() &&
+        run: cargo test
+";
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("ci.yml");
+        fs::write(&file, content).unwrap();
+        let mut result = ValidationResult::new();
+        result.add_issue_at(
+            "missing-runs-on",
+            "jobs.build",
+            "Job 'build' is missing 'runs-on' field".to_string(),
+        );
+        result.add_issue_at(
+            "invalid-expression",
+            "jobs.build.steps[1].if",
+            "Invalid 'if' expression".to_string(),
+        );
+        result.add_issue_at("missing-name", "", "Workflow is missing a name".to_string());
+        let positions = |report: FileReport| {
+            report
+                .issues
+                .into_iter()
+                .map(|finding| (finding.line, finding.column))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            positions(FileReport::new(
+                &file,
+                Platform::GitHub,
+                Ok(result),
+                Vec::new()
+            )),
+            [(Some(8), Some(3)), (Some(13), Some(9)), (None, None)]
+        );
+        fs::write(&file, "jobs:\n  build: [\n").unwrap();
+        let error = "Invalid YAML".to_string();
+        assert_eq!(
+            positions(FileReport::new(
+                &file,
+                Platform::GitHub,
+                Err(error),
+                Vec::new()
+            )),
+            [(Some(3), Some(1))]
+        );
+
+        let report = FileReport {
+            file: PathBuf::from("./.github/workflows/ci.yml"),
+            platform: Platform::GitHub,
+            valid: false,
+            issues: vec![Finding {
+                code: "missing-runs-on".to_string(),
+                message: "Job 'build' is missing 'runs-on' field".to_string(),
+                path: "jobs.build".to_string(),
+                line: Some(8),
+                column: Some(3),
+            }],
+            warnings: vec![Finding {
+                code: "odd-workflow".to_string(),
+                message: "Something odd".to_string(),
+                path: String::new(),
+                line: None,
+                column: None,
+            }],
+        };
+        let log = sarif(&[report]);
+        let results = log["runs"][0]["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        let location = &results[0]["locations"][0]["physicalLocation"];
+        assert_eq!(
+            location["artifactLocation"]["uri"],
+            ".github/workflows/ci.yml"
+        );
+        assert_eq!(location["region"]["startLine"], 8);
+        assert_eq!(location["region"]["startColumn"], 3);
+        assert_eq!(results[0]["ruleId"], "missing-runs-on");
+        assert_eq!(
+            log["runs"][0]["tool"]["driver"]["rules"],
+            json!([{ "id": "missing-runs-on" }, { "id": "odd-workflow" }])
+        );
+        assert_eq!(results[1]["level"], "warning");
+        assert!(results[1]["locations"][0]["physicalLocation"]
+            .get("region")
+            .is_none());
+    }
+}
@@ -0,0 +1,85 @@
+// Severities of validation rules and the runner labels set by a project's
+// .wrkflw.toml
+use models::{DiagnosticSeverity, RuleSeverity, ValidationResult};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::RwLock;
+
+static SEVERITIES: RwLock<BTreeMap<String, RuleSeverity>> = RwLock::new(BTreeMap::new());
+
+static RUNNER_LABELS: RwLock<BTreeSet<String>> = RwLock::new(BTreeSet::new());
+
+/// Report the findings of the rules in `severities` as errors or warnings,
+/// or not at all, for the rest of the run
+pub fn configure(severities: BTreeMap<String, RuleSeverity>) {
+    if let Ok(mut current) = SEVERITIES.write() {
+        *current = severities;
+    }
+}
+
+/// Accept these `runs-on` labels, which the project maps to an image or to
+/// emulation, for the rest of the run
+pub fn configure_runner_labels(labels: BTreeSet<String>) {
+    if let Ok(mut current) = RUNNER_LABELS.write() {
+        *current = labels;
+    }
+}
+
+pub fn runner_labels() -> BTreeSet<String> {
+    RUNNER_LABELS
+        .read()
+        .map(|labels| labels.clone())
+        .unwrap_or_default()
+}
+
+/// Apply the configured severities to a workflow's findings
+pub fn apply(result: &mut ValidationResult) {
+    if let Ok(severities) = SEVERITIES.read() {
+        if !severities.is_empty() {
+            apply_severities(result, &severities);
+        }
+    }
+}
+
+fn apply_severities(result: &mut ValidationResult, severities: &BTreeMap<String, RuleSeverity>) {
+    let diagnostics = std::mem::take(&mut result.diagnostics);
+    *result = ValidationResult::new();
+    for mut diagnostic in diagnostics {
+        diagnostic.severity = match severities.get(&diagnostic.code) {
+            None => diagnostic.severity,
+            Some(RuleSeverity::Error) => DiagnosticSeverity::Error,
+            Some(RuleSeverity::Warning) => DiagnosticSeverity::Warning,
+            Some(RuleSeverity::Ignore) => continue,
+        };
+        result.report(diagnostic);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overrides_rule_severities() {
+        let mut result = ValidationResult::new();
+        result.add_issue_at("missing-runs-on", "jobs.a", "no runs-on".to_string());
+        result.add_warning_at("unpinned-action", "jobs.a.steps[0]", "tag".to_string());
+        result.add_issue_at("unknown-need", "jobs.a.needs", "no such job".to_string());
+
+        let severities = BTreeMap::from([
+            ("missing-runs-on".to_string(), RuleSeverity::Warning),
+            ("unpinned-action".to_string(), RuleSeverity::Error),
+            ("unknown-need".to_string(), RuleSeverity::Ignore),
+        ]);
+        apply_severities(&mut result, &severities);
+        assert!(!result.is_valid);
+        assert_eq!(result.issues, ["tag"]);
+        assert_eq!(result.warnings, ["no runs-on"]);
+        assert_eq!(result.diagnostics.len(), 2);
+        assert_eq!(result.diagnostics[0].path, "jobs.a");
+
+        let severities = BTreeMap::from([("unpinned-action".to_string(), RuleSeverity::Ignore)]);
+        apply_severities(&mut result, &severities);
+        assert!(result.is_valid);
+        assert!(result.issues.is_empty());
+    }
+}
@@ -0,0 +1,671 @@
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Environment variable that overrides where fetched actions are cached
+pub const ACTIONS_DIR_ENV: &str = "WRKFLW_ACTIONS_DIR";
+
+/// Environment variable that overrides the host actions are cloned from
+pub const ACTIONS_URL_ENV: &str = "WRKFLW_ACTIONS_URL";
+
+/// Path actions are mounted at inside job containers
+pub const CONTAINER_ACTION_DIR: &str = "/github/action";
+
+/// Actions wrkflw does itself rather than fetching them
+const BUILT_IN_ACTIONS: &[&str] = &[
+    "actions/checkout",
+    "actions/upload-artifact",
+    "actions/download-artifact",
+];
+
+/// Whether `uses` names an action wrkflw does itself, which is never fetched
+pub fn is_built_in(uses: &str) -> bool {
+    BUILT_IN_ACTIONS
+        .iter()
+        .any(|action| uses.starts_with(action))
+}
+
+/// A remote action reference such as `owner/repo/sub/dir@v1`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActionRef {
+    pub repo: String,
+    pub path: Option<String>,
+    pub git_ref: String,
+}
+
+impl ActionRef {
+    pub fn parse(uses: &str) -> Option<ActionRef> {
+        let (target, git_ref) = uses.split_once('@')?;
+        if target.starts_with("./") || target.starts_with("docker://") || git_ref.is_empty() {
+            return None;
+        }
+
+        let mut parts = target.splitn(3, '/');
+        let owner = parts.next().filter(|s| !s.is_empty())?;
+        let name = parts.next().filter(|s| !s.is_empty())?;
+        Some(ActionRef {
+            repo: format!("{}/{}", owner, name),
+            path: parts.next().map(|p| p.trim_matches('/').to_string()),
+            git_ref: git_ref.to_string(),
+        })
+    }
+}
+
+/// The parts of an action's metadata file the executor acts on
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActionMetadata {
+    pub using: String,
+    pub main: Option<String>,
+    /// Dockerfile path or `docker://` image of a container action
+    pub image: Option<String>,
+    pub entrypoint: Option<String>,
+    pub args: Vec<String>,
+    pub env: HashMap<String, String>,
+    /// Declared inputs and their defaults
+    pub inputs: Vec<(String, Option<String>)>,
+}
+
+impl ActionMetadata {
+    pub fn is_node(&self) -> bool {
+        self.using.starts_with("node")
+    }
+
+    pub fn is_docker(&self) -> bool {
+        self.using == "docker"
+    }
+
+    /// Metadata for a `uses: docker://image` step, which has no action.yml
+    pub fn for_image(image: &str) -> ActionMetadata {
+        ActionMetadata {
+            using: "docker".to_string(),
+            main: None,
+            image: Some(image.to_string()),
+            entrypoint: None,
+            args: Vec::new(),
+            env: HashMap::new(),
+            inputs: Vec::new(),
+        }
+    }
+
+    /// Image with the node version the action was written for
+    pub fn node_image(&self) -> &'static str {
+        match self.using.as_str() {
+            "node20" => "node:20-bookworm-slim",
+            _ => "node:16-buster-slim",
+        }
+    }
+
+    /// `INPUT_*` variables for the action, taking `with:` values over defaults
+    pub fn input_env(&self, with: &IndexMap<String, String>) -> HashMap<String, String> {
+        let mut env = HashMap::new();
+        for (name, default) in &self.inputs {
+            if let Some(value) = with.get(name).or(default.as_ref()) {
+                env.insert(input_env_name(name), value.clone());
+            }
+        }
+        // Undeclared inputs still reach the action, as they do on GitHub
+        for (name, value) in with {
+            env.entry(input_env_name(name))
+                .or_insert_with(|| value.clone());
+        }
+        env
+    }
+}
+
+fn input_env_name(name: &str) -> String {
+    format!("INPUT_{}", name.replace(' ', "_").to_uppercase())
+}
+
+/// Read `action.yml` (or `action.yaml`) from an action directory
+pub fn load_action_metadata(action_dir: &Path) -> Result<ActionMetadata, String> {
+    let file = ["action.yml", "action.yaml"]
+        .iter()
+        .map(|name| action_dir.join(name))
+        .find(|path| path.exists())
+        .ok_or_else(|| {
+            format!(
+                "No action.yml or action.yaml found in {}",
+                action_dir.display()
+            )
+        })?;
+
+    let content =
+        fs::read_to_string(&file).map_err(|e| format!("Failed to read action file: {}", e))?;
+    let def: serde_yaml::Value =
+        serde_yaml::from_str(&content).map_err(|e| format!("Invalid action YAML: {}", e))?;
+
+    let runs = def
+        .get("runs")
+        .ok_or_else(|| format!("{} has no 'runs' section", file.display()))?;
+    let using = runs
+        .get("using")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| format!("{} has no 'runs.using'", file.display()))?;
+
+    let inputs = def
+        .get("inputs")
+        .and_then(|v| v.as_mapping())
+        .map(|inputs| {
+            inputs
+                .iter()
+                .filter_map(|(name, input)| {
+                    let default = input.get("default").and_then(scalar_string);
+                    Some((name.as_str()?.to_string(), default))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let string = |key: &str| runs.get(key).and_then(scalar_string);
+    Ok(ActionMetadata {
+        using: using.to_string(),
+        main: string("main"),
+        image: string("image"),
+        entrypoint: string("entrypoint"),
+        args: runs
+            .get("args")
+            .and_then(|v| v.as_sequence())
+            .map(|args| args.iter().filter_map(scalar_string).collect())
+            .unwrap_or_default(),
+        env: runs
+            .get("env")
+            .and_then(|v| v.as_mapping())
+            .map(|env| {
+                env.iter()
+                    .filter_map(|(k, v)| Some((k.as_str()?.to_string(), scalar_string(v)?)))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        inputs,
+    })
+}
+
+fn scalar_string(value: &serde_yaml::Value) -> Option<String> {
+    match value {
+        serde_yaml::Value::String(s) => Some(s.clone()),
+        serde_yaml::Value::Bool(b) => Some(b.to_string()),
+        serde_yaml::Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// Split a `with: args:` string into arguments, honouring quotes
+pub fn split_args(args: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut in_token = false;
+    let mut quote: Option<char> = None;
+
+    for c in args.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => current.push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                in_token = true;
+            }
+            None if c.is_whitespace() => {
+                if in_token {
+                    parts.push(std::mem::take(&mut current));
+                    in_token = false;
+                }
+            }
+            None => {
+                current.push(c);
+                in_token = true;
+            }
+        }
+    }
+    if in_token {
+        parts.push(current);
+    }
+    parts
+}
+
+/// Root directory holding cloned actions
+pub fn actions_root() -> PathBuf {
+    if let Ok(dir) = std::env::var(ACTIONS_DIR_ENV) {
+        if !dir.is_empty() {
+            return PathBuf::from(dir);
+        }
+    }
+
+    dirs::cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("wrkflw")
+        .join("actions")
+}
+
+/// Host actions are cloned from, `https://github.com` unless overridden
+pub fn actions_base_url() -> String {
+    std::env::var(ACTIONS_URL_ENV)
+        .ok()
+        .filter(|url| !url.is_empty())
+        .unwrap_or_else(|| "https://github.com".to_string())
+}
+
+/// Where the files of commit `sha` of an action's repository are kept.
+/// Refs pointing at the same commit share them.
+fn tree_dir(cache_root: &Path, sha: &str) -> PathBuf {
+    cache_root.join("trees").join(sha)
+}
+
+/// The file naming the commit an action's ref was at when it was fetched
+fn ref_file(action: &ActionRef, cache_root: &Path) -> PathBuf {
+    cache_root
+        .join("refs")
+        .join(action.repo.replace('/', "__"))
+        .join(action.git_ref.replace('/', "__"))
+}
+
+fn is_commit_sha(git_ref: &str) -> bool {
+    git_ref.len() == 40 && git_ref.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// The cached files of the commit an action's ref points at
+fn cached_tree(action: &ActionRef, cache_root: &Path) -> Option<PathBuf> {
+    let sha = if is_commit_sha(&action.git_ref) {
+        action.git_ref.to_ascii_lowercase()
+    } else {
+        fs::read_to_string(ref_file(action, cache_root))
+            .ok()?
+            .trim()
+            .to_string()
+    };
+    let tree = tree_dir(cache_root, &sha);
+    tree.is_dir().then_some(tree)
+}
+
+/// The directory of `action` in its repository's files
+fn action_dir(action: &ActionRef, tree: PathBuf) -> PathBuf {
+    match &action.path {
+        Some(path) => tree.join(path),
+        None => tree,
+    }
+}
+
+/// Whether an action was fetched already, so using it needs no network
+pub fn is_fetched(action: &ActionRef, cache_root: &Path) -> bool {
+    cached_tree(action, cache_root).is_some()
+}
+
+/// Fetch an action at its ref into the cache, returning the action directory.
+///
+/// A ref that was fetched before is served from the cache without touching
+/// the network, so later runs work offline.
+pub fn fetch_action(
+    action: &ActionRef,
+    base_url: &str,
+    cache_root: &Path,
+) -> Result<PathBuf, String> {
+    let tree = match cached_tree(action, cache_root) {
+        Some(tree) => tree,
+        None => download_action(action, base_url, cache_root)?,
+    };
+    Ok(action_dir(action, tree))
+}
+
+/// Fetch an action again unless its ref is a commit SHA, moving a tag or
+/// branch to the commit it points at now
+pub fn update_action(
+    action: &ActionRef,
+    base_url: &str,
+    cache_root: &Path,
+) -> Result<PathBuf, String> {
+    if is_commit_sha(&action.git_ref) {
+        return fetch_action(action, base_url, cache_root);
+    }
+    Ok(action_dir(
+        action,
+        download_action(action, base_url, cache_root)?,
+    ))
+}
+
+/// Download the files of an action's ref, without its git history, into
+/// the tree of the commit it's at and point the ref at that commit
+fn download_action(
+    action: &ActionRef,
+    base_url: &str,
+    cache_root: &Path,
+) -> Result<PathBuf, String> {
+    let url = format!("{}/{}", base_url.trim_end_matches('/'), action.repo);
+
+    let trees = cache_root.join("trees");
+    fs::create_dir_all(&trees).map_err(|e| format!("Failed to create action cache: {}", e))?;
+    // Clone next to the final location and move it into place once complete
+    let staging = tempfile::tempdir_in(&trees)
+        .map_err(|e| format!("Failed to create action staging directory: {}", e))?;
+
+    let git = |args: &[&str]| {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(staging.path())
+            .output()
+            .map_err(|e| format!("Failed to run git: {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "Failed to fetch {}@{}: {}",
+                action.repo,
+                action.git_ref,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+    // Fetching the ref directly works for branches, tags and commit SHAs alike
+    git(&["init", "--quiet"])?;
+    git(&["remote", "add", "origin", &url])?;
+    git(&[
+        "fetch",
+        "--quiet",
+        "--depth",
+        "1",
+        "origin",
+        &action.git_ref,
+    ])?;
+    git(&["checkout", "--quiet", "FETCH_HEAD"])?;
+    let sha = git(&["rev-parse", "FETCH_HEAD"])?.to_ascii_lowercase();
+    fs::remove_dir_all(staging.path().join(".git"))
+        .map_err(|e| format!("Failed to cache action: {}", e))?;
+
+    let tree = tree_dir(cache_root, &sha);
+    if !tree.exists() {
+        let staged = staging.into_path();
+        if let Err(e) = fs::rename(&staged, &tree) {
+            let _ = fs::remove_dir_all(&staged);
+            // Another run may have cached the same commit in the meantime
+            if !tree.exists() {
+                return Err(format!("Failed to cache action: {}", e));
+            }
+        }
+    }
+
+    if !is_commit_sha(&action.git_ref) {
+        let file = ref_file(action, cache_root);
+        let write = || {
+            let dir = file.parent().unwrap_or(cache_root);
+            fs::create_dir_all(dir)?;
+            let mut staged = tempfile::NamedTempFile::new_in(dir)?;
+            std::io::Write::write_all(&mut staged, sha.as_bytes())?;
+            staged.persist(&file).map_err(|e| e.error)?;
+            Ok::<_, std::io::Error>(())
+        };
+        write().map_err(|e| format!("Failed to cache action: {}", e))?;
+    }
+    Ok(tree)
+}
+
+/// A workflow command a step printed
+#[derive(Debug, Clone, PartialEq)]
+pub enum WorkflowCommand {
+    Annotation(Annotation),
+    SetOutput {
+        name: String,
+        value: String,
+    },
+    /// A value to hide from the logs from now on
+    AddMask(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnnotationLevel {
+    Error,
+    Warning,
+    Notice,
+}
+
+/// An error, warning or notice a step printed, pointing at a place in a
+/// file when it gave one
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Annotation {
+    pub level: AnnotationLevel,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub col: Option<u32>,
+}
+
+impl fmt::Display for Annotation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}", file)?;
+            for number in [self.line, self.col].into_iter().map_while(|n| n) {
+                write!(f, ":{}", number)?;
+            }
+            write!(f, ": ")?;
+        }
+        if let Some(title) = &self.title {
+            write!(f, "{}: ", title)?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Pick out `::error::`, `::warning::`, `::notice::`, `::set-output` and
+/// `::add-mask::` lines
+pub fn parse_workflow_commands(stdout: &str) -> Vec<WorkflowCommand> {
+    let mut commands = Vec::new();
+    for line in stdout.lines() {
+        let Some(rest) = line.trim_start().strip_prefix("::") else {
+            continue;
+        };
+        let Some((head, data)) = rest.split_once("::") else {
+            continue;
+        };
+        let (command, properties) = head.split_once(' ').unwrap_or((head, ""));
+        let properties: HashMap<&str, String> = properties
+            .split(',')
+            .filter_map(|prop| prop.trim().split_once('='))
+            .map(|(key, value)| (key, unescape(value, true)))
+            .collect();
+        let data = unescape(data, false);
+
+        let level = match command {
+            "error" => AnnotationLevel::Error,
+            "warning" => AnnotationLevel::Warning,
+            "notice" => AnnotationLevel::Notice,
+            "set-output" => {
+                if let Some(name) = properties.get("name") {
+                    commands.push(WorkflowCommand::SetOutput {
+                        name: name.clone(),
+                        value: data,
+                    });
+                }
+                continue;
+            }
+            "add-mask" => {
+                if !data.trim().is_empty() {
+                    commands.push(WorkflowCommand::AddMask(data));
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        let number = |key: &str| properties.get(key).and_then(|n| n.trim().parse().ok());
+        commands.push(WorkflowCommand::Annotation(Annotation {
+            level,
+            message: data,
+            title: properties.get("title").cloned(),
+            file: properties.get("file").cloned(),
+            line: number("line"),
+            col: number("col"),
+        }));
+    }
+    commands
+}
+
+/// Undo the percent-encoding of a command's data, and of the `:` and `,`
+/// that properties can't hold otherwise
+fn unescape(value: &str, property: bool) -> String {
+    let mut value = value.replace("%0D", "\r").replace("%0A", "\n");
+    if property {
+        value = value.replace("%3A", ":").replace("%2C", ",");
+    }
+    value.replace("%25", "%")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_action_refs() {
+        assert_eq!(
+            ActionRef::parse("actions/setup-node@v4"),
+            Some(ActionRef {
+                repo: "actions/setup-node".to_string(),
+                path: None,
+                git_ref: "v4".to_string(),
+            })
+        );
+        assert_eq!(
+            ActionRef::parse("github/codeql-action/init@v3")
+                .and_then(|a| a.path)
+                .as_deref(),
+            Some("init")
+        );
+        assert_eq!(ActionRef::parse("./local-action"), None);
+        assert_eq!(ActionRef::parse("docker://alpine:3@sha"), None);
+    }
+
+    #[test]
+    fn fetches_and_caches_actions() {
+        let remote = tempfile::tempdir().unwrap();
+        let repo = remote.path().join("acme").join("tools");
+        fs::create_dir_all(repo.join("greet")).unwrap();
+        fs::write(
+            repo.join("greet").join("action.yml"),
+            "runs:\n  using: node20\n  main: index.js\n",
+        )
+        .unwrap();
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+                .args(args)
+                .current_dir(&repo)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {:?} failed", args);
+        };
+        git(&["init", "--quiet"]);
+        git(&["add", "-A"]);
+        git(&["commit", "--quiet", "-m", "init"]);
+        git(&["tag", "v1"]);
+
+        let cache = tempfile::tempdir().unwrap();
+        let base_url = format!("file://{}", remote.path().display());
+        let action = ActionRef::parse("acme/tools/greet@v1").unwrap();
+        let dir = fetch_action(&action, &base_url, cache.path()).unwrap();
+        assert!(load_action_metadata(&dir).unwrap().is_node());
+        assert!(!dir.parent().unwrap().join(".git").exists());
+
+        // Refs at the same commit share its files
+        git(&["tag", "v1.0"]);
+        let same_commit = ActionRef::parse("acme/tools/greet@v1.0").unwrap();
+        assert_eq!(
+            fetch_action(&same_commit, &base_url, cache.path()).unwrap(),
+            dir
+        );
+
+        // Updating moves a tag to where it points now
+        fs::write(repo.join("greet").join("index.js"), "").unwrap();
+        git(&["add", "-A"]);
+        git(&["commit", "--quiet", "-m", "more"]);
+        git(&["tag", "-f", "v1"]);
+        assert_eq!(fetch_action(&action, &base_url, cache.path()).unwrap(), dir);
+        let updated = update_action(&action, &base_url, cache.path()).unwrap();
+        assert!(updated.join("index.js").exists());
+
+        // Fetching again is served from the cache without touching the remote
+        drop(remote);
+        assert_eq!(
+            fetch_action(&action, &base_url, cache.path()).unwrap(),
+            updated
+        );
+        assert!(is_fetched(&same_commit, cache.path()));
+    }
+
+    #[test]
+    fn reads_docker_action_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("action.yaml"),
+            "runs:\n  using: docker\n  image: Dockerfile\n  entrypoint: /entry.sh\n  args:\n    - ${{ inputs.name }}\n    - --fast\n  env:\n    MODE: ci\n",
+        )
+        .unwrap();
+        let metadata = load_action_metadata(dir.path()).unwrap();
+        assert!(metadata.is_docker());
+        assert_eq!(metadata.image.as_deref(), Some("Dockerfile"));
+        assert_eq!(metadata.entrypoint.as_deref(), Some("/entry.sh"));
+        assert_eq!(metadata.args, vec!["${{ inputs.name }}", "--fast"]);
+        assert_eq!(metadata.env["MODE"], "ci");
+
+        assert_eq!(
+            split_args(r#"lint --message "hello world" 'a b'"#),
+            vec!["lint", "--message", "hello world", "a b"]
+        );
+        assert_eq!(split_args("  "), Vec::<String>::new());
+    }
+
+    #[test]
+    fn builds_inputs_and_reads_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("action.yml"),
+            "name: t\ninputs:\n  who to greet:\n    default: world\n  count:\n    default: 3\nruns:\n  using: node20\n  main: dist/index.js\n",
+        )
+        .unwrap();
+        let metadata = load_action_metadata(dir.path()).unwrap();
+        assert!(metadata.is_node());
+        assert_eq!(metadata.node_image(), "node:20-bookworm-slim");
+        assert_eq!(metadata.main.as_deref(), Some("dist/index.js"));
+
+        let with = IndexMap::from([("count".to_string(), "5".to_string())]);
+        let env = metadata.input_env(&with);
+        assert_eq!(env["INPUT_WHO_TO_GREET"], "world");
+        assert_eq!(env["INPUT_COUNT"], "5");
+
+        let commands = parse_workflow_commands(
+            "hello\n::warning file=a.js,line=1::careful\n::set-output name=greeting::hi\n::error title=Build%3A failed::50%25 done%0Aretry\n::add-mask::s3cret\n::add-mask::\n",
+        );
+        let warning = Annotation {
+            level: AnnotationLevel::Warning,
+            message: "careful".to_string(),
+            title: None,
+            file: Some("a.js".to_string()),
+            line: Some(1),
+            col: None,
+        };
+        assert_eq!(warning.to_string(), "a.js:1: careful");
+        assert_eq!(
+            commands,
+            vec![
+                WorkflowCommand::Annotation(warning),
+                WorkflowCommand::SetOutput {
+                    name: "greeting".to_string(),
+                    value: "hi".to_string(),
+                },
+                WorkflowCommand::Annotation(Annotation {
+                    level: AnnotationLevel::Error,
+                    message: "50% done\nretry".to_string(),
+                    title: Some("Build: failed".to_string()),
+                    file: None,
+                    line: None,
+                    col: None,
+                }),
+                WorkflowCommand::AddMask("s3cret".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,724 @@
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::environment::CONTAINER_WORKSPACE;
+
+/// Environment variable that overrides where artifact runs are stored
+pub const ARTIFACTS_DIR_ENV: &str = "WRKFLW_ARTIFACTS_DIR";
+
+const RUN_METADATA_FILE: &str = "run.json";
+
+/// Directory of a run holding the full output of every step, kept apart
+/// from the artifacts
+const STEP_OUTPUTS_DIR: &str = ".step-outputs";
+
+/// Artifact storage scoped to a single workflow run.
+///
+/// Each run gets its own directory under [`artifacts_root`], and every
+/// artifact is a sub-directory holding the uploaded files with their
+/// relative layout preserved.
+#[derive(Debug, Clone)]
+pub struct ArtifactStore {
+    run_id: String,
+    run_dir: PathBuf,
+    workflow: String,
+    size_limits: SizeLimits,
+}
+
+/// Sizes above which saving an artifact or cache is warned about. Locally
+/// nothing is rejected, but hosted CI refuses or crawls through uploads
+/// this large.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SizeLimits {
+    pub artifact_bytes: u64,
+    pub cache_bytes: u64,
+}
+
+impl Default for SizeLimits {
+    fn default() -> Self {
+        SizeLimits {
+            // GitLab.com's maximum artifact size
+            artifact_bytes: 1024 * 1024 * 1024,
+            // GitHub's cache storage limit per repository
+            cache_bytes: 10 * 1024 * 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunMetadata {
+    pub run_id: String,
+    pub workflow: String,
+    pub started_at: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ArtifactInfo {
+    pub name: String,
+    pub path: PathBuf,
+    pub files: usize,
+    pub size: u64,
+}
+
+/// What to do when an upload pattern matches nothing
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum IfNoFilesFound {
+    #[default]
+    Warn,
+    Error,
+    Ignore,
+}
+
+impl IfNoFilesFound {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim() {
+            "warn" => Ok(Self::Warn),
+            "error" => Ok(Self::Error),
+            "ignore" => Ok(Self::Ignore),
+            other => Err(format!(
+                "Invalid if-no-files-found value '{}', expected warn, error or ignore",
+                other
+            )),
+        }
+    }
+}
+
+/// Root directory holding all artifact runs
+pub fn artifacts_root() -> PathBuf {
+    if let Ok(dir) = std::env::var(ARTIFACTS_DIR_ENV) {
+        if !dir.is_empty() {
+            return PathBuf::from(dir);
+        }
+    }
+
+    dirs::cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("wrkflw")
+        .join("artifacts")
+}
+
+/// List the runs that have stored artifacts, most recent first
+pub fn list_runs(root: &Path) -> Result<Vec<RunMetadata>, String> {
+    if !root.exists() {
+        return Ok(Vec::new());
+    }
+
+    let entries =
+        fs::read_dir(root).map_err(|e| format!("Failed to read artifacts directory: {}", e))?;
+
+    let mut runs = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_dir() {
+            continue;
+        }
+
+        let run_id = entry.file_name().to_string_lossy().to_string();
+        let metadata = fs::read_to_string(path.join(RUN_METADATA_FILE))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or(RunMetadata {
+                run_id: run_id.clone(),
+                workflow: String::new(),
+                started_at: String::new(),
+            });
+        runs.push(metadata);
+    }
+
+    // Run ids start with a timestamp, so they sort chronologically
+    runs.sort_by(|a, b| b.run_id.cmp(&a.run_id));
+    Ok(runs)
+}
+
+impl ArtifactStore {
+    /// Create a store for a new run. Nothing is written to disk until the
+    /// first artifact is uploaded.
+    pub fn new_run(root: &Path, workflow: &str) -> Self {
+        let uuid = uuid::Uuid::new_v4().simple().to_string();
+        let run_id = format!("{}-{}", Local::now().format("%Y%m%d-%H%M%S"), &uuid[..8]);
+
+        ArtifactStore {
+            run_dir: root.join(&run_id),
+            run_id,
+            workflow: workflow.to_string(),
+            size_limits: SizeLimits::default(),
+        }
+    }
+
+    pub fn with_size_limits(mut self, size_limits: SizeLimits) -> Self {
+        self.size_limits = size_limits;
+        self
+    }
+
+    pub fn size_limits(&self) -> SizeLimits {
+        self.size_limits
+    }
+
+    /// Open an existing run, or the most recent one when `run_id` is `None`
+    pub fn open_run(root: &Path, run_id: Option<&str>) -> Result<Self, String> {
+        let run_id = match run_id {
+            Some(id) => id.to_string(),
+            None => list_runs(root)?
+                .into_iter()
+                .next()
+                .map(|run| run.run_id)
+                .ok_or_else(|| format!("No artifact runs found in {}", root.display()))?,
+        };
+
+        let run_dir = root.join(&run_id);
+        if !run_dir.is_dir() {
+            return Err(format!("Artifact run '{}' not found", run_id));
+        }
+
+        Ok(ArtifactStore {
+            run_id,
+            run_dir,
+            workflow: String::new(),
+            size_limits: SizeLimits::default(),
+        })
+    }
+
+    pub fn run_id(&self) -> &str {
+        &self.run_id
+    }
+
+    pub fn run_dir(&self) -> &Path {
+        &self.run_dir
+    }
+
+    /// Write the full output of a step to
+    /// `.step-outputs/<job>/<NN>-<step>.log` in the run directory
+    pub fn save_step_output(
+        &self,
+        job_name: &str,
+        step_idx: usize,
+        step_name: &str,
+        output: &str,
+    ) -> Result<PathBuf, String> {
+        self.ensure_run_dir()?;
+        let job_dir = self
+            .run_dir
+            .join(STEP_OUTPUTS_DIR)
+            .join(file_name_part(job_name));
+        fs::create_dir_all(&job_dir)
+            .map_err(|e| format!("Failed to create step output directory: {}", e))?;
+        let path = job_dir.join(format!(
+            "{:02}-{}.log",
+            step_idx + 1,
+            file_name_part(step_name)
+        ));
+        fs::write(&path, output)
+            .map_err(|e| format!("Failed to write output of step '{}': {}", step_name, e))?;
+        Ok(path)
+    }
+
+    /// Upload the files matched by `patterns` (newline separated, globs and
+    /// `!` exclusions allowed) relative to `workspace`.
+    pub fn upload(
+        &self,
+        name: &str,
+        patterns: &str,
+        workspace: &Path,
+        if_no_files_found: IfNoFilesFound,
+        overwrite: bool,
+    ) -> Result<Option<ArtifactInfo>, String> {
+        validate_artifact_name(name)?;
+
+        let mut includes = Vec::new();
+        let mut excludes = Vec::new();
+        for line in patterns.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            if let Some(exclude) = line.strip_prefix('!') {
+                excludes.push(resolve_workspace_path(exclude.trim(), workspace));
+            } else {
+                includes.push(resolve_workspace_path(line, workspace));
+            }
+        }
+
+        if includes.is_empty() {
+            return Err("Input required and not supplied: path".to_string());
+        }
+
+        let exclude_patterns = excludes
+            .iter()
+            .map(|p| glob::Pattern::new(&p.to_string_lossy()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Invalid exclude pattern: {}", e))?;
+
+        let mut files = Vec::new();
+        for include in &includes {
+            let matches = glob::glob(&include.to_string_lossy())
+                .map_err(|e| format!("Invalid path pattern '{}': {}", include.display(), e))?;
+            for path in matches.flatten() {
+                collect_files(&path, &mut files);
+            }
+        }
+        files.retain(|file| !exclude_patterns.iter().any(|p| p.matches_path(file)));
+        files.sort();
+        files.dedup();
+
+        if files.is_empty() {
+            let message = format!(
+                "No files were found with the provided path: {}. No artifacts will be uploaded.",
+                patterns.trim()
+            );
+            return match if_no_files_found {
+                IfNoFilesFound::Error => Err(message),
+                IfNoFilesFound::Warn => {
+                    logging::warning(&message);
+                    Ok(None)
+                }
+                IfNoFilesFound::Ignore => Ok(None),
+            };
+        }
+
+        let size = total_size(&files);
+        if size > self.size_limits.artifact_bytes {
+            logging::warning(&format!(
+                "Artifact '{}' is {}, above the {} warning threshold. Hosted CI may reject it or upload it slowly.",
+                name,
+                format_size(size),
+                format_size(self.size_limits.artifact_bytes)
+            ));
+        }
+
+        let artifact_dir = self.run_dir.join(name);
+        if artifact_dir.exists() {
+            if !overwrite {
+                return Err(format!(
+                    "An artifact with the name '{}' already exists in this run",
+                    name
+                ));
+            }
+            fs::remove_dir_all(&artifact_dir)
+                .map_err(|e| format!("Failed to replace artifact '{}': {}", name, e))?;
+        }
+
+        self.ensure_run_dir()?;
+
+        // Like upload-artifact, the least common ancestor of the search
+        // paths becomes the artifact root
+        let root = common_ancestor(includes.iter().map(|p| search_root(p)))
+            .unwrap_or_else(|| workspace.to_path_buf());
+
+        for file in &files {
+            let relative = file
+                .strip_prefix(&root)
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|_| PathBuf::from(file.file_name().unwrap_or_default()));
+            let dest = artifact_dir.join(relative);
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| format!("Failed to create artifact directory: {}", e))?;
+            }
+            fs::copy(file, &dest)
+                .map_err(|e| format!("Failed to copy '{}': {}", file.display(), e))?;
+        }
+
+        artifact_info(name, &artifact_dir)
+    }
+
+    /// Download artifacts into `destination`. With a name, the artifact is
+    /// extracted directly into `destination`; without one, every artifact
+    /// (optionally filtered by `pattern`) gets its own sub-directory unless
+    /// `merge_multiple` is set.
+    pub fn download(
+        &self,
+        name: Option<&str>,
+        pattern: Option<&str>,
+        merge_multiple: bool,
+        destination: &Path,
+    ) -> Result<Vec<ArtifactInfo>, String> {
+        if let Some(name) = name {
+            let artifact = self.get(name)?;
+            copy_dir(&artifact.path, destination)?;
+            return Ok(vec![artifact]);
+        }
+
+        let pattern = pattern
+            .map(glob::Pattern::new)
+            .transpose()
+            .map_err(|e| format!("Invalid artifact pattern: {}", e))?;
+
+        let artifacts: Vec<ArtifactInfo> = self
+            .list()?
+            .into_iter()
+            .filter(|a| pattern.as_ref().is_none_or(|p| p.matches(&a.name)))
+            .collect();
+
+        for artifact in &artifacts {
+            let target = if merge_multiple {
+                destination.to_path_buf()
+            } else {
+                destination.join(&artifact.name)
+            };
+            copy_dir(&artifact.path, &target)?;
+        }
+
+        Ok(artifacts)
+    }
+
+    /// Look up a single artifact by name
+    pub fn get(&self, name: &str) -> Result<ArtifactInfo, String> {
+        validate_artifact_name(name)?;
+        let path = self.run_dir.join(name);
+        if !path.is_dir() {
+            return Err(format!(
+                "Unable to find an artifact with the name: {}",
+                name
+            ));
+        }
+        artifact_info(name, &path)?
+            .ok_or_else(|| format!("Unable to find an artifact with the name: {}", name))
+    }
+
+    /// List all artifacts uploaded during this run
+    pub fn list(&self) -> Result<Vec<ArtifactInfo>, String> {
+        if !self.run_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let entries = fs::read_dir(&self.run_dir)
+            .map_err(|e| format!("Failed to read artifact run directory: {}", e))?;
+
+        let mut artifacts = Vec::new();
+        for entry in entries.flatten() {
+            if !entry.path().is_dir() {
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name == STEP_OUTPUTS_DIR {
+                continue;
+            }
+            if let Some(info) = artifact_info(&name, &entry.path())? {
+                artifacts.push(info);
+            }
+        }
+        artifacts.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(artifacts)
+    }
+
+    fn ensure_run_dir(&self) -> Result<(), String> {
+        if self.run_dir.exists() {
+            return Ok(());
+        }
+
+        fs::create_dir_all(&self.run_dir)
+            .map_err(|e| format!("Failed to create artifact run directory: {}", e))?;
+
+        let metadata = RunMetadata {
+            run_id: self.run_id.clone(),
+            workflow: self.workflow.clone(),
+            started_at: Local::now().to_rfc3339(),
+        };
+        let content = serde_json::to_string_pretty(&metadata)
+            .map_err(|e| format!("Failed to serialize run metadata: {}", e))?;
+        fs::write(self.run_dir.join(RUN_METADATA_FILE), content)
+            .map_err(|e| format!("Failed to write run metadata: {}", e))?;
+
+        logging::info(&format!(
+            "Storing artifacts for run {} in {}",
+            self.run_id,
+            self.run_dir.display()
+        ));
+
+        Ok(())
+    }
+}
+
+/// Resolve a path from a workflow against the job workspace, mapping the
+/// container workspace mount back onto the host directory
+pub fn resolve_workspace_path(path: &str, workspace: &Path) -> PathBuf {
+    if let Some(rest) = path.strip_prefix(CONTAINER_WORKSPACE) {
+        return workspace.join(rest.trim_start_matches('/'));
+    }
+
+    let path = Path::new(path);
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        workspace.join(path)
+    }
+}
+
+/// Total size of the files matched by newline separated `patterns`, as
+/// they would be saved by a cache step. `!` exclusions are ignored.
+pub fn paths_size(patterns: &str, workspace: &Path) -> u64 {
+    let mut files = Vec::new();
+    for line in patterns.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('!') {
+            continue;
+        }
+        let pattern = resolve_workspace_path(line, workspace);
+        for path in glob::glob(&pattern.to_string_lossy())
+            .into_iter()
+            .flatten()
+            .flatten()
+        {
+            collect_files(&path, &mut files);
+        }
+    }
+    files.sort();
+    files.dedup();
+    total_size(&files)
+}
+
+fn total_size(files: &[PathBuf]) -> u64 {
+    files
+        .iter()
+        .filter_map(|file| fs::metadata(file).ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// Human readable size, e.g. `1.5 GiB`
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+fn validate_artifact_name(name: &str) -> Result<(), String> {
+    const INVALID_CHARS: &[char] = &['"', ':', '<', '>', '|', '*', '?', '\r', '\n', '\\', '/'];
+
+    if name.trim().is_empty() {
+        return Err("Artifact name cannot be empty".to_string());
+    }
+    if name == "." || name == ".." || name.contains(INVALID_CHARS) {
+        return Err(format!(
+            "Artifact name '{}' is not valid. Names cannot contain \", :, <, >, |, *, ?, \\ or /",
+            name
+        ));
+    }
+    if name == STEP_OUTPUTS_DIR {
+        return Err(format!(
+            "Artifact name '{}' is reserved for step outputs",
+            name
+        ));
+    }
+
+    Ok(())
+}
+
+/// A job or step name made safe to use in a file name
+fn file_name_part(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect()
+}
+
+/// The non-wildcard prefix of a search path, used to compute the artifact root
+fn search_root(pattern: &Path) -> PathBuf {
+    let mut root = PathBuf::new();
+    let mut has_glob = false;
+    for component in pattern.components() {
+        let part = component.as_os_str().to_string_lossy();
+        if part.contains(['*', '?', '[']) {
+            has_glob = true;
+            break;
+        }
+        root.push(component);
+    }
+
+    if !has_glob && root.is_file() {
+        root.pop();
+    }
+    root
+}
+
+fn common_ancestor(mut paths: impl Iterator<Item = PathBuf>) -> Option<PathBuf> {
+    let first = paths.next()?;
+    let mut ancestor: Vec<Component> = first.components().collect();
+
+    for path in paths {
+        let common = ancestor
+            .iter()
+            .zip(path.components())
+            .take_while(|(a, b)| **a == *b)
+            .count();
+        ancestor.truncate(common);
+    }
+
+    Some(ancestor.iter().collect())
+}
+
+fn collect_files(path: &Path, files: &mut Vec<PathBuf>) {
+    if path.is_dir() {
+        if let Ok(entries) = fs::read_dir(path) {
+            for entry in entries.flatten() {
+                collect_files(&entry.path(), files);
+            }
+        }
+    } else if path.is_file() {
+        files.push(path.to_path_buf());
+    }
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<(), String> {
+    fs::create_dir_all(to).map_err(|e| format!("Failed to create '{}': {}", to.display(), e))?;
+
+    let entries =
+        fs::read_dir(from).map_err(|e| format!("Failed to read '{}': {}", from.display(), e))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let dest = to.join(entry.file_name());
+        if path.is_dir() {
+            copy_dir(&path, &dest)?;
+        } else {
+            fs::copy(&path, &dest)
+                .map_err(|e| format!("Failed to copy '{}': {}", path.display(), e))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn artifact_info(name: &str, path: &Path) -> Result<Option<ArtifactInfo>, String> {
+    if !path.is_dir() {
+        return Ok(None);
+    }
+
+    let mut files = Vec::new();
+    collect_files(path, &mut files);
+    let size = files
+        .iter()
+        .filter_map(|f| fs::metadata(f).ok())
+        .map(|m| m.len())
+        .sum();
+
+    Ok(Some(ArtifactInfo {
+        name: name.to_string(),
+        path: path.to_path_buf(),
+        files: files.len(),
+        size,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn upload_and_download_round_trip() {
+        let root = tempfile::tempdir().unwrap();
+        let workspace = tempfile::tempdir().unwrap();
+        fs::create_dir_all(workspace.path().join("dist/nested")).unwrap();
+        fs::write(workspace.path().join("dist/app.bin"), "app").unwrap();
+        fs::write(workspace.path().join("dist/nested/lib.so"), "lib").unwrap();
+        fs::write(workspace.path().join("dist/debug.log"), "log").unwrap();
+
+        let store = ArtifactStore::new_run(root.path(), "ci.yml");
+        let info = store
+            .upload(
+                "build",
+                "dist/\n!**/*.log",
+                workspace.path(),
+                IfNoFilesFound::Error,
+                false,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(info.files, 2);
+
+        // Same name again is rejected unless overwrite is set
+        assert!(store
+            .upload(
+                "build",
+                "dist/",
+                workspace.path(),
+                IfNoFilesFound::Error,
+                false
+            )
+            .is_err());
+
+        let dest = tempfile::tempdir().unwrap();
+        store
+            .download(Some("build"), None, false, dest.path())
+            .unwrap();
+        assert!(dest.path().join("app.bin").exists());
+        assert!(dest.path().join("nested/lib.so").exists());
+        assert!(!dest.path().join("debug.log").exists());
+
+        // Step outputs share the run directory without being artifacts
+        let output = store
+            .save_step_output("test (os: ubuntu/22.04)", 2, "Run tests", "ok\n")
+            .unwrap();
+        assert_eq!(
+            output,
+            store
+                .run_dir()
+                .join(".step-outputs/test (os_ ubuntu_22.04)/03-Run tests.log")
+        );
+        assert_eq!(fs::read_to_string(&output).unwrap(), "ok\n");
+
+        let reopened = ArtifactStore::open_run(root.path(), None).unwrap();
+        assert_eq!(reopened.run_id(), store.run_id());
+        assert_eq!(reopened.list().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn missing_files_follow_if_no_files_found() {
+        let root = tempfile::tempdir().unwrap();
+        let workspace = tempfile::tempdir().unwrap();
+        let store = ArtifactStore::new_run(root.path(), "ci.yml");
+
+        assert!(store
+            .upload(
+                "a",
+                "missing/*",
+                workspace.path(),
+                IfNoFilesFound::Warn,
+                false
+            )
+            .unwrap()
+            .is_none());
+        assert!(store
+            .upload(
+                "a",
+                "missing/*",
+                workspace.path(),
+                IfNoFilesFound::Error,
+                false
+            )
+            .is_err());
+        assert!(!store.run_dir().exists());
+    }
+
+    #[test]
+    fn measures_cache_paths() {
+        let workspace = tempfile::tempdir().unwrap();
+        fs::create_dir_all(workspace.path().join("target/debug")).unwrap();
+        fs::write(workspace.path().join("target/debug/app"), vec![0u8; 2048]).unwrap();
+        fs::write(workspace.path().join("target/stamp"), "12345").unwrap();
+
+        assert_eq!(paths_size("target\n", workspace.path()), 2053);
+        // Overlapping patterns count each file once
+        assert_eq!(
+            paths_size(
+                "target/debug\n/github/workspace/target/*\n!target/stamp",
+                workspace.path()
+            ),
+            2053
+        );
+        assert_eq!(paths_size("missing", workspace.path()), 0);
+
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(2053), "2.0 KiB");
+        assert_eq!(format_size(SizeLimits::default().cache_bytes), "10.0 GiB");
+    }
+}
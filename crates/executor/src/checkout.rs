@@ -0,0 +1,397 @@
+// Emulating actions/checkout: copying the project into the workspace, or
+// fetching a ref of it or of another repository with git
+use crate::environment;
+use crate::workspace::{self, CopyStats};
+use indexmap::IndexMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Variable the token is passed to git's credential helper in, which keeps
+/// it out of command lines and `.git/config`
+const TOKEN_ENV: &str = "WRKFLW_CHECKOUT_TOKEN";
+
+/// Whether to check out the submodules of the repository too
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Submodules {
+    #[default]
+    No,
+    Yes,
+    Recursive,
+}
+
+/// The `with:` inputs of an actions/checkout step wrkflw acts on
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CheckoutOptions {
+    /// `owner/repo` of another repository than the workflow's own
+    pub repository: Option<String>,
+    pub git_ref: Option<String>,
+    /// Directory under the workspace to check out into
+    pub path: String,
+    pub token: Option<String>,
+    /// Commits of history to fetch, `Some(0)` for all of them. Unset, only
+    /// the commit itself is fetched.
+    pub fetch_depth: Option<u32>,
+    pub submodules: Submodules,
+}
+
+impl CheckoutOptions {
+    pub fn from_with(with: &IndexMap<String, String>) -> Result<Self, String> {
+        let input = |name: &str| {
+            with.get(name)
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        let fetch_depth = input("fetch-depth")
+            .map(|depth| {
+                depth
+                    .parse()
+                    .map_err(|_| format!("'fetch-depth' must be a number, not '{}'", depth))
+            })
+            .transpose()?;
+        let submodules = match input("submodules").as_deref() {
+            None | Some("false") => Submodules::No,
+            Some("true") => Submodules::Yes,
+            Some("recursive") => Submodules::Recursive,
+            Some(other) => {
+                return Err(format!(
+                    "'submodules' must be true, false or recursive, not '{}'",
+                    other
+                ))
+            }
+        };
+        Ok(CheckoutOptions {
+            repository: input("repository"),
+            git_ref: input("ref"),
+            path: input("path").unwrap_or_default(),
+            token: input("token"),
+            fetch_depth,
+            submodules,
+        })
+    }
+
+    /// Whether these check out the workflow's own repository, `current`
+    fn is_project(&self, current: Option<&str>) -> bool {
+        match (&self.repository, current) {
+            (None, _) => true,
+            (Some(repository), Some(current)) => repository.eq_ignore_ascii_case(current),
+            (Some(_), None) => false,
+        }
+    }
+
+    /// The `--depth` to fetch with, `None` for the whole history
+    fn depth(&self) -> Option<u32> {
+        match self.fetch_depth {
+            Some(0) => None,
+            depth => Some(depth.unwrap_or(1)),
+        }
+    }
+}
+
+/// What a checkout did
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Checkout {
+    /// The project's files were copied, local changes included
+    Copied { dir: PathBuf, stats: CopyStats },
+    /// A commit was fetched and checked out with git
+    Fetched {
+        dir: PathBuf,
+        repository: String,
+        git_ref: String,
+        commit: String,
+    },
+}
+
+/// Check out what `options` ask for into `workspace`, `project` being the
+/// local repository of `current_repository` and `host` where other
+/// repositories are cloned from
+///
+/// Without a `ref`, `repository` or git options the project's files are
+/// copied, so a run tests local changes. A `fetch-depth` adds the history
+/// of the project's `HEAD` under them. A `ref` of the project is fetched from
+/// the local repository, and other repositories are cloned from `host`.
+pub fn checkout(
+    options: &CheckoutOptions,
+    project: &Path,
+    workspace: &Path,
+    current_repository: Option<&str>,
+    host: &str,
+) -> Result<Checkout, String> {
+    if Path::new(&options.path).is_absolute() {
+        return Err(format!(
+            "'path' must be relative to the workspace, not '{}'",
+            options.path
+        ));
+    }
+    let dir = environment::resolve_working_directory(workspace, &options.path)?;
+    let is_project = options.is_project(current_repository);
+
+    // With --bind-workspace the workspace is the project, which is left as
+    // it is rather than copied into itself or cleared
+    if same_dir(project, workspace) {
+        if is_project && options.git_ref.is_none() && same_dir(project, &dir) {
+            return Ok(Checkout::Copied {
+                dir,
+                stats: CopyStats::default(),
+            });
+        }
+        return Err(
+            "Can't check out a ref, another repository or into a 'path' in the project \
+             directory itself; run without --bind-workspace"
+                .to_string(),
+        );
+    }
+
+    if is_project && options.git_ref.is_none() {
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        let stats = workspace::copy_workspace(project, &dir)
+            .map_err(|e| format!("Failed to copy project files: {}", e))?;
+        if options.fetch_depth.is_some() && project.join(".git").exists() {
+            let git = Git::new(&dir, None);
+            let _ = fs::remove_dir_all(dir.join(".git"));
+            git.run(&["init", "--quiet"])?;
+            git.run(&["remote", "add", "origin", &file_url(project)?])?;
+            git.fetch(options.depth(), "HEAD")?;
+            // Leaves the copied files alone, so local changes show as such
+            git.run(&["reset", "--quiet", "FETCH_HEAD"])?;
+        }
+        return Ok(Checkout::Copied { dir, stats });
+    }
+
+    let (repository, url) = match &options.repository {
+        Some(repository) if !is_project => (
+            repository.clone(),
+            format!("{}/{}", host.trim_end_matches('/'), repository),
+        ),
+        _ => (
+            current_repository.unwrap_or("project").to_string(),
+            file_url(project)?,
+        ),
+    };
+    let git_ref = options
+        .git_ref
+        .clone()
+        .unwrap_or_else(|| "HEAD".to_string());
+
+    empty_dir(&dir).map_err(|e| format!("Failed to clear {}: {}", dir.display(), e))?;
+    let git = Git::new(&dir, options.token.as_deref());
+    git.run(&["init", "--quiet"])?;
+    git.run(&["remote", "add", "origin", &url])?;
+    git.fetch(options.depth(), &git_ref)
+        .map_err(|e| match options.token {
+            None if !is_project => format!("{}; private repositories need a 'token'", e),
+            _ => e,
+        })?;
+    let commit = git.run(&["rev-parse", "FETCH_HEAD"])?;
+    if options.depth().is_none() {
+        git.run(&["fetch", "--quiet", "origin", "+refs/tags/*:refs/tags/*"])?;
+    }
+    git.run(&["checkout", "--quiet", "--force", "--detach", &commit])?;
+
+    if options.submodules != Submodules::No {
+        let mut update = vec!["submodule", "update", "--init", "--quiet"];
+        if options.submodules == Submodules::Recursive {
+            update.push("--recursive");
+        }
+        let depth = options.depth().map(|depth| depth.to_string());
+        if let Some(depth) = &depth {
+            update.extend(["--depth", depth]);
+        }
+        git.run(&update)?;
+    }
+
+    Ok(Checkout::Fetched {
+        dir,
+        repository,
+        git_ref,
+        commit,
+    })
+}
+
+/// Runs git in a directory, with a token for HTTPS remotes if given
+struct Git<'a> {
+    dir: &'a Path,
+    token: Option<&'a str>,
+}
+
+impl<'a> Git<'a> {
+    fn new(dir: &'a Path, token: Option<&'a str>) -> Self {
+        Git { dir, token }
+    }
+
+    fn fetch(&self, depth: Option<u32>, git_ref: &str) -> Result<String, String> {
+        let depth = depth.map(|depth| depth.to_string());
+        let mut args = vec!["fetch", "--quiet", "--no-tags"];
+        if let Some(depth) = &depth {
+            args.extend(["--depth", depth]);
+        }
+        args.extend(["origin", git_ref]);
+        self.run(&args)
+    }
+
+    fn run(&self, args: &[&str]) -> Result<String, String> {
+        let mut command = Command::new("git");
+        command
+            .args(args)
+            .current_dir(self.dir)
+            .env("GIT_TERMINAL_PROMPT", "0");
+        if let Some(token) = self.token {
+            command
+                .env(TOKEN_ENV, token)
+                .env("GIT_CONFIG_COUNT", "1")
+                .env("GIT_CONFIG_KEY_0", "credential.helper")
+                .env(
+                    "GIT_CONFIG_VALUE_0",
+                    format!(
+                        "!f() {{ echo username=x-access-token; echo password=${}; }}; f",
+                        TOKEN_ENV
+                    ),
+                );
+        }
+        let output = command
+            .output()
+            .map_err(|e| format!("Failed to run git: {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "git {} failed: {}",
+                args.first().unwrap_or(&""),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+}
+
+/// The project as a `file://` URL, which unlike a plain path lets git fetch
+/// shallowly
+fn file_url(project: &Path) -> Result<String, String> {
+    let project = fs::canonicalize(project)
+        .map_err(|e| format!("Failed to find {}: {}", project.display(), e))?;
+    Ok(format!("file://{}", project.display()))
+}
+
+fn same_dir(a: &Path, b: &Path) -> bool {
+    matches!((fs::canonicalize(a), fs::canonicalize(b)), (Ok(a), Ok(b)) if a == b)
+}
+
+/// Remove everything in `dir`, creating it if needed
+fn empty_dir(dir: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(dir)?;
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            fs::remove_dir_all(entry.path())?;
+        } else {
+            fs::remove_file(entry.path())?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn git(dir: &Path, args: &[&str]) -> String {
+        Git::new(dir, None).run(args).unwrap()
+    }
+
+    #[test]
+    fn checks_out_refs_and_other_repositories() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path();
+        git(root, &["init", "--quiet", "--initial-branch", "main"]);
+        git(root, &["config", "user.email", "ci@example.com"]);
+        git(root, &["config", "user.name", "CI"]);
+        fs::write(root.join("version"), "1").unwrap();
+        git(root, &["add", "."]);
+        git(root, &["commit", "--quiet", "-m", "one"]);
+        git(root, &["tag", "v1"]);
+        fs::write(root.join("version"), "2").unwrap();
+        git(root, &["commit", "--quiet", "-am", "two"]);
+        fs::write(root.join("version"), "local").unwrap();
+
+        let workspace = tempfile::tempdir().unwrap();
+        let with = |pairs: &[(&str, &str)]| {
+            let with: IndexMap<String, String> = pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+            CheckoutOptions::from_with(&with).unwrap()
+        };
+        let read = |file: &str| fs::read_to_string(workspace.path().join(file)).unwrap();
+
+        // Local changes are copied, with the history asked for under them
+        let copied = checkout(
+            &with(&[("fetch-depth", "0")]),
+            root,
+            workspace.path(),
+            Some("me/app"),
+            "",
+        )
+        .unwrap();
+        assert!(matches!(copied, Checkout::Copied { .. }));
+        assert_eq!(read("version"), "local");
+        assert_eq!(git(workspace.path(), &["rev-list", "--count", "HEAD"]), "2");
+
+        // A ref is checked out as committed
+        let fetched = checkout(
+            &with(&[("ref", "v1"), ("repository", "Me/App")]),
+            root,
+            workspace.path(),
+            Some("me/app"),
+            "",
+        )
+        .unwrap();
+        assert_eq!(read("version"), "1");
+        let Checkout::Fetched { commit, .. } = fetched else {
+            panic!("expected a fetched checkout");
+        };
+        assert_eq!(commit, git(root, &["rev-parse", "v1"]));
+        assert_eq!(git(workspace.path(), &["rev-list", "--count", "HEAD"]), "1");
+
+        // Other repositories come from the actions host, here a directory
+        let host = tempfile::tempdir().unwrap();
+        let lib = host.path().join("org").join("lib");
+        git(
+            host.path(),
+            &[
+                "clone",
+                "--quiet",
+                "--bare",
+                &root.to_string_lossy(),
+                &lib.to_string_lossy(),
+            ],
+        );
+        let other = checkout(
+            &with(&[("repository", "org/lib"), ("path", "deps/lib")]),
+            root,
+            workspace.path(),
+            Some("me/app"),
+            &format!("file://{}", host.path().display()),
+        );
+        assert!(
+            matches!(other.unwrap(), Checkout::Fetched { repository, .. } if repository == "org/lib")
+        );
+        assert_eq!(read("deps/lib/version"), "2");
+
+        assert!(checkout(&with(&[("path", "/etc")]), root, workspace.path(), None, "").is_err());
+        assert!(checkout(&with(&[("ref", "main")]), root, root, None, "").is_err());
+    }
+
+    #[test]
+    fn parses_checkout_inputs() {
+        let with: IndexMap<String, String> = [("submodules", "recursive"), ("fetch-depth", "0")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let options = CheckoutOptions::from_with(&with).unwrap();
+        assert_eq!(options.submodules, Submodules::Recursive);
+        assert_eq!(options.depth(), None);
+        assert_eq!(CheckoutOptions::default().depth(), Some(1));
+
+        let bad: IndexMap<String, String> = [("submodules".to_string(), "some".to_string())].into();
+        assert!(CheckoutOptions::from_with(&bad).is_err());
+    }
+}
@@ -0,0 +1,273 @@
+// Workflow `concurrency:` groups shared by the runs of one wrkflw process
+use expressions::EvaluationContext;
+use models::github::{Concurrency, OrExpression};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+/// How often a run waiting for its group checks whether it can start, or
+/// a cancellation is checked for
+const WAIT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A `concurrency:` setting with its expressions resolved
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConcurrencyGroup {
+    pub name: String,
+    pub cancel_in_progress: bool,
+}
+
+impl ConcurrencyGroup {
+    /// Resolve a `concurrency:` value, either a group name or a mapping with
+    /// `group` and `cancel-in-progress`
+    pub fn resolve(concurrency: &Concurrency, ctx: &EvaluationContext) -> Result<Self, String> {
+        let interpolate = |text: &str| {
+            expressions::interpolate(text, ctx)
+                .map_err(|e| format!("Invalid concurrency expression '{}': {}", text, e))
+        };
+        let (group, cancel_in_progress) = match concurrency {
+            Concurrency::Group(group) => (interpolate(group)?, false),
+            Concurrency::Detailed {
+                group,
+                cancel_in_progress,
+            } => {
+                let cancel_in_progress = match cancel_in_progress {
+                    None => false,
+                    Some(OrExpression::Value(cancel)) => *cancel,
+                    Some(OrExpression::Expression(cancel)) => interpolate(cancel)? == "true",
+                };
+                (interpolate(group)?, cancel_in_progress)
+            }
+        };
+        Ok(ConcurrencyGroup {
+            name: group,
+            cancel_in_progress,
+        })
+    }
+}
+
+/// Shared flag that stops a run before its next step, with the reason why
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<Mutex<Option<String>>>);
+
+impl CancellationToken {
+    /// Cancel the run; the first reason given is kept
+    pub fn cancel(&self, reason: &str) {
+        let mut cancelled = lock(&self.0);
+        if cancelled.is_none() {
+            *cancelled = Some(reason.to_string());
+        }
+    }
+
+    pub fn reason(&self) -> Option<String> {
+        lock(&self.0).clone()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        lock(&self.0).is_some()
+    }
+
+    /// Wait until the run is cancelled
+    pub async fn cancelled(&self) {
+        while !self.is_cancelled() {
+            tokio::time::sleep(WAIT_INTERVAL).await;
+        }
+    }
+
+    fn same_run(&self, other: &CancellationToken) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// The runs of a group: at most one in progress and one waiting, like GitHub
+#[derive(Debug, Default)]
+struct GroupRuns {
+    running: Option<CancellationToken>,
+    pending: Option<CancellationToken>,
+}
+
+/// Tracks which runs hold each concurrency group. Clones share the same state.
+#[derive(Debug, Clone, Default)]
+pub struct ConcurrencyManager {
+    groups: Arc<Mutex<HashMap<String, GroupRuns>>>,
+}
+
+impl ConcurrencyManager {
+    /// Add a run to its group before it starts. An older run still waiting in
+    /// the group is cancelled, and so is the one in progress when the group
+    /// has `cancel-in-progress`. Adding the same run twice, or a run that's
+    /// already cancelled, does nothing.
+    pub fn enqueue(&self, group: &ConcurrencyGroup, run: &CancellationToken) {
+        let mut groups = lock(&self.groups);
+        let runs = groups.entry(group.name.clone()).or_default();
+        let known =
+            |slot: &Option<CancellationToken>| slot.as_ref().is_some_and(|t| t.same_run(run));
+        if run.is_cancelled() || known(&runs.running) || known(&runs.pending) {
+            return;
+        }
+
+        let reason = format!(
+            "cancelled by a newer run in concurrency group '{}'",
+            group.name
+        );
+        if let Some(pending) = runs.pending.replace(run.clone()) {
+            pending.cancel(&reason);
+        }
+        if group.cancel_in_progress {
+            if let Some(running) = &runs.running {
+                running.cancel(&reason);
+            }
+        }
+    }
+
+    /// Wait until no other run of the group is in progress, then hold the
+    /// group until the returned guard is dropped. Fails with the reason if
+    /// the run is cancelled while waiting.
+    pub async fn start(
+        &self,
+        group: &ConcurrencyGroup,
+        run: &CancellationToken,
+    ) -> Result<ConcurrencyGuard, String> {
+        self.enqueue(group, run);
+        loop {
+            if let Some(guard) = self.try_start(group, run)? {
+                return Ok(guard);
+            }
+            tokio::time::sleep(WAIT_INTERVAL).await;
+        }
+    }
+
+    fn try_start(
+        &self,
+        group: &ConcurrencyGroup,
+        run: &CancellationToken,
+    ) -> Result<Option<ConcurrencyGuard>, String> {
+        let mut groups = lock(&self.groups);
+        let runs = groups.entry(group.name.clone()).or_default();
+        let is_pending = runs.pending.as_ref().is_some_and(|t| t.same_run(run));
+        if let Some(reason) = run.reason() {
+            if is_pending {
+                runs.pending = None;
+            }
+            return Err(reason);
+        }
+        if runs.running.is_some() {
+            return Ok(None);
+        }
+        if is_pending {
+            runs.pending = None;
+        }
+        runs.running = Some(run.clone());
+        Ok(Some(ConcurrencyGuard {
+            groups: Arc::clone(&self.groups),
+            group: group.name.clone(),
+            run: run.clone(),
+        }))
+    }
+}
+
+/// Holds a concurrency group for a run in progress
+#[derive(Debug)]
+pub struct ConcurrencyGuard {
+    groups: Arc<Mutex<HashMap<String, GroupRuns>>>,
+    group: String,
+    run: CancellationToken,
+}
+
+impl Drop for ConcurrencyGuard {
+    fn drop(&mut self) {
+        let mut groups = lock(&self.groups);
+        if let Some(runs) = groups.get_mut(&self.group) {
+            if runs.running.as_ref().is_some_and(|t| t.same_run(&self.run)) {
+                runs.running = None;
+            }
+            if runs.running.is_none() && runs.pending.is_none() {
+                groups.remove(&self.group);
+            }
+        }
+    }
+}
+
+// A panicking run shouldn't stop the others from using their groups
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group(name: &str, cancel_in_progress: bool) -> ConcurrencyGroup {
+        ConcurrencyGroup {
+            name: name.to_string(),
+            cancel_in_progress,
+        }
+    }
+
+    #[test]
+    fn resolves_concurrency_settings() {
+        let mut ctx = EvaluationContext::new();
+        ctx.set(
+            "github",
+            serde_json::json!({"workflow": "CI", "ref": "refs/heads/main"}),
+        );
+        let resolve =
+            |yaml: &str| ConcurrencyGroup::resolve(&serde_yaml::from_str(yaml).unwrap(), &ctx);
+        let parse = |yaml: &str| serde_yaml::from_str::<Concurrency>(yaml);
+
+        assert_eq!(resolve("deploy").unwrap(), group("deploy", false));
+        assert_eq!(
+            resolve(
+                "group: ${{ github.workflow }}-${{ github.ref }}\ncancel-in-progress: ${{ github.ref != 'refs/heads/main' }}"
+            )
+            .unwrap(),
+            group("CI-refs/heads/main", false)
+        );
+        assert_eq!(
+            resolve("group: deploy\ncancel-in-progress: true").unwrap(),
+            group("deploy", true)
+        );
+        assert!(parse("cancel-in-progress: true").is_err());
+    }
+
+    #[tokio::test]
+    async fn newer_runs_replace_waiting_and_cancelled_runs() {
+        let manager = ConcurrencyManager::default();
+        let first = CancellationToken::default();
+        let guard = manager
+            .start(&group("deploy", false), &first)
+            .await
+            .unwrap();
+
+        // A second run waits for the first; a third replaces it while it waits
+        let second = CancellationToken::default();
+        manager.enqueue(&group("deploy", false), &second);
+        assert!(manager
+            .try_start(&group("deploy", false), &second)
+            .unwrap()
+            .is_none());
+        let third = CancellationToken::default();
+        manager.enqueue(&group("deploy", false), &third);
+        assert_eq!(
+            manager
+                .start(&group("deploy", false), &second)
+                .await
+                .unwrap_err(),
+            "cancelled by a newer run in concurrency group 'deploy'"
+        );
+        assert!(!first.is_cancelled());
+
+        // Once the first run is done the third starts
+        drop(guard);
+        let guard = manager
+            .start(&group("deploy", false), &third)
+            .await
+            .unwrap();
+
+        // With cancel-in-progress a newer run cancels the one in progress
+        let fourth = CancellationToken::default();
+        manager.enqueue(&group("deploy", true), &fourth);
+        assert!(third.is_cancelled());
+        drop(guard);
+        assert!(manager.start(&group("deploy", true), &fourth).await.is_ok());
+    }
+}
@@ -1,7 +1,59 @@
-use parser::workflow::WorkflowDefinition;
+use models::github::{Job, Workflow};
 use std::collections::{HashMap, HashSet};
 
-pub fn resolve_dependencies(workflow: &WorkflowDefinition) -> Result<Vec<Vec<String>>, String> {
+/// Which jobs of a workflow should run. An empty selection runs them all.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JobSelection {
+    /// Jobs to run; all jobs when empty
+    pub jobs: Vec<String>,
+    /// Jobs to leave out, even when they are needed by a selected job
+    pub skip: Vec<String>,
+    /// Also run the jobs the selected ones transitively need
+    pub with_needs: bool,
+}
+
+impl JobSelection {
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty() && self.skip.is_empty()
+    }
+}
+
+/// Names of the jobs picked by `selection`
+pub fn select_jobs(
+    workflow: &Workflow,
+    selection: &JobSelection,
+) -> Result<HashSet<String>, String> {
+    for name in selection.jobs.iter().chain(&selection.skip) {
+        if !workflow.jobs.contains_key(name) {
+            return Err(format!("Job '{}' does not exist in the workflow", name));
+        }
+    }
+
+    let mut selected: HashSet<String> = if selection.jobs.is_empty() {
+        workflow.jobs.keys().cloned().collect()
+    } else {
+        selection.jobs.iter().cloned().collect()
+    };
+
+    if selection.with_needs {
+        let mut pending: Vec<String> = selected.iter().cloned().collect();
+        while let Some(name) = pending.pop() {
+            let needs = workflow.jobs.get(&name).map(Job::needs);
+            for needed in needs.into_iter().flatten() {
+                if selected.insert(needed.clone()) {
+                    pending.push(needed.clone());
+                }
+            }
+        }
+    }
+
+    for name in &selection.skip {
+        selected.remove(name);
+    }
+    Ok(selected)
+}
+
+pub fn resolve_dependencies(workflow: &Workflow) -> Result<Vec<Vec<String>>, String> {
     let jobs = &workflow.jobs;
 
     // Build adjacency list with String keys
@@ -16,33 +68,31 @@ pub fn resolve_dependencies(workflow: &WorkflowDefinition) -> Result<Vec<Vec<Str
 
     // Populate dependencies
     for (job_name, job) in jobs {
-        if let Some(needs) = &job.needs {
-            for needed_job in needs {
-                if !jobs.contains_key(needed_job) {
-                    return Err(format!(
-                        "Job '{}' depends on non-existent job '{}'",
-                        job_name, needed_job
-                    ));
-                }
-                // Get mutable reference to the dependency set for this job, with error handling
-                if let Some(deps) = dependencies.get_mut(job_name) {
-                    deps.insert(needed_job.clone());
-                } else {
-                    return Err(format!(
-                        "Internal error: Failed to update dependencies for job '{}'",
-                        job_name
-                    ));
-                }
+        for needed_job in job.needs() {
+            if !jobs.contains_key(needed_job) {
+                return Err(format!(
+                    "Job '{}' depends on non-existent job '{}'",
+                    job_name, needed_job
+                ));
+            }
+            // Get mutable reference to the dependency set for this job, with error handling
+            if let Some(deps) = dependencies.get_mut(job_name) {
+                deps.insert(needed_job.clone());
+            } else {
+                return Err(format!(
+                    "Internal error: Failed to update dependencies for job '{}'",
+                    job_name
+                ));
+            }
 
-                // Get mutable reference to the dependents set for the needed job, with error handling
-                if let Some(deps) = dependents.get_mut(needed_job) {
-                    deps.insert(job_name.clone());
-                } else {
-                    return Err(format!(
-                        "Internal error: Failed to update dependents for job '{}'",
-                        needed_job
-                    ));
-                }
+            // Get mutable reference to the dependents set for the needed job, with error handling
+            if let Some(deps) = dependents.get_mut(needed_job) {
+                deps.insert(job_name.clone());
+            } else {
+                return Err(format!(
+                    "Internal error: Failed to update dependents for job '{}'",
+                    needed_job
+                ));
             }
         }
     }
@@ -110,3 +160,79 @@ pub fn resolve_dependencies(workflow: &WorkflowDefinition) -> Result<Vec<Vec<Str
 
     Ok(result)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workflow() -> Workflow {
+        serde_yaml::from_str(
+            r#"
+name: CI
+on: push
+jobs:
+  lint:
+    runs-on: ubuntu-latest
+    steps: []
+  build:
+    runs-on: ubuntu-latest
+    needs: [lint]
+    steps: []
+  test:
+    runs-on: ubuntu-latest
+    needs: [build]
+    steps: []
+  docs:
+    runs-on: ubuntu-latest
+    steps: []
+"#,
+        )
+        .unwrap()
+    }
+
+    fn sorted(jobs: HashSet<String>) -> Vec<String> {
+        let mut jobs: Vec<String> = jobs.into_iter().collect();
+        jobs.sort();
+        jobs
+    }
+
+    #[test]
+    fn selects_jobs_and_their_needs() {
+        let workflow = workflow();
+        let mut selection = JobSelection {
+            jobs: vec!["test".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            sorted(select_jobs(&workflow, &selection).unwrap()),
+            ["test"]
+        );
+
+        selection.with_needs = true;
+        assert_eq!(
+            sorted(select_jobs(&workflow, &selection).unwrap()),
+            ["build", "lint", "test"]
+        );
+
+        selection.skip = vec!["lint".to_string()];
+        assert_eq!(
+            sorted(select_jobs(&workflow, &selection).unwrap()),
+            ["build", "test"]
+        );
+
+        let skip_only = JobSelection {
+            skip: vec!["docs".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            sorted(select_jobs(&workflow, &skip_only).unwrap()),
+            ["build", "lint", "test"]
+        );
+
+        let unknown = JobSelection {
+            jobs: vec!["deploy".to_string()],
+            ..Default::default()
+        };
+        assert!(select_jobs(&workflow, &unknown).is_err());
+    }
+}
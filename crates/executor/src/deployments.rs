@@ -0,0 +1,133 @@
+// Deployment environments jobs name with `environment:`: the variables and
+// secrets a project gives them locally, and approving the jobs that deploy
+// to protected ones
+use std::collections::{BTreeMap, HashMap};
+use std::sync::mpsc;
+use tokio::sync::oneshot;
+use utils::project::EnvironmentConfig;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DeploymentEnvironment {
+    /// Values of the `vars` context
+    pub vars: HashMap<String, String>,
+    /// Secrets that win over the run's own
+    pub secrets: HashMap<String, String>,
+    /// Ask before running jobs that deploy to it
+    pub require_approval: bool,
+}
+
+/// The environments of a project's config, with their secrets files read
+pub fn from_project(
+    environments: &BTreeMap<String, EnvironmentConfig>,
+) -> Result<HashMap<String, DeploymentEnvironment>, String> {
+    environments
+        .iter()
+        .map(|(name, environment)| {
+            let secrets = match &environment.secrets_file {
+                Some(file) => crate::environment::read_secrets_file(file)?,
+                None => HashMap::new(),
+            };
+            let deployment = DeploymentEnvironment {
+                vars: environment.variables.clone().into_iter().collect(),
+                secrets,
+                require_approval: environment.require_approval,
+            };
+            Ok((name.clone(), deployment))
+        })
+        .collect()
+}
+
+/// A job waiting for its deployment to be approved. Dropping it rejects
+/// the deployment.
+#[derive(Debug)]
+pub struct PendingDeployment {
+    pub job: String,
+    pub environment: String,
+    reply: oneshot::Sender<bool>,
+}
+
+impl PendingDeployment {
+    pub fn decide(self, approved: bool) {
+        // The run may have ended in the meantime
+        let _ = self.reply.send(approved);
+    }
+}
+
+/// Who approves deployments to protected environments, through the
+/// receiver handed out by `new`. Runs without one can't deploy to them.
+#[derive(Debug, Clone, Default)]
+pub struct Approvals {
+    sender: Option<mpsc::Sender<PendingDeployment>>,
+}
+
+impl Approvals {
+    pub fn new() -> (Self, mpsc::Receiver<PendingDeployment>) {
+        let (sender, receiver) = mpsc::channel();
+        let approvals = Approvals {
+            sender: Some(sender),
+        };
+        (approvals, receiver)
+    }
+
+    /// Wait until a job's deployment to a protected environment is approved
+    pub(crate) async fn approve(&self, job: &str, environment: &str) -> Result<(), String> {
+        let Some(sender) = &self.sender else {
+            return Err(format!(
+                "Job '{}' deploys to '{}', which requires approval, and nobody is there to approve it",
+                job, environment
+            ));
+        };
+        let (reply, decision) = oneshot::channel();
+        logging::info(&format!(
+            "Job '{}' is waiting for approval to deploy to '{}'",
+            job, environment
+        ));
+        let pending = PendingDeployment {
+            job: job.to_string(),
+            environment: environment.to_string(),
+            reply,
+        };
+        let approved = sender.send(pending).is_ok() && decision.await.unwrap_or(false);
+        if approved {
+            Ok(())
+        } else {
+            Err(format!(
+                "Deployment of job '{}' to '{}' was rejected",
+                job, environment
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn waits_for_deployments_to_be_decided() {
+        let (approvals, pending) = Approvals::new();
+        let approver = std::thread::spawn(move || {
+            let deployment = pending.recv().unwrap();
+            assert_eq!(
+                (deployment.job.as_str(), deployment.environment.as_str()),
+                ("deploy", "production")
+            );
+            deployment.decide(true);
+            // A dropped deployment is rejected
+            drop(pending.recv().unwrap());
+        });
+        assert!(approvals.approve("deploy", "production").await.is_ok());
+        assert!(approvals
+            .approve("deploy", "staging")
+            .await
+            .unwrap_err()
+            .ends_with("was rejected"));
+        approver.join().unwrap();
+
+        assert!(Approvals::default()
+            .approve("deploy", "production")
+            .await
+            .unwrap_err()
+            .contains("nobody is there to approve it"));
+    }
+}
@@ -1,22 +1,35 @@
+use crate::platforms::JobPlatforms;
+use crate::progress::ProgressReporter;
+use crate::resources::{oom_message, JobResources, ResourceLimits};
+use crate::retry::{self, RetryPolicy};
 use async_trait::async_trait;
 use bollard::{
-    container::{Config, CreateContainerOptions},
-    models::HostConfig,
-    network::CreateNetworkOptions,
+    auth::DockerCredentials,
+    container::{Config, CreateContainerOptions, NetworkingConfig, RemoveContainerOptions},
+    exec::{CreateExecOptions, StartExecResults},
+    models::{EndpointSettings, HealthConfig, HostConfig, PortBinding},
+    network::{ConnectNetworkOptions, CreateNetworkOptions},
+    volume::CreateVolumeOptions,
     Docker,
 };
 use futures_util::StreamExt;
 use logging;
 use once_cell::sync::Lazy;
-use runtime::container::{ContainerError, ContainerOutput, ContainerRuntime};
+use runtime::container::{
+    BackgroundContainer, ContainerError, ContainerOutput, ContainerRuntime, ContainerTimings,
+    StartedContainer, Stopper,
+};
 use std::collections::HashMap;
+use std::io::Write;
 use std::path::Path;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use utils;
 use utils::fd;
 
 static RUNNING_CONTAINERS: Lazy<Mutex<Vec<String>>> = Lazy::new(|| Mutex::new(Vec::new()));
 static CREATED_NETWORKS: Lazy<Mutex<Vec<String>>> = Lazy::new(|| Mutex::new(Vec::new()));
+// Client of the latest runtime, which the tracked resources belong to
+static CLEANUP_CLIENT: Lazy<Mutex<Option<Docker>>> = Lazy::new(|| Mutex::new(None));
 // Map to track customized images for a job
 #[allow(dead_code)]
 static CUSTOMIZED_IMAGES: Lazy<Mutex<HashMap<String, String>>> =
@@ -24,15 +37,351 @@ static CUSTOMIZED_IMAGES: Lazy<Mutex<HashMap<String, String>>> =
 
 pub struct DockerRuntime {
     docker: Docker,
+    /// Name and ID of the network of the run this runtime belongs to,
+    /// joined by every container
+    network: Mutex<Option<(String, String)>>,
+    /// How API calls that fail for transient reasons are retried
+    retry: RetryPolicy,
+    /// Command line client that interactive shells are opened with
+    cli: &'static str,
+    /// What the run's containers may reach outside its network
+    network_policy: NetworkPolicy,
+    /// ID of the proxy container of an allow-list policy
+    proxy: Mutex<Option<String>>,
+    /// Aliases of the run's background containers, which are reached
+    /// without the proxy
+    local_hosts: Mutex<Vec<String>>,
+    /// CPU and memory limits of job containers
+    resources: JobResources,
+    /// Platforms images are pulled for and job containers run as
+    platforms: JobPlatforms,
+    /// Where image pull progress is sent
+    progress: ProgressReporter,
+    /// Containers of this runtime, which `stopper` stops and no others
+    containers: Arc<RunningContainers>,
+}
+
+/// What the containers of a run may reach over the network
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum NetworkPolicy {
+    #[default]
+    Open,
+    /// Only each other, like a job's services
+    Offline,
+    /// Each other, and these hosts through an HTTP(S) proxy. Patterns like
+    /// `*.example.com` match subdomains.
+    AllowList(Vec<String>),
 }
 
+/// Image of the allow-list proxy, built from `PROXY_DOCKERFILE` the first
+/// time a run needs it and reused after that
+const PROXY_IMAGE: &str = "wrkflw-proxy:tinyproxy-alpine3.20";
+const PROXY_DOCKERFILE: &str = "FROM alpine:3.20\nRUN apk add --no-cache tinyproxy\n";
+/// Name the run's containers reach the proxy by
+const PROXY_ALIAS: &str = "wrkflw-proxy";
+const PROXY_PORT: u16 = 8888;
+
+/// Shell opened by `open_shell`: bash where the image has it
+const INTERACTIVE_SHELL: &str =
+    "if command -v bash >/dev/null 2>&1; then exec bash; else exec sh; fi";
+
 impl DockerRuntime {
     pub fn new() -> Result<Self, ContainerError> {
         let docker = Docker::connect_with_local_defaults().map_err(|e| {
             ContainerError::ContainerStart(format!("Failed to connect to Docker: {}", e))
         })?;
 
-        Ok(DockerRuntime { docker })
+        Ok(Self::with_client(docker))
+    }
+
+    /// Run containers through an existing client, e.g. one connected to Podman
+    pub fn with_client(docker: Docker) -> Self {
+        if let Ok(mut client) = CLEANUP_CLIENT.lock() {
+            *client = Some(docker.clone());
+        }
+        DockerRuntime {
+            docker,
+            network: Mutex::new(None),
+            retry: RetryPolicy::default(),
+            cli: "docker",
+            network_policy: NetworkPolicy::Open,
+            proxy: Mutex::new(None),
+            local_hosts: Mutex::new(Vec::new()),
+            resources: JobResources::default(),
+            platforms: JobPlatforms::default(),
+            progress: ProgressReporter::default(),
+            containers: Arc::default(),
+        }
+    }
+
+    /// Pull images and run job containers for other platforms than the
+    /// host's, like `linux/arm64`
+    pub fn with_platforms(mut self, platforms: JobPlatforms) -> Self {
+        self.platforms = platforms;
+        self
+    }
+
+    /// Limit the CPUs and memory of job containers
+    pub fn with_resources(mut self, resources: JobResources) -> Self {
+        self.resources = resources;
+        self
+    }
+
+    /// Restrict what the run's containers may reach outside its network
+    pub fn with_network_policy(mut self, policy: NetworkPolicy) -> Self {
+        self.network_policy = policy;
+        self
+    }
+
+    /// Report the progress of image pulls to the run's observers
+    pub fn with_progress(mut self, progress: ProgressReporter) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// Retry failed API calls following `retry` instead of the default policy
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Open interactive shells with this client, e.g. `podman`, instead of
+    /// `docker`
+    pub fn with_cli(mut self, cli: &'static str) -> Self {
+        self.cli = cli;
+        self
+    }
+
+    fn network(&self) -> Option<String> {
+        let network = self.network.lock().ok()?;
+        network.as_ref().map(|(name, _)| name.clone())
+    }
+
+    /// Variables sending the HTTP(S) requests of a container through the
+    /// proxy, when there is one
+    fn proxy_env(&self) -> Vec<String> {
+        if self.proxy.lock().map_or(true, |proxy| proxy.is_none()) {
+            return Vec::new();
+        }
+        let local_hosts = self
+            .local_hosts
+            .lock()
+            .map(|hosts| hosts.clone())
+            .unwrap_or_default();
+        proxy_env(&local_hosts)
+    }
+
+    /// Start the proxy that lets the run's containers reach `hosts` and
+    /// nothing else outside its network. It joins the run's network and the
+    /// default one, which it reaches the hosts through.
+    async fn start_proxy(&self, network: &str, hosts: &[String]) -> Result<String, ContainerError> {
+        self.ensure_proxy_image().await?;
+        let mut host_config = HostConfig::default();
+        let networking_config =
+            self.network_settings(&mut host_config, vec![PROXY_ALIAS.to_string()]);
+        let config = Config {
+            image: Some(PROXY_IMAGE.to_string()),
+            entrypoint: Some(vec!["sh".to_string(), "-c".to_string()]),
+            cmd: Some(vec![proxy_script()]),
+            env: Some(vec![format!("WRKFLW_ALLOWED_HOSTS={}", hosts.join("\n"))]),
+            healthcheck: Some(HealthConfig {
+                test: Some(vec![
+                    "CMD-SHELL".to_string(),
+                    format!("nc -z 127.0.0.1 {}", PROXY_PORT),
+                ]),
+                interval: Some(1_000_000_000),
+                start_period: Some(10_000_000_000),
+                ..Default::default()
+            }),
+            host_config: Some(host_config),
+            networking_config,
+            ..Default::default()
+        };
+        let created = self
+            .create_container_named("the proxy container", PROXY_ALIAS, None, &config, None)
+            .await
+            .map_err(|e| ContainerError::ContainerStart(e.to_string()))?;
+        self.track(&created.id);
+
+        let default_network = if self.cli == "podman" {
+            "podman"
+        } else {
+            "bridge"
+        };
+        let started = async {
+            self.docker
+                .connect_network(
+                    default_network,
+                    ConnectNetworkOptions {
+                        container: created.id.as_str(),
+                        endpoint_config: EndpointSettings::default(),
+                    },
+                )
+                .await
+                .map_err(|e| ContainerError::NetworkOperation(e.to_string()))?;
+            self.docker
+                .start_container::<String>(&created.id, None)
+                .await
+                .map_err(|e| ContainerError::ContainerStart(e.to_string()))?;
+            self.wait_until_healthy(&created.id).await
+        }
+        .await;
+        if let Err(e) = started {
+            let _ = self.stop_service(&created.id).await;
+            return Err(ContainerError::ContainerStart(format!(
+                "the proxy for the hosts allowed on {} didn't start: {}",
+                network, e
+            )));
+        }
+        logging::info(&format!(
+            "Containers of this run can only reach {} through the proxy",
+            hosts.join(", ")
+        ));
+        Ok(created.id)
+    }
+
+    /// Build the proxy's image unless an earlier run did. It is built for the
+    /// host's platform, whatever the jobs' are.
+    async fn ensure_proxy_image(&self) -> Result<(), ContainerError> {
+        if self.docker.inspect_image(PROXY_IMAGE).await.is_ok() {
+            return Ok(());
+        }
+        logging::info(&format!(
+            "Building the allow-list proxy image {}",
+            PROXY_IMAGE
+        ));
+        let built = async {
+            let temp_dir = tempfile::tempdir().map_err(|e| {
+                ContainerError::ImageBuild(format!("Failed to create temp directory: {}", e))
+            })?;
+            let dockerfile = temp_dir.path().join("Dockerfile");
+            std::fs::write(&dockerfile, PROXY_DOCKERFILE).map_err(|e| {
+                ContainerError::ImageBuild(format!("Failed to write Dockerfile: {}", e))
+            })?;
+            self.build_image_for(&dockerfile, PROXY_IMAGE, None).await
+        }
+        .await;
+        built.map_err(|e| {
+            ContainerError::ImageBuild(format!(
+                "--network allow-list needs the proxy image {}, which couldn't be built from alpine:3.20 with tinyproxy: {}",
+                PROXY_IMAGE, e
+            ))
+        })
+    }
+
+    /// Create a container named `<prefix>-<uuid>`, retrying transient
+    /// failures with a fresh name each time. An attempt that failed, say by
+    /// timing out on the client side, may still have created its container,
+    /// so the one by that name is removed before going on.
+    async fn create_container_named(
+        &self,
+        what: &str,
+        prefix: &str,
+        platform: Option<&str>,
+        config: &Config<String>,
+        timeout: Option<std::time::Duration>,
+    ) -> Result<bollard::models::ContainerCreateResponse, bollard::errors::Error> {
+        let attempted: Mutex<Option<String>> = Mutex::new(None);
+        let created = self
+            .retry
+            .run(&format!("Creating {}", what), || async {
+                let orphan = attempted.lock().ok().and_then(|mut name| name.take());
+                if let Some(orphan) = orphan {
+                    self.remove_orphan(&orphan).await;
+                }
+                let name = format!("{}-{}", prefix, uuid::Uuid::new_v4());
+                if let Ok(mut attempted) = attempted.lock() {
+                    *attempted = Some(name.clone());
+                }
+                let options = Some(CreateContainerOptions {
+                    name,
+                    platform: platform.map(str::to_string),
+                });
+                let create = self.docker.create_container(options, config.clone());
+                match timeout {
+                    Some(timeout) => retry::with_timeout(timeout, create).await,
+                    None => create.await,
+                }
+            })
+            .await;
+        if created.is_err() {
+            let orphan = attempted.lock().ok().and_then(|mut name| name.take());
+            if let Some(orphan) = orphan {
+                self.remove_orphan(&orphan).await;
+            }
+        }
+        created
+    }
+
+    /// Remove the container a failed create may have left behind, if any
+    async fn remove_orphan(&self, name: &str) {
+        let options = RemoveContainerOptions {
+            force: true,
+            v: true,
+            ..Default::default()
+        };
+        let removed = tokio::time::timeout(
+            std::time::Duration::from_secs(15),
+            self.docker.remove_container(name, Some(options)),
+        )
+        .await;
+        if let Ok(Ok(())) = removed {
+            logging::debug(&format!("Removed container {} of a failed create", name));
+        }
+    }
+
+    /// Remove the images tagged like `replaces*` other than `tag`, which a
+    /// newer build of theirs took the place of
+    async fn remove_images_replaced_by(&self, tag: &str, replaces: &str) {
+        let options = bollard::image::ListImagesOptions {
+            filters: HashMap::from([("reference".to_string(), vec![format!("{}*", replaces)])]),
+            ..Default::default()
+        };
+        let Ok(images) = self.docker.list_images(Some(options)).await else {
+            return;
+        };
+        let stale = images
+            .iter()
+            .flat_map(|image| &image.repo_tags)
+            .filter(|old| old.starts_with(replaces) && *old != tag);
+        for old in stale {
+            match self.docker.remove_image(old, None, None).await {
+                Ok(_) => logging::debug(&format!("Removed image {}, replaced by {}", old, tag)),
+                Err(e) => logging::debug(&format!("Failed to remove image {}: {}", old, e)),
+            }
+        }
+    }
+
+    /// Track a container for this runtime's stopper and for the cleanup on
+    /// exit
+    fn track(&self, id: &str) {
+        self.containers.track(id);
+        track_container(id);
+    }
+
+    fn untrack(&self, id: &str) {
+        self.containers.untrack(id);
+        untrack_container(id);
+    }
+
+    /// Host config and endpoint settings that attach a container to the
+    /// run's network under `aliases`
+    fn network_settings(
+        &self,
+        host_config: &mut HostConfig,
+        aliases: Vec<String>,
+    ) -> Option<NetworkingConfig<String>> {
+        let network = self.network()?;
+        host_config.network_mode = Some(network.clone());
+        Some(NetworkingConfig {
+            endpoints_config: HashMap::from([(
+                network,
+                EndpointSettings {
+                    aliases: (!aliases.is_empty()).then_some(aliases),
+                    ..Default::default()
+                },
+            )]),
+        })
     }
 
     // Add a method to store and retrieve customized images (e.g., with Python installed)
@@ -397,6 +746,12 @@ pub fn is_available() -> bool {
     false
 }
 
+/// Client to remove the tracked containers and networks with, if a runtime
+/// has been created
+pub fn cleanup_client() -> Option<Docker> {
+    CLEANUP_CLIENT.lock().ok()?.clone()
+}
+
 // Add container to tracking
 pub fn track_container(id: &str) {
     if let Ok(mut containers) = RUNNING_CONTAINERS.lock() {
@@ -452,6 +807,48 @@ pub async fn cleanup_resources(docker: &Docker) {
     }
 }
 
+/// Containers of one runtime that haven't been removed yet
+#[derive(Debug, Default)]
+struct RunningContainers(Mutex<Vec<String>>);
+
+impl RunningContainers {
+    fn track(&self, id: &str) {
+        if let Ok(mut containers) = self.0.lock() {
+            containers.push(id.to_string());
+        }
+    }
+
+    fn untrack(&self, id: &str) {
+        if let Ok(mut containers) = self.0.lock() {
+            containers.retain(|c| c != id);
+        }
+    }
+}
+
+/// Removes the containers of one runtime when its run is cancelled, leaving
+/// those of other runs in the process alone
+struct ContainerStopper {
+    docker: Docker,
+    containers: Arc<RunningContainers>,
+}
+
+#[async_trait]
+impl Stopper for ContainerStopper {
+    async fn stop(&self) {
+        let running = self
+            .containers
+            .0
+            .lock()
+            .map(|containers| containers.clone())
+            .unwrap_or_default();
+        for container_id in running {
+            stop_and_remove(&self.docker, &container_id).await;
+            self.containers.untrack(&container_id);
+            untrack_container(&container_id);
+        }
+    }
+}
+
 // Clean up all tracked containers
 pub async fn cleanup_containers(docker: &Docker) -> Result<(), String> {
     // Getting the containers to clean up should not take a long time
@@ -485,33 +882,7 @@ pub async fn cleanup_containers(docker: &Docker) -> Result<(), String> {
 
     // Process each container with a timeout
     for container_id in containers_to_cleanup {
-        // First try to stop the container
-        match tokio::time::timeout(
-            std::time::Duration::from_millis(1000),
-            docker.stop_container(&container_id, None),
-        )
-        .await
-        {
-            Ok(Ok(_)) => logging::debug(&format!("Stopped container: {}", container_id)),
-            Ok(Err(e)) => {
-                logging::warning(&format!("Error stopping container {}: {}", container_id, e))
-            }
-            Err(_) => logging::warning(&format!("Timeout stopping container: {}", container_id)),
-        }
-
-        // Then try to remove it
-        match tokio::time::timeout(
-            std::time::Duration::from_millis(1000),
-            docker.remove_container(&container_id, None),
-        )
-        .await
-        {
-            Ok(Ok(_)) => logging::debug(&format!("Removed container: {}", container_id)),
-            Ok(Err(e)) => {
-                logging::warning(&format!("Error removing container {}: {}", container_id, e))
-            }
-            Err(_) => logging::warning(&format!("Timeout removing container: {}", container_id)),
-        }
+        stop_and_remove(docker, &container_id).await;
 
         // Always untrack the container whether or not we succeeded to avoid future cleanup attempts
         untrack_container(&container_id);
@@ -520,6 +891,37 @@ pub async fn cleanup_containers(docker: &Docker) -> Result<(), String> {
     Ok(())
 }
 
+/// Stop a container, then remove it, each with a timeout
+async fn stop_and_remove(docker: &Docker, container_id: &str) {
+    // First try to stop the container
+    match tokio::time::timeout(
+        std::time::Duration::from_millis(1000),
+        docker.stop_container(container_id, None),
+    )
+    .await
+    {
+        Ok(Ok(_)) => logging::debug(&format!("Stopped container: {}", container_id)),
+        Ok(Err(e)) => {
+            logging::warning(&format!("Error stopping container {}: {}", container_id, e))
+        }
+        Err(_) => logging::warning(&format!("Timeout stopping container: {}", container_id)),
+    }
+
+    // Then try to remove it
+    match tokio::time::timeout(
+        std::time::Duration::from_millis(1000),
+        docker.remove_container(container_id, None),
+    )
+    .await
+    {
+        Ok(Ok(_)) => logging::debug(&format!("Removed container: {}", container_id)),
+        Ok(Err(e)) => {
+            logging::warning(&format!("Error removing container {}: {}", container_id, e))
+        }
+        Err(_) => logging::warning(&format!("Timeout removing container: {}", container_id)),
+    }
+}
+
 // Clean up all tracked networks
 pub async fn cleanup_networks(docker: &Docker) -> Result<(), String> {
     // Getting the networks to clean up should not take a long time
@@ -572,11 +974,232 @@ pub async fn cleanup_networks(docker: &Docker) -> Result<(), String> {
 
 // Create a new Docker network for a job
 pub async fn create_job_network(docker: &Docker) -> Result<String, ContainerError> {
-    let network_name = format!("wrkflw-network-{}", uuid::Uuid::new_v4());
+    create_network(
+        docker,
+        &format!("wrkflw-network-{}", uuid::Uuid::new_v4()),
+        false,
+    )
+    .await
+}
+
+/// Script of the proxy container: tinyproxy, allowing only the hosts in
+/// `WRKFLW_ALLOWED_HOSTS`, one per line
+fn proxy_script() -> String {
+    format!(
+        "printf '%s\\n' \"$WRKFLW_ALLOWED_HOSTS\" > /etc/tinyproxy/allowed \
+         && printf 'Port {}\\nListen 0.0.0.0\\nTimeout 600\\nFilter \"/etc/tinyproxy/allowed\"\\nFilterType fnmatch\\nFilterDefaultDeny Yes\\n' > /etc/tinyproxy/wrkflw.conf \
+         && exec tinyproxy -d -c /etc/tinyproxy/wrkflw.conf",
+        PROXY_PORT
+    )
+}
+
+/// Proxy variables for a container, leaving out the run's own containers
+fn proxy_env(local_hosts: &[String]) -> Vec<String> {
+    let proxy = format!("http://{}:{}", PROXY_ALIAS, PROXY_PORT);
+    let no_proxy = ["localhost", "127.0.0.1", "::1"]
+        .into_iter()
+        .chain(local_hosts.iter().map(String::as_str))
+        .collect::<Vec<_>>()
+        .join(",");
+    ["HTTP_PROXY", "HTTPS_PROXY", "http_proxy", "https_proxy"]
+        .into_iter()
+        .map(|name| format!("{}={}", name, proxy))
+        .chain(
+            ["NO_PROXY", "no_proxy"]
+                .into_iter()
+                .map(|name| format!("{}={}", name, no_proxy)),
+        )
+        .collect()
+}
+
+/// Name of a run's network, e.g. `wrkflw-ci-1718000000-3f9a2c` for run
+/// 1718000000 of the "CI" workflow. Run ids are timestamps in seconds, so a
+/// random suffix tells apart runs started in the same second.
+pub fn run_network_name(workflow_name: &str, run_id: &str) -> String {
+    let mut slug = String::new();
+    for c in workflow_name.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    // Docker allows long names, but they're typed when debugging
+    slug.truncate(40);
+    let slug = slug.trim_end_matches('-');
+    let suffix = &uuid::Uuid::new_v4().simple().to_string()[..6];
+    if slug.is_empty() {
+        format!("wrkflw-{}-{}", run_id, suffix)
+    } else {
+        format!("wrkflw-{}-{}-{}", slug, run_id, suffix)
+    }
+}
+
+/// Split a service's port like `8080:80/udp` into the container port key
+/// Docker expects (`80/udp`) and the host port, if one is given
+fn parse_port(port: &str) -> (String, Option<String>) {
+    let (ports, protocol) = port.split_once('/').unwrap_or((port, "tcp"));
+    match ports.rsplit_once(':') {
+        Some((host, container)) => (
+            format!("{}/{}", container, protocol),
+            // `127.0.0.1:8080:80` binds to one interface; the port is last
+            Some(host.rsplit(':').next().unwrap_or(host).to_string()),
+        ),
+        None => (format!("{}/{}", ports, protocol), None),
+    }
+}
+
+/// The `container.options` and `services.<id>.options` flags wrkflw
+/// applies when creating the container
+#[derive(Debug, Default, PartialEq)]
+struct ContainerOptions {
+    user: Option<String>,
+    hostname: Option<String>,
+    privileged: bool,
+    env: Vec<String>,
+    binds: Vec<String>,
+    cap_add: Vec<String>,
+    extra_hosts: Vec<String>,
+    memory: Option<i64>,
+    nano_cpus: Option<i64>,
+    shm_size: Option<i64>,
+    healthcheck: Option<HealthConfig>,
+}
+
+impl ContainerOptions {
+    /// Parse `docker create` flags, warning about the ones that aren't supported
+    fn parse(options: &str) -> Self {
+        let mut parsed = ContainerOptions::default();
+        let mut args = crate::actions::split_args(options).into_iter();
+        while let Some(arg) = args.next() {
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => {
+                    (flag.to_string(), Some(value.to_string()))
+                }
+                _ => (arg.clone(), None),
+            };
+            if flag == "--privileged" {
+                parsed.privileged = inline.is_none_or(|value| value != "false");
+                continue;
+            }
+            if !flag.starts_with('-') {
+                logging::warning(&format!("Ignoring unexpected container option '{}'", arg));
+                continue;
+            }
+            // Flags without a value mustn't swallow the next option
+            if matches!(
+                flag.as_str(),
+                "--init" | "--rm" | "--read-only" | "-t" | "--tty" | "-i" | "--interactive"
+            ) {
+                logging::warning(&format!(
+                    "Container option {} is not supported and was ignored",
+                    flag
+                ));
+                continue;
+            }
+            let Some(value) = inline.or_else(|| args.next()) else {
+                logging::warning(&format!("Container option {} needs a value", flag));
+                break;
+            };
+
+            if let Some(setting) = flag.strip_prefix("--health-") {
+                let health = parsed.healthcheck.get_or_insert_with(HealthConfig::default);
+                match setting {
+                    "cmd" => health.test = Some(vec!["CMD-SHELL".to_string(), value]),
+                    "interval" => health.interval = parse_duration(&value),
+                    "timeout" => health.timeout = parse_duration(&value),
+                    "start-period" => health.start_period = parse_duration(&value),
+                    "retries" => health.retries = value.parse().ok(),
+                    _ => logging::warning(&format!(
+                        "Container option {} is not supported and was ignored",
+                        flag
+                    )),
+                }
+                continue;
+            }
+
+            match flag.as_str() {
+                "-u" | "--user" => parsed.user = Some(value),
+                "-h" | "--hostname" => parsed.hostname = Some(value),
+                "-e" | "--env" => parsed.env.push(value),
+                "-v" | "--volume" => parsed.binds.push(value),
+                "--cap-add" => parsed.cap_add.push(value),
+                "--add-host" => parsed.extra_hosts.push(value),
+                "-m" | "--memory" => parsed.memory = parse_size(&value),
+                "--shm-size" => parsed.shm_size = parse_size(&value),
+                "--cpus" => {
+                    parsed.nano_cpus = value.parse::<f64>().ok().map(|cpus| (cpus * 1e9) as i64)
+                }
+                _ => logging::warning(&format!(
+                    "Container option {} is not supported and was ignored",
+                    flag
+                )),
+            }
+        }
+        parsed
+    }
+
+    fn apply(self, config: &mut Config<String>, host_config: &mut HostConfig) {
+        config.user = self.user.or(config.user.take());
+        config.hostname = self.hostname;
+        config.env.get_or_insert_with(Vec::new).extend(self.env);
+        config.healthcheck = self.healthcheck;
+        host_config
+            .binds
+            .get_or_insert_with(Vec::new)
+            .extend(self.binds);
+        host_config.privileged = Some(self.privileged);
+        host_config.cap_add = (!self.cap_add.is_empty()).then_some(self.cap_add);
+        host_config.extra_hosts = (!self.extra_hosts.is_empty()).then_some(self.extra_hosts);
+        host_config.memory = self.memory.or(host_config.memory);
+        host_config.nano_cpus = self.nano_cpus.or(host_config.nano_cpus);
+        host_config.shm_size = self.shm_size;
+    }
+}
+
+/// Bytes in a size like `512m` or `2g`
+pub(crate) fn parse_size(size: &str) -> Option<i64> {
+    let size = size.trim().to_ascii_lowercase();
+    let size = size.strip_suffix('b').unwrap_or(&size);
+    let (number, unit) = match size.char_indices().last()? {
+        (i, 'k') => (&size[..i], 1 << 10),
+        (i, 'm') => (&size[..i], 1 << 20),
+        (i, 'g') => (&size[..i], 1 << 30),
+        _ => (size, 1),
+    };
+    number.parse::<f64>().ok().map(|n| (n * unit as f64) as i64)
+}
+
+/// Nanoseconds in a duration like `10s`, `500ms` or `1m`
+fn parse_duration(duration: &str) -> Option<i64> {
+    let duration = duration.trim();
+    let (number, unit) = if let Some(n) = duration.strip_suffix("ms") {
+        (n, 1e6)
+    } else if let Some(n) = duration.strip_suffix('s') {
+        (n, 1e9)
+    } else if let Some(n) = duration.strip_suffix('m') {
+        (n, 60e9)
+    } else if let Some(n) = duration.strip_suffix('h') {
+        (n, 3600e9)
+    } else {
+        (duration, 1e9)
+    };
+    number.parse::<f64>().ok().map(|n| (n * unit) as i64)
+}
 
+/// Create a bridge network with the given name and track it for cleanup.
+/// Containers on an internal one can only reach each other.
+pub async fn create_network(
+    docker: &Docker,
+    network_name: &str,
+    internal: bool,
+) -> Result<String, ContainerError> {
     let options = CreateNetworkOptions {
-        name: network_name.clone(),
+        name: network_name.to_string(),
         driver: "bridge".to_string(),
+        check_duplicate: true,
+        internal,
+        labels: HashMap::from([("wrkflw".to_string(), "true".to_string())]),
         ..Default::default()
     };
 
@@ -615,7 +1238,7 @@ impl ContainerRuntime for DockerRuntime {
         // Run the entire container operation with a timeout
         match tokio::time::timeout(
             timeout_duration,
-            self.run_container_inner(image, cmd, env_vars, working_dir, volumes),
+            self.run_container_inner(image, None, cmd, env_vars, working_dir, volumes),
         )
         .await
         {
@@ -629,40 +1252,68 @@ impl ContainerRuntime for DockerRuntime {
         }
     }
 
-    async fn pull_image(&self, image: &str) -> Result<(), ContainerError> {
-        // Add a timeout for pull operations
-        let timeout_duration = std::time::Duration::from_secs(30);
+    async fn run_container_with_entrypoint(
+        &self,
+        image: &str,
+        entrypoint: &[&str],
+        cmd: &[&str],
+        env_vars: &[(&str, &str)],
+        working_dir: &Path,
+        volumes: &[(&Path, &Path)],
+    ) -> Result<ContainerOutput, ContainerError> {
+        logging::info(&format!(
+            "Docker: Running container with image: {} (entrypoint: {:?})",
+            image, entrypoint
+        ));
 
-        match tokio::time::timeout(timeout_duration, self.pull_image_inner(image)).await {
+        match tokio::time::timeout(
+            std::time::Duration::from_secs(360),
+            self.run_container_inner(image, Some(entrypoint), cmd, env_vars, working_dir, volumes),
+        )
+        .await
+        {
             Ok(result) => result,
             Err(_) => {
-                logging::warning(&format!(
-                    "Pull of image {} timed out, continuing with existing image",
-                    image
-                ));
-                // Return success to allow continuing with existing image
-                Ok(())
+                logging::error("Docker operation timed out after 360 seconds");
+                Err(ContainerError::ContainerExecution(
+                    "Operation timed out".to_string(),
+                ))
             }
         }
     }
 
+    async fn pull_image(&self, image: &str) -> Result<(), ContainerError> {
+        self.pull_image_for(image, self.platforms.default.as_deref())
+            .await
+    }
+
     async fn build_image(&self, dockerfile: &Path, tag: &str) -> Result<(), ContainerError> {
-        // Add a timeout for build operations
-        let timeout_duration = std::time::Duration::from_secs(120); // 2 minutes timeout for builds
+        self.build_image_for(dockerfile, tag, self.platforms.default.as_deref())
+            .await
+    }
 
-        match tokio::time::timeout(timeout_duration, self.build_image_inner(dockerfile, tag)).await
-        {
-            Ok(result) => result,
-            Err(_) => {
-                logging::error(&format!(
-                    "Building image {} timed out after 120 seconds",
-                    tag
-                ));
-                Err(ContainerError::ImageBuild(
-                    "Operation timed out".to_string(),
-                ))
+    async fn build_image_once(
+        &self,
+        dockerfile: &Path,
+        tag: &str,
+        replaces: &str,
+    ) -> Result<(), ContainerError> {
+        // An image built for another platform by an earlier run is rebuilt
+        if let Ok(image) = self.docker.inspect_image(tag).await {
+            let platform = format!(
+                "{}/{}",
+                image.os.unwrap_or_default(),
+                image.architecture.unwrap_or_default()
+            );
+            let wanted = self.platforms.default.as_deref();
+            if wanted.is_none_or(|wanted| wanted.starts_with(&platform)) {
+                logging::debug(&format!("Reusing image {}", tag));
+                return Ok(());
             }
         }
+        self.build_image(dockerfile, tag).await?;
+        self.remove_images_replaced_by(tag, replaces).await;
+        Ok(())
     }
 
     async fn prepare_language_environment(
@@ -798,6 +1449,200 @@ impl ContainerRuntime for DockerRuntime {
 
         Ok(image_tag)
     }
+
+    async fn create_network(&self, name: &str) -> Result<(), ContainerError> {
+        let internal = self.network_policy != NetworkPolicy::Open;
+        let id = create_network(&self.docker, name, internal).await?;
+        if let Ok(mut network) = self.network.lock() {
+            *network = Some((name.to_string(), id));
+        }
+        if let NetworkPolicy::AllowList(hosts) = &self.network_policy {
+            let proxy = self.start_proxy(name, hosts).await?;
+            if let Ok(mut current) = self.proxy.lock() {
+                *current = Some(proxy);
+            }
+        }
+        Ok(())
+    }
+
+    async fn remove_network(&self) -> Result<(), ContainerError> {
+        let proxy = self.proxy.lock().ok().and_then(|mut proxy| proxy.take());
+        if let Some(proxy) = proxy {
+            let _ = self.stop_service(&proxy).await;
+        }
+        let Some((name, id)) = self
+            .network
+            .lock()
+            .ok()
+            .and_then(|mut network| network.take())
+        else {
+            return Ok(());
+        };
+        let removed = self
+            .docker
+            .remove_network(&name)
+            .await
+            .map_err(|e| ContainerError::NetworkOperation(format!("removing {}: {}", name, e)));
+        // A network that couldn't be removed stays tracked, so cleanup on
+        // exit tries again
+        if removed.is_ok() {
+            untrack_network(&id);
+            logging::info(&format!("Removed Docker network: {}", name));
+        }
+        removed
+    }
+
+    async fn create_volume(
+        &self,
+        name: &str,
+        labels: &HashMap<String, String>,
+    ) -> Result<(), ContainerError> {
+        self.docker
+            .create_volume(CreateVolumeOptions {
+                name: name.to_string(),
+                labels: labels.clone(),
+                ..Default::default()
+            })
+            .await
+            .map(|_| ())
+            .map_err(|e| ContainerError::ContainerStart(format!("creating volume {}: {}", name, e)))
+    }
+
+    fn keep_network(&self) -> Option<String> {
+        let (name, id) = self.network.lock().ok()?.take()?;
+        // Kept networks outlive the process, so exit cleanup leaves them too
+        untrack_network(&id);
+        Some(name)
+    }
+
+    async fn start_service(
+        &self,
+        service: &BackgroundContainer,
+    ) -> Result<Option<StartedContainer>, ContainerError> {
+        let config = Config {
+            entrypoint: service.entrypoint.clone(),
+            cmd: service.command.clone(),
+            ..Default::default()
+        };
+        let started = self
+            .start_background(
+                service,
+                "wrkflw-service",
+                config,
+                ResourceLimits::default(),
+                self.platforms.default.as_deref(),
+            )
+            .await?;
+        Ok(Some(started))
+    }
+
+    async fn start_job_container(
+        &self,
+        container: &BackgroundContainer,
+    ) -> Result<Option<StartedContainer>, ContainerError> {
+        // Keep the container alive so each step can be executed in it
+        let config = Config {
+            entrypoint: Some(vec!["tail".to_string()]),
+            cmd: Some(vec!["-f".to_string(), "/dev/null".to_string()]),
+            working_dir: Some("/github/workspace".to_string()),
+            ..Default::default()
+        };
+        let job = container.aliases.first().map(String::as_str);
+        let started = self
+            .start_background(
+                container,
+                "wrkflw-job",
+                config,
+                self.resources.for_job(job),
+                self.platforms.for_job(job),
+            )
+            .await?;
+        Ok(Some(started))
+    }
+
+    async fn exec_in_container(
+        &self,
+        id: &str,
+        cmd: &[&str],
+        env_vars: &[(&str, &str)],
+        working_dir: &Path,
+    ) -> Result<ContainerOutput, ContainerError> {
+        let timeout_duration = std::time::Duration::from_secs(360);
+        match tokio::time::timeout(
+            timeout_duration,
+            self.exec_in_container_inner(id, cmd, env_vars, working_dir),
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(_) => Err(ContainerError::ContainerExecution(format!(
+                "Command in the job container timed out after {} seconds",
+                timeout_duration.as_secs()
+            ))),
+        }
+    }
+
+    async fn open_shell(
+        &self,
+        id: &str,
+        env_vars: &[(&str, &str)],
+        working_dir: &Path,
+    ) -> Result<(), ContainerError> {
+        // The API can't hand the terminal over to a container, so the client
+        // attaches it. Variables go through a file to keep secrets out of the
+        // process list; the file format can't hold multi-line values.
+        let mut env_file = tempfile::NamedTempFile::new()
+            .map_err(|e| ContainerError::ContainerExecution(e.to_string()))?;
+        let mut command = tokio::process::Command::new(self.cli);
+        command.args(["exec", "-it", "-w"]).arg(working_dir);
+        for (key, value) in env_vars {
+            if value.contains('\n') {
+                command.arg("-e").arg(format!("{}={}", key, value));
+            } else {
+                writeln!(env_file, "{}={}", key, value)
+                    .map_err(|e| ContainerError::ContainerExecution(e.to_string()))?;
+            }
+        }
+        command
+            .arg("--env-file")
+            .arg(env_file.path())
+            .args([id, "sh", "-c", INTERACTIVE_SHELL]);
+
+        // Whatever the last command in the shell exited with is up to the user
+        let status = command.status().await.map_err(|e| {
+            ContainerError::ContainerExecution(format!("Failed to run `{} exec`: {}", self.cli, e))
+        })?;
+        logging::debug(&format!("Interactive shell exited with {}", status));
+        Ok(())
+    }
+
+    async fn stop_service(&self, id: &str) -> Result<(), ContainerError> {
+        let options = RemoveContainerOptions {
+            force: true,
+            v: true,
+            ..Default::default()
+        };
+        let removed = tokio::time::timeout(
+            std::time::Duration::from_secs(15),
+            self.docker.remove_container(id, Some(options)),
+        )
+        .await;
+        self.untrack(id);
+        match removed {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(e)) => Err(ContainerError::ContainerExecution(e.to_string())),
+            Err(_) => Err(ContainerError::ContainerExecution(
+                "Removing the service container timed out".to_string(),
+            )),
+        }
+    }
+
+    fn stopper(&self) -> Option<Arc<dyn Stopper>> {
+        Some(Arc::new(ContainerStopper {
+            docker: self.docker.clone(),
+            containers: self.containers.clone(),
+        }))
+    }
 }
 
 // Move the actual implementation to internal methods
@@ -805,16 +1650,15 @@ impl DockerRuntime {
     async fn run_container_inner(
         &self,
         image: &str,
+        entrypoint: Option<&[&str]>,
         cmd: &[&str],
         env_vars: &[(&str, &str)],
         working_dir: &Path,
         volumes: &[(&Path, &Path)],
     ) -> Result<ContainerOutput, ContainerError> {
         // Collect environment variables
-        let mut env: Vec<String> = env_vars
-            .iter()
-            .map(|(k, v)| format!("{}={}", k, v))
-            .collect();
+        let mut env = self.proxy_env();
+        env.extend(env_vars.iter().map(|(k, v)| format!("{}={}", k, v)));
 
         let mut binds = Vec::new();
         for (host_path, container_path) in volumes {
@@ -836,32 +1680,29 @@ impl DockerRuntime {
         let is_windows_image = image.contains("windows")
             || image.contains("servercore")
             || image.contains("nanoserver");
-        let is_macos_emu =
-            image.contains("act-") && (image.contains("catthehacker") || image.contains("nektos"));
-
-        // Add platform-specific environment variables
-        if is_macos_emu {
-            // Add macOS-specific environment variables
-            env.push("RUNNER_OS=macOS".to_string());
-            env.push("RUNNER_ARCH=X64".to_string());
-            env.push("TMPDIR=/tmp".to_string());
-            env.push("HOME=/root".to_string());
-            env.push("GITHUB_WORKSPACE=/github/workspace".to_string());
-            env.push("PATH=/usr/local/bin:/usr/bin:/bin:/usr/sbin:/sbin".to_string());
+
+        // Step containers can be reached from services by their job's name
+        let job_alias = env_vars
+            .iter()
+            .find(|(k, _)| *k == "GITHUB_JOB")
+            .map(|(_, job)| job.to_string());
+        let platform = self.platforms.for_job(job_alias.as_deref());
+        // Images were pulled for the run's platform, not the job's own
+        if platform.is_some() && platform != self.platforms.default.as_deref() {
+            if let Err(e) = self.pull_image_for(image, platform).await {
+                logging::warning(&format!("Failed to pull image {}: {}", image, e));
+            }
         }
 
         // Create appropriate container options based on platform
-        let options = Some(CreateContainerOptions {
-            name: format!("wrkflw-{}", uuid::Uuid::new_v4()),
-            platform: if is_windows_image {
-                Some("windows".to_string())
-            } else {
-                None
-            },
-        });
+        let create_platform = if is_windows_image {
+            Some("windows")
+        } else {
+            platform
+        };
 
         // Configure host configuration based on platform
-        let host_config = if is_windows_image {
+        let mut host_config = if is_windows_image {
             HostConfig {
                 binds: Some(binds),
                 isolation: Some(bollard::models::HostConfigIsolationEnum::PROCESS),
@@ -874,81 +1715,89 @@ impl DockerRuntime {
             }
         };
 
+        let limits = self.resources.for_job(job_alias.as_deref());
+        host_config.memory = limits.memory;
+        host_config.nano_cpus = limits.nano_cpus;
+        let networking_config =
+            self.network_settings(&mut host_config, job_alias.into_iter().collect());
+
         // Create container config with platform-specific settings
-        let mut config = Config {
+        let config = Config {
             image: Some(image.to_string()),
-            cmd: Some(cmd_vec),
+            // An empty command keeps the image's own CMD
+            cmd: if cmd_vec.is_empty() {
+                None
+            } else {
+                Some(cmd_vec)
+            },
             env: Some(env),
             working_dir: Some(working_dir.to_string_lossy().to_string()),
             host_config: Some(host_config),
+            networking_config,
             // Windows containers need specific configuration
             user: if is_windows_image {
                 Some("ContainerAdministrator".to_string())
-            } else {
-                None // Don't specify user for macOS emulation - use default root user
-            },
-            // Map appropriate entrypoint for different platforms
-            entrypoint: if is_macos_emu {
-                // For macOS, ensure we use bash
-                Some(vec!["bash".to_string(), "-l".to_string(), "-c".to_string()])
             } else {
                 None
             },
+            entrypoint: entrypoint.map(|parts| parts.iter().map(|s| s.to_string()).collect()),
             ..Default::default()
         };
 
-        // Run platform-specific container setup
-        if is_macos_emu {
-            // Add special labels for macOS
-            let mut labels = HashMap::new();
-            labels.insert("wrkflw.platform".to_string(), "macos".to_string());
-            config.labels = Some(labels);
-        }
-
-        // Create container with a shorter timeout
-        let create_result = tokio::time::timeout(
-            std::time::Duration::from_secs(15),
-            self.docker.create_container(options, config),
-        )
-        .await;
+        // Create container with a shorter timeout per attempt
+        let startup_started = std::time::Instant::now();
+        let create_result = self
+            .create_container_named(
+                "a container",
+                "wrkflw",
+                create_platform,
+                &config,
+                Some(std::time::Duration::from_secs(15)),
+            )
+            .await;
 
         let container = match create_result {
-            Ok(Ok(container)) => container,
-            Ok(Err(e)) => return Err(ContainerError::ContainerStart(e.to_string())),
-            Err(_) => {
+            Ok(container) => container,
+            Err(bollard::errors::Error::RequestTimeoutError) => {
                 return Err(ContainerError::ContainerStart(
                     "Container creation timed out".to_string(),
                 ))
             }
+            Err(e) => return Err(ContainerError::ContainerStart(e.to_string())),
         };
 
         // Track the container before starting it to ensure cleanup even if starting fails
-        track_container(&container.id);
-
-        // Start container with a timeout
-        let start_result = tokio::time::timeout(
-            std::time::Duration::from_secs(15),
-            self.docker.start_container::<String>(&container.id, None),
-        )
-        .await;
+        self.track(&container.id);
+
+        // Start container with a timeout per attempt
+        let start_result = self
+            .retry
+            .run("Starting a container", || {
+                retry::with_timeout(
+                    std::time::Duration::from_secs(15),
+                    self.docker.start_container::<String>(&container.id, None),
+                )
+            })
+            .await;
 
         match start_result {
-            Ok(Ok(_)) => {}
-            Ok(Err(e)) => {
-                // Clean up the container if start fails
-                let _ = self.docker.remove_container(&container.id, None).await;
-                untrack_container(&container.id);
-                return Err(ContainerError::ContainerExecution(e.to_string()));
-            }
-            Err(_) => {
+            Ok(_) => {}
+            Err(bollard::errors::Error::RequestTimeoutError) => {
                 // Clean up the container if starting times out
                 let _ = self.docker.remove_container(&container.id, None).await;
-                untrack_container(&container.id);
+                self.untrack(&container.id);
                 return Err(ContainerError::ContainerExecution(
                     "Container start timed out".to_string(),
                 ));
             }
+            Err(e) => {
+                // Clean up the container if start fails
+                let _ = self.docker.remove_container(&container.id, None).await;
+                self.untrack(&container.id);
+                return Err(ContainerError::ContainerExecution(e.to_string()));
+            }
         }
+        let startup = startup_started.elapsed();
 
         // Wait for container to finish with a timeout (300 seconds)
         let wait_result = tokio::time::timeout(
@@ -962,6 +1811,10 @@ impl DockerRuntime {
         let exit_code = match wait_result {
             Ok(results) => match results.first() {
                 Some(Ok(exit)) => exit.status_code as i32,
+                // Non-zero exit codes come back as errors
+                Some(Err(bollard::errors::Error::DockerContainerWaitError { code, .. })) => {
+                    *code as i32
+                }
                 _ => -1,
             },
             Err(_) => {
@@ -997,6 +1850,19 @@ impl DockerRuntime {
         } else {
             logging::warning("Retrieving container logs timed out");
         }
+        if exit_code != 0 {
+            let oom_killed = self
+                .docker
+                .inspect_container(&container.id, None)
+                .await
+                .ok()
+                .and_then(|inspected| inspected.state?.oom_killed)
+                .unwrap_or(false);
+            if let Some(message) = oom_message(oom_killed, exit_code, limits.memory) {
+                logging::error(&message);
+                stderr.push_str(&format!("\n{}\n", message));
+            }
+        }
 
         // Clean up container with a timeout
         let _ = tokio::time::timeout(
@@ -1004,7 +1870,7 @@ impl DockerRuntime {
             self.docker.remove_container(&container.id, None),
         )
         .await;
-        untrack_container(&container.id);
+        self.untrack(&container.id);
 
         // Log detailed information about the command execution for debugging
         if exit_code != 0 {
@@ -1021,83 +1887,387 @@ impl DockerRuntime {
             stdout,
             stderr,
             exit_code,
+            timings: ContainerTimings {
+                startup,
+                ..Default::default()
+            },
         })
     }
 
-    async fn pull_image_inner(&self, image: &str) -> Result<(), ContainerError> {
-        let options = bollard::image::CreateImageOptions {
-            from_image: image,
+    /// Pull, create and start a container that keeps running in the
+    /// background, starting from `config`
+    async fn start_background(
+        &self,
+        container: &BackgroundContainer,
+        name_prefix: &str,
+        mut config: Config<String>,
+        limits: ResourceLimits,
+        platform: Option<&str>,
+    ) -> Result<StartedContainer, ContainerError> {
+        let pull_started = std::time::Instant::now();
+        match &container.credentials {
+            Some((username, password)) => {
+                let credentials = DockerCredentials {
+                    username: Some(username.clone()),
+                    password: Some(password.clone()),
+                    ..Default::default()
+                };
+                tokio::time::timeout(
+                    std::time::Duration::from_secs(120),
+                    self.pull_image_with(&container.image, Some(credentials), platform),
+                )
+                .await
+                .map_err(|_| {
+                    ContainerError::ImagePull(format!("Pulling {} timed out", container.image))
+                })??
+            }
+            None => self.pull_image_for(&container.image, platform).await?,
+        }
+        let image_pull = pull_started.elapsed();
+
+        let mut exposed_ports = HashMap::new();
+        let mut port_bindings = HashMap::new();
+        for port in &container.ports {
+            let (container_port, host_port) = parse_port(port);
+            exposed_ports.insert(container_port.clone(), HashMap::new());
+            port_bindings.insert(
+                container_port,
+                Some(vec![PortBinding {
+                    host_ip: None,
+                    host_port,
+                }]),
+            );
+        }
+        let mut host_config = HostConfig {
+            binds: Some(container.volumes.clone()),
+            port_bindings: Some(port_bindings),
+            memory: limits.memory,
+            nano_cpus: limits.nano_cpus,
             ..Default::default()
         };
+        config.image = Some(container.image.clone());
+        let mut env = self.proxy_env();
+        env.extend(container.env.iter().map(|(k, v)| format!("{}={}", k, v)));
+        config.env = Some(env);
+        config.exposed_ports = Some(exposed_ports);
+        let options = container
+            .options
+            .as_deref()
+            .map(ContainerOptions::parse)
+            .unwrap_or_default();
+        let wait_for_health = options.healthcheck.is_some();
+        options.apply(&mut config, &mut host_config);
+        config.networking_config =
+            self.network_settings(&mut host_config, container.aliases.clone());
+        if let Ok(mut local_hosts) = self.local_hosts.lock() {
+            local_hosts.extend(container.aliases.iter().cloned());
+        }
+        config.host_config = Some(host_config);
+
+        let startup_started = std::time::Instant::now();
+        let created = self
+            .create_container_named("a container", name_prefix, platform, &config, None)
+            .await
+            .map_err(|e| ContainerError::ContainerStart(e.to_string()))?;
+        self.track(&created.id);
+
+        let started = self
+            .retry
+            .run("Starting a container", || {
+                self.docker.start_container::<String>(&created.id, None)
+            })
+            .await
+            .map_err(|e| ContainerError::ContainerStart(e.to_string()));
+        let ready = match started {
+            Ok(()) if wait_for_health => self.wait_until_healthy(&created.id).await,
+            other => other,
+        };
+        if let Err(e) = ready {
+            let _ = self.stop_service(&created.id).await;
+            return Err(e);
+        }
+        let startup = startup_started.elapsed();
+        let ports = self.published_ports(&created.id).await;
+        Ok(StartedContainer {
+            id: created.id,
+            timings: ContainerTimings {
+                image_pull,
+                startup,
+            },
+            network: self.network(),
+            ports,
+        })
+    }
 
-        let mut stream = self.docker.create_image(Some(options), None, None);
+    /// The host ports a container's ports ended up on, like `5432` ->
+    /// `49153`, when they were published without one
+    async fn published_ports(&self, id: &str) -> HashMap<String, String> {
+        let Ok(inspected) = self.docker.inspect_container(id, None).await else {
+            return HashMap::new();
+        };
+        inspected
+            .network_settings
+            .and_then(|settings| settings.ports)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(port, bindings)| {
+                let host_port = bindings?
+                    .into_iter()
+                    .find_map(|binding| binding.host_port)?;
+                let port = port.strip_suffix("/tcp").unwrap_or(&port).to_string();
+                Some((port, host_port))
+            })
+            .collect()
+    }
 
-        while let Some(result) = stream.next().await {
-            if let Err(e) = result {
-                return Err(ContainerError::ImagePull(e.to_string()));
+    /// Wait for a container's `--health-cmd` to pass, like GitHub does
+    /// before running a job's steps
+    async fn wait_until_healthy(&self, id: &str) -> Result<(), ContainerError> {
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(180);
+        loop {
+            let inspected = self
+                .docker
+                .inspect_container(id, None)
+                .await
+                .map_err(|e| ContainerError::ContainerStart(e.to_string()))?;
+            let status = inspected
+                .state
+                .and_then(|state| state.health)
+                .and_then(|health| health.status)
+                .map(|status| status.to_string())
+                .unwrap_or_default();
+            match status.as_str() {
+                "healthy" => return Ok(()),
+                "unhealthy" => {
+                    return Err(ContainerError::ContainerStart(format!(
+                        "Container {} is unhealthy",
+                        id
+                    )))
+                }
+                _ if std::time::Instant::now() > deadline => {
+                    return Err(ContainerError::ContainerStart(format!(
+                        "Container {} didn't become healthy in time",
+                        id
+                    )))
+                }
+                _ => tokio::time::sleep(std::time::Duration::from_secs(1)).await,
             }
         }
+    }
 
-        Ok(())
+    async fn exec_in_container_inner(
+        &self,
+        id: &str,
+        cmd: &[&str],
+        env_vars: &[(&str, &str)],
+        working_dir: &Path,
+    ) -> Result<ContainerOutput, ContainerError> {
+        logging::debug(&format!("Running command in job container: {:?}", cmd));
+        let options = CreateExecOptions {
+            cmd: Some(cmd.iter().map(|s| s.to_string()).collect()),
+            env: Some(
+                env_vars
+                    .iter()
+                    .map(|(k, v)| format!("{}={}", k, v))
+                    .collect(),
+            ),
+            working_dir: Some(working_dir.to_string_lossy().to_string()),
+            attach_stdout: Some(true),
+            attach_stderr: Some(true),
+            ..Default::default()
+        };
+        let exec = self
+            .retry
+            .run("Creating an exec instance", || {
+                self.docker.create_exec(id, options.clone())
+            })
+            .await
+            .map_err(|e| ContainerError::ContainerExecution(e.to_string()))?;
+
+        let mut stdout = String::new();
+        let mut stderr = String::new();
+        let started = self
+            .docker
+            .start_exec(&exec.id, None)
+            .await
+            .map_err(|e| ContainerError::ContainerExecution(e.to_string()))?;
+        if let StartExecResults::Attached { mut output, .. } = started {
+            while let Some(Ok(log)) = output.next().await {
+                match log {
+                    bollard::container::LogOutput::StdOut { message } => {
+                        stdout.push_str(&String::from_utf8_lossy(&message));
+                    }
+                    bollard::container::LogOutput::StdErr { message } => {
+                        stderr.push_str(&String::from_utf8_lossy(&message));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let inspected = self
+            .retry
+            .run("Inspecting an exec instance", || {
+                self.docker.inspect_exec(&exec.id)
+            })
+            .await
+            .map_err(|e| ContainerError::ContainerExecution(e.to_string()))?;
+        let exit_code = inspected.exit_code.map_or(-1, |code| code as i32);
+        if exit_code != 0 {
+            let memory = self
+                .docker
+                .inspect_container(id, None)
+                .await
+                .ok()
+                .and_then(|container| container.host_config?.memory);
+            if let Some(message) = oom_message(false, exit_code, memory) {
+                logging::error(&message);
+                stderr.push_str(&format!("\n{}\n", message));
+            }
+        }
+        Ok(ContainerOutput {
+            stdout,
+            stderr,
+            exit_code,
+            timings: ContainerTimings::default(),
+        })
     }
 
-    async fn build_image_inner(&self, dockerfile: &Path, tag: &str) -> Result<(), ContainerError> {
-        let _context_dir = dockerfile.parent().unwrap_or(Path::new("."));
+    /// Pull an image for `platform`, or the host's, going on with the image
+    /// there is when the pull times out
+    async fn pull_image_for(
+        &self,
+        image: &str,
+        platform: Option<&str>,
+    ) -> Result<(), ContainerError> {
+        // Add a timeout for pull operations
+        let timeout_duration = std::time::Duration::from_secs(30);
 
-        let tar_buffer = {
-            let mut tar_builder = tar::Builder::new(Vec::new());
+        match tokio::time::timeout(timeout_duration, self.pull_image_inner(image, platform)).await {
+            Ok(result) => result,
+            Err(_) => {
+                logging::warning(&format!(
+                    "Pull of image {} timed out, continuing with existing image",
+                    image
+                ));
+                // Return success to allow continuing with existing image
+                Ok(())
+            }
+        }
+    }
 
-            // Add Dockerfile to tar
-            if let Ok(file) = std::fs::File::open(dockerfile) {
-                let mut header = tar::Header::new_gnu();
-                let metadata = file.metadata().map_err(|e| {
-                    ContainerError::ContainerExecution(format!(
-                        "Failed to get file metadata: {}",
-                        e
-                    ))
-                })?;
-                let modified_time = metadata
-                    .modified()
-                    .map_err(|e| {
-                        ContainerError::ContainerExecution(format!(
-                            "Failed to get file modification time: {}",
-                            e
-                        ))
-                    })?
-                    .elapsed()
-                    .map_err(|e| {
-                        ContainerError::ContainerExecution(format!(
-                            "Failed to get elapsed time since modification: {}",
-                            e
-                        ))
-                    })?
-                    .as_secs();
-                header.set_size(metadata.len());
-                header.set_mode(0o644);
-                header.set_mtime(modified_time);
-                header.set_cksum();
-
-                tar_builder
-                    .append_data(&mut header, "Dockerfile", file)
-                    .map_err(|e| ContainerError::ImageBuild(e.to_string()))?;
-            } else {
-                return Err(ContainerError::ImageBuild(format!(
-                    "Cannot open Dockerfile at {}",
-                    dockerfile.display()
-                )));
+    async fn pull_image_inner(
+        &self,
+        image: &str,
+        platform: Option<&str>,
+    ) -> Result<(), ContainerError> {
+        self.pull_image_with(image, None, platform).await
+    }
+
+    async fn pull_image_with(
+        &self,
+        image: &str,
+        credentials: Option<DockerCredentials>,
+        platform: Option<&str>,
+    ) -> Result<(), ContainerError> {
+        let options = bollard::image::CreateImageOptions {
+            from_image: image,
+            platform: platform.unwrap_or_default(),
+            ..Default::default()
+        };
+
+        // A pull cut off halfway starts over, reusing the layers it got
+        self.retry
+            .run(&format!("Pulling {}", image), || async {
+                let mut stream =
+                    self.docker
+                        .create_image(Some(options.clone()), None, credentials.clone());
+                while let Some(result) = stream.next().await {
+                    let info = result?;
+                    if let Some(status) = info.status {
+                        let status = [info.id, Some(status), info.progress]
+                            .into_iter()
+                            .flatten()
+                            .collect::<Vec<_>>()
+                            .join(" ");
+                        self.progress.image_pull(image, &status);
+                    }
+                }
+                Ok(())
+            })
+            .await
+            .map_err(|e| ContainerError::ImagePull(e.to_string()))
+    }
+
+    /// Build an image for `platform`, or the host's
+    async fn build_image_for(
+        &self,
+        dockerfile: &Path,
+        tag: &str,
+        platform: Option<&str>,
+    ) -> Result<(), ContainerError> {
+        // Add a timeout for build operations
+        let timeout_duration = std::time::Duration::from_secs(120); // 2 minutes timeout for builds
+
+        match tokio::time::timeout(
+            timeout_duration,
+            self.build_image_inner(dockerfile, tag, platform),
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(_) => {
+                logging::error(&format!(
+                    "Building image {} timed out after 120 seconds",
+                    tag
+                ));
+                Err(ContainerError::ImageBuild(
+                    "Operation timed out".to_string(),
+                ))
             }
+        }
+    }
+
+    async fn build_image_inner(
+        &self,
+        dockerfile: &Path,
+        tag: &str,
+        platform: Option<&str>,
+    ) -> Result<(), ContainerError> {
+        if !dockerfile.is_file() {
+            return Err(ContainerError::ImageBuild(format!(
+                "Cannot open Dockerfile at {}",
+                dockerfile.display()
+            )));
+        }
+        let context_dir = match dockerfile.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let dockerfile_name = dockerfile
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| "Dockerfile".to_string());
 
+        // Send the Dockerfile's whole directory as the build context so
+        // COPY and ADD instructions can reach the files next to it
+        let tar_buffer = {
+            let mut tar_builder = tar::Builder::new(Vec::new());
+            tar_builder
+                .append_dir_all(".", context_dir)
+                .map_err(|e| ContainerError::ImageBuild(e.to_string()))?;
             tar_builder
                 .into_inner()
                 .map_err(|e| ContainerError::ImageBuild(e.to_string()))?
         };
 
         let options = bollard::image::BuildImageOptions {
-            dockerfile: "Dockerfile",
+            dockerfile: dockerfile_name.as_str(),
             t: tag,
             q: false,
             nocache: false,
             rm: true,
+            platform: platform.unwrap_or_default(),
             ..Default::default()
         };
 
@@ -1107,8 +2277,11 @@ impl DockerRuntime {
 
         while let Some(result) = stream.next().await {
             match result {
-                Ok(_) => {
-                    // For verbose output, we could log the build progress here
+                // Failing Dockerfile instructions are reported in the stream, not as errors
+                Ok(info) => {
+                    if let Some(error) = info.error {
+                        return Err(ContainerError::ImageBuild(error));
+                    }
                 }
                 Err(e) => {
                     return Err(ContainerError::ImageBuild(e.to_string()));
@@ -1138,3 +2311,81 @@ pub fn get_tracked_networks() -> Vec<String> {
         vec![]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_networks_and_parses_ports() {
+        let name = run_network_name("Build & Test", "1718000000");
+        let suffix = name.strip_prefix("wrkflw-build-test-1718000000-").unwrap();
+        assert_eq!(suffix.len(), 6);
+        assert!(suffix.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(run_network_name("Build & Test", "1718000000"), name);
+        assert!(run_network_name("🚀", "42").starts_with("wrkflw-42-"));
+
+        assert_eq!(parse_port("5432"), ("5432/tcp".to_string(), None));
+        assert_eq!(
+            parse_port("8080:80/udp"),
+            ("80/udp".to_string(), Some("8080".to_string()))
+        );
+        assert_eq!(
+            parse_port("127.0.0.1:6380:6379"),
+            ("6379/tcp".to_string(), Some("6380".to_string()))
+        );
+    }
+
+    #[test]
+    fn sends_requests_outside_the_run_through_the_proxy() {
+        let env = proxy_env(&["postgres".to_string()]);
+        assert!(env.contains(&"HTTPS_PROXY=http://wrkflw-proxy:8888".to_string()));
+        assert!(env.contains(&"no_proxy=localhost,127.0.0.1,::1,postgres".to_string()));
+        assert!(proxy_script().contains("FilterDefaultDeny Yes"));
+        // tinyproxy comes with the image, not with every run
+        assert!(PROXY_DOCKERFILE.contains("apk add --no-cache tinyproxy"));
+        assert!(!proxy_script().contains("apk"));
+    }
+
+    #[test]
+    fn stops_only_the_containers_of_its_own_runtime() {
+        let runtime = || DockerRuntime::with_client(Docker::connect_with_local_defaults().unwrap());
+        let (first, second) = (runtime(), runtime());
+        first.track("first-job");
+        second.track("second-job");
+        assert_eq!(*first.containers.0.lock().unwrap(), ["first-job"]);
+        first.untrack("first-job");
+        second.untrack("second-job");
+        assert!(first.containers.0.lock().unwrap().is_empty());
+        assert!(!get_tracked_containers().contains(&"second-job".to_string()));
+    }
+
+    #[test]
+    fn parses_container_options() {
+        let options = ContainerOptions::parse(
+            "--user 1001 --cpus=1.5 -m 512m --privileged -e 'GREETING=hi there' \
+             --health-cmd \"pg_isready -U postgres\" --health-interval 10s --health-retries 5 \
+             --init --entrypoint /bin/sh --hostname db",
+        );
+        assert_eq!(options.user.as_deref(), Some("1001"));
+        assert_eq!(options.nano_cpus, Some(1_500_000_000));
+        assert_eq!(options.memory, Some(512 * 1024 * 1024));
+        assert!(options.privileged);
+        assert_eq!(options.env, vec!["GREETING=hi there"]);
+        assert_eq!(options.hostname.as_deref(), Some("db"));
+        let health = options.healthcheck.unwrap();
+        assert_eq!(
+            health.test,
+            Some(vec![
+                "CMD-SHELL".to_string(),
+                "pg_isready -U postgres".to_string()
+            ])
+        );
+        assert_eq!(health.interval, Some(10_000_000_000));
+        assert_eq!(health.retries, Some(5));
+
+        assert_eq!(parse_size("2g"), Some(2 << 30));
+        assert_eq!(parse_duration("500ms"), Some(500_000_000));
+        assert_eq!(ContainerOptions::parse(""), ContainerOptions::default());
+    }
+}
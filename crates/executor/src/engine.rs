@@ -2,23 +2,188 @@
 use bollard::Docker;
 use futures::future;
 use regex;
+use serde::{Deserialize, Serialize};
 use serde_yaml::Value;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
 use thiserror::Error;
-
-use crate::dependency;
+use tracing::Instrument;
+
+use crate::actions::{
+    self, ActionMetadata, ActionRef, Annotation, AnnotationLevel, WorkflowCommand,
+};
+use crate::artifacts::{self, ArtifactStore, IfNoFilesFound, SizeLimits};
+use crate::checkout::{self, Checkout, CheckoutOptions};
+use crate::concurrency::{CancellationToken, ConcurrencyGroup, ConcurrencyManager};
+use crate::dependency::{self, JobSelection};
+use crate::deployments::{Approvals, DeploymentEnvironment};
 use crate::docker;
-use crate::environment;
+use crate::docker::NetworkPolicy;
+use crate::environment::{
+    self, RunnerDirs, SimulatedEvent, StrategyContext, CONTAINER_RUNNER_TEMP, CONTAINER_TOOL_CACHE,
+    CONTAINER_WORKSPACE,
+};
+use crate::history;
+use crate::permissions::{self, TokenPermissions};
+use crate::platforms::{self, JobPlatforms};
+use crate::podman;
+use crate::progress::{ExecutionEvent, ProgressReporter};
+use crate::requirements;
+use crate::resources::JobResources;
+use crate::retry::RetryPolicy;
+use crate::runner_image;
+use crate::shell::Shell;
+use crate::stepping::{StepControl, StepDecision};
+use crate::tool_cache;
+use crate::workspace;
+use expressions::{EvaluationContext, JobState};
+use indexmap::IndexMap;
 use logging;
 use matrix::MatrixCombination;
-use models::gitlab::Pipeline;
+use models::github::{self, Job, JobContainer, OrExpression, Step, Workflow};
+use models::gitlab::{Job as GitlabJob, Pipeline};
 use parser::gitlab::{self, parse_pipeline};
-use parser::workflow::{self, parse_workflow, ActionInfo, Job, WorkflowDefinition};
-use runtime::container::ContainerRuntime;
+use parser::gitlab_rules::{self, RuleOutcome};
+use parser::sniff::{self, Platform};
+use parser::workflow::{parse_workflow, resolve_action, ActionInfo};
+use runtime::bubblewrap;
+use runtime::container::{
+    BackgroundContainer, ContainerError, ContainerOutput, ContainerRuntime, ContainerTimings,
+    StartedContainer,
+};
 use runtime::emulation;
+use runtime::setup_actions::{SetupAction, SetupActions, SetupContext};
+
+/// Settings for a single execution, passed down to every job and step
+/// instead of living in process-wide state
+#[derive(Debug, Clone)]
+pub struct ExecutionConfig {
+    pub runtime_type: RuntimeType,
+    pub verbose: bool,
+    /// Print "Would execute GitHub action" for actions that are only emulated
+    pub show_action_messages: bool,
+    /// Where job and step events are sent
+    pub progress: ProgressReporter,
+    /// Artifact and cache sizes that are warned about
+    pub size_limits: SizeLimits,
+    /// Jobs to run; the others are reported as skipped
+    pub job_selection: JobSelection,
+    /// Event the workflow runs as, instead of one guessed from its triggers
+    pub event: Option<SimulatedEvent>,
+    /// Warn before running about actions the runner can't support
+    pub version_check: bool,
+    /// Concurrency groups held by runs in this process, shared between the
+    /// configs of runs that should wait for or cancel each other
+    pub concurrency: ConcurrencyManager,
+    /// Cancels this run before its next step
+    pub cancellation: CancellationToken,
+    /// Leave the run's Docker network in place afterwards, for debugging
+    pub keep_network: bool,
+    /// What the run's containers may reach outside its network
+    pub network_policy: NetworkPolicy,
+    /// CPU and memory limits of job containers
+    pub resources: JobResources,
+    /// Platforms like `linux/arm64` that job containers run as
+    pub platforms: JobPlatforms,
+    /// Set `CI=true` and `GITHUB_ACTIONS=true` (or `GITLAB_CI=true`) in jobs
+    pub ci_env: bool,
+    /// Run the file as this kind of workflow instead of detecting it
+    pub platform: Option<Platform>,
+    /// Run emulated jobs in their own copy of the workspace with only the
+    /// run's environment and a few host variables
+    pub sandbox: bool,
+    /// Setup actions like actions/setup-node that emulation mode handles
+    /// with locally installed toolchains
+    pub setup_actions: Arc<SetupActions>,
+    /// How Docker and Podman API calls that fail for transient reasons are
+    /// retried
+    pub docker_retry: RetryPolicy,
+    /// Record the run's results in the history store
+    pub history: bool,
+    /// Write the run's log to a file in its directory under the logs root
+    pub log_file: bool,
+    /// Values of the `secrets` context
+    pub secrets: HashMap<String, String>,
+    /// Variables every job starts with, over the ones wrkflw sets
+    pub env: HashMap<String, String>,
+    /// Values of the `github` context, like `run_number`, over the ones
+    /// detected from the repository; see `environment::GITHUB_CONTEXT_KEYS`
+    pub github_context: HashMap<String, String>,
+    /// Stands in for the token GitHub gives each run, as `github.token` and
+    /// `secrets.GITHUB_TOKEN` when the secrets don't set one
+    pub github_token: String,
+    /// Images to run jobs in by `runs-on` label, ahead of the built-in ones
+    pub runner_images: HashMap<String, String>,
+    /// Mount named volumes over package manager caches in container jobs,
+    /// so they survive between runs
+    pub tool_cache: bool,
+    /// Run jobs in the project directory itself rather than a copy of it
+    pub bind_workspace: bool,
+    /// Open a shell where a failed `run` step ran, in Docker and Podman mode
+    pub debug_shell: bool,
+    /// Steps to pause before, and where to ask what to do with them
+    pub step_control: StepControl,
+    /// Deployment environments by name, for jobs with `environment:`
+    pub environments: HashMap<String, DeploymentEnvironment>,
+    /// Where deployments to protected environments are approved
+    pub approvals: Approvals,
+    /// Where the run's log lines go instead of the terminal and the shared
+    /// store, for callers running several workflows in one process
+    pub log_sink: Option<logging::LogSink>,
+}
+
+impl ExecutionConfig {
+    pub fn new(runtime_type: RuntimeType, verbose: bool) -> Self {
+        ExecutionConfig {
+            runtime_type,
+            verbose,
+            show_action_messages: false,
+            progress: ProgressReporter::default(),
+            size_limits: SizeLimits::default(),
+            job_selection: JobSelection::default(),
+            event: None,
+            version_check: false,
+            concurrency: ConcurrencyManager::default(),
+            cancellation: CancellationToken::default(),
+            keep_network: false,
+            network_policy: NetworkPolicy::Open,
+            resources: JobResources::default(),
+            platforms: JobPlatforms::default(),
+            ci_env: true,
+            platform: None,
+            sandbox: true,
+            setup_actions: Arc::default(),
+            docker_retry: RetryPolicy::default(),
+            history: false,
+            log_file: false,
+            secrets: HashMap::new(),
+            env: HashMap::new(),
+            github_context: HashMap::new(),
+            github_token: format!("ghs_{}", uuid::Uuid::new_v4().simple()),
+            runner_images: HashMap::new(),
+            tool_cache: false,
+            bind_workspace: false,
+            debug_shell: false,
+            step_control: StepControl::default(),
+            environments: HashMap::new(),
+            approvals: Approvals::default(),
+            log_sink: None,
+        }
+    }
+
+    /// The run's secrets with its token as `GITHUB_TOKEN`
+    fn secrets_with_token(&self) -> HashMap<String, String> {
+        let mut secrets = self.secrets.clone();
+        secrets
+            .entry("GITHUB_TOKEN".to_string())
+            .or_insert_with(|| self.github_token.clone());
+        secrets
+    }
+}
 
 #[allow(unused_variables, unused_assignments)]
 /// Execute a GitHub Actions workflow file locally
@@ -27,106 +192,282 @@ pub async fn execute_workflow(
     runtime_type: RuntimeType,
     verbose: bool,
 ) -> Result<ExecutionResult, ExecutionError> {
-    logging::info(&format!("Executing workflow: {}", workflow_path.display()));
-    logging::info(&format!("Runtime: {:?}", runtime_type));
-
-    // Determine if this is a GitLab CI/CD pipeline or GitHub Actions workflow
-    let is_gitlab = is_gitlab_pipeline(workflow_path);
+    execute_workflow_with_config(workflow_path, &ExecutionConfig::new(runtime_type, verbose)).await
+}
 
-    if is_gitlab {
-        execute_gitlab_pipeline(workflow_path, runtime_type, verbose).await
-    } else {
-        execute_github_workflow(workflow_path, runtime_type, verbose).await
+/// Execute a workflow file with explicit execution settings
+pub async fn execute_workflow_with_config(
+    workflow_path: &Path,
+    config: &ExecutionConfig,
+) -> Result<ExecutionResult, ExecutionError> {
+    let span = logging::workflow_span(&workflow_path.display().to_string(), config.log_file);
+    if let Some(sink) = &config.log_sink {
+        logging::set_span_sink(&span, sink.clone());
     }
-}
+    async {
+        logging::info(&format!("Executing workflow: {}", workflow_path.display()));
+        logging::info(&format!("Runtime: {:?}", config.runtime_type));
+
+        let platform = match config.platform {
+            Some(platform) => platform,
+            None => detect_platform(workflow_path)?,
+        };
 
-/// Determine if a file is a GitLab CI/CD pipeline
-fn is_gitlab_pipeline(path: &Path) -> bool {
-    // Check the file name
-    if let Some(file_name) = path.file_name() {
-        if let Some(file_name_str) = file_name.to_str() {
-            return file_name_str == ".gitlab-ci.yml" || file_name_str.ends_with("gitlab-ci.yml");
+        match platform {
+            Platform::GitLab => execute_gitlab_pipeline(workflow_path, config).await,
+            Platform::GitHub => execute_github_workflow(workflow_path, config).await,
+            other => Err(validate_only(workflow_path, other)),
         }
     }
+    .instrument(span)
+    .await
+}
 
-    // If file name check fails, try to read and determine by content
-    if let Ok(content) = fs::read_to_string(path) {
-        // GitLab CI/CD pipelines typically have stages, before_script, after_script at the top level
-        if content.contains("stages:")
-            || content.contains("before_script:")
-            || content.contains("after_script:")
-        {
-            // Check for GitHub Actions specific keys that would indicate it's not GitLab
-            if !content.contains("on:")
-                && !content.contains("runs-on:")
-                && !content.contains("uses:")
-            {
-                return true;
-            }
-        }
+/// Send the rest of the run's log to its file, when it writes one
+fn start_run_log(config: &ExecutionConfig, artifact_store: &ArtifactStore) {
+    if config.log_file {
+        logging::write_run_log(artifact_store.run_id());
+        logging::info(&format!(
+            "Writing the run's log to {}",
+            logging::run_log_path(artifact_store.run_id()).display()
+        ));
     }
+}
+
+/// The error for CircleCI and Azure Pipelines configs, which aren't run
+pub(crate) fn validate_only(path: &Path, platform: Platform) -> ExecutionError {
+    ExecutionError::Execution(format!(
+        "{}: wrkflw can validate {}s but not run them",
+        path.display(),
+        platform
+    ))
+}
 
-    false
+/// Whether a file is a GitHub workflow or a GitLab pipeline, refusing to
+/// guess when it looks like both
+pub(crate) fn detect_platform(path: &Path) -> Result<Platform, ExecutionError> {
+    let detection = sniff::detect_platform(path);
+    logging::debug(&format!("{}: {}", path.display(), detection.describe()));
+    detection.platform().ok_or_else(|| {
+        ExecutionError::Execution(format!(
+            "{} looks like both a GitHub workflow and a GitLab pipeline ({}); say which it is",
+            path.display(),
+            detection.describe()
+        ))
+    })
 }
 
 /// Execute a GitHub Actions workflow file locally
 async fn execute_github_workflow(
     workflow_path: &Path,
-    runtime_type: RuntimeType,
-    verbose: bool,
+    config: &ExecutionConfig,
 ) -> Result<ExecutionResult, ExecutionError> {
-    // 1. Parse workflow file
-    let workflow = parse_workflow(workflow_path)?;
+    let runtime_type = config.runtime_type.clone();
+    let progress = &config.progress;
+    let started_at = chrono::Local::now().to_rfc3339();
+
+    // 1. Parse workflow file; like GitHub, name unnamed workflows after their file
+    let mut workflow = parse_workflow(workflow_path)?;
+    workflow
+        .name
+        .get_or_insert_with(|| workflow_path.display().to_string());
+
+    if config.version_check {
+        let runner = requirements::RunnerCapabilities::for_runtime(&runtime_type);
+        for warning in requirements::check_workflow(&workflow, &runner) {
+            logging::warning(&warning.message);
+        }
+    }
 
     // 2. Resolve job dependencies and create execution plan
     let execution_plan = dependency::resolve_dependencies(&workflow)?;
+    let selected_jobs = dependency::select_jobs(&workflow, &config.job_selection)?;
+    let selected_plan = select_from_plan(&execution_plan, &selected_jobs);
+    progress.emit(ExecutionEvent::Planned {
+        total_steps: planned_step_count(&workflow, &selected_plan),
+    });
 
     // 3. Initialize appropriate runtime
-    let runtime = initialize_runtime(runtime_type.clone())?;
+    let (runtime, runtime_type) = initialize_runtime(runtime_type, config).await?;
+    let _stop_on_cancel = StopOnCancel::watch(runtime.as_ref(), &config.cancellation);
+    let debug_shell = debug_shell(config, &runtime_type);
+    if matches!(runtime_type, RuntimeType::Docker | RuntimeType::Podman) {
+        for (name, job) in &workflow.jobs {
+            if !selected_jobs.contains(name) {
+                continue;
+            }
+            if let Some(warning) =
+                runner_image::foreign_os_warning(name, job, &config.runner_images)
+            {
+                logging::warning(&warning);
+            }
+        }
+    }
 
     // Create a temporary workspace directory
     let workspace_dir = tempfile::tempdir()
         .map_err(|e| ExecutionError::Execution(format!("Failed to create workspace: {}", e)))?;
 
     // 4. Set up GitHub-like environment
-    let mut env_context = environment::create_github_context(&workflow, workspace_dir.path());
-
-    // Add runtime mode to environment
-    env_context.insert(
-        "WRKFLW_RUNTIME_MODE".to_string(),
-        if runtime_type == RuntimeType::Emulation {
-            "emulation".to_string()
-        } else {
-            "docker".to_string()
-        },
-    );
-
-    // Add flag to hide GitHub action messages when in emulation mode
-    env_context.insert(
-        "WRKFLW_HIDE_ACTION_MESSAGES".to_string(),
-        "true".to_string(),
-    );
+    let env_context = github_env_context(
+        &workflow,
+        workflow_path,
+        workspace_dir.path(),
+        &runtime_type,
+        config,
+    )?;
+    let secrets = config.secrets_with_token();
+
+    // Runs of the same concurrency group wait for, or cancel, each other.
+    // A run cancelled while waiting skips all of its jobs below.
+    let concurrency_group = resolve_concurrency(&workflow, &env_context)?;
+    let _concurrency_guard = match &concurrency_group {
+        Some(group) => {
+            logging::info(&format!("Joining concurrency group '{}'", group.name));
+            config
+                .concurrency
+                .start(group, &config.cancellation)
+                .await
+                .ok()
+        }
+        None => None,
+    };
 
-    // Setup GitHub environment files
-    environment::setup_github_environment_files(workspace_dir.path()).map_err(|e| {
-        ExecutionError::Execution(format!("Failed to setup GitHub env files: {}", e))
-    })?;
+    let run_id = env_context
+        .get("GITHUB_RUN_ID")
+        .cloned()
+        .unwrap_or_default();
+    create_run_network(
+        runtime.as_ref(),
+        workflow.name.as_deref().unwrap_or_default(),
+        &run_id,
+    )
+    .await?;
+
+    // Artifacts are shared between all jobs of this run
+    let artifact_store = ArtifactStore::new_run(
+        &artifacts::artifacts_root(),
+        &workflow_path.to_string_lossy(),
+    )
+    .with_size_limits(config.size_limits);
+    start_run_log(config, &artifact_store);
 
     // 5. Execute jobs according to the plan
     let mut results = Vec::new();
     let mut has_failures = false;
     let mut failure_details = String::new();
+    let mut finished_jobs: HashMap<String, JobSummary> = HashMap::new();
 
     for job_batch in execution_plan {
+        // Jobs whose `if:` condition is false are skipped before the batch runs
+        let mut job_results = Vec::new();
+        let mut runnable_jobs = Vec::new();
+        for job_name in &job_batch {
+            if !selected_jobs.contains(job_name) {
+                job_results.push(not_selected_result(job_name));
+                continue;
+            }
+            if let Some(reason) = config.cancellation.reason() {
+                skip_planned_steps(job_name, &workflow, progress);
+                job_results.push(JobResult {
+                    name: job_name.clone(),
+                    status: JobStatus::Skipped,
+                    steps: Vec::new(),
+                    logs: format!("Job skipped: the run was {}", reason),
+                    outputs: HashMap::new(),
+                    duration: Duration::ZERO,
+                    overhead: Overhead::default(),
+                    summary: String::new(),
+                });
+                continue;
+            }
+            match check_job_condition(job_name, &workflow, &env_context, &finished_jobs) {
+                Ok(true) => runnable_jobs.push(job_name.clone()),
+                Ok(false) => {
+                    logging::info(&format!("Skipping job '{}': condition not met", job_name));
+                    skip_planned_steps(job_name, &workflow, progress);
+                    job_results.push(JobResult {
+                        name: job_name.clone(),
+                        status: JobStatus::Skipped,
+                        steps: Vec::new(),
+                        logs: "Job skipped: 'if' condition evaluated to false or a needed job did not succeed".to_string(),
+                        outputs: HashMap::new(),
+                        duration: Duration::ZERO,
+                        overhead: Overhead::default(),
+                        summary: String::new(),
+                    });
+                }
+                Err(e) => {
+                    skip_planned_steps(job_name, &workflow, progress);
+                    job_results.push(JobResult {
+                        name: job_name.clone(),
+                        status: JobStatus::Failure,
+                        steps: Vec::new(),
+                        logs: e,
+                        outputs: HashMap::new(),
+                        duration: Duration::ZERO,
+                        overhead: Overhead::default(),
+                        summary: String::new(),
+                    });
+                }
+            }
+        }
+
         // Execute jobs in parallel if they don't depend on each other
-        let job_results = execute_job_batch(
-            &job_batch,
-            &workflow,
-            runtime.as_ref(),
-            &env_context,
-            verbose,
-        )
-        .await?;
+        let batch_ctx = BatchExecutionContext {
+            workflow: &workflow,
+            runtime: runtime.as_ref(),
+            env_context: &env_context,
+            artifact_store: &artifact_store,
+            finished_jobs: &finished_jobs,
+            progress,
+            cancellation: &config.cancellation,
+            step_control: &config.step_control,
+            verbose: config.verbose,
+            show_action_messages: config.show_action_messages,
+            sandbox: config.sandbox,
+            setup_actions: &config.setup_actions,
+            secrets: &secrets,
+            runner_images: &config.runner_images,
+            tool_cache: config.tool_cache,
+            bind_workspace: config.bind_workspace,
+            debug_shell,
+            environments: &config.environments,
+            approvals: &config.approvals,
+        };
+        match execute_job_batch(&runnable_jobs, &batch_ctx).await {
+            Ok(batch_results) => job_results.extend(batch_results),
+            Err(e) => {
+                release_run_network(runtime.as_ref(), config.keep_network).await;
+                return Err(e);
+            }
+        }
+
+        // Matrix jobs report one result per combination, named "job (...)".
+        // Unselected jobs aren't recorded, so the jobs that need them still run.
+        for job_name in job_batch
+            .iter()
+            .filter(|name| selected_jobs.contains(*name))
+        {
+            let prefix = format!("{} (", job_name);
+            let mut statuses = Vec::new();
+            let mut outputs = HashMap::new();
+            for result in job_results
+                .iter()
+                .filter(|r| &r.name == job_name || r.name.starts_with(&prefix))
+            {
+                statuses.push(&result.status);
+                outputs.extend(result.outputs.clone());
+            }
+            let status = if statuses.contains(&&JobStatus::Failure) {
+                JobStatus::Failure
+            } else if statuses.contains(&&JobStatus::Success) {
+                JobStatus::Success
+            } else {
+                JobStatus::Skipped
+            };
+            finished_jobs.insert(job_name.clone(), JobSummary { status, outputs });
+        }
 
         // Check for job failures and collect details
         for job_result in &job_results {
@@ -146,57 +487,1016 @@ async fn execute_github_workflow(
         results.extend(job_results);
     }
 
+    release_run_network(runtime.as_ref(), config.keep_network).await;
+
+    if let Some(reason) = config.cancellation.reason() {
+        logging::warning(&format!("Workflow run {}", reason));
+    }
+
     // If there were failures, add detailed failure information to the result
     if has_failures {
         logging::error(&format!("Workflow execution failed:{}", failure_details));
     }
 
-    Ok(ExecutionResult {
+    let result = ExecutionResult {
         jobs: results,
         failure_details: if has_failures {
             Some(failure_details)
         } else {
             None
         },
+    };
+    if config.history {
+        record_history(
+            workflow_path,
+            &artifact_store,
+            &runtime_type,
+            &started_at,
+            &result,
+            config.cancellation.reason(),
+        );
+    }
+    Ok(result)
+}
+
+/// Variables every job of a GitHub workflow run starts with
+fn github_env_context(
+    workflow: &Workflow,
+    workflow_path: &Path,
+    workspace_dir: &Path,
+    runtime_type: &RuntimeType,
+    config: &ExecutionConfig,
+) -> Result<HashMap<String, String>, ExecutionError> {
+    let mut env_context = environment::create_github_context(workflow, workspace_dir);
+    environment::set_ci_env(&mut env_context, "GITHUB_ACTIONS", config.ci_env);
+
+    // Add runtime mode to environment
+    env_context.insert(
+        "WRKFLW_RUNTIME_MODE".to_string(),
+        runtime_type.mode_name().to_string(),
+    );
+    let in_container = runs_in_container(&env_context);
+    environment::set_runner_env(
+        &mut env_context,
+        in_container,
+        config.platforms.default.as_deref(),
+    );
+
+    // Setup GitHub environment files
+    environment::setup_github_environment_files(workspace_dir).map_err(|e| {
+        ExecutionError::Execution(format!("Failed to setup GitHub env files: {}", e))
+    })?;
+    // Without an event, steps still find a payload at $GITHUB_EVENT_PATH
+    let event = config.event.clone().unwrap_or_else(|| SimulatedEvent {
+        name: env_context["GITHUB_EVENT_NAME"].clone(),
+        payload: serde_json::json!({}),
+    });
+    environment::apply_event(&mut env_context, workspace_dir, &event).map_err(|e| {
+        ExecutionError::Execution(format!("Failed to write the event payload: {}", e))
+    })?;
+    let workflow_file = std::env::current_dir()
+        .ok()
+        .and_then(|dir| workflow_path.strip_prefix(dir).ok().map(Path::to_path_buf))
+        .unwrap_or_else(|| workflow_path.to_path_buf());
+    environment::complete_github_context(
+        &mut env_context,
+        Some(&workflow_file),
+        &config.github_context,
+    )
+    .map_err(ExecutionError::Execution)?;
+    env_context.extend(config.env.clone());
+    Ok(env_context)
+}
+
+/// Prepare a job of a GitHub workflow like a run does, with its workspace,
+/// services and container, and open a shell in its container instead of
+/// running its steps. Jobs without a `container:` get one of their runner
+/// image.
+pub async fn open_job_shell(
+    workflow_path: &Path,
+    job_name: &str,
+    config: &ExecutionConfig,
+) -> Result<(), ExecutionError> {
+    let mut workflow = parse_workflow(workflow_path)?;
+    workflow
+        .name
+        .get_or_insert_with(|| workflow_path.display().to_string());
+    let job = workflow.jobs.get(job_name).ok_or_else(|| {
+        let mut names: Vec<&String> = workflow.jobs.keys().collect();
+        names.sort();
+        ExecutionError::Execution(format!(
+            "{} has no job '{}'. Its jobs are: {}",
+            workflow_path.display(),
+            job_name,
+            names
+                .iter()
+                .map(|name| name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))
+    })?;
+    if runner_image::runs_on_host(job, &config.runner_images) {
+        return Err(ExecutionError::Execution(format!(
+            "Job '{}' runs on the host, as its runs-on label is mapped to {}, so it has no container to open a shell in",
+            job_name,
+            runner_image::HOST
+        )));
+    }
+
+    let (runtime, runtime_type) = initialize_runtime(config.runtime_type.clone(), config).await?;
+    if !matches!(runtime_type, RuntimeType::Docker | RuntimeType::Podman) {
+        return Err(ExecutionError::Runtime(
+            "Opening a shell in a job's container needs Docker or Podman".to_string(),
+        ));
+    }
+    let workspace_dir = tempfile::tempdir()
+        .map_err(|e| ExecutionError::Execution(format!("Failed to create workspace: {}", e)))?;
+    let env_context = github_env_context(
+        &workflow,
+        workflow_path,
+        workspace_dir.path(),
+        &runtime_type,
+        config,
+    )?;
+    let run_id = env_context
+        .get("GITHUB_RUN_ID")
+        .cloned()
+        .unwrap_or_default();
+    create_run_network(
+        runtime.as_ref(),
+        workflow.name.as_deref().unwrap_or_default(),
+        &run_id,
+    )
+    .await?;
+
+    let shell = open_shell_in_job(
+        &workflow,
+        job_name,
+        job,
+        runtime.as_ref(),
+        &env_context,
+        config,
+    )
+    .await;
+    release_run_network(runtime.as_ref(), config.keep_network).await;
+    shell
+}
+
+async fn open_shell_in_job(
+    workflow: &Workflow,
+    job_name: &str,
+    job: &Job,
+    runtime: &dyn ContainerRuntime,
+    env_context: &HashMap<String, String>,
+    config: &ExecutionConfig,
+) -> Result<(), ExecutionError> {
+    let needs = needs_context(job, &HashMap::new());
+    let secrets = config.secrets_with_token();
+    let mut job_env = env_context.clone();
+    job_env.extend(job_env_values(
+        workflow,
+        job,
+        env_context,
+        None,
+        &needs,
+        &secrets,
+        &HashMap::new(),
+    ));
+
+    let job_dir = job_workspace(workflow, job_name, config.bind_workspace)?;
+    let file_commands_dir = tempfile::tempdir().map_err(|e| {
+        ExecutionError::Execution(format!("Failed to create step output directory: {}", e))
+    })?;
+    let mut runner_dirs =
+        create_runner_dirs(&mut job_env, config.sandbox.then_some(job_dir.path()))?;
+    if config.tool_cache {
+        mount_tool_caches(runtime, &mut runner_dirs, &mut job_env, workflow, job_name).await;
+    }
+    job_env.insert("GITHUB_JOB".to_string(), job_name.to_string());
+    add_job_token(&mut job_env, workflow, job, &secrets);
+
+    let expression_ctx = JobExpressionState {
+        job_env: &job_env,
+        env_file: HashMap::new(),
+        secrets: &secrets,
+        vars: &HashMap::new(),
+        matrix: None,
+        needs,
+        steps: serde_json::Map::new(),
+        strategy: StrategyContext::default(),
+        job: serde_json::Map::new(),
+        cancellation: &config.cancellation,
+    }
+    .context(true);
+    let mut overhead = Overhead::default();
+    let services = start_job_services(job_name, job, runtime, &expression_ctx, &mut overhead)
+        .await
+        .map_err(ExecutionError::Execution)?;
+    let mounts = job_volume_paths(job_dir.path(), file_commands_dir.path(), &runner_dirs);
+    let container = match start_job_container(
+        job_name,
+        job,
+        runtime,
+        &mounts,
+        &expression_ctx,
+        &mut overhead,
+    )
+    .await
+    {
+        Ok(Some(started)) => Ok(started.id),
+        Ok(None) => {
+            let image = runner_image::job_image(job, &config.runner_images);
+            logging::info(&format!("Starting {} for job '{}'", image, job_name));
+            shell_container(runtime, &image, &mounts).await
+        }
+        Err(e) => Err(e),
+    };
+
+    let shell = match &container {
+        Ok(id) => {
+            let env_vars: Vec<(&str, &str)> = job_env
+                .iter()
+                .map(|(k, v)| (k.as_str(), v.as_str()))
+                .collect();
+            runtime
+                .open_shell(id, &env_vars, Path::new(CONTAINER_WORKSPACE))
+                .await
+                .map_err(|e| ExecutionError::Runtime(e.to_string()))
+        }
+        Err(e) => Err(ExecutionError::Runtime(e.clone())),
+    };
+    let service_ids = services.iter().map(|(_, service)| &service.id);
+    stop_job_services(runtime, container.iter().chain(service_ids)).await;
+    shell
+}
+
+/// Add a finished run to the history store, under the id of its artifacts
+fn record_history(
+    path: &Path,
+    artifact_store: &ArtifactStore,
+    runtime_type: &RuntimeType,
+    started_at: &str,
+    result: &ExecutionResult,
+    cancelled: Option<String>,
+) {
+    let record = history::RunRecord {
+        id: artifact_store.run_id().to_string(),
+        workflow: path.to_path_buf(),
+        runtime: runtime_type.mode_name().to_string(),
+        started_at: started_at.to_string(),
+        finished_at: chrono::Local::now().to_rfc3339(),
+        success: result.failure_details.is_none(),
+        jobs: result.jobs.clone(),
+        failure_details: result.failure_details.clone(),
+        cancelled,
+    };
+    if let Err(e) = history::save(&history::history_root(), &record) {
+        logging::warning(&format!("Failed to record the run in the history: {}", e));
+    }
+}
+
+/// Number of steps a job runs, counting every matrix combination
+fn planned_job_steps(job: &Job) -> usize {
+    job.steps.len() * planned_job_runs(job)
+}
+
+/// Number of times a job's steps run, once per matrix combination
+fn planned_job_runs(job: &Job) -> usize {
+    match job_matrix(job) {
+        // A matrix that fails to expand fails the job before any step runs
+        Ok(Some(matrix_config)) => matrix::expand_matrix(&matrix_config)
+            .map(|combinations| combinations.len())
+            .unwrap_or(0),
+        Ok(None) => 1,
+        Err(_) => 0,
+    }
+}
+
+/// The `strategy.matrix` of a job, with its settings, in the form the
+/// matrix crate expands
+pub(crate) fn job_matrix(job: &Job) -> Result<Option<matrix::MatrixConfig>, String> {
+    let Some(strategy) = &job.strategy else {
+        return Ok(None);
+    };
+    let (parameters, include, exclude) = match &strategy.matrix {
+        None => return Ok(None),
+        Some(github::Matrix::Expression(expression)) => {
+            return Err(format!(
+                "matrices built by an expression ('{}') aren't supported",
+                expression
+            ))
+        }
+        Some(github::Matrix::Values {
+            parameters,
+            include,
+            exclude,
+        }) => (parameters, include, exclude),
+    };
+    let entries = |entries: &Vec<IndexMap<String, serde_yaml::Value>>| {
+        entries
+            .iter()
+            .map(|entry| entry.clone().into_iter().collect())
+            .collect()
+    };
+
+    Ok(Some(matrix::MatrixConfig {
+        parameters: parameters.clone(),
+        include: entries(include),
+        exclude: entries(exclude),
+        max_parallel: match strategy.max_parallel {
+            Some(OrExpression::Value(max)) => Some(max as usize),
+            _ => None,
+        },
+        fail_fast: match strategy.fail_fast {
+            Some(OrExpression::Value(fail_fast)) => Some(fail_fast),
+            _ => None,
+        },
+    }))
+}
+
+/// The batches of an execution plan, keeping only the selected jobs
+fn select_from_plan(plan: &[Vec<String>], selected_jobs: &HashSet<String>) -> Vec<Vec<String>> {
+    plan.iter()
+        .map(|batch| {
+            batch
+                .iter()
+                .filter(|job_name| selected_jobs.contains(*job_name))
+                .cloned()
+                .collect()
+        })
+        .collect()
+}
+
+fn not_selected_result(job_name: &str) -> JobResult {
+    JobResult {
+        name: job_name.to_string(),
+        status: JobStatus::Skipped,
+        steps: Vec::new(),
+        logs: "Job skipped: not selected to run".to_string(),
+        outputs: HashMap::new(),
+        duration: Duration::ZERO,
+        overhead: Overhead::default(),
+        summary: String::new(),
+    }
+}
+
+/// Names of the converted jobs that GitLab job names stand for
+fn expand_gitlab_job_names(pipeline: &Pipeline, names: &[String]) -> Vec<String> {
+    names
+        .iter()
+        .flat_map(|name| gitlab::instance_names(pipeline, name))
+        .collect()
+}
+
+fn planned_step_count(workflow: &Workflow, plan: &[Vec<String>]) -> usize {
+    plan.iter()
+        .flatten()
+        .filter_map(|job_name| workflow.jobs.get(job_name))
+        .map(planned_job_steps)
+        .sum()
+}
+
+/// Report every step of a job that won't run as skipped, so progress still
+/// reaches the planned total
+fn skip_planned_steps(job_name: &str, workflow: &Workflow, progress: &ProgressReporter) {
+    if let Some(job) = workflow.jobs.get(job_name) {
+        for _ in 0..planned_job_runs(job) {
+            for (idx, step) in job.steps.iter().enumerate() {
+                let step_name = step_display_name(step, idx);
+                progress.step_completed(job_name, &step_name, &StepStatus::Skipped);
+            }
+        }
+    }
+}
+
+fn step_display_name(step: &Step, idx: usize) -> String {
+    step.name
+        .clone()
+        .unwrap_or_else(|| format!("Step {}", idx + 1))
+}
+
+/// A job and the reason it wouldn't run, if any
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobPreview {
+    pub name: String,
+    pub skip_reason: Option<String>,
+}
+
+/// Work out which jobs of a workflow would run for the event and job
+/// selection of `config`, assuming every job that runs succeeds
+pub fn preview_jobs(
+    workflow: &Workflow,
+    config: &ExecutionConfig,
+) -> Result<Vec<JobPreview>, ExecutionError> {
+    let execution_plan = dependency::resolve_dependencies(workflow)?;
+    let selected_jobs = dependency::select_jobs(workflow, &config.job_selection)?;
+    let (_workspace_dir, env_context) = preview_env_context(workflow, config)?;
+
+    let mut previews = Vec::new();
+    let mut finished_jobs = HashMap::new();
+    for job_name in execution_plan.iter().flat_map(|batch| {
+        let mut batch: Vec<&String> = batch.iter().collect();
+        batch.sort();
+        batch
+    }) {
+        if !selected_jobs.contains(job_name) {
+            previews.push(JobPreview {
+                name: job_name.clone(),
+                skip_reason: Some("not selected to run".to_string()),
+            });
+            continue;
+        }
+        let skip_reason =
+            match check_job_condition(job_name, workflow, &env_context, &finished_jobs)? {
+                true => None,
+                false => Some("'if' condition is false or a needed job did not run".to_string()),
+            };
+        let status = if skip_reason.is_none() {
+            JobStatus::Success
+        } else {
+            JobStatus::Skipped
+        };
+        finished_jobs.insert(
+            job_name.clone(),
+            JobSummary {
+                status,
+                outputs: HashMap::new(),
+            },
+        );
+        previews.push(JobPreview {
+            name: job_name.clone(),
+            skip_reason,
+        });
+    }
+    Ok(previews)
+}
+
+/// What a dry run expects a job, or one matrix combination of it, to do
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JobPlan {
+    pub name: String,
+    /// Position in the execution order; jobs of the same batch run in parallel
+    pub batch: usize,
+    pub skip_reason: Option<String>,
+    /// Images pulled for the job, in Docker and Podman mode
+    pub images: Vec<String>,
+    pub steps: Vec<StepPlan>,
+}
+
+/// What a dry run expects a step to do
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StepPlan {
+    pub name: String,
+    pub uses: Option<String>,
+    /// First line of the step's `run:` command
+    pub run: Option<String>,
+    pub skip_reason: Option<String>,
+    /// Why running the step would need network access, if it would
+    pub network: Option<String>,
+}
+
+/// Work out which jobs and steps of a workflow or pipeline would run, in
+/// which order and with which images, without running anything. Jobs and
+/// steps are assumed to succeed.
+pub fn plan_workflow_file(
+    path: &Path,
+    config: &ExecutionConfig,
+) -> Result<Vec<JobPlan>, ExecutionError> {
+    let platform = match config.platform {
+        Some(platform) => platform,
+        None => detect_platform(path)?,
+    };
+    match platform {
+        Platform::GitHub => {
+            let workflow = parse_workflow(path)?;
+            plan_jobs(&workflow, &config.job_selection, config)
+        }
+        Platform::GitLab => {
+            let pipeline = parse_pipeline(path).map_err(|e| {
+                ExecutionError::Parse(format!("Failed to parse GitLab pipeline: {}", e))
+            })?;
+            let selection = JobSelection {
+                jobs: expand_gitlab_job_names(&pipeline, &config.job_selection.jobs),
+                skip: expand_gitlab_job_names(&pipeline, &config.job_selection.skip),
+                with_needs: config.job_selection.with_needs,
+            };
+            let workflow = gitlab::convert_to_workflow_format(&pipeline);
+            plan_jobs(&workflow, &selection, config)
+        }
+        other => Err(validate_only(path, other)),
+    }
+}
+
+fn plan_jobs(
+    workflow: &Workflow,
+    selection: &JobSelection,
+    config: &ExecutionConfig,
+) -> Result<Vec<JobPlan>, ExecutionError> {
+    let execution_plan = dependency::resolve_dependencies(workflow)?;
+    let selected_jobs = dependency::select_jobs(workflow, selection)?;
+    let (_workspace_dir, env_context) = preview_env_context(workflow, config)?;
+
+    let mut plans = Vec::new();
+    let mut finished_jobs = HashMap::new();
+    for (batch_idx, batch) in execution_plan.iter().enumerate() {
+        let mut batch: Vec<&String> = batch.iter().collect();
+        batch.sort();
+        for job_name in batch {
+            let job = &workflow.jobs[job_name];
+            let skip_reason = if !selected_jobs.contains(job_name) {
+                Some("not selected to run".to_string())
+            } else if !check_job_condition(job_name, workflow, &env_context, &finished_jobs)? {
+                Some("'if' condition is false or a needed job did not run".to_string())
+            } else {
+                None
+            };
+            let status = match skip_reason {
+                None => JobStatus::Success,
+                Some(_) => JobStatus::Skipped,
+            };
+
+            let combinations = match job_matrix(job) {
+                Ok(Some(matrix_config)) => matrix::expand_matrix(&matrix_config).map_err(|e| {
+                    ExecutionError::Execution(format!("Failed to expand matrix: {}", e))
+                })?,
+                Ok(None) => Vec::new(),
+                Err(e) => {
+                    return Err(ExecutionError::Execution(format!(
+                        "Job '{}': {}",
+                        job_name, e
+                    )))
+                }
+            };
+            let instances: Vec<(String, Option<&HashMap<String, Value>>)> =
+                if combinations.is_empty() {
+                    vec![(job_name.clone(), None)]
+                } else {
+                    combinations
+                        .iter()
+                        .map(|combination| {
+                            (
+                                matrix::format_combination_name(job_name, combination),
+                                Some(&combination.values),
+                            )
+                        })
+                        .collect()
+                };
+
+            if skip_reason.is_none()
+                && matches!(
+                    config.runtime_type,
+                    RuntimeType::Docker | RuntimeType::Podman
+                )
+            {
+                if let Some(warning) =
+                    runner_image::foreign_os_warning(job_name, job, &config.runner_images)
+                {
+                    logging::warning(&warning);
+                }
+            }
+            for (name, matrix) in instances {
+                let mut plan = JobPlan {
+                    name,
+                    batch: batch_idx + 1,
+                    skip_reason: skip_reason.clone(),
+                    images: Vec::new(),
+                    steps: Vec::new(),
+                };
+                if skip_reason.is_none() {
+                    plan_job_steps(
+                        &mut plan,
+                        workflow,
+                        job,
+                        &env_context,
+                        matrix,
+                        &finished_jobs,
+                        config,
+                    );
+                }
+                plans.push(plan);
+            }
+            finished_jobs.insert(
+                job_name.clone(),
+                JobSummary {
+                    status,
+                    outputs: HashMap::new(),
+                },
+            );
+        }
+    }
+    Ok(plans)
+}
+
+fn plan_job_steps(
+    plan: &mut JobPlan,
+    workflow: &Workflow,
+    job: &Job,
+    env_context: &HashMap<String, String>,
+    matrix: Option<&HashMap<String, Value>>,
+    finished_jobs: &HashMap<String, JobSummary>,
+    config: &ExecutionConfig,
+) {
+    let pulls_images = matches!(
+        config.runtime_type,
+        RuntimeType::Docker | RuntimeType::Podman
+    );
+    let needs = needs_context(job, finished_jobs);
+    let secrets = config.secrets_with_token();
+    let mut job_env = env_context.clone();
+    job_env.extend(job_env_values(
+        workflow,
+        job,
+        env_context,
+        matrix,
+        &needs,
+        &secrets,
+        &HashMap::new(),
+    ));
+    let cancellation = CancellationToken::default();
+    let mut expression_state = JobExpressionState {
+        job_env: &job_env,
+        env_file: HashMap::new(),
+        secrets: &secrets,
+        vars: &HashMap::new(),
+        matrix,
+        needs,
+        steps: serde_json::Map::new(),
+        strategy: StrategyContext::default(),
+        job: serde_json::Map::new(),
+        cancellation: &cancellation,
+    };
+
+    if pulls_images {
+        let ctx = expression_state.context(true);
+        let render =
+            |image: &str| expressions::interpolate(image, &ctx).unwrap_or(image.to_string());
+        if !runner_image::runs_on_host(job, &config.runner_images) {
+            plan.images
+                .push(render(&runner_image::job_image(job, &config.runner_images)));
+        }
+        plan.images
+            .extend(job.services.values().map(|service| render(&service.image)));
+    }
+
+    for (idx, step) in job.steps.iter().enumerate() {
+        let ctx = expression_state.context(true);
+        let skip_reason = check_step_condition(step, idx, &ctx, true).map(|result| {
+            let reason = result.output.strip_prefix("Step skipped: ");
+            reason.map_or(result.output.clone(), str::to_string)
+        });
+        let network = match &step.uses {
+            Some(uses) if skip_reason.is_none() => step_network(uses, pulls_images),
+            _ => None,
+        };
+        if let (Some(uses), Some(_)) = (&step.uses, &network) {
+            if let Some(image) = uses.strip_prefix("docker://") {
+                plan.images.push(image.to_string());
+            }
+        }
+        let status = match skip_reason {
+            None => StepStatus::Success,
+            Some(_) => StepStatus::Skipped,
+        };
+        expression_state.record_step(step, &status, HashMap::new());
+        plan.steps.push(StepPlan {
+            name: step_display_name(step, idx),
+            uses: step.uses.clone(),
+            run: step
+                .run
+                .as_ref()
+                .and_then(|run| run.lines().next())
+                .map(str::to_string),
+            skip_reason,
+            network,
+        });
+    }
+    let mut seen = HashSet::new();
+    plan.images.retain(|image| seen.insert(image.clone()));
+}
+
+/// Why running `uses` would reach the network: container runtimes pull
+/// `docker://` images and clone actions that aren't cached yet, which
+/// emulation mode doesn't do
+fn step_network(uses: &str, pulls_images: bool) -> Option<String> {
+    if !pulls_images || uses.starts_with("./") {
+        return None;
+    }
+    if let Some(image) = uses.strip_prefix("docker://") {
+        return Some(format!("pulls {}", image));
+    }
+    if actions::is_built_in(uses) {
+        return None;
+    }
+    let action = ActionRef::parse(uses)?;
+    (!actions::is_fetched(&action, &actions::actions_root())).then(|| {
+        format!(
+            "clones {} from {}",
+            action.repo,
+            actions::actions_base_url()
+        )
     })
 }
 
+/// The concurrency group a run of `workflow` would join, for the event of `config`
+pub fn concurrency_group(
+    workflow: &Workflow,
+    config: &ExecutionConfig,
+) -> Result<Option<ConcurrencyGroup>, ExecutionError> {
+    let (_workspace_dir, env_context) = preview_env_context(workflow, config)?;
+    resolve_concurrency(workflow, &env_context)
+}
+
+/// The environment a run would start with, for looking at a workflow
+/// without running it. The event payload lives in the returned directory.
+fn preview_env_context(
+    workflow: &Workflow,
+    config: &ExecutionConfig,
+) -> Result<(tempfile::TempDir, HashMap<String, String>), ExecutionError> {
+    let workspace_dir = tempfile::tempdir()
+        .map_err(|e| ExecutionError::Execution(format!("Failed to create workspace: {}", e)))?;
+    let mut env_context = environment::create_github_context(workflow, workspace_dir.path());
+    environment::set_ci_env(&mut env_context, "GITHUB_ACTIONS", config.ci_env);
+    environment::setup_github_environment_files(workspace_dir.path()).map_err(|e| {
+        ExecutionError::Execution(format!("Failed to setup GitHub env files: {}", e))
+    })?;
+    if let Some(event) = &config.event {
+        environment::apply_event(&mut env_context, workspace_dir.path(), event).map_err(|e| {
+            ExecutionError::Execution(format!("Failed to write the event payload: {}", e))
+        })?;
+    }
+    environment::complete_github_context(&mut env_context, None, &config.github_context)
+        .map_err(ExecutionError::Execution)?;
+    env_context.extend(config.env.clone());
+    Ok((workspace_dir, env_context))
+}
+
+fn resolve_concurrency(
+    workflow: &Workflow,
+    env_context: &HashMap<String, String>,
+) -> Result<Option<ConcurrencyGroup>, ExecutionError> {
+    let Some(concurrency) = &workflow.concurrency else {
+        return Ok(None);
+    };
+    let ctx = environment::create_expression_context(env_context, None);
+    ConcurrencyGroup::resolve(concurrency, &ctx)
+        .map(Some)
+        .map_err(ExecutionError::Execution)
+}
+
+/// Decide whether a job should run, based on its `if:` condition and the
+/// results of the jobs it needs
+fn check_job_condition(
+    job_name: &str,
+    workflow: &Workflow,
+    env_context: &HashMap<String, String>,
+    finished_jobs: &HashMap<String, JobSummary>,
+) -> Result<bool, String> {
+    let Some(job) = workflow.jobs.get(job_name) else {
+        return Ok(true);
+    };
+
+    let mut all_succeeded = true;
+    let mut any_failed = false;
+    for need in job.needs() {
+        if let Some(summary) = finished_jobs.get(need) {
+            all_succeeded &= summary.status == JobStatus::Success;
+            any_failed |= summary.status == JobStatus::Failure;
+        }
+    }
+
+    let Some(condition) = &job.if_condition else {
+        return Ok(all_succeeded);
+    };
+
+    // Only status functions like always() can run a job whose needs did not all succeed
+    if !all_succeeded && !expressions::has_status_function(condition) {
+        return Ok(false);
+    }
+
+    let mut ctx = environment::create_expression_context(env_context, None);
+    ctx.set("needs", needs_context(job, finished_jobs));
+    ctx.job_state = if any_failed {
+        JobState::Failure
+    } else {
+        JobState::Success
+    };
+
+    expressions::evaluate_condition(condition, &ctx).map_err(|e| {
+        format!(
+            "Invalid 'if' condition '{}' on job '{}': {}",
+            condition, job_name, e
+        )
+    })
+}
+
+/// Result of a finished job as seen by the jobs that need it
+struct JobSummary {
+    status: JobStatus,
+    outputs: HashMap<String, String>,
+}
+
+/// Build the `needs` context of a job from the jobs that already finished
+fn needs_context(job: &Job, finished_jobs: &HashMap<String, JobSummary>) -> serde_json::Value {
+    let mut needs = serde_json::Map::new();
+    for need in job.needs() {
+        if let Some(summary) = finished_jobs.get(need) {
+            let result = match summary.status {
+                JobStatus::Success => "success",
+                JobStatus::Failure => "failure",
+                JobStatus::Skipped => "skipped",
+            };
+            needs.insert(
+                need.clone(),
+                serde_json::json!({ "result": result, "outputs": summary.outputs }),
+            );
+        }
+    }
+    serde_json::Value::Object(needs)
+}
+
+/// What the environment a job deploys to gives it: its variables, and its
+/// secrets over the run's. Jobs deploying to a protected environment wait
+/// for approval first.
+async fn job_deployment(
+    job_name: &str,
+    job: &Job,
+    env_context: &HashMap<String, String>,
+    finished_jobs: &HashMap<String, JobSummary>,
+    secrets: &HashMap<String, String>,
+    environments: &HashMap<String, DeploymentEnvironment>,
+    approvals: &Approvals,
+) -> Result<DeploymentEnvironment, String> {
+    let mut deployment = DeploymentEnvironment {
+        secrets: secrets.clone(),
+        ..Default::default()
+    };
+    let Some(environment) = &job.environment else {
+        return Ok(deployment);
+    };
+    let mut ctx = environment::create_expression_context(env_context, None);
+    ctx.set("needs", needs_context(job, finished_jobs));
+    let name = expressions::interpolate(environment.name(), &ctx)
+        .map_err(|e| format!("Job '{}': invalid environment name: {}", job_name, e))?;
+    // Environments a project doesn't set up are created empty, like on GitHub
+    let Some(configured) = environments.get(&name) else {
+        return Ok(deployment);
+    };
+    if configured.require_approval {
+        approvals.approve(job_name, &name).await?;
+    }
+    deployment.vars = configured.vars.clone();
+    deployment.secrets.extend(configured.secrets.clone());
+    Ok(deployment)
+}
+
+/// Give a job's steps its token's permissions, and its token to actions
+/// that look for the runner's
+fn add_job_token(
+    job_env: &mut HashMap<String, String>,
+    workflow: &Workflow,
+    job: &Job,
+    secrets: &HashMap<String, String>,
+) {
+    let permissions =
+        TokenPermissions::resolve(workflow.permissions.as_ref(), job.permissions.as_ref());
+    job_env.insert(
+        permissions::PERMISSIONS_ENV.to_string(),
+        permissions.to_json(),
+    );
+    if let Some(token) = secrets.get("GITHUB_TOKEN") {
+        job_env.insert("ACTIONS_RUNTIME_TOKEN".to_string(), token.clone());
+    }
+}
+
+/// The workflow's and a job's `env:` with their expressions evaluated, the
+/// job's winning, which can use the github, needs, matrix, secrets and vars
+/// contexts. Values that fail to evaluate are kept as written.
+fn job_env_values(
+    workflow: &Workflow,
+    job: &Job,
+    env_context: &HashMap<String, String>,
+    matrix: Option<&HashMap<String, Value>>,
+    needs: &serde_json::Value,
+    secrets: &HashMap<String, String>,
+    vars: &HashMap<String, String>,
+) -> HashMap<String, String> {
+    let mut ctx = environment::create_expression_context(env_context, matrix);
+    ctx.set("needs", needs.clone());
+    environment::set_secrets_context(&mut ctx, secrets);
+    ctx.set("vars", serde_json::json!(vars));
+    environment::merge_env_levels(&mut ctx, env_context, &[&workflow.env, &job.env])
+}
+
 /// Execute a GitLab CI/CD pipeline locally
 async fn execute_gitlab_pipeline(
     pipeline_path: &Path,
-    runtime_type: RuntimeType,
-    verbose: bool,
+    config: &ExecutionConfig,
 ) -> Result<ExecutionResult, ExecutionError> {
+    let runtime_type = config.runtime_type.clone();
+    let progress = &config.progress;
+    let started_at = chrono::Local::now().to_rfc3339();
+
     logging::info("Executing GitLab CI/CD pipeline");
 
     // 1. Parse the GitLab pipeline file
     let pipeline = parse_pipeline(pipeline_path)
         .map_err(|e| ExecutionError::Parse(format!("Failed to parse GitLab pipeline: {}", e)))?;
 
-    // 2. Convert the GitLab pipeline to a format compatible with the workflow executor
-    let workflow = gitlab::convert_to_workflow_format(&pipeline);
+    let validation = gitlab::validate_pipeline_structure(&pipeline);
+    if !validation.is_valid {
+        return Err(ExecutionError::Parse(format!(
+            "Invalid GitLab pipeline: {}",
+            validation.issues.join("; ")
+        )));
+    }
+
+    // 2. Convert the GitLab pipeline to a format compatible with the workflow executor.
+    // Stage order and `needs` both end up as the converted jobs' needs
+    let mut workflow = gitlab::convert_to_workflow_format(&pipeline);
+    // Converted jobs are named after job instances, so `parallel:` copies
+    // are looked up through their instance name
+    let gitlab_jobs: HashMap<String, &GitlabJob> = pipeline
+        .jobs
+        .iter()
+        .flat_map(|(name, job)| {
+            gitlab::job_instances(name, job)
+                .into_iter()
+                .map(move |instance| (instance.name, job))
+        })
+        .collect();
 
-    // 3. Resolve job dependencies based on stages
-    let execution_plan = resolve_gitlab_dependencies(&pipeline, &workflow)?;
+    // 3. Resolve job dependencies based on stages. Selecting a job selects
+    // all of its `parallel:` instances
+    let execution_plan = dependency::resolve_dependencies(&workflow)?;
+    let selection = JobSelection {
+        jobs: expand_gitlab_job_names(&pipeline, &config.job_selection.jobs),
+        skip: expand_gitlab_job_names(&pipeline, &config.job_selection.skip),
+        with_needs: config.job_selection.with_needs,
+    };
+    let selected_jobs = dependency::select_jobs(&workflow, &selection)?;
+    let selected_plan = select_from_plan(&execution_plan, &selected_jobs);
+    progress.emit(ExecutionEvent::Planned {
+        total_steps: planned_step_count(&workflow, &selected_plan),
+    });
 
     // 4. Initialize appropriate runtime
-    let runtime = initialize_runtime(runtime_type.clone())?;
+    let (runtime, runtime_type) = initialize_runtime(runtime_type, config).await?;
+    let _stop_on_cancel = StopOnCancel::watch(runtime.as_ref(), &config.cancellation);
+    let debug_shell = debug_shell(config, &runtime_type);
 
     // Create a temporary workspace directory
     let workspace_dir = tempfile::tempdir()
         .map_err(|e| ExecutionError::Execution(format!("Failed to create workspace: {}", e)))?;
 
-    // 5. Set up GitLab-like environment
-    let mut env_context = create_gitlab_context(&pipeline, workspace_dir.path());
+    // 5. Set up GitLab-like environment. Like GitHub runs, jobs work on the
+    // project directory itself
+    let project_dir = std::env::current_dir().map_err(|e| {
+        ExecutionError::Execution(format!("Failed to get current directory: {}", e))
+    })?;
+    let ci_variables = environment::gitlab_ci_variables(config.event.as_ref());
+    let mut env_context = create_gitlab_context(&pipeline, &project_dir, ci_variables);
+    environment::set_ci_env(&mut env_context, "GITLAB_CI", config.ci_env);
+    env_context.extend(config.env.clone());
+
+    // Which jobs the pipeline gets and how they start, going by the rules
+    // and the simulated CI variables
+    let workflow_variables = gitlab_rules::pipeline_variables(&pipeline, &env_context)
+        .map_err(|e| ExecutionError::Parse(format!("Invalid workflow:rules: {}", e)))?;
+    let pipeline_left_out = workflow_variables.is_none();
+    if pipeline_left_out {
+        logging::warning(
+            "The pipeline's workflow:rules leave it out for these CI variables, so no job runs",
+        );
+    }
+    let workflow_variables = workflow_variables.unwrap_or_default();
+    env_context.extend(workflow_variables.clone());
+    let mut rule_outcomes: HashMap<String, RuleOutcome> = HashMap::new();
+    for (name, gitlab_job) in gitlab_jobs.iter().filter(|_| !pipeline_left_out) {
+        let Some(job) = workflow.jobs.get_mut(name) else {
+            continue;
+        };
+        // Workflow rule variables win over global ones, not the job's own
+        let own = gitlab_job.variables.as_ref();
+        for (key, value) in &workflow_variables {
+            if !own.is_some_and(|own| own.contains_key(key)) {
+                job.env.insert(key.clone(), value.clone());
+            }
+        }
+        let mut variables = env_context.clone();
+        variables.extend(job.env.clone());
+        let outcome = gitlab_rules::job_outcome(gitlab_job, &variables).map_err(|e| {
+            ExecutionError::Parse(format!("Invalid rules of job '{}': {}", name, e))
+        })?;
+        if let Some(outcome) = outcome {
+            job.env.extend(outcome.variables.clone());
+            rule_outcomes.insert(name.clone(), outcome);
+        }
+    }
 
     // Add runtime mode to environment
     env_context.insert(
         "WRKFLW_RUNTIME_MODE".to_string(),
-        if runtime_type == RuntimeType::Emulation {
-            "emulation".to_string()
-        } else {
-            "docker".to_string()
-        },
+        runtime_type.mode_name().to_string(),
     );
 
     // Setup environment files
@@ -204,33 +1504,154 @@ async fn execute_gitlab_pipeline(
         ExecutionError::Execution(format!("Failed to setup environment files: {}", e))
     })?;
 
+    let pipeline_name = pipeline_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let run_id = chrono::Utc::now().timestamp().to_string();
+    create_run_network(runtime.as_ref(), &pipeline_name, &run_id).await?;
+
+    let artifact_store = ArtifactStore::new_run(
+        &artifacts::artifacts_root(),
+        &pipeline_path.to_string_lossy(),
+    )
+    .with_size_limits(config.size_limits);
+    start_run_log(config, &artifact_store);
+
     // 6. Execute jobs according to the plan
     let mut results = Vec::new();
     let mut has_failures = false;
     let mut failure_details = String::new();
 
+    let finished_jobs = HashMap::new();
+    let batch_ctx = BatchExecutionContext {
+        workflow: &workflow,
+        runtime: runtime.as_ref(),
+        env_context: &env_context,
+        artifact_store: &artifact_store,
+        finished_jobs: &finished_jobs,
+        progress,
+        cancellation: &config.cancellation,
+        step_control: &config.step_control,
+        verbose: config.verbose,
+        show_action_messages: config.show_action_messages,
+        sandbox: config.sandbox,
+        setup_actions: &config.setup_actions,
+        secrets: &config.secrets,
+        runner_images: &config.runner_images,
+        tool_cache: config.tool_cache,
+        bind_workspace: config.bind_workspace,
+        debug_shell,
+        environments: &config.environments,
+        approvals: &config.approvals,
+    };
+
+    // Jobs that failed, or were skipped because something before them failed
+    let mut failed_upstream: HashSet<String> = HashSet::new();
+    // Jobs skipped for any other reason, like manual jobs
+    let mut not_run: HashSet<String> = HashSet::new();
+
     for job_batch in execution_plan {
-        // Execute jobs in parallel if they don't depend on each other
-        let job_results = execute_job_batch(
-            &job_batch,
-            &workflow,
-            runtime.as_ref(),
-            &env_context,
-            verbose,
-        )
-        .await?;
+        let mut job_results = Vec::new();
+        let mut runnable_jobs = Vec::new();
+        for job_name in &job_batch {
+            let (Some(gitlab_job), Some(job)) =
+                (gitlab_jobs.get(job_name), workflow.jobs.get(job_name))
+            else {
+                continue;
+            };
+            // Jobs left out of the selection don't hold back the selected ones
+            if !selected_jobs.contains(job_name) {
+                job_results.push(not_selected_result(job_name));
+                continue;
+            }
+            let waits_for = job.needs();
+            let upstream_failed = waits_for.iter().any(|n| failed_upstream.contains(n));
+            let outcome = rule_outcomes.get(job_name);
+            let skip_reason = match outcome.map(|outcome| outcome.when.as_str()) {
+                None if pipeline_left_out => Some("the pipeline's workflow:rules leave it out"),
+                None => Some("its rules leave it out of this pipeline"),
+                Some("manual") => Some("manual jobs are not started locally"),
+                Some("always") => None,
+                Some("on_failure") if !upstream_failed => Some("no earlier job failed"),
+                Some("on_failure") => None,
+                _ if upstream_failed => Some("an earlier job failed"),
+                // A job that explicitly needs a job that never ran can't start either
+                _ if gitlab_job.needs.is_some()
+                    && waits_for.iter().any(|n| not_run.contains(n)) =>
+                {
+                    Some("a needed job did not run")
+                }
+                _ => None,
+            };
+
+            let Some(reason) = skip_reason else {
+                if let Some(outcome) = outcome.filter(|outcome| outcome.when == "delayed") {
+                    logging::info(&format!(
+                        "Starting delayed job '{}' right away instead of after {}",
+                        job_name,
+                        outcome.start_in.as_deref().unwrap_or("its delay")
+                    ));
+                }
+                runnable_jobs.push(job_name.clone());
+                continue;
+            };
+            logging::info(&format!("Skipping job '{}': {}", job_name, reason));
+            skip_planned_steps(job_name, &workflow, progress);
+            if upstream_failed {
+                failed_upstream.insert(job_name.clone());
+            } else {
+                not_run.insert(job_name.clone());
+            }
+            job_results.push(JobResult {
+                name: job_name.clone(),
+                status: JobStatus::Skipped,
+                steps: Vec::new(),
+                logs: format!("Job skipped: {}", reason),
+                outputs: HashMap::new(),
+                duration: Duration::ZERO,
+                overhead: Overhead::default(),
+                summary: String::new(),
+            });
+        }
+
+        match execute_job_batch(&runnable_jobs, &batch_ctx).await {
+            Ok(batch_results) => job_results.extend(batch_results),
+            Err(e) => {
+                release_run_network(runtime.as_ref(), config.keep_network).await;
+                return Err(e);
+            }
+        }
 
         // Check for job failures and collect details
-        for job_result in &job_results {
-            if job_result.status == JobStatus::Failure {
-                has_failures = true;
-                failure_details.push_str(&format!("\n❌ Job failed: {}\n", job_result.name));
+        for job_result in &mut job_results {
+            if job_result.status != JobStatus::Failure {
+                continue;
+            }
 
-                // Add step details for failed jobs
-                for step in &job_result.steps {
-                    if step.status == StepStatus::Failure {
-                        failure_details.push_str(&format!("  ❌ {}: {}\n", step.name, step.output));
-                    }
+            let allow_failure = rule_outcomes
+                .get(&job_result.name)
+                .and_then(|outcome| outcome.allow_failure)
+                .unwrap_or(false);
+            if allow_failure {
+                logging::warning(&format!(
+                    "Job '{}' failed, but is allowed to fail",
+                    job_result.name
+                ));
+                job_result
+                    .logs
+                    .push_str("\nJob failed, but is allowed to fail (allow_failure: true)\n");
+                continue;
+            }
+
+            failed_upstream.insert(job_result.name.clone());
+            has_failures = true;
+            failure_details.push_str(&format!("\n❌ Job failed: {}\n", job_result.name));
+
+            // Add step details for failed jobs
+            for step in &job_result.steps {
+                if step.status == StepStatus::Failure {
+                    failure_details.push_str(&format!("  ❌ {}: {}\n", step.name, step.output));
                 }
             }
         }
@@ -238,28 +1659,42 @@ async fn execute_gitlab_pipeline(
         results.extend(job_results);
     }
 
+    release_run_network(runtime.as_ref(), config.keep_network).await;
+
     // If there were failures, add detailed failure information to the result
     if has_failures {
         logging::error(&format!("Pipeline execution failed:{}", failure_details));
     }
 
-    Ok(ExecutionResult {
+    let result = ExecutionResult {
         jobs: results,
         failure_details: if has_failures {
             Some(failure_details)
         } else {
             None
         },
-    })
+    };
+    if config.history {
+        record_history(
+            pipeline_path,
+            &artifact_store,
+            &runtime_type,
+            &started_at,
+            &result,
+            config.cancellation.reason(),
+        );
+    }
+    Ok(result)
 }
 
-/// Create an environment context for GitLab CI/CD pipeline execution
-fn create_gitlab_context(pipeline: &Pipeline, workspace_dir: &Path) -> HashMap<String, String> {
-    let mut env_context = HashMap::new();
-
-    // Add GitLab CI/CD environment variables
-    env_context.insert("CI".to_string(), "true".to_string());
-    env_context.insert("GITLAB_CI".to_string(), "true".to_string());
+/// Create an environment context for GitLab CI/CD pipeline execution, with
+/// the predefined CI variables under the pipeline's own
+fn create_gitlab_context(
+    pipeline: &Pipeline,
+    workspace_dir: &Path,
+    ci_variables: HashMap<String, String>,
+) -> HashMap<String, String> {
+    let mut env_context = ci_variables;
 
     // Add custom environment variable to indicate use in wrkflw
     env_context.insert("WRKFLW_CI".to_string(), "true".to_string());
@@ -286,119 +1721,437 @@ fn create_gitlab_context(pipeline: &Pipeline, workspace_dir: &Path) -> HashMap<S
     env_context
 }
 
-/// Resolve GitLab CI/CD pipeline dependencies
-fn resolve_gitlab_dependencies(
-    pipeline: &Pipeline,
-    workflow: &WorkflowDefinition,
-) -> Result<Vec<Vec<String>>, ExecutionError> {
-    // For GitLab CI/CD pipelines, jobs within the same stage can run in parallel,
-    // but jobs in different stages run sequentially
-
-    // Get stages from the pipeline or create a default one
-    let stages = match &pipeline.stages {
-        Some(defined_stages) => defined_stages.clone(),
-        None => vec![
-            "build".to_string(),
-            "test".to_string(),
-            "deploy".to_string(),
-        ],
-    };
-
-    // Create an execution plan based on stages
-    let mut execution_plan = Vec::new();
+/// Give the run its own network, so its jobs and services reach each other
+/// by name without seeing the containers of other runs
+async fn create_run_network(
+    runtime: &dyn ContainerRuntime,
+    workflow_name: &str,
+    run_id: &str,
+) -> Result<(), ExecutionError> {
+    let name = docker::run_network_name(workflow_name, run_id);
+    runtime
+        .create_network(&name)
+        .await
+        .map_err(|e| ExecutionError::Runtime(e.to_string()))
+}
 
-    // For each stage, collect the jobs that belong to it
-    for stage in stages {
-        let mut stage_jobs = Vec::new();
+/// Remove the run's network once its jobs are done, or keep it for debugging
+async fn release_run_network(runtime: &dyn ContainerRuntime, keep_network: bool) {
+    if keep_network {
+        if let Some(name) = runtime.keep_network() {
+            logging::info(&format!(
+                "Keeping Docker network '{}'; remove it with `docker network rm {}`",
+                name, name
+            ));
+        }
+    } else if let Err(e) = runtime.remove_network().await {
+        logging::warning(&e.to_string());
+    }
+}
 
-        for (job_name, job) in &pipeline.jobs {
-            // Skip template jobs
-            if let Some(true) = job.template {
-                continue;
+/// Start the containers of a job's `services:`, reachable by their keys.
+/// If one fails, the ones already started are stopped again.
+async fn start_job_services(
+    job_name: &str,
+    job: &Job,
+    runtime: &dyn ContainerRuntime,
+    ctx: &EvaluationContext,
+    overhead: &mut Overhead,
+) -> Result<Vec<(String, StartedContainer)>, String> {
+    let mut service_names: Vec<&String> = job.services.keys().collect();
+    service_names.sort();
+
+    let mut started = Vec::new();
+    for service_name in service_names {
+        let service = &job.services[service_name];
+        let started_service = match background_container(service, vec![service_name.clone()], ctx) {
+            Ok(container) => runtime
+                .start_service(&container)
+                .await
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(e),
+        };
+        match started_service {
+            Ok(Some(service_container)) => {
+                logging::info(&format!(
+                    "Started service '{}' ({}) for job '{}'",
+                    service_name, service.image, job_name
+                ));
+                overhead.add(&service_container.timings);
+                started.push((service_name.clone(), service_container));
             }
-
-            // Get the job's stage, or assume "test" if not specified
-            let default_stage = "test".to_string();
-            let job_stage = job.stage.as_ref().unwrap_or(&default_stage);
-
-            // If the job belongs to the current stage, add it to the batch
-            if job_stage == &stage {
-                stage_jobs.push(job_name.clone());
+            Ok(None) => logging::warning(&format!(
+                "Service '{}' of job '{}' is not started: service containers need Docker mode",
+                service_name, job_name
+            )),
+            Err(e) => {
+                stop_job_services(runtime, started.iter().map(|(_, service)| &service.id)).await;
+                return Err(format!("Failed to start service '{}': {}", service_name, e));
             }
         }
+    }
+    Ok(started)
+}
+
+/// Start the job's `container:`, which its steps are then executed in.
+/// Returns None when the job has none or the runtime doesn't run containers.
+async fn start_job_container(
+    job_name: &str,
+    job: &Job,
+    runtime: &dyn ContainerRuntime,
+    mounts: &[(&Path, &Path)],
+    ctx: &EvaluationContext,
+    overhead: &mut Overhead,
+) -> Result<Option<StartedContainer>, String> {
+    let container = match &job.container {
+        None => return Ok(None),
+        Some(JobContainer::Image(image)) => github::Container {
+            image: image.clone(),
+            ..Default::default()
+        },
+        Some(JobContainer::Detailed(container)) => (**container).clone(),
+    };
+    let mut container = background_container(&container, vec![job_name.to_string()], ctx)
+        .map_err(|e| format!("Failed to start the container of job '{}': {}", job_name, e))?;
+    container.volumes.extend(
+        mounts.iter().map(|(host, target)| {
+            format!("{}:{}", host.to_string_lossy(), target.to_string_lossy())
+        }),
+    );
 
-        if !stage_jobs.is_empty() {
-            execution_plan.push(stage_jobs);
+    match runtime.start_job_container(&container).await {
+        Ok(Some(started)) => {
+            logging::info(&format!(
+                "Started container {} for job '{}'",
+                container.image, job_name
+            ));
+            overhead.add(&started.timings);
+            Ok(Some(started))
         }
+        Ok(None) => {
+            logging::warning(&format!(
+                "Job '{}' runs without its container {}: job containers need Docker or Podman mode",
+                job_name, container.image
+            ));
+            Ok(None)
+        }
+        Err(e) => Err(format!(
+            "Failed to start the container of job '{}': {}",
+            job_name, e
+        )),
     }
+}
 
-    // Also create a batch for jobs without a stage
-    let mut stageless_jobs = Vec::new();
+/// The `job` context of a job's started container and services, with the
+/// ids, networks and host ports steps reach them by
+fn job_context(
+    container: Option<&StartedContainer>,
+    services: &[(String, StartedContainer)],
+) -> serde_json::Map<String, serde_json::Value> {
+    let mut job = serde_json::Map::new();
+    if let Some(container) = container {
+        job.insert(
+            "container".to_string(),
+            serde_json::json!({ "id": container.id, "network": container.network }),
+        );
+    }
+    let services: serde_json::Map<String, serde_json::Value> = services
+        .iter()
+        .map(|(name, service)| {
+            let service = serde_json::json!({
+                "id": service.id,
+                "network": service.network,
+                "ports": service.ports,
+            });
+            (name.clone(), service)
+        })
+        .collect();
+    job.insert("services".to_string(), serde_json::Value::Object(services));
+    job
+}
 
-    for (job_name, job) in &pipeline.jobs {
-        // Skip template jobs
-        if let Some(true) = job.template {
-            continue;
+/// A `container:` or `services:` entry with its expressions evaluated, so
+/// credentials can come from secrets
+fn background_container(
+    container: &github::Container,
+    aliases: Vec<String>,
+    ctx: &EvaluationContext,
+) -> Result<BackgroundContainer, String> {
+    let render = |text: &str| {
+        expressions::interpolate(text, ctx)
+            .map_err(|e| format!("Failed to evaluate expression in '{}': {}", text, e))
+    };
+    let credentials = match &container.credentials {
+        Some(credentials) => Some((
+            render(&credentials.username)?,
+            render(&credentials.password)?,
+        )),
+        None => None,
+    };
+    Ok(BackgroundContainer {
+        image: render(&container.image)?,
+        aliases: aliases
+            .into_iter()
+            .chain(container.aliases.iter().cloned())
+            .collect(),
+        env: container
+            .env
+            .iter()
+            .map(|(k, v)| Ok((k.clone(), render(v)?)))
+            .collect::<Result<_, String>>()?,
+        ports: container.ports.clone(),
+        volumes: container.volumes.clone(),
+        options: container.options.as_deref().map(render).transpose()?,
+        credentials,
+        entrypoint: container.entrypoint.clone(),
+        command: container.command.clone(),
+    })
+}
+
+async fn stop_job_services(
+    runtime: &dyn ContainerRuntime,
+    services: impl IntoIterator<Item = &String>,
+) {
+    for id in services {
+        if let Err(e) = runtime.stop_service(id).await {
+            logging::warning(&format!("Failed to remove service container {}: {}", id, e));
         }
+    }
+}
 
-        if job.stage.is_none() {
-            stageless_jobs.push(job_name.clone());
+/// Create the runtime for `runtime_type`, and the type it ended up being
+/// when Docker wasn't there to use. Podman and bubblewrap are only used when
+/// asked for, so they fail with what to fix instead.
+async fn initialize_runtime(
+    runtime_type: RuntimeType,
+    config: &ExecutionConfig,
+) -> Result<(Box<dyn ContainerRuntime>, RuntimeType), ExecutionError> {
+    let (runtime, runtime_type) = create_runtime(runtime_type, config).await?;
+    // Steps on the host would reach everything, so the run can't go on
+    let containers = matches!(runtime_type, RuntimeType::Docker | RuntimeType::Podman);
+    if config.network_policy != NetworkPolicy::Open && !containers {
+        return Err(ExecutionError::Runtime(format!(
+            "The network can only be restricted for steps in Docker or Podman containers, and this run uses {}",
+            runtime_type.mode_name()
+        )));
+    }
+    if !config.resources.is_empty() && !containers {
+        logging::warning(&format!(
+            "CPU and memory limits only apply to Docker and Podman containers and are ignored in {} mode",
+            runtime_type.mode_name()
+        ));
+    }
+    if !config.platforms.is_empty() && !containers {
+        logging::warning(&format!(
+            "Platforms only apply to Docker and Podman containers, and steps run on this host in {} mode",
+            runtime_type.mode_name()
+        ));
+    } else if containers {
+        let mut warned = HashSet::new();
+        for platform in config.platforms.all() {
+            if warned.insert(platform) {
+                if let Some(warning) = platforms::emulation_warning(platform) {
+                    logging::warning(&warning);
+                }
+            }
         }
     }
+    Ok((runtime, runtime_type))
+}
+
+/// Stops what a runtime has running as soon as the run is cancelled, rather
+/// than after the steps in flight finish. Watching ends when it's dropped.
+struct StopOnCancel(Option<tokio::task::JoinHandle<()>>);
 
-    if !stageless_jobs.is_empty() {
-        execution_plan.push(stageless_jobs);
+impl StopOnCancel {
+    fn watch(runtime: &dyn ContainerRuntime, cancellation: &CancellationToken) -> Self {
+        let (Some(stopper), Ok(handle)) =
+            (runtime.stopper(), tokio::runtime::Handle::try_current())
+        else {
+            return StopOnCancel(None);
+        };
+        let cancellation = cancellation.clone();
+        StopOnCancel(Some(handle.spawn(async move {
+            cancellation.cancelled().await;
+            logging::warning(&format!(
+                "Stopping the steps in flight: the run was {}",
+                cancellation.reason().unwrap_or_default()
+            ));
+            stopper.stop().await;
+        })))
     }
+}
 
-    Ok(execution_plan)
+impl Drop for StopOnCancel {
+    fn drop(&mut self) {
+        if let Some(task) = &self.0 {
+            task.abort();
+        }
+    }
 }
 
-// Determine if Docker is available or fall back to emulation
-fn initialize_runtime(
+async fn create_runtime(
     runtime_type: RuntimeType,
-) -> Result<Box<dyn ContainerRuntime>, ExecutionError> {
+    config: &ExecutionConfig,
+) -> Result<(Box<dyn ContainerRuntime>, RuntimeType), ExecutionError> {
+    let sandbox = config.sandbox;
     match runtime_type {
         RuntimeType::Docker => {
             if docker::is_available() {
                 // Handle the Result returned by DockerRuntime::new()
                 match docker::DockerRuntime::new() {
-                    Ok(docker_runtime) => Ok(Box::new(docker_runtime)),
-                    Err(e) => {
-                        logging::error(&format!(
-                            "Failed to initialize Docker runtime: {}, falling back to emulation mode",
-                            e
-                        ));
-                        Ok(Box::new(emulation::EmulationRuntime::new()))
-                    }
+                    Ok(docker_runtime) => Ok((
+                        Box::new(
+                            docker_runtime
+                                .with_retry(config.docker_retry)
+                                .with_network_policy(config.network_policy.clone())
+                                .with_resources(config.resources.clone())
+                                .with_platforms(config.platforms.clone())
+                                .with_progress(config.progress.clone()),
+                        ),
+                        RuntimeType::Docker,
+                    )),
+                    Err(e) => Ok(fallback_runtime(
+                        &format!("Failed to initialize Docker runtime: {}", e),
+                        sandbox,
+                    )),
                 }
             } else {
-                logging::error("Docker not available, falling back to emulation mode");
-                Ok(Box::new(emulation::EmulationRuntime::new()))
+                Ok(fallback_runtime("Docker not available", sandbox))
             }
         }
-        RuntimeType::Emulation => Ok(Box::new(emulation::EmulationRuntime::new())),
+        RuntimeType::Podman => {
+            let client = podman::connect().await.map_err(ExecutionError::Runtime)?;
+            Ok((
+                Box::new(
+                    docker::DockerRuntime::with_client(client)
+                        .with_retry(config.docker_retry)
+                        .with_cli("podman")
+                        .with_network_policy(config.network_policy.clone())
+                        .with_resources(config.resources.clone())
+                        .with_platforms(config.platforms.clone())
+                        .with_progress(config.progress.clone()),
+                ),
+                RuntimeType::Podman,
+            ))
+        }
+        RuntimeType::Bubblewrap => {
+            if !bubblewrap::is_available() {
+                return Err(ExecutionError::Runtime(
+                    "bubblewrap can't create sandboxes here. Install it (the bubblewrap package) and make sure unprivileged user namespaces are enabled, or pick another runtime with --runtime".to_string(),
+                ));
+            }
+            Ok((
+                Box::new(emulation::EmulationRuntime::bubblewrap(sandbox)),
+                RuntimeType::Bubblewrap,
+            ))
+        }
+        RuntimeType::Emulation => {
+            Ok((Box::new(emulation_runtime(sandbox)), RuntimeType::Emulation))
+        }
+    }
+}
+
+/// Whether failed steps get a shell, warning when the runtime can't open one
+fn debug_shell(config: &ExecutionConfig, runtime_type: &RuntimeType) -> bool {
+    let containers = matches!(runtime_type, RuntimeType::Docker | RuntimeType::Podman);
+    if config.debug_shell && !containers {
+        logging::warning(&format!(
+            "Shells for failed steps need Docker or Podman mode, so this {} run won't open any",
+            runtime_type.mode_name()
+        ));
+    }
+    config.debug_shell && containers
+}
+
+fn emulation_runtime(sandbox: bool) -> emulation::EmulationRuntime {
+    if sandbox {
+        emulation::EmulationRuntime::sandboxed()
+    } else {
+        emulation::EmulationRuntime::new()
+    }
+}
+
+/// Without Docker, jobs run in bubblewrap sandboxes where it works and
+/// directly on the host otherwise
+fn fallback_runtime(reason: &str, sandbox: bool) -> (Box<dyn ContainerRuntime>, RuntimeType) {
+    let runtime_type = fallback_runtime_type();
+    if runtime_type == RuntimeType::Bubblewrap {
+        logging::warning(&format!(
+            "{}, falling back to bubblewrap sandboxes. {}",
+            reason,
+            bubblewrap::LIMITATIONS
+        ));
+        (
+            Box::new(emulation::EmulationRuntime::bubblewrap(sandbox)),
+            runtime_type,
+        )
+    } else {
+        logging::error(&format!("{}, falling back to emulation mode", reason));
+        (Box::new(emulation_runtime(sandbox)), runtime_type)
+    }
+}
+
+/// The runtime used when no container engine is available
+pub fn fallback_runtime_type() -> RuntimeType {
+    if bubblewrap::is_available() {
+        RuntimeType::Bubblewrap
+    } else {
+        RuntimeType::Emulation
     }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum RuntimeType {
     Docker,
+    Podman,
     Emulation,
+    /// Emulation with every command in a bubblewrap sandbox, on Linux
+    Bubblewrap,
+}
+
+impl RuntimeType {
+    /// Value of `WRKFLW_RUNTIME_MODE` for steps
+    pub fn mode_name(&self) -> &'static str {
+        match self {
+            RuntimeType::Docker => "docker",
+            RuntimeType::Podman => "podman",
+            RuntimeType::Emulation => "emulation",
+            RuntimeType::Bubblewrap => "bubblewrap",
+        }
+    }
 }
 
+#[derive(Serialize)]
 pub struct ExecutionResult {
     pub jobs: Vec<JobResult>,
     pub failure_details: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JobResult {
     pub name: String,
     pub status: JobStatus,
     pub steps: Vec<StepResult>,
+    /// Step outputs and errors, which the steps already carry
+    #[serde(skip)]
     pub logs: String,
+    pub outputs: HashMap<String, String>,
+    #[serde(
+        rename = "duration_secs",
+        serialize_with = "serialize_secs",
+        deserialize_with = "deserialize_secs"
+    )]
+    pub duration: Duration,
+    /// Time spent getting the job's service and job containers ready
+    #[serde(default)]
+    pub overhead: Overhead,
+    /// Markdown the steps wrote to `$GITHUB_STEP_SUMMARY`
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub summary: String,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[allow(dead_code)]
 pub enum JobStatus {
     Success,
@@ -406,14 +2159,74 @@ pub enum JobStatus {
     Skipped,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StepResult {
     pub name: String,
     pub status: StepStatus,
     pub output: String,
+    /// File holding the full output of a step that ran, for when the output
+    /// shown is truncated
+    pub output_file: Option<std::path::PathBuf>,
+    /// Exit code of the step's command, for steps that ran one
+    pub exit_code: Option<i32>,
+    #[serde(
+        rename = "duration_secs",
+        serialize_with = "serialize_secs",
+        deserialize_with = "deserialize_secs"
+    )]
+    pub duration: Duration,
+    /// Part of the duration spent pulling images and starting containers
+    /// rather than running the step's command
+    #[serde(default)]
+    pub overhead: Overhead,
+    /// Errors, warnings and notices the step printed as workflow commands
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub annotations: Vec<Annotation>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// Time spent pulling images and starting containers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Overhead {
+    #[serde(
+        rename = "image_pull_secs",
+        serialize_with = "serialize_secs",
+        deserialize_with = "deserialize_secs"
+    )]
+    pub image_pull: Duration,
+    #[serde(
+        rename = "container_startup_secs",
+        serialize_with = "serialize_secs",
+        deserialize_with = "deserialize_secs"
+    )]
+    pub container_startup: Duration,
+}
+
+impl Overhead {
+    pub fn total(&self) -> Duration {
+        self.image_pull + self.container_startup
+    }
+
+    fn add(&mut self, timings: &ContainerTimings) {
+        self.image_pull += timings.image_pull;
+        self.container_startup += timings.startup;
+    }
+}
+
+fn serialize_secs<S: serde::Serializer>(
+    duration: &Duration,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64())
+}
+
+fn deserialize_secs<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Duration, D::Error> {
+    let secs = f64::deserialize(deserializer)?;
+    Duration::try_from_secs_f64(secs).map_err(serde::de::Error::custom)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[allow(dead_code)]
 pub enum StepStatus {
     Success,
@@ -494,17 +2307,37 @@ async fn prepare_action(
     Ok("node:16-buster-slim".to_string())
 }
 
+/// State shared by every job in a batch
+struct BatchExecutionContext<'a> {
+    workflow: &'a Workflow,
+    runtime: &'a dyn ContainerRuntime,
+    env_context: &'a HashMap<String, String>,
+    artifact_store: &'a ArtifactStore,
+    finished_jobs: &'a HashMap<String, JobSummary>,
+    progress: &'a ProgressReporter,
+    cancellation: &'a CancellationToken,
+    step_control: &'a StepControl,
+    verbose: bool,
+    show_action_messages: bool,
+    sandbox: bool,
+    setup_actions: &'a SetupActions,
+    secrets: &'a HashMap<String, String>,
+    runner_images: &'a HashMap<String, String>,
+    tool_cache: bool,
+    bind_workspace: bool,
+    debug_shell: bool,
+    environments: &'a HashMap<String, DeploymentEnvironment>,
+    approvals: &'a Approvals,
+}
+
 async fn execute_job_batch(
     jobs: &[String],
-    workflow: &WorkflowDefinition,
-    runtime: &dyn ContainerRuntime,
-    env_context: &HashMap<String, String>,
-    verbose: bool,
+    ctx: &BatchExecutionContext<'_>,
 ) -> Result<Vec<JobResult>, ExecutionError> {
     // Execute jobs in parallel
-    let futures = jobs
-        .iter()
-        .map(|job_name| execute_job_with_matrix(job_name, workflow, runtime, env_context, verbose));
+    let futures = jobs.iter().map(|job_name| {
+        execute_job_with_matrix(job_name, ctx).instrument(logging::job_span(job_name))
+    });
 
     let result_arrays = future::join_all(futures).await;
 
@@ -523,27 +2356,132 @@ async fn execute_job_batch(
 // Before execute_job_with_matrix implementation, add this struct
 struct JobExecutionContext<'a> {
     job_name: &'a str,
-    workflow: &'a WorkflowDefinition,
+    workflow: &'a Workflow,
     runtime: &'a dyn ContainerRuntime,
     env_context: &'a HashMap<String, String>,
+    artifact_store: &'a ArtifactStore,
+    finished_jobs: &'a HashMap<String, JobSummary>,
+    progress: &'a ProgressReporter,
+    cancellation: &'a CancellationToken,
+    step_control: &'a StepControl,
     verbose: bool,
+    show_action_messages: bool,
+    sandbox: bool,
+    setup_actions: &'a SetupActions,
+    secrets: &'a HashMap<String, String>,
+    /// Values of the `vars` context, from the job's environment
+    vars: &'a HashMap<String, String>,
+    runner_images: &'a HashMap<String, String>,
+    tool_cache: bool,
+    bind_workspace: bool,
+    debug_shell: bool,
 }
 
 /// Execute a job, expanding matrix if present
 async fn execute_job_with_matrix(
     job_name: &str,
-    workflow: &WorkflowDefinition,
-    runtime: &dyn ContainerRuntime,
-    env_context: &HashMap<String, String>,
-    verbose: bool,
+    batch: &BatchExecutionContext<'_>,
 ) -> Result<Vec<JobResult>, ExecutionError> {
+    let BatchExecutionContext {
+        workflow,
+        runtime,
+        env_context,
+        artifact_store,
+        finished_jobs,
+        progress,
+        cancellation,
+        step_control,
+        verbose,
+        show_action_messages,
+        sandbox,
+        setup_actions,
+        secrets,
+        runner_images,
+        tool_cache,
+        bind_workspace,
+        debug_shell,
+        environments,
+        approvals,
+    } = *batch;
+
     // Get the job definition
     let job = workflow.jobs.get(job_name).ok_or_else(|| {
         ExecutionError::Execution(format!("Job '{}' not found in workflow", job_name))
     })?;
 
+    let deployment = match job_deployment(
+        job_name,
+        job,
+        env_context,
+        finished_jobs,
+        secrets,
+        environments,
+        approvals,
+    )
+    .await
+    {
+        Ok(deployment) => deployment,
+        Err(e) => {
+            logging::error(&e);
+            skip_planned_steps(job_name, workflow, progress);
+            return Ok(vec![JobResult {
+                name: job_name.to_string(),
+                status: JobStatus::Failure,
+                steps: Vec::new(),
+                logs: e,
+                outputs: HashMap::new(),
+                duration: Duration::ZERO,
+                overhead: Overhead::default(),
+                summary: String::new(),
+            }]);
+        }
+    };
+    let (secrets, vars) = (&deployment.secrets, &deployment.vars);
+
+    let permissions =
+        TokenPermissions::resolve(workflow.permissions.as_ref(), job.permissions.as_ref());
+    for (idx, step) in job.steps.iter().enumerate() {
+        let Some(script) = &step.run else {
+            continue;
+        };
+        for warning in permissions.check_script(script) {
+            logging::warning(&format!(
+                "Job '{}', step '{}': {}",
+                job_name,
+                step_display_name(step, idx),
+                warning
+            ));
+        }
+    }
+
+    // Jobs on labels mapped to the host run there while the others get
+    // containers
+    let host_runtime;
+    let host_env;
+    let (runtime, env_context) =
+        if runs_in_container(env_context) && runner_image::runs_on_host(job, runner_images) {
+            logging::info(&format!(
+                "Job '{}' runs on the host, as its runs-on label is mapped to {}",
+                job_name,
+                runner_image::HOST
+            ));
+            host_runtime = emulation_runtime(sandbox);
+            let mut env = env_context.clone();
+            env.insert(
+                "WRKFLW_RUNTIME_MODE".to_string(),
+                RuntimeType::Emulation.mode_name().to_string(),
+            );
+            environment::set_runner_env(&mut env, false, None);
+            host_env = env;
+            (&host_runtime as &dyn ContainerRuntime, &host_env)
+        } else {
+            (runtime, env_context)
+        };
+
     // Check if this is a matrix job
-    if let Some(matrix_config) = &job.matrix {
+    let matrix_config = job_matrix(job)
+        .map_err(|e| ExecutionError::Execution(format!("Job '{}': {}", job_name, e)))?;
+    if let Some(matrix_config) = &matrix_config {
         // Expand the matrix into combinations
         let combinations = matrix::expand_matrix(matrix_config)
             .map_err(|e| ExecutionError::Execution(format!("Failed to expand matrix: {}", e)))?;
@@ -579,7 +2517,21 @@ async fn execute_job_with_matrix(
             workflow,
             runtime,
             env_context,
+            artifact_store,
+            finished_jobs,
+            progress,
+            cancellation,
+            step_control,
             verbose,
+            show_action_messages,
+            sandbox,
+            setup_actions,
+            secrets,
+            vars,
+            runner_images,
+            tool_cache,
+            bind_workspace,
+            debug_shell,
         })
         .await
     } else {
@@ -589,7 +2541,21 @@ async fn execute_job_with_matrix(
             workflow,
             runtime,
             env_context,
+            artifact_store,
+            finished_jobs,
+            progress,
+            cancellation,
+            step_control,
             verbose,
+            show_action_messages,
+            sandbox,
+            setup_actions,
+            secrets,
+            vars,
+            runner_images,
+            tool_cache,
+            bind_workspace,
+            debug_shell,
         };
         let result = execute_job(ctx).await?;
         Ok(vec![result])
@@ -598,65 +2564,197 @@ async fn execute_job_with_matrix(
 
 #[allow(unused_variables, unused_assignments)]
 async fn execute_job(ctx: JobExecutionContext<'_>) -> Result<JobResult, ExecutionError> {
+    let started = Instant::now();
     // Get job definition
     let job = ctx.workflow.jobs.get(ctx.job_name).ok_or_else(|| {
         ExecutionError::Execution(format!("Job '{}' not found in workflow", ctx.job_name))
     })?;
 
     // Clone context and add job-specific variables
+    let needs = needs_context(job, ctx.finished_jobs);
     let mut job_env = ctx.env_context.clone();
-
-    // Add job-level environment variables
-    for (key, value) in &job.env {
-        job_env.insert(key.clone(), value.clone());
-    }
+    job_env.extend(job_env_values(
+        ctx.workflow,
+        job,
+        ctx.env_context,
+        None,
+        &needs,
+        ctx.secrets,
+        ctx.vars,
+    ));
 
     // Execute job steps
     let mut step_results = Vec::new();
     let mut job_logs = String::new();
+    let mut job_summary = String::new();
+    // Values steps asked to hide with `::add-mask::`
+    let mut masks = Vec::new();
 
-    // Create a temporary directory for this job execution
-    let job_dir = tempfile::tempdir()
-        .map_err(|e| ExecutionError::Execution(format!("Failed to create job directory: {}", e)))?;
+    let job_dir = job_workspace(ctx.workflow, ctx.job_name, ctx.bind_workspace)?;
 
-    // Get the current project directory
-    let current_dir = std::env::current_dir().map_err(|e| {
-        ExecutionError::Execution(format!("Failed to get current directory: {}", e))
+    // Step output files live outside the workspace so they are never copied around
+    let file_commands_dir = tempfile::tempdir().map_err(|e| {
+        ExecutionError::Execution(format!("Failed to create step output directory: {}", e))
     })?;
+    let mut runner_dirs = create_runner_dirs(&mut job_env, ctx.sandbox.then_some(job_dir.path()))?;
+    if ctx.tool_cache {
+        mount_tool_caches(
+            ctx.runtime,
+            &mut runner_dirs,
+            &mut job_env,
+            ctx.workflow,
+            ctx.job_name,
+        )
+        .await;
+    }
+    job_env.insert("GITHUB_JOB".to_string(), ctx.job_name.to_string());
+    add_job_token(&mut job_env, ctx.workflow, job, ctx.secrets);
 
-    // Copy project files to the job workspace directory
-    logging::info(&format!(
-        "Copying project files to job workspace: {}",
-        job_dir.path().display()
-    ));
-    copy_directory_contents(&current_dir, job_dir.path())?;
-
-    logging::info(&format!("Executing job: {}", ctx.job_name));
+    ctx.progress.emit(ExecutionEvent::JobStarted {
+        job: ctx.job_name.to_string(),
+    });
 
     let mut job_success = true;
+    let mut expression_state = JobExpressionState {
+        job_env: &job_env,
+        env_file: HashMap::new(),
+        secrets: ctx.secrets,
+        vars: ctx.vars,
+        matrix: None,
+        needs,
+        steps: serde_json::Map::new(),
+        strategy: StrategyContext::default(),
+        job: serde_json::Map::new(),
+        cancellation: ctx.cancellation,
+    };
+
+    // Without its services the job's steps are skipped like after a failure
+    let mut job_overhead = Overhead::default();
+    let services = match start_job_services(
+        ctx.job_name,
+        job,
+        ctx.runtime,
+        &expression_state.context(true),
+        &mut job_overhead,
+    )
+    .await
+    {
+        Ok(services) => services,
+        Err(e) => {
+            logging::error(&e);
+            job_logs.push_str(&format!("{}\n", e));
+            job_success = false;
+            Vec::new()
+        }
+    };
+    let mounts = job_volume_paths(job_dir.path(), file_commands_dir.path(), &runner_dirs);
+    let job_container = match start_job_container(
+        ctx.job_name,
+        job,
+        ctx.runtime,
+        &mounts,
+        &expression_state.context(true),
+        &mut job_overhead,
+    )
+    .await
+    {
+        Ok(container) => container,
+        Err(e) => {
+            logging::error(&e);
+            job_logs.push_str(&format!("{}\n", e));
+            job_success = false;
+            None
+        }
+    };
+    expression_state.job = job_context(job_container.as_ref(), &services);
 
     // Execute job steps
+    let mut cancelled = false;
     for (idx, step) in job.steps.iter().enumerate() {
-        let step_result = execute_step(StepExecutionContext {
-            step,
-            step_idx: idx,
-            job_env: &job_env,
-            working_dir: job_dir.path(),
-            runtime: ctx.runtime,
-            workflow: ctx.workflow,
-            runner_image: &get_runner_image(&job.runs_on),
-            verbose: ctx.verbose,
-            matrix_combination: &None,
-        })
-        .await;
+        let expression_ctx = expression_state.context(job_success);
+        cancelled |= expression_ctx.job_state == JobState::Cancelled;
+        let skipped = match check_step_condition(step, idx, &expression_ctx, job_success) {
+            Some(skipped) => Some(skipped),
+            None => decide_step(ctx.step_control, ctx.cancellation, ctx.job_name, step, idx).await,
+        };
+        if let Some(skipped) = skipped {
+            // Aborting at a pause cancels the run from this step on
+            cancelled |= ctx.cancellation.is_cancelled();
+            job_logs.push_str(&format!(
+                "Step '{}' completed with status: {:?}\n",
+                skipped.name, skipped.status
+            ));
+            if skipped.status == StepStatus::Failure {
+                job_success = false;
+            }
+            expression_state.record_step(step, &skipped.status, HashMap::new());
+            ctx.progress
+                .step_completed(ctx.job_name, &skipped.name, &skipped.status);
+            step_results.push(skipped);
+            continue;
+        }
+
+        ctx.progress
+            .step_started(ctx.job_name, &step_display_name(step, idx));
+        let step_started = Instant::now();
+        let step_overhead = Mutex::new(Overhead::default());
+        let step_result =
+            match interpolate_step(&with_run_defaults(step, job, ctx.workflow), &expression_ctx) {
+                Ok(step) => {
+                    execute_step(StepExecutionContext {
+                        step: &step,
+                        step_idx: idx,
+                        job_env: &job_env,
+                        working_dir: job_dir.path(),
+                        file_commands_dir: file_commands_dir.path(),
+                        runner_dirs: &runner_dirs,
+                        setup_actions: ctx.setup_actions,
+                        runtime: ctx.runtime,
+                        runner_image: &runner_image::job_image(job, ctx.runner_images),
+                        job_container: job_container.as_ref().map(|started| JobContainerRef {
+                            id: &started.id,
+                            file_commands_dir: file_commands_dir.path(),
+                        }),
+                        artifact_store: ctx.artifact_store,
+                        verbose: ctx.verbose,
+                        show_action_messages: ctx.show_action_messages,
+                        matrix_combination: &None,
+                        overhead: &step_overhead,
+                        debug_shell: ctx.debug_shell,
+                    })
+                    .instrument(logging::step_span(&step_display_name(&step, idx)))
+                    .await
+                }
+                Err(e) => Err(ExecutionError::Execution(e)),
+            };
+        let step_overhead = step_overhead
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner);
 
         match step_result {
-            Ok(result) => {
-                // Check if step was successful
-                if result.status == StepStatus::Failure {
+            Ok(mut result) => {
+                result.duration = step_started.elapsed();
+                result.overhead = step_overhead;
+                apply_workflow_commands(&mut result, &mut masks, file_commands_dir.path(), idx);
+                save_step_output(ctx.artifact_store, ctx.job_name, idx, &mut result);
+
+                // Check if step was successful; continue-on-error steps can't fail the job
+                if result.status == StepStatus::Failure && !step.continues_on_error() {
                     job_success = false;
                 }
 
+                expression_state.record_step(
+                    step,
+                    &result.status,
+                    environment::read_step_outputs(file_commands_dir.path(), idx),
+                );
+                runner_dirs.apply_file_commands(file_commands_dir.path(), idx);
+                expression_state.env_file = runner_dirs.env_file();
+                job_summary.push_str(&environment::read_step_summary(
+                    file_commands_dir.path(),
+                    idx,
+                ));
+
                 // Add step output to logs only in verbose mode or if there's an error
                 if ctx.verbose || result.status == StepStatus::Failure {
                     job_logs.push_str(&format!(
@@ -671,40 +2769,90 @@ async fn execute_job(ctx: JobExecutionContext<'_>) -> Result<JobResult, Executio
                     ));
                 }
 
+                ctx.progress
+                    .step_output(ctx.job_name, &result.name, &result.output);
+                ctx.progress
+                    .step_completed(ctx.job_name, &result.name, &result.status);
                 step_results.push(result);
             }
             Err(e) => {
-                job_success = false;
+                let e = mask(&e.to_string(), &masks);
+                job_success &= step.continues_on_error();
                 job_logs.push_str(&format!("\n=== ERROR in step {} ===\n{}\n", idx + 1, e));
+                expression_state.record_step(step, &StepStatus::Failure, HashMap::new());
 
                 // Record the error as a failed step
-                step_results.push(StepResult {
+                let mut failed = StepResult {
                     name: step
                         .name
                         .clone()
                         .unwrap_or_else(|| format!("Step {}", idx + 1)),
                     status: StepStatus::Failure,
                     output: format!("Error: {}", e),
-                });
-
-                // Stop executing further steps
-                break;
+                    output_file: None,
+                    exit_code: None,
+                    duration: step_started.elapsed(),
+                    overhead: step_overhead,
+                    annotations: Vec::new(),
+                };
+                save_step_output(ctx.artifact_store, ctx.job_name, idx, &mut failed);
+                ctx.progress
+                    .step_output(ctx.job_name, &failed.name, &failed.output);
+                ctx.progress
+                    .step_completed(ctx.job_name, &failed.name, &failed.status);
+                step_results.push(failed);
             }
         }
     }
+    let containers = job_container
+        .iter()
+        .chain(services.iter().map(|(_, service)| service));
+    stop_job_services(ctx.runtime, containers.map(|container| &container.id)).await;
+
+    // Caches are saved once the job is done, so that's when their size counts
+    for warning in oversized_caches(job, &job_env, job_dir.path(), ctx.artifact_store) {
+        logging::warning(&warning);
+        job_logs.push_str(&format!("Warning: {}\n", warning));
+    }
+
+    // A cancelled job that didn't fail counts as skipped, as it didn't finish
+    let status = if !job_success {
+        JobStatus::Failure
+    } else if cancelled {
+        JobStatus::Skipped
+    } else {
+        JobStatus::Success
+    };
+    ctx.progress.emit(ExecutionEvent::JobCompleted {
+        job: ctx.job_name.to_string(),
+        status: status.clone(),
+    });
 
     Ok(JobResult {
         name: ctx.job_name.to_string(),
-        status: if job_success {
-            JobStatus::Success
-        } else {
-            JobStatus::Failure
-        },
+        status,
         steps: step_results,
         logs: job_logs,
+        outputs: expression_state.job_outputs(job, job_success),
+        duration: started.elapsed(),
+        overhead: job_overhead,
+        summary: job_summary,
     })
 }
 
+/// Keep the full output of a step that ran in the run directory
+fn save_step_output(
+    artifact_store: &ArtifactStore,
+    job_name: &str,
+    step_idx: usize,
+    result: &mut StepResult,
+) {
+    match artifact_store.save_step_output(job_name, step_idx, &result.name, &result.output) {
+        Ok(path) => result.output_file = Some(path),
+        Err(e) => logging::warning(&e),
+    }
+}
+
 // Before the execute_matrix_combinations function, add this struct
 struct MatrixExecutionContext<'a> {
     job_name: &'a str,
@@ -712,10 +2860,25 @@ struct MatrixExecutionContext<'a> {
     combinations: &'a [MatrixCombination],
     max_parallel: usize,
     fail_fast: bool,
-    workflow: &'a WorkflowDefinition,
+    workflow: &'a Workflow,
     runtime: &'a dyn ContainerRuntime,
     env_context: &'a HashMap<String, String>,
+    artifact_store: &'a ArtifactStore,
+    finished_jobs: &'a HashMap<String, JobSummary>,
+    progress: &'a ProgressReporter,
+    cancellation: &'a CancellationToken,
+    step_control: &'a StepControl,
     verbose: bool,
+    show_action_messages: bool,
+    sandbox: bool,
+    setup_actions: &'a SetupActions,
+    secrets: &'a HashMap<String, String>,
+    /// Values of the `vars` context, from the job's environment
+    vars: &'a HashMap<String, String>,
+    runner_images: &'a HashMap<String, String>,
+    tool_cache: bool,
+    bind_workspace: bool,
+    debug_shell: bool,
 }
 
 /// Execute a set of matrix combinations
@@ -726,33 +2889,43 @@ async fn execute_matrix_combinations(
     let mut any_failed = false;
 
     // Process combinations in chunks limited by max_parallel
-    for chunk in ctx.combinations.chunks(ctx.max_parallel) {
-        // Skip processing if fail-fast is enabled and a previous job failed
-        if ctx.fail_fast && any_failed {
+    for (chunk_idx, chunk) in ctx.combinations.chunks(ctx.max_parallel).enumerate() {
+        // Skip processing if fail-fast is enabled and a previous job failed,
+        // or the run was cancelled
+        let cancellation = ctx.cancellation.reason();
+        if (ctx.fail_fast && any_failed) || cancellation.is_some() {
             // Add skipped results for remaining combinations
             for combination in chunk {
                 let combination_name = matrix::format_combination_name(ctx.job_name, combination);
+                for (idx, step) in ctx.job_template.steps.iter().enumerate() {
+                    ctx.progress.step_completed(
+                        &combination_name,
+                        &step_display_name(step, idx),
+                        &StepStatus::Skipped,
+                    );
+                }
                 results.push(JobResult {
                     name: combination_name,
                     status: JobStatus::Skipped,
                     steps: Vec::new(),
-                    logs: "Job skipped due to previous matrix job failure".to_string(),
+                    logs: match &cancellation {
+                        Some(reason) => format!("Job skipped: the run was {}", reason),
+                        None => "Job skipped due to previous matrix job failure".to_string(),
+                    },
+                    outputs: HashMap::new(),
+                    duration: Duration::ZERO,
+                    overhead: Overhead::default(),
+                    summary: String::new(),
                 });
             }
             continue;
         }
 
         // Process this chunk of combinations in parallel
-        let chunk_futures = chunk.iter().map(|combination| {
-            execute_matrix_job(
-                ctx.job_name,
-                ctx.job_template,
-                combination,
-                ctx.workflow,
-                ctx.runtime,
-                ctx.env_context,
-                ctx.verbose,
-            )
+        let chunk_futures = chunk.iter().enumerate().map(|(idx, combination)| {
+            let name = matrix::format_combination_name(ctx.job_name, combination);
+            let job_index = chunk_idx * ctx.max_parallel + idx;
+            execute_matrix_job(&ctx, job_index, combination).instrument(logging::job_span(&name))
         });
 
         let chunk_results = future::join_all(chunk_futures).await;
@@ -784,69 +2957,214 @@ async fn execute_matrix_combinations(
 
 /// Execute a single matrix job combination
 async fn execute_matrix_job(
-    job_name: &str,
-    job_template: &Job,
+    ctx: &MatrixExecutionContext<'_>,
+    job_index: usize,
     combination: &MatrixCombination,
-    workflow: &WorkflowDefinition,
-    runtime: &dyn ContainerRuntime,
-    base_env_context: &HashMap<String, String>,
-    verbose: bool,
 ) -> Result<JobResult, ExecutionError> {
+    let started = Instant::now();
+    let job_name = ctx.job_name;
+    let job_template = ctx.job_template;
+    let workflow = ctx.workflow;
+    let runtime = ctx.runtime;
+    let base_env_context = ctx.env_context;
+    let artifact_store = ctx.artifact_store;
+    let verbose = ctx.verbose;
+
     // Create the matrix-specific job name
     let matrix_job_name = matrix::format_combination_name(job_name, combination);
 
-    logging::info(&format!("Executing matrix job: {}", matrix_job_name));
+    ctx.progress.emit(ExecutionEvent::JobStarted {
+        job: matrix_job_name.clone(),
+    });
 
     // Clone the environment and add matrix-specific values
     let mut job_env = base_env_context.clone();
     environment::add_matrix_context(&mut job_env, combination);
 
     // Add job-level environment variables
-    for (key, value) in &job_template.env {
-        // TODO: Substitute matrix variable references in env values
-        job_env.insert(key.clone(), value.clone());
-    }
+    let needs = needs_context(job_template, ctx.finished_jobs);
+    job_env.extend(job_env_values(
+        ctx.workflow,
+        job_template,
+        &job_env,
+        Some(&combination.values),
+        &needs,
+        ctx.secrets,
+        ctx.vars,
+    ));
 
     // Execute the job steps
     let mut step_results = Vec::new();
     let mut job_logs = String::new();
+    let mut job_summary = String::new();
+    // Values steps asked to hide with `::add-mask::`
+    let mut masks = Vec::new();
 
-    // Create a temporary directory for this job execution
-    let job_dir = tempfile::tempdir()
-        .map_err(|e| ExecutionError::Execution(format!("Failed to create job directory: {}", e)))?;
+    let job_dir = job_workspace(ctx.workflow, &matrix_job_name, ctx.bind_workspace)?;
 
-    // Get the current project directory
-    let current_dir = std::env::current_dir().map_err(|e| {
-        ExecutionError::Execution(format!("Failed to get current directory: {}", e))
+    let file_commands_dir = tempfile::tempdir().map_err(|e| {
+        ExecutionError::Execution(format!("Failed to create step output directory: {}", e))
     })?;
+    let mut runner_dirs = create_runner_dirs(&mut job_env, ctx.sandbox.then_some(job_dir.path()))?;
+    if ctx.tool_cache {
+        mount_tool_caches(runtime, &mut runner_dirs, &mut job_env, workflow, job_name).await;
+    }
+    job_env.insert("GITHUB_JOB".to_string(), job_name.to_string());
+    add_job_token(&mut job_env, workflow, job_template, ctx.secrets);
 
-    // Copy project files to the job workspace directory
-    logging::info(&format!(
-        "Copying project files to job workspace: {}",
-        job_dir.path().display()
-    ));
-    copy_directory_contents(&current_dir, job_dir.path())?;
+    let mut job_success = true;
+    let mut cancelled = false;
+    let mut expression_state = JobExpressionState {
+        job_env: &job_env,
+        env_file: HashMap::new(),
+        secrets: ctx.secrets,
+        vars: ctx.vars,
+        matrix: Some(&combination.values),
+        needs,
+        steps: serde_json::Map::new(),
+        strategy: StrategyContext {
+            job_index,
+            job_total: ctx.combinations.len(),
+            fail_fast: ctx.fail_fast,
+            max_parallel: ctx.max_parallel.min(ctx.combinations.len()),
+        },
+        job: serde_json::Map::new(),
+        cancellation: ctx.cancellation,
+    };
+
+    let mut job_overhead = Overhead::default();
+    let services = match start_job_services(
+        &matrix_job_name,
+        job_template,
+        runtime,
+        &expression_state.context(true),
+        &mut job_overhead,
+    )
+    .await
+    {
+        Ok(services) => services,
+        Err(e) => {
+            logging::error(&e);
+            job_logs.push_str(&format!("{}\n\n", e));
+            job_success = false;
+            Vec::new()
+        }
+    };
+    let mounts = job_volume_paths(job_dir.path(), file_commands_dir.path(), &runner_dirs);
+    let job_container = match start_job_container(
+        job_name,
+        job_template,
+        runtime,
+        &mounts,
+        &expression_state.context(true),
+        &mut job_overhead,
+    )
+    .await
+    {
+        Ok(container) => container,
+        Err(e) => {
+            logging::error(&e);
+            job_logs.push_str(&format!("{}\n\n", e));
+            job_success = false;
+            None
+        }
+    };
+    expression_state.job = job_context(job_container.as_ref(), &services);
 
-    let job_success = if job_template.steps.is_empty() {
+    if job_template.steps.is_empty() {
         logging::warning(&format!("Job '{}' has no steps", matrix_job_name));
-        true
     } else {
         // Execute each step
         for (idx, step) in job_template.steps.iter().enumerate() {
-            match execute_step(StepExecutionContext {
-                step,
-                step_idx: idx,
-                job_env: &job_env,
-                working_dir: job_dir.path(),
-                runtime,
-                workflow,
-                runner_image: &get_runner_image(&job_template.runs_on),
-                verbose,
-                matrix_combination: &Some(combination.values.clone()),
-            })
-            .await
-            {
-                Ok(result) => {
+            let expression_ctx = expression_state.context(job_success);
+            cancelled |= expression_ctx.job_state == JobState::Cancelled;
+            let skipped = match check_step_condition(step, idx, &expression_ctx, job_success) {
+                Some(skipped) => Some(skipped),
+                None => {
+                    decide_step(
+                        ctx.step_control,
+                        ctx.cancellation,
+                        &matrix_job_name,
+                        step,
+                        idx,
+                    )
+                    .await
+                }
+            };
+            if let Some(skipped) = skipped {
+                // Aborting at a pause cancels the run from this step on
+                cancelled |= ctx.cancellation.is_cancelled();
+                job_logs.push_str(&format!("Step: {}\n", skipped.name));
+                job_logs.push_str(&format!("Status: {:?}\n", skipped.status));
+                job_logs.push_str(&skipped.output);
+                job_logs.push_str("\n\n");
+                if skipped.status == StepStatus::Failure {
+                    job_success = false;
+                }
+                expression_state.record_step(step, &skipped.status, HashMap::new());
+                ctx.progress
+                    .step_completed(&matrix_job_name, &skipped.name, &skipped.status);
+                step_results.push(skipped);
+                continue;
+            }
+
+            ctx.progress
+                .step_started(&matrix_job_name, &step_display_name(step, idx));
+            let step_started = Instant::now();
+            let step_overhead = Mutex::new(Overhead::default());
+            let step_result = match interpolate_step(
+                &with_run_defaults(step, job_template, ctx.workflow),
+                &expression_ctx,
+            ) {
+                Ok(step) => {
+                    execute_step(StepExecutionContext {
+                        step: &step,
+                        step_idx: idx,
+                        job_env: &job_env,
+                        working_dir: job_dir.path(),
+                        file_commands_dir: file_commands_dir.path(),
+                        runner_dirs: &runner_dirs,
+                        setup_actions: ctx.setup_actions,
+                        runtime,
+                        runner_image: &runner_image::job_image(job_template, ctx.runner_images),
+                        job_container: job_container.as_ref().map(|started| JobContainerRef {
+                            id: &started.id,
+                            file_commands_dir: file_commands_dir.path(),
+                        }),
+                        artifact_store,
+                        verbose,
+                        show_action_messages: ctx.show_action_messages,
+                        matrix_combination: &Some(combination.values.clone()),
+                        overhead: &step_overhead,
+                        debug_shell: ctx.debug_shell,
+                    })
+                    .instrument(logging::step_span(&step_display_name(&step, idx)))
+                    .await
+                }
+                Err(e) => Err(ExecutionError::Execution(e)),
+            };
+            let step_overhead = step_overhead
+                .into_inner()
+                .unwrap_or_else(PoisonError::into_inner);
+
+            match step_result {
+                Ok(mut result) => {
+                    result.duration = step_started.elapsed();
+                    result.overhead = step_overhead;
+                    apply_workflow_commands(&mut result, &mut masks, file_commands_dir.path(), idx);
+                    save_step_output(artifact_store, &matrix_job_name, idx, &mut result);
+                    expression_state.record_step(
+                        step,
+                        &result.status,
+                        environment::read_step_outputs(file_commands_dir.path(), idx),
+                    );
+                    runner_dirs.apply_file_commands(file_commands_dir.path(), idx);
+                    expression_state.env_file = runner_dirs.env_file();
+                    job_summary.push_str(&environment::read_step_summary(
+                        file_commands_dir.path(),
+                        idx,
+                    ));
+
                     job_logs.push_str(&format!("Step: {}\n", result.name));
                     job_logs.push_str(&format!("Status: {:?}\n", result.status));
 
@@ -859,59 +3177,385 @@ async fn execute_matrix_job(
                         job_logs.push('\n');
                     }
 
-                    step_results.push(result.clone());
-
-                    if result.status != StepStatus::Success {
-                        // Step failed, abort job
-                        return Ok(JobResult {
-                            name: matrix_job_name,
-                            status: JobStatus::Failure,
-                            steps: step_results,
-                            logs: job_logs,
-                        });
+                    if result.status == StepStatus::Failure && !step.continues_on_error() {
+                        // Later steps only run if their condition allows it
+                        job_success = false;
                     }
+
+                    ctx.progress
+                        .step_output(&matrix_job_name, &result.name, &result.output);
+                    ctx.progress
+                        .step_completed(&matrix_job_name, &result.name, &result.status);
+                    step_results.push(result);
                 }
                 Err(e) => {
-                    // Log the error and abort the job
+                    let e = mask(&e.to_string(), &masks);
+                    job_success &= step.continues_on_error();
                     job_logs.push_str(&format!("Step execution error: {}\n\n", e));
-                    return Ok(JobResult {
-                        name: matrix_job_name,
-                        status: JobStatus::Failure,
-                        steps: step_results,
-                        logs: job_logs,
-                    });
+                    expression_state.record_step(step, &StepStatus::Failure, HashMap::new());
+                    let mut failed = StepResult {
+                        name: step
+                            .name
+                            .clone()
+                            .unwrap_or_else(|| format!("Step {}", idx + 1)),
+                        status: StepStatus::Failure,
+                        output: format!("Error: {}", e),
+                        output_file: None,
+                        exit_code: None,
+                        duration: step_started.elapsed(),
+                        overhead: step_overhead,
+                        annotations: Vec::new(),
+                    };
+                    save_step_output(artifact_store, &matrix_job_name, idx, &mut failed);
+                    ctx.progress
+                        .step_output(&matrix_job_name, &failed.name, &failed.output);
+                    ctx.progress
+                        .step_completed(&matrix_job_name, &failed.name, &failed.status);
+                    step_results.push(failed);
                 }
             }
         }
+    }
+    let containers = job_container
+        .iter()
+        .chain(services.iter().map(|(_, service)| service));
+    stop_job_services(runtime, containers.map(|container| &container.id)).await;
+
+    for warning in oversized_caches(job_template, &job_env, job_dir.path(), artifact_store) {
+        logging::warning(&warning);
+        job_logs.push_str(&format!("Warning: {}\n", warning));
+    }
 
-        true
+    // A cancelled job that didn't fail counts as skipped, as it didn't finish
+    let status = if !job_success {
+        JobStatus::Failure
+    } else if cancelled {
+        JobStatus::Skipped
+    } else {
+        JobStatus::Success
     };
+    ctx.progress.emit(ExecutionEvent::JobCompleted {
+        job: matrix_job_name.clone(),
+        status: status.clone(),
+    });
 
     // Return job result
     Ok(JobResult {
         name: matrix_job_name,
-        status: if job_success {
-            JobStatus::Success
-        } else {
-            JobStatus::Failure
-        },
+        status,
         steps: step_results,
         logs: job_logs,
+        outputs: expression_state.job_outputs(job_template, job_success),
+        duration: started.elapsed(),
+        overhead: job_overhead,
+        summary: job_summary,
+    })
+}
+
+/// Step outputs and outcomes seen so far in a job, plus the results of the
+/// jobs it needs. Backs the `steps` and `needs` expression contexts.
+struct JobExpressionState<'a> {
+    job_env: &'a HashMap<String, String>,
+    /// What steps wrote to `$GITHUB_ENV` so far, which the job's own
+    /// variables win over
+    env_file: HashMap<String, String>,
+    secrets: &'a HashMap<String, String>,
+    vars: &'a HashMap<String, String>,
+    matrix: Option<&'a HashMap<String, Value>>,
+    needs: serde_json::Value,
+    steps: serde_json::Map<String, serde_json::Value>,
+    strategy: StrategyContext,
+    /// The `job` context, but for its status
+    job: serde_json::Map<String, serde_json::Value>,
+    cancellation: &'a CancellationToken,
+}
+
+impl JobExpressionState<'_> {
+    fn context(&self, job_success: bool) -> EvaluationContext {
+        let mut env = self.env_file.clone();
+        env.extend(self.job_env.clone());
+        let mut ctx = environment::create_expression_context(&env, self.matrix);
+        ctx.set("steps", serde_json::Value::Object(self.steps.clone()));
+        ctx.set("needs", self.needs.clone());
+        environment::set_secrets_context(&mut ctx, self.secrets);
+        ctx.set("vars", serde_json::json!(self.vars));
+        ctx.set("strategy", self.strategy.to_json());
+        let (job_state, status) = if self.cancellation.is_cancelled() {
+            (JobState::Cancelled, "cancelled")
+        } else if job_success {
+            (JobState::Success, "success")
+        } else {
+            (JobState::Failure, "failure")
+        };
+        let mut job = self.job.clone();
+        job.insert("status".to_string(), serde_json::json!(status));
+        ctx.set("job", serde_json::Value::Object(job));
+        ctx.job_state = job_state;
+        ctx
+    }
+
+    /// Record a finished step so later steps can read `steps.<id>.outputs`
+    fn record_step(&mut self, step: &Step, status: &StepStatus, outputs: HashMap<String, String>) {
+        let Some(id) = &step.id else {
+            return;
+        };
+        let outcome = match status {
+            StepStatus::Success => "success",
+            StepStatus::Failure => "failure",
+            StepStatus::Skipped => "skipped",
+        };
+        self.steps.insert(
+            id.clone(),
+            serde_json::json!({ "outputs": outputs, "outcome": outcome, "conclusion": outcome }),
+        );
+    }
+
+    /// Evaluate the job's `outputs:` once all of its steps have run
+    fn job_outputs(&self, job: &Job, job_success: bool) -> HashMap<String, String> {
+        let ctx = self.context(job_success);
+        job.outputs
+            .iter()
+            .filter_map(
+                |(name, template)| match expressions::interpolate(template, &ctx) {
+                    Ok(value) => Some((name.clone(), value)),
+                    Err(e) => {
+                        logging::warning(&format!(
+                            "Failed to evaluate job output '{}': {}",
+                            name, e
+                        ));
+                        None
+                    }
+                },
+            )
+            .collect()
+    }
+}
+
+/// Fill in what a step leaves to the `defaults.run` of its job or workflow
+fn with_run_defaults(step: &Step, job: &Job, workflow: &Workflow) -> Step {
+    let defaults = [&job.defaults, &workflow.defaults]
+        .into_iter()
+        .flatten()
+        .filter_map(|defaults| defaults.run.as_ref());
+    let mut step = step.clone();
+    if step.shell.is_none() {
+        step.shell = defaults.clone().find_map(|run| run.shell.clone());
+    }
+    if step.working_directory.is_none() {
+        step.working_directory = defaults
+            .clone()
+            .find_map(|run| run.working_directory.clone());
+    }
+    step
+}
+
+/// Substitute `${{ }}` expressions in the parts of a step that are evaluated
+/// before it runs
+fn interpolate_step(step: &Step, ctx: &EvaluationContext) -> Result<Step, String> {
+    let render = |text: &str, ctx: &EvaluationContext| {
+        expressions::interpolate(text, ctx)
+            .map_err(|e| format!("Failed to evaluate expression in '{}': {}", text, e))
+    };
+
+    let mut step = step.clone();
+    // The step's `env:` is evaluated first, as its other fields see it in
+    // the `env` context. Variables the runner sets keep their values.
+    step.env.retain(|key, _| !github::is_default_variable(key));
+    for value in step.env.values_mut() {
+        *value = render(value, ctx)?;
+    }
+    let mut ctx = ctx.clone();
+    if let Some(serde_json::Value::Object(env)) = ctx.get("env") {
+        let mut env = env.clone();
+        env.extend(
+            step.env
+                .iter()
+                .map(|(key, value)| (key.clone(), serde_json::Value::String(value.clone()))),
+        );
+        ctx.set("env", serde_json::Value::Object(env));
+    }
+
+    step.name = step.name.as_deref().map(|t| render(t, &ctx)).transpose()?;
+    step.run = step.run.as_deref().map(|t| render(t, &ctx)).transpose()?;
+    step.working_directory = step
+        .working_directory
+        .as_deref()
+        .map(|t| render(t, &ctx))
+        .transpose()?;
+    for value in step.with.values_mut() {
+        *value = render(value, &ctx)?;
+    }
+    Ok(step)
+}
+
+/// Evaluate a step's `if:` condition against the job state so far. Returns
+/// the result to record instead of running the step, or None if it should run.
+fn check_step_condition(
+    step: &Step,
+    step_idx: usize,
+    ctx: &EvaluationContext,
+    job_success: bool,
+) -> Option<StepResult> {
+    let name = step
+        .name
+        .clone()
+        .unwrap_or_else(|| format!("Step {}", step_idx + 1));
+
+    let Some(condition) = &step.if_condition else {
+        // Without a condition, a step behaves as `if: success()`
+        return match ctx.job_state {
+            JobState::Cancelled => Some(StepResult {
+                name,
+                status: StepStatus::Skipped,
+                output: "Step skipped: the run was cancelled".to_string(),
+                output_file: None,
+                exit_code: None,
+                duration: Duration::ZERO,
+                overhead: Overhead::default(),
+                annotations: Vec::new(),
+            }),
+            _ if job_success => None,
+            _ => Some(StepResult {
+                name,
+                status: StepStatus::Skipped,
+                output: "Step skipped due to a previous step failure".to_string(),
+                output_file: None,
+                exit_code: None,
+                duration: Duration::ZERO,
+                overhead: Overhead::default(),
+                annotations: Vec::new(),
+            }),
+        };
+    };
+
+    match expressions::evaluate_condition(condition, ctx) {
+        Ok(true) => None,
+        Ok(false) => Some(StepResult {
+            name,
+            status: StepStatus::Skipped,
+            output: format!("Step skipped: condition '{}' evaluated to false", condition),
+            output_file: None,
+            exit_code: None,
+            duration: Duration::ZERO,
+            overhead: Overhead::default(),
+            annotations: Vec::new(),
+        }),
+        Err(e) => Some(StepResult {
+            name,
+            status: StepStatus::Failure,
+            output: format!("Invalid 'if' condition '{}': {}", condition, e),
+            output_file: None,
+            exit_code: None,
+            duration: Duration::ZERO,
+            overhead: Overhead::default(),
+            annotations: Vec::new(),
+        }),
+    }
+}
+
+/// Pause before a step when the run asks for it, and return the step as
+/// skipped when the user skips it or aborts the run
+async fn decide_step(
+    control: &StepControl,
+    cancellation: &CancellationToken,
+    job_name: &str,
+    step: &Step,
+    step_idx: usize,
+) -> Option<StepResult> {
+    let name = step_display_name(step, step_idx);
+    let output = match control.before_step(job_name, &name, step_idx).await {
+        StepDecision::Run => return None,
+        StepDecision::Skip => "Step skipped at a pause",
+        StepDecision::Abort => {
+            cancellation.cancel("aborted");
+            "Step skipped: the run was aborted"
+        }
+    };
+    Some(StepResult {
+        name,
+        status: StepStatus::Skipped,
+        output: output.to_string(),
+        output_file: None,
+        exit_code: None,
+        duration: Duration::ZERO,
+        overhead: Overhead::default(),
+        annotations: Vec::new(),
     })
 }
 
+/// Where the per-step file command directory is mounted in Docker mode
+const CONTAINER_FILE_COMMANDS_DIR: &str = "/github/file_commands";
+
+/// A running job container and the file command directory mounted into it
+#[derive(Clone, Copy)]
+struct JobContainerRef<'a> {
+    id: &'a str,
+    file_commands_dir: &'a Path,
+}
+
 // Before the execute_step function, add this struct
 struct StepExecutionContext<'a> {
-    step: &'a workflow::Step,
+    step: &'a Step,
     step_idx: usize,
     job_env: &'a HashMap<String, String>,
     working_dir: &'a Path,
+    file_commands_dir: &'a Path,
+    runner_dirs: &'a RunnerDirs,
+    setup_actions: &'a SetupActions,
     runtime: &'a dyn ContainerRuntime,
-    workflow: &'a WorkflowDefinition,
     runner_image: &'a str,
+    /// The job's `container:`, which `run` steps are executed in
+    job_container: Option<JobContainerRef<'a>>,
+    artifact_store: &'a ArtifactStore,
     verbose: bool,
+    show_action_messages: bool,
     #[allow(dead_code)]
     matrix_combination: &'a Option<HashMap<String, Value>>,
+    /// Where the time the step spends pulling images and starting
+    /// containers adds up
+    overhead: &'a Mutex<Overhead>,
+    /// Open a shell where the step ran when it fails
+    debug_shell: bool,
+}
+
+impl StepExecutionContext<'_> {
+    /// Pull an image a step runs in, warning instead of failing when it
+    /// can't be pulled
+    async fn pull_image(&self, image: &str) {
+        let started = Instant::now();
+        if let Err(e) = self.runtime.pull_image(image).await {
+            logging::warning(&format!("Failed to pull image {}: {}", image, e));
+        }
+        self.add_overhead(&ContainerTimings {
+            image_pull: started.elapsed(),
+            ..Default::default()
+        });
+    }
+
+    fn add_overhead(&self, timings: &ContainerTimings) {
+        if let Ok(mut overhead) = self.overhead.lock() {
+            overhead.add(timings);
+        }
+    }
+}
+
+/// `GITHUB_ACTION` of a step: its id, or as GitHub names steps without one,
+/// `__run` for scripts and `__owner_repo` for actions
+fn step_action_name(step: &Step) -> String {
+    if let Some(id) = &step.id {
+        return id.clone();
+    }
+    match step.uses.as_deref().and_then(ActionRef::parse) {
+        Some(action) => format!(
+            "__{}",
+            action
+                .repo
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                .collect::<String>()
+        ),
+        None => "__run".to_string(),
+    }
 }
 
 async fn execute_step(ctx: StepExecutionContext<'_>) -> Result<StepResult, ExecutionError> {
@@ -921,81 +3565,90 @@ async fn execute_step(ctx: StepExecutionContext<'_>) -> Result<StepResult, Execu
         .clone()
         .unwrap_or_else(|| format!("Step {}", ctx.step_idx + 1));
 
-    if ctx.verbose {
-        logging::info(&format!("  Executing step: {}", step_name));
-    }
-
-    // Prepare step environment
-    let mut step_env = ctx.job_env.clone();
+    // Prepare step environment: what earlier steps wrote to $GITHUB_ENV,
+    // which the job's own variables win over
+    let mut step_env = ctx.runner_dirs.env_file();
+    step_env.extend(ctx.job_env.clone());
 
-    // Add step-level environment variables
+    // Then what setup actions exported, and step-level environment variables
+    step_env.extend(ctx.runner_dirs.exports());
     for (key, value) in &ctx.step.env {
         step_env.insert(key.clone(), value.clone());
     }
 
-    // Execute the step based on its type
-    let step_result = if let Some(uses) = &ctx.step.uses {
-        // Action step
-        let action_info = ctx.workflow.resolve_action(uses);
-
-        // Check if this is the checkout action
-        if uses.starts_with("actions/checkout") {
-            // Get the current directory (assumes this is where your project is)
-            let current_dir = std::env::current_dir().map_err(|e| {
-                ExecutionError::Execution(format!("Failed to get current dir: {}", e))
-            })?;
-
-            // Copy the project files to the workspace
-            copy_directory_contents(&current_dir, ctx.working_dir)?;
-
-            // Add info for logs
-            let output = if ctx.verbose {
-                let mut detailed_output =
-                    "Emulated checkout: Copied current directory to workspace\n\n".to_string();
-
-                // Add checkout action details
-                detailed_output.push_str("Checkout Details:\n");
-                detailed_output.push_str("  - Source: Local directory\n");
-                detailed_output
-                    .push_str(&format!("  - Destination: {}\n", ctx.working_dir.display()));
-
-                // Add list of top-level files/directories that were copied (limit to 10)
-                detailed_output.push_str("\nTop-level files/directories copied:\n");
-                if let Ok(entries) = std::fs::read_dir(&current_dir) {
-                    for (i, entry) in entries.take(10).enumerate() {
-                        if let Ok(entry) = entry {
-                            let file_type = if entry.path().is_dir() {
-                                "directory"
-                            } else {
-                                "file"
-                            };
-                            detailed_output.push_str(&format!(
-                                "  - {} ({})\n",
-                                entry.file_name().to_string_lossy(),
-                                file_type
-                            ));
-                        }
+    // Directories earlier steps wrote to $GITHUB_PATH go in front of the
+    // PATH, which only the shell knows in containers
+    let path_file = ctx.runner_dirs.path_file();
+    if !path_file.is_empty() && !runs_in_container(ctx.job_env) {
+        let path = step_env
+            .get("PATH")
+            .cloned()
+            .or_else(|| std::env::var("PATH").ok())
+            .unwrap_or_default();
+        let dirs = path_file
+            .iter()
+            .map(PathBuf::from)
+            .chain(std::env::split_paths(&path));
+        if let Ok(path) = std::env::join_paths(dirs) {
+            step_env.insert("PATH".to_string(), path.to_string_lossy().to_string());
+        }
+    }
 
-                        if i >= 9 {
-                            detailed_output.push_str("  - ... (more items not shown)\n");
-                            break;
-                        }
-                    }
-                }
+    step_env.insert("GITHUB_ACTION".to_string(), step_action_name(ctx.step));
+    if let Some(action) = ctx.step.uses.as_deref().and_then(ActionRef::parse) {
+        step_env.insert("GITHUB_ACTION_REPOSITORY".to_string(), action.repo);
+        step_env.insert("GITHUB_ACTION_REF".to_string(), action.git_ref);
+    }
 
-                detailed_output
-            } else {
-                "Emulated checkout: Copied current directory to workspace".to_string()
-            };
+    // Each step writes to fresh file command files, read back by the job loop
+    for (variable, name) in environment::STEP_FILE_COMMANDS {
+        let file = environment::step_file(ctx.file_commands_dir, name, ctx.step_idx);
+        fs::write(&file, "").map_err(|e| {
+            ExecutionError::Execution(format!("Failed to create ${} file: {}", variable, e))
+        })?;
+        let path = if runs_in_container(ctx.job_env) {
+            container_file_commands_path(&ctx, &file)
+        } else {
+            file
+        };
+        step_env.insert(variable.to_string(), path.to_string_lossy().to_string());
+    }
 
-            if ctx.verbose {
-                println!("  Emulated actions/checkout: copied project files to workspace");
-            }
+    // Containers see the event payload through the file command mount
+    if let Some(event_path) = ctx.job_env.get("GITHUB_EVENT_PATH") {
+        if runs_in_container(ctx.job_env) {
+            fs::copy(event_path, ctx.file_commands_dir.join("event.json")).map_err(|e| {
+                ExecutionError::Execution(format!("Failed to copy the event payload: {}", e))
+            })?;
+            step_env.insert(
+                "GITHUB_EVENT_PATH".to_string(),
+                container_file_commands_path(&ctx, &ctx.file_commands_dir.join("event.json"))
+                    .to_string_lossy()
+                    .to_string(),
+            );
+        }
+    }
 
-            StepResult {
-                name: step_name,
-                status: StepStatus::Success,
-                output,
+    // Execute the step based on its type
+    let step_result = if let Some(uses) = &ctx.step.uses {
+        // Action step
+        let action_info = resolve_action(uses);
+
+        if uses.starts_with("actions/upload-artifact") {
+            upload_artifact(&ctx, step_name)
+        } else if uses.starts_with("actions/download-artifact") {
+            download_artifact(&ctx, step_name)
+        } else if uses.starts_with("actions/checkout") {
+            checkout_step(&ctx, step_name)
+        } else if let Some((handler, shims)) =
+            ctx.setup_actions.find(uses).zip(ctx.runner_dirs.shims())
+        {
+            setup_toolchain(&ctx, handler, shims, step_name)
+        } else if let Some((action_dir, metadata)) = runnable_action(&ctx, &action_info, uses) {
+            if metadata.is_docker() {
+                execute_docker_action(&ctx, &action_dir, &metadata, &step_env, step_name).await?
+            } else {
+                execute_node_action(&ctx, &action_dir, &metadata, &step_env, step_name).await?
             }
         } else {
             // Get action info
@@ -1005,16 +3658,7 @@ async fn execute_step(ctx: StepExecutionContext<'_>) -> Result<StepResult, Execu
             if image == "composite" && action_info.is_local {
                 // Handle composite action
                 let action_path = Path::new(&action_info.repository);
-                execute_composite_action(
-                    ctx.step,
-                    action_path,
-                    &step_env,
-                    ctx.working_dir,
-                    ctx.runtime,
-                    ctx.runner_image,
-                    ctx.verbose,
-                )
-                .await?
+                execute_composite_action(&ctx, action_path, &step_env).await?
             } else {
                 // Regular Docker or JavaScript action processing
                 // ... (rest of the existing code for handling regular actions)
@@ -1041,6 +3685,11 @@ async fn execute_step(ctx: StepExecutionContext<'_>) -> Result<StepResult, Execu
                             name: step_name,
                             status: StepStatus::Success,
                             output: format!("Using system Rust: {}", rustc_version.trim()),
+                            output_file: None,
+                            exit_code: None,
+                            duration: Duration::ZERO,
+                            overhead: Overhead::default(),
+                            annotations: Vec::new(),
                         });
                     }
 
@@ -1058,97 +3707,101 @@ async fn execute_step(ctx: StepExecutionContext<'_>) -> Result<StepResult, Execu
                         ));
 
                         // Get the command from the 'with' parameters
-                        if let Some(with_params) = &ctx.step.with {
-                            if let Some(command) = with_params.get("command") {
-                                logging::info(&format!("🔄 Found command parameter: {}", command));
-
-                                // Build the actual command
-                                let mut real_command = format!("cargo {}", command);
-
-                                // Add any arguments if specified
-                                if let Some(args) = with_params.get("args") {
-                                    if !args.is_empty() {
-                                        // Resolve GitHub-style variables in args
-                                        let resolved_args = if args.contains("${{") {
-                                            logging::info(&format!(
-                                                "🔄 Resolving workflow variables in: {}",
-                                                args
-                                            ));
-
-                                            // Handle common matrix variables
-                                            let mut resolved =
-                                                args.replace("${{ matrix.target }}", "");
-                                            resolved = resolved.replace("${{ matrix.os }}", "");
-
-                                            // Handle any remaining ${{ variables }} by removing them
-                                            let re_pattern =
-                                                regex::Regex::new(r"\$\{\{\s*([^}]+)\s*\}\}")
-                                                    .unwrap_or_else(|_| {
-                                                        logging::error(
-                                                            "Failed to create regex pattern",
-                                                        );
-                                                        regex::Regex::new(r"\$\{\{.*?\}\}").unwrap()
-                                                    });
-
-                                            let resolved =
-                                                re_pattern.replace_all(&resolved, "").to_string();
-                                            logging::info(&format!("🔄 Resolved to: {}", resolved));
-
-                                            resolved.trim().to_string()
-                                        } else {
-                                            args.clone()
-                                        };
-
-                                        // Only add if we have something left after resolving variables
-                                        // and it's not just "--target" without a value
-                                        if !resolved_args.is_empty() && resolved_args != "--target"
-                                        {
-                                            real_command.push_str(&format!(" {}", resolved_args));
-                                        }
+                        let with_params = &ctx.step.with;
+                        if let Some(command) = with_params.get("command") {
+                            logging::info(&format!("🔄 Found command parameter: {}", command));
+
+                            // Build the actual command
+                            let mut real_command = format!("cargo {}", command);
+
+                            // Add any arguments if specified
+                            if let Some(args) = with_params.get("args") {
+                                if !args.is_empty() {
+                                    // Resolve GitHub-style variables in args
+                                    let resolved_args = if args.contains("${{") {
+                                        logging::info(&format!(
+                                            "🔄 Resolving workflow variables in: {}",
+                                            args
+                                        ));
+
+                                        // Handle common matrix variables
+                                        let mut resolved = args.replace("${{ matrix.target }}", "");
+                                        resolved = resolved.replace("${{ matrix.os }}", "");
+
+                                        // Handle any remaining ${{ variables }} by removing them
+                                        let re_pattern =
+                                            regex::Regex::new(r"\$\{\{\s*([^}]+)\s*\}\}")
+                                                .unwrap_or_else(|_| {
+                                                    logging::error(
+                                                        "Failed to create regex pattern",
+                                                    );
+                                                    regex::Regex::new(r"\$\{\{.*?\}\}").unwrap()
+                                                });
+
+                                        let resolved =
+                                            re_pattern.replace_all(&resolved, "").to_string();
+                                        logging::info(&format!("🔄 Resolved to: {}", resolved));
+
+                                        resolved.trim().to_string()
+                                    } else {
+                                        args.clone()
+                                    };
+
+                                    // Only add if we have something left after resolving variables
+                                    // and it's not just "--target" without a value
+                                    if !resolved_args.is_empty() && resolved_args != "--target" {
+                                        real_command.push_str(&format!(" {}", resolved_args));
                                     }
                                 }
+                            }
 
-                                logging::info(&format!(
-                                    "🔄 Running actual command: {}",
-                                    real_command
-                                ));
+                            logging::info(&format!("🔄 Running actual command: {}", real_command));
 
-                                // Execute the command
-                                let mut cmd = Command::new("sh");
-                                cmd.arg("-c");
-                                cmd.arg(&real_command);
-                                cmd.current_dir(ctx.working_dir);
+                            // Execute the command
+                            let mut cmd = Command::new("sh");
+                            cmd.arg("-c");
+                            cmd.arg(&real_command);
+                            cmd.current_dir(ctx.working_dir);
 
-                                // Add environment variables
-                                for (key, value) in step_env {
-                                    cmd.env(key, value);
-                                }
+                            // Add environment variables
+                            for (key, value) in step_env {
+                                cmd.env(key, value);
+                            }
 
-                                match cmd.output() {
-                                    Ok(output) => {
-                                        let exit_code = output.status.code().unwrap_or(-1);
-                                        let stdout =
-                                            String::from_utf8_lossy(&output.stdout).to_string();
-                                        let stderr =
-                                            String::from_utf8_lossy(&output.stderr).to_string();
-
-                                        return Ok(StepResult {
-                                            name: step_name,
-                                            status: if exit_code == 0 {
-                                                StepStatus::Success
-                                            } else {
-                                                StepStatus::Failure
-                                            },
-                                            output: format!("{}\n{}", stdout, stderr),
-                                        });
-                                    }
-                                    Err(e) => {
-                                        return Ok(StepResult {
-                                            name: step_name,
-                                            status: StepStatus::Failure,
-                                            output: format!("Failed to execute command: {}", e),
-                                        });
-                                    }
+                            match cmd.output() {
+                                Ok(output) => {
+                                    let exit_code = output.status.code().unwrap_or(-1);
+                                    let stdout =
+                                        String::from_utf8_lossy(&output.stdout).to_string();
+                                    let stderr =
+                                        String::from_utf8_lossy(&output.stderr).to_string();
+
+                                    return Ok(StepResult {
+                                        name: step_name,
+                                        status: if exit_code == 0 {
+                                            StepStatus::Success
+                                        } else {
+                                            StepStatus::Failure
+                                        },
+                                        output: format!("{}\n{}", stdout, stderr),
+                                        output_file: None,
+                                        exit_code: None,
+                                        duration: Duration::ZERO,
+                                        overhead: Overhead::default(),
+                                        annotations: Vec::new(),
+                                    });
+                                }
+                                Err(e) => {
+                                    return Ok(StepResult {
+                                        name: step_name,
+                                        status: StepStatus::Failure,
+                                        output: format!("Failed to execute command: {}", e),
+                                        output_file: None,
+                                        exit_code: None,
+                                        duration: Duration::ZERO,
+                                        overhead: Overhead::default(),
+                                        annotations: Vec::new(),
+                                    });
                                 }
                             }
                         }
@@ -1180,28 +3833,13 @@ async fn execute_step(ctx: StepExecutionContext<'_>) -> Result<StepResult, Execu
                     // For GitHub actions, check if we have special handling
                     if let Err(e) = emulation::handle_special_action(uses).await {
                         // Log error but continue
-                        println!("   Warning: Special action handling failed: {}", e);
+                        logging::warning(&format!("Special action handling failed: {}", e));
                     }
 
-                    // Check if we should hide GitHub action messages
-                    let hide_action_value = ctx
-                        .job_env
-                        .get("WRKFLW_HIDE_ACTION_MESSAGES")
-                        .cloned()
-                        .unwrap_or_else(|| "not set".to_string());
-
-                    logging::debug(&format!(
-                        "WRKFLW_HIDE_ACTION_MESSAGES value: {}",
-                        hide_action_value
-                    ));
-
-                    let hide_messages = hide_action_value == "true";
-                    logging::debug(&format!("Should hide messages: {}", hide_messages));
-
                     // Only log a message to the console if we're showing action messages
-                    if !hide_messages {
+                    if ctx.show_action_messages {
                         // For Emulation mode, log a message about what action would be executed
-                        println!("   ⚙️ Would execute GitHub action: {}", uses);
+                        logging::info(&format!("   ⚙️ Would execute GitHub action: {}", uses));
                     }
 
                     // Extract the actual command from the GitHub action if applicable
@@ -1209,82 +3847,78 @@ async fn execute_step(ctx: StepExecutionContext<'_>) -> Result<StepResult, Execu
                     let mut real_command_parts = Vec::new();
 
                     // Check if this action has 'with' parameters that specify a command to run
-                    if let Some(with_params) = &ctx.step.with {
-                        // Common GitHub action pattern: has a 'command' parameter
-                        if let Some(cmd) = with_params.get("command") {
-                            if ctx.verbose {
-                                logging::info(&format!("🔄 Found command parameter: {}", cmd));
-                            }
+                    let with_params = &ctx.step.with;
+                    // Common GitHub action pattern: has a 'command' parameter
+                    if let Some(cmd) = with_params.get("command") {
+                        if ctx.verbose {
+                            logging::info(&format!("🔄 Found command parameter: {}", cmd));
+                        }
 
-                            // Convert to real command based on action type patterns
-                            if uses.contains("cargo") || uses.contains("rust") {
-                                // Cargo command pattern
-                                real_command_parts.push("cargo".to_string());
+                        // Convert to real command based on action type patterns
+                        if uses.contains("cargo") || uses.contains("rust") {
+                            // Cargo command pattern
+                            real_command_parts.push("cargo".to_string());
+                            real_command_parts.push(cmd.clone());
+                            should_run_real_command = true;
+                        } else if uses.contains("node") || uses.contains("npm") {
+                            // Node.js command pattern
+                            if cmd == "npm" || cmd == "yarn" || cmd == "pnpm" {
                                 real_command_parts.push(cmd.clone());
-                                should_run_real_command = true;
-                            } else if uses.contains("node") || uses.contains("npm") {
-                                // Node.js command pattern
-                                if cmd == "npm" || cmd == "yarn" || cmd == "pnpm" {
-                                    real_command_parts.push(cmd.clone());
-                                } else {
-                                    real_command_parts.push("npm".to_string());
-                                    real_command_parts.push("run".to_string());
-                                    real_command_parts.push(cmd.clone());
-                                }
-                                should_run_real_command = true;
-                            } else if uses.contains("python") || uses.contains("pip") {
-                                // Python command pattern
-                                if cmd == "pip" {
-                                    real_command_parts.push("pip".to_string());
-                                } else {
-                                    real_command_parts.push("python".to_string());
-                                    real_command_parts.push("-m".to_string());
-                                    real_command_parts.push(cmd.clone());
-                                }
-                                should_run_real_command = true;
                             } else {
-                                // Generic command - try to execute directly if available
+                                real_command_parts.push("npm".to_string());
+                                real_command_parts.push("run".to_string());
                                 real_command_parts.push(cmd.clone());
-                                should_run_real_command = true;
                             }
+                            should_run_real_command = true;
+                        } else if uses.contains("python") || uses.contains("pip") {
+                            // Python command pattern
+                            if cmd == "pip" {
+                                real_command_parts.push("pip".to_string());
+                            } else {
+                                real_command_parts.push("python".to_string());
+                                real_command_parts.push("-m".to_string());
+                                real_command_parts.push(cmd.clone());
+                            }
+                            should_run_real_command = true;
+                        } else {
+                            // Generic command - try to execute directly if available
+                            real_command_parts.push(cmd.clone());
+                            should_run_real_command = true;
+                        }
 
-                            // Add any arguments if specified
-                            if let Some(args) = with_params.get("args") {
-                                if !args.is_empty() {
-                                    // Resolve GitHub-style variables in args
-                                    let resolved_args = if args.contains("${{") {
-                                        logging::info(&format!(
-                                            "🔄 Resolving workflow variables in: {}",
-                                            args
-                                        ));
-
-                                        // Handle common matrix variables
-                                        let mut resolved = args.replace("${{ matrix.target }}", "");
-                                        resolved = resolved.replace("${{ matrix.os }}", "");
-
-                                        // Handle any remaining ${{ variables }} by removing them
-                                        let re_pattern =
-                                            regex::Regex::new(r"\$\{\{\s*([^}]+)\s*\}\}")
-                                                .unwrap_or_else(|_| {
-                                                    logging::error(
-                                                        "Failed to create regex pattern",
-                                                    );
-                                                    regex::Regex::new(r"\$\{\{.*?\}\}").unwrap()
-                                                });
+                        // Add any arguments if specified
+                        if let Some(args) = with_params.get("args") {
+                            if !args.is_empty() {
+                                // Resolve GitHub-style variables in args
+                                let resolved_args = if args.contains("${{") {
+                                    logging::info(&format!(
+                                        "🔄 Resolving workflow variables in: {}",
+                                        args
+                                    ));
+
+                                    // Handle common matrix variables
+                                    let mut resolved = args.replace("${{ matrix.target }}", "");
+                                    resolved = resolved.replace("${{ matrix.os }}", "");
+
+                                    // Handle any remaining ${{ variables }} by removing them
+                                    let re_pattern = regex::Regex::new(r"\$\{\{\s*([^}]+)\s*\}\}")
+                                        .unwrap_or_else(|_| {
+                                            logging::error("Failed to create regex pattern");
+                                            regex::Regex::new(r"\$\{\{.*?\}\}").unwrap()
+                                        });
 
-                                        let resolved =
-                                            re_pattern.replace_all(&resolved, "").to_string();
-                                        logging::info(&format!("🔄 Resolved to: {}", resolved));
+                                    let resolved =
+                                        re_pattern.replace_all(&resolved, "").to_string();
+                                    logging::info(&format!("🔄 Resolved to: {}", resolved));
 
-                                        resolved.trim().to_string()
-                                    } else {
-                                        args.clone()
-                                    };
+                                    resolved.trim().to_string()
+                                } else {
+                                    args.clone()
+                                };
 
-                                    // Only add if we have something left after resolving variables
-                                    if !resolved_args.is_empty() {
-                                        real_command_parts.push(resolved_args);
-                                    }
+                                // Only add if we have something left after resolving variables
+                                if !resolved_args.is_empty() {
+                                    real_command_parts.push(resolved_args);
                                 }
                             }
                         }
@@ -1314,10 +3948,8 @@ async fn execute_step(ctx: StepExecutionContext<'_>) -> Result<StepResult, Execu
                 }
 
                 // Convert 'with' parameters to environment variables
-                if let Some(with_params) = &ctx.step.with {
-                    for (key, value) in with_params {
-                        step_env.insert(format!("INPUT_{}", key.to_uppercase()), value.clone());
-                    }
+                for (key, value) in &ctx.step.with {
+                    step_env.insert(format!("INPUT_{}", key.to_uppercase()), value.clone());
                 }
 
                 // Convert environment HashMap to Vec<(&str, &str)> for container runtime
@@ -1326,23 +3958,10 @@ async fn execute_step(ctx: StepExecutionContext<'_>) -> Result<StepResult, Execu
                     .map(|(k, v)| (k.as_str(), v.as_str()))
                     .collect();
 
-                // Define the standard workspace path inside the container
-                let container_workspace = Path::new("/github/workspace");
-
-                // Set up volume mapping from host working dir to container workspace
-                let volumes: Vec<(&Path, &Path)> = vec![(ctx.working_dir, container_workspace)];
-
-                let output = ctx
-                    .runtime
-                    .run_container(
-                        ctx.runner_image,
-                        &cmd.to_vec(),
-                        &env_vars,
-                        container_workspace,
-                        &volumes,
-                    )
-                    .await
-                    .map_err(|e| ExecutionError::Runtime(format!("{}", e)))?;
+                let output =
+                    run_step_command(&ctx, &cmd, &env_vars, Path::new(CONTAINER_WORKSPACE))
+                        .await
+                        .map_err(|e| ExecutionError::Runtime(format!("{}", e)))?;
 
                 // Check if this was called from 'run' branch - don't try to hide these outputs
                 if output.exit_code == 0 {
@@ -1355,9 +3974,9 @@ async fn execute_step(ctx: StepExecutionContext<'_>) -> Result<StepResult, Execu
                             format!("Would execute GitHub action: {}\n", uses);
 
                         // Add information about the action inputs if available
-                        if let Some(with_params) = &ctx.step.with {
+                        if !ctx.step.with.is_empty() {
                             detailed_output.push_str("\nAction inputs:\n");
-                            for (key, value) in with_params {
+                            for (key, value) in &ctx.step.with {
                                 detailed_output.push_str(&format!("  {}: {}\n", key, value));
                             }
                         }
@@ -1410,6 +4029,11 @@ async fn execute_step(ctx: StepExecutionContext<'_>) -> Result<StepResult, Execu
                             name: step_name,
                             status: StepStatus::Failure,
                             output: format!("{}\n{}", output_text, error_details),
+                            output_file: None,
+                            exit_code: Some(output.exit_code),
+                            duration: Duration::ZERO,
+                            overhead: Overhead::default(),
+                            annotations: Vec::new(),
                         });
                     }
 
@@ -1426,6 +4050,11 @@ async fn execute_step(ctx: StepExecutionContext<'_>) -> Result<StepResult, Execu
 {}",
                             output.exit_code, output.stdout, output.stderr
                         ),
+                        output_file: None,
+                        exit_code: Some(output.exit_code),
+                        duration: Duration::ZERO,
+                        overhead: Overhead::default(),
+                        annotations: Vec::new(),
                     }
                 } else {
                     StepResult {
@@ -1435,249 +4064,1107 @@ async fn execute_step(ctx: StepExecutionContext<'_>) -> Result<StepResult, Execu
                             "Exit code: {}\n{}\n{}",
                             output.exit_code, output.stdout, output.stderr
                         ),
+                        output_file: None,
+                        exit_code: Some(output.exit_code),
+                        duration: Duration::ZERO,
+                        overhead: Overhead::default(),
+                        annotations: Vec::new(),
+                    }
+                }
+            }
+        }
+    } else if let Some(run) = &ctx.step.run {
+        // Run step
+        let mut output = String::new();
+        let mut status = StepStatus::Success;
+        let mut error_details = None;
+        let mut exit_code = None;
+
+        // Check if this is a cargo command
+        let is_cargo_cmd = run.trim().starts_with("cargo");
+
+        let prepared = run_working_dir(&ctx, &step_env)
+            .and_then(|dir| script_command(&ctx, run).map(|command| (dir, command)));
+        let (working_dir, command) = match prepared {
+            Ok(prepared) => prepared,
+            Err(e) => {
+                return Ok(StepResult {
+                    name: step_name,
+                    status: StepStatus::Failure,
+                    output: e,
+                    output_file: None,
+                    exit_code: None,
+                    duration: Duration::ZERO,
+                    overhead: Overhead::default(),
+                    annotations: Vec::new(),
+                })
+            }
+        };
+        let cmd_parts: Vec<&str> = command.iter().map(String::as_str).collect();
+
+        // Convert environment variables to the required format
+        let env_vars: Vec<(&str, &str)> = step_env
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect();
+
+        // Execute the command
+        match run_step_command(&ctx, &cmd_parts, &env_vars, &working_dir).await {
+            Ok(container_output) => {
+                exit_code = Some(container_output.exit_code);
+                // Add command details to output
+                output.push_str(&format!("Command: {}\n\n", run));
+
+                if !container_output.stdout.is_empty() {
+                    output.push_str("Standard Output:\n");
+                    output.push_str(&container_output.stdout);
+                    output.push('\n');
+                }
+
+                if !container_output.stderr.is_empty() {
+                    output.push_str("Standard Error:\n");
+                    output.push_str(&container_output.stderr);
+                    output.push('\n');
+                }
+
+                if container_output.exit_code != 0 {
+                    status = StepStatus::Failure;
+
+                    // For cargo commands, add more detailed error information
+                    if is_cargo_cmd {
+                        let mut error_msg = String::new();
+                        error_msg.push_str(&format!(
+                            "\nCargo command failed with exit code {}\n",
+                            container_output.exit_code
+                        ));
+                        error_msg.push_str("Common causes for cargo command failures:\n");
+
+                        if run.contains("fmt") {
+                            error_msg.push_str(
+                                "- Code formatting issues. Run 'cargo fmt' locally to fix.\n",
+                            );
+                        } else if run.contains("clippy") {
+                            error_msg.push_str("- Linter warnings treated as errors. Run 'cargo clippy' locally to see details.\n");
+                        } else if run.contains("test") {
+                            error_msg.push_str("- Test failures. Run 'cargo test' locally to see which tests failed.\n");
+                        } else if run.contains("build") {
+                            error_msg.push_str(
+                                "- Compilation errors. Check the error messages above.\n",
+                            );
+                        }
+
+                        error_details = Some(error_msg);
                     }
                 }
             }
+            Err(e) => {
+                status = StepStatus::Failure;
+                exit_code = failed_exit_code(&e.to_string());
+                output.push_str(&format!("Error executing command: {}\n", e));
+            }
+        }
+
+        // If there are error details, append them to the output
+        if let Some(details) = error_details {
+            output.push_str(&details);
+        }
+
+        StepResult {
+            name: step_name,
+            status,
+            output,
+            output_file: None,
+            exit_code,
+            duration: Duration::ZERO,
+            overhead: Overhead::default(),
+            annotations: Vec::new(),
+        }
+    } else {
+        return Ok(StepResult {
+            name: step_name,
+            status: StepStatus::Skipped,
+            output: "Step has neither 'uses' nor 'run'".to_string(),
+            output_file: None,
+            exit_code: None,
+            duration: Duration::ZERO,
+            overhead: Overhead::default(),
+            annotations: Vec::new(),
+        });
+    };
+
+    Ok(step_result)
+}
+
+/// The directory a `run` step starts in: its `working-directory` inside
+/// the workspace, which has to exist, or the workspace itself
+fn run_working_dir(
+    ctx: &StepExecutionContext<'_>,
+    step_env: &HashMap<String, String>,
+) -> Result<PathBuf, String> {
+    let Some(dir) = ctx.step.working_directory.as_deref() else {
+        return Ok(PathBuf::from(CONTAINER_WORKSPACE));
+    };
+    // Emulated steps run in whichever workspace GITHUB_WORKSPACE names
+    let in_container = runs_in_container(ctx.job_env);
+    let host_workspace = match step_env.get("GITHUB_WORKSPACE") {
+        Some(workspace) if !in_container => PathBuf::from(workspace),
+        _ => ctx.working_dir.to_path_buf(),
+    };
+    let workspace = if in_container {
+        PathBuf::from(CONTAINER_WORKSPACE)
+    } else {
+        host_workspace.clone()
+    };
+
+    let resolved = environment::resolve_working_directory(&workspace, dir)?;
+    let on_host = match resolved.strip_prefix(&workspace) {
+        Ok(relative) => host_workspace.join(relative),
+        // Absolute directories of a container are only there
+        Err(_) if in_container => return Ok(resolved),
+        Err(_) => resolved.clone(),
+    };
+    if !on_host.is_dir() {
+        return Err(format!("Working directory '{}' doesn't exist", dir));
+    }
+    Ok(resolved)
+}
+
+/// Write a `run` step's script where its shell can read it, and return the
+/// command running it
+fn script_command(ctx: &StepExecutionContext<'_>, run: &str) -> Result<Vec<String>, String> {
+    let shell = Shell::parse(ctx.step.shell.as_deref())?;
+    let script = ctx
+        .file_commands_dir
+        .join(format!("step_{}.{}", ctx.step_idx, shell.extension()));
+    fs::write(&script, shell.script(run))
+        .map_err(|e| format!("Failed to write the step's script: {}", e))?;
+
+    let in_container = runs_in_container(ctx.job_env);
+    let script = if in_container {
+        container_file_commands_path(ctx, &script)
+    } else {
+        script
+    };
+    let command = shell.command(
+        &script.to_string_lossy(),
+        in_container,
+        which::which("bash").is_ok(),
+    )?;
+
+    // Put $GITHUB_PATH directories in front of the container's own PATH
+    let path_file = ctx.runner_dirs.path_file();
+    if !in_container || path_file.is_empty() {
+        return Ok(command);
+    }
+    let prefix = ["sh", "-c", "PATH=\"$0:$PATH\" exec \"$@\""].map(String::from);
+    Ok(prefix
+        .into_iter()
+        .chain(Some(path_file.join(":")))
+        .chain(command)
+        .collect())
+}
+
+/// The exit code in the error emulation mode returns for a command that
+/// exited with one other than 0
+fn failed_exit_code(error: &str) -> Option<i32> {
+    let (_, rest) = error.split_once("Command failed with exit code: ")?;
+    rest.lines().next()?.trim().parse().ok()
+}
+
+/// Run a step's command in `working_dir` of the job's container, or of a
+/// fresh container of the runner image when the job has none
+async fn run_step_command(
+    ctx: &StepExecutionContext<'_>,
+    cmd: &[&str],
+    env_vars: &[(&str, &str)],
+    working_dir: &Path,
+) -> Result<ContainerOutput, ContainerError> {
+    let output = match ctx.job_container {
+        Some(container) => {
+            ctx.runtime
+                .exec_in_container(container.id, cmd, env_vars, working_dir)
+                .await
+        }
+        None => {
+            ctx.runtime
+                .run_container(
+                    ctx.runner_image,
+                    cmd,
+                    env_vars,
+                    working_dir,
+                    &job_volumes(ctx),
+                )
+                .await
+        }
+    }?;
+    ctx.add_overhead(&output.timings);
+    if ctx.debug_shell && output.exit_code != 0 {
+        open_debug_shell(ctx, env_vars, working_dir).await;
+    }
+    Ok(output)
+}
+
+/// Only one shell at a time can have the terminal
+static DEBUG_SHELL: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// Open a shell where a failed step ran, in its working directory with its
+/// variables, and wait for the user to leave it. Without a job container the
+/// shell gets a fresh container of the runner image, which sees the same
+/// workspace and runner directories as the step did.
+async fn open_debug_shell(
+    ctx: &StepExecutionContext<'_>,
+    env_vars: &[(&str, &str)],
+    working_dir: &Path,
+) {
+    let _terminal = DEBUG_SHELL.lock().await;
+    let step_name = ctx.step.name.as_deref().unwrap_or("run");
+
+    let shell_container = match ctx.job_container {
+        Some(_) => None,
+        None => match shell_container(ctx.runtime, ctx.runner_image, &job_volumes(ctx)).await {
+            Ok(id) => Some(id),
+            Err(e) => {
+                logging::warning(&format!(
+                    "Can't open a shell for step '{}': {}",
+                    step_name, e
+                ));
+                return;
+            }
+        },
+    };
+    let Some(id) = ctx
+        .job_container
+        .as_ref()
+        .map(|container| container.id)
+        .or(shell_container.as_deref())
+    else {
+        return;
+    };
+
+    logging::warning(&format!(
+        "Step '{}' failed. Opening a shell in its container at {}; exit it to go on with the run",
+        step_name,
+        working_dir.display()
+    ));
+    if let Err(e) = ctx.runtime.open_shell(id, env_vars, working_dir).await {
+        logging::warning(&format!("Failed to open a shell: {}", e));
+    }
+    stop_job_services(ctx.runtime, &shell_container).await;
+}
+
+/// Start a container of `image` that stays up for shells, with `mounts`
+async fn shell_container(
+    runtime: &dyn ContainerRuntime,
+    image: &str,
+    mounts: &[(&Path, &Path)],
+) -> Result<String, String> {
+    let container = BackgroundContainer {
+        image: image.to_string(),
+        volumes: mounts
+            .iter()
+            .map(|(host, target)| {
+                format!("{}:{}", host.to_string_lossy(), target.to_string_lossy())
+            })
+            .collect(),
+        ..Default::default()
+    };
+    match runtime.start_job_container(&container).await {
+        Ok(Some(started)) => Ok(started.id),
+        Ok(None) => Err("shells need Docker or Podman mode".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Where a file in the step's file command directory is seen from its
+/// container. A job container has the job's directory mounted, so nested
+/// composite directories keep their relative path.
+fn container_file_commands_path(ctx: &StepExecutionContext<'_>, file: &Path) -> PathBuf {
+    let relative = match ctx.job_container {
+        Some(container) => file
+            .strip_prefix(container.file_commands_dir)
+            .unwrap_or(file)
+            .to_path_buf(),
+        None => PathBuf::from(file.file_name().unwrap_or_default()),
+    };
+    Path::new(CONTAINER_FILE_COMMANDS_DIR).join(relative)
+}
+
+/// Host directories mounted into every step container of a job
+fn job_volumes<'b>(ctx: &'b StepExecutionContext<'_>) -> Vec<(&'b Path, &'b Path)> {
+    job_volume_paths(ctx.working_dir, ctx.file_commands_dir, ctx.runner_dirs)
+}
+
+fn job_volume_paths<'b>(
+    working_dir: &'b Path,
+    file_commands_dir: &'b Path,
+    runner_dirs: &'b RunnerDirs,
+) -> Vec<(&'b Path, &'b Path)> {
+    let mut volumes = vec![
+        (working_dir, Path::new(CONTAINER_WORKSPACE)),
+        (file_commands_dir, Path::new(CONTAINER_FILE_COMMANDS_DIR)),
+        (runner_dirs.temp(), Path::new(CONTAINER_RUNNER_TEMP)),
+        (runner_dirs.tool_cache(), Path::new(CONTAINER_TOOL_CACHE)),
+    ];
+    volumes.extend(
+        runner_dirs
+            .cache_volumes()
+            .iter()
+            .map(|(name, target)| (name.as_path(), target.as_path())),
+    );
+    volumes
+}
+
+/// Mount the tool cache volumes of a job that runs in containers, creating
+/// them on its first run. Without them the job runs with empty caches.
+async fn mount_tool_caches(
+    runtime: &dyn ContainerRuntime,
+    runner_dirs: &mut RunnerDirs,
+    job_env: &mut HashMap<String, String>,
+    workflow: &Workflow,
+    job_name: &str,
+) {
+    if !runs_in_container(job_env) {
+        return;
+    }
+    let workflow_name = workflow.name.as_deref().unwrap_or("workflow");
+    let mut mounted = Vec::new();
+    for volume in tool_cache::job_volumes(workflow_name, job_name) {
+        if let Err(e) = runtime.create_volume(&volume.name, &volume.labels).await {
+            logging::warning(&format!(
+                "Job '{}' runs without its tool caches: {}",
+                job_name, e
+            ));
+            return;
+        }
+        mounted.push((PathBuf::from(volume.name), volume.target));
+    }
+    // The job's own settings win
+    for (name, path) in tool_cache::cache_env() {
+        job_env
+            .entry(name.to_string())
+            .or_insert_with(|| path.to_string());
+    }
+    runner_dirs.mount_cache_volumes(mounted);
+}
+
+/// Create the job's runner directories and expose them in its environment.
+/// Emulated jobs given a `sandbox` workspace run in it instead of the project.
+fn create_runner_dirs(
+    job_env: &mut HashMap<String, String>,
+    sandbox: Option<&Path>,
+) -> Result<RunnerDirs, ExecutionError> {
+    let mut runner_dirs = RunnerDirs::create(environment::tool_cache_dir()).map_err(|e| {
+        ExecutionError::Execution(format!("Failed to create runner directories: {}", e))
+    })?;
+    let in_container = runs_in_container(job_env);
+    runner_dirs.apply(job_env, in_container);
+    if !in_container {
+        runner_dirs.add_shims(job_env).map_err(|e| {
+            ExecutionError::Execution(format!("Failed to create the job's shim directory: {}", e))
+        })?;
+        if let Some(workspace) = sandbox {
+            RunnerDirs::sandbox(job_env, workspace);
+        }
+    }
+    Ok(runner_dirs)
+}
+
+fn runs_in_container(env: &HashMap<String, String>) -> bool {
+    env.get("WRKFLW_RUNTIME_MODE")
+        .is_some_and(|mode| mode == "docker" || mode == "podman")
+}
+
+/// Locate a JavaScript or container action and its metadata.
+///
+/// Local JavaScript actions run in either mode; container actions and
+/// remote actions need Docker, emulation keeps its lighter-weight stand-ins.
+fn runnable_action(
+    ctx: &StepExecutionContext<'_>,
+    action_info: &ActionInfo,
+    uses: &str,
+) -> Option<(std::path::PathBuf, ActionMetadata)> {
+    let in_container = runs_in_container(ctx.job_env);
+    let action_dir = if action_info.is_local {
+        Path::new(&action_info.repository).to_path_buf()
+    } else if action_info.is_docker {
+        // `uses: docker://image` runs the image directly
+        return in_container.then(|| {
+            (
+                ctx.working_dir.to_path_buf(),
+                ActionMetadata::for_image(uses),
+            )
+        });
+    } else if !in_container {
+        return None;
+    } else {
+        let action_ref = ActionRef::parse(uses)?;
+        match actions::fetch_action(
+            &action_ref,
+            &actions::actions_base_url(),
+            &actions::actions_root(),
+        ) {
+            Ok(dir) => dir,
+            Err(e) => {
+                logging::warning(&format!("{}; falling back to emulation", e));
+                return None;
+            }
         }
-    } else if let Some(run) = &ctx.step.run {
-        // Run step
-        let mut output = String::new();
-        let mut status = StepStatus::Success;
-        let mut error_details = None;
+    };
 
-        // Check if this is a cargo command
-        let is_cargo_cmd = run.trim().starts_with("cargo");
+    let metadata = actions::load_action_metadata(&action_dir).ok()?;
+    let runnable = metadata.is_node() || (metadata.is_docker() && in_container);
+    runnable.then_some((action_dir, metadata))
+}
+
+/// Run a container action: build or pull its image, then run it with the
+/// action's (or the step's) entrypoint and args
+async fn execute_docker_action(
+    ctx: &StepExecutionContext<'_>,
+    action_dir: &Path,
+    metadata: &ActionMetadata,
+    step_env: &HashMap<String, String>,
+    step_name: String,
+) -> Result<StepResult, ExecutionError> {
+    let image = match metadata.image.as_deref() {
+        Some(image) if image.starts_with("docker://") => {
+            let image = image.trim_start_matches("docker://");
+            ctx.pull_image(image).await;
+            image.to_string()
+        }
+        Some(dockerfile) => {
+            let dockerfile = action_dir.join(dockerfile);
+            let (tag, replaces) = action_image_tag(ctx.step.uses.as_deref(), &dockerfile);
+            ctx.runtime
+                .build_image_once(&dockerfile, &tag, &replaces)
+                .await
+                .map_err(|e| {
+                    ExecutionError::Runtime(format!("Failed to build action image: {}", e))
+                })?;
+            tag
+        }
+        None => {
+            return Err(ExecutionError::Execution(format!(
+                "Container action in {} has no 'runs.image'",
+                action_dir.display()
+            )))
+        }
+    };
 
-        // Convert command string to array of string slices
-        let cmd_parts: Vec<&str> = run.split_whitespace().collect();
+    let with = &ctx.step.with;
+    let inputs = metadata.input_env(with);
 
-        // Convert environment variables to the required format
-        let env_vars: Vec<(&str, &str)> = step_env
+    // args and env in action.yml may refer to ${{ inputs.* }}
+    let mut expression_ctx = EvaluationContext::new();
+    let mut input_values = serde_json::Map::new();
+    for (name, default) in &metadata.inputs {
+        if let Some(value) = with.get(name).or(default.as_ref()) {
+            input_values.insert(name.clone(), serde_json::Value::String(value.clone()));
+        }
+    }
+    expression_ctx.set("inputs", serde_json::Value::Object(input_values));
+    let interpolate = |value: &str| {
+        expressions::interpolate(value, &expression_ctx)
+            .map_err(|e| ExecutionError::Execution(format!("Invalid expression in action: {}", e)))
+    };
+
+    let args = match with.get("args") {
+        Some(args) => actions::split_args(args),
+        None => metadata
+            .args
             .iter()
-            .map(|(k, v)| (k.as_str(), v.as_str()))
-            .collect();
+            .map(|arg| interpolate(arg))
+            .collect::<Result<Vec<_>, _>>()?,
+    };
+    let entrypoint = with.get("entrypoint").or(metadata.entrypoint.as_ref());
 
-        // Define the standard workspace path inside the container
-        let container_workspace = Path::new("/github/workspace");
+    let mut env = step_env.clone();
+    env.extend(inputs);
+    for (key, value) in &metadata.env {
+        env.insert(key.clone(), interpolate(value)?);
+    }
+    let env_vars: Vec<(&str, &str)> = env.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+    let args: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+    let volumes = job_volumes(ctx);
+    let working_dir = Path::new(CONTAINER_WORKSPACE);
 
-        // Set up volume mapping from host working dir to container workspace
-        let volumes: Vec<(&Path, &Path)> = vec![(ctx.working_dir, container_workspace)];
+    if ctx.verbose {
+        logging::info(&format!(
+            "  Running container action {} with args {:?}",
+            image, args
+        ));
+    }
+    let output = match entrypoint {
+        Some(entrypoint) => {
+            ctx.runtime
+                .run_container_with_entrypoint(
+                    &image,
+                    &[entrypoint.as_str()],
+                    &args,
+                    &env_vars,
+                    working_dir,
+                    &volumes,
+                )
+                .await
+        }
+        None => {
+            ctx.runtime
+                .run_container(&image, &args, &env_vars, working_dir, &volumes)
+                .await
+        }
+    }
+    .map_err(|e| ExecutionError::Runtime(format!("{}", e)))?;
+    ctx.add_overhead(&output.timings);
 
-        // Execute the command
-        match ctx
-            .runtime
-            .run_container(
-                ctx.runner_image,
-                &cmd_parts,
-                &env_vars,
-                container_workspace,
-                &volumes,
-            )
-            .await
-        {
-            Ok(container_output) => {
-                // Add command details to output
-                output.push_str(&format!("Command: {}\n\n", run));
+    Ok(StepResult {
+        name: step_name,
+        status: if output.exit_code == 0 {
+            StepStatus::Success
+        } else {
+            StepStatus::Failure
+        },
+        output: format!("{}\n{}", output.stdout, output.stderr),
+        output_file: None,
+        exit_code: Some(output.exit_code),
+        duration: Duration::ZERO,
+        overhead: Overhead::default(),
+        annotations: Vec::new(),
+    })
+}
 
-                if !container_output.stdout.is_empty() {
-                    output.push_str("Standard Output:\n");
-                    output.push_str(&container_output.stdout);
-                    output.push('\n');
-                }
+/// Tag of a Docker action's image, by the action and a hash of its ref and
+/// Dockerfile so that runs reuse it until either changes, and the start of
+/// the tags of the action's other versions, which it replaces
+fn action_image_tag(action: Option<&str>, dockerfile: &Path) -> (String, String) {
+    let action = action.unwrap_or("local");
+    let mut hash = utils::hash::Fnv::new();
+    hash.write(action.as_bytes());
+    hash.write(&std::fs::read(dockerfile).unwrap_or_default());
+    let name = action.split('@').next().unwrap_or(action);
+    // Tags can't start with a dash
+    let name = match crate::tool_cache::slug(name) {
+        slug if slug.is_empty() => "action".to_string(),
+        slug => slug,
+    };
+    let replaces = format!("wrkflw-action:{}-", name);
+    (format!("{}{:016x}", replaces, hash.finish()), replaces)
+}
 
-                if !container_output.stderr.is_empty() {
-                    output.push_str("Standard Error:\n");
-                    output.push_str(&container_output.stderr);
-                    output.push('\n');
-                }
+/// Run a JavaScript action's entrypoint with node
+async fn execute_node_action(
+    ctx: &StepExecutionContext<'_>,
+    action_dir: &Path,
+    metadata: &ActionMetadata,
+    step_env: &HashMap<String, String>,
+    step_name: String,
+) -> Result<StepResult, ExecutionError> {
+    let main = metadata.main.as_deref().ok_or_else(|| {
+        ExecutionError::Execution(format!(
+            "JavaScript action in {} has no 'runs.main'",
+            action_dir.display()
+        ))
+    })?;
+    let action_dir = action_dir.canonicalize().map_err(|e| {
+        ExecutionError::Execution(format!(
+            "Failed to resolve action directory {}: {}",
+            action_dir.display(),
+            e
+        ))
+    })?;
 
-                if container_output.exit_code != 0 {
-                    status = StepStatus::Failure;
+    let in_container = runs_in_container(ctx.job_env);
+    let (action_path, working_dir, image) = if in_container {
+        let image = metadata.node_image();
+        ctx.pull_image(image).await;
+        (
+            Path::new(actions::CONTAINER_ACTION_DIR).to_path_buf(),
+            Path::new(CONTAINER_WORKSPACE),
+            image,
+        )
+    } else {
+        (action_dir.clone(), ctx.working_dir, ctx.runner_image)
+    };
 
-                    // For cargo commands, add more detailed error information
-                    if is_cargo_cmd {
-                        let mut error_msg = String::new();
-                        error_msg.push_str(&format!(
-                            "\nCargo command failed with exit code {}\n",
-                            container_output.exit_code
-                        ));
-                        error_msg.push_str("Common causes for cargo command failures:\n");
+    let mut env = step_env.clone();
+    env.extend(metadata.input_env(&ctx.step.with));
+    env.insert(
+        "GITHUB_ACTION_PATH".to_string(),
+        action_path.to_string_lossy().to_string(),
+    );
+    let env_vars: Vec<(&str, &str)> = env.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
 
-                        if run.contains("fmt") {
-                            error_msg.push_str(
-                                "- Code formatting issues. Run 'cargo fmt' locally to fix.\n",
-                            );
-                        } else if run.contains("clippy") {
-                            error_msg.push_str("- Linter warnings treated as errors. Run 'cargo clippy' locally to see details.\n");
-                        } else if run.contains("test") {
-                            error_msg.push_str("- Test failures. Run 'cargo test' locally to see which tests failed.\n");
-                        } else if run.contains("build") {
-                            error_msg.push_str(
-                                "- Compilation errors. Check the error messages above.\n",
-                            );
-                        }
+    let script = action_path.join(main).to_string_lossy().to_string();
+    let mut volumes = job_volumes(ctx);
+    volumes.push((&action_dir, Path::new(actions::CONTAINER_ACTION_DIR)));
 
-                        error_details = Some(error_msg);
-                    }
+    if ctx.verbose {
+        logging::info(&format!(
+            "  Running {} action: node {}",
+            metadata.using, script
+        ));
+    }
+    let output = ctx
+        .runtime
+        .run_container(image, &["node", &script], &env_vars, working_dir, &volumes)
+        .await
+        .map_err(|e| ExecutionError::Runtime(format!("{}", e)))?;
+    ctx.add_overhead(&output.timings);
+
+    Ok(StepResult {
+        name: step_name,
+        status: if output.exit_code == 0 {
+            StepStatus::Success
+        } else {
+            StepStatus::Failure
+        },
+        output: format!("{}\n{}", output.stdout, output.stderr),
+        output_file: None,
+        exit_code: Some(output.exit_code),
+        duration: Duration::ZERO,
+        overhead: Overhead::default(),
+        annotations: Vec::new(),
+    })
+}
+
+/// Act on the workflow commands a step printed: attach and log its
+/// annotations, add legacy `::set-output` values to its $GITHUB_OUTPUT and
+/// hide `::add-mask::` values from its output and that of every later step
+fn apply_workflow_commands(
+    result: &mut StepResult,
+    masks: &mut Vec<String>,
+    file_commands_dir: &Path,
+    step_idx: usize,
+) {
+    let mut legacy_outputs = String::new();
+    for command in actions::parse_workflow_commands(&result.output) {
+        match command {
+            WorkflowCommand::Annotation(annotation) => {
+                let line = mask(&format!("{}: {}", result.name, annotation), masks);
+                match annotation.level {
+                    AnnotationLevel::Error => logging::error(&line),
+                    AnnotationLevel::Warning => logging::warning(&line),
+                    AnnotationLevel::Notice => logging::info(&line),
                 }
+                result.annotations.push(annotation);
             }
-            Err(e) => {
-                status = StepStatus::Failure;
-                output.push_str(&format!("Error executing command: {}\n", e));
+            WorkflowCommand::SetOutput { name, value } => {
+                legacy_outputs.push_str(&format!("{}={}\n", name, value));
             }
+            WorkflowCommand::AddMask(value) => masks.push(value),
+        }
+    }
+    if !legacy_outputs.is_empty() {
+        let output_file = environment::step_output_file(file_commands_dir, step_idx);
+        let mut content = fs::read_to_string(&output_file).unwrap_or_default();
+        content.push_str(&legacy_outputs);
+        if let Err(e) = fs::write(&output_file, content) {
+            logging::warning(&format!("Failed to record step outputs: {}", e));
         }
+    }
 
-        // If there are error details, append them to the output
-        if let Some(details) = error_details {
-            output.push_str(&details);
+    result.output = mask(&result.output, masks);
+    for annotation in &mut result.annotations {
+        annotation.message = mask(&annotation.message, masks);
+        annotation.title = annotation.title.as_deref().map(|title| mask(title, masks));
+    }
+}
+
+/// Replace every masked value in a log with `***`, longest values first so
+/// that a value containing another is hidden whole
+fn mask(text: &str, masks: &[String]) -> String {
+    let mut masks: Vec<&String> = masks.iter().collect();
+    masks.sort_by_key(|value| std::cmp::Reverse(value.len()));
+    masks.into_iter().fold(text.to_string(), |text, value| {
+        text.replace(value.as_str(), "***")
+    })
+}
+
+fn artifact_workspace(ctx: &StepExecutionContext<'_>) -> std::path::PathBuf {
+    host_workspace(ctx.job_env, ctx.working_dir)
+}
+
+/// The directory on the host that the job's workspace lives in
+fn host_workspace(job_env: &HashMap<String, String>, job_dir: &Path) -> std::path::PathBuf {
+    let emulated = job_env
+        .get("WRKFLW_RUNTIME_MODE")
+        .is_some_and(|mode| mode == "emulation" || mode == "bubblewrap");
+
+    match job_env.get("GITHUB_WORKSPACE") {
+        Some(workspace) if emulated && Path::new(workspace).exists() => workspace.into(),
+        _ => job_dir.to_path_buf(),
+    }
+}
+
+/// Warnings for `actions/cache` steps of a finished job whose paths add up
+/// to more than the cache size limit
+fn oversized_caches(
+    job: &Job,
+    job_env: &HashMap<String, String>,
+    job_dir: &Path,
+    artifact_store: &ArtifactStore,
+) -> Vec<String> {
+    let limit = artifact_store.size_limits().cache_bytes;
+    let workspace = host_workspace(job_env, job_dir);
+    let home = dirs::home_dir().filter(|_| !runs_in_container(job_env));
+
+    let mut warnings = Vec::new();
+    for (idx, step) in job.steps.iter().enumerate() {
+        let saves_cache = step.uses.as_deref().is_some_and(|uses| {
+            uses.starts_with("actions/cache") && !uses.starts_with("actions/cache/restore")
+        });
+        let paths = step.with.get("path");
+        let Some(paths) = paths.filter(|_| saves_cache) else {
+            continue;
+        };
+
+        // `~` is only measurable when the job ran on this machine's home
+        let paths: String = paths
+            .lines()
+            .filter(|line| !line.contains("${{"))
+            .filter_map(|line| match line.trim().strip_prefix("~/") {
+                Some(rest) => home
+                    .as_ref()
+                    .map(|home| home.join(rest).to_string_lossy().to_string()),
+                None => Some(line.to_string()),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let size = artifacts::paths_size(&paths, &workspace);
+        if size > limit {
+            warnings.push(format!(
+                "Cache saved by '{}' is {}, above the {} warning threshold. Hosted CI may evict it or refuse to save it.",
+                step_display_name(step, idx),
+                artifacts::format_size(size),
+                artifacts::format_size(limit)
+            ));
         }
+    }
+    warnings
+}
 
-        StepResult {
-            name: step_name,
-            status,
-            output,
+/// Emulate a setup action like actions/setup-node with a toolchain found on
+/// this machine, keeping the variables it sets for the job's later steps
+fn setup_toolchain(
+    ctx: &StepExecutionContext<'_>,
+    handler: &dyn SetupAction,
+    shims: &Path,
+    step_name: String,
+) -> StepResult {
+    let workspace = artifact_workspace(ctx);
+    let setup = handler.setup(&SetupContext {
+        uses: ctx.step.uses.as_deref().unwrap_or_default(),
+        with: &ctx.step.with,
+        workspace: &workspace,
+        tool_cache: ctx.runner_dirs.tool_cache(),
+        shims,
+    });
+    let (status, output) = match setup {
+        Ok(outcome) => {
+            let mut output = outcome.report;
+            for (key, value) in outcome.env {
+                output.push(format!("Set {}={}", key, value));
+                ctx.runner_dirs.export(key, value);
+            }
+            (StepStatus::Success, output.join("\n"))
         }
-    } else {
-        return Ok(StepResult {
-            name: step_name,
-            status: StepStatus::Skipped,
-            output: "Step has neither 'uses' nor 'run'".to_string(),
+        Err(e) => (StepStatus::Failure, format!("Error: {}", e)),
+    };
+    StepResult {
+        name: step_name,
+        status,
+        output,
+        output_file: None,
+        exit_code: None,
+        duration: Duration::ZERO,
+        overhead: Overhead::default(),
+        annotations: Vec::new(),
+    }
+}
+
+/// Emulate actions/upload-artifact by copying files into the run's artifact store
+fn upload_artifact(ctx: &StepExecutionContext<'_>, step_name: String) -> StepResult {
+    let with = &ctx.step.with;
+    let name = with.get("name").map(|s| s.as_str()).unwrap_or("artifact");
+    let path = with.get("path").map(|s| s.as_str()).unwrap_or("");
+    let overwrite = with.get("overwrite").is_some_and(|v| v == "true");
+
+    let result = with
+        .get("if-no-files-found")
+        .map(|v| IfNoFilesFound::parse(v))
+        .unwrap_or(Ok(IfNoFilesFound::default()))
+        .and_then(|if_no_files_found| {
+            ctx.artifact_store.upload(
+                name,
+                path,
+                &artifact_workspace(ctx),
+                if_no_files_found,
+                overwrite,
+            )
         });
+
+    match result {
+        Ok(Some(info)) => StepResult {
+            name: step_name,
+            status: StepStatus::Success,
+            output: format!(
+                "Uploaded artifact '{}' ({} file(s), {} bytes) to run {}",
+                info.name,
+                info.files,
+                info.size,
+                ctx.artifact_store.run_id()
+            ),
+            output_file: None,
+            exit_code: None,
+            duration: Duration::ZERO,
+            overhead: Overhead::default(),
+            annotations: Vec::new(),
+        },
+        Ok(None) => StepResult {
+            name: step_name,
+            status: StepStatus::Success,
+            output: format!("No files found for artifact '{}', nothing uploaded", name),
+            output_file: None,
+            exit_code: None,
+            duration: Duration::ZERO,
+            overhead: Overhead::default(),
+            annotations: Vec::new(),
+        },
+        Err(e) => StepResult {
+            name: step_name,
+            status: StepStatus::Failure,
+            output: format!("Error: {}", e),
+            output_file: None,
+            exit_code: None,
+            duration: Duration::ZERO,
+            overhead: Overhead::default(),
+            annotations: Vec::new(),
+        },
+    }
+}
+
+/// Emulate actions/checkout: copy the project, or fetch the ref or
+/// repository the step asks for
+fn checkout_step(ctx: &StepExecutionContext<'_>, step_name: String) -> StepResult {
+    let result = CheckoutOptions::from_with(&ctx.step.with).and_then(|options| {
+        let project =
+            std::env::current_dir().map_err(|e| format!("Failed to get current dir: {}", e))?;
+        let started = Instant::now();
+        let done = checkout::checkout(
+            &options,
+            &project,
+            ctx.working_dir,
+            ctx.job_env.get("GITHUB_REPOSITORY").map(String::as_str),
+            &actions::actions_base_url(),
+        )?;
+        if let Checkout::Copied { dir, stats } = &done {
+            logging::debug(&format!(
+                "Copied {} files ({}) to {} in {:.2?}",
+                stats.files,
+                artifacts::format_size(stats.bytes),
+                dir.display(),
+                started.elapsed()
+            ));
+        }
+        Ok(done)
+    });
+
+    let (status, output) = match result {
+        Ok(Checkout::Copied { dir, .. }) => (
+            StepStatus::Success,
+            format!(
+                "Emulated checkout: Copied current directory to {}",
+                dir.display()
+            ),
+        ),
+        Ok(Checkout::Fetched {
+            dir,
+            repository,
+            git_ref,
+            commit,
+        }) => (
+            StepStatus::Success,
+            format!(
+                "Emulated checkout: Checked out {}@{} ({}) to {}",
+                repository,
+                git_ref,
+                &commit[..commit.len().min(12)],
+                dir.display()
+            ),
+        ),
+        Err(e) => (StepStatus::Failure, format!("Error: {}", e)),
     };
+    if ctx.verbose {
+        logging::info(&format!("  {}", output));
+    }
 
-    Ok(step_result)
+    StepResult {
+        name: step_name,
+        status,
+        output,
+        output_file: None,
+        exit_code: None,
+        duration: Duration::ZERO,
+        overhead: Overhead::default(),
+        annotations: Vec::new(),
+    }
 }
 
-fn copy_directory_contents(from: &Path, to: &Path) -> Result<(), ExecutionError> {
-    for entry in std::fs::read_dir(from)
-        .map_err(|e| ExecutionError::Execution(format!("Failed to read directory: {}", e)))?
+/// Emulate actions/download-artifact from the run's artifact store
+fn download_artifact(ctx: &StepExecutionContext<'_>, step_name: String) -> StepResult {
+    let with = &ctx.step.with;
+    let name = with
+        .get("name")
+        .map(|s| s.as_str())
+        .filter(|s| !s.is_empty());
+    let pattern = with
+        .get("pattern")
+        .map(|s| s.as_str())
+        .filter(|s| !s.is_empty());
+    let merge_multiple = with.get("merge-multiple").is_some_and(|v| v == "true");
+    let workspace = artifact_workspace(ctx);
+    let destination = with
+        .get("path")
+        .map(|p| artifacts::resolve_workspace_path(p, &workspace))
+        .unwrap_or_else(|| workspace.clone());
+
+    match ctx
+        .artifact_store
+        .download(name, pattern, merge_multiple, &destination)
     {
-        let entry =
-            entry.map_err(|e| ExecutionError::Execution(format!("Failed to read entry: {}", e)))?;
-        let path = entry.path();
-
-        // Skip hidden files/dirs and target directory for efficiency
-        let file_name = match path.file_name() {
-            Some(name) => name.to_string_lossy(),
-            None => {
-                return Err(ExecutionError::Execution(format!(
-                    "Failed to get file name from path: {:?}",
-                    path
-                )));
+        Ok(downloaded) => {
+            let names = downloaded
+                .iter()
+                .map(|a| a.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            StepResult {
+                name: step_name,
+                status: StepStatus::Success,
+                output: format!(
+                    "Downloaded {} artifact(s) to {}: {}",
+                    downloaded.len(),
+                    destination.display(),
+                    names
+                ),
+                output_file: None,
+                exit_code: None,
+                duration: Duration::ZERO,
+                overhead: Overhead::default(),
+                annotations: Vec::new(),
             }
-        };
-        if file_name.starts_with(".") || file_name == "target" {
-            continue;
         }
+        Err(e) => StepResult {
+            name: step_name,
+            status: StepStatus::Failure,
+            output: format!("Error: {}", e),
+            output_file: None,
+            exit_code: None,
+            duration: Duration::ZERO,
+            overhead: Overhead::default(),
+            annotations: Vec::new(),
+        },
+    }
+}
 
-        let dest_path = match path.file_name() {
-            Some(name) => to.join(name),
-            None => {
-                return Err(ExecutionError::Execution(format!(
-                    "Failed to get file name from path: {:?}",
-                    path
-                )));
-            }
-        };
-
-        if path.is_dir() {
-            std::fs::create_dir_all(&dest_path)
-                .map_err(|e| ExecutionError::Execution(format!("Failed to create dir: {}", e)))?;
+/// Where a job runs: the workspace the job keeps between runs, synced with
+/// the project, or with `--bind-workspace` the project directory itself. A
+/// fresh copy when another run holds the kept one.
+enum JobWorkspace {
+    Synced(workspace::PersistentWorkspace),
+    Copy(tempfile::TempDir),
+    Bound(std::path::PathBuf),
+}
 
-            // Recursively copy subdirectories
-            copy_directory_contents(&path, &dest_path)?;
-        } else {
-            std::fs::copy(&path, &dest_path)
-                .map_err(|e| ExecutionError::Execution(format!("Failed to copy file: {}", e)))?;
+impl JobWorkspace {
+    fn path(&self) -> &Path {
+        match self {
+            JobWorkspace::Synced(dir) => dir.path(),
+            JobWorkspace::Copy(dir) => dir.path(),
+            JobWorkspace::Bound(dir) => dir,
         }
     }
-
-    Ok(())
 }
 
-fn get_runner_image(runs_on: &str) -> String {
-    // Map GitHub runners to Docker images
-    match runs_on.trim() {
-        // ubuntu runners - micro images (minimal size)
-        "ubuntu-latest" => "node:16-buster-slim",
-        "ubuntu-22.04" => "node:16-bullseye-slim",
-        "ubuntu-20.04" => "node:16-buster-slim",
-        "ubuntu-18.04" => "node:16-buster-slim",
-
-        // ubuntu runners - medium images (with more tools)
-        "ubuntu-latest-medium" => "catthehacker/ubuntu:act-latest",
-        "ubuntu-22.04-medium" => "catthehacker/ubuntu:act-22.04",
-        "ubuntu-20.04-medium" => "catthehacker/ubuntu:act-20.04",
-        "ubuntu-18.04-medium" => "catthehacker/ubuntu:act-18.04",
-
-        // ubuntu runners - large images (with most tools)
-        "ubuntu-latest-large" => "catthehacker/ubuntu:full-latest",
-        "ubuntu-22.04-large" => "catthehacker/ubuntu:full-22.04",
-        "ubuntu-20.04-large" => "catthehacker/ubuntu:full-20.04",
-        "ubuntu-18.04-large" => "catthehacker/ubuntu:full-18.04",
-
-        // macOS runners - use a standard Rust image for compatibility
-        "macos-latest" => "rust:latest",
-        "macos-12" => "rust:latest",    // Monterey equivalent
-        "macos-11" => "rust:latest",    // Big Sur equivalent
-        "macos-10.15" => "rust:latest", // Catalina equivalent
-
-        // Windows runners - using servercore-based images
-        "windows-latest" => "mcr.microsoft.com/windows/servercore:ltsc2022",
-        "windows-2022" => "mcr.microsoft.com/windows/servercore:ltsc2022",
-        "windows-2019" => "mcr.microsoft.com/windows/servercore:ltsc2019",
-
-        // Language-specific runners
-        "python-latest" => "python:3.11-slim",
-        "python-3.11" => "python:3.11-slim",
-        "python-3.10" => "python:3.10-slim",
-        "python-3.9" => "python:3.9-slim",
-        "python-3.8" => "python:3.8-slim",
-
-        "node-latest" => "node:20-slim",
-        "node-20" => "node:20-slim",
-        "node-18" => "node:18-slim",
-        "node-16" => "node:16-slim",
-
-        "java-latest" => "eclipse-temurin:17-jdk",
-        "java-17" => "eclipse-temurin:17-jdk",
-        "java-11" => "eclipse-temurin:11-jdk",
-        "java-8" => "eclipse-temurin:8-jdk",
-
-        "go-latest" => "golang:1.21-slim",
-        "go-1.21" => "golang:1.21-slim",
-        "go-1.20" => "golang:1.20-slim",
-        "go-1.19" => "golang:1.19-slim",
-
-        "dotnet-latest" => "mcr.microsoft.com/dotnet/sdk:7.0",
-        "dotnet-7.0" => "mcr.microsoft.com/dotnet/sdk:7.0",
-        "dotnet-6.0" => "mcr.microsoft.com/dotnet/sdk:6.0",
-        "dotnet-5.0" => "mcr.microsoft.com/dotnet/sdk:5.0",
-
-        // Default case for other runners or custom strings
-        _ => {
-            // Check for platform prefixes and provide appropriate images
-            let runs_on_lower = runs_on.trim().to_lowercase();
-            if runs_on_lower.starts_with("macos") {
-                "rust:latest" // Use Rust image for macOS runners
-            } else if runs_on_lower.starts_with("windows") {
-                "mcr.microsoft.com/windows/servercore:ltsc2022" // Default Windows image
-            } else if runs_on_lower.starts_with("python") {
-                "python:3.11-slim" // Default Python image
-            } else if runs_on_lower.starts_with("node") {
-                "node:20-slim" // Default Node.js image
-            } else if runs_on_lower.starts_with("java") {
-                "eclipse-temurin:17-jdk" // Default Java image
-            } else if runs_on_lower.starts_with("go") {
-                "golang:1.21-slim" // Default Go image
-            } else if runs_on_lower.starts_with("dotnet") {
-                "mcr.microsoft.com/dotnet/sdk:7.0" // Default .NET image
-            } else {
-                "ubuntu:latest" // Default to Ubuntu for everything else
+fn job_workspace(
+    workflow: &Workflow,
+    job: &str,
+    bind: bool,
+) -> Result<JobWorkspace, ExecutionError> {
+    let current_dir = std::env::current_dir().map_err(|e| {
+        ExecutionError::Execution(format!("Failed to get current directory: {}", e))
+    })?;
+    if bind {
+        logging::info(&format!(
+            "Using the project directory as the job workspace: {}",
+            current_dir.display()
+        ));
+        return Ok(JobWorkspace::Bound(current_dir));
+    }
+
+    let workflow_name = workflow.name.as_deref().unwrap_or("workflow");
+    match workspace::PersistentWorkspace::acquire(
+        &workspace::workspaces_root(),
+        &current_dir,
+        workflow_name,
+        job,
+    ) {
+        Ok(Some(kept)) => {
+            logging::info(&format!(
+                "Syncing project files to job workspace: {}",
+                kept.path().display()
+            ));
+            let started = Instant::now();
+            match kept.sync(&current_dir) {
+                Ok(synced) => {
+                    logging::debug(&format!(
+                        "Synced {} files ({}), {} unchanged, {} removed, to {} in {:.2?}",
+                        synced.files,
+                        crate::artifacts::format_size(synced.bytes),
+                        synced.unchanged,
+                        synced.removed,
+                        kept.path().display(),
+                        started.elapsed()
+                    ));
+                    return Ok(JobWorkspace::Synced(kept));
+                }
+                // E.g. files a container left that can't be removed
+                Err(e) => logging::warning(&format!(
+                    "Failed to sync job workspace {}, copying the project instead: {}",
+                    kept.path().display(),
+                    e
+                )),
             }
         }
+        Ok(None) => logging::debug(&format!(
+            "The workspace of job '{}' is in use by another run, copying the project instead",
+            job
+        )),
+        Err(e) => logging::debug(&format!("No persistent job workspace: {}", e)),
     }
-    .to_string()
+
+    let job_dir = tempfile::tempdir()
+        .map_err(|e| ExecutionError::Execution(format!("Failed to create job directory: {}", e)))?;
+    logging::info(&format!(
+        "Copying project files to job workspace: {}",
+        job_dir.path().display()
+    ));
+    copy_project(&current_dir, job_dir.path())?;
+    Ok(JobWorkspace::Copy(job_dir))
+}
+
+/// Copy the project's files to a workspace, without the ignored ones
+fn copy_project(from: &Path, to: &Path) -> Result<(), ExecutionError> {
+    let started = Instant::now();
+    let copied = workspace::copy_workspace(from, to)
+        .map_err(|e| ExecutionError::Execution(format!("Failed to copy project files: {}", e)))?;
+    logging::debug(&format!(
+        "Copied {} files ({}) to {} in {:.2?}",
+        copied.files,
+        crate::artifacts::format_size(copied.bytes),
+        to.display(),
+        started.elapsed()
+    ));
+    Ok(())
 }
 
 #[allow(dead_code)]
@@ -1733,14 +5220,17 @@ fn extract_language_info(image: &str) -> Option<(&'static str, Option<&str>)> {
 }
 
 async fn execute_composite_action(
-    step: &workflow::Step,
+    ctx: &StepExecutionContext<'_>,
     action_path: &Path,
     job_env: &HashMap<String, String>,
-    working_dir: &Path,
-    runtime: &dyn ContainerRuntime,
-    runner_image: &str,
-    verbose: bool,
 ) -> Result<StepResult, ExecutionError> {
+    let step = ctx.step;
+    let working_dir = ctx.working_dir;
+    let runtime = ctx.runtime;
+    let runner_image = ctx.runner_image;
+    let artifact_store = ctx.artifact_store;
+    let verbose = ctx.verbose;
+
     // Find the action definition file
     let action_yaml = action_path.join("action.yml");
     let action_yaml_alt = action_path.join("action.yaml");
@@ -1791,8 +5281,7 @@ async fn execute_composite_action(
                             // Check if the input was provided in the 'with' section
                             let input_value = step
                                 .with
-                                .as_ref()
-                                .and_then(|with| with.get(input_name_str))
+                                .get(input_name_str)
                                 .unwrap_or(&default_value.to_string())
                                 .clone();
 
@@ -1806,6 +5295,14 @@ async fn execute_composite_action(
                 }
             }
 
+            // Nested steps get their own output files so they don't clobber the job's
+            let composite_commands_dir = ctx
+                .file_commands_dir
+                .join(format!("composite_{}", ctx.step_idx));
+            fs::create_dir_all(&composite_commands_dir).map_err(|e| {
+                ExecutionError::Execution(format!("Failed to create step output directory: {}", e))
+            })?;
+
             // Execute each step
             let mut step_outputs = Vec::new();
             for (idx, step_def) in steps.iter().enumerate() {
@@ -1827,16 +5324,18 @@ async fn execute_composite_action(
                     step_idx: idx,
                     job_env: &action_env,
                     working_dir,
+                    file_commands_dir: &composite_commands_dir,
+                    runner_dirs: ctx.runner_dirs,
+                    setup_actions: ctx.setup_actions,
                     runtime,
-                    workflow: &workflow::WorkflowDefinition {
-                        name: "Composite Action".to_string(),
-                        on: vec![],
-                        on_raw: serde_yaml::Value::Null,
-                        jobs: HashMap::new(),
-                    },
                     runner_image,
+                    job_container: ctx.job_container,
+                    artifact_store,
                     verbose,
+                    show_action_messages: ctx.show_action_messages,
                     matrix_combination: &None,
+                    overhead: ctx.overhead,
+                    debug_shell: ctx.debug_shell,
                 }))
                 .await?;
 
@@ -1852,6 +5351,11 @@ async fn execute_composite_action(
                             .unwrap_or_else(|| "Composite Action".to_string()),
                         status: StepStatus::Failure,
                         output: step_outputs.join("\n"),
+                        output_file: None,
+                        exit_code: None,
+                        duration: Duration::ZERO,
+                        overhead: Overhead::default(),
+                        annotations: Vec::new(),
                     });
                 }
             }
@@ -1901,6 +5405,11 @@ async fn execute_composite_action(
                     .unwrap_or_else(|| "Composite Action".to_string()),
                 status: StepStatus::Success,
                 output,
+                output_file: None,
+                exit_code: None,
+                duration: Duration::ZERO,
+                overhead: Overhead::default(),
+                annotations: Vec::new(),
             })
         }
         _ => Err(ExecutionError::Execution(
@@ -1910,64 +5419,76 @@ async fn execute_composite_action(
 }
 
 // Helper function to convert YAML step to our Step struct
-fn convert_yaml_to_step(step_yaml: &serde_yaml::Value) -> Result<workflow::Step, String> {
-    // Extract step properties
-    let name = step_yaml
-        .get("name")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
-
-    let uses = step_yaml
-        .get("uses")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
-
-    let run = step_yaml
-        .get("run")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
-
-    let shell = step_yaml
-        .get("shell")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
-
-    let with = step_yaml.get("with").and_then(|v| v.as_mapping()).map(|m| {
-        let mut with_map = HashMap::new();
-        for (k, v) in m {
-            if let (Some(key), Some(value)) = (k.as_str(), v.as_str()) {
-                with_map.insert(key.to_string(), value.to_string());
-            }
-        }
-        with_map
-    });
-
-    let env = step_yaml
-        .get("env")
-        .and_then(|v| v.as_mapping())
-        .map(|m| {
-            let mut env_map = HashMap::new();
-            for (k, v) in m {
-                if let (Some(key), Some(value)) = (k.as_str(), v.as_str()) {
-                    env_map.insert(key.to_string(), value.to_string());
-                }
-            }
-            env_map
-        })
-        .unwrap_or_default();
-
-    // For composite steps with shell, construct a run step
-    let final_run = run;
+fn convert_yaml_to_step(step_yaml: &serde_yaml::Value) -> Result<Step, String> {
+    serde_yaml::from_value(step_yaml.clone()).map_err(|e| e.to_string())
+}
 
-    // Extract continue_on_error
-    let continue_on_error = step_yaml.get("continue-on-error").and_then(|v| v.as_bool());
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags_action_images_by_ref_and_dockerfile() {
+        let dir = tempfile::tempdir().unwrap();
+        let dockerfile = dir.path().join("Dockerfile");
+        std::fs::write(&dockerfile, "FROM alpine:3.20\n").unwrap();
+        let (tag, replaces) = action_image_tag(Some("owner/lint@v1"), &dockerfile);
+        assert_eq!(replaces, "wrkflw-action:owner-lint-");
+        assert!(tag.starts_with(&replaces));
+        assert_eq!(action_image_tag(Some("owner/lint@v1"), &dockerfile).0, tag);
+        let (v2, v2_replaces) = action_image_tag(Some("owner/lint@v2"), &dockerfile);
+        assert_ne!(v2, tag);
+        assert_eq!(v2_replaces, replaces);
+
+        std::fs::write(&dockerfile, "FROM alpine:3.21\n").unwrap();
+        let (newer, newer_replaces) = action_image_tag(Some("owner/lint@v1"), &dockerfile);
+        assert_ne!(newer, tag);
+        assert_eq!(newer_replaces, replaces);
+        assert!(action_image_tag(Some("🚀"), &dockerfile)
+            .0
+            .starts_with("wrkflw-action:action-"));
+    }
 
-    Ok(workflow::Step {
-        name,
-        uses,
-        run: final_run,
-        with,
-        env,
-        continue_on_error,
-    })
+    #[test]
+    fn masks_values_in_later_steps_and_errors() {
+        let file_commands_dir = tempfile::tempdir().unwrap();
+        let step = |name: &str, output: &str| StepResult {
+            name: name.to_string(),
+            status: StepStatus::Success,
+            output: output.to_string(),
+            output_file: None,
+            exit_code: Some(0),
+            duration: Duration::ZERO,
+            overhead: Overhead::default(),
+            annotations: Vec::new(),
+        };
+        let mut masks = Vec::new();
+
+        let mut login = step("Login", "::add-mask::hunter2\nlogged in with hunter2\n");
+        apply_workflow_commands(&mut login, &mut masks, file_commands_dir.path(), 0);
+        assert_eq!(login.output, "::add-mask::***\nlogged in with ***\n");
+
+        let mut deploy = step(
+            "Deploy",
+            "::add-mask::hunter2-prod\n::warning title=hunter2::token hunter2-prod expires soon\nusing hunter2-prod\n",
+        );
+        apply_workflow_commands(&mut deploy, &mut masks, file_commands_dir.path(), 1);
+        // The longer value is hidden whole rather than as `***-prod`
+        assert_eq!(
+            deploy.output,
+            "::add-mask::***\n::warning title=***::token *** expires soon\nusing ***\n"
+        );
+        assert_eq!(deploy.annotations[0].message, "token *** expires soon");
+        assert_eq!(deploy.annotations[0].title.as_deref(), Some("***"));
+
+        let mut later = step("Report", "posted hunter2 and hunter2-prod\n");
+        apply_workflow_commands(&mut later, &mut masks, file_commands_dir.path(), 2);
+        assert_eq!(later.output, "posted *** and ***\n");
+
+        let error = ExecutionError::Runtime("curl -u admin:hunter2 failed".to_string());
+        assert_eq!(
+            mask(&error.to_string(), &masks),
+            "Runtime error: curl -u admin:*** failed"
+        );
+    }
 }
@@ -1,8 +1,19 @@
 use chrono::Utc;
+use expressions::EvaluationContext;
+use indexmap::IndexMap;
 use matrix::MatrixCombination;
-use parser::workflow::WorkflowDefinition;
+use models::github::{self, Workflow};
+use serde_json::Value as JsonValue;
 use serde_yaml::Value;
-use std::{collections::HashMap, fs, io, path::Path};
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::{Component, Path, PathBuf},
+    sync::Mutex,
+};
+
+/// Environment variable that overrides where the tool cache lives
+pub const TOOL_CACHE_DIR_ENV: &str = "WRKFLW_TOOL_CACHE";
 
 pub fn setup_github_environment_files(workspace_dir: &Path) -> io::Result<()> {
     // Create necessary directories
@@ -24,15 +35,17 @@ pub fn setup_github_environment_files(workspace_dir: &Path) -> io::Result<()> {
     Ok(())
 }
 
-pub fn create_github_context(
-    workflow: &WorkflowDefinition,
-    workspace_dir: &Path,
-) -> HashMap<String, String> {
+pub fn create_github_context(workflow: &Workflow, workspace_dir: &Path) -> HashMap<String, String> {
     let mut env = HashMap::new();
 
     // Basic GitHub environment variables
-    env.insert("GITHUB_WORKFLOW".to_string(), workflow.name.clone());
-    env.insert("GITHUB_ACTION".to_string(), "run".to_string());
+    env.insert(
+        "GITHUB_WORKFLOW".to_string(),
+        workflow.name.clone().unwrap_or_default(),
+    );
+    env.insert("GITHUB_ACTION".to_string(), "__run".to_string());
+    // Lets scripts tell a local run from a real one
+    env.insert("WRKFLW_CI".to_string(), "true".to_string());
     env.insert("GITHUB_ACTOR".to_string(), "wrkflw".to_string());
     env.insert("GITHUB_REPOSITORY".to_string(), get_repo_name());
     env.insert("GITHUB_EVENT_NAME".to_string(), get_event_name(workflow));
@@ -79,13 +92,552 @@ pub fn create_github_context(
     env.insert("GITHUB_RUN_ID".to_string(), format!("{}", now.timestamp()));
     env.insert("GITHUB_RUN_NUMBER".to_string(), "1".to_string());
 
-    // Path-related variables
-    env.insert("RUNNER_TEMP".to_string(), get_temp_dir());
-    env.insert("RUNNER_TOOL_CACHE".to_string(), get_tool_cache_dir());
+    // RUNNER_TEMP is per job, see RunnerDirs
+    env.insert(
+        "RUNNER_TOOL_CACHE".to_string(),
+        tool_cache_dir().to_string_lossy().to_string(),
+    );
 
     env
 }
 
+/// Set `CI` and the platform's own marker, e.g. `GITHUB_ACTIONS`, to `true`
+/// like hosted runners do, unless the run asked for a plain environment
+pub fn set_ci_env(env: &mut HashMap<String, String>, platform_var: &str, enabled: bool) {
+    if enabled {
+        env.insert("CI".to_string(), "true".to_string());
+        env.insert(platform_var.to_string(), "true".to_string());
+    }
+}
+
+/// Set the `RUNNER_*` variables describing the machine a job runs on:
+/// Linux containers of `platform`'s architecture, or else this host
+pub fn set_runner_env(
+    env: &mut HashMap<String, String>,
+    in_container: bool,
+    platform: Option<&str>,
+) {
+    let (os, arch) = if in_container {
+        let mut parts = platform.unwrap_or_default().split('/');
+        let os = match parts.next() {
+            Some("windows") => "Windows",
+            _ => "Linux",
+        };
+        (
+            os,
+            runner_arch(parts.next().unwrap_or(std::env::consts::ARCH)),
+        )
+    } else {
+        (host_runner_os(), runner_arch(std::env::consts::ARCH))
+    };
+    env.insert("RUNNER_OS".to_string(), os.to_string());
+    env.insert("RUNNER_ARCH".to_string(), arch);
+    env.insert("RUNNER_NAME".to_string(), "wrkflw".to_string());
+    env.insert("RUNNER_ENVIRONMENT".to_string(), "self-hosted".to_string());
+}
+
+/// `runner.os` of this host, like `Linux` or `macOS`
+fn host_runner_os() -> &'static str {
+    match std::env::consts::OS {
+        "macos" => "macOS",
+        "windows" => "Windows",
+        _ => "Linux",
+    }
+}
+
+/// `runner.arch` of a Rust or Docker architecture, like `ARM64` for both
+/// `aarch64` and `arm64`
+fn runner_arch(arch: &str) -> String {
+    match arch {
+        "x86_64" | "amd64" => "X64".to_string(),
+        "aarch64" | "arm64" => "ARM64".to_string(),
+        "x86" | "386" | "i386" | "i686" => "X86".to_string(),
+        "arm" => "ARM".to_string(),
+        other => other.to_uppercase(),
+    }
+}
+
+/// The `strategy` context of a job: where it is among the instances of its
+/// matrix, the only one for jobs without a matrix
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StrategyContext {
+    pub job_index: usize,
+    pub job_total: usize,
+    pub fail_fast: bool,
+    pub max_parallel: usize,
+}
+
+impl Default for StrategyContext {
+    fn default() -> Self {
+        StrategyContext {
+            job_index: 0,
+            job_total: 1,
+            fail_fast: true,
+            max_parallel: 1,
+        }
+    }
+}
+
+impl StrategyContext {
+    pub fn to_json(&self) -> JsonValue {
+        serde_json::json!({
+            "job-index": self.job_index,
+            "job-total": self.job_total,
+            "fail-fast": self.fail_fast,
+            "max-parallel": self.max_parallel,
+        })
+    }
+}
+
+/// An event to run a workflow as, given with `--event` / `--event-file`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulatedEvent {
+    pub name: String,
+    /// The webhook payload, `{}` when none was given
+    pub payload: JsonValue,
+}
+
+impl SimulatedEvent {
+    pub fn new(name: &str, payload_file: Option<&Path>) -> Result<Self, String> {
+        let payload = match payload_file {
+            Some(path) => {
+                let content = fs::read_to_string(path)
+                    .map_err(|e| format!("Failed to read event file {}: {}", path.display(), e))?;
+                let payload: JsonValue = serde_json::from_str(&content)
+                    .map_err(|e| format!("Invalid JSON in event file {}: {}", path.display(), e))?;
+                if !payload.is_object() {
+                    return Err(format!(
+                        "Event file {} must contain a JSON object",
+                        path.display()
+                    ));
+                }
+                payload
+            }
+            None => JsonValue::Object(serde_json::Map::new()),
+        };
+        Ok(SimulatedEvent {
+            name: name.to_string(),
+            payload,
+        })
+    }
+}
+
+/// Run as `event`: write its payload to `$GITHUB_EVENT_PATH` and take the
+/// ref, commit, actor and repository from it the way GitHub does
+pub fn apply_event(
+    env: &mut HashMap<String, String>,
+    workspace_dir: &Path,
+    event: &SimulatedEvent,
+) -> io::Result<()> {
+    let event_path = workspace_dir.join("github").join("event.json");
+    fs::write(&event_path, serde_json::to_string_pretty(&event.payload)?)?;
+    env.insert(
+        "GITHUB_EVENT_PATH".to_string(),
+        event_path.to_string_lossy().to_string(),
+    );
+    env.insert("GITHUB_EVENT_NAME".to_string(), event.name.clone());
+
+    let payload = &event.payload;
+    let text = |pointer: &str| {
+        payload
+            .pointer(pointer)
+            .and_then(JsonValue::as_str)
+            .map(str::to_string)
+    };
+
+    let (git_ref, sha) = match event.name.as_str() {
+        "pull_request" | "pull_request_review" | "pull_request_review_comment" => {
+            if let Some(head) = text("/pull_request/head/ref") {
+                env.insert("GITHUB_HEAD_REF".to_string(), head);
+            }
+            if let Some(base) = text("/pull_request/base/ref") {
+                env.insert("GITHUB_BASE_REF".to_string(), base);
+            }
+            (
+                payload
+                    .pointer("/pull_request/number")
+                    .or_else(|| payload.get("number"))
+                    .and_then(JsonValue::as_u64)
+                    .map(|number| format!("refs/pull/{}/merge", number)),
+                text("/pull_request/merge_commit_sha").or_else(|| text("/pull_request/head/sha")),
+            )
+        }
+        // pull_request_target runs in the context of the base branch
+        "pull_request_target" => {
+            if let Some(head) = text("/pull_request/head/ref") {
+                env.insert("GITHUB_HEAD_REF".to_string(), head);
+            }
+            let base = text("/pull_request/base/ref");
+            if let Some(base) = &base {
+                env.insert("GITHUB_BASE_REF".to_string(), base.clone());
+            }
+            (
+                base.map(|base| format!("refs/heads/{}", base)),
+                text("/pull_request/base/sha"),
+            )
+        }
+        "release" => (
+            text("/release/tag_name").map(|tag| format!("refs/tags/{}", tag)),
+            None,
+        ),
+        "push" => (text("/ref"), text("/after")),
+        _ => (text("/ref"), text("/sha")),
+    };
+    if let Some(git_ref) = git_ref {
+        let git_ref = if git_ref.starts_with("refs/") {
+            git_ref
+        } else {
+            // workflow_dispatch payloads may name a bare branch
+            format!("refs/heads/{}", git_ref)
+        };
+        env.insert("GITHUB_REF".to_string(), git_ref);
+    }
+    if let Some(sha) = sha {
+        env.insert("GITHUB_SHA".to_string(), sha);
+    }
+    if let Some(actor) = text("/sender/login") {
+        env.insert("GITHUB_ACTOR".to_string(), actor);
+    }
+    for (variable, pointer) in [
+        ("GITHUB_ACTOR_ID", "/sender/id"),
+        ("GITHUB_REPOSITORY_ID", "/repository/id"),
+        ("GITHUB_REPOSITORY_OWNER_ID", "/repository/owner/id"),
+    ] {
+        if let Some(id) = payload.pointer(pointer).and_then(JsonValue::as_u64) {
+            env.insert(variable.to_string(), id.to_string());
+        }
+    }
+    if let Some(repository) = text("/repository/full_name") {
+        env.insert("GITHUB_REPOSITORY".to_string(), repository);
+    }
+    Ok(())
+}
+
+/// `github` context values `--github-context` can set, through the
+/// `GITHUB_*` variables they mirror. The others belong to the runner, like
+/// `workspace` or `job`, or to the event, which `--event-file` sets.
+pub const GITHUB_CONTEXT_KEYS: &[&str] = &[
+    "actor",
+    "actor_id",
+    "api_url",
+    "base_ref",
+    "event_name",
+    "graphql_url",
+    "head_ref",
+    "ref",
+    "ref_name",
+    "ref_protected",
+    "ref_type",
+    "repository",
+    "repository_id",
+    "repository_owner",
+    "repository_owner_id",
+    "retention_days",
+    "run_attempt",
+    "run_id",
+    "run_number",
+    "server_url",
+    "sha",
+    "triggering_actor",
+    "workflow",
+    "workflow_ref",
+    "workflow_sha",
+];
+
+/// The variable a `github` context value is set through, e.g.
+/// `GITHUB_RUN_NUMBER` for `run_number`
+pub fn github_context_variable(key: &str) -> Result<String, String> {
+    if GITHUB_CONTEXT_KEYS.contains(&key) {
+        Ok(format!("GITHUB_{}", key.to_ascii_uppercase()))
+    } else {
+        Err(format!(
+            "'{}' isn't a github context value that can be set; use one of {}",
+            key,
+            GITHUB_CONTEXT_KEYS.join(", ")
+        ))
+    }
+}
+
+/// Set `overrides` of `github` context values, then fill in the variables
+/// runners set that follow from the others, like `GITHUB_REF_NAME` from
+/// `GITHUB_REF`, unless overridden too. `workflow_file` is the workflow's
+/// path in the repository, for `GITHUB_WORKFLOW_REF`.
+pub fn complete_github_context(
+    env: &mut HashMap<String, String>,
+    workflow_file: Option<&Path>,
+    overrides: &HashMap<String, String>,
+) -> Result<(), String> {
+    let mut overridden = HashSet::new();
+    for (key, value) in overrides {
+        let variable = github_context_variable(key)?;
+        env.insert(variable.clone(), value.clone());
+        overridden.insert(variable);
+    }
+
+    let get = |name: &str| env.get(name).cloned().unwrap_or_default();
+    let git_ref = get("GITHUB_REF");
+    let repository = get("GITHUB_REPOSITORY");
+    let ref_name = git_ref
+        .strip_prefix("refs/heads/")
+        .or_else(|| git_ref.strip_prefix("refs/tags/"))
+        .or_else(|| git_ref.strip_prefix("refs/pull/"))
+        .unwrap_or(&git_ref);
+    let ref_type = match git_ref.starts_with("refs/tags/") {
+        true => "tag",
+        false => "branch",
+    };
+    let mut derived = vec![
+        ("GITHUB_REF_NAME", ref_name.to_string()),
+        ("GITHUB_REF_TYPE", ref_type.to_string()),
+        (
+            "GITHUB_REPOSITORY_OWNER",
+            repository.split('/').next().unwrap_or_default().to_string(),
+        ),
+        ("GITHUB_TRIGGERING_ACTOR", get("GITHUB_ACTOR")),
+        ("GITHUB_WORKFLOW_SHA", get("GITHUB_SHA")),
+    ];
+    if let Some(file) = workflow_file {
+        derived.push((
+            "GITHUB_WORKFLOW_REF",
+            format!("{}/{}@{}", repository, file.display(), git_ref),
+        ));
+    }
+    for (variable, value) in derived {
+        if !overridden.contains(variable) {
+            env.insert(variable.to_string(), value);
+        }
+    }
+
+    // Simulated, as there's no GitHub run behind a local one
+    for (variable, value) in [
+        ("GITHUB_SERVER_URL", "https://github.com"),
+        ("GITHUB_API_URL", "https://api.github.com"),
+        ("GITHUB_GRAPHQL_URL", "https://api.github.com/graphql"),
+        ("GITHUB_RUN_ATTEMPT", "1"),
+        ("GITHUB_RETENTION_DAYS", "90"),
+        ("GITHUB_REF_PROTECTED", "false"),
+        ("GITHUB_ACTOR_ID", "1"),
+        ("GITHUB_REPOSITORY_ID", "1"),
+        ("GITHUB_REPOSITORY_OWNER_ID", "1"),
+        ("GITHUB_BASE_REF", ""),
+        ("GITHUB_HEAD_REF", ""),
+    ] {
+        env.entry(variable.to_string())
+            .or_insert_with(|| value.to_string());
+    }
+    Ok(())
+}
+
+/// The predefined variables GitLab gives a pipeline that `rules:` look at,
+/// for the current branch and commit. `--event` picks the pipeline source,
+/// e.g. `merge_request_event`, and the string, number and boolean values of
+/// an event file's top-level object override the variables.
+pub fn gitlab_ci_variables(event: Option<&SimulatedEvent>) -> HashMap<String, String> {
+    let git_ref = get_current_ref();
+    let branch = git_ref.strip_prefix("refs/heads/").unwrap_or(&git_ref);
+    simulated_gitlab_variables(event, branch, &get_current_sha(), &get_default_branch())
+}
+
+fn simulated_gitlab_variables(
+    event: Option<&SimulatedEvent>,
+    branch: &str,
+    sha: &str,
+    default_branch: &str,
+) -> HashMap<String, String> {
+    let source = event.map_or("push", |event| event.name.as_str());
+    let mut variables: HashMap<String, String> = [
+        ("CI_PIPELINE_SOURCE", source),
+        ("CI_COMMIT_SHA", sha),
+        ("CI_COMMIT_SHORT_SHA", &sha[..sha.len().min(8)]),
+        ("CI_COMMIT_REF_NAME", branch),
+        ("CI_DEFAULT_BRANCH", default_branch),
+    ]
+    .into_iter()
+    .map(|(key, value)| (key.to_string(), value.to_string()))
+    .collect();
+    if source == "merge_request_event" {
+        // Merge request pipelines have no CI_COMMIT_BRANCH
+        for (key, value) in [
+            ("CI_MERGE_REQUEST_IID", "1"),
+            ("CI_MERGE_REQUEST_ID", "1"),
+            ("CI_MERGE_REQUEST_EVENT_TYPE", "detached"),
+            ("CI_MERGE_REQUEST_SOURCE_BRANCH_NAME", branch),
+            ("CI_MERGE_REQUEST_TARGET_BRANCH_NAME", default_branch),
+        ] {
+            variables.insert(key.to_string(), value.to_string());
+        }
+    } else {
+        variables.insert("CI_COMMIT_BRANCH".to_string(), branch.to_string());
+    }
+
+    let overrides = event.and_then(|event| event.payload.as_object());
+    for (key, value) in overrides.into_iter().flatten() {
+        let value = match value {
+            JsonValue::String(text) => text.clone(),
+            JsonValue::Number(_) | JsonValue::Bool(_) => value.to_string(),
+            _ => continue,
+        };
+        variables.insert(key.clone(), value);
+    }
+    // Tag pipelines have no branch either
+    if let Some(tag) = variables.get("CI_COMMIT_TAG").cloned() {
+        variables.remove("CI_COMMIT_BRANCH");
+        variables.insert("CI_COMMIT_REF_NAME".to_string(), tag);
+    }
+    variables
+}
+
+/// Workspace path inside job containers
+pub const CONTAINER_WORKSPACE: &str = "/github/workspace";
+
+/// Runner temp directory inside job containers
+pub const CONTAINER_RUNNER_TEMP: &str = "/github/runner_temp";
+
+/// Tool cache path inside job containers, where setup actions expect it
+pub const CONTAINER_TOOL_CACHE: &str = "/opt/hostedtoolcache";
+
+/// The standard runner directories of a single job.
+///
+/// The temp directory is created fresh for every job and removed when the
+/// job is done; the tool cache is shared and persists across runs.
+pub struct RunnerDirs {
+    temp: tempfile::TempDir,
+    tool_cache: PathBuf,
+    /// Where setup actions link local toolchains, for emulated jobs
+    shims: Option<tempfile::TempDir>,
+    /// Variables setup actions set for the job's later steps
+    exports: Mutex<Vec<(String, String)>>,
+    /// Variables steps wrote to `$GITHUB_ENV`, which the job's `env:` wins over
+    env_file: Mutex<HashMap<String, String>>,
+    /// Directories steps wrote to `$GITHUB_PATH`, the latest first
+    path_file: Mutex<Vec<String>>,
+    /// Named volumes and where they are mounted in the job's containers
+    cache_volumes: Vec<(PathBuf, PathBuf)>,
+}
+
+impl RunnerDirs {
+    pub fn create(tool_cache: PathBuf) -> io::Result<RunnerDirs> {
+        fs::create_dir_all(&tool_cache)?;
+        Ok(RunnerDirs {
+            temp: tempfile::Builder::new().prefix("wrkflw-temp-").tempdir()?,
+            tool_cache,
+            shims: None,
+            exports: Mutex::default(),
+            env_file: Mutex::default(),
+            path_file: Mutex::default(),
+            cache_volumes: Vec::new(),
+        })
+    }
+
+    pub fn temp(&self) -> &Path {
+        self.temp.path()
+    }
+
+    pub fn tool_cache(&self) -> &Path {
+        &self.tool_cache
+    }
+
+    pub fn shims(&self) -> Option<&Path> {
+        self.shims.as_ref().map(|dir| dir.path())
+    }
+
+    pub fn cache_volumes(&self) -> &[(PathBuf, PathBuf)] {
+        &self.cache_volumes
+    }
+
+    /// Mount named volumes, given by name and container path, in the job's
+    /// containers along with its directories
+    pub fn mount_cache_volumes(&mut self, volumes: Vec<(PathBuf, PathBuf)>) {
+        self.cache_volumes = volumes;
+    }
+
+    /// Put a shim directory at the front of an emulated job's PATH
+    pub fn add_shims(&mut self, env: &mut HashMap<String, String>) -> io::Result<()> {
+        let shims = tempfile::Builder::new().prefix("wrkflw-shims-").tempdir()?;
+        let host_path = std::env::var_os("PATH").unwrap_or_default();
+        let path = std::env::join_paths(
+            std::iter::once(shims.path().to_path_buf()).chain(std::env::split_paths(&host_path)),
+        )
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        env.insert("PATH".to_string(), path.to_string_lossy().to_string());
+        self.shims = Some(shims);
+        Ok(())
+    }
+
+    /// Run an emulated job in its own copy of the workspace
+    pub fn sandbox(env: &mut HashMap<String, String>, workspace: &Path) {
+        let workspace = workspace.to_string_lossy().to_string();
+        if env.contains_key("CI_PROJECT_DIR") {
+            env.insert("CI_PROJECT_DIR".to_string(), workspace.clone());
+        }
+        env.insert("GITHUB_WORKSPACE".to_string(), workspace);
+    }
+
+    pub fn export(&self, key: String, value: String) {
+        let mut exports = self.exports.lock().unwrap();
+        exports.retain(|(name, _)| *name != key);
+        exports.push((key, value));
+    }
+
+    pub fn exports(&self) -> Vec<(String, String)> {
+        self.exports.lock().unwrap().clone()
+    }
+
+    /// Take in what a finished step wrote to its `$GITHUB_ENV` and
+    /// `$GITHUB_PATH` files, for the job's later steps
+    pub fn apply_file_commands(&self, dir: &Path, step_idx: usize) {
+        if let Ok(content) = fs::read_to_string(step_file(dir, "env", step_idx)) {
+            self.env_file
+                .lock()
+                .unwrap()
+                .extend(parse_file_command(&content));
+        }
+        if let Ok(content) = fs::read_to_string(step_file(dir, "path", step_idx)) {
+            let mut path = self.path_file.lock().unwrap();
+            for dir in content.lines().map(str::trim).filter(|dir| !dir.is_empty()) {
+                path.retain(|added| added != dir);
+                path.insert(0, dir.to_string());
+            }
+        }
+    }
+
+    pub fn env_file(&self) -> HashMap<String, String> {
+        self.env_file.lock().unwrap().clone()
+    }
+
+    pub fn path_file(&self) -> Vec<String> {
+        self.path_file.lock().unwrap().clone()
+    }
+
+    /// Point GITHUB_WORKSPACE, RUNNER_TEMP and RUNNER_TOOL_CACHE at these
+    /// directories, as seen from wherever the job's steps run
+    pub fn apply(&self, env: &mut HashMap<String, String>, in_container: bool) {
+        let (temp, tool_cache) = if in_container {
+            (CONTAINER_RUNNER_TEMP.into(), CONTAINER_TOOL_CACHE.into())
+        } else {
+            (
+                self.temp().to_string_lossy().to_string(),
+                self.tool_cache.to_string_lossy().to_string(),
+            )
+        };
+        env.insert("RUNNER_TEMP".to_string(), temp);
+        env.insert("RUNNER_TOOL_CACHE".to_string(), tool_cache);
+        if in_container {
+            env.insert(
+                "GITHUB_WORKSPACE".to_string(),
+                CONTAINER_WORKSPACE.to_string(),
+            );
+            // GitLab jobs know the workspace as CI_PROJECT_DIR
+            if env.contains_key("CI_PROJECT_DIR") {
+                env.insert(
+                    "CI_PROJECT_DIR".to_string(),
+                    CONTAINER_WORKSPACE.to_string(),
+                );
+            }
+        }
+    }
+}
+
 /// Add matrix context variables to the environment
 pub fn add_matrix_context(
     env: &mut HashMap<String, String>,
@@ -104,6 +656,164 @@ pub fn add_matrix_context(
     }
 }
 
+/// File commands each step gets a fresh file for, by variable and file name
+pub const STEP_FILE_COMMANDS: &[(&str, &str)] = &[
+    ("GITHUB_OUTPUT", "output"),
+    ("GITHUB_ENV", "env"),
+    ("GITHUB_PATH", "path"),
+    ("GITHUB_STEP_SUMMARY", "summary"),
+];
+
+/// Path of one of a step's file command files, see [`STEP_FILE_COMMANDS`]
+pub fn step_file(dir: &Path, name: &str, step_idx: usize) -> PathBuf {
+    dir.join(format!("{}_{}", name, step_idx))
+}
+
+/// Path of the `$GITHUB_OUTPUT` file for a step
+pub fn step_output_file(dir: &Path, step_idx: usize) -> PathBuf {
+    step_file(dir, "output", step_idx)
+}
+
+/// The Markdown a step wrote to its `$GITHUB_STEP_SUMMARY` file
+pub fn read_step_summary(dir: &Path, step_idx: usize) -> String {
+    fs::read_to_string(step_file(dir, "summary", step_idx)).unwrap_or_default()
+}
+
+/// Read the outputs a step wrote to its `$GITHUB_OUTPUT` file
+pub fn read_step_outputs(dir: &Path, step_idx: usize) -> HashMap<String, String> {
+    fs::read_to_string(step_output_file(dir, step_idx))
+        .map(|content| parse_file_command(&content))
+        .unwrap_or_default()
+}
+
+/// Parse the `name=value` and `name<<DELIMITER` forms written to
+/// `$GITHUB_OUTPUT` and `$GITHUB_ENV`
+pub fn parse_file_command(content: &str) -> HashMap<String, String> {
+    let mut values = HashMap::new();
+    let mut lines = content.lines();
+
+    while let Some(line) = lines.next() {
+        let equals = line.find('=');
+        let heredoc = line.find("<<");
+
+        match (equals, heredoc) {
+            (Some(eq), heredoc) if heredoc.is_none_or(|h| eq < h) => {
+                values.insert(line[..eq].to_string(), line[eq + 1..].to_string());
+            }
+            (_, Some(h)) => {
+                let delimiter = &line[h + 2..];
+                let mut value = Vec::new();
+                for body_line in lines.by_ref() {
+                    if body_line == delimiter {
+                        break;
+                    }
+                    value.push(body_line);
+                }
+                values.insert(line[..h].to_string(), value.join("\n"));
+            }
+            _ => {}
+        }
+    }
+
+    values
+}
+
+/// Read a secrets file of `NAME=value` and `NAME<<DELIMITER` entries, the
+/// same forms as `$GITHUB_ENV`, where lines starting with `#` are comments
+pub fn read_secrets_file(path: &Path) -> Result<HashMap<String, String>, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read secrets file {}: {}", path.display(), e))?;
+    let entries: Vec<&str> = content
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .collect();
+    Ok(parse_file_command(&entries.join("\n")))
+}
+
+/// Build the context used to evaluate `${{ }}` expressions from the
+/// environment of the job or step being evaluated
+pub fn create_expression_context(
+    env: &HashMap<String, String>,
+    matrix: Option<&HashMap<String, Value>>,
+) -> EvaluationContext {
+    let mut ctx = EvaluationContext::new();
+
+    // github.* mirrors the GITHUB_* variables, e.g. GITHUB_EVENT_NAME -> github.event_name
+    let mut github = serde_json::Map::new();
+    for (key, value) in env {
+        if let Some(name) = key.strip_prefix("GITHUB_") {
+            github.insert(name.to_lowercase(), JsonValue::String(value.clone()));
+        }
+    }
+    if let Some(git_ref) = env.get("GITHUB_REF") {
+        let ref_name = git_ref
+            .strip_prefix("refs/heads/")
+            .or_else(|| git_ref.strip_prefix("refs/tags/"))
+            .or_else(|| git_ref.strip_prefix("refs/pull/"))
+            .unwrap_or(git_ref);
+        github
+            .entry("ref_name")
+            .or_insert_with(|| JsonValue::String(ref_name.to_string()));
+    }
+    if let Some(repository) = env.get("GITHUB_REPOSITORY") {
+        github.insert(
+            "repositoryUrl".to_string(),
+            JsonValue::String(format!("git://github.com/{}.git", repository)),
+        );
+    }
+    // github.event is the payload the run was started with
+    let event = env
+        .get("GITHUB_EVENT_PATH")
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_else(|| JsonValue::Object(serde_json::Map::new()));
+    github.insert("event".to_string(), event);
+    ctx.set("github", JsonValue::Object(github));
+
+    ctx.set("env", serde_json::json!(env));
+    // runner.* mirrors the RUNNER_* variables, with this host for the
+    // ones a job doesn't have
+    let runner_var = |name: &str| env.get(name).cloned().unwrap_or_default();
+    ctx.set(
+        "runner",
+        serde_json::json!({
+            "os": env.get("RUNNER_OS").map_or(host_runner_os(), String::as_str),
+            "arch": env
+                .get("RUNNER_ARCH")
+                .cloned()
+                .unwrap_or_else(|| runner_arch(std::env::consts::ARCH)),
+            "name": env.get("RUNNER_NAME").map_or("wrkflw", String::as_str),
+            "environment": env.get("RUNNER_ENVIRONMENT").map_or("self-hosted", String::as_str),
+            "temp": runner_var("RUNNER_TEMP"),
+            "tool_cache": runner_var("RUNNER_TOOL_CACHE"),
+        }),
+    );
+
+    let matrix = matrix
+        .and_then(|m| serde_json::to_value(m).ok())
+        .unwrap_or_else(|| JsonValue::Object(serde_json::Map::new()));
+    ctx.set("matrix", matrix);
+
+    ctx.set("strategy", StrategyContext::default().to_json());
+    for name in ["secrets", "vars", "inputs", "needs", "steps", "job"] {
+        ctx.set(name, JsonValue::Object(serde_json::Map::new()));
+    }
+
+    ctx
+}
+
+/// Set the `secrets` context, and `github.token` to its `GITHUB_TOKEN`
+pub fn set_secrets_context(ctx: &mut EvaluationContext, secrets: &HashMap<String, String>) {
+    if let (Some(JsonValue::Object(github)), Some(token)) =
+        (ctx.get("github"), secrets.get("GITHUB_TOKEN"))
+    {
+        let mut github = github.clone();
+        github.insert("token".to_string(), JsonValue::String(token.clone()));
+        ctx.set("github", JsonValue::Object(github));
+    }
+    ctx.set("secrets", serde_json::json!(secrets));
+}
+
 /// Convert a serde_yaml::Value to a string for environment variables
 fn value_to_string(value: &Value) -> String {
     match value {
@@ -183,10 +893,10 @@ fn extract_repo_from_url(url: &str) -> Option<String> {
     None
 }
 
-fn get_event_name(workflow: &WorkflowDefinition) -> String {
+fn get_event_name(workflow: &Workflow) -> String {
     // Try to extract from the workflow trigger
-    if let Some(first_trigger) = workflow.on.first() {
-        return first_trigger.clone();
+    if let Some(first_trigger) = workflow.on.events().first() {
+        return first_trigger.to_string();
     }
     "workflow_dispatch".to_string()
 }
@@ -227,16 +937,308 @@ fn get_current_ref() -> String {
     "refs/heads/main".to_string()
 }
 
-fn get_temp_dir() -> String {
-    let temp_dir = std::env::temp_dir();
-    temp_dir.join("wrkflw").to_string_lossy().to_string()
+/// The branch `origin/HEAD` points at, or `main`
+fn get_default_branch() -> String {
+    if let Ok(output) = std::process::Command::new("git")
+        .args(["symbolic-ref", "--short", "refs/remotes/origin/HEAD"])
+        .output()
+    {
+        if output.status.success() {
+            let head = String::from_utf8_lossy(&output.stdout);
+            if let Some(branch) = head.trim().strip_prefix("origin/") {
+                return branch.to_string();
+            }
+        }
+    }
+
+    "main".to_string()
+}
+
+/// Evaluate `env:` maps from the outermost in, e.g. a workflow's then a
+/// job's, so each wins over the ones before it. A level's expressions see
+/// `base` and what earlier levels set in the `env` context. Values that fail
+/// to evaluate are kept as written, and variables the runner sets, like
+/// `GITHUB_SHA`, aren't overridden.
+pub fn merge_env_levels(
+    ctx: &mut EvaluationContext,
+    base: &HashMap<String, String>,
+    levels: &[&IndexMap<String, String>],
+) -> HashMap<String, String> {
+    let mut visible = base.clone();
+    let mut merged = HashMap::new();
+    for level in levels {
+        ctx.set("env", serde_json::json!(visible));
+        let evaluated: Vec<(String, String)> = level
+            .iter()
+            .filter(|(key, _)| !github::is_default_variable(key))
+            .map(|(key, value)| {
+                let value = expressions::interpolate(value, ctx).unwrap_or_else(|e| {
+                    logging::warning(&format!("Failed to evaluate env '{}': {}", key, e));
+                    value.clone()
+                });
+                (key.clone(), value)
+            })
+            .collect();
+        for (key, value) in evaluated {
+            visible.insert(key.clone(), value.clone());
+            merged.insert(key, value);
+        }
+    }
+    ctx.set("env", serde_json::json!(visible));
+    merged
 }
 
-fn get_tool_cache_dir() -> String {
-    let home_dir = dirs::home_dir().unwrap_or_default();
-    home_dir
+/// Resolve a step's `working-directory` against the workspace. Relative
+/// directories have to stay inside it, absolute ones are taken as they are.
+pub fn resolve_working_directory(workspace: &Path, dir: &str) -> Result<PathBuf, String> {
+    let mut resolved = workspace.to_path_buf();
+    for component in Path::new(dir).components() {
+        match component {
+            Component::Normal(part) => resolved.push(part),
+            Component::ParentDir if resolved != workspace => {
+                resolved.pop();
+            }
+            Component::ParentDir => {
+                return Err(format!(
+                    "Working directory '{}' is outside the workspace",
+                    dir
+                ))
+            }
+            Component::CurDir => {}
+            Component::RootDir | Component::Prefix(_) => return Ok(PathBuf::from(dir)),
+        }
+    }
+    Ok(resolved)
+}
+
+/// Tool cache shared by all runs, `~/.wrkflw/tools` unless overridden
+pub fn tool_cache_dir() -> PathBuf {
+    if let Ok(dir) = std::env::var(TOOL_CACHE_DIR_ENV) {
+        if !dir.is_empty() {
+            return PathBuf::from(dir);
+        }
+    }
+
+    dirs::home_dir()
+        .unwrap_or_default()
         .join(".wrkflw")
         .join("tools")
-        .to_string_lossy()
-        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runner_dirs_follow_the_runtime() {
+        let cache = tempfile::tempdir().unwrap();
+        let dirs = RunnerDirs::create(cache.path().join("tools")).unwrap();
+        assert!(dirs.temp().is_dir() && dirs.tool_cache().is_dir());
+
+        let mut env = HashMap::new();
+        dirs.apply(&mut env, false);
+        assert_eq!(env["RUNNER_TEMP"], dirs.temp().to_string_lossy());
+        assert!(!env.contains_key("GITHUB_WORKSPACE"));
+
+        dirs.apply(&mut env, true);
+        assert_eq!(env["RUNNER_TEMP"], CONTAINER_RUNNER_TEMP);
+        assert_eq!(env["RUNNER_TOOL_CACHE"], CONTAINER_TOOL_CACHE);
+        assert_eq!(env["GITHUB_WORKSPACE"], CONTAINER_WORKSPACE);
+
+        let mut dirs = dirs;
+        let workspace = cache.path().join("workspace");
+        RunnerDirs::sandbox(&mut env, &workspace);
+        dirs.add_shims(&mut env).unwrap();
+        let shims = dirs.shims().unwrap().to_path_buf();
+        assert_eq!(env["GITHUB_WORKSPACE"], workspace.to_string_lossy());
+        assert!(env["PATH"].starts_with(&*shims.to_string_lossy()));
+
+        // The temp and shim directories go away with the job
+        let temp = dirs.temp().to_path_buf();
+        drop(dirs);
+        assert!(!temp.exists() && !shims.exists());
+    }
+
+    #[test]
+    fn keeps_working_directories_inside_the_workspace() {
+        let workspace = Path::new(CONTAINER_WORKSPACE);
+        assert_eq!(
+            resolve_working_directory(workspace, "./packages/app/../web").unwrap(),
+            workspace.join("packages/web")
+        );
+        assert_eq!(
+            resolve_working_directory(workspace, "/tmp").unwrap(),
+            Path::new("/tmp")
+        );
+        assert!(resolve_working_directory(workspace, "packages/../../other")
+            .unwrap_err()
+            .contains("outside the workspace"));
+    }
+
+    #[test]
+    fn merges_env_levels_in_order() {
+        let base = HashMap::from([
+            ("GITHUB_SHA".to_string(), "abc".to_string()),
+            ("HOME".to_string(), "/home/runner".to_string()),
+        ]);
+        let workflow: IndexMap<String, String> = [
+            ("TARGET", "release"),
+            ("OUT", "${{ env.HOME }}/out"),
+            ("GITHUB_SHA", "overridden"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        let job: IndexMap<String, String> = [
+            ("TARGET", "debug"),
+            ("DIR", "${{ env.OUT }}/${{ env.TARGET }}"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+        let mut ctx = create_expression_context(&base, None);
+        let merged = merge_env_levels(&mut ctx, &base, &[&workflow, &job]);
+        assert_eq!(merged["TARGET"], "debug");
+        // Job values see the workflow's, not their own level's
+        assert_eq!(merged["DIR"], "/home/runner/out/release");
+        assert!(!merged.contains_key("GITHUB_SHA"));
+        assert_eq!(ctx.get("env").unwrap()["TARGET"], "debug");
+    }
+
+    #[test]
+    fn applies_pull_request_events() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("github")).unwrap();
+        let payload_file = dir.path().join("payload.json");
+        fs::write(
+            &payload_file,
+            r#"{
+                "number": 42,
+                "pull_request": {
+                    "title": "Fix the build",
+                    "head": {"ref": "fix-build", "sha": "abc123"},
+                    "base": {"ref": "main", "sha": "def456"}
+                },
+                "sender": {"login": "octocat"}
+            }"#,
+        )
+        .unwrap();
+
+        let event = SimulatedEvent::new("pull_request", Some(&payload_file)).unwrap();
+        let mut env = HashMap::new();
+        env.insert("GITHUB_REF".to_string(), "refs/heads/local".to_string());
+        apply_event(&mut env, dir.path(), &event).unwrap();
+        assert_eq!(env["GITHUB_EVENT_NAME"], "pull_request");
+        assert_eq!(env["GITHUB_REF"], "refs/pull/42/merge");
+        assert_eq!(env["GITHUB_SHA"], "abc123");
+        assert_eq!(env["GITHUB_HEAD_REF"], "fix-build");
+        assert_eq!(env["GITHUB_BASE_REF"], "main");
+        assert_eq!(env["GITHUB_ACTOR"], "octocat");
+
+        let ctx = create_expression_context(&env, None);
+        assert!(expressions::evaluate_condition(
+            "github.event_name == 'pull_request' && github.event.pull_request.title == 'Fix the build'",
+            &ctx
+        )
+        .unwrap());
+        assert!(expressions::evaluate_condition("github.ref_name == '42/merge'", &ctx).unwrap());
+
+        let target = SimulatedEvent::new("pull_request_target", Some(&payload_file)).unwrap();
+        apply_event(&mut env, dir.path(), &target).unwrap();
+        assert_eq!(env["GITHUB_REF"], "refs/heads/main");
+        assert_eq!(env["GITHUB_SHA"], "def456");
+
+        fs::write(&payload_file, "[]").unwrap();
+        assert!(SimulatedEvent::new("push", Some(&payload_file)).is_err());
+    }
+
+    #[test]
+    fn completes_the_github_context() {
+        let mut env = HashMap::from([
+            ("GITHUB_REF".to_string(), "refs/tags/v1.2.0".to_string()),
+            ("GITHUB_REPOSITORY".to_string(), "octo/app".to_string()),
+            ("GITHUB_SHA".to_string(), "abc".to_string()),
+            ("GITHUB_ACTOR".to_string(), "octocat".to_string()),
+        ]);
+        let overrides = HashMap::from([
+            ("run_number".to_string(), "42".to_string()),
+            ("repository_owner".to_string(), "someone".to_string()),
+        ]);
+        complete_github_context(
+            &mut env,
+            Some(Path::new(".github/workflows/ci.yml")),
+            &overrides,
+        )
+        .unwrap();
+        assert_eq!(env["GITHUB_REF_NAME"], "v1.2.0");
+        assert_eq!(env["GITHUB_REF_TYPE"], "tag");
+        assert_eq!(env["GITHUB_RUN_NUMBER"], "42");
+        assert_eq!(env["GITHUB_REPOSITORY_OWNER"], "someone");
+        assert_eq!(env["GITHUB_TRIGGERING_ACTOR"], "octocat");
+        assert_eq!(
+            env["GITHUB_WORKFLOW_REF"],
+            "octo/app/.github/workflows/ci.yml@refs/tags/v1.2.0"
+        );
+        assert_eq!(env["GITHUB_API_URL"], "https://api.github.com");
+
+        let ctx = create_expression_context(&env, None);
+        assert_eq!(ctx.get("github").unwrap()["run_number"], "42");
+        assert_eq!(ctx.get("github").unwrap()["ref_type"], "tag");
+
+        let workspace = HashMap::from([("workspace".to_string(), "/tmp".to_string())]);
+        assert!(complete_github_context(&mut env, None, &workspace).is_err());
+    }
+
+    #[test]
+    fn describes_the_runner() {
+        let mut env = HashMap::new();
+        set_runner_env(&mut env, true, Some("linux/arm64"));
+        assert_eq!(env["RUNNER_OS"], "Linux");
+        assert_eq!(env["RUNNER_ARCH"], "ARM64");
+
+        let ctx = create_expression_context(&env, None);
+        let runner = ctx.get("runner").unwrap();
+        assert_eq!(runner["arch"], "ARM64");
+        assert_eq!(runner["environment"], "self-hosted");
+        assert_eq!(ctx.get("strategy").unwrap()["job-total"], 1);
+
+        set_runner_env(&mut env, false, None);
+        assert_eq!(env["RUNNER_OS"], host_runner_os());
+        assert_eq!(runner_arch("x86_64"), "X64");
+        assert_eq!(runner_arch("amd64"), "X64");
+    }
+
+    #[test]
+    fn simulates_gitlab_pipeline_variables() {
+        let push = simulated_gitlab_variables(None, "feature", "0123456789abcdef", "main");
+        assert_eq!(push["CI_PIPELINE_SOURCE"], "push");
+        assert_eq!(push["CI_COMMIT_BRANCH"], "feature");
+        assert_eq!(push["CI_COMMIT_SHORT_SHA"], "01234567");
+
+        let event = SimulatedEvent::new("merge_request_event", None).unwrap();
+        let merge_request =
+            simulated_gitlab_variables(Some(&event), "feature", "0123456789abcdef", "main");
+        assert!(!merge_request.contains_key("CI_COMMIT_BRANCH"));
+        assert_eq!(merge_request["CI_MERGE_REQUEST_TARGET_BRANCH_NAME"], "main");
+
+        let tag = SimulatedEvent {
+            name: "push".to_string(),
+            payload: serde_json::json!({ "CI_COMMIT_TAG": "v1.0", "RETRIES": 3 }),
+        };
+        let tagged = simulated_gitlab_variables(Some(&tag), "feature", "0123", "main");
+        assert!(!tagged.contains_key("CI_COMMIT_BRANCH"));
+        assert_eq!(tagged["CI_COMMIT_REF_NAME"], "v1.0");
+        assert_eq!(tagged["RETRIES"], "3");
+    }
+
+    #[test]
+    fn parses_file_commands() {
+        let content = "version=1.2.3\nempty=\nnotes<<EOF\nline one\nline=two\nEOF\nurl=a<<b\n";
+        let values = parse_file_command(content);
+        assert_eq!(values["version"], "1.2.3");
+        assert_eq!(values["empty"], "");
+        assert_eq!(values["notes"], "line one\nline=two");
+        assert_eq!(values["url"], "a<<b");
+    }
 }
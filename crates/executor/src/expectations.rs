@@ -0,0 +1,152 @@
+// Test files holding what a workflow run is expected to produce, which
+// `wrkflw scaffold-tests` writes from the JSON results of a run
+use crate::engine::{JobStatus, StepStatus};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// The expected results of running one workflow, as in a `ci.test.yml`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunExpectations {
+    pub workflow: PathBuf,
+    pub success: bool,
+    pub jobs: IndexMap<String, JobExpectation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobExpectation {
+    pub status: JobStatus,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub outputs: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub steps: Vec<StepExpectation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StepExpectation {
+    pub name: String,
+    pub status: StepStatus,
+    #[serde(default, rename = "exit-code", skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+}
+
+/// The parts of `run --format json` output that expectations are made of
+#[derive(Deserialize)]
+struct RunResults {
+    file: PathBuf,
+    success: bool,
+    jobs: Vec<JobResults>,
+}
+
+#[derive(Deserialize)]
+struct JobResults {
+    name: String,
+    status: JobStatus,
+    #[serde(default)]
+    outputs: BTreeMap<String, String>,
+    #[serde(default)]
+    steps: Vec<StepResults>,
+}
+
+#[derive(Deserialize)]
+struct StepResults {
+    name: String,
+    status: StepStatus,
+    exit_code: Option<i32>,
+}
+
+impl RunExpectations {
+    /// Expect exactly what the run in `json`, as `run --format json` or
+    /// `--report json:PATH` wrote it, did
+    pub fn from_results_json(json: &str) -> Result<Self, String> {
+        let results: RunResults =
+            serde_json::from_str(json).map_err(|e| format!("Invalid run results: {}", e))?;
+        let jobs = results
+            .jobs
+            .into_iter()
+            .map(|job| {
+                let steps = job
+                    .steps
+                    .into_iter()
+                    .map(|step| StepExpectation {
+                        name: step.name,
+                        status: step.status,
+                        exit_code: step.exit_code,
+                    })
+                    .collect();
+                let expectation = JobExpectation {
+                    status: job.status,
+                    outputs: job.outputs,
+                    steps,
+                };
+                (job.name, expectation)
+            })
+            .collect();
+        Ok(RunExpectations {
+            workflow: results.file,
+            success: results.success,
+            jobs,
+        })
+    }
+
+    /// The test file, with a header saying where it came from
+    pub fn to_yaml(&self) -> Result<String, String> {
+        let body = serde_yaml::to_string(self).map_err(|e| e.to_string())?;
+        Ok(format!(
+            "# Expected results of {}, generated by `wrkflw scaffold-tests` from a\n\
+             # run of it. Edit the values to what the workflow should produce.\n{}",
+            self.workflow.display(),
+            body
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scaffolds_from_run_results() {
+        let json = r#"{
+            "file": ".github/workflows/ci.yml",
+            "platform": "github",
+            "success": false,
+            "jobs": [
+                {
+                    "name": "build",
+                    "status": "Failure",
+                    "outputs": { "version": "1.2.3" },
+                    "duration_secs": 2.5,
+                    "steps": [
+                        { "name": "Checkout", "status": "Success", "output": "", "output_file": null, "exit_code": null, "duration_secs": 0.1 },
+                        { "name": "Test", "status": "Failure", "output": "boom", "output_file": null, "exit_code": 101, "duration_secs": 2.4 }
+                    ]
+                },
+                { "name": "deploy", "status": "Skipped", "outputs": {}, "duration_secs": 0.0, "steps": [] }
+            ],
+            "failure_details": "build failed"
+        }"#;
+
+        let expectations = RunExpectations::from_results_json(json).unwrap();
+        assert_eq!(
+            expectations.jobs.keys().collect::<Vec<_>>(),
+            ["build", "deploy"]
+        );
+        let yaml = expectations.to_yaml().unwrap();
+        assert!(yaml.starts_with("# Expected results of .github/workflows/ci.yml"));
+        assert!(yaml.contains("    outputs:\n      version: 1.2.3\n"));
+        assert!(yaml.contains("    - name: Test\n      status: Failure\n      exit-code: 101\n"));
+        assert!(yaml.contains("  deploy:\n    status: Skipped\n"));
+
+        let body: String = yaml
+            .lines()
+            .filter(|l| !l.starts_with('#'))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let parsed: RunExpectations = serde_yaml::from_str(&body).unwrap();
+        assert_eq!(parsed, expectations);
+
+        assert!(RunExpectations::from_results_json("{}").is_err());
+    }
+}
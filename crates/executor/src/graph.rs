@@ -0,0 +1,455 @@
+// The dependency graph of a workflow's jobs by their `needs:`, or of a
+// pipeline's by stage and `needs:`, drawn as text, DOT or Mermaid
+use crate::engine::{self, preview_jobs, ExecutionConfig, ExecutionError};
+use models::github::Workflow;
+use models::gitlab::Pipeline;
+use parser::gitlab;
+use parser::sniff::Platform;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// A job on the graph
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphJob {
+    pub name: String,
+    /// Stage of a pipeline job
+    pub stage: Option<String>,
+    /// Jobs a matrix or `parallel:` fans the job out to, 1 without one
+    pub instances: usize,
+    /// Jobs that have to finish first
+    pub needs: Vec<String>,
+    /// Why the job wouldn't run, if it wouldn't
+    pub skip_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JobGraph {
+    pub name: String,
+    pub jobs: Vec<GraphJob>,
+}
+
+/// The graph of a workflow or pipeline file, with the jobs the event and job
+/// selection of `config` skip
+pub fn graph_file(path: &Path, config: &ExecutionConfig) -> Result<JobGraph, ExecutionError> {
+    let platform = match config.platform {
+        Some(platform) => platform,
+        None => engine::detect_platform(path)?,
+    };
+    match platform {
+        Platform::GitHub => workflow_graph(&parser::workflow::parse_workflow(path)?, config),
+        Platform::GitLab => {
+            let pipeline = gitlab::parse_pipeline(path).map_err(|e| {
+                ExecutionError::Parse(format!("Failed to parse GitLab pipeline: {}", e))
+            })?;
+            Ok(pipeline_graph(&pipeline, &path.display().to_string()))
+        }
+        other => Err(engine::validate_only(path, other)),
+    }
+}
+
+pub fn workflow_graph(
+    workflow: &Workflow,
+    config: &ExecutionConfig,
+) -> Result<JobGraph, ExecutionError> {
+    let skip_reasons: HashMap<String, Option<String>> = preview_jobs(workflow, config)?
+        .into_iter()
+        .map(|preview| (preview.name, preview.skip_reason))
+        .collect();
+    let mut jobs = Vec::new();
+    for (name, job) in &workflow.jobs {
+        let instances = match engine::job_matrix(job) {
+            Ok(Some(matrix_config)) => matrix::expand_matrix(&matrix_config)
+                .map_err(|e| ExecutionError::Execution(format!("Failed to expand matrix: {}", e)))?
+                .len()
+                .max(1),
+            _ => 1,
+        };
+        jobs.push(GraphJob {
+            name: name.clone(),
+            stage: None,
+            instances,
+            needs: job.needs().to_vec(),
+            skip_reason: skip_reasons.get(name).cloned().flatten(),
+        });
+    }
+    Ok(JobGraph {
+        name: workflow.name.clone().unwrap_or_default(),
+        jobs,
+    })
+}
+
+/// The graph of a pipeline, assuming every job that runs succeeds. Jobs
+/// without `needs:` wait for the closest earlier stage that has jobs.
+pub fn pipeline_graph(pipeline: &Pipeline, name: &str) -> JobGraph {
+    let stages = gitlab::pipeline_stages(pipeline);
+    let position = |stage: &str| stages.iter().position(|s| s == stage).unwrap_or(usize::MAX);
+    let mut runnable: Vec<(&String, &models::gitlab::Job)> = pipeline
+        .jobs
+        .iter()
+        .filter(|(name, job)| gitlab::is_runnable_job(name, job))
+        .collect();
+    runnable.sort_by_key(|(name, job)| (position(gitlab::job_stage(job)), name.as_str()));
+
+    let mut jobs: Vec<GraphJob> = Vec::new();
+    for (name, job) in &runnable {
+        let own_stage = position(gitlab::job_stage(job));
+        let needs: Vec<String> = match &job.needs {
+            Some(needs) => needs
+                .iter()
+                .map(|need| need.job().to_string())
+                .filter(|need| pipeline.jobs.contains_key(need))
+                .collect(),
+            None => {
+                let previous = runnable
+                    .iter()
+                    .map(|(_, other)| position(gitlab::job_stage(other)))
+                    .filter(|&stage| stage < own_stage)
+                    .max();
+                runnable
+                    .iter()
+                    .filter(|(_, other)| Some(position(gitlab::job_stage(other))) == previous)
+                    .map(|(other, _)| other.to_string())
+                    .collect()
+            }
+        };
+        let needed_job_skipped = job.needs.is_some()
+            && jobs
+                .iter()
+                .any(|other| other.skip_reason.is_some() && needs.contains(&other.name));
+        let skip_reason = match job.when.as_deref() {
+            Some("manual") => Some("manual jobs are not started locally"),
+            Some("never") => Some("'when: never'"),
+            Some("on_failure") => Some("no earlier job failed"),
+            _ if needed_job_skipped => Some("a needed job did not run"),
+            _ => None,
+        };
+        jobs.push(GraphJob {
+            name: name.to_string(),
+            stage: Some(gitlab::job_stage(job).to_string()),
+            instances: gitlab::job_instances(name, job).len(),
+            needs,
+            skip_reason: skip_reason.map(str::to_string),
+        });
+    }
+    JobGraph {
+        name: name.to_string(),
+        jobs,
+    }
+}
+
+impl JobGraph {
+    /// The jobs by how many jobs deep in the graph they are: jobs needing
+    /// nothing first, then the ones only needing those, and so on
+    pub fn levels(&self) -> Vec<Vec<&GraphJob>> {
+        let by_name: HashMap<&str, &GraphJob> = self
+            .jobs
+            .iter()
+            .map(|job| (job.name.as_str(), job))
+            .collect();
+        let mut depths: HashMap<&str, usize> = HashMap::new();
+        let mut levels: Vec<Vec<&GraphJob>> = Vec::new();
+        for job in &self.jobs {
+            let depth = depth(job, &by_name, &mut depths, &mut Vec::new());
+            if levels.len() <= depth {
+                levels.resize_with(depth + 1, Vec::new);
+            }
+            levels[depth].push(job);
+        }
+        levels
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for (i, level) in self.levels().iter().enumerate() {
+            text.push_str(&format!("Level {}\n", i + 1));
+            for job in level {
+                text.push_str(&format!("  {}", job.name));
+                if let Some(stage) = &job.stage {
+                    text.push_str(&format!("  [stage: {}]", stage));
+                }
+                if job.instances > 1 {
+                    text.push_str(&format!("  [x{}]", job.instances));
+                }
+                if !job.needs.is_empty() {
+                    text.push_str(&format!("  <- {}", job.needs.join(", ")));
+                }
+                if let Some(reason) = &job.skip_reason {
+                    text.push_str(&format!("  (skipped: {})", reason));
+                }
+                text.push('\n');
+            }
+        }
+        text
+    }
+
+    /// Graphviz source, with stages as clusters, fanned out jobs drawn
+    /// thicker and skipped jobs dashed
+    pub fn to_dot(&self) -> String {
+        let node = |job: &GraphJob| {
+            let mut attributes = Vec::new();
+            if job.instances > 1 {
+                attributes.push(format!(
+                    "label=\"{} x{}\"",
+                    dot_escape(&job.name),
+                    job.instances
+                ));
+                attributes.push("penwidth=2".to_string());
+            }
+            if job.skip_reason.is_some() {
+                attributes.push("style=\"rounded,dashed\"".to_string());
+                attributes.push("color=gray50".to_string());
+                attributes.push("fontcolor=gray50".to_string());
+            }
+            match attributes.is_empty() {
+                true => format!("\"{}\";", dot_escape(&job.name)),
+                false => format!("\"{}\" [{}];", dot_escape(&job.name), attributes.join(", ")),
+            }
+        };
+
+        let mut dot = format!(
+            "digraph \"{}\" {{\n  rankdir=LR;\n  node [shape=box, style=rounded];\n",
+            dot_escape(&self.name)
+        );
+        for (i, (stage, jobs)) in self.stages().into_iter().enumerate() {
+            match stage {
+                Some(stage) => {
+                    dot.push_str(&format!(
+                        "  subgraph \"cluster_{}\" {{\n    label=\"{}\";\n",
+                        i,
+                        dot_escape(stage)
+                    ));
+                    for job in jobs {
+                        dot.push_str(&format!("    {}\n", node(job)));
+                    }
+                    dot.push_str("  }\n");
+                }
+                None => {
+                    for job in jobs {
+                        dot.push_str(&format!("  {}\n", node(job)));
+                    }
+                }
+            }
+        }
+        for (from, to) in self.edges() {
+            dot.push_str(&format!(
+                "  \"{}\" -> \"{}\";\n",
+                dot_escape(from),
+                dot_escape(to)
+            ));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// A Mermaid flowchart, with stages as subgraphs
+    pub fn to_mermaid(&self) -> String {
+        let ids: HashMap<&str, String> = self
+            .jobs
+            .iter()
+            .enumerate()
+            .map(|(i, job)| (job.name.as_str(), format!("j{}", i)))
+            .collect();
+        let node = |job: &GraphJob| {
+            let label = match job.instances {
+                1 => job.name.clone(),
+                n => format!("{} x{}", job.name, n),
+            };
+            let class = match (&job.skip_reason, job.instances) {
+                (Some(_), _) => ":::skipped",
+                (None, 1) => "",
+                (None, _) => ":::matrix",
+            };
+            format!(
+                "{}[\"{}\"]{}",
+                ids[job.name.as_str()],
+                label.replace('"', "#quot;"),
+                class
+            )
+        };
+
+        let mut mermaid = String::from("flowchart LR\n");
+        for (i, (stage, jobs)) in self.stages().into_iter().enumerate() {
+            match stage {
+                Some(stage) => {
+                    mermaid.push_str(&format!(
+                        "  subgraph s{}[\"{}\"]\n",
+                        i,
+                        stage.replace('"', "#quot;")
+                    ));
+                    for job in jobs {
+                        mermaid.push_str(&format!("    {}\n", node(job)));
+                    }
+                    mermaid.push_str("  end\n");
+                }
+                None => {
+                    for job in jobs {
+                        mermaid.push_str(&format!("  {}\n", node(job)));
+                    }
+                }
+            }
+        }
+        for (from, to) in self.edges() {
+            mermaid.push_str(&format!("  {} --> {}\n", ids[from], ids[to]));
+        }
+        mermaid.push_str("  classDef matrix stroke-width:3px\n");
+        mermaid.push_str("  classDef skipped stroke-dasharray:5 5,color:#888\n");
+        mermaid
+    }
+
+    /// Each need as an edge from the needed job, leaving out unknown jobs
+    fn edges(&self) -> Vec<(&str, &str)> {
+        self.jobs
+            .iter()
+            .flat_map(|job| {
+                job.needs
+                    .iter()
+                    .filter(|need| self.jobs.iter().any(|other| &other.name == *need))
+                    .map(move |need| (need.as_str(), job.name.as_str()))
+            })
+            .collect()
+    }
+
+    /// Jobs grouped by stage in order, or all in one group without stages
+    fn stages(&self) -> Vec<(Option<&str>, Vec<&GraphJob>)> {
+        let mut stages: Vec<(Option<&str>, Vec<&GraphJob>)> = Vec::new();
+        for job in &self.jobs {
+            let stage = job.stage.as_deref();
+            match stages.iter_mut().find(|(s, _)| *s == stage) {
+                Some((_, jobs)) => jobs.push(job),
+                None => stages.push((stage, vec![job])),
+            }
+        }
+        stages
+    }
+}
+
+/// Longest chain of needs below a job; jobs in a cycle count as roots
+fn depth<'a>(
+    job: &'a GraphJob,
+    by_name: &HashMap<&str, &'a GraphJob>,
+    depths: &mut HashMap<&'a str, usize>,
+    visiting: &mut Vec<&'a str>,
+) -> usize {
+    if let Some(&depth) = depths.get(job.name.as_str()) {
+        return depth;
+    }
+    if visiting.contains(&job.name.as_str()) {
+        return 0;
+    }
+    visiting.push(&job.name);
+    let depth = job
+        .needs
+        .iter()
+        .filter_map(|need| by_name.get(need.as_str()))
+        .map(|need| depth(need, by_name, depths, visiting) + 1)
+        .max()
+        .unwrap_or(0);
+    visiting.pop();
+    depths.insert(&job.name, depth);
+    depth
+}
+
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::RuntimeType;
+
+    #[test]
+    fn draws_needs_matrices_and_skipped_jobs() {
+        let workflow: Workflow = serde_yaml::from_str(
+            r#"
+name: CI
+on: push
+jobs:
+  lint:
+    runs-on: ubuntu-latest
+    steps: [{run: make lint}]
+  build:
+    runs-on: ubuntu-latest
+    strategy:
+      matrix:
+        os: [linux, mac]
+        rust: [stable, beta, nightly]
+    steps: [{run: make}]
+  test:
+    needs: [build, lint]
+    runs-on: ubuntu-latest
+    steps: [{run: make test}]
+  deploy:
+    needs: test
+    if: github.event_name == 'release'
+    runs-on: ubuntu-latest
+    steps: [{run: make deploy}]
+"#,
+        )
+        .unwrap();
+        let config = ExecutionConfig::new(RuntimeType::Emulation, false);
+        let graph = workflow_graph(&workflow, &config).unwrap();
+
+        let levels: Vec<Vec<&str>> = graph
+            .levels()
+            .iter()
+            .map(|level| level.iter().map(|job| job.name.as_str()).collect())
+            .collect();
+        assert_eq!(
+            levels,
+            [vec!["lint", "build"], vec!["test"], vec!["deploy"]]
+        );
+        assert_eq!(graph.jobs[1].instances, 6);
+        assert!(graph.jobs[3].skip_reason.is_some());
+
+        let text = graph.to_text();
+        assert!(text.contains("  build  [x6]\n"));
+        assert!(text.contains("  test  <- build, lint\n"));
+        let dot = graph.to_dot();
+        assert!(dot.contains("\"build\" [label=\"build x6\", penwidth=2];"));
+        assert!(dot.contains("\"test\" -> \"deploy\";"));
+        let mermaid = graph.to_mermaid();
+        assert!(mermaid.contains("j1[\"build x6\"]:::matrix"));
+        assert!(mermaid.contains("j3[\"deploy\"]:::skipped"));
+        assert!(mermaid.contains("j2 --> j3"));
+    }
+
+    #[test]
+    fn links_pipeline_jobs_to_the_previous_stage() {
+        let pipeline: Pipeline = serde_yaml::from_str(
+            r#"
+stages: [build, test, deploy]
+compile:
+  stage: build
+  script: [make]
+unit:
+  stage: test
+  parallel: 3
+  script: [make test]
+lint:
+  needs: []
+  stage: test
+  script: [make lint]
+release:
+  stage: deploy
+  when: manual
+  script: [make release]
+.template:
+  script: [echo template]
+"#,
+        )
+        .unwrap();
+        let graph = pipeline_graph(&pipeline, ".gitlab-ci.yml");
+
+        let names: Vec<&str> = graph.jobs.iter().map(|job| job.name.as_str()).collect();
+        assert_eq!(names, ["compile", "lint", "unit", "release"]);
+        assert!(graph.jobs[1].needs.is_empty());
+        assert_eq!(graph.jobs[2].needs, ["compile"]);
+        assert_eq!(graph.jobs[2].instances, 3);
+        assert_eq!(graph.jobs[3].needs, ["lint", "unit"]);
+        assert!(graph.jobs[3].skip_reason.is_some());
+        assert!(graph
+            .to_dot()
+            .contains("subgraph \"cluster_1\" {\n    label=\"test\";"));
+    }
+}
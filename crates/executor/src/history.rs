@@ -0,0 +1,340 @@
+// Results of past runs kept on disk, for `wrkflw history` and the TUI's
+// History tab
+use crate::engine::{JobResult, JobStatus, StepResult, StepStatus};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Environment variable that overrides where the history is stored
+pub const HISTORY_DIR_ENV: &str = "WRKFLW_HISTORY_DIR";
+
+/// Runs kept; older ones are removed when a new one is recorded
+const MAX_RUNS: usize = 200;
+
+/// One run of a workflow or pipeline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+    /// Same as the id of the run's artifacts and step output logs
+    pub id: String,
+    pub workflow: PathBuf,
+    /// Mode the run used, after any fallback from Docker or Podman
+    pub runtime: String,
+    pub started_at: String,
+    pub finished_at: String,
+    pub success: bool,
+    pub jobs: Vec<JobResult>,
+    pub failure_details: Option<String>,
+    /// Why the run was cancelled before it finished, if it was
+    #[serde(default)]
+    pub cancelled: Option<String>,
+}
+
+/// Root directory of the history store
+pub fn history_root() -> PathBuf {
+    if let Ok(dir) = std::env::var(HISTORY_DIR_ENV) {
+        if !dir.is_empty() {
+            return PathBuf::from(dir);
+        }
+    }
+
+    dirs::data_local_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("wrkflw")
+        .join("history")
+}
+
+/// Store `record`, removing the oldest runs beyond the ones kept
+pub fn save(root: &Path, record: &RunRecord) -> Result<PathBuf, String> {
+    fs::create_dir_all(root).map_err(|e| format!("Failed to create history directory: {}", e))?;
+    let path = root.join(format!("{}.json", record.id));
+    let json = serde_json::to_string(record).map_err(|e| e.to_string())?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    for old in run_files(root).into_iter().skip(MAX_RUNS) {
+        let _ = fs::remove_file(old);
+    }
+    Ok(path)
+}
+
+/// Recorded runs, most recent first. Files that can't be read, e.g. from
+/// another version of wrkflw, are left out.
+pub fn list(root: &Path) -> Vec<RunRecord> {
+    run_files(root)
+        .into_iter()
+        .filter_map(|path| read(&path).ok())
+        .collect()
+}
+
+/// The run with id `id`, or the only one whose id starts with it
+pub fn load(root: &Path, id: &str) -> Result<RunRecord, String> {
+    let exact = root.join(format!("{}.json", id));
+    if exact.is_file() {
+        return read(&exact);
+    }
+    let matches: Vec<PathBuf> = run_files(root)
+        .into_iter()
+        .filter(|path| file_id(path).starts_with(id))
+        .collect();
+    match matches.as_slice() {
+        [path] => read(path),
+        [] => Err(format!("No run '{}' in the history", id)),
+        _ => Err(format!(
+            "'{}' matches {} runs, give more of the id",
+            id,
+            matches.len()
+        )),
+    }
+}
+
+/// History files, most recent first
+fn run_files(root: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(root)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    // Run ids start with a timestamp, so they sort chronologically
+    files.sort_by_key(|path| std::cmp::Reverse(file_id(path)));
+    files
+}
+
+fn file_id(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn read(path: &Path) -> Result<RunRecord, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Invalid run {}: {}", path.display(), e))
+}
+
+/// A job, step or job output that differs between two runs; `None` when
+/// only one of the runs has it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub job: String,
+    pub item: Option<String>,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let side = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".to_string());
+        match &self.item {
+            Some(item) => write!(f, "{} / {}", self.job, item)?,
+            None => write!(f, "{}", self.job)?,
+        }
+        write!(f, ": {} -> {}", side(&self.before), side(&self.after))
+    }
+}
+
+/// How the job and step results and job outputs of run `b` differ from
+/// those of run `a`
+pub fn diff(a: &RunRecord, b: &RunRecord) -> Vec<Change> {
+    let mut changes = Vec::new();
+    let mut job_names: Vec<&str> = a.jobs.iter().map(|job| job.name.as_str()).collect();
+    for job in &b.jobs {
+        if !job_names.contains(&job.name.as_str()) {
+            job_names.push(&job.name);
+        }
+    }
+    let find = |run: &RunRecord, name: &str| -> Option<JobResult> {
+        run.jobs.iter().find(|job| job.name == name).cloned()
+    };
+
+    for name in job_names {
+        let (before, after) = (find(a, name), find(b, name));
+        let mut change = |item: Option<String>, before: Option<String>, after: Option<String>| {
+            if before != after {
+                changes.push(Change {
+                    job: name.to_string(),
+                    item,
+                    before,
+                    after,
+                });
+            }
+        };
+        change(
+            None,
+            before
+                .as_ref()
+                .map(|job| job_status(&job.status).to_string()),
+            after
+                .as_ref()
+                .map(|job| job_status(&job.status).to_string()),
+        );
+        let (Some(before), Some(after)) = (before, after) else {
+            continue;
+        };
+
+        let before_steps = keyed_steps(&before.steps);
+        let after_steps = keyed_steps(&after.steps);
+        let mut keys: Vec<&String> = before_steps.iter().map(|(key, _)| key).collect();
+        keys.extend(
+            after_steps
+                .iter()
+                .map(|(key, _)| key)
+                .filter(|key| !before_steps.iter().any(|(k, _)| k == *key)),
+        );
+        let step = |steps: &[(String, &StepResult)], key: &String| {
+            steps
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, step)| step_summary(step))
+        };
+        for key in keys {
+            change(
+                Some(key.clone()),
+                step(&before_steps, key),
+                step(&after_steps, key),
+            );
+        }
+
+        let mut outputs: Vec<&String> = before.outputs.keys().collect();
+        outputs.extend(
+            after
+                .outputs
+                .keys()
+                .filter(|key| !before.outputs.contains_key(*key)),
+        );
+        outputs.sort();
+        for output in outputs {
+            change(
+                Some(format!("outputs.{}", output)),
+                before.outputs.get(output).cloned(),
+                after.outputs.get(output).cloned(),
+            );
+        }
+    }
+    changes
+}
+
+/// Steps by name, numbered when a job has several of the same name
+fn keyed_steps(steps: &[StepResult]) -> Vec<(String, &StepResult)> {
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    steps
+        .iter()
+        .map(|step| {
+            let count = seen.entry(step.name.as_str()).or_default();
+            *count += 1;
+            let key = match count {
+                1 => step.name.clone(),
+                n => format!("{} #{}", step.name, n),
+            };
+            (key, step)
+        })
+        .collect()
+}
+
+pub fn job_status(status: &JobStatus) -> &'static str {
+    match status {
+        JobStatus::Success => "success",
+        JobStatus::Failure => "failure",
+        JobStatus::Skipped => "skipped",
+    }
+}
+
+pub fn step_status(status: &StepStatus) -> &'static str {
+    match status {
+        StepStatus::Success => "success",
+        StepStatus::Failure => "failure",
+        StepStatus::Skipped => "skipped",
+    }
+}
+
+/// A step's status with its exit code, when that was not 0
+fn step_summary(step: &StepResult) -> String {
+    match step.exit_code {
+        Some(code) if code != 0 => format!("{} (exit code {})", step_status(&step.status), code),
+        _ => step_status(&step.status).to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::Overhead;
+    use std::time::Duration;
+
+    fn step(name: &str, status: StepStatus, exit_code: Option<i32>) -> StepResult {
+        StepResult {
+            name: name.to_string(),
+            status,
+            output: String::new(),
+            output_file: None,
+            exit_code,
+            duration: Duration::from_millis(250),
+            overhead: Overhead::default(),
+            annotations: Vec::new(),
+        }
+    }
+
+    fn run(id: &str, test: StepResult, version: &str) -> RunRecord {
+        RunRecord {
+            id: id.to_string(),
+            workflow: PathBuf::from(".github/workflows/ci.yml"),
+            runtime: "emulation".to_string(),
+            started_at: String::new(),
+            finished_at: String::new(),
+            success: test.status == StepStatus::Success,
+            jobs: vec![JobResult {
+                name: "build".to_string(),
+                status: JobStatus::Success,
+                steps: vec![step("Run", StepStatus::Success, Some(0)), test],
+                logs: String::new(),
+                outputs: HashMap::from([("version".to_string(), version.to_string())]),
+                duration: Duration::from_secs(1),
+                overhead: Overhead::default(),
+                summary: String::new(),
+            }],
+            failure_details: None,
+            cancelled: None,
+        }
+    }
+
+    #[test]
+    fn stores_and_diffs_runs() {
+        let root = tempfile::tempdir().unwrap();
+        let old = run(
+            "20260101-120000-aaaaaaaa",
+            step("Run", StepStatus::Success, Some(0)),
+            "1.0",
+        );
+        let new = run(
+            "20260102-120000-bbbbbbbb",
+            step("Run", StepStatus::Failure, Some(2)),
+            "1.1",
+        );
+        save(root.path(), &old).unwrap();
+        save(root.path(), &new).unwrap();
+        fs::write(root.path().join("20260103-000000-broken.json"), "{").unwrap();
+
+        let ids: Vec<String> = list(root.path()).into_iter().map(|run| run.id).collect();
+        assert_eq!(ids, [new.id.clone(), old.id.clone()]);
+        assert_eq!(load(root.path(), "20260101").unwrap().id, old.id);
+        assert!(load(root.path(), "2026").is_err());
+        assert!(load(root.path(), "1999").is_err());
+        let loaded = load(root.path(), &new.id).unwrap();
+        assert_eq!(loaded.jobs[0].steps[1].duration, Duration::from_millis(250));
+
+        let changes: Vec<String> = diff(&old, &loaded)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                "build / Run #2: success -> failure (exit code 2)",
+                "build / outputs.version: 1.0 -> 1.1"
+            ]
+        );
+        assert!(diff(&old, &old).is_empty());
+    }
+}
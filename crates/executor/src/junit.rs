@@ -0,0 +1,228 @@
+// JUnit XML reports of runs, which CI dashboards and IDE test explorers read:
+// a testsuite per job and a testcase per step
+use crate::engine::{ExecutionResult, JobResult, JobStatus, StepStatus};
+use std::fmt::Write;
+use std::time::Duration;
+
+/// Render `result` as a JUnit XML document named after the run's workflow
+pub fn render(workflow: &str, result: &ExecutionResult) -> String {
+    let cases = |job: &JobResult| job.steps.len().max(1);
+    let count = |job: &JobResult, status: StepStatus, job_status: JobStatus| {
+        if job.steps.is_empty() {
+            usize::from(job.status == job_status)
+        } else {
+            job.steps
+                .iter()
+                .filter(|step| step.status == status)
+                .count()
+        }
+    };
+    let tests: usize = result.jobs.iter().map(cases).sum();
+    let failures: usize = result
+        .jobs
+        .iter()
+        .map(|job| count(job, StepStatus::Failure, JobStatus::Failure))
+        .sum();
+    let skipped: usize = result
+        .jobs
+        .iter()
+        .map(|job| count(job, StepStatus::Skipped, JobStatus::Skipped))
+        .sum();
+    let time: Duration = result.jobs.iter().map(|job| job.duration).sum();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        xml,
+        "<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">",
+        escape(workflow),
+        tests,
+        failures,
+        skipped,
+        time.as_secs_f64()
+    );
+    for job in &result.jobs {
+        let _ = writeln!(
+            xml,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">",
+            escape(&job.name),
+            cases(job),
+            count(job, StepStatus::Failure, JobStatus::Failure),
+            count(job, StepStatus::Skipped, JobStatus::Skipped),
+            job.duration.as_secs_f64()
+        );
+        if job.steps.is_empty() {
+            // Jobs that didn't run any steps, e.g. skipped ones, still show up
+            let status = match job.status {
+                JobStatus::Success => StepStatus::Success,
+                JobStatus::Failure => StepStatus::Failure,
+                JobStatus::Skipped => StepStatus::Skipped,
+            };
+            write_case(
+                &mut xml,
+                &job.name,
+                &job.name,
+                &status,
+                None,
+                &job.logs,
+                job.duration,
+            );
+        }
+        for step in &job.steps {
+            write_case(
+                &mut xml,
+                &job.name,
+                &step.name,
+                &step.status,
+                step.exit_code,
+                &step.output,
+                step.duration,
+            );
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+fn write_case(
+    xml: &mut String,
+    job: &str,
+    name: &str,
+    status: &StepStatus,
+    exit_code: Option<i32>,
+    output: &str,
+    duration: Duration,
+) {
+    let _ = writeln!(
+        xml,
+        "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\">",
+        escape(name),
+        escape(job),
+        duration.as_secs_f64()
+    );
+    match status {
+        StepStatus::Success => {}
+        StepStatus::Failure => {
+            let message = match exit_code {
+                Some(code) => format!("Failed with exit code {}", code),
+                None => "Failed".to_string(),
+            };
+            let _ = writeln!(
+                xml,
+                "      <failure message=\"{}\">{}</failure>",
+                escape(&message),
+                escape(output)
+            );
+        }
+        StepStatus::Skipped => {
+            let _ = writeln!(
+                xml,
+                "      <skipped message=\"{}\"/>",
+                escape(output.trim())
+            );
+        }
+    }
+    // Failures carry their output already
+    if *status == StepStatus::Success && !output.is_empty() {
+        let _ = writeln!(xml, "      <system-out>{}</system-out>", escape(output));
+    }
+    xml.push_str("    </testcase>\n");
+}
+
+/// Escape text for XML, dropping control characters like the ANSI escapes
+/// in command output, which XML 1.0 doesn't allow
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{Overhead, StepResult};
+    use std::collections::HashMap;
+
+    fn step(name: &str, status: StepStatus, exit_code: Option<i32>, output: &str) -> StepResult {
+        StepResult {
+            name: name.to_string(),
+            status,
+            output: output.to_string(),
+            output_file: None,
+            exit_code,
+            duration: Duration::from_millis(1500),
+            overhead: Overhead::default(),
+            annotations: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn writes_jobs_as_suites_and_steps_as_cases() {
+        let result = ExecutionResult {
+            jobs: vec![
+                JobResult {
+                    name: "build".to_string(),
+                    status: JobStatus::Failure,
+                    steps: vec![
+                        step("Checkout", StepStatus::Success, None, "done"),
+                        step(
+                            "Test <unit>",
+                            StepStatus::Failure,
+                            Some(101),
+                            "\x1b[31mpanicked\x1b[0m & failed",
+                        ),
+                        step(
+                            "Deploy",
+                            StepStatus::Skipped,
+                            None,
+                            "Step skipped: if: false",
+                        ),
+                    ],
+                    logs: String::new(),
+                    outputs: HashMap::new(),
+                    duration: Duration::from_secs(3),
+                    overhead: Overhead::default(),
+                    summary: String::new(),
+                },
+                JobResult {
+                    name: "release".to_string(),
+                    status: JobStatus::Skipped,
+                    steps: Vec::new(),
+                    logs: "Job skipped: a needed job failed".to_string(),
+                    outputs: HashMap::new(),
+                    duration: Duration::ZERO,
+                    overhead: Overhead::default(),
+                    summary: String::new(),
+                },
+            ],
+            failure_details: Some("build failed".to_string()),
+        };
+
+        let xml = render("CI", &result);
+        assert!(xml.contains(
+            "<testsuites name=\"CI\" tests=\"4\" failures=\"1\" skipped=\"2\" time=\"3.000\">"
+        ));
+        assert!(xml.contains(
+            "<testsuite name=\"build\" tests=\"3\" failures=\"1\" skipped=\"1\" time=\"3.000\">"
+        ));
+        assert!(xml
+            .contains("<testcase name=\"Test &lt;unit&gt;\" classname=\"build\" time=\"1.500\">"));
+        assert!(xml.contains(
+            "<failure message=\"Failed with exit code 101\">[31mpanicked[0m &amp; failed</failure>"
+        ));
+        assert!(xml.contains("<skipped message=\"Step skipped: if: false\"/>"));
+        assert!(xml.contains("<testcase name=\"release\" classname=\"release\" time=\"0.000\">"));
+        assert!(xml.contains("<skipped message=\"Job skipped: a needed job failed\"/>"));
+    }
+}
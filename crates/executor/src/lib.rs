@@ -2,12 +2,44 @@
 
 #![allow(unused_variables, unused_assignments)]
 
+pub mod actions;
+pub mod artifacts;
+pub mod checkout;
+pub mod concurrency;
 pub mod dependency;
+pub mod deployments;
 pub mod docker;
 pub mod engine;
 pub mod environment;
+pub mod expectations;
+pub mod graph;
+pub mod history;
+pub mod junit;
+pub mod permissions;
+pub mod platforms;
+pub mod podman;
+pub mod profile;
+pub mod progress;
+pub mod requirements;
+pub mod resources;
+pub mod retry;
+pub mod runner_image;
+pub mod shell;
+pub mod stepping;
 pub mod substitution;
+pub mod summary;
+pub mod tool_cache;
+pub mod workspace;
 
 // Re-export public items
+pub use concurrency::{CancellationToken, ConcurrencyGroup, ConcurrencyManager};
 pub use docker::cleanup_resources;
-pub use engine::{execute_workflow, JobResult, JobStatus, RuntimeType, StepResult, StepStatus};
+pub use engine::{
+    concurrency_group, execute_workflow, execute_workflow_with_config, fallback_runtime_type,
+    open_job_shell, plan_workflow_file, preview_jobs, ExecutionConfig, ExecutionError, JobPlan,
+    JobPreview, JobResult, JobStatus, Overhead, RuntimeType, StepPlan, StepResult, StepStatus,
+};
+pub use progress::{
+    CiObserver, ExecutionEvent, ExecutionObserver, JsonLinesObserver, LogObserver, Progress,
+    ProgressReporter,
+};
@@ -0,0 +1,302 @@
+// What a job's `GITHUB_TOKEN` may do, from its `permissions:`, and the
+// GitHub API calls in `run` scripts that need more than that
+use models::github::Permissions;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Variable with the job token's permissions as JSON, e.g.
+/// `{"contents":"read"}`
+pub const PERMISSIONS_ENV: &str = "WRKFLW_TOKEN_PERMISSIONS";
+
+/// Scopes of the `permissions:` key
+pub const SCOPES: &[&str] = &[
+    "actions",
+    "attestations",
+    "checks",
+    "contents",
+    "deployments",
+    "discussions",
+    "id-token",
+    "issues",
+    "metadata",
+    "packages",
+    "pages",
+    "pull-requests",
+    "repository-projects",
+    "security-events",
+    "statuses",
+];
+
+static GITHUB_API_URL: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"https://api\.github\.com/([^\s'\x22?#]+)").unwrap());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Access {
+    None,
+    Read,
+    Write,
+}
+
+impl Access {
+    fn parse(level: &str) -> Option<Self> {
+        match level.trim() {
+            "none" => Some(Access::None),
+            "read" => Some(Access::Read),
+            "write" => Some(Access::Write),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Access {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Access::None => "none",
+            Access::Read => "read",
+            Access::Write => "write",
+        })
+    }
+}
+
+/// Access of a job's token per scope. Jobs of workflows that don't set
+/// `permissions:` get the repository's defaults, which wrkflw can't know,
+/// so they are taken to allow everything.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenPermissions {
+    scopes: BTreeMap<&'static str, Access>,
+    declared: bool,
+}
+
+impl TokenPermissions {
+    /// The permissions of a job, whose own `permissions:` replace the
+    /// workflow's
+    pub fn resolve(workflow: Option<&Permissions>, job: Option<&Permissions>) -> Self {
+        let Some(permissions) = job.or(workflow) else {
+            return Self::all(Access::Write, false);
+        };
+        match permissions {
+            Permissions::All(level) => match level.trim() {
+                "read-all" => Self::all(Access::Read, true),
+                "write-all" => Self::all(Access::Write, true),
+                // Expressions and unknown levels are left to `validate`
+                _ => Self::all(Access::Write, false),
+            },
+            Permissions::Scopes(scopes) => {
+                let mut permissions = Self::all(Access::None, true);
+                for (scope, level) in scopes {
+                    let known = SCOPES.iter().find(|known| **known == scope.as_str());
+                    if let (Some(scope), Some(access)) = (known, Access::parse(level)) {
+                        permissions.scopes.insert(scope, access);
+                    }
+                }
+                // Every token can read the repository's metadata
+                let metadata = permissions.scopes.entry("metadata").or_insert(Access::None);
+                *metadata = (*metadata).max(Access::Read);
+                permissions
+            }
+        }
+    }
+
+    fn all(access: Access, declared: bool) -> Self {
+        TokenPermissions {
+            scopes: SCOPES.iter().map(|scope| (*scope, access)).collect(),
+            declared,
+        }
+    }
+
+    pub fn get(&self, scope: &str) -> Access {
+        self.scopes.get(scope).copied().unwrap_or(Access::None)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::json!(self
+            .scopes
+            .iter()
+            .map(|(scope, access)| (*scope, access.to_string()))
+            .collect::<BTreeMap<_, _>>())
+        .to_string()
+    }
+
+    /// Warnings about the GitHub API calls in a script that the token isn't
+    /// allowed to make
+    pub fn check_script(&self, script: &str) -> Vec<String> {
+        if !self.declared {
+            return Vec::new();
+        }
+        api_calls(script)
+            .into_iter()
+            .filter(|call| self.get(call.scope) < call.access)
+            .map(|call| {
+                format!(
+                    "`{}` needs `{}: {}`, but the job's token has `{}: {}`",
+                    call.command,
+                    call.scope,
+                    call.access,
+                    call.scope,
+                    self.get(call.scope)
+                )
+            })
+            .collect()
+    }
+}
+
+/// A GitHub API call a script makes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiCall {
+    pub command: String,
+    pub scope: &'static str,
+    pub access: Access,
+}
+
+/// The GitHub API calls of a script, made with the `gh` CLI or with curl
+/// or wget on api.github.com, whose scope can be told
+pub fn api_calls(script: &str) -> Vec<ApiCall> {
+    script
+        .lines()
+        .flat_map(|line| line.split(['|', ';']).flat_map(|part| part.split("&&")))
+        .filter_map(|command| {
+            let words: Vec<&str> = command.split_whitespace().collect();
+            let call = match words.first().copied() {
+                Some("gh") => gh_call(&words[1..]),
+                Some("curl") | Some("wget") => http_call(command, &words[1..]),
+                _ => None,
+            }?;
+            Some(ApiCall {
+                command: command.trim().to_string(),
+                scope: call.0,
+                access: call.1,
+            })
+        })
+        .collect()
+}
+
+fn gh_call(args: &[&str]) -> Option<(&'static str, Access)> {
+    let (group, verb) = (*args.first()?, args.get(1).copied().unwrap_or_default());
+    if group == "api" {
+        let endpoint = args[1..]
+            .iter()
+            .find(|arg| arg.trim_start_matches('/').starts_with("repos/"))?;
+        return endpoint_call(endpoint, gh_api_writes(&args[1..]));
+    }
+    let scope = match group {
+        "pr" => "pull-requests",
+        "issue" | "label" => "issues",
+        "release" => "contents",
+        "workflow" | "run" | "cache" => "actions",
+        _ => return None,
+    };
+    let reads = [
+        "list", "view", "status", "diff", "checks", "download", "watch",
+    ];
+    let access = if reads.contains(&verb) {
+        Access::Read
+    } else {
+        Access::Write
+    };
+    Some((scope, access))
+}
+
+fn gh_api_writes(args: &[&str]) -> bool {
+    match args
+        .iter()
+        .position(|arg| *arg == "-X" || *arg == "--method")
+    {
+        Some(idx) => !args
+            .get(idx + 1)
+            .is_some_and(|method| method.eq_ignore_ascii_case("GET")),
+        None => args
+            .iter()
+            .any(|arg| ["-f", "-F", "--field", "--raw-field", "--input"].contains(arg)),
+    }
+}
+
+fn http_call(command: &str, args: &[&str]) -> Option<(&'static str, Access)> {
+    let url = GITHUB_API_URL.captures(command)?;
+    let writes = match args
+        .iter()
+        .position(|arg| *arg == "-X" || *arg == "--request" || *arg == "--method")
+    {
+        Some(idx) => !args
+            .get(idx + 1)
+            .is_some_and(|method| method.trim_matches(['\'', '"']).eq_ignore_ascii_case("GET")),
+        None => args.iter().any(|arg| {
+            arg.starts_with("-d")
+                || arg.starts_with("--data")
+                || arg.starts_with("--post-data")
+                || arg.starts_with("--json")
+        }),
+    };
+    endpoint_call(&url[1], writes)
+}
+
+/// The scope of a `repos/{owner}/{repo}/...` endpoint
+fn endpoint_call(endpoint: &str, writes: bool) -> Option<(&'static str, Access)> {
+    let segments: Vec<&str> = endpoint.trim_start_matches('/').split('/').collect();
+    if segments.first() != Some(&"repos") {
+        return None;
+    }
+    let scope = match segments.get(3).copied() {
+        None => "metadata",
+        Some("issues" | "labels" | "milestones") => "issues",
+        Some("pulls") => "pull-requests",
+        Some(
+            "contents" | "git" | "commits" | "branches" | "releases" | "tags" | "merges"
+            | "compare" | "dispatches",
+        ) => "contents",
+        Some("actions") => "actions",
+        Some("check-runs" | "check-suites") => "checks",
+        Some("deployments" | "environments") => "deployments",
+        Some("statuses") => "statuses",
+        Some("pages") => "pages",
+        Some("code-scanning") => "security-events",
+        _ => return None,
+    };
+    let access = if writes { Access::Write } else { Access::Read };
+    Some((scope, access))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn warns_about_api_calls_the_token_cant_make() {
+        let workflow: Permissions = serde_yaml::from_str("contents: read").unwrap();
+        let job: Permissions =
+            serde_yaml::from_str("contents: read\npull-requests: write").unwrap();
+        let permissions = TokenPermissions::resolve(Some(&workflow), Some(&job));
+        assert_eq!(permissions.get("pull-requests"), Access::Write);
+        assert_eq!(permissions.get("metadata"), Access::Read);
+        assert_eq!(permissions.get("issues"), Access::None);
+
+        let warnings = permissions.check_script(
+            "gh pr comment 1 --body hi && gh issue close 2\n\
+             gh api repos/o/r/releases -f tag_name=v1\n\
+             gh release view v1\n\
+             gh api -X DELETE repos/{owner}/{repo}/labels/bug\n\
+             curl -H \"Authorization: token $T\" https://api.github.com/repos/o/r/statuses/abc -d '{}'",
+        );
+        assert_eq!(
+            warnings,
+            [
+                "`gh issue close 2` needs `issues: write`, but the job's token has `issues: none`",
+                "`gh api repos/o/r/releases -f tag_name=v1` needs `contents: write`, but the job's token has `contents: read`",
+                "`gh api -X DELETE repos/{owner}/{repo}/labels/bug` needs `issues: write`, but the job's token has `issues: none`",
+                "`curl -H \"Authorization: token $T\" https://api.github.com/repos/o/r/statuses/abc -d '{}'` needs `statuses: write`, but the job's token has `statuses: none`",
+            ]
+        );
+
+        // Without permissions: the repository's defaults apply
+        let defaults = TokenPermissions::resolve(None, None);
+        assert!(defaults.check_script("gh issue close 2").is_empty());
+        let read_all = Permissions::All("read-all".to_string());
+        assert_eq!(
+            TokenPermissions::resolve(None, Some(&read_all))
+                .check_script("gh run rerun 1")
+                .len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,135 @@
+// Platforms like `linux/arm64` that job images are pulled for and their
+// containers run as, and whether this host can emulate them
+use std::collections::HashMap;
+use std::path::Path;
+use utils::project::ProjectConfig;
+
+/// Where the kernel lists the interpreters of foreign binaries
+const BINFMT_MISC_DIR: &str = "/proc/sys/fs/binfmt_misc";
+
+/// Platforms of a run's job containers, by job ID. Without one, Docker
+/// picks the host's.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JobPlatforms {
+    pub default: Option<String>,
+    pub jobs: HashMap<String, String>,
+}
+
+impl JobPlatforms {
+    /// The project's platforms, with `platform` over its default one
+    pub fn from_project(project: &ProjectConfig, platform: Option<&str>) -> Result<Self, String> {
+        let default = platform.or(project.platform.as_deref());
+        let platforms = JobPlatforms {
+            default: default.map(str::to_string),
+            jobs: project.platforms.clone().into_iter().collect(),
+        };
+        for platform in platforms.all() {
+            validate(platform)?;
+        }
+        Ok(platforms)
+    }
+
+    /// The platform of a job's containers, its own over the default one
+    pub fn for_job(&self, job: Option<&str>) -> Option<&str> {
+        job.and_then(|job| self.jobs.get(job))
+            .or(self.default.as_ref())
+            .map(String::as_str)
+    }
+
+    /// Every platform the run's containers may use
+    pub fn all(&self) -> impl Iterator<Item = &str> {
+        self.default
+            .iter()
+            .chain(self.jobs.values())
+            .map(String::as_str)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.default.is_none() && self.jobs.is_empty()
+    }
+}
+
+/// Check that a platform looks like `os/arch` or `os/arch/variant`
+pub fn validate(platform: &str) -> Result<(), String> {
+    let parts: Vec<&str> = platform.split('/').collect();
+    if (2..=3).contains(&parts.len()) && parts.iter().all(|part| !part.is_empty()) {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid platform '{}', expected one like linux/arm64",
+            platform
+        ))
+    }
+}
+
+/// Name the kernel and QEMU give a Docker architecture, like `aarch64`
+/// for `arm64`
+fn machine(arch: &str) -> &str {
+    match arch {
+        "amd64" => "x86_64",
+        "arm64" => "aarch64",
+        "386" => "i386",
+        other => other,
+    }
+}
+
+/// A warning when containers of `platform` can't run on this host, because
+/// it has another architecture and no QEMU registered with binfmt_misc to
+/// emulate it. Docker Desktop emulates other architectures itself.
+pub fn emulation_warning(platform: &str) -> Option<String> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+    missing_emulator(platform, std::env::consts::ARCH, Path::new(BINFMT_MISC_DIR))
+}
+
+fn missing_emulator(platform: &str, host_arch: &str, binfmt_misc: &Path) -> Option<String> {
+    let arch = platform.split('/').nth(1)?;
+    let machine = machine(arch);
+    // 32-bit ARM binaries run natively on most 64-bit ARM hosts
+    if machine == host_arch || (machine == "arm" && host_arch == "aarch64") {
+        return None;
+    }
+    if binfmt_misc.join(format!("qemu-{}", machine)).exists() {
+        return None;
+    }
+    Some(format!(
+        "Containers for {} may not start on this {} host, as no QEMU emulator is registered for {}. Install one with `docker run --privileged --rm tonistiigi/binfmt --install {}`",
+        platform, host_arch, machine, arch
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_job_platforms_and_warns_without_an_emulator() {
+        let project = ProjectConfig::parse(
+            "platform = \"linux/amd64\"\n[platforms]\nbuild-arm = \"linux/arm64\"\n",
+        )
+        .unwrap();
+        let platforms = JobPlatforms::from_project(&project, None).unwrap();
+        assert_eq!(platforms.for_job(Some("build-arm")), Some("linux/arm64"));
+        assert_eq!(platforms.for_job(Some("test")), Some("linux/amd64"));
+        let overridden = JobPlatforms::from_project(&project, Some("linux/arm/v7")).unwrap();
+        assert_eq!(overridden.for_job(None), Some("linux/arm/v7"));
+        assert!(JobPlatforms::from_project(&project, Some("arm64")).is_err());
+
+        let binfmt_misc = tempfile::tempdir().unwrap();
+        assert_eq!(
+            missing_emulator("linux/amd64", "x86_64", binfmt_misc.path()),
+            None
+        );
+        assert!(
+            missing_emulator("linux/arm64", "x86_64", binfmt_misc.path())
+                .unwrap()
+                .contains("--install arm64")
+        );
+        std::fs::write(binfmt_misc.path().join("qemu-aarch64"), "enabled").unwrap();
+        assert_eq!(
+            missing_emulator("linux/arm64", "x86_64", binfmt_misc.path()),
+            None
+        );
+    }
+}
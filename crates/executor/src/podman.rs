@@ -0,0 +1,164 @@
+// Podman, reached through its Docker-compatible API socket
+use bollard::{Docker, API_DEFAULT_VERSION};
+use serde_json::Value;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::Duration;
+
+/// Seconds a request to the Podman socket may take
+const REQUEST_TIMEOUT: u64 = 120;
+
+/// How long Podman gets to answer the first ping
+const PING_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Connect to Podman, with an explanation of what to do when that fails
+pub async fn connect() -> Result<Docker, String> {
+    let socket = socket_path()?;
+    // A unix socket, or a named pipe on Windows
+    let docker = Docker::connect_with_socket(
+        &socket.to_string_lossy(),
+        REQUEST_TIMEOUT,
+        API_DEFAULT_VERSION,
+    )
+    .map_err(|e| format!("Failed to connect to Podman at {}: {}", socket.display(), e))?;
+
+    match tokio::time::timeout(PING_TIMEOUT, docker.ping()).await {
+        Ok(Ok(_)) => {
+            logging::info(&format!("Connected to Podman at {}", socket.display()));
+            Ok(docker)
+        }
+        Ok(Err(e)) => Err(format!(
+            "Podman's API socket at {} doesn't answer ({}). {}",
+            socket.display(),
+            e,
+            START_HINT
+        )),
+        Err(_) => Err(format!(
+            "Podman's API socket at {} timed out. {}",
+            socket.display(),
+            START_HINT
+        )),
+    }
+}
+
+#[cfg(any(target_os = "macos", windows))]
+const START_HINT: &str = "Start the Podman machine with `podman machine start`.";
+#[cfg(not(any(target_os = "macos", windows)))]
+const START_HINT: &str = "Start the API service with `systemctl --user start podman.socket`, or run `podman system service --time=0` in another terminal.";
+
+/// Find Podman's API socket: `CONTAINER_HOST`, then the socket of the
+/// running Podman machine on macOS and Windows, or the rootless and rootful
+/// service sockets on Linux
+pub fn socket_path() -> Result<PathBuf, String> {
+    if let Ok(host) = std::env::var("CONTAINER_HOST") {
+        let local = host
+            .strip_prefix("unix://")
+            .or_else(|| host.strip_prefix("npipe://"));
+        return match local {
+            Some(path) => Ok(PathBuf::from(path)),
+            None => Err(format!(
+                "CONTAINER_HOST is {}, but only local unix:// and npipe:// Podman sockets are supported",
+                host
+            )),
+        };
+    }
+
+    let podman = |args: &[&str]| {
+        Command::new("podman")
+            .args(args)
+            .output()
+            .map_err(|_| {
+                "Podman is not installed or not on PATH. Install it from https://podman.io, or pick another runtime with --runtime docker or --runtime emulation".to_string()
+            })
+    };
+
+    // On macOS and Windows containers run in a VM, whose socket is forwarded
+    // to the host
+    if cfg!(any(target_os = "macos", windows)) {
+        let output = podman(&["machine", "inspect"])?;
+        if !output.status.success() {
+            return Err(
+                "No Podman machine found. Create and start one with `podman machine init && podman machine start`"
+                    .to_string(),
+            );
+        }
+        return machine_socket(&String::from_utf8_lossy(&output.stdout));
+    }
+
+    let mut candidates = Vec::new();
+    let info = podman(&["info", "--format", "{{.Host.RemoteSocket.Path}}"])?;
+    let reported = String::from_utf8_lossy(&info.stdout).trim().to_string();
+    if info.status.success() && !reported.is_empty() {
+        candidates.push(PathBuf::from(reported.trim_start_matches("unix://")));
+    }
+    if let Ok(runtime_dir) = std::env::var("XDG_RUNTIME_DIR") {
+        candidates.push(PathBuf::from(runtime_dir).join("podman/podman.sock"));
+    }
+    candidates.push(PathBuf::from("/run/podman/podman.sock"));
+
+    candidates
+        .into_iter()
+        .find(|socket| socket.exists())
+        .ok_or_else(|| format!("Podman's API socket isn't running. {}", START_HINT))
+}
+
+/// The socket of the first running machine in `podman machine inspect` output
+fn machine_socket(inspect: &str) -> Result<PathBuf, String> {
+    let machines: Vec<Value> = serde_json::from_str(inspect)
+        .map_err(|e| format!("Unexpected `podman machine inspect` output: {}", e))?;
+    let Some(first) = machines.first() else {
+        return Err(
+            "No Podman machine found. Create and start one with `podman machine init && podman machine start`"
+                .to_string(),
+        );
+    };
+
+    let running = machines
+        .iter()
+        .find(|machine| machine["State"].as_str() == Some("running"));
+    let Some(machine) = running else {
+        let name = first["Name"].as_str().unwrap_or("podman-machine-default");
+        let state = first["State"].as_str().unwrap_or("not running");
+        return Err(format!(
+            "The Podman machine '{}' is {}. Start it with `podman machine start {}`",
+            name, state, name
+        ));
+    };
+    // Windows machines are reached through a named pipe instead
+    let connection = if cfg!(windows) {
+        "PodmanPipe"
+    } else {
+        "PodmanSocket"
+    };
+    machine["ConnectionInfo"][connection]["Path"]
+        .as_str()
+        .map(PathBuf::from)
+        .ok_or_else(|| {
+            "The running Podman machine doesn't expose an API socket. Recreate it with `podman machine init`"
+                .to_string()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(not(windows))]
+    fn finds_the_running_machine_socket() {
+        let stopped = r#"[{"Name": "podman-machine-default", "State": "stopped",
+            "ConnectionInfo": {"PodmanSocket": {"Path": "/tmp/podman.sock"}}}]"#;
+        let error = machine_socket(stopped).unwrap_err();
+        assert!(error.contains("'podman-machine-default' is stopped"));
+        assert!(error.contains("podman machine start podman-machine-default"));
+
+        let running = stopped.replace("stopped", "running");
+        assert_eq!(
+            machine_socket(&running).unwrap(),
+            PathBuf::from("/tmp/podman.sock")
+        );
+        assert!(machine_socket("[]")
+            .unwrap_err()
+            .contains("podman machine init"));
+    }
+}
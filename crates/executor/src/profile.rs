@@ -0,0 +1,281 @@
+// Where the time of a run went: its slowest steps, and how much of it was
+// spent pulling images and starting containers instead of running commands
+use crate::engine::{JobResult, Overhead};
+use serde::Serialize;
+use std::fmt;
+use std::time::Duration;
+
+/// Steps listed as the slowest
+const SLOWEST_STEPS: usize = 10;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Profile {
+    pub jobs: Vec<JobProfile>,
+    /// Slowest steps first
+    pub slowest_steps: Vec<StepProfile>,
+    #[serde(flatten)]
+    pub breakdown: Breakdown,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JobProfile {
+    pub name: String,
+    #[serde(rename = "duration_secs", serialize_with = "secs")]
+    pub duration: Duration,
+    #[serde(flatten)]
+    pub breakdown: Breakdown,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StepProfile {
+    pub job: String,
+    pub step: String,
+    #[serde(rename = "duration_secs", serialize_with = "secs")]
+    pub duration: Duration,
+    pub overhead: Overhead,
+}
+
+/// Time of one or more jobs by what it was spent on. Jobs running side by
+/// side each count in full.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct Breakdown {
+    #[serde(rename = "image_pull_secs", serialize_with = "secs")]
+    pub image_pull: Duration,
+    #[serde(rename = "container_startup_secs", serialize_with = "secs")]
+    pub container_startup: Duration,
+    /// Job time outside its steps and containers, like copying the
+    /// workspace and cleaning up
+    #[serde(rename = "job_setup_secs", serialize_with = "secs")]
+    pub job_setup: Duration,
+    /// Time the steps' commands and actions ran
+    #[serde(rename = "script_secs", serialize_with = "secs")]
+    pub script: Duration,
+}
+
+impl Breakdown {
+    fn of_job(job: &JobResult) -> Self {
+        let steps: Duration = job.steps.iter().map(|step| step.duration).sum();
+        let step_pulls: Duration = job.steps.iter().map(|s| s.overhead.image_pull).sum();
+        let step_startups: Duration = job.steps.iter().map(|s| s.overhead.container_startup).sum();
+        Breakdown {
+            image_pull: job.overhead.image_pull + step_pulls,
+            container_startup: job.overhead.container_startup + step_startups,
+            job_setup: job
+                .duration
+                .saturating_sub(steps)
+                .saturating_sub(job.overhead.total()),
+            script: steps.saturating_sub(step_pulls + step_startups),
+        }
+    }
+
+    pub fn total(&self) -> Duration {
+        self.overhead() + self.script
+    }
+
+    /// Everything but the script time
+    pub fn overhead(&self) -> Duration {
+        self.image_pull + self.container_startup + self.job_setup
+    }
+
+    fn add(&mut self, other: &Breakdown) {
+        self.image_pull += other.image_pull;
+        self.container_startup += other.container_startup;
+        self.job_setup += other.job_setup;
+        self.script += other.script;
+    }
+}
+
+pub fn profile(jobs: &[JobResult]) -> Profile {
+    let mut breakdown = Breakdown::default();
+    let jobs_profiles = jobs
+        .iter()
+        .map(|job| {
+            let job_breakdown = Breakdown::of_job(job);
+            breakdown.add(&job_breakdown);
+            JobProfile {
+                name: job.name.clone(),
+                duration: job.duration,
+                breakdown: job_breakdown,
+            }
+        })
+        .collect();
+
+    let mut slowest_steps: Vec<StepProfile> = jobs
+        .iter()
+        .flat_map(|job| {
+            job.steps.iter().map(|step| StepProfile {
+                job: job.name.clone(),
+                step: step.name.clone(),
+                duration: step.duration,
+                overhead: step.overhead,
+            })
+        })
+        .filter(|step| !step.duration.is_zero())
+        .collect();
+    slowest_steps.sort_by_key(|step| std::cmp::Reverse(step.duration));
+    slowest_steps.truncate(SLOWEST_STEPS);
+
+    Profile {
+        jobs: jobs_profiles,
+        slowest_steps,
+        breakdown,
+    }
+}
+
+fn secs<S: serde::Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64())
+}
+
+fn format_secs(duration: Duration) -> String {
+    format!("{:.1}s", duration.as_secs_f64())
+}
+
+fn percent(part: Duration, total: Duration) -> String {
+    if total.is_zero() {
+        return "-".to_string();
+    }
+    format!("{:.0}%", part.as_secs_f64() / total.as_secs_f64() * 100.0)
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name_width = self
+            .jobs
+            .iter()
+            .map(|job| job.name.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(3);
+
+        writeln!(f, "Profile")?;
+        writeln!(
+            f,
+            "  {:<name_width$}  {:>9}  {:>9}  {:>9}",
+            "Job", "total", "script", "overhead"
+        )?;
+        for job in &self.jobs {
+            writeln!(
+                f,
+                "  {:<name_width$}  {:>9}  {:>9}  {:>9}  ({})",
+                job.name,
+                format_secs(job.duration),
+                format_secs(job.breakdown.script),
+                format_secs(job.breakdown.overhead()),
+                percent(job.breakdown.overhead(), job.breakdown.total())
+            )?;
+        }
+
+        if !self.slowest_steps.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "  Slowest steps")?;
+            for step in &self.slowest_steps {
+                write!(
+                    f,
+                    "  {:>9}  {} › {}",
+                    format_secs(step.duration),
+                    step.job,
+                    step.step
+                )?;
+                if !step.overhead.total().is_zero() {
+                    write!(
+                        f,
+                        "  (pull {}, startup {})",
+                        format_secs(step.overhead.image_pull),
+                        format_secs(step.overhead.container_startup)
+                    )?;
+                }
+                writeln!(f)?;
+            }
+        }
+
+        let total = self.breakdown.total();
+        writeln!(f)?;
+        writeln!(f, "  Time spent, adding up jobs that ran side by side")?;
+        for (label, time) in [
+            ("Image pulls", self.breakdown.image_pull),
+            ("Container startup", self.breakdown.container_startup),
+            ("Other job setup", self.breakdown.job_setup),
+            ("Running steps", self.breakdown.script),
+        ] {
+            writeln!(
+                f,
+                "  {:<18} {:>9}  {:>4}",
+                label,
+                format_secs(time),
+                percent(time, total)
+            )?;
+        }
+        write!(f, "  {:<18} {:>9}", "Total", format_secs(total))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{JobStatus, StepResult, StepStatus};
+    use std::collections::HashMap;
+
+    fn step(name: &str, secs: u64, overhead: Overhead) -> StepResult {
+        StepResult {
+            name: name.to_string(),
+            status: StepStatus::Success,
+            output: String::new(),
+            output_file: None,
+            exit_code: Some(0),
+            duration: Duration::from_secs(secs),
+            overhead,
+            annotations: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn splits_job_time_into_overhead_and_script_time() {
+        let job = JobResult {
+            name: "test".to_string(),
+            status: JobStatus::Success,
+            steps: vec![
+                step(
+                    "Checkout",
+                    2,
+                    Overhead {
+                        image_pull: Duration::from_secs(1),
+                        container_startup: Duration::ZERO,
+                    },
+                ),
+                step(
+                    "Run tests",
+                    10,
+                    Overhead {
+                        image_pull: Duration::ZERO,
+                        container_startup: Duration::from_secs(2),
+                    },
+                ),
+            ],
+            logs: String::new(),
+            outputs: HashMap::new(),
+            duration: Duration::from_secs(20),
+            overhead: Overhead {
+                image_pull: Duration::from_secs(4),
+                container_startup: Duration::from_secs(3),
+            },
+            summary: String::new(),
+        };
+
+        let profile = profile(&[job]);
+        assert_eq!(
+            profile.breakdown,
+            Breakdown {
+                image_pull: Duration::from_secs(5),
+                container_startup: Duration::from_secs(5),
+                job_setup: Duration::from_secs(1),
+                script: Duration::from_secs(9),
+            }
+        );
+        assert_eq!(profile.breakdown.total(), Duration::from_secs(20));
+        assert_eq!(profile.slowest_steps[0].step, "Run tests");
+
+        let text = profile.to_string();
+        assert!(text.contains("test › Run tests  (pull 0.0s, startup 2.0s)"));
+        assert!(text.contains("Running steps           9.0s   45%"));
+    }
+}
@@ -0,0 +1,463 @@
+use crate::engine::{ExecutionResult, JobStatus, StepStatus};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::Write;
+use std::sync::{mpsc, Arc, Mutex};
+
+/// Events emitted while a workflow runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ExecutionEvent {
+    /// Number of steps the run will go through, after matrix and dependency expansion
+    Planned {
+        total_steps: usize,
+    },
+    JobStarted {
+        job: String,
+    },
+    StepStarted {
+        job: String,
+        step: String,
+    },
+    /// Output of a step that ran, sent before it completes
+    StepOutput {
+        job: String,
+        step: String,
+        output: String,
+    },
+    /// A step finished or was skipped
+    StepCompleted {
+        job: String,
+        step: String,
+        status: StepStatus,
+    },
+    JobCompleted {
+        job: String,
+        status: JobStatus,
+    },
+    /// Progress of pulling an image, like `Downloading 12.1MB/40.3MB`
+    ImagePull {
+        image: String,
+        status: String,
+    },
+}
+
+/// Gets the events of a run as they happen, from the task running the job.
+/// Every method does nothing unless implemented.
+pub trait ExecutionObserver: Send + Sync {
+    fn run_planned(&self, _total_steps: usize) {}
+    fn job_started(&self, _job: &str) {}
+    fn step_started(&self, _job: &str, _step: &str) {}
+    fn step_output(&self, _job: &str, _step: &str, _output: &str) {}
+    fn step_completed(&self, _job: &str, _step: &str, _status: &StepStatus) {}
+    fn job_completed(&self, _job: &str, _status: &JobStatus) {}
+    fn image_pull(&self, _image: &str, _status: &str) {}
+
+    /// Called with every event, passing it to the method for its kind
+    fn on_event(&self, event: &ExecutionEvent) {
+        match event {
+            ExecutionEvent::Planned { total_steps } => self.run_planned(*total_steps),
+            ExecutionEvent::JobStarted { job } => self.job_started(job),
+            ExecutionEvent::StepStarted { job, step } => self.step_started(job, step),
+            ExecutionEvent::StepOutput { job, step, output } => self.step_output(job, step, output),
+            ExecutionEvent::StepCompleted { job, step, status } => {
+                self.step_completed(job, step, status)
+            }
+            ExecutionEvent::JobCompleted { job, status } => self.job_completed(job, status),
+            ExecutionEvent::ImagePull { image, status } => self.image_pull(image, status),
+        }
+    }
+}
+
+/// Lets one observer be shared, e.g. to read what it collected after a run
+impl<T: ExecutionObserver + ?Sized> ExecutionObserver for Arc<T> {
+    fn on_event(&self, event: &ExecutionEvent) {
+        (**self).on_event(event)
+    }
+}
+
+/// Forwards every event to a receiver, e.g. on the TUI's thread
+impl ExecutionObserver for mpsc::Sender<ExecutionEvent> {
+    fn on_event(&self, event: &ExecutionEvent) {
+        // The listener going away shouldn't interrupt the run
+        let _ = self.send(event.clone());
+    }
+}
+
+/// Writes jobs and steps starting and finishing to the log, which the CLI
+/// prints in verbose mode and the TUI shows in its Logs tab
+#[derive(Debug, Default)]
+pub struct LogObserver;
+
+impl ExecutionObserver for LogObserver {
+    fn job_started(&self, job: &str) {
+        logging::info(&format!("Executing job: {}", job));
+    }
+
+    fn step_started(&self, _job: &str, step: &str) {
+        logging::info(&format!("  Executing step: {}", step));
+    }
+
+    fn step_completed(&self, job: &str, step: &str, status: &StepStatus) {
+        // Steps of jobs that don't run have no name
+        if !step.is_empty() {
+            logging::debug(&format!(
+                "Step '{}' of job {} finished: {:?}",
+                step, job, status
+            ));
+        }
+    }
+
+    fn job_completed(&self, job: &str, status: &JobStatus) {
+        logging::info(&format!("Job {} finished: {:?}", job, status));
+    }
+
+    fn image_pull(&self, image: &str, status: &str) {
+        logging::debug(&format!("Pulling {}: {}", image, status));
+    }
+}
+
+/// Writes each event as a line of JSON, for tools following a run
+pub struct JsonLinesObserver {
+    output: Mutex<Box<dyn Write + Send>>,
+}
+
+impl JsonLinesObserver {
+    pub fn new(output: impl Write + Send + 'static) -> Self {
+        JsonLinesObserver {
+            output: Mutex::new(Box::new(output)),
+        }
+    }
+}
+
+impl ExecutionObserver for JsonLinesObserver {
+    fn on_event(&self, event: &ExecutionEvent) {
+        let line = serde_json::to_string(event).expect("events serialize");
+        let mut output = self
+            .output
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        // A closed pipe shouldn't interrupt the run
+        let _ = writeln!(output, "{}", line).and_then(|_| output.flush());
+    }
+}
+
+/// Prints a run as plain, timestamped lines for CI logs, with each step's
+/// output between `::group::` and `::endgroup::` lines, which GitHub Actions
+/// folds. A step's output is printed at once, so the output of jobs running
+/// side by side doesn't interleave.
+pub struct CiObserver {
+    output: Mutex<Box<dyn Write + Send>>,
+}
+
+impl CiObserver {
+    pub fn new(output: impl Write + Send + 'static) -> Self {
+        CiObserver {
+            output: Mutex::new(Box::new(output)),
+        }
+    }
+
+    fn write(&self, text: &str) {
+        let mut output = self
+            .output
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        // A closed pipe shouldn't interrupt the run
+        let _ = output
+            .write_all(text.as_bytes())
+            .and_then(|_| output.flush());
+    }
+
+    fn event(&self, event: &str, fields: &[(&str, &str)]) {
+        let mut line = format!(
+            "{} {}",
+            chrono::Utc::now().format("%Y-%m-%dT%H:%M:%S%.3fZ"),
+            event
+        );
+        for (name, value) in fields {
+            line.push_str(&format!(" {}={:?}", name, value));
+        }
+        line.push('\n');
+        self.write(&line);
+    }
+
+    /// Print the annotations the run's steps made and how the run ended
+    pub fn run_completed(&self, result: &ExecutionResult) {
+        for job in &result.jobs {
+            for step in &job.steps {
+                for annotation in &step.annotations {
+                    let level = format!("{:?}", annotation.level).to_lowercase();
+                    self.event(
+                        "annotation",
+                        &[
+                            ("level", &level),
+                            ("job", &job.name),
+                            ("step", &step.name),
+                            ("message", &annotation.to_string()),
+                        ],
+                    );
+                }
+            }
+        }
+        let status = match result.failure_details {
+            None => "success",
+            Some(_) => "failure",
+        };
+        self.event("run_completed", &[("status", status)]);
+    }
+}
+
+impl ExecutionObserver for CiObserver {
+    fn run_planned(&self, total_steps: usize) {
+        self.event("run_planned", &[("steps", &total_steps.to_string())]);
+    }
+
+    fn job_started(&self, job: &str) {
+        self.event("job_started", &[("job", job)]);
+    }
+
+    fn step_started(&self, job: &str, step: &str) {
+        self.event("step_started", &[("job", job), ("step", step)]);
+    }
+
+    fn step_output(&self, job: &str, step: &str, output: &str) {
+        let mut group = format!("::group::{} / {}\n", job, step);
+        for line in strip_ansi(output).lines() {
+            group.push_str(line);
+            group.push('\n');
+        }
+        group.push_str("::endgroup::\n");
+        self.write(&group);
+    }
+
+    fn step_completed(&self, job: &str, step: &str, status: &StepStatus) {
+        // Steps of jobs that don't run have no name
+        if !step.is_empty() {
+            let status = format!("{:?}", status).to_lowercase();
+            self.event(
+                "step_completed",
+                &[("job", job), ("step", step), ("status", &status)],
+            );
+        }
+    }
+
+    fn job_completed(&self, job: &str, status: &JobStatus) {
+        let status = format!("{:?}", status).to_lowercase();
+        self.event("job_completed", &[("job", job), ("status", &status)]);
+    }
+
+    // Pull progress is left out, differing from one run to the next
+}
+
+/// `text` without the escape sequences that color it or move the cursor
+fn strip_ansi(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            plain.push(c);
+            continue;
+        }
+        match chars.next() {
+            // CSI: parameters up to a final byte between @ and ~
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // OSC: up to BEL or ESC \
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' {
+                        break;
+                    }
+                    if c == '\x1b' && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    plain
+}
+
+/// Passes a run's events to its observers, if it has any
+#[derive(Clone, Default)]
+pub struct ProgressReporter {
+    observers: Vec<Arc<dyn ExecutionObserver>>,
+}
+
+impl fmt::Debug for ProgressReporter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProgressReporter")
+            .field("observers", &self.observers.len())
+            .finish()
+    }
+}
+
+impl ProgressReporter {
+    /// A reporter sending every event to `sender`
+    pub fn new(sender: mpsc::Sender<ExecutionEvent>) -> Self {
+        Self::default().with_observer(sender)
+    }
+
+    /// Also pass events to `observer`, after the observers added before
+    pub fn with_observer(mut self, observer: impl ExecutionObserver + 'static) -> Self {
+        self.observers.push(Arc::new(observer));
+        self
+    }
+
+    pub fn emit(&self, event: ExecutionEvent) {
+        for observer in &self.observers {
+            observer.on_event(&event);
+        }
+    }
+
+    pub(crate) fn step_started(&self, job: &str, step: &str) {
+        self.emit(ExecutionEvent::StepStarted {
+            job: job.to_string(),
+            step: step.to_string(),
+        });
+    }
+
+    /// Send the output of a step that printed any
+    pub(crate) fn step_output(&self, job: &str, step: &str, output: &str) {
+        if !output.is_empty() {
+            self.emit(ExecutionEvent::StepOutput {
+                job: job.to_string(),
+                step: step.to_string(),
+                output: output.to_string(),
+            });
+        }
+    }
+
+    pub(crate) fn step_completed(&self, job: &str, step: &str, status: &StepStatus) {
+        self.emit(ExecutionEvent::StepCompleted {
+            job: job.to_string(),
+            step: step.to_string(),
+            status: status.clone(),
+        });
+    }
+
+    pub(crate) fn image_pull(&self, image: &str, status: &str) {
+        self.emit(ExecutionEvent::ImagePull {
+            image: image.to_string(),
+            status: status.to_string(),
+        });
+    }
+}
+
+/// Steps completed against the total planned, built up from execution events
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Progress {
+    pub completed_steps: usize,
+    pub total_steps: usize,
+}
+
+impl Progress {
+    pub fn apply(&mut self, event: &ExecutionEvent) {
+        match event {
+            ExecutionEvent::Planned { total_steps } => self.total_steps = *total_steps,
+            ExecutionEvent::StepCompleted { .. } => self.completed_steps += 1,
+            _ => {}
+        }
+    }
+
+    /// Completion between 0.0 and 1.0
+    pub fn fraction(&self) -> f64 {
+        if self.total_steps == 0 {
+            0.0
+        } else {
+            (self.completed_steps as f64 / self.total_steps as f64).min(1.0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_completed_steps() {
+        let (tx, rx) = mpsc::channel();
+        let reporter = ProgressReporter::new(tx);
+        reporter.emit(ExecutionEvent::Planned { total_steps: 4 });
+        reporter.step_completed("build", "Step 1", &StepStatus::Success);
+        reporter.step_completed("build", "Step 2", &StepStatus::Skipped);
+
+        let mut progress = Progress::default();
+        for event in rx.try_iter() {
+            progress.apply(&event);
+        }
+        assert_eq!(progress.completed_steps, 2);
+        assert_eq!(progress.fraction(), 0.5);
+
+        // Reporting without a listener is a no-op
+        ProgressReporter::default().emit(ExecutionEvent::Planned { total_steps: 1 });
+    }
+
+    #[derive(Default)]
+    struct Outputs(Mutex<Vec<String>>);
+
+    impl ExecutionObserver for Outputs {
+        fn step_output(&self, job: &str, step: &str, output: &str) {
+            let line = format!("{}/{}: {}", job, step, output);
+            self.0.lock().unwrap().push(line);
+        }
+    }
+
+    #[test]
+    fn passes_events_to_every_observer() {
+        let outputs = Arc::new(Outputs::default());
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let reporter = ProgressReporter::default()
+            .with_observer(outputs.clone())
+            .with_observer(JsonLinesObserver::new(file.reopen().unwrap()));
+        reporter.step_started("build", "Compile");
+        reporter.step_output("build", "Compile", "");
+        reporter.step_output("build", "Compile", "ok");
+        reporter.image_pull("rust:1", "Pull complete");
+
+        assert_eq!(*outputs.0.lock().unwrap(), ["build/Compile: ok"]);
+        let lines = std::fs::read_to_string(file.path()).unwrap();
+        let types: Vec<String> = lines
+            .lines()
+            .map(|line| {
+                serde_json::from_str::<serde_json::Value>(line).unwrap()["type"].to_string()
+            })
+            .collect();
+        assert_eq!(
+            types,
+            ["\"step_started\"", "\"step_output\"", "\"image_pull\""]
+        );
+    }
+
+    #[test]
+    fn prints_plain_lines_and_groups_for_ci() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let reporter =
+            ProgressReporter::default().with_observer(CiObserver::new(file.reopen().unwrap()));
+        reporter.step_started("build", "Run tests");
+        reporter.step_output(
+            "build",
+            "Run tests",
+            "\x1b[32mok\x1b[0m\n\x1b]8;;url\x07link",
+        );
+        reporter.step_completed("build", "Run tests", &StepStatus::Success);
+        reporter.image_pull("rust:1", "Pull complete");
+
+        let text = std::fs::read_to_string(file.path()).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines[0].ends_with(" step_started job=\"build\" step=\"Run tests\""));
+        assert_eq!(
+            lines[1..5],
+            ["::group::build / Run tests", "ok", "link", "::endgroup::"]
+        );
+        assert!(lines[5].ends_with(" status=\"success\""));
+        assert_eq!(lines.len(), 6);
+    }
+}
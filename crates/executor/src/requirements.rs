@@ -0,0 +1,238 @@
+// Runner features that actions need, checked before a run starts
+use crate::engine::RuntimeType;
+use models::github::Workflow;
+use models::{Diagnostic, DiagnosticSeverity};
+use std::process::Command;
+
+/// Node.js version each major version of an action runs on, from the first
+/// major version that needed it. Actions wrkflw emulates itself, like
+/// checkout and the artifact actions, aren't listed.
+const NODE_RUNTIMES: &[(&str, u32, u32)] = &[
+    ("actions/cache", 3, 16),
+    ("actions/cache", 4, 20),
+    ("actions/setup-node", 3, 16),
+    ("actions/setup-node", 4, 20),
+    ("actions/setup-node", 5, 24),
+    ("actions/setup-python", 4, 16),
+    ("actions/setup-python", 5, 20),
+    ("actions/setup-python", 6, 24),
+    ("actions/setup-go", 4, 20),
+    ("actions/setup-go", 6, 24),
+    ("actions/setup-java", 3, 16),
+    ("actions/setup-java", 4, 20),
+    ("actions/setup-java", 5, 24),
+    ("actions/github-script", 6, 16),
+    ("actions/github-script", 7, 20),
+    ("actions/github-script", 8, 24),
+];
+
+/// Actions that drive `docker buildx`
+const BUILDX_ACTIONS: &[&str] = &["docker/build-push-action", "docker/bake-action"];
+
+/// Node.js versions JavaScript actions run on in Docker mode, see
+/// `ActionMetadata::node_image`
+const DOCKER_NODE_VERSIONS: &[u32] = &[16, 20];
+
+/// Something an action needs from the runner
+#[derive(Debug, Clone, PartialEq)]
+pub enum Requirement {
+    /// Node.js of at least this major version
+    Node(u32),
+    Buildx,
+}
+
+/// What the runner of a run offers
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunnerCapabilities {
+    /// Name of the runner in warnings, e.g. "Docker mode"
+    pub name: String,
+    pub node_versions: Vec<u32>,
+    pub buildx: bool,
+}
+
+impl RunnerCapabilities {
+    pub fn for_runtime(runtime_type: &RuntimeType) -> Self {
+        match runtime_type {
+            RuntimeType::Docker | RuntimeType::Podman => RunnerCapabilities {
+                name: match runtime_type {
+                    RuntimeType::Podman => "Podman mode".to_string(),
+                    _ => "Docker mode".to_string(),
+                },
+                node_versions: DOCKER_NODE_VERSIONS.to_vec(),
+                // Job containers don't get a Docker daemon
+                buildx: false,
+            },
+            RuntimeType::Emulation | RuntimeType::Bubblewrap => Self::host(),
+        }
+    }
+
+    /// Detect what the machine running emulation mode has installed
+    pub fn host() -> Self {
+        let output = |program: &str, args: &[&str]| {
+            Command::new(program)
+                .args(args)
+                .output()
+                .ok()
+                .filter(|output| output.status.success())
+                .map(|output| String::from_utf8_lossy(&output.stdout).to_string())
+        };
+        let node_version = output("node", &["--version"]).and_then(|version| {
+            version
+                .trim()
+                .trim_start_matches('v')
+                .split('.')
+                .next()?
+                .parse()
+                .ok()
+        });
+        RunnerCapabilities {
+            name: "emulation mode".to_string(),
+            node_versions: node_version.into_iter().collect(),
+            buildx: output("docker", &["buildx", "version"]).is_some(),
+        }
+    }
+
+    /// Why `requirement` can't be met, if it can't
+    fn unmet(&self, requirement: &Requirement) -> Option<String> {
+        match requirement {
+            Requirement::Node(needed) if !self.node_versions.iter().any(|v| v >= needed) => {
+                let available = match self.node_versions.as_slice() {
+                    [] => "no Node.js".to_string(),
+                    versions => format!(
+                        "Node.js {}",
+                        versions
+                            .iter()
+                            .map(u32::to_string)
+                            .collect::<Vec<_>>()
+                            .join(" or ")
+                    ),
+                };
+                Some(format!(
+                    "needs Node.js {}, but {} has {}",
+                    needed, self.name, available
+                ))
+            }
+            Requirement::Buildx if !self.buildx => Some(format!(
+                "needs docker buildx, which {} doesn't provide",
+                self.name
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// What running the action in `uses` needs from the runner
+pub fn action_requirements(uses: &str) -> Vec<Requirement> {
+    let (action, version) = uses.split_once('@').unwrap_or((uses, ""));
+    let mut requirements = Vec::new();
+
+    // Only tags like v4 or v4.1.0 tell which version runs
+    let major = version
+        .strip_prefix('v')
+        .and_then(|v| v.split('.').next())
+        .and_then(|v| v.parse::<u32>().ok());
+    if let Some(major) = major {
+        let node = NODE_RUNTIMES
+            .iter()
+            .filter(|(name, first, _)| *name == action && *first <= major)
+            .max_by_key(|(_, first, _)| *first);
+        if let Some((_, _, node)) = node {
+            requirements.push(Requirement::Node(*node));
+        }
+    }
+    if BUILDX_ACTIONS.contains(&action) {
+        requirements.push(Requirement::Buildx);
+    }
+    requirements
+}
+
+/// Warnings for steps whose actions need something `runner` doesn't have
+pub fn check_workflow(workflow: &Workflow, runner: &RunnerCapabilities) -> Vec<Diagnostic> {
+    let mut job_names: Vec<&String> = workflow.jobs.keys().collect();
+    job_names.sort();
+
+    let mut warnings = Vec::new();
+    for job_name in job_names {
+        for (idx, step) in workflow.jobs[job_name].steps.iter().enumerate() {
+            let Some(uses) = &step.uses else {
+                continue;
+            };
+            for requirement in action_requirements(uses) {
+                if let Some(reason) = runner.unmet(&requirement) {
+                    warnings.push(Diagnostic::new(
+                        DiagnosticSeverity::Warning,
+                        "unmet-requirement",
+                        format!("jobs.{}.steps[{}].uses", job_name, idx),
+                        format!("Job '{}', step {}: {} {}", job_name, idx + 1, uses, reason),
+                    ));
+                }
+            }
+        }
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_actions_against_the_runner() {
+        assert_eq!(
+            action_requirements("actions/cache@v4.2.0"),
+            [Requirement::Node(20)]
+        );
+        assert_eq!(
+            action_requirements("actions/setup-node@v5"),
+            [Requirement::Node(24)]
+        );
+        assert!(
+            action_requirements("actions/cache@0c45773b623bea8c8e75f6c82b208c3cf94ea4f9")
+                .is_empty()
+        );
+        assert_eq!(
+            action_requirements("docker/build-push-action@v6"),
+            [Requirement::Buildx]
+        );
+
+        let workflow: Workflow = serde_yaml::from_str(
+            r#"
+name: CI
+on: push
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@v5
+      - uses: actions/cache@v4
+        with:
+          path: target
+          key: build
+      - uses: actions/setup-node@v5
+      - uses: docker/build-push-action@v6
+"#,
+        )
+        .unwrap();
+
+        let docker = RunnerCapabilities::for_runtime(&RuntimeType::Docker);
+        let warnings = check_workflow(&workflow, &docker);
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(
+            warnings[0].message,
+            "Job 'build', step 3: actions/setup-node@v5 needs Node.js 24, but Docker mode has Node.js 16 or 20"
+        );
+        assert_eq!(warnings[0].path, "jobs.build.steps[2].uses");
+        assert!(warnings[1].message.contains("needs docker buildx"));
+
+        let old_host = RunnerCapabilities {
+            name: "emulation mode".to_string(),
+            node_versions: vec![18],
+            buildx: true,
+        };
+        let warnings = check_workflow(&workflow, &old_host);
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0]
+            .message
+            .contains("actions/cache@v4 needs Node.js 20, but emulation mode has Node.js 18"));
+    }
+}
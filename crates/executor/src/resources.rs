@@ -0,0 +1,150 @@
+// CPU and memory limits of job containers, from `--cpus`/`--memory` and a
+// project's `[resources]`, and telling when a step ran out of memory
+use std::collections::HashMap;
+use utils::project::{ResourceLimitsConfig, ResourcesConfig};
+
+/// Exit code of a process killed with SIGKILL, which the OOM killer sends
+const SIGKILL_EXIT_CODE: i32 = 137;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ResourceLimits {
+    /// Billionths of a CPU, like Docker's `NanoCpus`
+    pub nano_cpus: Option<i64>,
+    /// Bytes
+    pub memory: Option<i64>,
+}
+
+impl ResourceLimits {
+    /// Limits from values like `docker run`'s, e.g. `1.5` CPUs and `512m`
+    pub fn parse(cpus: Option<f64>, memory: Option<&str>) -> Result<Self, String> {
+        let nano_cpus = match cpus {
+            Some(cpus) if cpus.is_finite() && cpus > 0.0 => Some((cpus * 1e9) as i64),
+            Some(cpus) => return Err(format!("Invalid CPU limit {}", cpus)),
+            None => None,
+        };
+        let memory = match memory {
+            Some(size) => match crate::docker::parse_size(size) {
+                Some(bytes) if bytes > 0 => Some(bytes),
+                _ => return Err(format!("Invalid memory limit '{}'", size)),
+            },
+            None => None,
+        };
+        Ok(ResourceLimits { nano_cpus, memory })
+    }
+
+    fn from_config(config: &ResourceLimitsConfig) -> Result<Self, String> {
+        Self::parse(config.cpus, config.memory.as_deref())
+    }
+
+    /// These limits, with `fallback`'s where they have none
+    pub fn or(self, fallback: ResourceLimits) -> Self {
+        ResourceLimits {
+            nano_cpus: self.nano_cpus.or(fallback.nano_cpus),
+            memory: self.memory.or(fallback.memory),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nano_cpus.is_none() && self.memory.is_none()
+    }
+}
+
+/// Limits of a run's job containers, by job ID
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JobResources {
+    pub default: ResourceLimits,
+    pub jobs: HashMap<String, ResourceLimits>,
+}
+
+impl JobResources {
+    pub fn from_project(config: &ResourcesConfig) -> Result<Self, String> {
+        let jobs = config
+            .jobs
+            .iter()
+            .map(|(job, limits)| {
+                ResourceLimits::from_config(limits)
+                    .map(|limits| (job.clone(), limits))
+                    .map_err(|e| format!("{} of job '{}'", e, job))
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(JobResources {
+            default: ResourceLimits::from_config(&config.limits)?,
+            jobs,
+        })
+    }
+
+    /// The limits of a job's containers, its own over the default ones
+    pub fn for_job(&self, job: Option<&str>) -> ResourceLimits {
+        job.and_then(|job| self.jobs.get(job))
+            .map_or(self.default, |limits| limits.or(self.default))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.default.is_empty() && self.jobs.values().all(ResourceLimits::is_empty)
+    }
+}
+
+/// Memory in a readable unit, e.g. `512 MiB`
+pub fn format_memory(bytes: i64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if size.fract() == 0.0 {
+        format!("{} {}", size, UNITS[unit])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// What to tell about a step that was killed for running out of `memory`.
+/// Only the container's main process is known to be OOM-killed; other
+/// processes of a limited container are taken to be when they get SIGKILL.
+pub fn oom_message(oom_killed: bool, exit_code: i32, memory: Option<i64>) -> Option<String> {
+    let limit = memory.filter(|bytes| *bytes > 0);
+    if !oom_killed && (exit_code != SIGKILL_EXIT_CODE || limit.is_none()) {
+        return None;
+    }
+    Some(match limit {
+        Some(bytes) => format!(
+            "The step was killed for running out of memory (limit {})",
+            format_memory(bytes)
+        ),
+        None => "The step was killed for running out of memory".to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_job_limits_and_tells_about_oom_kills() {
+        let config = utils::project::ProjectConfig::parse(
+            "[resources]\ncpus = 4\nmemory = \"16g\"\n\n[resources.jobs.build]\nmemory = \"512m\"\n",
+        )
+        .unwrap();
+        let resources = JobResources::from_project(&config.resources).unwrap();
+        assert_eq!(
+            resources.for_job(Some("build")),
+            ResourceLimits {
+                nano_cpus: Some(4_000_000_000),
+                memory: Some(512 << 20),
+            }
+        );
+        assert_eq!(resources.for_job(Some("test")).memory, Some(16 << 30));
+        assert!(ResourceLimits::parse(Some(0.0), None).is_err());
+        assert!(ResourceLimits::parse(None, Some("lots")).is_err());
+
+        assert_eq!(
+            oom_message(false, 137, Some(512 << 20)).as_deref(),
+            Some("The step was killed for running out of memory (limit 512 MiB)")
+        );
+        assert_eq!(oom_message(false, 137, None), None);
+        assert_eq!(oom_message(false, 1, Some(512 << 20)), None);
+        assert_eq!(format_memory(1536 << 20), "1.5 GiB");
+    }
+}
@@ -0,0 +1,192 @@
+// Retrying Docker API calls that fail because the daemon hiccuped, such as
+// a pull cut off with an EOF or a 500 from a daemon under load
+use bollard::errors::Error;
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+/// How often and how long to wait before trying a failed Docker call again
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// Extra attempts after the first one; 0 turns retries off
+    pub retries: u32,
+    /// Wait before the first retry, doubled for every one after it
+    pub initial_delay: Duration,
+    /// Longest wait between two attempts
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            retries: 3,
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(8),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// The default backoff with `retries` extra attempts
+    pub fn with_retries(retries: u32) -> Self {
+        RetryPolicy {
+            retries,
+            ..Self::default()
+        }
+    }
+
+    /// Run `call` until it succeeds, fails with an error that retrying won't
+    /// fix, or runs out of attempts
+    pub async fn run<T, F, Fut>(&self, what: &str, mut call: F) -> Result<T, Error>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        let mut attempt = 0;
+        loop {
+            match call().await {
+                Err(e) if attempt < self.retries && is_transient(&e) => {
+                    let delay = self.delay(attempt);
+                    attempt += 1;
+                    logging::warning(&format!(
+                        "{} failed: {}. Retrying in {} ms ({}/{})",
+                        what,
+                        e,
+                        delay.as_millis(),
+                        attempt,
+                        self.retries
+                    ));
+                    tokio::time::sleep(delay).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Exponential backoff, up to half of it random, so that parallel jobs
+    /// retrying the same daemon don't all come back at once
+    fn delay(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .initial_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+        let jitter = RandomState::new().build_hasher().finish() % 1000;
+        backoff / 2 + backoff / 2 * jitter as u32 / 1000
+    }
+}
+
+/// Whether `error` looks like the daemon or the connection to it having a
+/// bad moment, rather than the request itself being wrong
+pub fn is_transient(error: &Error) -> bool {
+    match error {
+        Error::DockerResponseServerError {
+            status_code,
+            message,
+        } => {
+            matches!(status_code, 408 | 429 | 502 | 503 | 504) || {
+                *status_code >= 500 && transient_message(message)
+            }
+        }
+        Error::DockerStreamError { error } => transient_message(error),
+        Error::RequestTimeoutError | Error::IOError { .. } | Error::HyperResponseError { .. } => {
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Messages of network errors the daemon passes on from registries
+fn transient_message(message: &str) -> bool {
+    let message = message.to_lowercase();
+    [
+        "eof",
+        "connection reset",
+        "connection refused",
+        "timeout",
+        "timed out",
+        "temporarily unavailable",
+        "too many requests",
+        "broken pipe",
+    ]
+    .iter()
+    .any(|pattern| message.contains(pattern))
+}
+
+/// Apply `timeout` to a single attempt, failing it as a timeout that
+/// [`RetryPolicy::run`] tries again
+pub async fn with_timeout<T>(
+    timeout: Duration,
+    call: impl Future<Output = Result<T, Error>>,
+) -> Result<T, Error> {
+    tokio::time::timeout(timeout, call)
+        .await
+        .unwrap_or(Err(Error::RequestTimeoutError))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn server_error(status_code: u16, message: &str) -> Error {
+        Error::DockerResponseServerError {
+            status_code,
+            message: message.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_only_transient_errors() {
+        assert!(is_transient(&server_error(503, "service unavailable")));
+        assert!(is_transient(&server_error(500, "unexpected EOF")));
+        assert!(!is_transient(&server_error(
+            500,
+            "invalid reference format"
+        )));
+        assert!(!is_transient(&server_error(404, "No such image: nope")));
+        assert!(is_transient(&Error::DockerStreamError {
+            error: "read tcp: connection reset by peer".to_string()
+        }));
+
+        let policy = RetryPolicy {
+            retries: 2,
+            initial_delay: Duration::ZERO,
+            max_delay: Duration::ZERO,
+        };
+        let calls = AtomicU32::new(0);
+        let result = policy
+            .run("Pulling alpine", || async {
+                match calls.fetch_add(1, Ordering::SeqCst) {
+                    0 => Err(server_error(500, "unexpected EOF")),
+                    n => Ok(n),
+                }
+            })
+            .await;
+        assert_eq!(result.unwrap(), 1);
+
+        calls.store(0, Ordering::SeqCst);
+        let result: Result<(), _> = policy
+            .run("Pulling alpine", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(server_error(503, "busy"))
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        calls.store(0, Ordering::SeqCst);
+        let result: Result<(), _> = policy
+            .run("Pulling nope", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(server_error(404, "No such image"))
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let policy = RetryPolicy::default();
+        assert!(policy.delay(10) <= policy.max_delay);
+        assert!(policy.delay(0) >= policy.initial_delay / 2);
+    }
+}
@@ -0,0 +1,164 @@
+// Images that jobs run in by their `runs-on` labels, when they run in Docker
+// or Podman
+use models::github::Job;
+use std::collections::HashMap;
+
+/// Image of `ubuntu-latest`, and of labels without an image of their own
+pub const DEFAULT_IMAGE: &str = "catthehacker/ubuntu:act-latest";
+
+/// Mapping a label to this runs its jobs on the host, as in emulation mode,
+/// while the other jobs run in containers
+pub const HOST: &str = "emulation";
+
+/// Built-in image for a runner label. Windows and macOS runners can't run in
+/// a container, so their jobs get the default Linux image.
+pub fn builtin_image(label: &str) -> &'static str {
+    let label = label.trim().to_lowercase();
+    match label.as_str() {
+        // The tags of catthehacker's images follow the hosted runners'
+        "ubuntu-latest" => DEFAULT_IMAGE,
+        "ubuntu-24.04" => "catthehacker/ubuntu:act-24.04",
+        "ubuntu-22.04" => "catthehacker/ubuntu:act-22.04",
+        "ubuntu-20.04" => "catthehacker/ubuntu:act-20.04",
+        "ubuntu-18.04" => "catthehacker/ubuntu:act-18.04",
+
+        // Nearly everything the hosted runners have installed
+        "ubuntu-latest-large" => "catthehacker/ubuntu:full-latest",
+        "ubuntu-22.04-large" => "catthehacker/ubuntu:full-22.04",
+        "ubuntu-20.04-large" => "catthehacker/ubuntu:full-20.04",
+        "ubuntu-18.04-large" => "catthehacker/ubuntu:full-18.04",
+
+        // Language-specific runners
+        "python-3.11" => "python:3.11-slim",
+        "python-3.10" => "python:3.10-slim",
+        "python-3.9" => "python:3.9-slim",
+        "python-3.8" => "python:3.8-slim",
+        "node-18" => "node:18-slim",
+        "node-16" => "node:16-slim",
+        "java-11" => "eclipse-temurin:11-jdk",
+        "java-8" => "eclipse-temurin:8-jdk",
+        "go-1.20" => "golang:1.20-slim",
+        "go-1.19" => "golang:1.19-slim",
+        "dotnet-6.0" => "mcr.microsoft.com/dotnet/sdk:6.0",
+        "dotnet-5.0" => "mcr.microsoft.com/dotnet/sdk:5.0",
+
+        // Older names of the default image's tags
+        "ubuntu-latest-medium" => DEFAULT_IMAGE,
+        "ubuntu-22.04-medium" => "catthehacker/ubuntu:act-22.04",
+        "ubuntu-20.04-medium" => "catthehacker/ubuntu:act-20.04",
+        "ubuntu-18.04-medium" => "catthehacker/ubuntu:act-18.04",
+
+        // Other versions get the newest image of their language
+        _ if label.starts_with("python") => "python:3.11-slim",
+        _ if label.starts_with("node") => "node:20-slim",
+        _ if label.starts_with("java") => "eclipse-temurin:17-jdk",
+        _ if label.starts_with("go") => "golang:1.21-slim",
+        _ if label.starts_with("dotnet") => "mcr.microsoft.com/dotnet/sdk:7.0",
+        _ => DEFAULT_IMAGE,
+    }
+}
+
+/// Image a job's steps run in: its `container:`, or else the image of the
+/// first of its labels in `mapping`, or else the built-in one of its first
+/// label
+pub fn job_image(job: &Job, mapping: &HashMap<String, String>) -> String {
+    if let Some(container) = &job.container {
+        return container.image().to_string();
+    }
+    mapped_image(job, mapping).cloned().unwrap_or_else(|| {
+        builtin_image(labels(job).first().map_or("", String::as_str)).to_string()
+    })
+}
+
+/// Whether a job's labels map it to the host rather than to an image
+pub fn runs_on_host(job: &Job, mapping: &HashMap<String, String>) -> bool {
+    job.container.is_none() && mapped_image(job, mapping).is_some_and(|image| image == HOST)
+}
+
+fn mapped_image<'a>(job: &Job, mapping: &'a HashMap<String, String>) -> Option<&'a String> {
+    labels(job)
+        .iter()
+        .find_map(|label| mapping.get(label.trim()))
+}
+
+fn labels(job: &Job) -> &[String] {
+    job.runs_on
+        .as_ref()
+        .map_or(&[][..], |runs_on| runs_on.labels())
+}
+
+/// A warning for a job that asks for a Windows or macOS runner but will run
+/// in a Linux container, because no image is mapped to any of its labels
+pub fn foreign_os_warning(
+    job_name: &str,
+    job: &Job,
+    mapping: &HashMap<String, String>,
+) -> Option<String> {
+    if job.container.is_some() {
+        return None;
+    }
+    if mapped_image(job, mapping).is_some() {
+        return None;
+    }
+    let label = labels(job).iter().find(|label| {
+        let label = label.trim().to_lowercase();
+        label.starts_with("windows") || label.starts_with("macos")
+    })?;
+    Some(format!(
+        "Job '{}' runs on {}, which can't run in a container; it runs in {} instead. Map the label to an image with --runner-image {}=IMAGE",
+        job_name,
+        label,
+        job_image(job, mapping),
+        label
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(yaml: &str) -> Job {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn maps_labels_to_images() {
+        let mapping = HashMap::from([(
+            "self-hosted".to_string(),
+            "ghcr.io/acme/runner:1".to_string(),
+        )]);
+
+        let ubuntu = job("runs-on: ubuntu-22.04\nsteps: []");
+        assert_eq!(
+            job_image(&ubuntu, &mapping),
+            "catthehacker/ubuntu:act-22.04"
+        );
+        assert_eq!(foreign_os_warning("build", &ubuntu, &mapping), None);
+
+        // Any mapped label wins over the built-in images
+        let self_hosted = job("runs-on: [linux, self-hosted]\nsteps: []");
+        assert_eq!(job_image(&self_hosted, &mapping), "ghcr.io/acme/runner:1");
+        assert_eq!(
+            job_image(&self_hosted, &HashMap::new()),
+            DEFAULT_IMAGE,
+            "unknown labels get the default image"
+        );
+
+        let macos = job("runs-on: macos-14\nsteps: []");
+        assert_eq!(job_image(&macos, &mapping), DEFAULT_IMAGE);
+        let warning = foreign_os_warning("test", &macos, &mapping).unwrap();
+        assert!(warning.contains("--runner-image macos-14=IMAGE"));
+        let mapped = HashMap::from([("macos-14".to_string(), "my/mac:1".to_string())]);
+        assert_eq!(foreign_os_warning("test", &macos, &mapped), None);
+        assert!(!runs_on_host(&macos, &mapped));
+        let host = HashMap::from([("gpu".to_string(), HOST.to_string())]);
+        assert!(runs_on_host(
+            &job("runs-on: [self-hosted, gpu]\nsteps: []"),
+            &host
+        ));
+
+        let container = job("runs-on: windows-latest\ncontainer: alpine:3\nsteps: []");
+        assert_eq!(job_image(&container, &mapping), "alpine:3");
+        assert_eq!(foreign_os_warning("test", &container, &mapping), None);
+    }
+}
@@ -0,0 +1,144 @@
+// Shells `run` steps run in: GitHub's built-in ones with the flags GitHub
+// starts them with, and custom templates like `perl {0}`
+
+/// The shell of a `run` step, from its `shell:` or `defaults.run.shell`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Shell {
+    /// No shell given: bash when there is one, sh otherwise
+    Default,
+    Bash,
+    Sh,
+    Pwsh,
+    Python,
+    /// `cmd` and `powershell`, which only Windows runners have
+    Windows(String),
+    /// A command with `{0}` where the script's path goes
+    Custom(String),
+}
+
+/// Where a custom shell template takes the script's path
+const SCRIPT_PLACEHOLDER: &str = "{0}";
+
+/// Pick bash when it's on the PATH and sh otherwise, like GitHub does for
+/// steps without a shell
+const DEFAULT_SHELL_LAUNCHER: &str =
+    "if command -v bash >/dev/null 2>&1; then exec bash -e \"$0\"; else exec sh -e \"$0\"; fi";
+
+impl Shell {
+    pub fn parse(shell: Option<&str>) -> Result<Shell, String> {
+        let Some(shell) = shell.map(str::trim) else {
+            return Ok(Shell::Default);
+        };
+        match shell {
+            "bash" => Ok(Shell::Bash),
+            "sh" => Ok(Shell::Sh),
+            "pwsh" => Ok(Shell::Pwsh),
+            "python" => Ok(Shell::Python),
+            "cmd" | "powershell" => Ok(Shell::Windows(shell.to_string())),
+            _ if shell.contains(SCRIPT_PLACEHOLDER) => Ok(Shell::Custom(shell.to_string())),
+            _ => Err(format!(
+                "Unknown shell '{}'; use bash, sh, pwsh, python, or a command with {{0}} for the script's path",
+                shell
+            )),
+        }
+    }
+
+    /// Extension of the script file, which some shells go by
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Shell::Pwsh => "ps1",
+            Shell::Python => "py",
+            Shell::Windows(shell) if shell == "cmd" => "cmd",
+            Shell::Windows(_) => "ps1",
+            _ => "sh",
+        }
+    }
+
+    /// The script file's contents for a step's `run`. PowerShell stops at
+    /// the first error and exits with the last native command's exit code.
+    pub fn script(&self, run: &str) -> String {
+        match self {
+            Shell::Pwsh => format!(
+                "$ErrorActionPreference = 'stop'\n{}\nif ((Test-Path -LiteralPath variable:\\LASTEXITCODE)) {{ exit $LASTEXITCODE }}\n",
+                run
+            ),
+            _ => run.to_string(),
+        }
+    }
+
+    /// The command running the script at `script`. Only in containers can
+    /// the default shell look for bash when the step starts; on the host
+    /// `bash_on_host` tells whether there is one.
+    pub fn command(
+        &self,
+        script: &str,
+        in_container: bool,
+        bash_on_host: bool,
+    ) -> Result<Vec<String>, String> {
+        let args: Vec<&str> = match self {
+            Shell::Default if in_container => vec!["sh", "-c", DEFAULT_SHELL_LAUNCHER, script],
+            Shell::Default if bash_on_host => vec!["bash", "-e", script],
+            Shell::Default => vec!["sh", "-e", script],
+            Shell::Bash => vec!["bash", "--noprofile", "--norc", "-eo", "pipefail", script],
+            Shell::Sh => vec!["sh", "-e", script],
+            Shell::Pwsh => vec!["pwsh", "-command", ". '{0}'"],
+            Shell::Python => vec!["python", script],
+            Shell::Windows(shell) => {
+                return Err(format!(
+                    "The '{}' shell only runs on Windows runners, and wrkflw runs steps on Linux",
+                    shell
+                ))
+            }
+            Shell::Custom(template) => template.split_whitespace().collect(),
+        };
+        Ok(args
+            .into_iter()
+            .map(|arg| arg.replace(SCRIPT_PLACEHOLDER, script))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_commands_like_github() {
+        let script = "/github/file_commands/step_0.sh";
+        let command = |shell: &str| {
+            Shell::parse(Some(shell))
+                .and_then(|shell| shell.command(script, true, true))
+                .map(|args| args.join(" "))
+        };
+
+        assert_eq!(
+            command("bash").unwrap(),
+            "bash --noprofile --norc -eo pipefail /github/file_commands/step_0.sh"
+        );
+        assert_eq!(
+            command("perl -w {0} --verbose").unwrap(),
+            "perl -w /github/file_commands/step_0.sh --verbose"
+        );
+        assert_eq!(
+            Shell::Pwsh.command("/tmp/step_1.ps1", true, true).unwrap(),
+            ["pwsh", "-command", ". '/tmp/step_1.ps1'"]
+        );
+        assert!(command("cmd").unwrap_err().contains("Windows"));
+        assert!(command("zsh")
+            .unwrap_err()
+            .starts_with("Unknown shell 'zsh'"));
+
+        let default = Shell::parse(None).unwrap();
+        assert_eq!(
+            default.command(script, true, false).unwrap()[..2],
+            ["sh", "-c"]
+        );
+        assert_eq!(
+            default.command(script, false, false).unwrap(),
+            ["sh", "-e", script]
+        );
+        assert!(Shell::Pwsh
+            .script("Write-Output hi")
+            .starts_with("$ErrorActionPreference"));
+    }
+}
@@ -0,0 +1,177 @@
+// Pausing a run before its steps, to confirm each of them or to stop at
+// breakpoints, and asking whoever drives the run what to do next
+use std::fmt;
+use std::str::FromStr;
+use std::sync::mpsc;
+use tokio::sync::oneshot;
+
+/// What to do with the step a run paused before
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepDecision {
+    Run,
+    Skip,
+    /// Cancel the run, skipping this step and everything after it
+    Abort,
+}
+
+/// A step to pause before, given as `job:step`, where the step is its name
+/// or its number counting from 1. The job also matches its matrix
+/// combinations and `parallel:` instances.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Breakpoint {
+    pub job: String,
+    pub step: String,
+}
+
+impl Breakpoint {
+    fn matches(&self, job: &str, step: &str, step_number: usize) -> bool {
+        let job_matches = job == self.job
+            || job
+                .strip_prefix(self.job.as_str())
+                .is_some_and(|rest| rest.starts_with(' '));
+        job_matches && (step == self.step || self.step == step_number.to_string())
+    }
+}
+
+impl FromStr for Breakpoint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some((job, step)) if !job.trim().is_empty() && !step.trim().is_empty() => {
+                Ok(Breakpoint {
+                    job: job.trim().to_string(),
+                    step: step.trim().to_string(),
+                })
+            }
+            _ => Err(format!(
+                "'{}' is not a breakpoint; give it as JOB:STEP, with the step's name or number",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.job, self.step)
+    }
+}
+
+/// A step the run paused before, waiting for a decision. Dropping it lets
+/// the step run.
+#[derive(Debug)]
+pub struct PausedStep {
+    pub job: String,
+    pub step: String,
+    reply: oneshot::Sender<StepDecision>,
+}
+
+impl PausedStep {
+    pub fn decide(self, decision: StepDecision) {
+        // The run may have ended in the meantime
+        let _ = self.reply.send(decision);
+    }
+}
+
+/// Where a run pauses and who it asks, through the receiver handed out by
+/// `new`. Runs without one never pause.
+#[derive(Debug, Clone, Default)]
+pub struct StepControl {
+    /// Pause before every step
+    single_step: bool,
+    breakpoints: Vec<Breakpoint>,
+    sender: Option<mpsc::Sender<PausedStep>>,
+}
+
+impl StepControl {
+    pub fn new(
+        single_step: bool,
+        breakpoints: Vec<Breakpoint>,
+    ) -> (Self, mpsc::Receiver<PausedStep>) {
+        let (sender, receiver) = mpsc::channel();
+        let control = StepControl {
+            single_step,
+            breakpoints,
+            sender: Some(sender),
+        };
+        (control, receiver)
+    }
+
+    fn pauses_at(&self, job: &str, step: &str, step_number: usize) -> bool {
+        self.sender.is_some()
+            && (self.single_step
+                || self
+                    .breakpoints
+                    .iter()
+                    .any(|breakpoint| breakpoint.matches(job, step, step_number)))
+    }
+
+    /// Wait for a decision on a step that's about to run, if the run pauses
+    /// before it
+    pub(crate) async fn before_step(&self, job: &str, step: &str, idx: usize) -> StepDecision {
+        let Some(sender) = self
+            .sender
+            .as_ref()
+            .filter(|_| self.pauses_at(job, step, idx + 1))
+        else {
+            return StepDecision::Run;
+        };
+        let (reply, decision) = oneshot::channel();
+        let paused = PausedStep {
+            job: job.to_string(),
+            step: step.to_string(),
+            reply,
+        };
+        logging::info(&format!("Paused before step '{}' of job '{}'", step, job));
+        if sender.send(paused).is_err() {
+            return StepDecision::Run;
+        }
+        decision.await.unwrap_or(StepDecision::Run)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn pauses_at_breakpoints_until_decided() {
+        let breakpoint: Breakpoint = "test: Run tests".parse().unwrap();
+        assert_eq!(breakpoint.to_string(), "test:Run tests");
+        assert!("test".parse::<Breakpoint>().is_err());
+
+        let (control, paused) =
+            StepControl::new(false, vec![breakpoint, "build:2".parse().unwrap()]);
+        assert!(control.pauses_at("test (ubuntu, 18)", "Run tests", 3));
+        assert!(!control.pauses_at("tests", "Run tests", 3));
+        assert!(control.pauses_at("build", "Compile", 2));
+        assert!(!control.pauses_at("build", "Compile", 1));
+
+        let decider = std::thread::spawn(move || {
+            let step = paused.recv().unwrap();
+            assert_eq!(
+                (step.job.as_str(), step.step.as_str()),
+                ("test", "Run tests")
+            );
+            step.decide(StepDecision::Skip);
+            // A dropped step runs
+            drop(paused.recv().unwrap());
+        });
+        assert_eq!(
+            control.before_step("test", "Run tests", 0).await,
+            StepDecision::Skip
+        );
+        assert_eq!(
+            control.before_step("build", "Compile", 1).await,
+            StepDecision::Run
+        );
+        assert_eq!(
+            control.before_step("lint", "Clippy", 0).await,
+            StepDecision::Run
+        );
+        decider.join().unwrap();
+
+        assert!(!StepControl::default().pauses_at("test", "Run tests", 1));
+    }
+}
@@ -0,0 +1,155 @@
+// Job summaries steps write to `$GITHUB_STEP_SUMMARY`, turned from Markdown
+// into plain lines for terminals
+use crate::engine::JobResult;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::fmt;
+
+static LINK: Lazy<Regex> = Lazy::new(|| Regex::new(r"!?\[([^\]]*)\]\(([^)\s]*)[^)]*\)").unwrap());
+static HTML_TAG: Lazy<Regex> = Lazy::new(|| Regex::new(r"</?[A-Za-z][^>]*>").unwrap());
+static TABLE_RULE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\|?(\s*:?-+:?\s*\|)+\s*:?-*:?\s*$").unwrap());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SummaryStyle {
+    Heading,
+    Text,
+    Quote,
+    Code,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SummaryLine {
+    pub style: SummaryStyle,
+    pub text: String,
+}
+
+/// Render a summary's Markdown line by line: headings, lists, quotes, code
+/// blocks and tables keep their shape, inline markup is dropped and links
+/// show their target
+pub fn render(markdown: &str) -> Vec<SummaryLine> {
+    let mut lines = Vec::new();
+    let mut in_code = false;
+    for line in markdown.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code = !in_code;
+            continue;
+        }
+        let (style, text) = if in_code {
+            (SummaryStyle::Code, line.to_string())
+        } else if let Some(heading) = heading(trimmed) {
+            (SummaryStyle::Heading, inline(heading))
+        } else if let Some(quote) = trimmed.strip_prefix('>') {
+            (
+                SummaryStyle::Quote,
+                format!("│ {}", inline(quote.trim_start())),
+            )
+        } else if TABLE_RULE.is_match(trimmed) {
+            (SummaryStyle::Text, trimmed.replace(['-', ':'], "─"))
+        } else if let Some(item) = ["- ", "* ", "+ "]
+            .iter()
+            .find_map(|marker| trimmed.strip_prefix(marker))
+        {
+            let indent = &line[..line.len() - trimmed.len()];
+            (SummaryStyle::Text, format!("{}• {}", indent, inline(item)))
+        } else {
+            (SummaryStyle::Text, inline(line))
+        };
+        lines.push(SummaryLine { style, text });
+    }
+    lines
+}
+
+fn heading(line: &str) -> Option<&str> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    let text = line[level..].strip_prefix(' ')?;
+    (1..=6)
+        .contains(&level)
+        .then(|| text.trim_end_matches('#').trim())
+}
+
+fn inline(text: &str) -> String {
+    let text = LINK.replace_all(text, |caps: &regex::Captures| match (&caps[1], &caps[2]) {
+        (label, "") => label.to_string(),
+        ("", url) => url.to_string(),
+        (label, url) => format!("{} ({})", label, url),
+    });
+    let text = HTML_TAG.replace_all(&text, "");
+    text.replace("**", "").replace("__", "").replace('`', "")
+}
+
+/// The summaries of a run's jobs that wrote one
+pub struct RunSummary<'a> {
+    jobs: Vec<(&'a str, Vec<SummaryLine>)>,
+}
+
+impl RunSummary<'_> {
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+}
+
+pub fn run_summary(jobs: &[JobResult]) -> RunSummary<'_> {
+    RunSummary {
+        jobs: jobs
+            .iter()
+            .filter(|job| !job.summary.trim().is_empty())
+            .map(|job| (job.name.as_str(), render(&job.summary)))
+            .collect(),
+    }
+}
+
+impl fmt::Display for RunSummary<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, (job, lines)) in self.jobs.iter().enumerate() {
+            if idx > 0 {
+                writeln!(f)?;
+            }
+            writeln!(f, "Summary of {}", job)?;
+            for line in lines {
+                match line.style {
+                    SummaryStyle::Heading => {
+                        writeln!(f, "  {}", line.text)?;
+                        writeln!(f, "  {}", "─".repeat(line.text.chars().count()))?;
+                    }
+                    SummaryStyle::Code => writeln!(f, "      {}", line.text)?,
+                    _ => writeln!(f, "  {}", line.text)?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_markdown_as_plain_lines() {
+        let lines = render(
+            "## Test results :rocket:\n\n- **42** passed\n  - see [the report](https://example.com/r)\n> Flaky: `net`\n\n| Suite | Time |\n|---|:--:|\n| unit | 3s |\n```\ncargo test\n```\n<details>done</details>",
+        );
+        let text: Vec<&str> = lines.iter().map(|line| line.text.as_str()).collect();
+        assert_eq!(
+            text,
+            [
+                "Test results :rocket:",
+                "",
+                "• 42 passed",
+                "  • see the report (https://example.com/r)",
+                "│ Flaky: net",
+                "",
+                "| Suite | Time |",
+                "|───|────|",
+                "| unit | 3s |",
+                "cargo test",
+                "done",
+            ]
+        );
+        assert_eq!(lines[0].style, SummaryStyle::Heading);
+        assert_eq!(lines[9].style, SummaryStyle::Code);
+        assert!(heading("#hashtag").is_none());
+    }
+}
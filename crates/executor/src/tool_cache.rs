@@ -0,0 +1,185 @@
+// Named volumes that keep package manager caches like ~/.cargo and ~/.npm
+// between runs of a job in Docker or Podman
+use bollard::volume::RemoveVolumeOptions;
+use bollard::Docker;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use utils::hash::fnv1a;
+
+/// Label every cache volume has, holding the tool it caches
+const TOOL_LABEL: &str = "wrkflw.tool-cache";
+const WORKFLOW_LABEL: &str = "wrkflw.workflow";
+const JOB_LABEL: &str = "wrkflw.job";
+
+/// A cache location in the runner images, which run as root
+struct ToolCache {
+    tool: &'static str,
+    path: &'static str,
+    /// Variable pointing the tool at `path` in images that move it, like
+    /// GOPATH=/go in the golang images
+    env: Option<&'static str>,
+}
+
+const TOOL_CACHES: &[ToolCache] = &[
+    ToolCache {
+        tool: "cargo-registry",
+        path: "/root/.cargo/registry",
+        env: None,
+    },
+    ToolCache {
+        tool: "cargo-git",
+        path: "/root/.cargo/git",
+        env: None,
+    },
+    ToolCache {
+        tool: "npm",
+        path: "/root/.npm",
+        env: Some("npm_config_cache"),
+    },
+    ToolCache {
+        tool: "pip",
+        path: "/root/.cache/pip",
+        env: Some("PIP_CACHE_DIR"),
+    },
+    ToolCache {
+        tool: "go-mod",
+        path: "/root/go/pkg/mod",
+        env: Some("GOMODCACHE"),
+    },
+];
+
+/// A volume mounted over one tool's cache directory in a job's containers
+#[derive(Debug, Clone, PartialEq)]
+pub struct CacheVolume {
+    pub name: String,
+    /// Where it is mounted in the container
+    pub target: PathBuf,
+    pub labels: HashMap<String, String>,
+}
+
+/// The cache volumes of a workflow's job. Matrix combinations of a job share
+/// them, as the tools lock their caches.
+pub fn job_volumes(workflow: &str, job: &str) -> Vec<CacheVolume> {
+    // The hash keeps `a-b` + `c` and `a` + `b-c` apart
+    let key = format!(
+        "{}-{}-{:08x}",
+        slug(workflow),
+        slug(job),
+        fnv1a(&format!("{}\n{}", workflow, job)) as u32
+    );
+    TOOL_CACHES
+        .iter()
+        .map(|cache| CacheVolume {
+            name: format!("wrkflw-cache-{}-{}", key, cache.tool),
+            target: PathBuf::from(cache.path),
+            labels: HashMap::from([
+                (TOOL_LABEL.to_string(), cache.tool.to_string()),
+                (WORKFLOW_LABEL.to_string(), workflow.to_string()),
+                (JOB_LABEL.to_string(), job.to_string()),
+            ]),
+        })
+        .collect()
+}
+
+/// Variables that point tools at the mounted cache directories
+pub fn cache_env() -> impl Iterator<Item = (&'static str, &'static str)> {
+    TOOL_CACHES
+        .iter()
+        .filter_map(|cache| Some((cache.env?, cache.path)))
+}
+
+pub(crate) fn slug(name: &str) -> String {
+    let slug: String = name
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c.to_ascii_lowercase(),
+            false => '-',
+        })
+        .collect();
+    let slug = slug.trim_matches('-');
+    slug.chars().take(40).collect()
+}
+
+/// A cache volume as Docker or Podman reports it
+#[derive(Debug, Clone, PartialEq)]
+pub struct CacheVolumeInfo {
+    pub name: String,
+    pub workflow: String,
+    pub job: String,
+    pub tool: String,
+    /// Bytes used, when the runtime says
+    pub size: Option<u64>,
+}
+
+/// Every cache volume, sorted by workflow, job and tool
+pub async fn list_volumes(docker: &Docker) -> Result<Vec<CacheVolumeInfo>, String> {
+    // The disk usage endpoint is the one that reports volume sizes
+    let usage = docker
+        .df()
+        .await
+        .map_err(|e| format!("Failed to list volumes: {}", e))?;
+    let mut volumes: Vec<CacheVolumeInfo> = usage
+        .volumes
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|volume| {
+            let label = |key: &str| volume.labels.get(key).cloned().unwrap_or_default();
+            Some(CacheVolumeInfo {
+                tool: volume.labels.get(TOOL_LABEL)?.clone(),
+                workflow: label(WORKFLOW_LABEL),
+                job: label(JOB_LABEL),
+                size: volume
+                    .usage_data
+                    .and_then(|usage| u64::try_from(usage.size).ok()),
+                name: volume.name,
+            })
+        })
+        .collect();
+    volumes.sort_by(|a, b| (&a.workflow, &a.job, &a.tool).cmp(&(&b.workflow, &b.job, &b.tool)));
+    Ok(volumes)
+}
+
+/// Remove the cache volumes of `workflow` and `job`, or all of them when
+/// not given. Returns the names of the removed volumes.
+pub async fn remove_volumes(
+    docker: &Docker,
+    workflow: Option<&str>,
+    job: Option<&str>,
+) -> Result<Vec<String>, String> {
+    let mut removed = Vec::new();
+    for volume in list_volumes(docker).await? {
+        if workflow.is_some_and(|w| w != volume.workflow) || job.is_some_and(|j| j != volume.job) {
+            continue;
+        }
+        docker
+            .remove_volume(&volume.name, Some(RemoveVolumeOptions { force: false }))
+            .await
+            .map_err(|e| format!("Failed to remove volume {}: {}", volume.name, e))?;
+        removed.push(volume.name);
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_volumes_by_workflow_and_job() {
+        let volumes = job_volumes("CI / Build", "test");
+        assert_eq!(volumes.len(), TOOL_CACHES.len());
+        assert!(volumes[0].name.starts_with("wrkflw-cache-ci---build-test-"));
+        assert!(volumes[0].name.ends_with("-cargo-registry"));
+        assert_eq!(volumes[0].target, PathBuf::from("/root/.cargo/registry"));
+        assert_eq!(volumes[2].labels[JOB_LABEL], "test");
+
+        // Same names every time, different ones for names that slug alike
+        assert_eq!(job_volumes("CI / Build", "test"), volumes);
+        assert_ne!(
+            job_volumes("a-b", "c")[0].name,
+            job_volumes("a", "b-c")[0].name
+        );
+
+        assert!(cache_env().any(|(name, path)| name == "GOMODCACHE" && path == "/root/go/pkg/mod"));
+    }
+}
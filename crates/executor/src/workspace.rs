@@ -0,0 +1,433 @@
+// Copying or syncing the project into a job's workspace, leaving out what
+// git, Docker and wrkflw are told to ignore
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::WalkBuilder;
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use utils::hash::fnv1a;
+
+/// File of gitignore-style patterns for what jobs shouldn't get, next to
+/// `.gitignore` files in any directory of the project
+pub const IGNORE_FILE: &str = ".wrkflwignore";
+
+/// Directories never copied: git's objects, and wrkflw's own caches
+const SKIPPED_DIRS: &[&str] = &[".git", ".wrkflw"];
+
+/// Environment variable that overrides where job workspaces are kept
+pub const WORKSPACES_DIR_ENV: &str = "WRKFLW_WORKSPACES_DIR";
+
+/// A lock left by a run that's gone for longer than this is taken over
+const STALE_LOCK: Duration = Duration::from_secs(12 * 60 * 60);
+
+/// What copying or syncing a workspace did
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CopyStats {
+    /// Files copied, and their size
+    pub files: u64,
+    pub bytes: u64,
+    /// Files that were up to date already
+    pub unchanged: u64,
+    /// Files and directories the project doesn't have (any more)
+    pub removed: u64,
+}
+
+/// Copy the files of the project in `from` to `to`, except those its
+/// `.gitignore`, `.dockerignore` and `.wrkflwignore` files leave out
+pub fn copy_workspace(from: &Path, to: &Path) -> io::Result<CopyStats> {
+    transfer(from, to, false)
+}
+
+/// Make `to` hold what [`copy_workspace`] would copy, like `rsync --delete`:
+/// files whose size and modification time match are left alone, others are
+/// copied with the project's modification time, and anything the project
+/// doesn't have is removed, including what an earlier job added
+pub fn sync_workspace(from: &Path, to: &Path) -> io::Result<CopyStats> {
+    transfer(from, to, true)
+}
+
+fn transfer(from: &Path, to: &Path, prune: bool) -> io::Result<CopyStats> {
+    let mut stats = CopyStats::default();
+    // Checking out into a workspace that is the project itself
+    if fs::canonicalize(from)? == fs::canonicalize(to)? {
+        return Ok(stats);
+    }
+
+    let mut synced: HashSet<PathBuf> = HashSet::new();
+    for entry in project_files(from) {
+        let entry = entry.map_err(io::Error::other)?;
+        let Ok(relative) = entry.path().strip_prefix(from) else {
+            continue;
+        };
+        if relative.as_os_str().is_empty() {
+            continue;
+        }
+        let Some(file_type) = entry.file_type() else {
+            continue;
+        };
+        let dest = to.join(relative);
+        synced.insert(relative.to_path_buf());
+        let existing = fs::symlink_metadata(&dest).ok();
+
+        if file_type.is_dir() {
+            if existing.as_ref().is_some_and(|meta| !meta.is_dir()) {
+                fs::remove_file(&dest)?;
+            }
+            fs::create_dir_all(&dest)?;
+        } else if file_type.is_symlink() {
+            if existing.is_some() && fs::read_link(&dest).ok() == fs::read_link(entry.path()).ok() {
+                stats.unchanged += 1;
+                continue;
+            }
+            remove(&dest)?;
+            copy_symlink(entry.path(), &dest)?;
+        } else {
+            let source = entry.metadata().map_err(io::Error::other)?;
+            match existing {
+                Some(meta) if meta.is_file() && meta.len() == source.len() => {
+                    if meta.modified().ok() == source.modified().ok() {
+                        stats.unchanged += 1;
+                        continue;
+                    }
+                    // Touched but not changed, as after switching branches
+                    if fs::read(entry.path())? == fs::read(&dest)? {
+                        set_modified(&dest, &source)?;
+                        stats.unchanged += 1;
+                        continue;
+                    }
+                }
+                Some(_) => remove(&dest)?,
+                None => {}
+            }
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            stats.bytes += fs::copy(entry.path(), &dest)?;
+            set_modified(&dest, &source)?;
+            stats.files += 1;
+        }
+    }
+    if prune {
+        remove_unsynced(to, Path::new(""), &synced, &mut stats)?;
+    }
+    Ok(stats)
+}
+
+/// The files and directories of the project that jobs get
+fn project_files(from: &Path) -> ignore::Walk {
+    let dockerignore = dockerignore(from);
+    WalkBuilder::new(from)
+        .hidden(false)
+        .ignore(false)
+        .require_git(false)
+        .add_custom_ignore_filename(IGNORE_FILE)
+        .filter_entry(move |entry| {
+            let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+            let skipped =
+                is_dir && SKIPPED_DIRS.contains(&entry.file_name().to_string_lossy().as_ref());
+            let ignored = dockerignore
+                .as_ref()
+                .is_some_and(|ignore| ignore.matched(entry.path(), is_dir).is_ignore());
+            !skipped && !ignored
+        })
+        .build()
+}
+
+fn remove_unsynced(
+    root: &Path,
+    relative: &Path,
+    synced: &HashSet<PathBuf>,
+    stats: &mut CopyStats,
+) -> io::Result<()> {
+    for entry in fs::read_dir(root.join(relative))? {
+        let entry = entry?;
+        let path = relative.join(entry.file_name());
+        if !synced.contains(&path) {
+            remove(&entry.path())?;
+            stats.removed += 1;
+        } else if entry.file_type()?.is_dir() {
+            remove_unsynced(root, &path, synced, stats)?;
+        }
+    }
+    Ok(())
+}
+
+/// Remove a file, link or directory, if there is one
+fn remove(path: &Path) -> io::Result<()> {
+    match fs::symlink_metadata(path) {
+        Ok(meta) if meta.is_dir() => fs::remove_dir_all(path),
+        Ok(_) => fs::remove_file(path),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+fn set_modified(path: &Path, source: &fs::Metadata) -> io::Result<()> {
+    match source.modified() {
+        Ok(modified) => fs::File::options()
+            .write(true)
+            .open(path)?
+            .set_modified(modified),
+        Err(_) => Ok(()),
+    }
+}
+
+/// Root directory holding the workspaces jobs keep between runs
+pub fn workspaces_root() -> PathBuf {
+    if let Ok(dir) = std::env::var(WORKSPACES_DIR_ENV) {
+        if !dir.is_empty() {
+            return PathBuf::from(dir);
+        }
+    }
+
+    dirs::cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("wrkflw")
+        .join("workspaces")
+}
+
+/// A workspace a job of a project's workflow keeps between runs, so the
+/// next run only syncs what changed. Held by one run at a time.
+#[derive(Debug)]
+pub struct PersistentWorkspace {
+    dir: PathBuf,
+    lock: PathBuf,
+}
+
+impl PersistentWorkspace {
+    /// Take the workspace of `job` of `workflow` in the project at `project`,
+    /// or `None` while another run has it
+    pub fn acquire(
+        root: &Path,
+        project: &Path,
+        workflow: &str,
+        job: &str,
+    ) -> io::Result<Option<Self>> {
+        let project = fs::canonicalize(project)?;
+        let key = format!("{}\n{}\n{}", project.display(), workflow, job);
+        let dir = root.join(format!("{}-{:016x}", slug(job), fnv1a(&key)));
+        let lock = dir.with_extension("lock");
+        fs::create_dir_all(root)?;
+
+        let take = || {
+            fs::File::options()
+                .write(true)
+                .create_new(true)
+                .open(&lock)
+                .and_then(|mut file| write!(file, "{}", std::process::id()))
+        };
+        match take() {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists && is_stale(&lock) => {
+                fs::remove_file(&lock)?;
+                if take().is_err() {
+                    return Ok(None);
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => return Ok(None),
+            Err(e) => return Err(e),
+        }
+
+        // A workspace of another project, e.g. after a hash collision, or
+        // one a failed sync left half done, is started over
+        let source = dir.with_extension("source");
+        if fs::read_to_string(&source).ok().as_deref() != Some(&key) {
+            remove(&dir)?;
+            fs::write(&source, &key)?;
+        }
+        fs::create_dir_all(&dir)?;
+        Ok(Some(PersistentWorkspace { dir, lock }))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Sync the project into the workspace, copying it again from scratch
+    /// if syncing fails
+    pub fn sync(&self, project: &Path) -> io::Result<CopyStats> {
+        sync_workspace(project, &self.dir).or_else(|_| {
+            remove(&self.dir)?;
+            fs::create_dir_all(&self.dir)?;
+            copy_workspace(project, &self.dir)
+        })
+    }
+}
+
+impl Drop for PersistentWorkspace {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.lock);
+    }
+}
+
+/// Whether the run holding a lock is gone
+fn is_stale(lock: &Path) -> bool {
+    let pid = fs::read_to_string(lock).unwrap_or_default();
+    if cfg!(target_os = "linux") && !pid.trim().is_empty() {
+        return !Path::new("/proc").join(pid.trim()).exists();
+    }
+    fs::metadata(lock)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age > STALE_LOCK)
+}
+
+fn slug(name: &str) -> String {
+    let slug: String = name
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c.to_ascii_lowercase(),
+            false => '-',
+        })
+        .collect();
+    slug.trim_matches('-').chars().take(40).collect()
+}
+
+/// The patterns of the `.dockerignore` at the project's root. Docker matches
+/// them from the root only, so each is anchored there.
+fn dockerignore(root: &Path) -> Option<Gitignore> {
+    let content = fs::read_to_string(root.join(".dockerignore")).ok()?;
+    let mut builder = GitignoreBuilder::new(root);
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (negation, pattern) = match line.strip_prefix('!') {
+            Some(pattern) => ("!", pattern),
+            None => ("", line),
+        };
+        let pattern = pattern.trim_start_matches("./").trim_start_matches('/');
+        let _ = builder.add_line(None, &format!("{}/{}", negation, pattern));
+    }
+    builder.build().ok()
+}
+
+#[cfg(unix)]
+fn copy_symlink(from: &Path, to: &Path) -> io::Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    std::os::unix::fs::symlink(fs::read_link(from)?, to)
+}
+
+#[cfg(not(unix))]
+fn copy_symlink(from: &Path, to: &Path) -> io::Result<()> {
+    if from.is_file() {
+        fs::copy(from, to)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leaves_out_ignored_files() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path();
+        for file in [
+            "src/main.rs",
+            "target/debug/app",
+            "node_modules/left-pad/index.js",
+            ".git/objects/ab/cdef",
+            ".github/workflows/ci.yml",
+            "docs/secret.md",
+            "sub/docs/kept.md",
+            "sub/debug.log",
+            "sub/keep.log",
+            "sub/local.tmp",
+        ] {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "x").unwrap();
+        }
+        fs::write(root.join(".gitignore"), "target/\nnode_modules/\n").unwrap();
+        fs::write(root.join(".dockerignore"), "# docs\ndocs\n").unwrap();
+        fs::write(root.join(IGNORE_FILE), "*.log\n!keep.log\n").unwrap();
+        fs::write(root.join("sub").join(IGNORE_FILE), "*.tmp\n").unwrap();
+
+        let workspace = tempfile::tempdir().unwrap();
+        let stats = copy_workspace(root, workspace.path()).unwrap();
+        let mut copied: Vec<String> = files_in(workspace.path());
+        copied.sort();
+        assert_eq!(
+            copied,
+            [
+                ".dockerignore",
+                ".github/workflows/ci.yml",
+                ".gitignore",
+                ".wrkflwignore",
+                "src/main.rs",
+                "sub/.wrkflwignore",
+                "sub/docs/kept.md",
+                "sub/keep.log",
+            ]
+        );
+        assert_eq!(stats.files, 8);
+
+        // Checking out into the project itself copies nothing
+        assert_eq!(copy_workspace(root, root).unwrap(), CopyStats::default());
+    }
+
+    #[test]
+    fn syncs_only_what_changed() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path();
+        for (file, content) in [("a.txt", "a"), ("b.txt", "b"), ("dir/c.txt", "c")] {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        let kept = tempfile::tempdir().unwrap();
+        let workspace = PersistentWorkspace::acquire(kept.path(), root, "CI", "build")
+            .unwrap()
+            .unwrap();
+        assert_eq!(workspace.sync(root).unwrap().files, 3);
+        // Held until dropped
+        assert!(
+            PersistentWorkspace::acquire(kept.path(), root, "CI", "build")
+                .unwrap()
+                .is_none()
+        );
+
+        // What a job left behind goes, what the project changed comes
+        fs::write(workspace.path().join("build.out"), "x").unwrap();
+        fs::write(root.join("b.txt"), "bb").unwrap();
+        fs::remove_dir_all(root.join("dir")).unwrap();
+        fs::write(root.join("dir"), "now a file").unwrap();
+        let stats = workspace.sync(root).unwrap();
+        assert_eq!((stats.files, stats.unchanged, stats.removed), (2, 1, 1));
+        let mut synced = files_in(workspace.path());
+        synced.sort();
+        assert_eq!(synced, ["a.txt", "b.txt", "dir"]);
+        assert_eq!(
+            fs::read_to_string(workspace.path().join("b.txt")).unwrap(),
+            "bb"
+        );
+
+        drop(workspace);
+        let workspace = PersistentWorkspace::acquire(kept.path(), root, "CI", "build")
+            .unwrap()
+            .unwrap();
+        assert_eq!(workspace.sync(root).unwrap().unchanged, 3);
+    }
+
+    fn files_in(dir: &Path) -> Vec<String> {
+        let mut files = Vec::new();
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                files.extend(files_in(&path).into_iter().map(|file| {
+                    format!("{}/{}", path.file_name().unwrap().to_string_lossy(), file)
+                }));
+            } else {
+                files.push(path.file_name().unwrap().to_string_lossy().to_string());
+            }
+        }
+        files
+    }
+}
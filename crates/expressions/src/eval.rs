@@ -0,0 +1,418 @@
+use crate::parser::{CompareOp, Expr};
+use crate::{EvaluationContext, ExpressionError, JobState};
+use serde_json::Value;
+use std::cmp::Ordering;
+
+pub fn evaluate(expr: &Expr, ctx: &EvaluationContext) -> Result<Value, ExpressionError> {
+    match expr {
+        Expr::Literal(value) => Ok(value.clone()),
+        Expr::Context(name) => Ok(ctx.get(name).cloned().unwrap_or(Value::Null)),
+        Expr::Property(base, name) => {
+            let base_value = evaluate(base, ctx)?;
+            if is_filtered(base) {
+                // Property access after `.*` maps over every element
+                let items = match base_value {
+                    Value::Array(items) => items,
+                    _ => Vec::new(),
+                };
+                Ok(Value::Array(
+                    items
+                        .iter()
+                        .map(|item| property(item, name))
+                        .filter(|v| !v.is_null())
+                        .collect(),
+                ))
+            } else {
+                Ok(property(&base_value, name))
+            }
+        }
+        Expr::Index(base, index) => {
+            let base_value = evaluate(base, ctx)?;
+            let index_value = evaluate(index, ctx)?;
+            Ok(match (&base_value, &index_value) {
+                (Value::Array(items), _) => {
+                    let idx = to_number(&index_value);
+                    if idx.is_finite() && idx >= 0.0 {
+                        items.get(idx as usize).cloned().unwrap_or(Value::Null)
+                    } else {
+                        Value::Null
+                    }
+                }
+                (Value::Object(_), _) => property(&base_value, &to_string(&index_value)),
+                _ => Value::Null,
+            })
+        }
+        Expr::Filter(base) => Ok(match evaluate(base, ctx)? {
+            Value::Array(items) => Value::Array(items),
+            Value::Object(map) => Value::Array(map.into_iter().map(|(_, v)| v).collect()),
+            _ => Value::Array(Vec::new()),
+        }),
+        Expr::Not(operand) => Ok(Value::Bool(!is_truthy(&evaluate(operand, ctx)?))),
+        Expr::And(left, right) => {
+            let left_value = evaluate(left, ctx)?;
+            if !is_truthy(&left_value) {
+                return Ok(left_value);
+            }
+            evaluate(right, ctx)
+        }
+        Expr::Or(left, right) => {
+            let left_value = evaluate(left, ctx)?;
+            if is_truthy(&left_value) {
+                return Ok(left_value);
+            }
+            evaluate(right, ctx)
+        }
+        Expr::Compare(left, op, right) => {
+            let left_value = evaluate(left, ctx)?;
+            let right_value = evaluate(right, ctx)?;
+            Ok(Value::Bool(compare(&left_value, *op, &right_value)))
+        }
+        Expr::Call(name, args) => call_function(name, args, ctx),
+    }
+}
+
+fn is_filtered(expr: &Expr) -> bool {
+    match expr {
+        Expr::Filter(_) => true,
+        Expr::Property(base, _) => is_filtered(base),
+        _ => false,
+    }
+}
+
+/// Property lookup is case-insensitive, like on GitHub
+fn property(value: &Value, name: &str) -> Value {
+    match value {
+        Value::Object(map) => map
+            .get(name)
+            .or_else(|| {
+                map.iter()
+                    .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                    .map(|(_, v)| v)
+            })
+            .cloned()
+            .unwrap_or(Value::Null),
+        _ => Value::Null,
+    }
+}
+
+pub fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|f| f != 0.0 && !f.is_nan()),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(_) | Value::Object(_) => true,
+    }
+}
+
+pub fn to_number(value: &Value) -> f64 {
+    match value {
+        Value::Null => 0.0,
+        Value::Bool(b) => {
+            if *b {
+                1.0
+            } else {
+                0.0
+            }
+        }
+        Value::Number(n) => n.as_f64().unwrap_or(f64::NAN),
+        Value::String(s) => {
+            let trimmed = s.trim();
+            if trimmed.is_empty() {
+                0.0
+            } else {
+                crate::lexer::parse_number(trimmed).unwrap_or(f64::NAN)
+            }
+        }
+        Value::Array(_) | Value::Object(_) => f64::NAN,
+    }
+}
+
+pub fn to_string(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => match n.as_f64() {
+            Some(f) if f.fract() == 0.0 && f.abs() < 1e15 => format!("{}", f as i64),
+            Some(f) => f.to_string(),
+            None => n.to_string(),
+        },
+        Value::String(s) => s.clone(),
+        Value::Array(_) => "Array".to_string(),
+        Value::Object(_) => "Object".to_string(),
+    }
+}
+
+fn loose_equals(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Null, Value::Null) => true,
+        (Value::Bool(a), Value::Bool(b)) => a == b,
+        (Value::String(a), Value::String(b)) => a.to_lowercase() == b.to_lowercase(),
+        (Value::Array(_), Value::Array(_)) | (Value::Object(_), Value::Object(_)) => left == right,
+        (Value::Array(_) | Value::Object(_), _) | (_, Value::Array(_) | Value::Object(_)) => false,
+        _ => to_number(left) == to_number(right),
+    }
+}
+
+fn compare(left: &Value, op: CompareOp, right: &Value) -> bool {
+    match op {
+        CompareOp::Eq => return loose_equals(left, right),
+        CompareOp::Ne => return !loose_equals(left, right),
+        _ => {}
+    }
+
+    let ordering = match (left, right) {
+        (Value::String(a), Value::String(b)) => Some(a.to_lowercase().cmp(&b.to_lowercase())),
+        _ => to_number(left).partial_cmp(&to_number(right)),
+    };
+
+    match ordering {
+        Some(ordering) => match op {
+            CompareOp::Lt => ordering == Ordering::Less,
+            CompareOp::Le => ordering != Ordering::Greater,
+            CompareOp::Gt => ordering == Ordering::Greater,
+            CompareOp::Ge => ordering != Ordering::Less,
+            CompareOp::Eq | CompareOp::Ne => unreachable!(),
+        },
+        // NaN never compares
+        None => false,
+    }
+}
+
+fn call_function(
+    name: &str,
+    args: &[Expr],
+    ctx: &EvaluationContext,
+) -> Result<Value, ExpressionError> {
+    crate::check_arity(name, args.len())?;
+
+    let values = args
+        .iter()
+        .map(|arg| evaluate(arg, ctx))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    match name.to_lowercase().as_str() {
+        "success" => Ok(Value::Bool(ctx.job_state == JobState::Success)),
+        "failure" => Ok(Value::Bool(ctx.job_state == JobState::Failure)),
+        "cancelled" => Ok(Value::Bool(ctx.job_state == JobState::Cancelled)),
+        "always" => Ok(Value::Bool(true)),
+        "contains" => Ok(Value::Bool(match &values[0] {
+            Value::Array(items) => items.iter().any(|item| loose_equals(item, &values[1])),
+            haystack => to_string(haystack)
+                .to_lowercase()
+                .contains(&to_string(&values[1]).to_lowercase()),
+        })),
+        "startswith" => Ok(Value::Bool(
+            to_string(&values[0])
+                .to_lowercase()
+                .starts_with(&to_string(&values[1]).to_lowercase()),
+        )),
+        "endswith" => Ok(Value::Bool(
+            to_string(&values[0])
+                .to_lowercase()
+                .ends_with(&to_string(&values[1]).to_lowercase()),
+        )),
+        "format" => format(&to_string(&values[0]), &values[1..]).map(Value::String),
+        "join" => {
+            let separator = values.get(1).map(to_string).unwrap_or_else(|| ",".into());
+            Ok(Value::String(match &values[0] {
+                Value::Array(items) => items
+                    .iter()
+                    .map(to_string)
+                    .collect::<Vec<_>>()
+                    .join(&separator),
+                other => to_string(other),
+            }))
+        }
+        "tojson" => serde_json::to_string_pretty(&values[0])
+            .map(Value::String)
+            .map_err(|e| ExpressionError::Evaluation(format!("toJSON failed: {}", e))),
+        "fromjson" => {
+            let input = to_string(&values[0]);
+            serde_json::from_str(input.trim()).map_err(|e| {
+                ExpressionError::Evaluation(format!("fromJSON failed for '{}': {}", input, e))
+            })
+        }
+        "hashfiles" => Err(ExpressionError::Evaluation(
+            "hashFiles() is not supported when running locally".to_string(),
+        )),
+        _ => Err(ExpressionError::UnknownFunction(name.to_string())),
+    }
+}
+
+/// `format('{0} and {1}', a, b)`, with `{{` and `}}` as escaped braces
+fn format(template: &str, args: &[Value]) -> Result<String, ExpressionError> {
+    let mut output = String::new();
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                output.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                output.push('}');
+            }
+            '{' => {
+                let mut index = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(ch) if ch.is_ascii_digit() => index.push(ch),
+                        _ => {
+                            return Err(ExpressionError::Evaluation(format!(
+                                "Invalid format string '{}'",
+                                template
+                            )))
+                        }
+                    }
+                }
+                let arg = index
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|i| args.get(i))
+                    .ok_or_else(|| {
+                        ExpressionError::Evaluation(format!(
+                            "Format string '{}' references missing argument {{{}}}",
+                            template, index
+                        ))
+                    })?;
+                output.push_str(&to_string(arg));
+            }
+            other => output.push(other),
+        }
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{evaluate, EvaluationContext, ExpressionError};
+    use serde_json::{json, Value};
+
+    fn eval(expression: &str) -> Value {
+        let mut ctx = EvaluationContext::new();
+        ctx.set("env", json!({ "Name": "wrkflw", "EMPTY": "" }));
+        evaluate(expression, &ctx).unwrap()
+    }
+
+    fn error(expression: &str) -> ExpressionError {
+        evaluate(expression, &EvaluationContext::new()).unwrap_err()
+    }
+
+    #[test]
+    fn compares_loosely() {
+        let cases = [
+            ("1 == '1'", true),
+            ("'' == 0", true),
+            ("true == 1", true),
+            ("false == '0'", true),
+            ("null == 0", true),
+            ("null == ''", true),
+            ("'ABC' == 'abc'", true),
+            ("'0x10' == 16", true),
+            ("' 2 ' == 2", true),
+            ("'abc' == 0", false),
+            ("'abc' != 'abc'", false),
+            ("fromJSON('[1]') == fromJSON('[1]')", true),
+            ("fromJSON('{}') == '{}'", false),
+            ("fromJSON('[]') == 0", false),
+            ("'a' < 'B'", true),
+            ("'10' > 9", true),
+            ("'10' > '9'", false),
+            ("true > false", true),
+            ("'x' < 1", false),
+            ("'x' >= 1", false),
+            ("null <= 0", true),
+        ];
+        for (expression, expected) in cases {
+            assert_eq!(eval(expression), json!(expected), "{}", expression);
+        }
+    }
+
+    #[test]
+    fn coerces_values() {
+        // && and || return an operand, not a boolean
+        assert_eq!(eval("'' || 'b'"), json!("b"));
+        assert_eq!(eval("0 && 'b'"), json!(0));
+        assert_eq!(eval("env.EMPTY || env.missing"), Value::Null);
+        assert_eq!(eval("!''"), json!(true));
+        assert_eq!(eval("!fromJSON('[]')"), json!(false));
+        // Properties are found regardless of case
+        assert_eq!(eval("env.name"), json!("wrkflw"));
+        assert_eq!(eval("env['NAME']"), json!("wrkflw"));
+        assert_eq!(eval("env.Name.length"), Value::Null);
+        assert_eq!(
+            eval("format('{0} {1} {2} {3} {4}', 3.0, 0.5, true, null, fromJSON('[1]'))"),
+            json!("3 0.5 true  Array")
+        );
+    }
+
+    #[test]
+    fn evaluates_from_json_contains_and_starts_with() {
+        assert_eq!(eval("fromJSON('{\"a\": [1, 2]}').a[1]"), json!(2));
+        assert_eq!(eval("fromJSON(' true ')"), json!(true));
+        assert_eq!(eval("fromJSON('[1, 2]')[5]"), Value::Null);
+        assert_eq!(eval("fromJSON('[1, 2]')[-1]"), Value::Null);
+        assert_eq!(
+            eval("fromJSON('{\"a\": {\"x\": 1}, \"b\": {\"x\": 2}}').*.x"),
+            json!([1, 2])
+        );
+
+        assert_eq!(eval("contains('Hello world', 'WORLD')"), json!(true));
+        assert_eq!(
+            eval("contains(fromJSON('[\"a\", \"B\"]'), 'b')"),
+            json!(true)
+        );
+        assert_eq!(eval("contains(fromJSON('[1, 2]'), '2')"), json!(true));
+        assert_eq!(eval("contains(fromJSON('[\"ab\"]'), 'a')"), json!(false));
+        assert_eq!(eval("contains(123, 2)"), json!(true));
+
+        assert_eq!(
+            eval("startsWith('Refs/Heads/main', 'refs/heads/')"),
+            json!(true)
+        );
+        assert_eq!(eval("startsWith(12, 1)"), json!(true));
+        assert_eq!(eval("startsWith('main', 'refs/')"), json!(false));
+        assert_eq!(eval("endsWith('release.TAR.gz', '.tar.GZ')"), json!(true));
+    }
+
+    #[test]
+    fn reports_evaluation_errors() {
+        assert!(matches!(
+            error("fromJSON('{nope')"),
+            ExpressionError::Evaluation(message) if message.starts_with("fromJSON failed for '{nope'")
+        ));
+        assert_eq!(
+            error("format('{1}', 'a')"),
+            ExpressionError::Evaluation(
+                "Format string '{1}' references missing argument {1}".to_string()
+            )
+        );
+        assert_eq!(
+            error("format('{a}', 'a')"),
+            ExpressionError::Evaluation("Invalid format string '{a}'".to_string())
+        );
+        assert!(matches!(
+            error("hashFiles('*.lock')"),
+            ExpressionError::Evaluation(_)
+        ));
+        assert!(matches!(
+            error("contains('a')"),
+            ExpressionError::ArgumentCount { .. }
+        ));
+        assert_eq!(
+            error("nope()"),
+            ExpressionError::UnknownFunction("nope".to_string())
+        );
+        // The right side of a short-circuited operator isn't evaluated
+        assert_eq!(eval("true || fromJSON('{')"), json!(true));
+        assert!(matches!(
+            error("false || fromJSON('{')"),
+            ExpressionError::Evaluation(_)
+        ));
+    }
+}
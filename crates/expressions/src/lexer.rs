@@ -0,0 +1,289 @@
+use crate::ExpressionError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Dot,
+    Comma,
+    Star,
+    Not,
+    And,
+    Or,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    String(String),
+    Number(f64),
+    True,
+    False,
+    Null,
+    Ident(String),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::LParen => write!(f, "("),
+            Token::RParen => write!(f, ")"),
+            Token::LBracket => write!(f, "["),
+            Token::RBracket => write!(f, "]"),
+            Token::Dot => write!(f, "."),
+            Token::Comma => write!(f, ","),
+            Token::Star => write!(f, "*"),
+            Token::Not => write!(f, "!"),
+            Token::And => write!(f, "&&"),
+            Token::Or => write!(f, "||"),
+            Token::Eq => write!(f, "=="),
+            Token::Ne => write!(f, "!="),
+            Token::Lt => write!(f, "<"),
+            Token::Le => write!(f, "<="),
+            Token::Gt => write!(f, ">"),
+            Token::Ge => write!(f, ">="),
+            Token::String(s) => write!(f, "'{}'", s),
+            Token::Number(n) => write!(f, "{}", n),
+            Token::True => write!(f, "true"),
+            Token::False => write!(f, "false"),
+            Token::Null => write!(f, "null"),
+            Token::Ident(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// Split an expression into tokens
+pub fn tokenize(input: &str) -> Result<Vec<Token>, ExpressionError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+
+    while pos < chars.len() {
+        let c = chars[pos];
+        let next = chars.get(pos + 1).copied();
+
+        match c {
+            c if c.is_whitespace() => {
+                pos += 1;
+            }
+            '(' => {
+                tokens.push(Token::LParen);
+                pos += 1;
+            }
+            ')' => {
+                tokens.push(Token::RParen);
+                pos += 1;
+            }
+            '[' => {
+                tokens.push(Token::LBracket);
+                pos += 1;
+            }
+            ']' => {
+                tokens.push(Token::RBracket);
+                pos += 1;
+            }
+            '.' if !next.is_some_and(|n| n.is_ascii_digit()) => {
+                tokens.push(Token::Dot);
+                pos += 1;
+            }
+            ',' => {
+                tokens.push(Token::Comma);
+                pos += 1;
+            }
+            '*' => {
+                tokens.push(Token::Star);
+                pos += 1;
+            }
+            '!' if next == Some('=') => {
+                tokens.push(Token::Ne);
+                pos += 2;
+            }
+            '!' => {
+                tokens.push(Token::Not);
+                pos += 1;
+            }
+            '=' if next == Some('=') => {
+                tokens.push(Token::Eq);
+                pos += 2;
+            }
+            '&' if next == Some('&') => {
+                tokens.push(Token::And);
+                pos += 2;
+            }
+            '|' if next == Some('|') => {
+                tokens.push(Token::Or);
+                pos += 2;
+            }
+            '<' if next == Some('=') => {
+                tokens.push(Token::Le);
+                pos += 2;
+            }
+            '<' => {
+                tokens.push(Token::Lt);
+                pos += 1;
+            }
+            '>' if next == Some('=') => {
+                tokens.push(Token::Ge);
+                pos += 2;
+            }
+            '>' => {
+                tokens.push(Token::Gt);
+                pos += 1;
+            }
+            '\'' => {
+                // Strings use single quotes, with '' as an escaped quote
+                let mut value = String::new();
+                pos += 1;
+                loop {
+                    match chars.get(pos) {
+                        Some('\'') if chars.get(pos + 1) == Some(&'\'') => {
+                            value.push('\'');
+                            pos += 2;
+                        }
+                        Some('\'') => {
+                            pos += 1;
+                            break;
+                        }
+                        Some(ch) => {
+                            value.push(*ch);
+                            pos += 1;
+                        }
+                        None => return Err(ExpressionError::UnterminatedString),
+                    }
+                }
+                tokens.push(Token::String(value));
+            }
+            c if c.is_ascii_digit()
+                || c == '.'
+                || (c == '-' && next.is_some_and(|n| n.is_ascii_digit() || n == '.')) =>
+            {
+                let start = pos;
+                pos += 1;
+                while let Some(ch) = chars.get(pos) {
+                    let prev = chars[pos - 1];
+                    let is_exponent_sign =
+                        (*ch == '+' || *ch == '-') && (prev == 'e' || prev == 'E');
+                    if ch.is_ascii_alphanumeric() || *ch == '.' || is_exponent_sign {
+                        pos += 1;
+                    } else {
+                        break;
+                    }
+                }
+                let literal: String = chars[start..pos].iter().collect();
+                tokens
+                    .push(Token::Number(parse_number(&literal).ok_or_else(|| {
+                        ExpressionError::InvalidNumber(literal.clone())
+                    })?));
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let start = pos;
+                while let Some(ch) = chars.get(pos) {
+                    if ch.is_alphanumeric() || *ch == '_' || *ch == '-' {
+                        pos += 1;
+                    } else {
+                        break;
+                    }
+                }
+                let word: String = chars[start..pos].iter().collect();
+                tokens.push(match word.as_str() {
+                    "true" => Token::True,
+                    "false" => Token::False,
+                    "null" => Token::Null,
+                    _ => Token::Ident(word),
+                });
+            }
+            other => return Err(ExpressionError::UnexpectedCharacter(other, pos)),
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Parse a numeric literal, including hex (0xff) and exponent (1e3) forms
+pub fn parse_number(literal: &str) -> Option<f64> {
+    let (negative, digits) = match literal.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, literal),
+    };
+
+    let value = if let Some(hex) = digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        i64::from_str_radix(hex, 16).ok()? as f64
+    } else if let Some(octal) = digits.strip_prefix("0o") {
+        i64::from_str_radix(octal, 8).ok()? as f64
+    } else {
+        digits.parse::<f64>().ok()?
+    };
+
+    Some(if negative { -value } else { value })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokenizes_operators_and_literals() {
+        assert_eq!(
+            tokenize("!a.b-c[0] != 'it''s' && (x <= -1.5e3 || y>=.5)").unwrap(),
+            [
+                Token::Not,
+                Token::Ident("a".to_string()),
+                Token::Dot,
+                Token::Ident("b-c".to_string()),
+                Token::LBracket,
+                Token::Number(0.0),
+                Token::RBracket,
+                Token::Ne,
+                Token::String("it's".to_string()),
+                Token::And,
+                Token::LParen,
+                Token::Ident("x".to_string()),
+                Token::Le,
+                Token::Number(-1500.0),
+                Token::Or,
+                Token::Ident("y".to_string()),
+                Token::Ge,
+                Token::Number(0.5),
+                Token::RParen,
+            ]
+        );
+        assert_eq!(
+            tokenize("true false null a.*").unwrap(),
+            [
+                Token::True,
+                Token::False,
+                Token::Null,
+                Token::Ident("a".to_string()),
+                Token::Dot,
+                Token::Star,
+            ]
+        );
+        assert_eq!(parse_number("0xff"), Some(255.0));
+        assert_eq!(parse_number("-0o17"), Some(-15.0));
+        assert_eq!(parse_number("2E-2"), Some(0.02));
+        assert_eq!(parse_number("1.2.3"), None);
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        assert_eq!(
+            tokenize("a = b"),
+            Err(ExpressionError::UnexpectedCharacter('=', 2))
+        );
+        assert_eq!(
+            tokenize("a & b"),
+            Err(ExpressionError::UnexpectedCharacter('&', 2))
+        );
+        assert_eq!(tokenize("'open"), Err(ExpressionError::UnterminatedString));
+        assert_eq!(
+            tokenize("12abc"),
+            Err(ExpressionError::InvalidNumber("12abc".to_string()))
+        );
+    }
+}
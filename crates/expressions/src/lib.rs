@@ -0,0 +1,379 @@
+// expressions crate
+
+mod eval;
+mod lexer;
+mod parser;
+
+use serde_json::Value;
+use std::collections::HashMap;
+use thiserror::Error;
+
+pub use eval::{is_truthy, to_string};
+pub use parser::{parse, CompareOp, Expr};
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ExpressionError {
+    #[error("Unexpected character '{0}' at position {1}")]
+    UnexpectedCharacter(char, usize),
+
+    #[error("Unterminated string literal")]
+    UnterminatedString,
+
+    #[error("Invalid number '{0}'")]
+    InvalidNumber(String),
+
+    #[error("Unexpected token '{0}'")]
+    UnexpectedToken(String),
+
+    #[error("Unexpected end of expression")]
+    UnexpectedEnd,
+
+    #[error("Unknown function '{0}'")]
+    UnknownFunction(String),
+
+    #[error("Function '{name}' expects {expected} argument(s), got {actual}")]
+    ArgumentCount {
+        name: String,
+        expected: String,
+        actual: usize,
+    },
+
+    #[error("Unknown context '{0}'")]
+    UnknownContext(String),
+
+    #[error("{0}")]
+    Evaluation(String),
+}
+
+/// Contexts that can be referenced from expressions
+pub const KNOWN_CONTEXTS: &[&str] = &[
+    "github", "env", "vars", "job", "jobs", "steps", "runner", "secrets", "strategy", "matrix",
+    "needs", "inputs",
+];
+
+/// Built-in functions with their minimum and maximum argument counts
+const FUNCTIONS: &[(&str, usize, Option<usize>)] = &[
+    ("contains", 2, Some(2)),
+    ("startsWith", 2, Some(2)),
+    ("endsWith", 2, Some(2)),
+    ("format", 1, None),
+    ("join", 1, Some(2)),
+    ("toJSON", 1, Some(1)),
+    ("fromJSON", 1, Some(1)),
+    ("hashFiles", 1, None),
+    ("success", 0, Some(0)),
+    ("always", 0, Some(0)),
+    ("cancelled", 0, Some(0)),
+    ("failure", 0, Some(0)),
+];
+
+const STATUS_FUNCTIONS: &[&str] = &["success", "always", "cancelled", "failure"];
+
+/// Outcome of the job so far, used by the status check functions
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum JobState {
+    #[default]
+    Success,
+    Failure,
+    Cancelled,
+}
+
+/// Values available while evaluating an expression
+#[derive(Debug, Clone, Default)]
+pub struct EvaluationContext {
+    contexts: HashMap<String, Value>,
+    pub job_state: JobState,
+}
+
+impl EvaluationContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set a top-level context such as `github` or `matrix`
+    pub fn set(&mut self, name: &str, value: Value) {
+        self.contexts.insert(name.to_lowercase(), value);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.contexts.get(&name.to_lowercase())
+    }
+}
+
+/// Remove the `${{ }}` wrapper from an expression, if present
+pub fn strip_delimiters(expression: &str) -> &str {
+    let trimmed = expression.trim();
+    match trimmed
+        .strip_prefix("${{")
+        .and_then(|rest| rest.strip_suffix("}}"))
+    {
+        Some(inner) if !inner.contains("${{") => inner.trim(),
+        _ => trimmed,
+    }
+}
+
+/// Evaluate an expression and return its value
+pub fn evaluate(expression: &str, ctx: &EvaluationContext) -> Result<Value, ExpressionError> {
+    let expr = parse(strip_delimiters(expression))?;
+    eval::evaluate(&expr, ctx)
+}
+
+/// Replace every `${{ expr }}` in a string with the expression's value
+pub fn interpolate(template: &str, ctx: &EvaluationContext) -> Result<String, ExpressionError> {
+    let mut output = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find("${{") {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 3..];
+        let end = find_closing_braces(after).ok_or(ExpressionError::UnexpectedEnd)?;
+        let value = eval::evaluate(&parse(after[..end].trim())?, ctx)?;
+        output.push_str(&to_string(&value));
+        rest = &after[end + 2..];
+    }
+
+    output.push_str(rest);
+    Ok(output)
+}
+
+/// The expressions inside every `${{ }}` of a string
+pub fn embedded_expressions(template: &str) -> Vec<&str> {
+    let mut found = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("${{") {
+        let after = &rest[start + 3..];
+        let Some(end) = find_closing_braces(after) else {
+            break;
+        };
+        found.push(after[..end].trim());
+        rest = &after[end + 2..];
+    }
+    found
+}
+
+/// Find the `}}` that closes an expression, ignoring any inside string literals
+fn find_closing_braces(source: &str) -> Option<usize> {
+    let bytes = source.as_bytes();
+    let mut in_string = false;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\'' => in_string = !in_string,
+            b'}' if !in_string && bytes.get(i + 1) == Some(&b'}') => return Some(i),
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Evaluate an `if:` condition. Conditions without a status check function
+/// behave as if wrapped in `success() && (...)`, matching GitHub Actions.
+pub fn evaluate_condition(
+    condition: &str,
+    ctx: &EvaluationContext,
+) -> Result<bool, ExpressionError> {
+    let source = strip_delimiters(condition);
+    if source.is_empty() {
+        return Ok(ctx.job_state == JobState::Success);
+    }
+
+    let expr = parse(source)?;
+    if !uses_status_function(&expr) && ctx.job_state != JobState::Success {
+        return Ok(false);
+    }
+
+    Ok(is_truthy(&eval::evaluate(&expr, ctx)?))
+}
+
+/// Check an expression for syntax errors, unknown functions, wrong argument
+/// counts and references to unknown contexts
+pub fn validate(expression: &str) -> Result<(), ExpressionError> {
+    let expr = parse(strip_delimiters(expression))?;
+
+    let mut error = None;
+    expr.walk(&mut |node| {
+        if error.is_some() {
+            return;
+        }
+        match node {
+            Expr::Call(name, args) => {
+                if let Err(e) = check_arity(name, args.len()) {
+                    error = Some(e);
+                }
+            }
+            Expr::Context(name) if !KNOWN_CONTEXTS.iter().any(|c| c.eq_ignore_ascii_case(name)) => {
+                error = Some(ExpressionError::UnknownContext(name.clone()));
+            }
+            _ => {}
+        }
+    });
+
+    match error {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+/// Whether a condition calls one of the status check functions, which opts
+/// it out of the implicit `success()` check
+pub fn has_status_function(condition: &str) -> bool {
+    parse(strip_delimiters(condition)).is_ok_and(|expr| uses_status_function(&expr))
+}
+
+fn uses_status_function(expr: &Expr) -> bool {
+    let mut found = false;
+    expr.walk(&mut |node| {
+        if let Expr::Call(name, _) = node {
+            if STATUS_FUNCTIONS
+                .iter()
+                .any(|f| f.eq_ignore_ascii_case(name))
+            {
+                found = true;
+            }
+        }
+    });
+    found
+}
+
+fn check_arity(name: &str, count: usize) -> Result<(), ExpressionError> {
+    let (canonical, min, max) = FUNCTIONS
+        .iter()
+        .find(|(f, _, _)| f.eq_ignore_ascii_case(name))
+        .ok_or_else(|| ExpressionError::UnknownFunction(name.to_string()))?;
+
+    if count < *min || max.is_some_and(|max| count > max) {
+        let expected = match max {
+            Some(max) if max == min => min.to_string(),
+            Some(max) => format!("{}-{}", min, max),
+            None => format!("at least {}", min),
+        };
+        return Err(ExpressionError::ArgumentCount {
+            name: canonical.to_string(),
+            expected,
+            actual: count,
+        });
+    }
+
+    Ok(())
+}
+
+fn number_value(n: f64) -> Value {
+    if n.fract() == 0.0 && n.abs() < 1e15 {
+        Value::from(n as i64)
+    } else {
+        serde_json::Number::from_f64(n)
+            .map(Value::Number)
+            .unwrap_or(Value::Null)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn context() -> EvaluationContext {
+        let mut ctx = EvaluationContext::new();
+        ctx.set(
+            "github",
+            json!({
+                "event_name": "push",
+                "ref": "refs/heads/main",
+                "event": { "commits": [{ "message": "fix" }, { "message": "docs" }] }
+            }),
+        );
+        ctx.set("matrix", json!({ "os": "ubuntu-latest", "node": 18 }));
+        ctx
+    }
+
+    #[test]
+    fn evaluates_comparisons_and_logic() {
+        let ctx = context();
+        assert!(evaluate_condition("github.event_name == 'push' && !cancelled()", &ctx).unwrap());
+        assert!(evaluate_condition("${{ github.event_name == 'PUSH' }}", &ctx).unwrap());
+        assert!(evaluate_condition("matrix.node >= 16 && matrix.node < '20'", &ctx).unwrap());
+        assert!(
+            !evaluate_condition("github.event_name != 'push' || matrix.missing", &ctx).unwrap()
+        );
+        assert_eq!(
+            evaluate("matrix.os || 'fallback'", &ctx).unwrap(),
+            json!("ubuntu-latest")
+        );
+    }
+
+    #[test]
+    fn evaluates_functions() {
+        let ctx = context();
+        assert!(evaluate_condition("startsWith(github.ref, 'refs/heads/')", &ctx).unwrap());
+        assert!(evaluate_condition("contains(fromJSON('[\"a\", \"b\"]'), 'B')", &ctx).unwrap());
+        assert!(
+            evaluate_condition("contains(github.event.commits.*.message, 'docs')", &ctx).unwrap()
+        );
+        assert_eq!(
+            evaluate("format('{0}-{1} {{x}}', matrix.os, matrix.node)", &ctx).unwrap(),
+            json!("ubuntu-latest-18 {x}")
+        );
+        assert_eq!(
+            evaluate("join(fromJSON('[1, 2]'), '+')", &ctx).unwrap(),
+            json!("1+2")
+        );
+    }
+
+    #[test]
+    fn interpolates_templates() {
+        let mut ctx = context();
+        ctx.set(
+            "steps",
+            json!({ "build": { "outputs": { "version": "1.2.3" } } }),
+        );
+        assert_eq!(
+            interpolate(
+                "v${{ steps.build.outputs.version }} on ${{ matrix.os }} ${{ format('{0}}}', 'x') }}",
+                &ctx
+            )
+            .unwrap(),
+            "v1.2.3 on ubuntu-latest x}"
+        );
+        assert_eq!(
+            interpolate("no expressions", &ctx).unwrap(),
+            "no expressions"
+        );
+        assert!(interpolate("${{ matrix.os", &ctx).is_err());
+        assert_eq!(
+            embedded_expressions("${{ runner.os }}-${{ hashFiles('a}}b') }}"),
+            ["runner.os", "hashFiles('a}}b')"]
+        );
+    }
+
+    #[test]
+    fn applies_implicit_success_check() {
+        let mut ctx = context();
+        ctx.job_state = JobState::Failure;
+        assert!(!evaluate_condition("github.event_name == 'push'", &ctx).unwrap());
+        assert!(evaluate_condition("failure()", &ctx).unwrap());
+        assert!(evaluate_condition("always()", &ctx).unwrap());
+    }
+
+    #[test]
+    fn reports_invalid_expressions() {
+        assert!(validate("github.event_name == 'push'").is_ok());
+        assert!(matches!(
+            validate("github.event_name == 'push"),
+            Err(ExpressionError::UnterminatedString)
+        ));
+        assert!(matches!(
+            validate("startsWith(github.ref)"),
+            Err(ExpressionError::ArgumentCount { .. })
+        ));
+        assert!(matches!(
+            validate("nope(github.ref)"),
+            Err(ExpressionError::UnknownFunction(_))
+        ));
+        assert!(matches!(
+            validate("gihtub.ref == 'x'"),
+            Err(ExpressionError::UnknownContext(_))
+        ));
+        assert!(validate("github.ref ==").is_err());
+    }
+}
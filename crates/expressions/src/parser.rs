@@ -0,0 +1,351 @@
+use crate::lexer::{tokenize, Token};
+use crate::ExpressionError;
+use serde_json::Value;
+
+/// Parsed form of a GitHub Actions expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Literal(Value),
+    /// A top-level context such as `github` or `matrix`
+    Context(String),
+    /// `base.name`
+    Property(Box<Expr>, String),
+    /// `base[index]`
+    Index(Box<Expr>, Box<Expr>),
+    /// `base.*`, the object filter
+    Filter(Box<Expr>),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Compare(Box<Expr>, CompareOp, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Expr {
+    /// Visit this expression and all of its children
+    pub fn walk(&self, visit: &mut impl FnMut(&Expr)) {
+        visit(self);
+        match self {
+            Expr::Literal(_) | Expr::Context(_) => {}
+            Expr::Property(base, _) | Expr::Filter(base) | Expr::Not(base) => base.walk(visit),
+            Expr::Index(base, index) => {
+                base.walk(visit);
+                index.walk(visit);
+            }
+            Expr::And(left, right) | Expr::Or(left, right) | Expr::Compare(left, _, right) => {
+                left.walk(visit);
+                right.walk(visit);
+            }
+            Expr::Call(_, args) => {
+                for arg in args {
+                    arg.walk(visit);
+                }
+            }
+        }
+    }
+}
+
+/// Parse an expression (without the surrounding `${{ }}`)
+pub fn parse(input: &str) -> Result<Expr, ExpressionError> {
+    let tokens = tokenize(input)?;
+    if tokens.is_empty() {
+        return Err(ExpressionError::UnexpectedEnd);
+    }
+
+    let mut parser = Parser { tokens, pos: 0 };
+    let expr = parser.parse_or()?;
+
+    match parser.peek() {
+        None => Ok(expr),
+        Some(token) => Err(ExpressionError::UnexpectedToken(token.to_string())),
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Result<Token, ExpressionError> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or(ExpressionError::UnexpectedEnd)?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), ExpressionError> {
+        let token = self.next()?;
+        if token == expected {
+            Ok(())
+        } else {
+            Err(ExpressionError::UnexpectedToken(token.to_string()))
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, ExpressionError> {
+        let mut left = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            let right = self.parse_and()?;
+            left = Expr::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, ExpressionError> {
+        let mut left = self.parse_equality()?;
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            let right = self.parse_equality()?;
+            left = Expr::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_equality(&mut self) -> Result<Expr, ExpressionError> {
+        let mut left = self.parse_comparison()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Eq) => CompareOp::Eq,
+                Some(Token::Ne) => CompareOp::Ne,
+                _ => return Ok(left),
+            };
+            self.pos += 1;
+            let right = self.parse_comparison()?;
+            left = Expr::Compare(Box::new(left), op, Box::new(right));
+        }
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, ExpressionError> {
+        let mut left = self.parse_unary()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Lt) => CompareOp::Lt,
+                Some(Token::Le) => CompareOp::Le,
+                Some(Token::Gt) => CompareOp::Gt,
+                Some(Token::Ge) => CompareOp::Ge,
+                _ => return Ok(left),
+            };
+            self.pos += 1;
+            let right = self.parse_unary()?;
+            left = Expr::Compare(Box::new(left), op, Box::new(right));
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, ExpressionError> {
+        if self.peek() == Some(&Token::Not) {
+            self.pos += 1;
+            let operand = self.parse_unary()?;
+            return Ok(Expr::Not(Box::new(operand)));
+        }
+        self.parse_postfix()
+    }
+
+    fn parse_postfix(&mut self) -> Result<Expr, ExpressionError> {
+        let mut expr = self.parse_primary()?;
+        loop {
+            match self.peek() {
+                Some(Token::Dot) => {
+                    self.pos += 1;
+                    expr = match self.next()? {
+                        Token::Star => Expr::Filter(Box::new(expr)),
+                        Token::Ident(name) => Expr::Property(Box::new(expr), name),
+                        // Keywords are valid property names, e.g. `inputs.true`
+                        token @ (Token::True | Token::False | Token::Null) => {
+                            Expr::Property(Box::new(expr), token.to_string())
+                        }
+                        token => return Err(ExpressionError::UnexpectedToken(token.to_string())),
+                    };
+                }
+                Some(Token::LBracket) => {
+                    self.pos += 1;
+                    expr = if self.peek() == Some(&Token::Star) {
+                        self.pos += 1;
+                        Expr::Filter(Box::new(expr))
+                    } else {
+                        let index = self.parse_or()?;
+                        Expr::Index(Box::new(expr), Box::new(index))
+                    };
+                    self.expect(Token::RBracket)?;
+                }
+                _ => return Ok(expr),
+            }
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, ExpressionError> {
+        match self.next()? {
+            Token::True => Ok(Expr::Literal(Value::Bool(true))),
+            Token::False => Ok(Expr::Literal(Value::Bool(false))),
+            Token::Null => Ok(Expr::Literal(Value::Null)),
+            Token::String(s) => Ok(Expr::Literal(Value::String(s))),
+            Token::Number(n) => Ok(Expr::Literal(crate::number_value(n))),
+            Token::LParen => {
+                let expr = self.parse_or()?;
+                self.expect(Token::RParen)?;
+                Ok(expr)
+            }
+            Token::Ident(name) => {
+                if self.peek() == Some(&Token::LParen) {
+                    self.pos += 1;
+                    let mut args = Vec::new();
+                    if self.peek() != Some(&Token::RParen) {
+                        loop {
+                            args.push(self.parse_or()?);
+                            if self.peek() == Some(&Token::Comma) {
+                                self.pos += 1;
+                            } else {
+                                break;
+                            }
+                        }
+                    }
+                    self.expect(Token::RParen)?;
+                    Ok(Expr::Call(name, args))
+                } else {
+                    Ok(Expr::Context(name))
+                }
+            }
+            token => Err(ExpressionError::UnexpectedToken(token.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn context(name: &str) -> Box<Expr> {
+        Box::new(Expr::Context(name.to_string()))
+    }
+
+    fn compare(left: Box<Expr>, op: CompareOp, right: Box<Expr>) -> Box<Expr> {
+        Box::new(Expr::Compare(left, op, right))
+    }
+
+    #[test]
+    fn parses_by_precedence() {
+        // && binds tighter than ||
+        assert_eq!(
+            parse("a || b && c").unwrap(),
+            Expr::Or(
+                context("a"),
+                Box::new(Expr::And(context("b"), context("c")))
+            )
+        );
+        assert_eq!(
+            parse("(a || b) && c").unwrap(),
+            Expr::And(Box::new(Expr::Or(context("a"), context("b"))), context("c"))
+        );
+        // Then equality, then ordering, then !
+        assert_eq!(
+            parse("a == b < c").unwrap(),
+            *compare(
+                context("a"),
+                CompareOp::Eq,
+                compare(context("b"), CompareOp::Lt, context("c"))
+            )
+        );
+        assert_eq!(
+            parse("!a == b").unwrap(),
+            *compare(
+                Box::new(Expr::Not(context("a"))),
+                CompareOp::Eq,
+                context("b")
+            )
+        );
+        assert_eq!(
+            parse("a && b == 1").unwrap(),
+            Expr::And(
+                context("a"),
+                compare(
+                    context("b"),
+                    CompareOp::Eq,
+                    Box::new(Expr::Literal(json!(1)))
+                )
+            )
+        );
+        // Comparisons of the same level are left-associative
+        assert_eq!(
+            parse("a != b != c").unwrap(),
+            *compare(
+                compare(context("a"), CompareOp::Ne, context("b")),
+                CompareOp::Ne,
+                context("c")
+            )
+        );
+    }
+
+    #[test]
+    fn parses_property_access_and_calls() {
+        assert_eq!(
+            parse("a.b[0].*.c").unwrap(),
+            Expr::Property(
+                Box::new(Expr::Filter(Box::new(Expr::Index(
+                    Box::new(Expr::Property(context("a"), "b".to_string())),
+                    Box::new(Expr::Literal(json!(0))),
+                )))),
+                "c".to_string()
+            )
+        );
+        assert_eq!(
+            parse("inputs.true").unwrap(),
+            Expr::Property(context("inputs"), "true".to_string())
+        );
+        assert_eq!(
+            parse("format('{0}', a[*], always())").unwrap(),
+            Expr::Call(
+                "format".to_string(),
+                vec![
+                    Expr::Literal(json!("{0}")),
+                    Expr::Filter(context("a")),
+                    Expr::Call("always".to_string(), Vec::new()),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_expressions() {
+        let error = |input| parse(input).unwrap_err();
+        assert_eq!(error(""), ExpressionError::UnexpectedEnd);
+        assert_eq!(error("a ||"), ExpressionError::UnexpectedEnd);
+        assert_eq!(error("a."), ExpressionError::UnexpectedEnd);
+        assert_eq!(error("a[0"), ExpressionError::UnexpectedEnd);
+        assert_eq!(error("(a"), ExpressionError::UnexpectedEnd);
+        assert_eq!(
+            error("a b"),
+            ExpressionError::UnexpectedToken("b".to_string())
+        );
+        assert_eq!(
+            error("f(a,)"),
+            ExpressionError::UnexpectedToken(")".to_string())
+        );
+        assert_eq!(
+            error("a.'b'"),
+            ExpressionError::UnexpectedToken("'b'".to_string())
+        );
+        assert_eq!(
+            error("== a"),
+            ExpressionError::UnexpectedToken("==".to_string())
+        );
+    }
+}
@@ -0,0 +1,273 @@
+// What GitHub has for the repositories and refs of actions, for checking
+// `uses:` references online
+use crate::{GithubClient, GithubError};
+use serde::de::{DeserializeOwned, IgnoredAny};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// How long a tag or branch is trusted to point where it did; a commit SHA
+/// can't move, so those are kept for good
+const CACHE_TTL_SECS: i64 = 24 * 60 * 60;
+
+/// The repository and ref of a `uses: owner/repo[/path]@ref` action
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ActionRef {
+    pub owner: String,
+    pub repo: String,
+    pub git_ref: String,
+}
+
+impl ActionRef {
+    /// `None` for local actions, `docker://` images and references without
+    /// a ref, which have nothing to look up
+    pub fn parse(uses: &str) -> Option<Self> {
+        if uses.starts_with("./") || uses.starts_with("docker://") {
+            return None;
+        }
+        let (path, git_ref) = uses.split_once('@')?;
+        let mut parts = path.split('/');
+        let (owner, repo) = (parts.next()?, parts.next()?);
+        if owner.is_empty() || repo.is_empty() || git_ref.is_empty() {
+            return None;
+        }
+        Some(ActionRef {
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+            git_ref: git_ref.to_string(),
+        })
+    }
+
+    /// `owner/repo@ref`, which is also its key in the cache
+    pub fn key(&self) -> String {
+        format!("{}/{}@{}", self.owner, self.repo, self.git_ref)
+    }
+
+    /// Whether the ref is a full commit SHA
+    pub fn is_pinned(&self) -> bool {
+        self.git_ref.len() == 40 && self.git_ref.chars().all(|c| c.is_ascii_hexdigit())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RefKind {
+    Commit,
+    Tag,
+    Branch,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "kebab-case")]
+pub enum RefResolution {
+    /// The ref is a commit, tag or branch, now at commit `sha`
+    Resolved { kind: RefKind, sha: String },
+    /// The repository has no such commit, tag or branch
+    UnknownRef,
+    /// There's no such repository, or it's private and the token can't
+    /// see it
+    MissingRepository,
+}
+
+/// A commit as `GET .../commits/{ref}` returns it
+#[derive(Deserialize)]
+struct Commit {
+    sha: String,
+}
+
+/// Ask the GitHub API what `action`'s ref is. A token on `client` raises
+/// the rate limit and gives access to private repositories.
+pub async fn resolve(
+    client: &GithubClient,
+    action: &ActionRef,
+) -> Result<RefResolution, GithubError> {
+    let repo_url = format!(
+        "{}/repos/{}/{}",
+        crate::client::API_URL,
+        action.owner,
+        action.repo
+    );
+
+    let commit: Option<Commit> =
+        get(client, &format!("{}/commits/{}", repo_url, action.git_ref)).await?;
+    let Some(Commit { sha }) = commit else {
+        return Ok(match get::<IgnoredAny>(client, &repo_url).await? {
+            Some(_) => RefResolution::UnknownRef,
+            None => RefResolution::MissingRepository,
+        });
+    };
+
+    let kind = if sha.eq_ignore_ascii_case(&action.git_ref) {
+        RefKind::Commit
+    } else if get::<IgnoredAny>(
+        client,
+        &format!("{}/git/ref/tags/{}", repo_url, action.git_ref),
+    )
+    .await?
+    .is_some()
+    {
+        RefKind::Tag
+    } else {
+        RefKind::Branch
+    };
+    Ok(RefResolution::Resolved { kind, sha })
+}
+
+/// The JSON at `url`, or `None` when GitHub says there's nothing there
+pub(crate) async fn get<T: DeserializeOwned>(
+    client: &GithubClient,
+    url: &str,
+) -> Result<Option<T>, GithubError> {
+    match client.get_json(url).await {
+        Ok(value) => Ok(Some(value)),
+        // 422 is what the commits endpoint answers for refs it can't parse
+        Err(GithubError::ApiError {
+            status: 404 | 422, ..
+        }) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedRef {
+    resolution: RefResolution,
+    /// Unix time of the lookup
+    fetched_at: i64,
+}
+
+/// Answers of earlier lookups, stored as one JSON file
+pub struct RefCache {
+    path: PathBuf,
+    entries: HashMap<String, CachedRef>,
+}
+
+impl RefCache {
+    /// The cache in `path`; a missing or unreadable file gives an empty one
+    pub fn load(path: &Path) -> Self {
+        let entries = fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        RefCache {
+            path: path.to_path_buf(),
+            entries,
+        }
+    }
+
+    /// The cached answer for `action`, unless it's too old to trust
+    pub fn get(&self, action: &ActionRef) -> Option<RefResolution> {
+        let cached = self.entries.get(&action.key())?;
+        let pinned = matches!(
+            cached.resolution,
+            RefResolution::Resolved {
+                kind: RefKind::Commit,
+                ..
+            }
+        );
+        let age = chrono::Utc::now().timestamp() - cached.fetched_at;
+        (pinned || age < CACHE_TTL_SECS).then(|| cached.resolution.clone())
+    }
+
+    pub fn insert(&mut self, action: &ActionRef, resolution: RefResolution) {
+        self.entries.insert(
+            action.key(),
+            CachedRef {
+                resolution,
+                fetched_at: chrono::Utc::now().timestamp(),
+            },
+        );
+    }
+
+    /// Write the cache back; one that can't be written only costs lookups.
+    /// Entries saved meanwhile, e.g. by files validated alongside, are kept,
+    /// and the file is replaced at once so it's never read half written.
+    pub fn save(&self) {
+        let mut entries = RefCache::load(&self.path).entries;
+        entries.extend(self.entries.clone());
+        let Ok(json) = serde_json::to_string(&entries) else {
+            return;
+        };
+        if let Some(dir) = self.path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        static SAVES: AtomicUsize = AtomicUsize::new(0);
+        let temp = self.path.with_extension(format!(
+            "json.{}-{}.tmp",
+            std::process::id(),
+            SAVES.fetch_add(1, Ordering::Relaxed)
+        ));
+        if fs::write(&temp, json).is_ok() && fs::rename(&temp, &self.path).is_err() {
+            let _ = fs::remove_file(&temp);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_references_and_caches_answers() {
+        let action = ActionRef::parse("github/codeql-action/init@v3").unwrap();
+        assert_eq!(action.key(), "github/codeql-action@v3");
+        assert!(!action.is_pinned());
+        assert!(
+            ActionRef::parse("actions/checkout@8ade135a41bc03ea155e62e844d188df1ea18608")
+                .unwrap()
+                .is_pinned()
+        );
+        for uses in [
+            "./local",
+            "docker://alpine:3",
+            "actions/checkout",
+            "checkout@v4",
+        ] {
+            assert_eq!(ActionRef::parse(uses), None, "{}", uses);
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache").join("actions.json");
+        let tag = RefResolution::Resolved {
+            kind: RefKind::Tag,
+            sha: "abc".to_string(),
+        };
+        let mut cache = RefCache::load(&path);
+        cache.insert(&action, tag.clone());
+        cache.save();
+
+        let mut cache = RefCache::load(&path);
+        assert_eq!(cache.get(&action), Some(tag));
+        // Tags and branches go stale, commits don't
+        cache.entries.get_mut(&action.key()).unwrap().fetched_at -= CACHE_TTL_SECS;
+        assert_eq!(cache.get(&action), None);
+        let commit = RefResolution::Resolved {
+            kind: RefKind::Commit,
+            sha: "abc".to_string(),
+        };
+        cache.insert(&action, commit.clone());
+        cache.entries.get_mut(&action.key()).unwrap().fetched_at = 0;
+        assert_eq!(cache.get(&action), Some(commit));
+    }
+
+    #[test]
+    fn saving_keeps_entries_saved_meanwhile() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("actions.json");
+        let checkout = ActionRef::parse("actions/checkout@v4").unwrap();
+        let cache_action = ActionRef::parse("actions/cache@v4").unwrap();
+
+        let mut first = RefCache::load(&path);
+        let mut second = RefCache::load(&path);
+        first.insert(&checkout, RefResolution::MissingRepository);
+        second.insert(&cache_action, RefResolution::MissingRepository);
+        first.save();
+        second.save();
+
+        let cache = RefCache::load(&path);
+        assert!(cache.get(&checkout).is_some());
+        assert!(cache.get(&cache_action).is_some());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}
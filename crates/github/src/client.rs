@@ -0,0 +1,206 @@
+// Authenticated client for the GitHub REST API, retrying what can be
+// retried and waiting out short rate limits
+use crate::GithubError;
+use reqwest::header::{self, HeaderMap, HeaderValue};
+use reqwest::{Method, StatusCode};
+use serde::de::DeserializeOwned;
+use std::time::Duration;
+
+pub(crate) const API_URL: &str = "https://api.github.com";
+
+/// Attempts at a request before its last error is returned
+const MAX_ATTEMPTS: u32 = 4;
+
+/// Longest wait for a rate limit to reset; longer ones fail right away
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+
+/// One connection pool and token for every request of a command
+#[derive(Debug, Clone)]
+pub struct GithubClient {
+    http: reqwest::Client,
+    authorization: Option<HeaderValue>,
+    max_attempts: u32,
+}
+
+impl GithubClient {
+    /// A client sending `token`, or anonymous requests without one
+    pub fn new(token: Option<&str>) -> Result<Self, GithubError> {
+        let authorization = token
+            .map(|token| {
+                let mut value = HeaderValue::from_str(&format!("Bearer {}", token.trim()))
+                    .map_err(|_| GithubError::GitParseError("Invalid token format".to_string()))?;
+                value.set_sensitive(true);
+                Ok::<_, GithubError>(value)
+            })
+            .transpose()?;
+        Ok(GithubClient {
+            http: reqwest::Client::new(),
+            authorization,
+            max_attempts: MAX_ATTEMPTS,
+        })
+    }
+
+    /// A client with the token in `GITHUB_TOKEN`
+    pub fn from_env() -> Result<Self, GithubError> {
+        let token = std::env::var("GITHUB_TOKEN").map_err(|_| GithubError::TokenNotFound)?;
+        Self::new(Some(&token))
+    }
+
+    /// Give up after `attempts` tries, e.g. 1 to fail fast when GitHub
+    /// is only nice to have
+    pub fn with_max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = attempts.max(1);
+        self
+    }
+
+    pub async fn get_json<T: DeserializeOwned>(&self, url: &str) -> Result<T, GithubError> {
+        let body = self.get_text(url).await?;
+        serde_json::from_str(&body)
+            .map_err(|e| GithubError::GitParseError(format!("Failed to parse API response: {}", e)))
+    }
+
+    pub async fn get_text(&self, url: &str) -> Result<String, GithubError> {
+        Ok(self.send(Method::GET, url, None).await?.text().await?)
+    }
+
+    pub async fn post_json(&self, url: &str, body: &serde_json::Value) -> Result<(), GithubError> {
+        self.send(Method::POST, url, Some(body)).await?;
+        Ok(())
+    }
+
+    /// Send a request until it succeeds, fails for good or runs out of
+    /// attempts. Only GETs are retried after GitHub has seen them, so a
+    /// dispatch is never sent twice.
+    async fn send(
+        &self,
+        method: Method,
+        url: &str,
+        body: Option<&serde_json::Value>,
+    ) -> Result<reqwest::Response, GithubError> {
+        let idempotent = method == Method::GET;
+        let mut attempt = 1;
+        loop {
+            let mut request = self
+                .http
+                .request(method.clone(), url)
+                .header(header::ACCEPT, "application/vnd.github.v3+json")
+                .header(header::USER_AGENT, "wrkflw-cli");
+            if let Some(authorization) = &self.authorization {
+                request = request.header(header::AUTHORIZATION, authorization.clone());
+            }
+            if let Some(body) = body {
+                request = request.json(body);
+            }
+            let last_attempt = attempt >= self.max_attempts;
+
+            let wait = match request.send().await {
+                Ok(response) if response.status().is_success() => return Ok(response),
+                Ok(response) => {
+                    let status = response.status();
+                    let now = chrono::Utc::now().timestamp();
+                    let rate_limit = rate_limit_wait(status, response.headers(), now);
+                    let message = response.text().await.unwrap_or_default();
+                    match rate_limit {
+                        Some(wait) if last_attempt || wait > MAX_RATE_LIMIT_WAIT => {
+                            return Err(GithubError::RateLimited(wait.as_secs()));
+                        }
+                        Some(wait) => wait,
+                        None if idempotent && status.is_server_error() && !last_attempt => {
+                            backoff(attempt)
+                        }
+                        None => {
+                            return Err(GithubError::ApiError {
+                                status: status.as_u16(),
+                                message,
+                            })
+                        }
+                    }
+                }
+                // A failed connection never reached GitHub, so any request
+                // may try again
+                Err(e) if !last_attempt && (e.is_connect() || (idempotent && e.is_timeout())) => {
+                    backoff(attempt)
+                }
+                Err(e) => return Err(e.into()),
+            };
+            tokio::time::sleep(wait).await;
+            attempt += 1;
+        }
+    }
+}
+
+/// 1s, 2s, 4s, ... before attempt `attempt + 1`
+fn backoff(attempt: u32) -> Duration {
+    Duration::from_secs(1 << (attempt - 1).min(5))
+}
+
+/// How long to wait when a response says the rate limit was hit, from its
+/// `retry-after` or `x-ratelimit-reset` header; `None` for other failures
+fn rate_limit_wait(status: StatusCode, headers: &HeaderMap, now: i64) -> Option<Duration> {
+    if status != StatusCode::FORBIDDEN && status != StatusCode::TOO_MANY_REQUESTS {
+        return None;
+    }
+    let number = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<i64>().ok())
+    };
+    if let Some(seconds) = number("retry-after") {
+        return Some(Duration::from_secs(seconds.max(0) as u64));
+    }
+    if number("x-ratelimit-remaining") == Some(0) {
+        let reset = number("x-ratelimit-reset").unwrap_or(now + 60);
+        // A second of slack for clock skew
+        return Some(Duration::from_secs((reset - now).max(0) as u64 + 1));
+    }
+    // A 403 without rate limit headers is a permissions problem
+    (status == StatusCode::TOO_MANY_REQUESTS).then(|| Duration::from_secs(60))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tells_rate_limits_from_other_failures() {
+        let headers = |pairs: &[(&'static str, &str)]| {
+            let mut map = HeaderMap::new();
+            for (name, value) in pairs {
+                map.insert(*name, HeaderValue::from_str(value).unwrap());
+            }
+            map
+        };
+
+        let exhausted = headers(&[
+            ("x-ratelimit-remaining", "0"),
+            ("x-ratelimit-reset", "1030"),
+        ]);
+        assert_eq!(
+            rate_limit_wait(StatusCode::FORBIDDEN, &exhausted, 1000),
+            Some(Duration::from_secs(31))
+        );
+        let secondary = headers(&[("retry-after", "5"), ("x-ratelimit-remaining", "12")]);
+        assert_eq!(
+            rate_limit_wait(StatusCode::FORBIDDEN, &secondary, 1000),
+            Some(Duration::from_secs(5))
+        );
+        let forbidden = headers(&[("x-ratelimit-remaining", "4999")]);
+        assert_eq!(
+            rate_limit_wait(StatusCode::FORBIDDEN, &forbidden, 1000),
+            None
+        );
+        assert_eq!(
+            rate_limit_wait(StatusCode::INTERNAL_SERVER_ERROR, &exhausted, 1000),
+            None
+        );
+        assert_eq!(
+            rate_limit_wait(StatusCode::TOO_MANY_REQUESTS, &HeaderMap::new(), 1000),
+            Some(Duration::from_secs(60))
+        );
+
+        assert_eq!(backoff(1), Duration::from_secs(1));
+        assert_eq!(backoff(3), Duration::from_secs(4));
+        assert!(GithubClient::new(Some("bad\ntoken")).is_err());
+    }
+}
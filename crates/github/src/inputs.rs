@@ -0,0 +1,166 @@
+// The `workflow_dispatch` inputs a workflow declares, for checking the
+// values of a dispatch and asking for the missing ones
+use crate::GithubError;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputKind {
+    String,
+    Boolean,
+    Number,
+    /// One of the listed options
+    Choice(Vec<String>),
+    /// Name of one of the repository's environments
+    Environment,
+}
+
+/// One input under `on.workflow_dispatch.inputs`
+#[derive(Debug, Clone, PartialEq)]
+pub struct DispatchInput {
+    pub name: String,
+    pub description: Option<String>,
+    pub required: bool,
+    pub default: Option<String>,
+    pub kind: InputKind,
+}
+
+impl DispatchInput {
+    fn from_yaml(name: &str, spec: &serde_yaml::Value) -> Self {
+        let text = |key: &str| spec.get(key).and_then(scalar);
+        let kind = match text("type").as_deref() {
+            Some("boolean") => InputKind::Boolean,
+            Some("number") => InputKind::Number,
+            Some("environment") => InputKind::Environment,
+            Some("choice") => InputKind::Choice(
+                spec.get("options")
+                    .and_then(|o| o.as_sequence())
+                    .map(|options| options.iter().filter_map(scalar).collect())
+                    .unwrap_or_default(),
+            ),
+            _ => InputKind::String,
+        };
+        DispatchInput {
+            name: name.to_string(),
+            description: text("description"),
+            required: spec
+                .get("required")
+                .and_then(|r| r.as_bool())
+                .unwrap_or(false),
+            default: text("default"),
+            kind,
+        }
+    }
+
+    /// Whether a dispatch has to give a value: GitHub fills in defaults
+    pub fn needs_value(&self) -> bool {
+        self.required && self.default.is_none()
+    }
+
+    /// Why `value` doesn't fit the input's type, if it doesn't
+    pub fn check(&self, value: &str) -> Result<(), String> {
+        match &self.kind {
+            InputKind::Boolean if value != "true" && value != "false" => Err(format!(
+                "input '{}' must be 'true' or 'false', got '{}'",
+                self.name, value
+            )),
+            InputKind::Number if value.parse::<f64>().is_err() => Err(format!(
+                "input '{}' must be a number, got '{}'",
+                self.name, value
+            )),
+            InputKind::Choice(options)
+                if !options.is_empty() && !options.iter().any(|o| o == value) =>
+            {
+                Err(format!(
+                    "input '{}' must be one of [{}], got '{}'",
+                    self.name,
+                    options.join(", "),
+                    value
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The dispatch API takes strings, so YAML scalars are compared as text
+fn scalar(value: &serde_yaml::Value) -> Option<String> {
+    match value {
+        serde_yaml::Value::String(s) => Some(s.clone()),
+        serde_yaml::Value::Number(n) => Some(n.to_string()),
+        serde_yaml::Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// The inputs `workflow_path` declares for `workflow_dispatch` in order,
+/// or `None` when it declares none
+pub fn dispatch_inputs(workflow_path: &Path) -> Result<Option<Vec<DispatchInput>>, GithubError> {
+    let content = fs::read_to_string(workflow_path)?;
+    let workflow: serde_yaml::Value = serde_yaml::from_str(&content).map_err(|e| {
+        GithubError::InvalidInputs(format!(
+            "failed to parse {}: {}",
+            workflow_path.display(),
+            e
+        ))
+    })?;
+
+    let declared = workflow
+        .get("on")
+        .and_then(|on| on.get("workflow_dispatch"))
+        .and_then(|dispatch| dispatch.get("inputs"))
+        .and_then(|inputs| inputs.as_mapping());
+    Ok(declared.map(|declared| {
+        declared
+            .iter()
+            .filter_map(|(name, spec)| Some(DispatchInput::from_yaml(name.as_str()?, spec)))
+            .collect()
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_declared_inputs_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("deploy.yml");
+        fs::write(
+            &path,
+            r#"
+on:
+  workflow_dispatch:
+    inputs:
+      environment:
+        description: Where to deploy
+        type: choice
+        required: true
+        options: [staging, production]
+      dry_run:
+        type: boolean
+        required: true
+        default: false
+      version:
+        required: false
+"#,
+        )
+        .unwrap();
+
+        let inputs = dispatch_inputs(&path).unwrap().unwrap();
+        let names: Vec<&str> = inputs.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(names, ["environment", "dry_run", "version"]);
+        assert_eq!(inputs[0].description.as_deref(), Some("Where to deploy"));
+        assert!(inputs[0].needs_value());
+        assert!(!inputs[1].needs_value());
+        assert_eq!(inputs[1].default.as_deref(), Some("false"));
+        assert_eq!(inputs[2].kind, InputKind::String);
+
+        assert!(inputs[0].check("production").is_ok());
+        assert!(inputs[0].check("qa").is_err());
+        assert!(inputs[1].check("yes").is_err());
+
+        fs::write(&path, "on: push\n").unwrap();
+        assert_eq!(dispatch_inputs(&path).unwrap(), None);
+    }
+}
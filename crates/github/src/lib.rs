@@ -2,14 +2,22 @@
 
 use lazy_static::lazy_static;
 use regex::Regex;
-use reqwest::header;
-use serde_json::{self};
+use serde::{Deserialize, Deserializer};
 use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::time::Duration;
 use thiserror::Error;
 
+pub mod action_refs;
+pub mod client;
+pub mod inputs;
+pub mod outdated;
+pub mod runs;
+
+pub use client::GithubClient;
+
 #[derive(Error, Debug)]
 pub enum GithubError {
     #[error("HTTP error: {0}")]
@@ -26,6 +34,15 @@ pub enum GithubError {
 
     #[error("API error: {status} - {message}")]
     ApiError { status: u16, message: String },
+
+    #[error("Invalid workflow inputs: {0}")]
+    InvalidInputs(String),
+
+    #[error("Timed out: {0}")]
+    Timeout(String),
+
+    #[error("Rate limited by GitHub, try again in {0}s")]
+    RateLimited(u64),
 }
 
 /// Information about a GitHub repository
@@ -36,6 +53,19 @@ pub struct RepoInfo {
     pub default_branch: String,
 }
 
+impl RepoInfo {
+    /// API URL of `path` under this repository, e.g. `actions/runs`
+    pub(crate) fn api_url(&self, path: &str) -> String {
+        format!(
+            "{}/repos/{}/{}/{}",
+            client::API_URL,
+            self.owner,
+            self.repo,
+            path
+        )
+    }
+}
+
 lazy_static! {
     static ref GITHUB_REPO_REGEX: Regex =
         Regex::new(r"(?:https://github\.com/|git@github\.com:)([^/]+)/([^/.]+)(?:\.git)?")
@@ -146,15 +176,7 @@ pub async fn trigger_workflow(
     branch: Option<&str>,
     inputs: Option<HashMap<String, String>>,
 ) -> Result<(), GithubError> {
-    // Get GitHub token from environment
-    let token = std::env::var("GITHUB_TOKEN").map_err(|_| GithubError::TokenNotFound)?;
-
-    // Trim the token to remove any leading or trailing whitespace
-    let trimmed_token = token.trim();
-
-    // Convert token to HeaderValue
-    let token_header = header::HeaderValue::from_str(&format!("Bearer {}", trimmed_token))
-        .map_err(|_| GithubError::GitParseError("Invalid token format".to_string()))?;
+    let client = GithubClient::from_env()?;
 
     // Get repository information
     let repo_info = get_repo_info()?;
@@ -176,87 +198,40 @@ pub async fn trigger_workflow(
 
     println!("Using workflow name: {}", workflow_name);
 
-    // Create simplified payload
-    let mut payload = serde_json::json!({
-        "ref": branch_ref
-    });
-
-    // Add inputs if provided
-    if let Some(input_map) = inputs {
-        payload["inputs"] = serde_json::json!(input_map);
+    if let Some(input_map) = &inputs {
         println!("With inputs: {:?}", input_map);
     }
-
-    // Send the workflow_dispatch event
-    let url = format!(
-        "https://api.github.com/repos/{}/{}/actions/workflows/{}.yml/dispatches",
-        repo_info.owner, repo_info.repo, workflow_name
+    println!(
+        "Triggering workflow at URL: {}",
+        dispatch_url(&repo_info, &format!("{}.yml", workflow_name))
     );
 
-    println!("Triggering workflow at URL: {}", url);
-
-    // Create a reqwest client
-    let client = reqwest::Client::new();
-
-    // Send the request using reqwest
-    let response = client
-        .post(&url)
-        .header(header::AUTHORIZATION, token_header)
-        .header(header::ACCEPT, "application/vnd.github.v3+json")
-        .header(header::CONTENT_TYPE, "application/json")
-        .header(header::USER_AGENT, "wrkflw-cli")
-        .json(&payload)
-        .send()
-        .await
-        .map_err(GithubError::RequestError)?;
-
-    if !response.status().is_success() {
-        let status = response.status().as_u16();
-        let error_message = response
-            .text()
-            .await
-            .unwrap_or_else(|_| format!("Unknown error (HTTP {})", status));
-
-        // Add more detailed error information
-        let error_details = if status == 500 {
-            "Internal server error from GitHub. This could be due to:\n\
-             1. The workflow file doesn't exist in the repository\n\
-             2. The GitHub token doesn't have sufficient permissions\n\
-             3. There's an issue with the workflow file itself\n\
-             Please check:\n\
-             - The workflow file exists at .github/workflows/rust.yml\n\
-             - Your GitHub token has the 'workflow' scope\n\
-             - The workflow file is valid YAML"
-        } else {
-            &error_message
-        };
-
-        return Err(GithubError::ApiError {
-            status,
-            message: error_details.to_string(),
-        });
-    }
+    dispatch_workflow(
+        &client,
+        &repo_info,
+        &format!("{}.yml", workflow_name),
+        branch_ref,
+        inputs.as_ref(),
+    )
+    .await?;
 
     println!("Workflow triggered successfully!");
     println!(
         "View runs at: https://github.com/{}/{}/actions/workflows/{}.yml",
         repo_info.owner, repo_info.repo, workflow_name
     );
+    println!(
+        "Follow the run with: wrkflw watch-run --workflow {}.yml --branch {}",
+        workflow_name, branch_ref
+    );
 
     // Attempt to verify the workflow was actually triggered
-    match list_recent_workflow_runs(&repo_info, workflow_name, &token).await {
+    match list_recent_workflow_runs(&client, &repo_info, workflow_name).await {
         Ok(runs) => {
             if !runs.is_empty() {
                 println!("\nRecent runs of this workflow:");
                 for run in runs.iter().take(3) {
-                    println!(
-                        "- Run #{} ({}): {}",
-                        run.get("id").and_then(|id| id.as_u64()).unwrap_or(0),
-                        run.get("status")
-                            .and_then(|s| s.as_str())
-                            .unwrap_or("unknown"),
-                        run.get("html_url").and_then(|u| u.as_str()).unwrap_or("")
-                    );
+                    println!("- Run #{} ({}): {}", run.id, run.status, run.html_url);
                 }
             } else {
                 println!("\nNo recent runs found. The workflow might still be initializing.");
@@ -276,12 +251,380 @@ pub async fn trigger_workflow(
     Ok(())
 }
 
+fn dispatch_url(repo_info: &RepoInfo, workflow_file: &str) -> String {
+    repo_info.api_url(&format!("actions/workflows/{}/dispatches", workflow_file))
+}
+
+/// Send a `workflow_dispatch` event for a workflow file, e.g. `deploy.yml`
+pub async fn dispatch_workflow(
+    client: &GithubClient,
+    repo_info: &RepoInfo,
+    workflow_file: &str,
+    branch: &str,
+    inputs: Option<&HashMap<String, String>>,
+) -> Result<(), GithubError> {
+    let mut payload = serde_json::json!({ "ref": branch });
+    if let Some(inputs) = inputs {
+        payload["inputs"] = serde_json::json!(inputs);
+    }
+
+    client
+        .post_json(&dispatch_url(repo_info, workflow_file), &payload)
+        .await
+        .map_err(|e| match e {
+            // Add more detailed error information
+            GithubError::ApiError { status: 500, .. } => GithubError::ApiError {
+                status: 500,
+                message: format!(
+                    "Internal server error from GitHub. This could be due to:\n\
+                     1. The workflow file doesn't exist in the repository\n\
+                     2. The GitHub token doesn't have sufficient permissions\n\
+                     3. There's an issue with the workflow file itself\n\
+                     Please check:\n\
+                     - The workflow file exists at .github/workflows/{}\n\
+                     - Your GitHub token has the 'workflow' scope\n\
+                     - The workflow file is valid YAML",
+                    workflow_file
+                ),
+            },
+            e => e,
+        })
+}
+
+/// A run of a workflow on GitHub
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct WorkflowRun {
+    pub id: u64,
+    /// Name of the workflow
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub name: String,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub head_branch: String,
+    /// `queued`, `in_progress` or `completed`
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub status: String,
+    /// Set once completed, e.g. `success` or `failure`
+    #[serde(default)]
+    pub conclusion: Option<String>,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub html_url: String,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub created_at: String,
+}
+
+/// A page of `GET .../runs`
+#[derive(Debug, Deserialize)]
+pub(crate) struct WorkflowRuns {
+    pub workflow_runs: Vec<WorkflowRun>,
+}
+
+/// Fields the API sends as `null` when they don't apply
+pub(crate) fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+/// Wait for the run started by a dispatch sent at `dispatched_at` (UTC,
+/// RFC 3339) to show up, then poll it until it completes
+pub async fn watch_dispatched_run(
+    client: &GithubClient,
+    repo_info: &RepoInfo,
+    workflow_file: &str,
+    branch: &str,
+    dispatched_at: &str,
+    poll_interval: Duration,
+) -> Result<WorkflowRun, GithubError> {
+    let mut run =
+        find_dispatched_run(client, repo_info, workflow_file, branch, dispatched_at).await?;
+    while run.status != "completed" {
+        tokio::time::sleep(poll_interval).await;
+        run = runs::fetch_run(client, repo_info, run.id).await?;
+    }
+    Ok(run)
+}
+
+/// Wait for the run started by a dispatch sent at `dispatched_at` (UTC,
+/// RFC 3339) to show up
+pub async fn find_dispatched_run(
+    client: &GithubClient,
+    repo_info: &RepoInfo,
+    workflow_file: &str,
+    branch: &str,
+    dispatched_at: &str,
+) -> Result<WorkflowRun, GithubError> {
+    // GitHub takes a few seconds to create the run after a dispatch
+    const RUN_APPEARS_WITHIN: u32 = 30;
+    let runs_url = repo_info.api_url(&format!(
+        "actions/workflows/{}/runs?event=workflow_dispatch&branch={}&per_page=5",
+        workflow_file, branch
+    ));
+
+    let mut run = None;
+    for _ in 0..RUN_APPEARS_WITHIN {
+        let runs: WorkflowRuns = client.get_json(&runs_url).await?;
+        run = runs
+            .workflow_runs
+            .into_iter()
+            // Timestamps are UTC RFC 3339, so they compare as strings
+            .filter(|run| run.created_at.as_str() >= dispatched_at)
+            .min_by(|a, b| a.created_at.cmp(&b.created_at));
+        if run.is_some() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_secs(2)).await;
+    }
+    run.ok_or_else(|| {
+        GithubError::Timeout(format!(
+            "no run of {} showed up after the dispatch",
+            workflow_file
+        ))
+    })
+}
+
+/// A local workflow picked for dispatch by name or pattern
+#[derive(Debug, Clone, PartialEq)]
+pub struct DispatchTarget {
+    pub path: PathBuf,
+    /// File name, which is how the API refers to the workflow
+    pub file_name: String,
+    /// The workflow's `name:`, or its file name without the extension
+    pub name: String,
+    /// Whether it has a `workflow_dispatch` trigger
+    pub dispatchable: bool,
+}
+
+/// Workflows in `dir` whose file name, file stem or `name:` matches the
+/// glob `pattern`, or every workflow without a pattern
+pub fn find_dispatch_targets(
+    dir: &Path,
+    pattern: Option<&str>,
+) -> Result<Vec<DispatchTarget>, GithubError> {
+    let pattern = pattern
+        .map(glob::Pattern::new)
+        .transpose()
+        .map_err(|e| GithubError::InvalidInputs(format!("invalid workflow pattern: {}", e)))?;
+
+    let mut targets = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if !path.is_file()
+            || !path
+                .extension()
+                .is_some_and(|ext| ext == "yml" || ext == "yaml")
+        {
+            continue;
+        }
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let stem = path
+            .file_stem()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let workflow: Option<serde_yaml::Value> = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_yaml::from_str(&content).ok());
+        let display_name = workflow
+            .as_ref()
+            .and_then(|w| w.get("name"))
+            .and_then(|n| n.as_str())
+            .map(str::to_string);
+
+        if let Some(pattern) = &pattern {
+            let matches = [Some(&file_name), Some(&stem), display_name.as_ref()]
+                .into_iter()
+                .flatten()
+                .any(|candidate| pattern.matches(candidate));
+            if !matches {
+                continue;
+            }
+        }
+
+        targets.push(DispatchTarget {
+            dispatchable: workflow.as_ref().is_some_and(has_dispatch_trigger),
+            name: display_name.unwrap_or(stem),
+            file_name,
+            path,
+        });
+    }
+    targets.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+    Ok(targets)
+}
+
+fn has_dispatch_trigger(workflow: &serde_yaml::Value) -> bool {
+    match workflow.get("on") {
+        Some(serde_yaml::Value::String(event)) => event == "workflow_dispatch",
+        Some(serde_yaml::Value::Sequence(events)) => events
+            .iter()
+            .any(|event| event.as_str() == Some("workflow_dispatch")),
+        Some(serde_yaml::Value::Mapping(events)) => events.contains_key("workflow_dispatch"),
+        _ => false,
+    }
+}
+
+/// The subset of shared inputs that a workflow declares, so one set of
+/// inputs can be sent to several workflows
+pub fn declared_inputs(
+    workflow_path: &Path,
+    inputs: &HashMap<String, String>,
+) -> Result<HashMap<String, String>, GithubError> {
+    let content = fs::read_to_string(workflow_path)?;
+    let workflow: serde_yaml::Value = serde_yaml::from_str(&content).map_err(|e| {
+        GithubError::InvalidInputs(format!(
+            "failed to parse {}: {}",
+            workflow_path.display(),
+            e
+        ))
+    })?;
+    let declared = workflow
+        .get("on")
+        .and_then(|on| on.get("workflow_dispatch"))
+        .and_then(|dispatch| dispatch.get("inputs"))
+        .and_then(|inputs| inputs.as_mapping());
+    Ok(inputs
+        .iter()
+        .filter(|(key, _)| declared.is_some_and(|declared| declared.contains_key(key.as_str())))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect())
+}
+
+/// Load workflow_dispatch inputs from a JSON or YAML file containing a flat mapping
+pub fn load_inputs_file(path: &Path) -> Result<HashMap<String, String>, GithubError> {
+    let content = fs::read_to_string(path)?;
+
+    let is_json = path.extension().is_some_and(|ext| ext == "json");
+    let value: serde_yaml::Value = if is_json {
+        serde_json::from_str(&content).map_err(|e| {
+            GithubError::InvalidInputs(format!("{} is not valid JSON: {}", path.display(), e))
+        })?
+    } else {
+        serde_yaml::from_str(&content).map_err(|e| {
+            GithubError::InvalidInputs(format!("{} is not valid YAML: {}", path.display(), e))
+        })?
+    };
+
+    let mapping = match value {
+        serde_yaml::Value::Mapping(mapping) => mapping,
+        serde_yaml::Value::Null => return Ok(HashMap::new()),
+        _ => {
+            return Err(GithubError::InvalidInputs(format!(
+                "{} must contain a mapping of input names to values",
+                path.display()
+            )))
+        }
+    };
+
+    let mut inputs = HashMap::new();
+    for (key, value) in mapping {
+        let key = match key {
+            serde_yaml::Value::String(key) => key,
+            other => serde_yaml::to_string(&other)
+                .unwrap_or_default()
+                .trim()
+                .to_string(),
+        };
+        // The dispatch API only accepts strings, so scalars are stringified
+        let value = match value {
+            serde_yaml::Value::String(s) => s,
+            serde_yaml::Value::Bool(b) => b.to_string(),
+            serde_yaml::Value::Number(n) => n.to_string(),
+            serde_yaml::Value::Null => String::new(),
+            _ => {
+                return Err(GithubError::InvalidInputs(format!(
+                    "input '{}' must be a string, number or boolean",
+                    key
+                )))
+            }
+        };
+        inputs.insert(key, value);
+    }
+
+    Ok(inputs)
+}
+
+/// Find the local file for a workflow given by name or path
+pub fn find_workflow_file(workflow_name: &str) -> Option<std::path::PathBuf> {
+    let path = Path::new(workflow_name);
+    if path.is_file() {
+        return Some(path.to_path_buf());
+    }
+
+    let stem = path.file_stem()?.to_str()?;
+    ["yml", "yaml"]
+        .iter()
+        .map(|ext| Path::new(".github/workflows").join(format!("{}.{}", stem, ext)))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Check inputs against the `workflow_dispatch` inputs declared in a workflow file.
+/// All problems are reported together, naming the offending keys.
+pub fn validate_dispatch_inputs(
+    workflow_path: &Path,
+    inputs: &HashMap<String, String>,
+) -> Result<(), GithubError> {
+    let mut problems = Vec::new();
+
+    let Some(declared) = inputs::dispatch_inputs(workflow_path)? else {
+        if !inputs.is_empty() {
+            let mut keys: Vec<&String> = inputs.keys().collect();
+            keys.sort();
+            problems.push(format!(
+                "workflow declares no workflow_dispatch inputs, but got: {}",
+                keys.iter()
+                    .map(|k| format!("'{}'", k))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        return if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(GithubError::InvalidInputs(problems.join("; ")))
+        };
+    };
+
+    let declared_names: Vec<&str> = declared.iter().map(|input| input.name.as_str()).collect();
+
+    let mut unknown: Vec<&String> = inputs
+        .keys()
+        .filter(|key| !declared_names.contains(&key.as_str()))
+        .collect();
+    unknown.sort();
+    for key in unknown {
+        problems.push(format!(
+            "unknown input '{}' (declared inputs: {})",
+            key,
+            declared_names.join(", ")
+        ));
+    }
+
+    for input in &declared {
+        match inputs.get(&input.name) {
+            Some(value) => problems.extend(input.check(value).err()),
+            None if input.needs_value() => {
+                problems.push(format!("missing required input '{}'", input.name))
+            }
+            None => {}
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(GithubError::InvalidInputs(problems.join("; ")))
+    }
+}
+
 /// List recent workflow runs for a specific workflow
 async fn list_recent_workflow_runs(
+    client: &GithubClient,
     repo_info: &RepoInfo,
     workflow_name: &str,
-    token: &str,
-) -> Result<Vec<serde_json::Value>, GithubError> {
+) -> Result<Vec<WorkflowRun>, GithubError> {
     // Extract just the workflow name from the path if it's a full path
     let workflow_name = if workflow_name.contains('/') {
         Path::new(workflow_name)
@@ -293,37 +636,124 @@ async fn list_recent_workflow_runs(
     };
 
     // Get recent workflow runs via GitHub API
-    let url = format!(
-        "https://api.github.com/repos/{}/{}/actions/workflows/{}.yml/runs?per_page=5",
-        repo_info.owner, repo_info.repo, workflow_name
-    );
+    let url = repo_info.api_url(&format!(
+        "actions/workflows/{}.yml/runs?per_page=5",
+        workflow_name
+    ));
+    let runs: WorkflowRuns = client.get_json(&url).await?;
+    Ok(runs.workflow_runs)
+}
 
-    let curl_output = Command::new("curl")
-        .arg("-s")
-        .arg("-H")
-        .arg(format!("Authorization: Bearer {}", token))
-        .arg("-H")
-        .arg("Accept: application/vnd.github.v3+json")
-        .arg(&url)
-        .output()
-        .map_err(|e| GithubError::GitParseError(format!("Failed to execute curl: {}", e)))?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const WORKFLOW: &str = r#"
+on:
+  workflow_dispatch:
+    inputs:
+      environment:
+        type: choice
+        required: true
+        options: [staging, production]
+      dry_run:
+        type: boolean
+        default: false
+      retries:
+        type: number
+"#;
+
+    fn write_file(dir: &Path, name: &str, content: &str) -> std::path::PathBuf {
+        let path = dir.join(name);
+        let mut file = fs::File::create(&path).unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        path
+    }
 
-    if !curl_output.status.success() {
-        let error_message = String::from_utf8_lossy(&curl_output.stderr).to_string();
-        return Err(GithubError::GitParseError(format!(
-            "Failed to list workflow runs: {}",
-            error_message
-        )));
+    #[test]
+    fn loads_inputs_from_json_and_yaml() {
+        let dir = tempfile::tempdir().unwrap();
+        let json = write_file(
+            dir.path(),
+            "inputs.json",
+            r#"{"environment": "staging", "dry_run": true, "retries": 3}"#,
+        );
+        let yaml = write_file(dir.path(), "inputs.yml", "environment: production\n");
+
+        let inputs = load_inputs_file(&json).unwrap();
+        assert_eq!(inputs["dry_run"], "true");
+        assert_eq!(inputs["retries"], "3");
+        assert_eq!(
+            load_inputs_file(&yaml).unwrap()["environment"],
+            "production"
+        );
+
+        let nested = write_file(dir.path(), "bad.json", r#"{"environment": {"a": 1}}"#);
+        assert!(load_inputs_file(&nested).is_err());
+    }
+
+    #[test]
+    fn finds_dispatch_targets_by_pattern() {
+        let dir = tempfile::tempdir().unwrap();
+        write_file(dir.path(), "deploy-staging.yml", WORKFLOW);
+        write_file(
+            dir.path(),
+            "deploy-prod.yaml",
+            "name: Production deploy\non: [push, workflow_dispatch]\njobs: {}\n",
+        );
+        write_file(dir.path(), "lint.yml", "name: Lint\non: push\njobs: {}\n");
+
+        let names = |pattern| -> Vec<String> {
+            find_dispatch_targets(dir.path(), pattern)
+                .unwrap()
+                .into_iter()
+                .map(|t| t.file_name)
+                .collect()
+        };
+        assert_eq!(
+            names(Some("deploy-*")),
+            ["deploy-prod.yaml", "deploy-staging.yml"]
+        );
+        assert_eq!(names(Some("Production*")), ["deploy-prod.yaml"]);
+        assert_eq!(names(None).len(), 3);
+
+        let targets = find_dispatch_targets(dir.path(), None).unwrap();
+        assert!(targets[0].dispatchable);
+        assert_eq!(targets[0].name, "Production deploy");
+        assert_eq!(targets[2].name, "Lint");
+        assert!(!targets[2].dispatchable);
+
+        let mut shared = HashMap::new();
+        shared.insert("environment".to_string(), "staging".to_string());
+        shared.insert("region".to_string(), "eu".to_string());
+        let inputs = declared_inputs(&targets[1].path, &shared).unwrap();
+        assert_eq!(inputs.len(), 1);
+        assert_eq!(inputs["environment"], "staging");
     }
 
-    let response_body = String::from_utf8_lossy(&curl_output.stdout).to_string();
-    let parsed: serde_json::Value = serde_json::from_str(&response_body)
-        .map_err(|e| GithubError::GitParseError(format!("Failed to parse workflow runs: {}", e)))?;
+    #[test]
+    fn validates_inputs_against_declared_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        let workflow = write_file(dir.path(), "deploy.yml", WORKFLOW);
 
-    // Extract the workflow runs from the response
-    if let Some(workflow_runs) = parsed.get("workflow_runs").and_then(|wr| wr.as_array()) {
-        Ok(workflow_runs.clone())
-    } else {
-        Ok(Vec::new())
+        let mut inputs = HashMap::new();
+        inputs.insert("environment".to_string(), "staging".to_string());
+        assert!(validate_dispatch_inputs(&workflow, &inputs).is_ok());
+
+        inputs.insert("environment".to_string(), "qa".to_string());
+        inputs.insert("dry_run".to_string(), "yes".to_string());
+        inputs.insert("typo".to_string(), "1".to_string());
+        let message = validate_dispatch_inputs(&workflow, &inputs)
+            .unwrap_err()
+            .to_string();
+        assert!(message.contains("unknown input 'typo'"));
+        assert!(message.contains("input 'environment' must be one of"));
+        assert!(message.contains("input 'dry_run' must be 'true' or 'false'"));
+
+        let message = validate_dispatch_inputs(&workflow, &HashMap::new())
+            .unwrap_err()
+            .to_string();
+        assert!(message.contains("missing required input 'environment'"));
     }
 }
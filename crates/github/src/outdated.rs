@@ -0,0 +1,201 @@
+// The versions of the actions workflows use against their latest releases,
+// for `wrkflw outdated`
+use crate::action_refs::{self, ActionRef};
+use crate::{GithubClient, GithubError};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Deserialize;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+lazy_static! {
+    /// A `uses:` line: its indentation and list marker, the reference, its
+    /// quotes and a trailing comment
+    static ref USES_LINE: Regex = Regex::new(
+        r#"^(?P<lead>\s*(?:-\s+)?uses:\s*)(?P<quote>['"]?)(?P<uses>[^'"\s#]+)['"]?(?:\s*(?:#\s*(?P<comment>.*))?)$"#
+    )
+    .unwrap();
+    static ref VERSION: Regex = Regex::new(r"^v?(\d+(?:\.\d+)*)$").unwrap();
+}
+
+/// A `uses: owner/repo[/path]@ref` line of a workflow
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActionUse {
+    /// 1-based line number
+    pub line: usize,
+    /// What's before the `@`, e.g. `github/codeql-action/init`
+    pub name: String,
+    pub action: ActionRef,
+    /// The version a pinned SHA is commented with, as in `@abc… # v4.1.0`
+    pub comment: Option<String>,
+}
+
+impl ActionUse {
+    /// The version the workflow uses: its ref, or the comment naming the
+    /// version of a pinned SHA
+    pub fn current_version(&self) -> &str {
+        match &self.comment {
+            Some(comment) if self.action.is_pinned() && version_of(comment).is_some() => comment,
+            _ => &self.action.git_ref,
+        }
+    }
+}
+
+/// The remote actions and reusable workflows `content` uses, found line by
+/// line so they can be rewritten without touching anything else
+pub fn find_action_uses(content: &str) -> Vec<ActionUse> {
+    content
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let captures = USES_LINE.captures(line)?;
+            let uses = &captures["uses"];
+            let action = ActionRef::parse(uses)?;
+            Some(ActionUse {
+                line: i + 1,
+                name: uses.split_once('@')?.0.to_string(),
+                action,
+                comment: captures
+                    .name("comment")
+                    .map(|comment| comment.as_str().trim().to_string())
+                    .filter(|comment| !comment.is_empty()),
+            })
+        })
+        .collect()
+}
+
+/// `content` with each `uses:` whose ref is a key of `shas` pointing at that
+/// commit instead, and the ref kept as a comment, as Dependabot writes them
+pub fn pin_shas(content: &str, shas: &HashMap<String, String>) -> String {
+    let mut pinned: Vec<String> = content
+        .lines()
+        .map(|line| {
+            let Some(captures) = USES_LINE.captures(line) else {
+                return line.to_string();
+            };
+            let uses = &captures["uses"];
+            let (Some(action), Some((name, _))) = (ActionRef::parse(uses), uses.split_once('@'))
+            else {
+                return line.to_string();
+            };
+            match shas.get(&action.key()) {
+                Some(sha) if !action.is_pinned() => format!(
+                    "{lead}{quote}{name}@{sha}{quote} # {version}",
+                    lead = &captures["lead"],
+                    quote = &captures["quote"],
+                    version = action.git_ref
+                ),
+                _ => line.to_string(),
+            }
+        })
+        .collect();
+    if content.ends_with('\n') {
+        pinned.push(String::new());
+    }
+    pinned.join("\n")
+}
+
+/// The numbers of a version tag like `v4` or `v1.2.3`
+pub fn version_of(tag: &str) -> Option<Vec<u64>> {
+    let captures = VERSION.captures(tag.trim())?;
+    captures[1]
+        .split('.')
+        .map(|part| part.parse().ok())
+        .collect()
+}
+
+/// Whether `current` is older than `latest`, compared as far as `current`
+/// goes, since a major tag like `v4` follows every `v4.x.y` release; `None`
+/// when either isn't a version
+pub fn is_outdated(current: &str, latest: &str) -> Option<bool> {
+    let (current, latest) = (version_of(current)?, version_of(latest)?);
+    let latest = &latest[..latest.len().min(current.len())];
+    let compared = current
+        .iter()
+        .zip(latest)
+        .fold(Ordering::Equal, |ordering, (a, b)| ordering.then(a.cmp(b)));
+    Some(compared == Ordering::Less)
+}
+
+#[derive(Deserialize)]
+struct Release {
+    tag_name: String,
+}
+
+#[derive(Deserialize)]
+struct Tag {
+    name: String,
+}
+
+/// The tag of a repository's latest release, or its highest version tag
+/// when it publishes none; `None` when it has neither
+pub async fn latest_version(
+    client: &GithubClient,
+    owner: &str,
+    repo: &str,
+) -> Result<Option<String>, GithubError> {
+    let repo_url = format!("{}/repos/{}/{}", crate::client::API_URL, owner, repo);
+    let release: Option<Release> =
+        action_refs::get(client, &format!("{}/releases/latest", repo_url)).await?;
+    if let Some(release) = release {
+        return Ok(Some(release.tag_name));
+    }
+    let tags: Option<Vec<Tag>> =
+        action_refs::get(client, &format!("{}/tags?per_page=100", repo_url)).await?;
+    Ok(tags
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|tag| Some((version_of(&tag.name)?, tag.name)))
+        .max()
+        .map(|(_, name)| name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORKFLOW: &str = "jobs:\n  build:\n    steps:\n      - uses: actions/checkout@v4\n      - name: Cache\n        uses: 'actions/cache@v3' # keep\n      - uses: ./local\n      - uses: github/codeql-action/init@0123456789abcdef0123456789abcdef01234567 # v3.25.1\n";
+
+    #[test]
+    fn finds_and_pins_remote_actions() {
+        let uses = find_action_uses(WORKFLOW);
+        let found: Vec<(usize, &str, &str)> = uses
+            .iter()
+            .map(|u| (u.line, u.name.as_str(), u.current_version()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (4, "actions/checkout", "v4"),
+                (6, "actions/cache", "v3"),
+                (8, "github/codeql-action/init", "v3.25.1"),
+            ]
+        );
+
+        let shas = HashMap::from([
+            ("actions/checkout@v4".to_string(), "a".repeat(40)),
+            ("actions/cache@v3".to_string(), "b".repeat(40)),
+        ]);
+        let pinned = pin_shas(WORKFLOW, &shas);
+        assert!(pinned.contains(&format!(
+            "      - uses: actions/checkout@{} # v4\n",
+            "a".repeat(40)
+        )));
+        assert!(pinned.contains(&format!(
+            "        uses: 'actions/cache@{}' # v3\n",
+            "b".repeat(40)
+        )));
+        assert!(pinned.ends_with("# v3.25.1\n"));
+        assert_eq!(pin_shas(WORKFLOW, &HashMap::new()), WORKFLOW);
+    }
+
+    #[test]
+    fn compares_versions_as_far_as_the_current_one_goes() {
+        assert_eq!(is_outdated("v4", "v4.2.1"), Some(false));
+        assert_eq!(is_outdated("v3", "v4.0.0"), Some(true));
+        assert_eq!(is_outdated("v4.1", "v4.2.0"), Some(true));
+        assert_eq!(is_outdated("v4.2.1", "v4.2.1"), Some(false));
+        assert_eq!(is_outdated("1.10", "v1.9"), Some(false));
+        assert_eq!(is_outdated("main", "v4"), None);
+    }
+}
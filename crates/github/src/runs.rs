@@ -0,0 +1,472 @@
+// Following a workflow run on GitHub: its jobs and steps as they start and
+// finish, and the logs of the jobs that are done
+use crate::{null_as_default, GithubClient, GithubError, RepoInfo, WorkflowRun, WorkflowRuns};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Deserialize;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+lazy_static! {
+    static ref RUN_URL_REGEX: Regex =
+        Regex::new(r"github\.com/([^/]+)/([^/]+)/actions/runs/(\d+)").unwrap();
+    /// What GitHub puts in front of every log line
+    static ref LOG_TIMESTAMP_REGEX: Regex =
+        Regex::new(r"^\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}(?:\.\d+)?Z ").unwrap();
+}
+
+/// Polls that may fail in a row before watching gives up
+const MAX_POLL_FAILURES: u32 = 5;
+
+/// A run given by id or by its URL on github.com
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunRef {
+    pub id: u64,
+    /// Owner and name of the repository, when given by URL
+    pub repository: Option<(String, String)>,
+}
+
+impl RunRef {
+    pub fn parse(run: &str) -> Option<Self> {
+        if let Ok(id) = run.trim().parse() {
+            return Some(RunRef {
+                id,
+                repository: None,
+            });
+        }
+        let captures = RUN_URL_REGEX.captures(run)?;
+        Some(RunRef {
+            id: captures[3].parse().ok()?,
+            repository: Some((captures[1].to_string(), captures[2].to_string())),
+        })
+    }
+}
+
+/// A job of a run and its steps, as the API last reported them
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+pub struct RunJob {
+    pub id: u64,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub name: String,
+    /// `queued`, `in_progress` or `completed`
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub status: String,
+    #[serde(default)]
+    pub conclusion: Option<String>,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub steps: Vec<RunStep>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+pub struct RunStep {
+    pub number: u64,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub name: String,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub status: String,
+    #[serde(default)]
+    pub conclusion: Option<String>,
+}
+
+/// A page of `GET .../runs/{id}/jobs`
+#[derive(Debug, Deserialize)]
+struct RunJobs {
+    jobs: Vec<RunJob>,
+}
+
+impl RunJob {
+    fn started(&self) -> bool {
+        self.status == "in_progress" || self.status == "completed"
+    }
+}
+
+/// What changed in a run between two polls
+#[derive(Debug, Clone, PartialEq)]
+pub enum RunEvent {
+    JobStarted {
+        job: String,
+    },
+    StepFinished {
+        job: String,
+        step: String,
+        conclusion: String,
+    },
+    JobFinished {
+        id: u64,
+        job: String,
+        conclusion: String,
+    },
+}
+
+/// The jobs that started, steps that finished and jobs that finished since
+/// the previous poll, job by job
+pub fn run_events(previous: &[RunJob], current: &[RunJob]) -> Vec<RunEvent> {
+    let mut events = Vec::new();
+    for job in current {
+        let before = previous.iter().find(|p| p.id == job.id);
+        if job.started() && !before.is_some_and(RunJob::started) {
+            events.push(RunEvent::JobStarted {
+                job: job.name.clone(),
+            });
+        }
+        for step in job.steps.iter().filter(|s| s.status == "completed") {
+            let finished_before = before
+                .and_then(|b| b.steps.iter().find(|s| s.number == step.number))
+                .is_some_and(|s| s.status == "completed");
+            if !finished_before {
+                events.push(RunEvent::StepFinished {
+                    job: job.name.clone(),
+                    step: step.name.clone(),
+                    conclusion: step.conclusion.clone().unwrap_or_default(),
+                });
+            }
+        }
+        if job.status == "completed" && before.is_none_or(|b| b.status != "completed") {
+            events.push(RunEvent::JobFinished {
+                id: job.id,
+                job: job.name.clone(),
+                conclusion: job.conclusion.clone().unwrap_or_default(),
+            });
+        }
+    }
+    events
+}
+
+/// The last `lines` lines of a job's log, without GitHub's timestamps and
+/// group markers
+pub fn log_tail(log: &str, lines: usize) -> Vec<&str> {
+    let lines_shown: Vec<&str> = log
+        .lines()
+        .map(|line| {
+            let line = LOG_TIMESTAMP_REGEX
+                .find(line)
+                .map_or(line, |m| &line[m.end()..]);
+            line.strip_prefix("##[group]").unwrap_or(line)
+        })
+        .filter(|line| *line != "##[endgroup]")
+        .collect();
+    lines_shown[lines_shown.len().saturating_sub(lines)..].to_vec()
+}
+
+/// Exit status for a run's conclusion: 0 when it succeeded, 1 otherwise
+pub fn conclusion_exit_code(conclusion: Option<&str>) -> i32 {
+    match conclusion {
+        Some("success" | "neutral" | "skipped") => 0,
+        _ => 1,
+    }
+}
+
+fn run_url(repo_info: &RepoInfo, run_id: u64) -> String {
+    repo_info.api_url(&format!("actions/runs/{}", run_id))
+}
+
+pub async fn fetch_run(
+    client: &GithubClient,
+    repo_info: &RepoInfo,
+    run_id: u64,
+) -> Result<WorkflowRun, GithubError> {
+    client.get_json(&run_url(repo_info, run_id)).await
+}
+
+/// The most recent run on `branch`, of one workflow file like `ci.yml`
+/// or of any
+pub async fn latest_run(
+    client: &GithubClient,
+    repo_info: &RepoInfo,
+    workflow_file: Option<&str>,
+    branch: &str,
+) -> Result<WorkflowRun, GithubError> {
+    let runs_url = match workflow_file {
+        Some(file) => repo_info.api_url(&format!(
+            "actions/workflows/{}/runs?branch={}&per_page=1",
+            file, branch
+        )),
+        None => repo_info.api_url(&format!("actions/runs?branch={}&per_page=1", branch)),
+    };
+    let runs: WorkflowRuns = client.get_json(&runs_url).await?;
+    runs.workflow_runs.into_iter().next().ok_or_else(|| {
+        GithubError::GitParseError(format!(
+            "no runs of {} on {} in {}/{}",
+            workflow_file.unwrap_or("any workflow"),
+            branch,
+            repo_info.owner,
+            repo_info.repo
+        ))
+    })
+}
+
+/// Jobs of the latest attempt of a run
+pub async fn fetch_jobs(
+    client: &GithubClient,
+    repo_info: &RepoInfo,
+    run_id: u64,
+) -> Result<Vec<RunJob>, GithubError> {
+    let url = format!(
+        "{}/jobs?filter=latest&per_page=100",
+        run_url(repo_info, run_id)
+    );
+    let jobs: RunJobs = client.get_json(&url).await?;
+    Ok(jobs.jobs)
+}
+
+/// The log of a job, which GitHub has once the job is done
+pub async fn fetch_job_log(
+    client: &GithubClient,
+    repo_info: &RepoInfo,
+    job_id: u64,
+) -> Result<String, GithubError> {
+    client
+        .get_text(&repo_info.api_url(&format!("actions/jobs/{}/logs", job_id)))
+        .await
+}
+
+/// How `watch_run` follows a run
+#[derive(Debug, Clone)]
+pub struct WatchOptions {
+    pub interval: Duration,
+    /// Lines of each finished job's log to print
+    pub tail: usize,
+    /// Directory to save each finished job's full log in
+    pub logs_dir: Option<PathBuf>,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        WatchOptions {
+            interval: Duration::from_secs(5),
+            tail: 10,
+            logs_dir: None,
+        }
+    }
+}
+
+fn conclusion_symbol(conclusion: &str) -> &'static str {
+    match conclusion {
+        "success" => "✅",
+        "failure" | "timed_out" | "startup_failure" => "❌",
+        "skipped" | "neutral" => "⏭️ ",
+        "cancelled" => "🚫",
+        _ => "❓",
+    }
+}
+
+/// Follow a run until it completes, printing its jobs and steps as they
+/// start and finish and the end of each finished job's log
+pub async fn watch_run(
+    client: &GithubClient,
+    repo_info: &RepoInfo,
+    mut run: WorkflowRun,
+    options: &WatchOptions,
+) -> Result<WorkflowRun, GithubError> {
+    println!(
+        "Watching run {} of {} on {}: {}",
+        run.id, run.name, run.head_branch, run.html_url
+    );
+    if let Some(dir) = &options.logs_dir {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut jobs = Vec::new();
+    let mut failures = 0;
+    loop {
+        // The run is checked before its jobs, so once it is complete the
+        // jobs fetched after it are too
+        let completed = run.status == "completed";
+        match fetch_jobs(client, repo_info, run.id).await {
+            Ok(current) => {
+                for event in run_events(&jobs, &current) {
+                    print_event(client, repo_info, &event, options).await;
+                }
+                jobs = current;
+                failures = 0;
+            }
+            Err(e) => {
+                failures += 1;
+                if failures >= MAX_POLL_FAILURES {
+                    return Err(e);
+                }
+                println!("⚠️  Could not fetch the run's jobs, trying again: {}", e);
+            }
+        }
+        if completed {
+            break;
+        }
+
+        tokio::time::sleep(options.interval).await;
+        match fetch_run(client, repo_info, run.id).await {
+            Ok(latest) => run = latest,
+            Err(e) => {
+                failures += 1;
+                if failures >= MAX_POLL_FAILURES {
+                    return Err(e);
+                }
+                println!("⚠️  Could not fetch the run, trying again: {}", e);
+            }
+        }
+    }
+
+    let conclusion = run.conclusion.as_deref().unwrap_or("unknown");
+    println!(
+        "\n{} Run {} finished: {}",
+        conclusion_symbol(conclusion),
+        run.id,
+        conclusion
+    );
+    Ok(run)
+}
+
+async fn print_event(
+    client: &GithubClient,
+    repo_info: &RepoInfo,
+    event: &RunEvent,
+    options: &WatchOptions,
+) {
+    match event {
+        RunEvent::JobStarted { job } => println!("▶️  {}", job),
+        RunEvent::StepFinished {
+            job,
+            step,
+            conclusion,
+        } => println!("  {} {} › {}", conclusion_symbol(conclusion), job, step),
+        RunEvent::JobFinished {
+            id,
+            job,
+            conclusion,
+        } => {
+            println!("{} {}: {}", conclusion_symbol(conclusion), job, conclusion);
+            if options.tail == 0 && options.logs_dir.is_none() {
+                return;
+            }
+            let log = match fetch_job_log(client, repo_info, *id).await {
+                Ok(log) => log,
+                Err(e) => {
+                    println!("    (log not available: {})", e);
+                    return;
+                }
+            };
+            if let Some(dir) = &options.logs_dir {
+                let file_name: String = job
+                    .chars()
+                    .map(
+                        |c| match c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                            true => c,
+                            false => '_',
+                        },
+                    )
+                    .collect();
+                let path = dir.join(format!("{}-{}.log", file_name, id));
+                if let Err(e) = fs::write(&path, &log) {
+                    println!("    (could not save the log to {}: {})", path.display(), e);
+                }
+            }
+            for line in log_tail(&log, options.tail) {
+                println!("    │ {}", line);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(id: u64, status: &str, steps: &[(&str, Option<&str>)]) -> RunJob {
+        RunJob {
+            id,
+            name: format!("job{}", id),
+            status: status.to_string(),
+            conclusion: (status == "completed").then(|| "success".to_string()),
+            steps: steps
+                .iter()
+                .enumerate()
+                .map(|(i, (name, conclusion))| RunStep {
+                    number: i as u64 + 1,
+                    name: name.to_string(),
+                    status: match conclusion {
+                        Some(_) => "completed".to_string(),
+                        None => "in_progress".to_string(),
+                    },
+                    conclusion: conclusion.map(str::to_string),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn reports_what_changed_between_polls() {
+        let first = vec![
+            job(
+                1,
+                "in_progress",
+                &[("Checkout", Some("success")), ("Build", None)],
+            ),
+            job(2, "queued", &[]),
+        ];
+        assert_eq!(
+            run_events(&[], &first),
+            [
+                RunEvent::JobStarted {
+                    job: "job1".to_string()
+                },
+                RunEvent::StepFinished {
+                    job: "job1".to_string(),
+                    step: "Checkout".to_string(),
+                    conclusion: "success".to_string()
+                },
+            ]
+        );
+        let second = vec![
+            job(
+                1,
+                "completed",
+                &[("Checkout", Some("success")), ("Build", Some("success"))],
+            ),
+            job(2, "in_progress", &[]),
+        ];
+        assert_eq!(
+            run_events(&first, &second),
+            [
+                RunEvent::StepFinished {
+                    job: "job1".to_string(),
+                    step: "Build".to_string(),
+                    conclusion: "success".to_string()
+                },
+                RunEvent::JobFinished {
+                    id: 1,
+                    job: "job1".to_string(),
+                    conclusion: "success".to_string()
+                },
+                RunEvent::JobStarted {
+                    job: "job2".to_string()
+                },
+            ]
+        );
+        assert!(run_events(&second, &second).is_empty());
+
+        let jobs: RunJobs = serde_json::from_str(
+            r#"{"jobs": [{"id": 3, "name": "job3", "status": "queued", "conclusion": null, "steps": null}]}"#,
+        )
+        .unwrap();
+        assert_eq!(jobs.jobs, [job(3, "queued", &[])]);
+
+        let log = "2024-05-01T10:00:00.1234567Z ##[group]Run cargo test\n\
+                   2024-05-01T10:00:00.2Z cargo test\n\
+                   2024-05-01T10:00:01Z ##[endgroup]\n\
+                   2024-05-01T10:00:09Z test result: ok\n";
+        assert_eq!(log_tail(log, 2), ["cargo test", "test result: ok"]);
+        assert_eq!(log_tail(log, 10).len(), 3);
+
+        assert_eq!(
+            RunRef::parse("https://github.com/bahdotsh/wrkflw/actions/runs/42/job/7"),
+            Some(RunRef {
+                id: 42,
+                repository: Some(("bahdotsh".to_string(), "wrkflw".to_string())),
+            })
+        );
+        assert_eq!(RunRef::parse("42").unwrap().repository, None);
+        assert_eq!(RunRef::parse("latest"), None);
+        assert_eq!(conclusion_exit_code(Some("success")), 0);
+        assert_eq!(conclusion_exit_code(Some("cancelled")), 1);
+    }
+}
@@ -0,0 +1,159 @@
+// The tracing layer behind wrkflw's logging: keeps lines for the TUI, prints
+// them, and writes them to the log of the run they belong to
+use crate::run_log::RunLogFile;
+use crate::{LogFormat, LogLevel, LogSink, Record};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record as SpanRecord};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+/// Fields of a span, like the `job` of a job's span
+#[derive(Default)]
+struct SpanFields(BTreeMap<&'static str, String>);
+
+impl Visit for SpanFields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name(), value.to_string());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name(), value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.insert(field.name(), format!("{:?}", value));
+    }
+}
+
+/// The log of a run, attached to workflow spans of runs that write one.
+/// Lines logged before its file is known wait in `pending`.
+#[derive(Default)]
+struct RunLog {
+    file: Option<RunLogFile>,
+    pending: Vec<String>,
+}
+
+pub(crate) struct WrkflwLayer;
+
+impl<S> Layer<S> for WrkflwLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut fields = SpanFields::default();
+        attrs.record(&mut fields);
+        let writes_log = fields
+            .0
+            .get(crate::RUN_LOG_FIELD)
+            .is_some_and(|v| v == "true");
+        let mut extensions = span.extensions_mut();
+        extensions.insert(fields);
+        if writes_log {
+            extensions.insert(Mutex::new(RunLog::default()));
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &SpanRecord<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut extensions = span.extensions_mut();
+        let mut recorded = SpanFields::default();
+        values.record(&mut recorded);
+        let run_id = recorded.0.get(crate::RUN_ID_FIELD).cloned();
+        let mut log_dir = None;
+        if let Some(fields) = extensions.get_mut::<SpanFields>() {
+            fields.0.extend(recorded.0);
+            log_dir = fields.0.get(crate::LOG_DIR_FIELD).map(PathBuf::from);
+        }
+        let (Some(run_id), Some(run_log)) = (run_id, extensions.get_mut::<Mutex<RunLog>>()) else {
+            return;
+        };
+        let run_log = run_log.get_mut().unwrap_or_else(|e| e.into_inner());
+        let root = log_dir.unwrap_or_else(crate::logs_root);
+        match RunLogFile::open_run(&root, &run_id) {
+            Ok(mut file) => {
+                for line in run_log.pending.drain(..) {
+                    file.write_line(&line);
+                }
+                run_log.file = Some(file);
+            }
+            Err(e) => eprintln!("Failed to open the run's log: {}", e),
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        if event.metadata().target() != crate::TARGET {
+            return;
+        }
+        let mut message = Message::default();
+        event.record(&mut message);
+        let level = LogLevel::from(*event.metadata().level());
+        let mut record = Record::new(level, message.0);
+
+        // Inner spans' fields win, e.g. a matrix combination's job name
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope.from_root() {
+                if let Some(fields) = span.extensions().get::<SpanFields>() {
+                    for (name, value) in &fields.0 {
+                        if crate::SCOPE_FIELDS.contains(name) {
+                            record.fields.insert(name, value.clone());
+                        }
+                    }
+                }
+            }
+        }
+        // Runs with a sink of their own keep their lines out of the store
+        let sink = ctx.event_scope(event).and_then(|mut scope| {
+            scope.find_map(|span| span.extensions().get::<LogSink>().cloned())
+        });
+        match sink {
+            Some(sink) => sink.send(&record),
+            None => crate::emit(&record),
+        }
+
+        let Some(scope) = ctx.event_scope(event) else {
+            return;
+        };
+        for span in scope {
+            let extensions = span.extensions();
+            let Some(run_log) = extensions.get::<Mutex<RunLog>>() else {
+                continue;
+            };
+            let mut run_log = run_log.lock().unwrap_or_else(|e| e.into_inner());
+            let line = record.format(LogFormat::Json);
+            match &mut run_log.file {
+                Some(file) => file.write_line(&line),
+                None => run_log.pending.push(line),
+            }
+            break;
+        }
+    }
+}
+
+/// The message of an event
+#[derive(Default)]
+struct Message(String);
+
+impl Visit for Message {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.0 = value.to_string();
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.0 = format!("{:?}", value);
+        }
+    }
+}
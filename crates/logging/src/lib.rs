@@ -1,9 +1,85 @@
-use chrono::Local;
+// Logging for wrkflw, built on tracing: lines are kept for the TUI, printed
+// as text or JSON, and written to the log file of the run they belong to
+use chrono::{DateTime, Local, Utc};
 use once_cell::sync::Lazy;
-use std::sync::{Arc, Mutex};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::{Arc, Mutex, Once};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
+
+mod layer;
+mod run_log;
+
+pub use run_log::{logs_root, run_log_path, LOG_DIR_ENV, RUN_LOG_FILE};
+
+/// Target of wrkflw's own events, telling them from those of libraries
+const TARGET: &str = "wrkflw";
+
+/// Span fields that log lines carry, from the spans they're logged in
+const SCOPE_FIELDS: &[&str] = &["workflow", "job", "step"];
+
+/// Field of a workflow span naming its run, which opens the run's log
+const RUN_ID_FIELD: &str = "run_id";
+
+/// Field of a workflow span saying whether its run writes a log file
+const RUN_LOG_FIELD: &str = "run_log";
+
+/// Field of a workflow span naming the logs root its run's log goes under
+const LOG_DIR_FIELD: &str = "log_dir";
+
+/// Lines kept for the TUI; older ones are dropped, so long runs don't grow
+/// without bound
+pub const MAX_STORED_LINES: usize = 10_000;
 
 // Thread-safe log storage
-static LOGS: Lazy<Arc<Mutex<Vec<String>>>> = Lazy::new(|| Arc::new(Mutex::new(Vec::new())));
+static LOGS: Lazy<Arc<Mutex<LogStore>>> = Lazy::new(|| Arc::new(Mutex::new(LogStore::default())));
+
+// Stored lines, with the revision of the first one and of the last clear,
+// so that revisions keep counting up as lines are dropped and cleared
+#[derive(Default)]
+struct LogStore {
+    lines: VecDeque<String>,
+    first: usize,
+    cleared: usize,
+}
+
+impl LogStore {
+    fn push(&mut self, line: String) {
+        if self.lines.len() == MAX_STORED_LINES {
+            self.lines.pop_front();
+            self.first += 1;
+        }
+        self.lines.push_back(line);
+    }
+
+    fn revision(&self) -> usize {
+        self.first + self.lines.len()
+    }
+}
+
+/// Lines logged since a revision, as returned by [`get_logs_since`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LogUpdate {
+    pub lines: Vec<String>,
+    /// Revision to ask for next time
+    pub revision: usize,
+    /// The logs were cleared after the requested revision, so copies of
+    /// earlier lines should be dropped before appending `lines`
+    pub reset: bool,
+}
+
+// Print every level to stderr, leaving stdout to machine-readable output
+static STDERR_ONLY: AtomicBool = AtomicBool::new(false);
+
+// How lines are printed, as the index of a `LogFormat`
+static FORMAT: AtomicU8 = AtomicU8::new(LogFormat::Text as u8);
+
+// Whether wrkflw's subscriber is the global one; when another was set
+// first, lines are kept and printed without going through tracing
+static OWN_SUBSCRIBER: AtomicBool = AtomicBool::new(false);
+static INIT: Once = Once::new();
 
 // Current log level
 static LOG_LEVEL: Lazy<Arc<Mutex<LogLevel>>> = Lazy::new(|| Arc::new(Mutex::new(LogLevel::Info)));
@@ -26,6 +102,145 @@ impl LogLevel {
             LogLevel::Error => "❌",
         }
     }
+
+    fn name(&self) -> &'static str {
+        match self {
+            LogLevel::Debug => "debug",
+            LogLevel::Info => "info",
+            LogLevel::Warning => "warning",
+            LogLevel::Error => "error",
+        }
+    }
+}
+
+impl From<tracing::Level> for LogLevel {
+    fn from(level: tracing::Level) -> Self {
+        match level {
+            tracing::Level::ERROR => LogLevel::Error,
+            tracing::Level::WARN => LogLevel::Warning,
+            tracing::Level::INFO => LogLevel::Info,
+            _ => LogLevel::Debug,
+        }
+    }
+}
+
+/// How log lines are printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// `[12:00:00] ℹ️ message`
+    Text,
+    /// An object per line with the time, level, message and the workflow,
+    /// job and step it was logged in
+    Json,
+    /// `2024-01-01T12:00:00.000Z info job=build step="Test" message`, in
+    /// UTC and without emoji, for CI logs
+    Plain,
+}
+
+/// Takes the lines logged during one run, in place of the shared store and
+/// the terminal, so runs in one process keep their logs apart
+#[derive(Clone)]
+pub struct LogSink(Arc<SinkFn>);
+
+type SinkFn = dyn Fn(LogLevel, &str) + Send + Sync;
+
+impl LogSink {
+    pub fn new(sink: impl Fn(LogLevel, &str) + Send + Sync + 'static) -> Self {
+        LogSink(Arc::new(sink))
+    }
+
+    fn send(&self, record: &Record) {
+        (self.0)(record.level, &record.message)
+    }
+}
+
+impl fmt::Debug for LogSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("LogSink")
+    }
+}
+
+/// A log line with the fields of the spans it was logged in
+pub(crate) struct Record {
+    level: LogLevel,
+    time: DateTime<Local>,
+    message: String,
+    fields: BTreeMap<&'static str, String>,
+}
+
+impl Record {
+    fn new(level: LogLevel, message: String) -> Self {
+        Record {
+            level,
+            time: Local::now(),
+            message,
+            fields: BTreeMap::new(),
+        }
+    }
+
+    fn format(&self, format: LogFormat) -> String {
+        match format {
+            LogFormat::Text => format!(
+                "[{}] {} {}",
+                self.time.format("%H:%M:%S"),
+                self.level.prefix(),
+                self.message
+            ),
+            LogFormat::Plain => {
+                let mut line = format!(
+                    "{} {}",
+                    self.time
+                        .with_timezone(&Utc)
+                        .format("%Y-%m-%dT%H:%M:%S%.3fZ"),
+                    self.level.name()
+                );
+                for (name, value) in &self.fields {
+                    line.push_str(&format!(" {}={:?}", name, value));
+                }
+                format!("{} {}", line, self.message)
+            }
+            LogFormat::Json => {
+                let mut line = serde_json::json!({
+                    "time": self.time.to_rfc3339(),
+                    "level": self.level.name(),
+                    "message": self.message,
+                });
+                for (name, value) in &self.fields {
+                    line[*name] = value.clone().into();
+                }
+                line.to_string()
+            }
+        }
+    }
+}
+
+/// Keep a line for the TUI and print it if its level is shown
+fn emit(record: &Record) {
+    if let Ok(mut logs) = LOGS.lock() {
+        logs.push(record.format(LogFormat::Text));
+    }
+
+    // Print to console if the message level is >= the current log level
+    // This ensures Debug messages only show up when the Debug level is set
+    if record.level < get_log_level() {
+        return;
+    }
+    let line = record.format(log_format());
+    // Print to stdout/stderr based on level
+    match record.level {
+        LogLevel::Error | LogLevel::Warning => eprintln!("{}", line),
+        _ if STDERR_ONLY.load(Ordering::Relaxed) => eprintln!("{}", line),
+        _ => println!("{}", line),
+    }
+}
+
+/// Make wrkflw's layer the global tracing subscriber, unless there is one
+fn init() {
+    INIT.call_once(|| {
+        let subscriber = tracing_subscriber::registry().with(layer::WrkflwLayer);
+        let own = tracing::subscriber::set_global_default(subscriber).is_ok();
+        OWN_SUBSCRIBER.store(own, Ordering::Relaxed);
+    });
 }
 
 // Set the current log level
@@ -45,34 +260,94 @@ pub fn get_log_level() -> LogLevel {
     }
 }
 
-// Log a message with timestamp and level
-pub fn log(level: LogLevel, message: &str) {
-    let timestamp = Local::now().format("%H:%M:%S").to_string();
+/// Print all log messages to stderr, e.g. while stdout carries JSON
+pub fn use_stderr() {
+    STDERR_ONLY.store(true, Ordering::Relaxed);
+}
 
-    // Always include timestamp in [HH:MM:SS] format to ensure consistency
-    let formatted = format!("[{}] {} {}", timestamp, level.prefix(), message);
+pub fn set_log_format(format: LogFormat) {
+    FORMAT.store(format as u8, Ordering::Relaxed);
+}
 
-    if let Ok(mut logs) = LOGS.lock() {
-        logs.push(formatted.clone());
+pub fn log_format() -> LogFormat {
+    match FORMAT.load(Ordering::Relaxed) {
+        f if f == LogFormat::Json as u8 => LogFormat::Json,
+        f if f == LogFormat::Plain as u8 => LogFormat::Plain,
+        _ => LogFormat::Text,
     }
+}
 
-    // Print to console if the message level is >= the current log level
-    // This ensures Debug messages only show up when the Debug level is set
-    if let Ok(current_level) = LOG_LEVEL.lock() {
-        if level >= *current_level {
-            // Print to stdout/stderr based on level
-            match level {
-                LogLevel::Error | LogLevel::Warning => eprintln!("{}", formatted),
-                _ => println!("{}", formatted),
-            }
-        }
+// Log a message with timestamp and level
+pub fn log(level: LogLevel, message: &str) {
+    init();
+    if !OWN_SUBSCRIBER.load(Ordering::Relaxed) {
+        emit(&Record::new(level, message.to_string()));
+        return;
+    }
+    match level {
+        LogLevel::Debug => tracing::debug!(target: TARGET, "{}", message),
+        LogLevel::Info => tracing::info!(target: TARGET, "{}", message),
+        LogLevel::Warning => tracing::warn!(target: TARGET, "{}", message),
+        LogLevel::Error => tracing::error!(target: TARGET, "{}", message),
     }
 }
 
+/// Span of a workflow run. Lines logged in it carry the workflow, and go to
+/// the run's log file once [`write_run_log`] names the run, if `run_log`.
+pub fn workflow_span(workflow: &str, run_log: bool) -> tracing::Span {
+    init();
+    tracing::info_span!(
+        target: TARGET,
+        "workflow",
+        workflow = workflow,
+        run_log = run_log,
+        log_dir = tracing::field::Empty,
+        run_id = tracing::field::Empty
+    )
+}
+
+/// Send the lines logged in `span`, a [`workflow_span`], to `sink` rather
+/// than printing and storing them. Lines still go to the run's log file.
+/// Does nothing when another tracing subscriber was set before wrkflw's.
+pub fn set_span_sink(span: &tracing::Span, sink: LogSink) {
+    span.with_subscriber(|(id, dispatch)| {
+        let span = dispatch
+            .downcast_ref::<tracing_subscriber::Registry>()
+            .and_then(|registry| registry.span(id));
+        if let Some(span) = span {
+            span.extensions_mut().insert(sink);
+        }
+    });
+}
+
+/// Span of a job, whose lines carry its name
+pub fn job_span(job: &str) -> tracing::Span {
+    tracing::info_span!(target: TARGET, "job", job = job)
+}
+
+/// Span of a step, whose lines carry its name
+pub fn step_span(step: &str) -> tracing::Span {
+    tracing::info_span!(target: TARGET, "step", step = step)
+}
+
+/// Write the lines of the current workflow span to the log of `run_id`,
+/// under [`logs_root`]
+pub fn write_run_log(run_id: &str) {
+    write_run_log_in(&logs_root(), run_id);
+}
+
+/// Write the lines of the current workflow span to the log of `run_id`,
+/// under `root`
+pub fn write_run_log_in(root: &std::path::Path, run_id: &str) {
+    let span = tracing::Span::current();
+    span.record(LOG_DIR_FIELD, root.to_string_lossy().as_ref());
+    span.record(RUN_ID_FIELD, run_id);
+}
+
 // Get all logs
 pub fn get_logs() -> Vec<String> {
     if let Ok(logs) = LOGS.lock() {
-        logs.clone()
+        logs.lines.iter().cloned().collect()
     } else {
         // If we can't access logs, return an error message with timestamp
         let timestamp = Local::now().format("%H:%M:%S").to_string();
@@ -80,11 +355,45 @@ pub fn get_logs() -> Vec<String> {
     }
 }
 
+/// Number of stored log lines
+pub fn log_count() -> usize {
+    LOGS.lock().map(|logs| logs.lines.len()).unwrap_or(0)
+}
+
+/// Stored log lines in `start..end`, clamped to what's there
+pub fn get_logs_range(start: usize, end: usize) -> Vec<String> {
+    let Ok(logs) = LOGS.lock() else {
+        return Vec::new();
+    };
+    let end = end.min(logs.lines.len());
+    logs.lines.range(start.min(end)..end).cloned().collect()
+}
+
+/// Lines logged after `revision`, for polling without copying the whole log.
+/// Start from revision 0 and pass the returned revision on the next call.
+pub fn get_logs_since(revision: usize) -> LogUpdate {
+    let Ok(logs) = LOGS.lock() else {
+        return LogUpdate {
+            revision,
+            ..Default::default()
+        };
+    };
+    let reset = revision < logs.cleared;
+    let start = revision.saturating_sub(logs.first).min(logs.lines.len());
+    LogUpdate {
+        lines: logs.lines.range(start..).cloned().collect(),
+        revision: logs.revision(),
+        reset,
+    }
+}
+
 // Clear all logs
 #[allow(dead_code)]
 pub fn clear_logs() {
     if let Ok(mut logs) = LOGS.lock() {
-        logs.clear();
+        logs.cleared = logs.revision();
+        logs.first = logs.cleared;
+        logs.lines.clear();
     }
 }
 
@@ -105,3 +414,84 @@ pub fn warning(message: &str) {
 pub fn error(message: &str) {
     log(LogLevel::Error, message);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Tests sharing the stored lines run one at a time
+    static STORE: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn polls_logs_by_revision() {
+        let _store = STORE.lock().unwrap_or_else(|e| e.into_inner());
+        clear_logs();
+        let update = get_logs_since(0);
+        let revision = update.revision;
+
+        info("first");
+        warning("second");
+        let update = get_logs_since(revision);
+        assert_eq!(update.lines.len(), 2);
+        assert!(update.lines[1].ends_with("second"));
+        assert!(!update.reset);
+        assert_eq!(update.revision, revision + 2);
+        assert!(get_logs_since(update.revision).lines.is_empty());
+        assert_eq!(log_count(), 2);
+        assert_eq!(get_logs_range(1, 10), update.lines[1..]);
+
+        clear_logs();
+        error("third");
+        let after_clear = get_logs_since(update.revision);
+        assert_eq!(after_clear.lines.len(), 1);
+        assert!(!after_clear.reset);
+        let stale = get_logs_since(revision);
+        assert!(stale.reset);
+        assert_eq!(stale.lines, after_clear.lines);
+    }
+
+    #[test]
+    fn drops_the_oldest_lines_beyond_the_limit() {
+        let mut store = LogStore::default();
+        for i in 0..=MAX_STORED_LINES {
+            store.push(i.to_string());
+        }
+        assert_eq!(store.lines.len(), MAX_STORED_LINES);
+        assert_eq!(store.lines[0], "1");
+        assert_eq!(store.revision(), MAX_STORED_LINES + 1);
+    }
+
+    #[test]
+    fn writes_lines_with_their_spans_to_the_run_log() {
+        let _store = STORE.lock().unwrap_or_else(|e| e.into_inner());
+        let dir = tempfile::tempdir().unwrap();
+
+        let run = workflow_span("ci.yml", true);
+        let _run = run.enter();
+        info("before the run is named");
+        write_run_log_in(dir.path(), "20240101-000000-abcd1234");
+        {
+            let _job = job_span("build").entered();
+            let _step = step_span("Compile").entered();
+            warning("inside a step");
+        }
+
+        let path = dir
+            .path()
+            .join("20240101-000000-abcd1234")
+            .join(RUN_LOG_FILE);
+        let log = std::fs::read_to_string(path).unwrap();
+        let lines: Vec<serde_json::Value> = log
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["message"], "before the run is named");
+        assert_eq!(lines[0]["workflow"], "ci.yml");
+        assert!(lines[0].get("job").is_none());
+        assert_eq!(lines[1]["level"], "warning");
+        assert_eq!(lines[1]["job"], "build");
+        assert_eq!(lines[1]["step"], "Compile");
+        assert!(get_logs().last().unwrap().ends_with("inside a step"));
+    }
+}
@@ -0,0 +1,153 @@
+// Log files of single runs, as JSON lines in a directory per run under the
+// logs root, rotated when they grow large
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Overrides where run logs are written
+pub const LOG_DIR_ENV: &str = "WRKFLW_LOG_DIR";
+
+/// Name of the log file in a run's directory
+pub const RUN_LOG_FILE: &str = "wrkflw.log";
+
+/// Size at which a run's log moves to `wrkflw.log.1` and starts over
+const MAX_LOG_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Rotated files kept per run, `wrkflw.log.1` being the newest
+const MAX_ROTATED_FILES: usize = 3;
+
+/// Run directories kept, the oldest being removed beyond these
+const MAX_RUNS: usize = 50;
+
+/// Root directory of the run logs
+pub fn logs_root() -> PathBuf {
+    match std::env::var(LOG_DIR_ENV) {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => dirs::data_local_dir()
+            .unwrap_or_else(std::env::temp_dir)
+            .join("wrkflw")
+            .join("logs"),
+    }
+}
+
+/// Where the log of a run goes
+pub fn run_log_path(run_id: &str) -> PathBuf {
+    logs_root().join(run_id).join(RUN_LOG_FILE)
+}
+
+/// An open run log, rotated once it passes its size limit
+pub(crate) struct RunLogFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_bytes: u64,
+}
+
+impl RunLogFile {
+    /// Open the log of a run under `root`, removing the directories of the
+    /// oldest runs beyond the ones kept
+    pub(crate) fn open_run(root: &Path, run_id: &str) -> Result<Self, String> {
+        let log = Self::open(&root.join(run_id).join(RUN_LOG_FILE), MAX_LOG_BYTES)?;
+        prune_runs(root, MAX_RUNS);
+        Ok(log)
+    }
+
+    fn open(path: &Path, max_bytes: u64) -> Result<Self, String> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        let size = file.metadata().map(|meta| meta.len()).unwrap_or(0);
+        Ok(RunLogFile {
+            path: path.to_path_buf(),
+            file,
+            size,
+            max_bytes,
+        })
+    }
+
+    pub(crate) fn write_line(&mut self, line: &str) {
+        if self.size > 0 && self.size + line.len() as u64 >= self.max_bytes {
+            if let Err(e) = self.rotate() {
+                eprintln!("{}", e);
+            }
+        }
+        if writeln!(self.file, "{}", line).is_ok() {
+            self.size += line.len() as u64 + 1;
+        }
+    }
+
+    /// Shift `wrkflw.log.N` to `wrkflw.log.N+1`, dropping the oldest, and
+    /// start a new file
+    fn rotate(&mut self) -> Result<(), String> {
+        let rotated = |n: usize| PathBuf::from(format!("{}.{}", self.path.display(), n));
+        let _ = fs::remove_file(rotated(MAX_ROTATED_FILES));
+        for n in (1..MAX_ROTATED_FILES).rev() {
+            let _ = fs::rename(rotated(n), rotated(n + 1));
+        }
+        fs::rename(&self.path, rotated(1))
+            .map_err(|e| format!("Failed to rotate {}: {}", self.path.display(), e))?;
+        *self = Self::open(&self.path, self.max_bytes)?;
+        Ok(())
+    }
+}
+
+/// Remove run directories beyond the `keep` most recent. Run ids start with
+/// a timestamp, so they sort chronologically.
+fn prune_runs(root: &Path, keep: usize) {
+    let Ok(entries) = fs::read_dir(root) else {
+        return;
+    };
+    let mut runs: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+    runs.sort_by(|a, b| b.cmp(a));
+    for old in runs.into_iter().skip(keep) {
+        let _ = fs::remove_dir_all(old);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotates_large_logs_and_prunes_old_runs() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run").join(RUN_LOG_FILE);
+        let mut log = RunLogFile::open(&path, 20).unwrap();
+        for line in ["first line", "second line", "third line", "fourth", "fifth"] {
+            log.write_line(line);
+        }
+        let read = |suffix: &str| {
+            fs::read_to_string(format!("{}{}", path.display(), suffix)).unwrap_or_default()
+        };
+        assert_eq!(read(""), "fifth\n");
+        assert_eq!(read(".1"), "third line\nfourth\n");
+        assert_eq!(read(".2"), "second line\n");
+        assert_eq!(read(".3"), "first line\n");
+
+        for run in [
+            "20240101-000000-a",
+            "20240102-000000-b",
+            "20240103-000000-c",
+        ] {
+            fs::create_dir(dir.path().join(run)).unwrap();
+        }
+        prune_runs(dir.path(), 2);
+        let mut left: Vec<String> = fs::read_dir(dir.path())
+            .unwrap()
+            .flatten()
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect();
+        left.sort();
+        assert_eq!(left, ["20240103-000000-c", "run"]);
+    }
+}
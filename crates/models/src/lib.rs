@@ -1,6 +1,68 @@
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct ValidationResult {
     pub is_valid: bool,
     pub issues: Vec<String>,
+    /// Problems worth pointing out that don't make the workflow invalid
+    pub warnings: Vec<String>,
+    /// The issues and warnings with the rule that found them and where
+    /// they are, in the order they were found
+    #[serde(default)]
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagnosticSeverity {
+    Error,
+    Warning,
+}
+
+/// What a project's `.wrkflw.toml` makes of a rule's findings
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, schemars::JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleSeverity {
+    Error,
+    Warning,
+    /// Drop the rule's findings
+    Ignore,
+}
+
+/// An issue or warning found by a validation rule
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Diagnostic {
+    pub severity: DiagnosticSeverity,
+    /// Name of the rule, e.g. `missing-runs-on`
+    pub code: String,
+    pub message: String,
+    /// Path of the YAML node it's about, e.g. `jobs.build.steps[1].if`,
+    /// or empty for the document as a whole
+    #[serde(default)]
+    pub path: String,
+    /// 1-based position in the file, once known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+}
+
+impl Diagnostic {
+    pub fn new(
+        severity: DiagnosticSeverity,
+        code: &str,
+        path: impl Into<String>,
+        message: String,
+    ) -> Self {
+        Diagnostic {
+            severity,
+            code: code.to_string(),
+            message,
+            path: path.into(),
+            line: None,
+            column: None,
+        }
+    }
 }
 
 impl Default for ValidationResult {
@@ -14,17 +76,46 @@ impl ValidationResult {
         ValidationResult {
             is_valid: true,
             issues: Vec::new(),
+            warnings: Vec::new(),
+            diagnostics: Vec::new(),
         }
     }
 
-    pub fn add_issue(&mut self, issue: String) {
-        self.is_valid = false;
-        self.issues.push(issue);
+    /// An issue found by rule `code` at the node `path`
+    pub fn add_issue_at(&mut self, code: &str, path: impl Into<String>, issue: String) {
+        self.report(Diagnostic::new(
+            DiagnosticSeverity::Error,
+            code,
+            path,
+            issue,
+        ));
+    }
+
+    /// A warning found by rule `code` at the node `path`
+    pub fn add_warning_at(&mut self, code: &str, path: impl Into<String>, warning: String) {
+        self.report(Diagnostic::new(
+            DiagnosticSeverity::Warning,
+            code,
+            path,
+            warning,
+        ));
+    }
+
+    pub fn report(&mut self, diagnostic: Diagnostic) {
+        match diagnostic.severity {
+            DiagnosticSeverity::Error => {
+                self.is_valid = false;
+                self.issues.push(diagnostic.message.clone());
+            }
+            DiagnosticSeverity::Warning => self.warnings.push(diagnostic.message.clone()),
+        }
+        self.diagnostics.push(diagnostic);
     }
 }
 
 // GitLab pipeline models
 pub mod gitlab {
+    use indexmap::IndexMap;
     use serde::{Deserialize, Serialize};
     use std::collections::HashMap;
 
@@ -36,7 +127,11 @@ pub mod gitlab {
         pub image: Option<Image>,
 
         /// Global variables available to all jobs
-        #[serde(skip_serializing_if = "Option::is_none")]
+        #[serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            deserialize_with = "deserialize_variables"
+        )]
         pub variables: Option<HashMap<String, String>>,
 
         /// Pipeline stages in execution order
@@ -51,6 +146,18 @@ pub mod gitlab {
         #[serde(skip_serializing_if = "Option::is_none")]
         pub after_script: Option<Vec<String>>,
 
+        /// Default services for all jobs
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub services: Option<Vec<Service>>,
+
+        /// Default cache for all jobs
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub cache: Option<Cache>,
+
+        /// Defaults for job keywords, taking precedence over the top-level ones
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub default: Option<JobDefaults>,
+
         /// Job definitions (name => job)
         #[serde(flatten)]
         pub jobs: HashMap<String, Job>,
@@ -104,13 +211,21 @@ pub mod gitlab {
         pub tags: Option<Vec<String>>,
 
         /// Job-specific variables
-        #[serde(skip_serializing_if = "Option::is_none")]
+        #[serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            deserialize_with = "deserialize_variables"
+        )]
         pub variables: Option<HashMap<String, String>>,
 
         /// Job dependencies
         #[serde(skip_serializing_if = "Option::is_none")]
         pub dependencies: Option<Vec<String>>,
 
+        /// Jobs that must finish before this one, regardless of stages
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub needs: Option<Vec<Need>>,
+
         /// Artifacts to store after job execution
         #[serde(skip_serializing_if = "Option::is_none")]
         pub artifacts: Option<Artifacts>,
@@ -139,9 +254,10 @@ pub mod gitlab {
         #[serde(skip_serializing_if = "Option::is_none")]
         pub timeout: Option<String>,
 
-        /// Mark job as parallel and specify instance count
+        /// Run several instances of the job, either a number of them or one
+        /// per `matrix` combination
         #[serde(skip_serializing_if = "Option::is_none")]
-        pub parallel: Option<usize>,
+        pub parallel: Option<Parallel>,
 
         /// Flag to indicate this is a template job
         #[serde(skip_serializing_if = "Option::is_none")]
@@ -152,6 +268,174 @@ pub mod gitlab {
         pub extends: Option<Vec<String>>,
     }
 
+    /// Read `variables:`, which besides strings may hold numbers, booleans,
+    /// or maps whose `value` is used (`description`, `options` and `expand`
+    /// only matter when running pipelines manually)
+    fn deserialize_variables<'de, D>(
+        deserializer: D,
+    ) -> Result<Option<HashMap<String, String>>, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        let Some(raw) = Option::<HashMap<String, serde_yaml::Value>>::deserialize(deserializer)?
+        else {
+            return Ok(None);
+        };
+        raw.into_iter()
+            .map(|(name, value)| match variable_value(&value) {
+                Ok(value) => Ok((name, value)),
+                Err(reason) => Err(D::Error::custom(format!("variable '{}' {}", name, reason))),
+            })
+            .collect::<Result<_, _>>()
+            .map(Some)
+    }
+
+    fn variable_value(value: &serde_yaml::Value) -> Result<String, String> {
+        use serde_yaml::Value;
+
+        match value {
+            Value::String(text) => Ok(text.clone()),
+            Value::Number(number) => Ok(number.to_string()),
+            Value::Bool(flag) => Ok(flag.to_string()),
+            Value::Null => Ok(String::new()),
+            Value::Mapping(map) => {
+                for key in map.keys() {
+                    let key = key.as_str().unwrap_or_default();
+                    if !["value", "description", "options", "expand"].contains(&key) {
+                        return Err(format!(
+                            "has unknown key '{}'; expected value, description, options or expand",
+                            key
+                        ));
+                    }
+                }
+                match map.get("value") {
+                    Some(Value::Mapping(_) | Value::Sequence(_)) => {
+                        Err("has a 'value' that is not a string".to_string())
+                    }
+                    Some(value) => variable_value(value),
+                    None => Ok(String::new()),
+                }
+            }
+            _ => Err("must be a string, number, boolean or a map with a 'value'".to_string()),
+        }
+    }
+
+    /// Keywords set under `default:`, inherited by jobs that don't set them
+    #[derive(Debug, Serialize, Deserialize, Clone)]
+    pub struct JobDefaults {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub image: Option<Image>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub before_script: Option<Vec<String>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub after_script: Option<Vec<String>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub services: Option<Vec<Service>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub cache: Option<Cache>,
+    }
+
+    /// A job's `parallel:` setting
+    #[derive(Debug, Serialize, Deserialize, Clone)]
+    #[serde(untagged)]
+    pub enum Parallel {
+        /// Number of identical instances
+        Count(usize),
+        /// Entries whose variable values are combined into one instance each
+        Matrix {
+            matrix: Vec<IndexMap<String, MatrixValues>>,
+        },
+    }
+
+    /// Values of a variable in a `parallel: matrix:` entry
+    #[derive(Debug, Serialize, Clone)]
+    #[serde(untagged)]
+    pub enum MatrixValues {
+        Single(String),
+        Multiple(Vec<String>),
+    }
+
+    /// Numbers and booleans are read as text, as jobs get them as variables
+    impl<'de> Deserialize<'de> for MatrixValues {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            use serde::de::Error;
+            use serde_yaml::Value;
+
+            match Value::deserialize(deserializer)? {
+                Value::Sequence(values) => values
+                    .iter()
+                    .map(matrix_value)
+                    .collect::<Result<_, _>>()
+                    .map(MatrixValues::Multiple),
+                value => matrix_value(&value).map(MatrixValues::Single),
+            }
+            .map_err(D::Error::custom)
+        }
+    }
+
+    fn matrix_value(value: &serde_yaml::Value) -> Result<String, String> {
+        use serde_yaml::Value;
+
+        match value {
+            Value::String(text) => Ok(text.clone()),
+            Value::Number(number) => Ok(number.to_string()),
+            Value::Bool(flag) => Ok(flag.to_string()),
+            _ => Err("matrix values must be strings, numbers or booleans".to_string()),
+        }
+    }
+
+    impl MatrixValues {
+        pub fn values(&self) -> &[String] {
+            match self {
+                MatrixValues::Single(value) => std::slice::from_ref(value),
+                MatrixValues::Multiple(values) => values,
+            }
+        }
+    }
+
+    /// An entry of a job's `needs`
+    #[derive(Debug, Serialize, Deserialize, Clone)]
+    #[serde(untagged)]
+    pub enum Need {
+        /// Name of the needed job
+        Simple(String),
+        /// Detailed need configuration
+        Detailed {
+            /// Name of the needed job
+            job: String,
+            /// Whether to download the job's artifacts
+            #[serde(skip_serializing_if = "Option::is_none")]
+            artifacts: Option<bool>,
+            /// Don't fail when the job isn't part of the pipeline
+            #[serde(skip_serializing_if = "Option::is_none")]
+            optional: Option<bool>,
+        },
+    }
+
+    impl Need {
+        pub fn job(&self) -> &str {
+            match self {
+                Need::Simple(job) => job,
+                Need::Detailed { job, .. } => job,
+            }
+        }
+
+        pub fn is_optional(&self) -> bool {
+            matches!(
+                self,
+                Need::Detailed {
+                    optional: Some(true),
+                    ..
+                }
+            )
+        }
+    }
+
     /// Docker image configuration
     #[derive(Debug, Serialize, Deserialize, Clone)]
     #[serde(untagged)]
@@ -168,6 +452,15 @@ pub mod gitlab {
         },
     }
 
+    impl Image {
+        pub fn name(&self) -> &str {
+            match self {
+                Image::Simple(name) => name,
+                Image::Detailed { name, .. } => name,
+            }
+        }
+    }
+
     /// Service container to run alongside a job
     #[derive(Debug, Serialize, Deserialize, Clone)]
     #[serde(untagged)]
@@ -184,9 +477,28 @@ pub mod gitlab {
             /// Entrypoint to override in the image
             #[serde(skip_serializing_if = "Option::is_none")]
             entrypoint: Option<Vec<String>>,
+            /// Extra hostnames, separated by commas
+            #[serde(skip_serializing_if = "Option::is_none")]
+            alias: Option<String>,
+            /// Variables of the service container, over the job's
+            #[serde(
+                default,
+                skip_serializing_if = "Option::is_none",
+                deserialize_with = "deserialize_variables"
+            )]
+            variables: Option<HashMap<String, String>>,
         },
     }
 
+    impl Service {
+        pub fn name(&self) -> &str {
+            match self {
+                Service::Simple(name) => name,
+                Service::Detailed { name, .. } => name,
+            }
+        }
+    }
+
     /// Artifacts configuration
     #[derive(Debug, Serialize, Deserialize, Clone)]
     pub struct Artifacts {
@@ -222,14 +534,24 @@ pub mod gitlab {
     #[derive(Debug, Serialize, Deserialize, Clone)]
     pub struct Rule {
         /// If condition expression
-        #[serde(skip_serializing_if = "Option::is_none")]
+        #[serde(rename = "if", skip_serializing_if = "Option::is_none")]
         pub if_: Option<String>,
         /// When to run if condition is true
         #[serde(skip_serializing_if = "Option::is_none")]
         pub when: Option<String>,
         /// Variables to set if condition is true
-        #[serde(skip_serializing_if = "Option::is_none")]
+        #[serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            deserialize_with = "deserialize_variables"
+        )]
         pub variables: Option<HashMap<String, String>>,
+        /// Whether the job may fail, over the job's own `allow_failure`
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub allow_failure: Option<bool>,
+        /// How long a `when: delayed` job waits, like `30 minutes`
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub start_in: Option<String>,
     }
 
     /// Only/except configuration
@@ -331,8 +653,1002 @@ pub mod gitlab {
             #[serde(skip_serializing_if = "Option::is_none")]
             template: Option<String>,
             /// Ref to use when including from project
-            #[serde(skip_serializing_if = "Option::is_none")]
+            #[serde(rename = "ref", skip_serializing_if = "Option::is_none")]
             ref_: Option<String>,
         },
     }
 }
+
+// GitHub Actions workflow models
+pub mod github {
+    use indexmap::IndexMap;
+    use serde::de::value::{MapAccessDeserializer, SeqAccessDeserializer};
+    use serde::de::{self, IntoDeserializer, MapAccess, SeqAccess, Visitor};
+    use serde::{Deserialize, Deserializer, Serialize};
+    use serde_yaml::Value;
+    use std::fmt;
+    use std::marker::PhantomData;
+
+    /// Variables runners set for every step, which `env:` can't change
+    pub const DEFAULT_VARIABLES: &[&str] = &[
+        "GITHUB_ACTION",
+        "GITHUB_ACTION_PATH",
+        "GITHUB_ACTION_REPOSITORY",
+        "GITHUB_ACTIONS",
+        "GITHUB_ACTOR",
+        "GITHUB_ACTOR_ID",
+        "GITHUB_API_URL",
+        "GITHUB_BASE_REF",
+        "GITHUB_ENV",
+        "GITHUB_EVENT_NAME",
+        "GITHUB_EVENT_PATH",
+        "GITHUB_GRAPHQL_URL",
+        "GITHUB_HEAD_REF",
+        "GITHUB_JOB",
+        "GITHUB_OUTPUT",
+        "GITHUB_PATH",
+        "GITHUB_REF",
+        "GITHUB_REF_NAME",
+        "GITHUB_REF_PROTECTED",
+        "GITHUB_REF_TYPE",
+        "GITHUB_REPOSITORY",
+        "GITHUB_REPOSITORY_ID",
+        "GITHUB_REPOSITORY_OWNER",
+        "GITHUB_REPOSITORY_OWNER_ID",
+        "GITHUB_RETENTION_DAYS",
+        "GITHUB_RUN_ATTEMPT",
+        "GITHUB_RUN_ID",
+        "GITHUB_RUN_NUMBER",
+        "GITHUB_SERVER_URL",
+        "GITHUB_SHA",
+        "GITHUB_STATE",
+        "GITHUB_STEP_SUMMARY",
+        "GITHUB_TRIGGERING_ACTOR",
+        "GITHUB_WORKFLOW",
+        "GITHUB_WORKFLOW_REF",
+        "GITHUB_WORKFLOW_SHA",
+        "GITHUB_WORKSPACE",
+        "RUNNER_ARCH",
+        "RUNNER_DEBUG",
+        "RUNNER_ENVIRONMENT",
+        "RUNNER_NAME",
+        "RUNNER_OS",
+        "RUNNER_TEMP",
+        "RUNNER_TOOL_CACHE",
+    ];
+
+    /// Whether `name` is one of the [`DEFAULT_VARIABLES`]. Names are matched
+    /// case-insensitively, as they are on Windows runners.
+    pub fn is_default_variable(name: &str) -> bool {
+        DEFAULT_VARIABLES
+            .iter()
+            .any(|default| default.eq_ignore_ascii_case(name))
+    }
+
+    /// Represents a GitHub Actions workflow
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+    pub struct Workflow {
+        /// Name shown for the workflow; GitHub uses the file path when unset
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub name: Option<String>,
+
+        /// Name of each run, which may use expressions
+        #[serde(rename = "run-name", default, skip_serializing_if = "Option::is_none")]
+        pub run_name: Option<String>,
+
+        /// Events that start the workflow
+        pub on: On,
+
+        /// Token permissions for all jobs
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub permissions: Option<Permissions>,
+
+        /// Variables available to all jobs
+        #[serde(
+            default,
+            skip_serializing_if = "IndexMap::is_empty",
+            deserialize_with = "deserialize_text_map"
+        )]
+        pub env: IndexMap<String, String>,
+
+        /// Defaults for the `run` steps of all jobs
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub defaults: Option<Defaults>,
+
+        /// Concurrency group of the workflow's runs
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub concurrency: Option<Concurrency>,
+
+        /// Job definitions (id => job)
+        pub jobs: IndexMap<String, Job>,
+    }
+
+    /// The `on:` block of a workflow
+    #[derive(Debug, Serialize, Clone, PartialEq)]
+    #[serde(untagged)]
+    pub enum On {
+        /// A single event, as in `on: push`
+        Event(String),
+        /// A list of events, as in `on: [push, pull_request]`
+        Events(Vec<String>),
+        /// Events with their filters and settings
+        Configured(IndexMap<String, Option<Trigger>>),
+    }
+
+    // The enums below read their shape from the YAML node itself rather than
+    // being untagged, so a mistake inside them is reported where it is instead
+    // of as "data did not match any variant" at the enclosing key
+    impl<'de> Deserialize<'de> for On {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct OnVisitor;
+
+            impl<'de> Visitor<'de> for OnVisitor {
+                type Value = On;
+
+                fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    f.write_str("an event, a list of events or a map of events to their settings")
+                }
+
+                fn visit_str<E: de::Error>(self, event: &str) -> Result<On, E> {
+                    Ok(On::Event(event.to_string()))
+                }
+
+                fn visit_seq<A: SeqAccess<'de>>(self, events: A) -> Result<On, A::Error> {
+                    Vec::deserialize(SeqAccessDeserializer::new(events)).map(On::Events)
+                }
+
+                fn visit_map<A: MapAccess<'de>>(self, events: A) -> Result<On, A::Error> {
+                    IndexMap::deserialize(MapAccessDeserializer::new(events)).map(On::Configured)
+                }
+            }
+
+            deserializer.deserialize_any(OnVisitor)
+        }
+    }
+
+    impl On {
+        /// Names of the events, in the order they're listed
+        pub fn events(&self) -> Vec<&str> {
+            match self {
+                On::Event(event) => vec![event.as_str()],
+                On::Events(events) => events.iter().map(String::as_str).collect(),
+                On::Configured(events) => events.keys().map(String::as_str).collect(),
+            }
+        }
+
+        pub fn listens_to(&self, event: &str) -> bool {
+            self.events().contains(&event)
+        }
+
+        /// Settings of an event, when it is listed with any
+        pub fn config(&self, event: &str) -> Option<&EventConfig> {
+            match self {
+                On::Configured(events) => match events.get(event) {
+                    Some(Some(Trigger::Config(config))) => Some(config),
+                    _ => None,
+                },
+                _ => None,
+            }
+        }
+
+        /// Entries of the `schedule` event
+        pub fn schedules(&self) -> &[Schedule] {
+            match self {
+                On::Configured(events) => match events.get("schedule") {
+                    Some(Some(Trigger::Schedule(schedules))) => schedules,
+                    _ => &[],
+                },
+                _ => &[],
+            }
+        }
+    }
+
+    /// Configuration of one event under `on:`
+    #[derive(Debug, Serialize, Clone, PartialEq)]
+    #[serde(untagged)]
+    pub enum Trigger {
+        /// Entries of `schedule:`
+        Schedule(Vec<Schedule>),
+        /// Filters and settings of any other event
+        Config(Box<EventConfig>),
+    }
+
+    impl<'de> Deserialize<'de> for Trigger {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct TriggerVisitor;
+
+            impl<'de> Visitor<'de> for TriggerVisitor {
+                type Value = Trigger;
+
+                fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    f.write_str("a list of schedules or a map of the event's settings")
+                }
+
+                fn visit_seq<A: SeqAccess<'de>>(self, schedules: A) -> Result<Trigger, A::Error> {
+                    Vec::deserialize(SeqAccessDeserializer::new(schedules)).map(Trigger::Schedule)
+                }
+
+                fn visit_map<A: MapAccess<'de>>(self, settings: A) -> Result<Trigger, A::Error> {
+                    EventConfig::deserialize(MapAccessDeserializer::new(settings))
+                        .map(|config| Trigger::Config(Box::new(config)))
+                }
+            }
+
+            deserializer.deserialize_any(TriggerVisitor)
+        }
+    }
+
+    /// A `schedule:` entry
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+    pub struct Schedule {
+        pub cron: String,
+    }
+
+    /// Filters and settings of an event
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+    pub struct EventConfig {
+        /// Activity types, e.g. `opened` for `pull_request`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub types: Option<StringList>,
+
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub branches: Option<StringList>,
+
+        #[serde(
+            rename = "branches-ignore",
+            default,
+            skip_serializing_if = "Option::is_none"
+        )]
+        pub branches_ignore: Option<StringList>,
+
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub tags: Option<StringList>,
+
+        #[serde(
+            rename = "tags-ignore",
+            default,
+            skip_serializing_if = "Option::is_none"
+        )]
+        pub tags_ignore: Option<StringList>,
+
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub paths: Option<StringList>,
+
+        #[serde(
+            rename = "paths-ignore",
+            default,
+            skip_serializing_if = "Option::is_none"
+        )]
+        pub paths_ignore: Option<StringList>,
+
+        /// Workflows whose runs trigger `workflow_run`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub workflows: Option<StringList>,
+
+        /// Inputs of `workflow_dispatch` and `workflow_call`
+        #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+        pub inputs: IndexMap<String, Input>,
+
+        /// Outputs of a reusable workflow
+        #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+        pub outputs: IndexMap<String, CallOutput>,
+
+        /// Secrets a reusable workflow accepts
+        #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+        pub secrets: IndexMap<String, Option<CallSecret>>,
+    }
+
+    /// An input of `workflow_dispatch` or `workflow_call`
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+    pub struct Input {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub description: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub required: Option<bool>,
+        /// Default value, a string, number or boolean depending on the type
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub default: Option<Value>,
+        /// string, boolean, number, choice or environment
+        #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+        pub input_type: Option<String>,
+        /// Values of a `choice` input
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        pub options: Vec<String>,
+    }
+
+    /// An output of a reusable workflow
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+    pub struct CallOutput {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub description: Option<String>,
+        /// Expression the output is read from
+        pub value: String,
+    }
+
+    /// A secret a reusable workflow accepts
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+    pub struct CallSecret {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub description: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub required: Option<bool>,
+    }
+
+    /// A setting given as one string or a list of them
+    #[derive(Debug, Serialize, Clone, PartialEq)]
+    #[serde(untagged)]
+    pub enum StringList {
+        Single(String),
+        Multiple(Vec<String>),
+    }
+
+    impl<'de> Deserialize<'de> for StringList {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct StringListVisitor;
+
+            impl<'de> Visitor<'de> for StringListVisitor {
+                type Value = StringList;
+
+                fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    f.write_str("a string or a list of strings")
+                }
+
+                fn visit_str<E: de::Error>(self, value: &str) -> Result<StringList, E> {
+                    Ok(StringList::Single(value.to_string()))
+                }
+
+                fn visit_seq<A: SeqAccess<'de>>(self, values: A) -> Result<StringList, A::Error> {
+                    Vec::deserialize(SeqAccessDeserializer::new(values)).map(StringList::Multiple)
+                }
+            }
+
+            deserializer.deserialize_any(StringListVisitor)
+        }
+    }
+
+    impl StringList {
+        pub fn values(&self) -> &[String] {
+            match self {
+                StringList::Single(value) => std::slice::from_ref(value),
+                StringList::Multiple(values) => values,
+            }
+        }
+    }
+
+    /// A setting that may also be a `${{ }}` expression evaluated at run time
+    #[derive(Debug, Serialize, Clone, PartialEq)]
+    #[serde(untagged)]
+    pub enum OrExpression<T> {
+        Value(T),
+        Expression(String),
+    }
+
+    impl<'de, T: Deserialize<'de>> Deserialize<'de> for OrExpression<T> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct OrExpressionVisitor<T>(PhantomData<T>);
+
+            impl<'de, T: Deserialize<'de>> Visitor<'de> for OrExpressionVisitor<T> {
+                type Value = OrExpression<T>;
+
+                fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    f.write_str("a single value or an expression")
+                }
+
+                fn visit_bool<E: de::Error>(self, value: bool) -> Result<Self::Value, E> {
+                    setting_value(value.into_deserializer())
+                }
+
+                fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+                    setting_value(value.into_deserializer())
+                }
+
+                fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+                    setting_value(value.into_deserializer())
+                }
+
+                fn visit_f64<E: de::Error>(self, value: f64) -> Result<Self::Value, E> {
+                    setting_value(value.into_deserializer())
+                }
+
+                // Text the setting can't take is an expression left to run time
+                fn visit_str<E: de::Error>(self, text: &str) -> Result<Self::Value, E> {
+                    let value: Result<T, E> = T::deserialize(text.into_deserializer());
+                    Ok(value.map_or_else(
+                        |_| OrExpression::Expression(text.to_string()),
+                        OrExpression::Value,
+                    ))
+                }
+            }
+
+            deserializer.deserialize_any(OrExpressionVisitor(PhantomData))
+        }
+    }
+
+    fn setting_value<'de, T, D>(deserializer: D) -> Result<OrExpression<T>, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        T::deserialize(deserializer)
+            .map(OrExpression::Value)
+            .map_err(|e| D::Error::custom(format!("{} or an expression", e)))
+    }
+
+    /// Permissions of the `GITHUB_TOKEN`
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+    #[serde(untagged)]
+    pub enum Permissions {
+        /// `read-all` or `write-all`
+        All(String),
+        /// Access per scope, e.g. `contents: read`
+        Scopes(IndexMap<String, String>),
+    }
+
+    /// Defaults under `defaults:`
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+    pub struct Defaults {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub run: Option<RunDefaults>,
+    }
+
+    /// Defaults for `run` steps
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+    pub struct RunDefaults {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub shell: Option<String>,
+        #[serde(
+            rename = "working-directory",
+            default,
+            skip_serializing_if = "Option::is_none"
+        )]
+        pub working_directory: Option<String>,
+    }
+
+    /// A `concurrency:` setting
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+    #[serde(untagged)]
+    pub enum Concurrency {
+        /// Just the group name
+        Group(String),
+        Detailed {
+            /// Group name, which may use expressions
+            group: String,
+            /// Cancel the group's run in progress instead of waiting for it
+            #[serde(
+                rename = "cancel-in-progress",
+                default,
+                skip_serializing_if = "Option::is_none"
+            )]
+            cancel_in_progress: Option<OrExpression<bool>>,
+        },
+    }
+
+    /// A job in a GitHub Actions workflow
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+    pub struct Job {
+        /// Name shown for the job
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub name: Option<String>,
+
+        /// Runner the job runs on; unset for reusable workflow calls
+        #[serde(rename = "runs-on", default, skip_serializing_if = "Option::is_none")]
+        pub runs_on: Option<RunsOn>,
+
+        /// Jobs that must finish before this one
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub needs: Option<StringList>,
+
+        /// Condition for running the job
+        #[serde(
+            rename = "if",
+            default,
+            skip_serializing_if = "Option::is_none",
+            deserialize_with = "deserialize_optional_text"
+        )]
+        pub if_condition: Option<String>,
+
+        /// Token permissions of the job
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub permissions: Option<Permissions>,
+
+        /// Deployment environment the job uses
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub environment: Option<Environment>,
+
+        /// Concurrency group of the job
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub concurrency: Option<Concurrency>,
+
+        /// Outputs other jobs read through `needs`
+        #[serde(
+            default,
+            skip_serializing_if = "IndexMap::is_empty",
+            deserialize_with = "deserialize_text_map"
+        )]
+        pub outputs: IndexMap<String, String>,
+
+        /// Job-specific variables
+        #[serde(
+            default,
+            skip_serializing_if = "IndexMap::is_empty",
+            deserialize_with = "deserialize_text_map"
+        )]
+        pub env: IndexMap<String, String>,
+
+        /// Defaults for the job's `run` steps
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub defaults: Option<Defaults>,
+
+        #[serde(
+            rename = "timeout-minutes",
+            default,
+            skip_serializing_if = "Option::is_none"
+        )]
+        pub timeout_minutes: Option<OrExpression<f64>>,
+
+        /// Allow the job to fail without failing the workflow
+        #[serde(
+            rename = "continue-on-error",
+            default,
+            skip_serializing_if = "Option::is_none"
+        )]
+        pub continue_on_error: Option<OrExpression<bool>>,
+
+        /// Matrix and its settings
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub strategy: Option<Strategy>,
+
+        /// Container the job's steps run in
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub container: Option<JobContainer>,
+
+        /// Service containers to run alongside the job
+        #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+        pub services: IndexMap<String, Container>,
+
+        /// Reusable workflow the job calls
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub uses: Option<String>,
+
+        /// Inputs of the called workflow
+        #[serde(
+            default,
+            skip_serializing_if = "IndexMap::is_empty",
+            deserialize_with = "deserialize_text_map"
+        )]
+        pub with: IndexMap<String, String>,
+
+        /// Secrets passed to the called workflow
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub secrets: Option<Secrets>,
+
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        pub steps: Vec<Step>,
+    }
+
+    impl Job {
+        /// Names of the jobs this one needs
+        pub fn needs(&self) -> &[String] {
+            self.needs.as_ref().map_or(&[], StringList::values)
+        }
+    }
+
+    /// The `runs-on:` setting of a job
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+    #[serde(untagged)]
+    pub enum RunsOn {
+        Label(String),
+        Labels(Vec<String>),
+        /// Runners of a runner group, optionally with labels
+        Group {
+            group: String,
+            #[serde(default, skip_serializing_if = "Option::is_none")]
+            labels: Option<StringList>,
+        },
+    }
+
+    impl RunsOn {
+        pub fn labels(&self) -> &[String] {
+            match self {
+                RunsOn::Label(label) => std::slice::from_ref(label),
+                RunsOn::Labels(labels) => labels,
+                RunsOn::Group { labels, .. } => labels.as_ref().map_or(&[], StringList::values),
+            }
+        }
+    }
+
+    /// A job's deployment environment
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+    #[serde(untagged)]
+    pub enum Environment {
+        Name(String),
+        Detailed {
+            name: String,
+            #[serde(default, skip_serializing_if = "Option::is_none")]
+            url: Option<String>,
+        },
+    }
+
+    impl Environment {
+        pub fn name(&self) -> &str {
+            match self {
+                Environment::Name(name) | Environment::Detailed { name, .. } => name,
+            }
+        }
+    }
+
+    /// The `strategy:` of a job
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+    pub struct Strategy {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub matrix: Option<Matrix>,
+
+        /// Cancel the other combinations when one fails
+        #[serde(rename = "fail-fast", default, skip_serializing_if = "Option::is_none")]
+        pub fail_fast: Option<OrExpression<bool>>,
+
+        /// Most combinations to run at the same time
+        #[serde(
+            rename = "max-parallel",
+            default,
+            skip_serializing_if = "Option::is_none"
+        )]
+        pub max_parallel: Option<OrExpression<u64>>,
+    }
+
+    /// A job's `strategy.matrix`
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+    #[serde(untagged)]
+    pub enum Matrix {
+        /// Expression producing the matrix, such as `${{ fromJSON(...) }}`
+        Expression(String),
+        Values {
+            /// Values of each matrix variable
+            #[serde(flatten)]
+            parameters: IndexMap<String, Value>,
+            /// Extra combinations, or values added to matching ones
+            #[serde(default, skip_serializing_if = "Vec::is_empty")]
+            include: Vec<IndexMap<String, Value>>,
+            /// Combinations to leave out
+            #[serde(default, skip_serializing_if = "Vec::is_empty")]
+            exclude: Vec<IndexMap<String, Value>>,
+        },
+    }
+
+    /// The `container:` of a job
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+    #[serde(untagged)]
+    pub enum JobContainer {
+        /// Just the image name
+        Image(String),
+        Detailed(Box<Container>),
+    }
+
+    impl JobContainer {
+        pub fn image(&self) -> &str {
+            match self {
+                JobContainer::Image(image) => image,
+                JobContainer::Detailed(container) => &container.image,
+            }
+        }
+    }
+
+    /// A job or service container
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+    pub struct Container {
+        pub image: String,
+
+        /// Registry login for a private image
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub credentials: Option<Credentials>,
+
+        #[serde(
+            default,
+            skip_serializing_if = "IndexMap::is_empty",
+            deserialize_with = "deserialize_text_map"
+        )]
+        pub env: IndexMap<String, String>,
+
+        /// Ports to publish, as `port` or `host:container`
+        #[serde(
+            default,
+            skip_serializing_if = "Vec::is_empty",
+            deserialize_with = "deserialize_text_list"
+        )]
+        pub ports: Vec<String>,
+
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        pub volumes: Vec<String>,
+
+        /// Extra `docker create` options
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub options: Option<String>,
+
+        /// Hostnames besides its key, which GitLab services have
+        #[serde(skip)]
+        pub aliases: Vec<String>,
+
+        /// Entrypoint and command of GitLab services, which GitHub can't set
+        #[serde(skip)]
+        pub entrypoint: Option<Vec<String>>,
+        #[serde(skip)]
+        pub command: Option<Vec<String>>,
+    }
+
+    /// Registry login of a container
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+    pub struct Credentials {
+        pub username: String,
+        pub password: String,
+    }
+
+    /// Secrets passed to a reusable workflow
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+    #[serde(untagged)]
+    pub enum Secrets {
+        /// `inherit`, passing all of the caller's secrets
+        Inherit(String),
+        Map(IndexMap<String, String>),
+    }
+
+    /// A step of a job
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+    pub struct Step {
+        /// Id other steps read the step's outputs through
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub id: Option<String>,
+
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub name: Option<String>,
+
+        /// Condition for running the step
+        #[serde(
+            rename = "if",
+            default,
+            skip_serializing_if = "Option::is_none",
+            deserialize_with = "deserialize_optional_text"
+        )]
+        pub if_condition: Option<String>,
+
+        /// Action the step runs
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub uses: Option<String>,
+
+        /// Commands the step runs
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub run: Option<String>,
+
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub shell: Option<String>,
+
+        #[serde(
+            rename = "working-directory",
+            default,
+            skip_serializing_if = "Option::is_none"
+        )]
+        pub working_directory: Option<String>,
+
+        /// Inputs of the action
+        #[serde(
+            default,
+            skip_serializing_if = "IndexMap::is_empty",
+            deserialize_with = "deserialize_text_map"
+        )]
+        pub with: IndexMap<String, String>,
+
+        /// Step-specific variables
+        #[serde(
+            default,
+            skip_serializing_if = "IndexMap::is_empty",
+            deserialize_with = "deserialize_text_map"
+        )]
+        pub env: IndexMap<String, String>,
+
+        /// Keep running the job when the step fails
+        #[serde(
+            rename = "continue-on-error",
+            default,
+            skip_serializing_if = "Option::is_none"
+        )]
+        pub continue_on_error: Option<OrExpression<bool>>,
+
+        #[serde(
+            rename = "timeout-minutes",
+            default,
+            skip_serializing_if = "Option::is_none"
+        )]
+        pub timeout_minutes: Option<OrExpression<f64>>,
+    }
+
+    impl Step {
+        /// Whether `continue-on-error` is `true`; expressions aren't evaluated
+        pub fn continues_on_error(&self) -> bool {
+            matches!(self.continue_on_error, Some(OrExpression::Value(true)))
+        }
+    }
+
+    /// Read a scalar as text, since YAML reads `if: false` or `PORT: 5432`
+    /// as a boolean or a number
+    fn scalar_text(value: Value) -> Result<String, String> {
+        match value {
+            Value::String(text) => Ok(text),
+            Value::Number(number) => Ok(number.to_string()),
+            Value::Bool(flag) => Ok(flag.to_string()),
+            Value::Null => Ok(String::new()),
+            _ => Err("must be a string, number or boolean".to_string()),
+        }
+    }
+
+    fn deserialize_text_map<'de, D>(deserializer: D) -> Result<IndexMap<String, String>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        let raw = Option::<IndexMap<String, Value>>::deserialize(deserializer)?;
+        raw.unwrap_or_default()
+            .into_iter()
+            .map(|(name, value)| match scalar_text(value) {
+                Ok(text) => Ok((name, text)),
+                Err(reason) => Err(D::Error::custom(format!("'{}' {}", name, reason))),
+            })
+            .collect()
+    }
+
+    fn deserialize_text_list<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        let raw = Option::<Vec<Value>>::deserialize(deserializer)?;
+        raw.unwrap_or_default()
+            .into_iter()
+            .map(|value| scalar_text(value).map_err(D::Error::custom))
+            .collect()
+    }
+
+    fn deserialize_optional_text<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        match Option::<Value>::deserialize(deserializer)? {
+            None | Some(Value::Null) => Ok(None),
+            Some(value) => scalar_text(value).map(Some).map_err(D::Error::custom),
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        const WORKFLOW: &str = r#"
+name: CI
+on:
+  push:
+    branches: [main]
+    paths-ignore: ["docs/**"]
+  pull_request:
+    types: opened
+  schedule:
+    - cron: "0 4 * * 1"
+  workflow_dispatch:
+    inputs:
+      level:
+        type: choice
+        options: [debug, info]
+        default: info
+permissions:
+  contents: read
+env:
+  RETRIES: 3
+concurrency:
+  group: ci-${{ github.ref }}
+  cancel-in-progress: true
+jobs:
+  test:
+    runs-on: [self-hosted, linux]
+    if: false
+    strategy:
+      fail-fast: false
+      matrix:
+        os: [ubuntu-latest, macos-latest]
+        include:
+          - os: windows-latest
+            experimental: true
+    services:
+      redis:
+        image: redis:7
+        ports: [6379]
+    steps:
+      - uses: actions/checkout@v4
+        with:
+          fetch-depth: 0
+      - run: cargo test
+        continue-on-error: ${{ matrix.experimental }}
+  release:
+    needs: test
+    uses: ./.github/workflows/release.yml
+    secrets: inherit
+"#;
+
+        #[test]
+        fn parses_and_round_trips_a_workflow() {
+            let workflow: Workflow = serde_yaml::from_str(WORKFLOW).unwrap();
+
+            assert_eq!(
+                workflow.on.events(),
+                ["push", "pull_request", "schedule", "workflow_dispatch"]
+            );
+            let push = workflow.on.config("push").unwrap();
+            assert_eq!(push.branches.as_ref().unwrap().values(), ["main"]);
+            assert_eq!(workflow.on.schedules()[0].cron, "0 4 * * 1");
+            assert_eq!(workflow.env["RETRIES"], "3");
+
+            let test = &workflow.jobs["test"];
+            assert_eq!(
+                test.runs_on.as_ref().unwrap().labels(),
+                ["self-hosted", "linux"]
+            );
+            assert_eq!(test.if_condition.as_deref(), Some("false"));
+            assert_eq!(test.services["redis"].ports, ["6379"]);
+            assert_eq!(test.steps[0].with["fetch-depth"], "0");
+            assert!(!test.steps[1].continues_on_error());
+            assert!(matches!(
+                test.strategy.as_ref().unwrap().matrix,
+                Some(Matrix::Values { ref include, .. }) if include.len() == 1
+            ));
+            assert_eq!(workflow.jobs["release"].needs(), ["test"]);
+
+            let yaml = serde_yaml::to_string(&workflow).unwrap();
+            assert_eq!(serde_yaml::from_str::<Workflow>(&yaml).unwrap(), workflow);
+        }
+
+        #[test]
+        fn reads_simple_triggers() {
+            let workflow: Workflow =
+                serde_yaml::from_str("on: [push, pull_request]\njobs: {}").unwrap();
+            assert!(workflow.on.listens_to("pull_request"));
+            assert!(workflow.on.config("push").is_none());
+            assert!(workflow.name.is_none());
+        }
+
+        #[test]
+        fn reads_fractional_timeouts_and_expressions() {
+            let workflow: Workflow = serde_yaml::from_str(
+                "on: push\njobs:\n  build:\n    timeout-minutes: 2.5\n    continue-on-error: ${{ matrix.experimental }}\n",
+            )
+            .unwrap();
+            let build = &workflow.jobs["build"];
+            assert_eq!(build.timeout_minutes, Some(OrExpression::Value(2.5)));
+            assert!(matches!(
+                build.continue_on_error,
+                Some(OrExpression::Expression(ref text)) if text == "${{ matrix.experimental }}"
+            ));
+        }
+
+        #[test]
+        fn reports_mistakes_at_the_setting() {
+            let error =
+                serde_yaml::from_str::<Workflow>("on:\n  push:\n    branches: 5\njobs: {}\n")
+                    .unwrap_err();
+            assert!(error
+                .to_string()
+                .starts_with("on.push.branches: invalid type: integer `5`, expected a string or a list of strings"));
+            assert_eq!(error.location().unwrap().line(), 3);
+
+            let error = serde_yaml::from_str::<Workflow>(
+                "on: push\njobs:\n  build:\n    continue-on-error: 5\n",
+            )
+            .unwrap_err();
+            assert!(error.to_string().starts_with(
+                "jobs.build.continue-on-error: invalid type: integer `5`, expected a boolean or an expression"
+            ));
+            assert_eq!(error.location().unwrap().line(), 4);
+        }
+    }
+}
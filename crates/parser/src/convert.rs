@@ -0,0 +1,2661 @@
+// `wrkflw convert`: GitHub workflows as GitLab CI pipelines and pipelines as
+// workflows, with a note for everything that has no equivalent
+use crate::expand::{expand_pipeline, ExpandOptions};
+use crate::gitlab::{job_instances, job_stage, pipeline_stages, GLOBAL_KEYWORDS};
+use crate::sniff::Platform;
+use crate::triggers::filter_pattern_regex;
+use models::github::{self, Environment, JobContainer, Matrix, OrExpression, RunDefaults};
+use models::gitlab::{self, Except, Image, Only, Parallel, Service};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde_yaml::{Mapping, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::Path;
+
+/// GitHub contexts and the GitLab predefined variables holding the same
+/// thing. The first entry for a name wins when looking up either way.
+const PREDEFINED_VARIABLES: &[(&str, &str)] = &[
+    ("github.sha", "CI_COMMIT_SHA"),
+    ("github.ref_name", "CI_COMMIT_REF_NAME"),
+    ("github.ref", "CI_COMMIT_REF_NAME"),
+    ("github.head_ref", "CI_MERGE_REQUEST_SOURCE_BRANCH_NAME"),
+    ("github.base_ref", "CI_MERGE_REQUEST_TARGET_BRANCH_NAME"),
+    ("github.repository", "CI_PROJECT_PATH"),
+    ("github.repository_owner", "CI_PROJECT_NAMESPACE"),
+    ("github.event.repository.name", "CI_PROJECT_NAME"),
+    (
+        "github.event.repository.default_branch",
+        "CI_DEFAULT_BRANCH",
+    ),
+    ("github.run_id", "CI_PIPELINE_ID"),
+    ("github.run_number", "CI_PIPELINE_IID"),
+    ("github.job", "CI_JOB_NAME"),
+    ("github.workspace", "CI_PROJECT_DIR"),
+    ("github.actor", "GITLAB_USER_LOGIN"),
+    ("github.event_name", "CI_PIPELINE_SOURCE"),
+    ("github.server_url", "CI_SERVER_URL"),
+    ("secrets.GITHUB_TOKEN", "CI_JOB_TOKEN"),
+    ("github.ref_name", "CI_COMMIT_BRANCH"),
+    ("github.ref_name", "CI_COMMIT_REF_SLUG"),
+];
+
+/// GitHub events and the GitLab pipeline sources they correspond to
+const EVENT_SOURCES: &[(&str, &str)] = &[
+    ("push", "push"),
+    ("pull_request", "merge_request_event"),
+    ("pull_request_target", "merge_request_event"),
+    ("schedule", "schedule"),
+    ("workflow_dispatch", "web"),
+    ("repository_dispatch", "api"),
+];
+
+/// Keys of a GitLab job the conversion reads
+const CONVERTED_JOB_KEYS: &[&str] = &[
+    "stage",
+    "image",
+    "script",
+    "before_script",
+    "after_script",
+    "when",
+    "allow_failure",
+    "services",
+    "tags",
+    "variables",
+    "dependencies",
+    "needs",
+    "artifacts",
+    "cache",
+    "rules",
+    "only",
+    "except",
+    "retry",
+    "timeout",
+    "parallel",
+    "environment",
+    "extends",
+];
+
+static GITHUB_EXPRESSION: Lazy<Regex> = Lazy::new(|| Regex::new(r"\$\{\{\s*(.*?)\s*\}\}").unwrap());
+
+/// Files GitHub reads after a step to set variables, outputs and the summary
+static GITHUB_FILE_COMMAND: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\b(GITHUB_(?:ENV|OUTPUT|PATH|STEP_SUMMARY|STATE))\b").unwrap());
+
+/// `$NAME` and `${NAME}` references
+static GITLAB_VARIABLE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\$(?:\{([A-Za-z_][A-Za-z0-9_]*)\}|([A-Za-z_][A-Za-z0-9_]*))").unwrap()
+});
+
+/// A context value compared with a string in a GitHub `if:`
+static GITHUB_COMPARISON: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"([A-Za-z_][\w-]*(?:\.[\w-]+)+)\s*(==|!=)\s*'([^']*)'").unwrap());
+
+static GITHUB_REF_PREFIX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"startsWith\(\s*github\.ref\s*,\s*'refs/(heads|tags)/'\s*\)").unwrap()
+});
+
+/// What a GitHub `if:` can't have once it is a GitLab rule: function calls,
+/// contexts, negation and single quotes
+static GITHUB_LEFTOVER: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"[A-Za-z_]\w*\(|[A-Za-z_][\w-]*\.[\w-]|![^=~]|'").unwrap());
+
+/// A variable compared with a value in a GitLab `if:`
+static GITLAB_COMPARISON: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"\$\{?([A-Za-z_][A-Za-z0-9_]*)\}?\s*(==|!=)\s*(?:"([^"]*)"|'([^']*)'|\$\{?([A-Za-z_][A-Za-z0-9_]*)\}?|(null))"#,
+    )
+    .unwrap()
+});
+
+static DURATION_PART: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)(\d+)\s*([a-z]*)").unwrap());
+
+/// A converted file and what couldn't be carried over
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conversion {
+    /// What the document is now
+    pub platform: Platform,
+    pub document: Value,
+    /// Constructs that were left out or only approximated
+    pub notes: Vec<String>,
+}
+
+impl Conversion {
+    /// The converted file, with the notes as comments on top
+    pub fn to_yaml(&self) -> Result<String, String> {
+        let yaml = serde_yaml::to_string(&self.document)
+            .map_err(|e| format!("Failed to write YAML: {}", e))?;
+        let yaml = crate::format::format_workflow(&yaml, self.platform == Platform::GitHub)
+            .unwrap_or(yaml);
+        if self.notes.is_empty() {
+            return Ok(yaml);
+        }
+        let mut header = String::from("# Converted by wrkflw. Not carried over as it was:\n");
+        for note in &self.notes {
+            header.push_str(&format!("# - {}\n", note));
+        }
+        Ok(format!("{}\n{}", header, yaml))
+    }
+}
+
+/// Convert the GitHub workflow at `path` to a GitLab CI pipeline
+pub fn convert_workflow(path: &Path) -> Result<Conversion, String> {
+    let workflow = crate::workflow::parse_workflow(path)?;
+    Ok(workflow_to_pipeline(&workflow))
+}
+
+/// Convert the GitLab CI pipeline at `path` to a GitHub workflow
+pub fn convert_pipeline(path: &Path) -> Result<Conversion, String> {
+    crate::gitlab::parse_pipeline(path).map_err(|e| e.to_string())?;
+    let expanded = expand_pipeline(path, ExpandOptions::default())?;
+    pipeline_to_workflow(&expanded)
+}
+
+fn set(mapping: &mut Mapping, key: &str, value: impl Into<Value>) {
+    mapping.insert(Value::from(key), value.into());
+}
+
+fn strings<'a>(values: impl IntoIterator<Item = &'a String>) -> Value {
+    Value::Sequence(
+        values
+            .into_iter()
+            .map(|v| Value::from(v.as_str()))
+            .collect(),
+    )
+}
+
+fn dedup(notes: Vec<String>) -> Vec<String> {
+    let mut seen = BTreeSet::new();
+    notes
+        .into_iter()
+        .filter(|note| seen.insert(note.clone()))
+        .collect()
+}
+
+/// A GitHub workflow as a GitLab CI pipeline
+pub fn workflow_to_pipeline(workflow: &github::Workflow) -> Conversion {
+    let mut notes = Vec::new();
+    let mut pipeline = Mapping::new();
+
+    let mut settings = Mapping::new();
+    if let Some(name) = &workflow.name {
+        set(
+            &mut settings,
+            "name",
+            gitlab_text(name, "the workflow name", &mut notes),
+        );
+    }
+    let rules = trigger_rules(&workflow.on, &mut notes);
+    if !rules.is_empty() {
+        set(&mut settings, "rules", Value::Sequence(rules));
+    }
+    // wrkflw's own pipeline model wants `rules` with a `workflow:`
+    if settings.contains_key("rules") {
+        set(&mut pipeline, "workflow", settings);
+    }
+
+    if !workflow.env.is_empty() {
+        let mut variables = Mapping::new();
+        for (name, value) in &workflow.env {
+            set(
+                &mut variables,
+                name,
+                gitlab_text(value, "the workflow's env", &mut notes),
+            );
+        }
+        set(&mut pipeline, "variables", variables);
+    }
+    if workflow.permissions.is_some() {
+        notes.push(
+            "`permissions` has no GitLab CI equivalent; what CI_JOB_TOKEN can access is set in the project's settings"
+                .to_string(),
+        );
+    }
+    if workflow.concurrency.is_some() {
+        notes.push(
+            "`concurrency` has no GitLab CI equivalent; `resource_group` runs a job one pipeline at a time"
+                .to_string(),
+        );
+    }
+
+    // GitLab reserves some top-level keys, so jobs named after them get a
+    // suffix. Jobs calling reusable workflows are left out, and so are
+    // needs of them.
+    let names: HashMap<&str, String> = workflow
+        .jobs
+        .iter()
+        .filter(|(_, job)| job.uses.is_none())
+        .map(|(id, _)| {
+            let name = match GLOBAL_KEYWORDS.contains(&id.as_str()) {
+                true => format!("{}-job", id),
+                false => id.clone(),
+            };
+            (id.as_str(), name)
+        })
+        .collect();
+    let run_defaults = workflow.defaults.as_ref().and_then(|d| d.run.as_ref());
+    for (id, job) in &workflow.jobs {
+        if let Some(converted) = gitlab_job(id, job, run_defaults, &names, &mut notes) {
+            set(&mut pipeline, &names[id.as_str()], converted);
+        }
+    }
+
+    Conversion {
+        platform: Platform::GitLab,
+        document: Value::Mapping(pipeline),
+        notes: dedup(notes),
+    }
+}
+
+fn gitlab_job(
+    id: &str,
+    job: &github::Job,
+    run_defaults: Option<&RunDefaults>,
+    names: &HashMap<&str, String>,
+    notes: &mut Vec<String>,
+) -> Option<Value> {
+    let context = format!("job '{}'", id);
+    if let Some(uses) = &job.uses {
+        notes.push(format!(
+            "{} calls the reusable workflow {}, which has no GitLab CI equivalent, so it was left out",
+            context, uses
+        ));
+        return None;
+    }
+
+    let mut converted = Mapping::new();
+    let mut variables = Mapping::new();
+    for (name, value) in &job.env {
+        set(&mut variables, name, gitlab_text(value, &context, notes));
+    }
+
+    match &job.container {
+        Some(container) => {
+            set(
+                &mut converted,
+                "image",
+                gitlab_text(container.image(), &context, notes),
+            );
+            if let JobContainer::Detailed(container) = container {
+                container_notes(container, &format!("the container of {}", context), notes);
+                for (name, value) in &container.env {
+                    set(&mut variables, name, gitlab_text(value, &context, notes));
+                }
+            }
+        }
+        None => {
+            if let Some(runs_on) = &job.runs_on {
+                runner(runs_on.labels(), &context, &mut converted, notes);
+            }
+        }
+    }
+
+    let needs: Vec<&String> = job
+        .needs()
+        .iter()
+        .filter_map(|need| names.get(need.as_str()))
+        .collect();
+    if !needs.is_empty() {
+        set(&mut converted, "needs", strings(needs));
+    }
+    if let Some(condition) = &job.if_condition {
+        if let Some(rules) = gitlab_rules(condition, &context, notes) {
+            set(&mut converted, "rules", rules);
+        }
+    }
+    if let Some(environment) = &job.environment {
+        let environment = match environment {
+            Environment::Name(name) => Value::from(gitlab_text(name, &context, notes)),
+            Environment::Detailed { name, url } => {
+                let mut detailed = Mapping::new();
+                set(&mut detailed, "name", gitlab_text(name, &context, notes));
+                if let Some(url) = url {
+                    set(&mut detailed, "url", gitlab_text(url, &context, notes));
+                }
+                Value::Mapping(detailed)
+            }
+        };
+        set(&mut converted, "environment", environment);
+    }
+    match &job.timeout_minutes {
+        Some(OrExpression::Value(minutes)) => {
+            set(&mut converted, "timeout", format!("{} minutes", minutes))
+        }
+        Some(OrExpression::Expression(_)) => notes.push(format!(
+            "{} sets `timeout-minutes` with an expression, which GitLab CI can't",
+            context
+        )),
+        None => {}
+    }
+    match &job.continue_on_error {
+        Some(OrExpression::Value(true)) => set(&mut converted, "allow_failure", true),
+        Some(OrExpression::Expression(_)) => notes.push(format!(
+            "{} sets `continue-on-error` with an expression, which GitLab CI can't",
+            context
+        )),
+        _ => {}
+    }
+    if let Some(strategy) = &job.strategy {
+        if let Some(matrix) = &strategy.matrix {
+            if let Some(parallel) = gitlab_matrix(matrix, &context, notes) {
+                set(&mut converted, "parallel", parallel);
+            }
+        }
+        if strategy.max_parallel.is_some() {
+            notes.push(format!(
+                "{} sets `max-parallel`, which GitLab CI has no equivalent for",
+                context
+            ));
+        }
+    }
+    if !job.services.is_empty() {
+        let mut services = Vec::new();
+        for (name, service) in &job.services {
+            let mut converted_service = Mapping::new();
+            set(
+                &mut converted_service,
+                "name",
+                gitlab_text(&service.image, &context, notes),
+            );
+            set(&mut converted_service, "alias", name.as_str());
+            services.push(Value::Mapping(converted_service));
+            container_notes(
+                service,
+                &format!("service '{}' of {}", name, context),
+                notes,
+            );
+            for (name, value) in &service.env {
+                set(&mut variables, name, gitlab_text(value, &context, notes));
+            }
+        }
+        set(&mut converted, "services", Value::Sequence(services));
+    }
+    if !job.outputs.is_empty() {
+        notes.push(format!(
+            "{} has `outputs`; GitLab jobs pass values on through `artifacts: reports: dotenv`",
+            context
+        ));
+    }
+    if job.permissions.is_some() || job.concurrency.is_some() {
+        notes.push(format!(
+            "{} sets `permissions` or `concurrency`, which GitLab CI has no equivalent for",
+            context
+        ));
+    }
+
+    let run_defaults = job
+        .defaults
+        .as_ref()
+        .and_then(|d| d.run.as_ref())
+        .or(run_defaults);
+    let steps = gitlab_steps(id, job, run_defaults, &mut variables, notes);
+
+    if !variables.is_empty() {
+        set(&mut converted, "variables", variables);
+    }
+    for (key, value) in steps {
+        set(&mut converted, key, value);
+    }
+    Some(Value::Mapping(converted))
+}
+
+fn container_notes(container: &github::Container, context: &str, notes: &mut Vec<String>) {
+    if container.credentials.is_some() {
+        notes.push(format!(
+            "{} has `credentials`; GitLab reads registry logins from DOCKER_AUTH_CONFIG",
+            context
+        ));
+    }
+    if container.options.is_some() || !container.volumes.is_empty() {
+        notes.push(format!(
+            "{} has `options` or `volumes`, which GitLab CI sets on the runner instead",
+            context
+        ));
+    }
+}
+
+/// `image:` or `tags:` for the labels a job runs on
+fn runner(labels: &[String], context: &str, job: &mut Mapping, notes: &mut Vec<String>) {
+    if labels.iter().any(|label| label.contains("${{")) {
+        notes.push(format!(
+            "{} picks its runner with an expression; set an image or runner tags for it",
+            context
+        ));
+        return;
+    }
+    let mut tags = Vec::new();
+    for label in labels {
+        if let Some(version) = label.strip_prefix("ubuntu-") {
+            set(job, "image", format!("ubuntu:{}", version));
+        } else if label.starts_with("windows-") || label.starts_with("macos-") {
+            notes.push(format!(
+                "{} runs on {}, which needs a GitLab runner tagged for it",
+                context, label
+            ));
+            tags.push(label);
+        } else if label != "self-hosted" {
+            tags.push(label);
+        }
+    }
+    if !tags.is_empty() {
+        set(job, "tags", strings(tags));
+    }
+}
+
+/// The `script:`, `artifacts:` and `cache:` a job's steps make
+fn gitlab_steps(
+    id: &str,
+    job: &github::Job,
+    run_defaults: Option<&RunDefaults>,
+    variables: &mut Mapping,
+    notes: &mut Vec<String>,
+) -> Vec<(&'static str, Value)> {
+    let mut script = Vec::new();
+    let mut artifacts = Mapping::new();
+    let mut artifact_paths = Vec::new();
+    let mut caches = Vec::new();
+
+    for (idx, step) in job.steps.iter().enumerate() {
+        let label = step
+            .name
+            .clone()
+            .or_else(|| step.id.clone())
+            .or_else(|| step.uses.clone())
+            .unwrap_or_else(|| format!("#{}", idx + 1));
+        let context = format!("step '{}' of job '{}'", label, id);
+
+        if let Some(uses) = &step.uses {
+            let action = uses.split('@').next().unwrap_or(uses).to_lowercase();
+            match action.as_str() {
+                // GitLab checks out the repository, and downloads the
+                // artifacts of the jobs a job needs, by itself
+                "actions/checkout" | "actions/download-artifact" => {}
+                "actions/upload-artifact" => {
+                    if let Some(path) = step.with.get("path") {
+                        artifact_paths.extend(
+                            path.lines()
+                                .map(str::trim)
+                                .filter(|line| !line.is_empty())
+                                .map(|line| gitlab_text(line, &context, notes)),
+                        );
+                    }
+                    if let Some(name) = step.with.get("name") {
+                        let name = Value::from(gitlab_text(name, &context, notes));
+                        match artifacts.get("name") {
+                            Some(existing) if *existing != name => notes.push(format!(
+                                "{} uploads another artifact; GitLab keeps one archive per job, named after the first",
+                                context
+                            )),
+                            _ => set(&mut artifacts, "name", name),
+                        }
+                    }
+                    if let Some(days) = step.with.get("retention-days") {
+                        set(&mut artifacts, "expire_in", format!("{} days", days));
+                    }
+                    for key in step.with.keys() {
+                        if !["path", "name", "retention-days"].contains(&key.as_str()) {
+                            notes.push(format!(
+                                "{} sets `{}`, which GitLab artifacts don't have",
+                                context, key
+                            ));
+                        }
+                    }
+                    match step.if_condition.as_deref().map(strip_expression) {
+                        Some(condition) if condition.contains("always()") => {
+                            set(&mut artifacts, "when", "always")
+                        }
+                        Some(condition) if condition.contains("failure()") => {
+                            set(&mut artifacts, "when", "on_failure")
+                        }
+                        _ => {}
+                    }
+                }
+                "actions/cache" | "actions/cache/restore" | "actions/cache/save" => {
+                    let mut cache = Mapping::new();
+                    if let Some(key) = step.with.get("key") {
+                        set(&mut cache, "key", gitlab_text(key, &context, notes));
+                    }
+                    if let Some(path) = step.with.get("path") {
+                        let paths: Vec<String> = path
+                            .lines()
+                            .map(str::trim)
+                            .filter(|line| !line.is_empty())
+                            .map(|line| gitlab_text(line, &context, notes))
+                            .collect();
+                        set(&mut cache, "paths", strings(&paths));
+                    }
+                    if let Some(restore_keys) = step.with.get("restore-keys") {
+                        let keys: Vec<String> = restore_keys
+                            .lines()
+                            .map(str::trim)
+                            .filter(|line| !line.is_empty())
+                            .map(|line| gitlab_text(line, &context, notes))
+                            .collect();
+                        set(&mut cache, "fallback_keys", strings(&keys));
+                    }
+                    match action.as_str() {
+                        "actions/cache/restore" => set(&mut cache, "policy", "pull"),
+                        "actions/cache/save" => set(&mut cache, "policy", "push"),
+                        _ => {}
+                    }
+                    caches.push(Value::Mapping(cache));
+                }
+                _ if action.starts_with("actions/setup-") => notes.push(format!(
+                    "{} uses {}; use an image that has the tool instead",
+                    context, uses
+                )),
+                _ => notes.push(format!(
+                    "{} uses the action {}, which has no GitLab CI equivalent, so it was left out",
+                    context, uses
+                )),
+            }
+            continue;
+        }
+
+        let Some(run) = &step.run else {
+            continue;
+        };
+        if let Some(condition) = &step.if_condition {
+            notes.push(format!(
+                "{} only runs `if: {}`; it now always runs",
+                context, condition
+            ));
+        }
+        if step.continue_on_error.is_some() {
+            notes.push(format!(
+                "{} has `continue-on-error`; GitLab scripts stop at the first failing command",
+                context
+            ));
+        }
+        if step.timeout_minutes.is_some() {
+            notes.push(format!(
+                "{} has `timeout-minutes`, which GitLab CI can't set per command",
+                context
+            ));
+        }
+        let shell = step
+            .shell
+            .as_deref()
+            .or(run_defaults.and_then(|d| d.shell.as_deref()));
+        if shell.is_some_and(|shell| shell != "bash" && shell != "sh") {
+            notes.push(format!(
+                "{} runs in {}; GitLab runs scripts in the image's shell",
+                context,
+                shell.unwrap_or_default()
+            ));
+        }
+        for (name, value) in &step.env {
+            let value = Value::from(gitlab_text(value, &context, notes));
+            match variables.get(name.as_str()) {
+                Some(existing) if *existing != value => notes.push(format!(
+                    "{} sets {} differently than its job; the job's value is kept",
+                    context, name
+                )),
+                _ => set(variables, name, value),
+            }
+        }
+
+        for caps in GITHUB_FILE_COMMAND.captures_iter(run) {
+            notes.push(format!(
+                "{} writes to ${}, which GitLab doesn't read",
+                context, &caps[1]
+            ));
+        }
+        let run = gitlab_text(run.trim_end(), &context, notes);
+        let directory = step
+            .working_directory
+            .as_deref()
+            .or(run_defaults.and_then(|d| d.working_directory.as_deref()));
+        script.push(Value::from(match directory {
+            Some(directory) => format!(
+                "cd \"{}\"\n{}\ncd \"$CI_PROJECT_DIR\"",
+                gitlab_text(directory, &context, notes),
+                run
+            ),
+            None => run,
+        }));
+    }
+
+    if script.is_empty() {
+        script.push(Value::from(format!("echo \"Nothing to run in {}\"", id)));
+    }
+    let mut keys = vec![("script", Value::Sequence(script))];
+    if !artifact_paths.is_empty() {
+        let mut converted = Mapping::new();
+        set(&mut converted, "paths", strings(&artifact_paths));
+        converted.extend(artifacts);
+        keys.push(("artifacts", Value::Mapping(converted)));
+    }
+    match caches.len() {
+        0 => {}
+        1 => keys.push(("cache", caches.remove(0))),
+        _ => keys.push(("cache", Value::Sequence(caches))),
+    }
+    keys
+}
+
+/// `${{ expr }}` without the braces around it
+fn strip_expression(condition: &str) -> &str {
+    let condition = condition.trim();
+    condition
+        .strip_prefix("${{")
+        .and_then(|rest| rest.strip_suffix("}}"))
+        .unwrap_or(condition)
+        .trim()
+}
+
+/// The GitLab variable holding what a GitHub expression reads
+fn gitlab_variable(expression: &str) -> Option<String> {
+    if let Some((_, variable)) = PREDEFINED_VARIABLES
+        .iter()
+        .find(|(context, _)| *context == expression)
+    {
+        return Some(variable.to_string());
+    }
+    let (context, name) = expression.split_once('.')?;
+    let is_name = !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    (["env", "vars", "secrets", "matrix"].contains(&context) && is_name).then(|| name.to_string())
+}
+
+/// Text with its `${{ }}` expressions turned into GitLab variables where
+/// there is one to turn them into
+fn gitlab_text(text: &str, context: &str, notes: &mut Vec<String>) -> String {
+    GITHUB_EXPRESSION
+        .replace_all(text, |caps: &Captures| {
+            let expression = &caps[1];
+            let Some(variable) = gitlab_variable(expression) else {
+                notes.push(format!(
+                    "{} uses `${{{{ {} }}}}`, which has no GitLab CI equivalent",
+                    context, expression
+                ));
+                return caps[0].to_string();
+            };
+            if let Some(secret) = expression.strip_prefix("secrets.") {
+                if secret != "GITHUB_TOKEN" {
+                    notes.push(format!(
+                        "secret {} is read from ${}; add it as a masked CI/CD variable",
+                        secret, secret
+                    ));
+                }
+            }
+            let end = caps.get(0).map_or(0, |m| m.end());
+            let followed_by_name = text[end..]
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_');
+            match followed_by_name {
+                true => format!("${{{}}}", variable),
+                false => format!("${}", variable),
+            }
+        })
+        .into_owned()
+}
+
+/// A job's `if:` as `rules:`, when it is one GitLab can check
+fn gitlab_rules(condition: &str, context: &str, notes: &mut Vec<String>) -> Option<Value> {
+    let condition = strip_expression(condition);
+    let mut rule = Mapping::new();
+    match condition {
+        "success()" => return None,
+        "always()" | "!cancelled()" => set(&mut rule, "when", "always"),
+        "failure()" => set(&mut rule, "when", "on_failure"),
+        _ => {
+            let converted =
+                GITHUB_REF_PREFIX.replace_all(condition, |caps: &Captures| match &caps[1] {
+                    "tags" => "$CI_COMMIT_TAG".to_string(),
+                    _ => "$CI_COMMIT_BRANCH".to_string(),
+                });
+            let converted = GITHUB_COMPARISON.replace_all(&converted, |caps: &Captures| {
+                let (context, operator, value) = (&caps[1], &caps[2], &caps[3]);
+                let comparison = |variable: &str, value: &str| {
+                    format!("${} {} \"{}\"", variable, operator, value)
+                };
+                match context {
+                    "github.ref" => match value.strip_prefix("refs/tags/") {
+                        Some(tag) => comparison("CI_COMMIT_TAG", tag),
+                        None => comparison(
+                            "CI_COMMIT_BRANCH",
+                            value.strip_prefix("refs/heads/").unwrap_or(value),
+                        ),
+                    },
+                    "github.event_name" => comparison(
+                        "CI_PIPELINE_SOURCE",
+                        EVENT_SOURCES
+                            .iter()
+                            .find(|(event, _)| *event == value)
+                            .map_or(value, |(_, source)| source),
+                    ),
+                    _ => match gitlab_variable(context) {
+                        Some(variable) => comparison(&variable, value),
+                        None => caps[0].to_string(),
+                    },
+                }
+            });
+            if GITHUB_LEFTOVER.is_match(&converted) {
+                notes.push(format!(
+                    "{} runs `if: {}`, which couldn't be made a GitLab rule; it now always runs",
+                    context, condition
+                ));
+                return None;
+            }
+            set(&mut rule, "if", converted.into_owned());
+        }
+    }
+    Some(Value::Sequence(vec![Value::Mapping(rule)]))
+}
+
+/// `strategy.matrix` as `parallel: matrix:`
+fn gitlab_matrix(matrix: &Matrix, context: &str, notes: &mut Vec<String>) -> Option<Value> {
+    let Matrix::Values {
+        parameters,
+        include,
+        exclude,
+    } = matrix
+    else {
+        notes.push(format!(
+            "{} builds its matrix with an expression, which GitLab CI can't",
+            context
+        ));
+        return None;
+    };
+
+    let mut entries = Vec::new();
+    let mut entry = Mapping::new();
+    for (name, values) in parameters {
+        let values = match values {
+            Value::Sequence(values) => values
+                .iter()
+                .map(|value| matrix_value(value, context, notes))
+                .collect(),
+            value => matrix_value(value, context, notes).map(|value| vec![value]),
+        };
+        let Some(values) = values else {
+            notes.push(format!(
+                "{}'s matrix has objects in `{}`, which GitLab's matrix can't hold",
+                context, name
+            ));
+            return None;
+        };
+        set(&mut entry, name, Value::Sequence(values));
+    }
+    if !entry.is_empty() {
+        entries.push(Value::Mapping(entry));
+    }
+    for combination in include {
+        if !parameters.is_empty() && !combination.keys().all(|key| parameters.contains_key(key)) {
+            notes.push(format!(
+                "{}'s matrix `include` adds values to combinations; they run as combinations of their own",
+                context
+            ));
+        }
+        let mut entry = Mapping::new();
+        for (name, value) in combination {
+            match matrix_value(value, context, notes) {
+                Some(value) => set(&mut entry, name, value),
+                None => {
+                    notes.push(format!(
+                        "{}'s matrix has objects in `{}`, which GitLab's matrix can't hold",
+                        context, name
+                    ));
+                    return None;
+                }
+            }
+        }
+        entries.push(Value::Mapping(entry));
+    }
+    if !exclude.is_empty() {
+        notes.push(format!(
+            "{}'s matrix `exclude` has no GitLab CI equivalent; those combinations run too",
+            context
+        ));
+    }
+
+    let mut parallel = Mapping::new();
+    set(&mut parallel, "matrix", Value::Sequence(entries));
+    Some(Value::Mapping(parallel))
+}
+
+fn matrix_value(value: &Value, context: &str, notes: &mut Vec<String>) -> Option<Value> {
+    match value {
+        Value::String(text) => Some(Value::from(gitlab_text(text, context, notes))),
+        Value::Number(number) => Some(Value::from(number.to_string())),
+        Value::Bool(flag) => Some(Value::from(flag.to_string())),
+        _ => None,
+    }
+}
+
+/// `workflow: rules:` running the pipeline for the events a workflow
+/// listens to
+fn trigger_rules(on: &github::On, notes: &mut Vec<String>) -> Vec<Value> {
+    let mut rules = Vec::new();
+    for event in on.events() {
+        let Some((_, source)) = EVENT_SOURCES.iter().find(|(name, _)| *name == event) else {
+            notes.push(format!(
+                "the `{}` trigger has no GitLab CI equivalent",
+                event
+            ));
+            continue;
+        };
+        let base = format!("$CI_PIPELINE_SOURCE == \"{}\"", source);
+        let config = on.config(event);
+
+        let mut ignored = Vec::new();
+        let mut conditions = Vec::new();
+        let (branch_variable, tag_variable) = match *source {
+            "merge_request_event" => ("CI_MERGE_REQUEST_TARGET_BRANCH_NAME", None),
+            _ => ("CI_COMMIT_BRANCH", Some("CI_COMMIT_TAG")),
+        };
+        if let Some(config) = config {
+            let filters = [
+                (
+                    &config.branches,
+                    &config.branches_ignore,
+                    Some(branch_variable),
+                ),
+                (&config.tags, &config.tags_ignore, tag_variable),
+            ];
+            for (patterns, ignore, variable) in filters {
+                let Some(variable) = variable else {
+                    continue;
+                };
+                for pattern in patterns.iter().flat_map(|p| p.values()) {
+                    match pattern.strip_prefix('!') {
+                        Some(pattern) => ignored.push(ref_condition(variable, pattern)),
+                        None => conditions.push(ref_condition(variable, pattern)),
+                    }
+                }
+                for pattern in ignore.iter().flat_map(|p| p.values()) {
+                    ignored.push(ref_condition(variable, pattern));
+                }
+            }
+            if config.types.is_some() {
+                notes.push(format!(
+                    "the `{}` trigger's `types` have no GitLab CI equivalent",
+                    event
+                ));
+            }
+            if config.paths_ignore.is_some() {
+                notes.push(format!(
+                    "the `{}` trigger's `paths-ignore` has no GitLab CI equivalent",
+                    event
+                ));
+            }
+            if !config.inputs.is_empty() {
+                notes.push(format!(
+                    "the `{}` trigger's inputs are CI/CD variables set when running the pipeline in GitLab",
+                    event
+                ));
+            }
+        }
+        for schedule in on.schedules() {
+            notes.push(format!(
+                "the schedule `{}` has to be set up under Build > Pipeline schedules in GitLab",
+                schedule.cron
+            ));
+        }
+
+        for condition in ignored {
+            let mut rule = Mapping::new();
+            set(&mut rule, "if", format!("{} && {}", base, condition));
+            set(&mut rule, "when", "never");
+            rules.push(Value::Mapping(rule));
+        }
+        let paths = config.and_then(|config| config.paths.as_ref());
+        let conditions = match conditions.is_empty() {
+            true => vec![base],
+            false => conditions
+                .into_iter()
+                .map(|condition| format!("{} && {}", base, condition))
+                .collect(),
+        };
+        for condition in conditions {
+            let mut rule = Mapping::new();
+            set(&mut rule, "if", condition);
+            if let Some(paths) = paths {
+                set(&mut rule, "changes", strings(paths.values()));
+            }
+            rules.push(Value::Mapping(rule));
+        }
+    }
+    rules
+}
+
+/// A branch or tag filter as a rule condition, a regular expression when
+/// it has wildcards
+fn ref_condition(variable: &str, pattern: &str) -> String {
+    match pattern.contains(['*', '?', '+', '[']) {
+        true => format!(
+            "${} =~ /{}/",
+            variable,
+            filter_pattern_regex(pattern).replace('/', "\\/")
+        ),
+        false => format!("${} == \"{}\"", variable, pattern),
+    }
+}
+
+/// A GitLab CI pipeline, as expanded by `expand_pipeline`, as a GitHub
+/// workflow
+pub fn pipeline_to_workflow(expanded: &Value) -> Result<Conversion, String> {
+    let pipeline: gitlab::Pipeline =
+        serde_yaml::from_value(expanded.clone()).map_err(|e| format!("Invalid pipeline: {}", e))?;
+    let Some(root) = expanded.as_mapping() else {
+        return Err("Pipeline file must contain a YAML mapping".to_string());
+    };
+    let mut notes = Vec::new();
+
+    let stages = pipeline_stages(&pipeline);
+    let mut jobs: Vec<PipelineJob> = root
+        .iter()
+        .filter_map(|(name, raw)| {
+            let name = name.as_str()?;
+            if GLOBAL_KEYWORDS.contains(&name) {
+                return None;
+            }
+            Some((name, pipeline.jobs.get(name)?, raw.as_mapping()?))
+        })
+        .collect();
+    jobs.sort_by_key(|(_, job, _)| stage_position(&stages, job));
+    let mut ids: HashMap<&str, String> = HashMap::new();
+    for (name, _, _) in &jobs {
+        let mut id = github_job_id(name);
+        while ids.values().any(|taken| *taken == id) {
+            id.push('_');
+        }
+        ids.insert(name, id);
+    }
+    let all = PipelineJobs {
+        pipeline: &pipeline,
+        jobs: &jobs,
+        ids: &ids,
+        stages: &stages,
+    };
+
+    let mut workflow = Mapping::new();
+    set(&mut workflow, "name", "CI");
+    set(
+        &mut workflow,
+        "on",
+        github_triggers(pipeline.workflow.as_ref(), &mut notes),
+    );
+
+    let mut predefined = BTreeSet::new();
+    let mut env = Mapping::new();
+    for (name, value) in sorted(pipeline.variables.as_ref()) {
+        let value = github_value(value, false);
+        collect_predefined(&value, &mut predefined);
+        set(&mut env, name, value);
+    }
+
+    let mut converted_jobs = Mapping::new();
+    for (name, job, raw) in &jobs {
+        let converted = github_job(name, job, raw, &all, &mut predefined, &mut notes);
+        set(&mut converted_jobs, &ids[name], converted);
+    }
+
+    // Scripts read GitLab's predefined variables, so those GitHub has
+    // something for are set from its contexts
+    for variable in predefined {
+        match PREDEFINED_VARIABLES
+            .iter()
+            .find(|(_, name)| *name == variable)
+        {
+            Some((context, _)) => set(&mut env, &variable, format!("${{{{ {} }}}}", context)),
+            None => notes.push(format!(
+                "${} has no GitHub Actions equivalent, so scripts reading it get nothing",
+                variable
+            )),
+        }
+    }
+    if !env.is_empty() {
+        set(&mut workflow, "env", env);
+    }
+    set(&mut workflow, "jobs", converted_jobs);
+
+    Ok(Conversion {
+        platform: Platform::GitHub,
+        document: Value::Mapping(workflow),
+        notes: dedup(notes),
+    })
+}
+
+/// A job of the pipeline by name, with its keys as written
+type PipelineJob<'a> = (&'a str, &'a gitlab::Job, &'a Mapping);
+
+fn stage_position(stages: &[String], job: &gitlab::Job) -> Option<usize> {
+    stages.iter().position(|stage| stage == job_stage(job))
+}
+
+/// The jobs of a pipeline being converted, with their GitHub job ids
+struct PipelineJobs<'a> {
+    pipeline: &'a gitlab::Pipeline,
+    jobs: &'a [PipelineJob<'a>],
+    ids: &'a HashMap<&'a str, String>,
+    stages: &'a [String],
+}
+
+impl<'a> PipelineJobs<'a> {
+    /// Jobs in stages before a job's
+    fn earlier(&self, job: &gitlab::Job) -> Vec<&'a PipelineJob<'a>> {
+        let own = stage_position(self.stages, job);
+        self.jobs
+            .iter()
+            .filter(|(_, other, _)| {
+                matches!((stage_position(self.stages, other), own), (Some(theirs), Some(own)) if theirs < own)
+            })
+            .collect()
+    }
+}
+
+fn sorted(variables: Option<&HashMap<String, String>>) -> BTreeMap<&String, &String> {
+    variables.into_iter().flatten().collect()
+}
+
+/// A job name as a GitHub job id, which only has letters, digits, `-`
+/// and `_` and doesn't start with a digit or `-`
+fn github_job_id(name: &str) -> String {
+    let mut id = String::new();
+    for c in name.chars() {
+        match c {
+            c if c.is_ascii_alphanumeric() || c == '_' => id.push(c),
+            _ if !id.ends_with('-') => id.push('-'),
+            _ => {}
+        }
+    }
+    let id = id.trim_matches('-').to_string();
+    match id.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => id,
+        _ => format!("_{}", id),
+    }
+}
+
+fn github_job(
+    name: &str,
+    job: &gitlab::Job,
+    raw: &Mapping,
+    all: &PipelineJobs,
+    predefined: &mut BTreeSet<String>,
+    notes: &mut Vec<String>,
+) -> Value {
+    let (pipeline, ids) = (all.pipeline, all.ids);
+    let context = format!("job '{}'", name);
+    let mut converted = Mapping::new();
+    if ids[name] != name {
+        set(&mut converted, "name", name);
+    }
+    for key in raw.keys().filter_map(Value::as_str) {
+        if !CONVERTED_JOB_KEYS.contains(&key) {
+            notes.push(format!(
+                "{}'s `{}` has no GitHub Actions equivalent, so it was left out",
+                context, key
+            ));
+        }
+    }
+
+    // Jobs wait for their `needs`, or for the closest earlier stage
+    let stage_of = |job: &gitlab::Job| stage_position(all.stages, job);
+    let earlier = all.earlier(job);
+    let previous_stage = earlier
+        .iter()
+        .filter_map(|(_, other, _)| stage_of(other))
+        .max();
+    let needs: Vec<&str> = match &job.needs {
+        Some(needs) => needs.iter().map(|need| need.job()).collect(),
+        None => earlier
+            .iter()
+            .filter(|(_, other, _)| stage_of(other) == previous_stage)
+            .map(|(name, _, _)| *name)
+            .collect(),
+    };
+    let needs: Vec<&String> = needs.iter().filter_map(|need| ids.get(need)).collect();
+    if !needs.is_empty() {
+        set(&mut converted, "needs", strings(needs));
+    }
+
+    if let Some(condition) = github_condition(job, &context, notes) {
+        set(&mut converted, "if", condition);
+    }
+    set(&mut converted, "runs-on", "ubuntu-latest");
+    if let Some(tags) = &job.tags {
+        notes.push(format!(
+            "{} ran on runners tagged {}; it now runs on ubuntu-latest",
+            context,
+            tags.join(", ")
+        ));
+    }
+    match raw.get("environment") {
+        Some(Value::String(environment)) => set(
+            &mut converted,
+            "environment",
+            github_value(environment, true),
+        ),
+        Some(Value::Mapping(environment)) => {
+            let mut detailed = Mapping::new();
+            for key in ["name", "url"] {
+                if let Some(value) = environment.get(key).and_then(Value::as_str) {
+                    set(&mut detailed, key, github_value(value, true));
+                }
+            }
+            set(&mut converted, "environment", detailed);
+        }
+        _ => {}
+    }
+    if let Some(timeout) = &job.timeout {
+        match duration_minutes(timeout) {
+            Some(minutes) => set(&mut converted, "timeout-minutes", minutes),
+            None => notes.push(format!(
+                "{}'s timeout `{}` couldn't be read",
+                context, timeout
+            )),
+        }
+    }
+    if job.allow_failure == Some(true) {
+        set(&mut converted, "continue-on-error", true);
+    }
+    if job.retry.is_some() {
+        notes.push(format!(
+            "{} is retried on failure, which GitHub Actions doesn't do for jobs",
+            context
+        ));
+    }
+
+    let mut env = Mapping::new();
+    if let Some(strategy) = github_strategy(name, job, &mut env) {
+        set(&mut converted, "strategy", strategy);
+    }
+    if let Some(image) = &job.image {
+        let mut container = Mapping::new();
+        set(
+            &mut container,
+            "image",
+            github_image(image.name(), job, pipeline),
+        );
+        if matches!(
+            image,
+            Image::Detailed {
+                entrypoint: Some(_),
+                ..
+            }
+        ) {
+            notes.push(format!(
+                "{}'s image `entrypoint` has no GitHub Actions equivalent",
+                context
+            ));
+        }
+        set(&mut converted, "container", container);
+    }
+    if let Some(services) = &job.services {
+        let mut converted_services = Mapping::new();
+        for service in services {
+            let (image, detailed) = match service {
+                Service::Simple(image) => (image, false),
+                Service::Detailed {
+                    name,
+                    command,
+                    entrypoint,
+                    ..
+                } => (name, command.is_some() || entrypoint.is_some()),
+            };
+            let mut converted_service = Mapping::new();
+            set(
+                &mut converted_service,
+                "image",
+                github_image(image, job, pipeline),
+            );
+            set(
+                &mut converted_services,
+                &service_name(image),
+                converted_service,
+            );
+            if detailed {
+                notes.push(format!(
+                    "{}'s service {} has a `command` or `entrypoint`, which GitHub Actions can't set",
+                    context, image
+                ));
+            }
+        }
+        if job.image.is_none() {
+            notes.push(format!(
+                "{} doesn't run in a container, so its services need `ports` to be reached from it",
+                context
+            ));
+        }
+        set(&mut converted, "services", converted_services);
+    }
+    for (name, value) in sorted(job.variables.as_ref()) {
+        let value = github_value(value, true);
+        collect_predefined(&value, predefined);
+        set(&mut env, name, value);
+    }
+    if !env.is_empty() {
+        set(&mut converted, "env", env);
+    }
+
+    let steps = github_steps(name, job, raw, &earlier, all, predefined, notes);
+    set(&mut converted, "steps", Value::Sequence(steps));
+    Value::Mapping(converted)
+}
+
+/// `strategy:` for a job's `parallel:`, setting the matrix values as
+/// the variables GitLab would
+fn github_strategy(name: &str, job: &gitlab::Job, env: &mut Mapping) -> Option<Value> {
+    let mut matrix = Mapping::new();
+    match job.parallel.as_ref()? {
+        Parallel::Count(count) if *count > 1 => {
+            set(
+                &mut matrix,
+                "index",
+                Value::Sequence((1..=*count).map(Value::from).collect()),
+            );
+            set(env, "CI_NODE_INDEX", "${{ matrix.index }}");
+            set(env, "CI_NODE_TOTAL", count.to_string());
+        }
+        Parallel::Count(_) => return None,
+        Parallel::Matrix { matrix: entries } => {
+            let mut variables = BTreeSet::new();
+            match entries.as_slice() {
+                [entry] => {
+                    for (variable, values) in entry {
+                        set(&mut matrix, variable, strings(values.values()));
+                        variables.insert(variable.clone());
+                    }
+                }
+                _ => {
+                    let combinations = job_instances(name, job)
+                        .into_iter()
+                        .map(|instance| {
+                            let mut combination = Mapping::new();
+                            for (variable, value) in instance.variables {
+                                if !variable.starts_with("CI_NODE_") {
+                                    set(&mut combination, &variable, value);
+                                    variables.insert(variable);
+                                }
+                            }
+                            Value::Mapping(combination)
+                        })
+                        .collect();
+                    set(&mut matrix, "include", Value::Sequence(combinations));
+                }
+            }
+            for variable in variables {
+                set(env, &variable, format!("${{{{ matrix.{} }}}}", variable));
+            }
+        }
+    }
+    let mut strategy = Mapping::new();
+    set(&mut strategy, "fail-fast", false);
+    set(&mut strategy, "matrix", matrix);
+    Some(Value::Mapping(strategy))
+}
+
+/// The name GitLab gives a service's container, e.g. `postgres` for
+/// `postgres:15`
+fn service_name(image: &str) -> String {
+    let image = image.rsplit_once('/').map_or(image, |(_, name)| name);
+    let image = image.split([':', '@']).next().unwrap_or(image);
+    github_job_id(image)
+}
+
+/// Checkout, artifact downloads, cache, the scripts and artifact uploads
+fn github_steps(
+    name: &str,
+    job: &gitlab::Job,
+    raw: &Mapping,
+    earlier: &[&PipelineJob],
+    all: &PipelineJobs,
+    predefined: &mut BTreeSet<String>,
+    notes: &mut Vec<String>,
+) -> Vec<Value> {
+    let ids = all.ids;
+    let context = format!("job '{}'", name);
+    let step = |entries: Vec<(&str, Value)>| {
+        let mut step = Mapping::new();
+        for (key, value) in entries {
+            set(&mut step, key, value);
+        }
+        Value::Mapping(step)
+    };
+    let with = |entries: Vec<(&str, String)>| {
+        let mut with = Mapping::new();
+        for (key, value) in entries {
+            set(&mut with, key, value);
+        }
+        Value::Mapping(with)
+    };
+    let mut steps = vec![step(vec![("uses", Value::from("actions/checkout@v4"))])];
+
+    // GitLab hands jobs the artifacts of their `dependencies`, or else of
+    // the jobs they need or that ran in earlier stages
+    let sources: Vec<&str> = match (&job.dependencies, &job.needs) {
+        (Some(dependencies), _) => dependencies.iter().map(String::as_str).collect(),
+        (None, Some(needs)) => needs
+            .iter()
+            .filter(|need| {
+                !matches!(
+                    need,
+                    gitlab::Need::Detailed {
+                        artifacts: Some(false),
+                        ..
+                    }
+                )
+            })
+            .map(|need| need.job())
+            .collect(),
+        (None, None) => earlier.iter().map(|(name, _, _)| *name).collect(),
+    };
+    for source in sources {
+        let Some((_, other, _)) = all.jobs.iter().find(|(name, _, _)| *name == source) else {
+            continue;
+        };
+        let Some(paths) = other.artifacts.as_ref().and_then(|a| a.paths.as_ref()) else {
+            continue;
+        };
+        let mut download = vec![("name", ids[source].clone())];
+        match paths.as_slice() {
+            [path] if !path.contains(['*', '?', '[']) => download.push(("path", path.clone())),
+            _ => notes.push(format!(
+                "{} gets the artifacts of '{}' in the workspace itself, without the directories they share",
+                context, source
+            )),
+        }
+        steps.push(step(vec![
+            ("uses", Value::from("actions/download-artifact@v4")),
+            ("with", with(download)),
+        ]));
+    }
+
+    let mut save_cache = None;
+    if let Some(cache) = &job.cache {
+        let key = cache.key.as_deref().unwrap_or("default");
+        let cache_with = with(vec![
+            ("key", github_value(key, true)),
+            ("path", cache.paths.clone().unwrap_or_default().join("\n")),
+        ]);
+        match cache.policy.as_deref() {
+            Some("pull") => steps.push(step(vec![
+                ("uses", Value::from("actions/cache/restore@v4")),
+                ("with", cache_with),
+            ])),
+            Some("push") => {
+                save_cache = Some(step(vec![
+                    ("uses", Value::from("actions/cache/save@v4")),
+                    ("with", cache_with),
+                ]))
+            }
+            _ => steps.push(step(vec![
+                ("uses", Value::from("actions/cache@v4")),
+                ("with", cache_with),
+            ])),
+        }
+        if cache
+            .when
+            .as_deref()
+            .is_some_and(|when| when != "on_success")
+        {
+            notes.push(format!(
+                "{}'s cache is saved `when: {}`; GitHub saves caches after successful jobs",
+                context,
+                cache.when.as_deref().unwrap_or_default()
+            ));
+        }
+    }
+
+    // `before_script` and `script` run in one shell in GitLab
+    let script: Vec<&String> = job
+        .before_script
+        .iter()
+        .flatten()
+        .chain(job.script.iter().flatten())
+        .collect();
+    if !script.is_empty() {
+        let run = script
+            .iter()
+            .map(|line| line.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        collect_predefined(&run, predefined);
+        steps.push(step(vec![
+            ("name", Value::from("Script")),
+            ("run", Value::from(run)),
+        ]));
+    }
+    if let Some(after_script) = job.after_script.as_ref().filter(|lines| !lines.is_empty()) {
+        let run = after_script.join("\n");
+        collect_predefined(&run, predefined);
+        steps.push(step(vec![
+            ("name", Value::from("After script")),
+            ("if", Value::from("always()")),
+            ("continue-on-error", Value::from(true)),
+            ("run", Value::from(run)),
+        ]));
+    }
+    steps.extend(save_cache);
+
+    if let Some(artifacts) = &job.artifacts {
+        let paths = artifacts.paths.clone().unwrap_or_default();
+        let mut upload_with = vec![("name", ids[name].clone()), ("path", paths.join("\n"))];
+        if let Some(expire_in) = &artifacts.expire_in {
+            match duration_minutes(expire_in) {
+                Some(minutes) => upload_with.push((
+                    "retention-days",
+                    minutes.div_ceil(24 * 60).max(1).to_string(),
+                )),
+                None => notes.push(format!(
+                    "{}'s artifacts expire `{}`, which GitHub can't",
+                    context, expire_in
+                )),
+            }
+        }
+        let mut upload = vec![("uses", Value::from("actions/upload-artifact@v4"))];
+        match artifacts.when.as_deref() {
+            Some("always") => upload.push(("if", Value::from("always()"))),
+            Some("on_failure") => upload.push(("if", Value::from("failure()"))),
+            _ => {}
+        }
+        upload.push(("with", with(upload_with)));
+        if !paths.is_empty() {
+            steps.push(step(upload));
+        }
+        if let Some(Value::Mapping(raw)) = raw.get("artifacts") {
+            for key in raw.keys().filter_map(Value::as_str) {
+                if key == "name" {
+                    notes.push(format!(
+                        "{}'s artifacts are named after the job rather than `{}`, so the jobs that need them can download them",
+                        context,
+                        raw.get("name").and_then(Value::as_str).unwrap_or_default()
+                    ));
+                } else if !["paths", "expire_in", "when"].contains(&key) {
+                    notes.push(format!(
+                        "{}'s artifacts `{}` has no GitHub Actions equivalent",
+                        context, key
+                    ));
+                }
+            }
+        }
+    }
+    steps
+}
+
+/// The GitLab predefined variables some text reads
+fn collect_predefined(text: &str, predefined: &mut BTreeSet<String>) {
+    for caps in GITLAB_VARIABLE.captures_iter(text) {
+        let variable = caps.get(1).or(caps.get(2)).map_or("", |m| m.as_str());
+        if variable.starts_with("CI_") || variable.starts_with("GITLAB_") {
+            predefined.insert(variable.to_string());
+        }
+    }
+}
+
+/// A variable's value, with references to other variables as `${{ }}`
+/// expressions since GitHub doesn't expand `env:`. The workflow's own
+/// `env:` can't read the `env` context, so it only gets GitHub's contexts.
+fn github_value(value: &str, env_context: bool) -> String {
+    GITLAB_VARIABLE
+        .replace_all(value, |caps: &Captures| {
+            let variable = caps.get(1).or(caps.get(2)).map_or("", |m| m.as_str());
+            match PREDEFINED_VARIABLES
+                .iter()
+                .find(|(_, name)| *name == variable)
+            {
+                Some((context, _)) => format!("${{{{ {} }}}}", context),
+                None if env_context && !variable.starts_with("CI_") => {
+                    format!("${{{{ env.{} }}}}", variable)
+                }
+                None => caps[0].to_string(),
+            }
+        })
+        .into_owned()
+}
+
+/// An image name with its variables filled in, since `container:` and
+/// `services:` can't read the `env` context
+fn github_image(image: &str, job: &gitlab::Job, pipeline: &gitlab::Pipeline) -> String {
+    GITLAB_VARIABLE
+        .replace_all(image, |caps: &Captures| {
+            let variable = caps.get(1).or(caps.get(2)).map_or("", |m| m.as_str());
+            let defined = [&job.variables, &pipeline.variables]
+                .into_iter()
+                .find_map(|variables| variables.as_ref()?.get(variable));
+            match defined {
+                Some(value) => value.clone(),
+                None => github_value(&caps[0], false),
+            }
+        })
+        .into_owned()
+}
+
+/// A job's `if:`, from its `when`, `rules` and `only`/`except`
+fn github_condition(job: &gitlab::Job, context: &str, notes: &mut Vec<String>) -> Option<String> {
+    let mut conditions = Vec::new();
+    if let Some(rules) = &job.rules {
+        // The first rule that matches decides, so each rule applies when
+        // the ones before it don't match
+        let mut condition = String::from("false");
+        for rule in rules.iter().rev() {
+            let runs = rule.when.as_deref() != Some("never");
+            if rule.when.as_deref() == Some("manual") {
+                notes.push(format!(
+                    "{} has a `when: manual` rule; it now runs without waiting (an environment with required reviewers can hold it)",
+                    context
+                ));
+            }
+            if rule.variables.is_some() {
+                notes.push(format!(
+                    "{}'s rules set `variables`, which were left out",
+                    context
+                ));
+            }
+            let Some(matches) = rule.if_.as_deref() else {
+                condition = runs.to_string();
+                continue;
+            };
+            let Some(matches) = github_expression(matches) else {
+                notes.push(format!(
+                    "{} has the rule `if: {}`, which couldn't be made a GitHub condition, so the rules were left out",
+                    context,
+                    rule.if_.as_deref().unwrap_or_default()
+                ));
+                condition = String::from("true");
+                break;
+            };
+            condition = match (runs, condition.as_str()) {
+                (true, "false") => matches,
+                (true, _) => format!("({}) || ({})", matches, condition),
+                (false, "false") => String::from("false"),
+                (false, _) => format!("!({}) && ({})", matches, condition),
+            };
+        }
+        if condition != "true" {
+            conditions.push(condition);
+        }
+    }
+
+    let refs =
+        |only: Option<&Vec<String>>, branches: Option<&Vec<String>>, tags: Option<&Vec<String>>| {
+            only.into_iter()
+                .chain(branches)
+                .chain(tags)
+                .flatten()
+                .cloned()
+                .collect::<Vec<String>>()
+        };
+    let only = match &job.only {
+        Some(Only::Refs(refs)) => Some((refs.clone(), false)),
+        Some(Only::Complex {
+            refs: only,
+            branches,
+            tags,
+            variables,
+            changes,
+        }) => Some((
+            refs(only.as_ref(), branches.as_ref(), tags.as_ref()),
+            variables.is_some() || changes.is_some(),
+        )),
+        None => None,
+    };
+    let except = match &job.except {
+        Some(Except::Refs(refs)) => Some((refs.clone(), false)),
+        Some(Except::Complex {
+            refs: except,
+            branches,
+            tags,
+            variables,
+            changes,
+        }) => Some((
+            refs(except.as_ref(), branches.as_ref(), tags.as_ref()),
+            variables.is_some() || changes.is_some(),
+        )),
+        None => None,
+    };
+    for (refs, unconverted, negated) in only
+        .map(|(refs, unconverted)| (refs, unconverted, false))
+        .into_iter()
+        .chain(except.map(|(refs, unconverted)| (refs, unconverted, true)))
+    {
+        if unconverted {
+            notes.push(format!(
+                "{}'s `only`/`except` `variables` or `changes` were left out",
+                context
+            ));
+        }
+        let alternatives: Vec<String> = refs
+            .iter()
+            .filter_map(|r| {
+                let condition = ref_expression(r);
+                if condition.is_none() {
+                    notes.push(format!(
+                        "{}'s `only`/`except` ref `{}` couldn't be made a GitHub condition",
+                        context, r
+                    ));
+                }
+                condition
+            })
+            .collect();
+        if alternatives.is_empty() {
+            continue;
+        }
+        let alternatives = alternatives.join(" || ");
+        conditions.push(match negated {
+            true => format!("!({})", alternatives),
+            false => alternatives,
+        });
+    }
+
+    let status = match job.when.as_deref() {
+        Some("always") => Some("always()"),
+        Some("on_failure") => Some("failure()"),
+        Some("manual") => {
+            notes.push(format!(
+                "{} is `when: manual`; it now runs without waiting (an environment with required reviewers can hold it)",
+                context
+            ));
+            None
+        }
+        Some("delayed") => {
+            notes.push(format!(
+                "{} is `when: delayed`; it now runs without waiting",
+                context
+            ));
+            None
+        }
+        _ => None,
+    };
+    match (status, conditions.len()) {
+        (None, 0) => None,
+        (Some(status), 0) => Some(status.to_string()),
+        (None, 1) => conditions.pop(),
+        (status, _) => {
+            let conditions = conditions
+                .iter()
+                .map(|condition| format!("({})", condition))
+                .collect::<Vec<_>>()
+                .join(" && ");
+            Some(match status {
+                Some(status) => format!("{} && {}", status, conditions),
+                None => conditions,
+            })
+        }
+    }
+}
+
+/// An `only:`/`except:` ref as a GitHub condition
+fn ref_expression(name: &str) -> Option<String> {
+    let event = |event: &str| Some(format!("github.event_name == '{}'", event));
+    match name {
+        "branches" => Some("startsWith(github.ref, 'refs/heads/')".to_string()),
+        "tags" => Some("startsWith(github.ref, 'refs/tags/')".to_string()),
+        "merge_requests" => event("pull_request"),
+        "schedules" => event("schedule"),
+        "web" => event("workflow_dispatch"),
+        "pushes" => event("push"),
+        "api" | "triggers" | "pipelines" | "external" | "chat" | "external_pull_requests" => None,
+        _ if name.starts_with('/') => None,
+        _ => Some(format!("github.ref_name == '{}'", name)),
+    }
+}
+
+/// A GitLab rule condition as a GitHub expression, when it only compares
+/// variables GitHub has something for
+fn github_expression(condition: &str) -> Option<String> {
+    if condition.contains("=~") || condition.contains("!~") {
+        return None;
+    }
+    let context = |variable: &str| match PREDEFINED_VARIABLES
+        .iter()
+        .find(|(_, name)| *name == variable)
+    {
+        Some((context, _)) => Some(context.to_string()),
+        None if variable.starts_with("CI_") || variable.starts_with("GITLAB_") => None,
+        None => Some(format!("vars.{}", variable)),
+    };
+
+    let mut failed = false;
+    let converted = GITLAB_COMPARISON.replace_all(condition, |caps: &Captures| {
+        let (variable, operator) = (&caps[1], &caps[2]);
+        let Some(left) = context(variable) else {
+            failed = true;
+            return String::new();
+        };
+        let right = match (caps.get(3).or(caps.get(4)), caps.get(5)) {
+            (Some(value), _) if variable == "CI_PIPELINE_SOURCE" => {
+                let source = value.as_str();
+                let event = EVENT_SOURCES
+                    .iter()
+                    .find(|(_, name)| *name == source)
+                    .map_or(source, |(event, _)| event);
+                format!("'{}'", event)
+            }
+            (Some(value), _) => format!("'{}'", value.as_str()),
+            (None, Some(other)) => match context(other.as_str()) {
+                Some(other) => other,
+                None => {
+                    failed = true;
+                    return String::new();
+                }
+            },
+            (None, None) => "''".to_string(),
+        };
+        format!("{} {} {}", left, operator, right)
+    });
+    // A variable on its own is true when it is set and not empty
+    let converted = GITLAB_VARIABLE.replace_all(&converted, |caps: &Captures| {
+        let variable = caps.get(1).or(caps.get(2)).map_or("", |m| m.as_str());
+        match variable {
+            "CI_COMMIT_TAG" => "startsWith(github.ref, 'refs/tags/')".to_string(),
+            "CI_COMMIT_BRANCH" => "startsWith(github.ref, 'refs/heads/')".to_string(),
+            "CI_MERGE_REQUEST_ID" | "CI_MERGE_REQUEST_IID" => {
+                "github.event_name == 'pull_request'".to_string()
+            }
+            _ => match context(variable) {
+                Some(context) => format!("{} != ''", context),
+                None => {
+                    failed = true;
+                    String::new()
+                }
+            },
+        }
+    });
+    (!failed && !converted.contains('"')).then(|| converted.into_owned())
+}
+
+/// A GitLab duration like `1h 30m` or `3 days` in minutes, rounded up
+fn duration_minutes(duration: &str) -> Option<u64> {
+    let mut seconds = 0;
+    let mut rest = duration.trim();
+    if rest.is_empty() {
+        return None;
+    }
+    while !rest.is_empty() {
+        let caps = DURATION_PART.captures(rest)?;
+        let part = caps.get(0)?;
+        if part.start() != 0 {
+            return None;
+        }
+        let amount: u64 = caps[1].parse().ok()?;
+        let unit = caps[2].to_lowercase();
+        let factor = match unit.as_str() {
+            "" | "s" | "sec" | "secs" | "second" | "seconds" => 1,
+            "m" | "min" | "mins" | "minute" | "minutes" => 60,
+            "h" | "hr" | "hrs" | "hour" | "hours" => 3600,
+            "d" | "day" | "days" => 86400,
+            "w" | "wk" | "week" | "weeks" => 7 * 86400,
+            "mo" | "month" | "months" => 30 * 86400,
+            _ => return None,
+        };
+        seconds += amount * factor;
+        rest = rest[part.end()..].trim_start_matches([' ', ',']);
+        rest = rest.strip_prefix("and ").unwrap_or(rest);
+    }
+    Some(seconds.div_ceil(60))
+}
+
+/// `on:` for a pipeline: the events its `workflow: rules` run it for, or
+/// pushes and manual runs like any GitLab pipeline
+fn github_triggers(workflow: Option<&gitlab::Workflow>, notes: &mut Vec<String>) -> Value {
+    let mut events: Vec<&str> = Vec::new();
+    if let Some(workflow) = workflow {
+        for rule in &workflow.rules {
+            if rule.when.as_deref() == Some("never") {
+                continue;
+            }
+            let Some(condition) = &rule.if_ else {
+                continue;
+            };
+            for (event, source) in EVENT_SOURCES {
+                let mentioned = condition.contains(&format!("\"{}\"", source))
+                    || condition.contains(&format!("'{}'", source));
+                if mentioned && !events.contains(event) && *event != "pull_request_target" {
+                    events.push(event);
+                }
+            }
+        }
+        notes.push(
+            "`workflow: rules` became the events they mention; conditions besides the pipeline source were left out"
+                .to_string(),
+        );
+    }
+    if events.is_empty() {
+        events = vec!["push", "workflow_dispatch"];
+    }
+    if !events.contains(&"schedule") {
+        return strings(&events.iter().map(|e| e.to_string()).collect::<Vec<_>>());
+    }
+
+    notes.push(
+        "GitLab keeps pipeline schedules in the project settings, so the `schedule` cron is a placeholder"
+            .to_string(),
+    );
+    let mut on = Mapping::new();
+    for event in events.iter().filter(|event| **event != "schedule") {
+        set(&mut on, event, Value::Null);
+    }
+    let mut schedule = Mapping::new();
+    set(&mut schedule, "cron", "0 0 * * *");
+    set(
+        &mut on,
+        "schedule",
+        Value::Sequence(vec![Value::Mapping(schedule)]),
+    );
+    Value::Mapping(on)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_between_workflows_and_pipelines() {
+        let workflow: github::Workflow = serde_yaml::from_str(
+            r#"
+name: CI
+on:
+  push:
+    branches: [main, 'release/*']
+  schedule:
+    - cron: '0 3 * * *'
+env:
+  CARGO_TERM_COLOR: always
+jobs:
+  build:
+    runs-on: ubuntu-22.04
+    strategy:
+      matrix:
+        rust: [stable, beta]
+    steps:
+      - uses: actions/checkout@v4
+      - uses: actions/cache@v4
+        with:
+          key: cargo-${{ matrix.rust }}
+          path: target
+      - run: cargo build --release --toolchain ${{ matrix.rust }}
+        env:
+          TOKEN: ${{ secrets.DEPLOY_TOKEN }}
+      - uses: actions/upload-artifact@v4
+        with:
+          path: target/release/app
+          retention-days: 3
+  deploy:
+    needs: build
+    if: github.ref == 'refs/heads/main'
+    runs-on: ubuntu-latest
+    steps:
+      - uses: some/deploy-action@v1
+      - run: ./deploy.sh ${{ github.event.head_commit.id }}
+"#,
+        )
+        .unwrap();
+        let conversion = workflow_to_pipeline(&workflow);
+        let pipeline: gitlab::Pipeline =
+            serde_yaml::from_str(&conversion.to_yaml().unwrap()).unwrap();
+
+        let build = &pipeline.jobs["build"];
+        assert_eq!(build.image.as_ref().unwrap().name(), "ubuntu:22.04");
+        assert_eq!(
+            build.script.as_deref().unwrap(),
+            ["cargo build --release --toolchain $rust"]
+        );
+        assert_eq!(build.variables.as_ref().unwrap()["TOKEN"], "$DEPLOY_TOKEN");
+        assert_eq!(
+            build.cache.as_ref().unwrap().key.as_deref(),
+            Some("cargo-$rust")
+        );
+        let artifacts = build.artifacts.as_ref().unwrap();
+        assert_eq!(artifacts.paths.as_deref().unwrap(), ["target/release/app"]);
+        assert_eq!(artifacts.expire_in.as_deref(), Some("3 days"));
+        assert!(
+            matches!(&build.parallel, Some(Parallel::Matrix { matrix }) if matrix[0]["rust"].values() == ["stable", "beta"])
+        );
+
+        let deploy = &pipeline.jobs["deploy"];
+        assert_eq!(deploy.needs.as_ref().unwrap()[0].job(), "build");
+        assert_eq!(
+            deploy.rules.as_ref().unwrap()[0].if_.as_deref(),
+            Some("$CI_COMMIT_BRANCH == \"main\"")
+        );
+        let rules: Vec<_> = pipeline
+            .workflow
+            .as_ref()
+            .unwrap()
+            .rules
+            .iter()
+            .map(|r| r.if_.clone().unwrap())
+            .collect();
+        assert_eq!(
+            rules,
+            [
+                "$CI_PIPELINE_SOURCE == \"push\" && $CI_COMMIT_BRANCH == \"main\"",
+                "$CI_PIPELINE_SOURCE == \"push\" && $CI_COMMIT_BRANCH =~ /^release\\/[^\\/]*$/",
+                "$CI_PIPELINE_SOURCE == \"schedule\"",
+            ]
+        );
+        let notes = conversion.notes.join("\n");
+        assert!(notes.contains("some/deploy-action@v1"));
+        assert!(notes.contains("github.event.head_commit.id"));
+        assert!(notes.contains("0 3 * * *"));
+        assert!(notes.contains("DEPLOY_TOKEN"));
+
+        let pipeline: Value = serde_yaml::from_str(
+            r#"
+stages: [build, test, deploy]
+variables:
+  IMAGE: registry.example.com/app:$CI_COMMIT_SHA
+build:
+  stage: build
+  image: rust:1.80
+  cache:
+    key: $CI_COMMIT_REF_SLUG
+    paths: [target]
+  script:
+    - cargo build
+  artifacts:
+    paths: [dist]
+    expire_in: 1 week
+test:
+  stage: test
+  parallel:
+    matrix:
+      - SUITE: [unit, integration]
+  script:
+    - make test-$SUITE
+  retry: 2
+deploy:prod:
+  stage: deploy
+  script:
+    - ./deploy.sh $CI_COMMIT_SHORT_SHA
+  rules:
+    - if: $CI_COMMIT_BRANCH == "main"
+  interruptible: true
+"#,
+        )
+        .unwrap();
+        let conversion = pipeline_to_workflow(&pipeline).unwrap();
+        let workflow: github::Workflow =
+            serde_yaml::from_str(&conversion.to_yaml().unwrap()).unwrap();
+
+        assert_eq!(workflow.on.events(), ["push", "workflow_dispatch"]);
+        assert_eq!(
+            workflow.env["IMAGE"],
+            "registry.example.com/app:${{ github.sha }}"
+        );
+        let build = &workflow.jobs["build"];
+        assert_eq!(build.container.as_ref().unwrap().image(), "rust:1.80");
+        let uses: Vec<_> = build
+            .steps
+            .iter()
+            .filter_map(|s| s.uses.as_deref())
+            .collect();
+        assert_eq!(
+            uses,
+            [
+                "actions/checkout@v4",
+                "actions/cache@v4",
+                "actions/upload-artifact@v4"
+            ]
+        );
+        assert_eq!(build.steps[1].with["key"], "${{ github.ref_name }}");
+        assert_eq!(build.steps[3].with["retention-days"], "7");
+
+        let test = &workflow.jobs["test"];
+        assert_eq!(test.needs(), ["build"]);
+        assert_eq!(test.env["SUITE"], "${{ matrix.SUITE }}");
+        assert_eq!(test.steps[1].with["path"], "dist");
+        assert_eq!(test.steps[2].run.as_deref(), Some("make test-$SUITE"));
+
+        let deploy = &workflow.jobs["deploy-prod"];
+        assert_eq!(deploy.name.as_deref(), Some("deploy:prod"));
+        assert_eq!(deploy.needs(), ["test"]);
+        assert_eq!(
+            deploy.if_condition.as_deref(),
+            Some("github.ref_name == 'main'")
+        );
+        let notes = conversion.notes.join("\n");
+        assert!(notes.contains("retried"));
+        assert!(notes.contains("`interruptible`"));
+        assert!(notes.contains("$CI_COMMIT_SHORT_SHA"));
+
+        assert_eq!(duration_minutes("1h 30m"), Some(90));
+        assert_eq!(duration_minutes("3600"), Some(60));
+        assert_eq!(duration_minutes("soon"), None);
+        assert_eq!(github_job_id("build:linux 1/2"), "build-linux-1-2");
+        assert_eq!(
+            github_expression("$CI_PIPELINE_SOURCE == \"merge_request_event\" && $DEPLOY")
+                .as_deref(),
+            Some("github.event_name == 'pull_request' && vars.DEPLOY != ''")
+        );
+    }
+
+    /// A workflow as a pipeline, and the notes on it
+    fn to_pipeline(workflow: &str) -> (Value, String) {
+        let workflow: github::Workflow = serde_yaml::from_str(workflow).unwrap();
+        let conversion = workflow_to_pipeline(&workflow);
+        (conversion.document, conversion.notes.join("\n"))
+    }
+
+    /// A pipeline as a workflow, and the notes on it
+    fn to_workflow(pipeline: &str) -> (Value, String) {
+        let pipeline: Value = serde_yaml::from_str(pipeline).unwrap();
+        let conversion = pipeline_to_workflow(&pipeline).unwrap();
+        (conversion.document, conversion.notes.join("\n"))
+    }
+
+    fn yaml(text: &str) -> Value {
+        serde_yaml::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn converts_matrices() {
+        let (pipeline, notes) = to_pipeline(
+            r#"
+on: push
+jobs:
+  test:
+    runs-on: ubuntu-latest
+    strategy:
+      max-parallel: 2
+      matrix:
+        os: [ubuntu, debian]
+        node: [18, 20]
+        include:
+          - os: alpine
+            node: 22
+        exclude:
+          - os: debian
+            node: 18
+    steps:
+      - run: make test
+  dynamic:
+    runs-on: ubuntu-latest
+    strategy:
+      matrix: ${{ fromJSON(vars.MATRIX) }}
+    steps:
+      - run: make
+"#,
+        );
+        assert_eq!(
+            pipeline["test"]["parallel"],
+            yaml(
+                r#"
+matrix:
+  - os: [ubuntu, debian]
+    node: ["18", "20"]
+  - os: alpine
+    node: "22"
+"#
+            )
+        );
+        assert!(pipeline["dynamic"].get("parallel").is_none());
+        assert!(notes.contains("job 'test''s matrix `exclude` has no GitLab CI equivalent"));
+        assert!(notes.contains("job 'test' sets `max-parallel`"));
+        assert!(notes.contains("job 'dynamic' builds its matrix with an expression"));
+
+        let (workflow, _) = to_workflow(
+            r#"
+single:
+  parallel:
+    matrix:
+      - RUBY: ["3.2", "3.3"]
+        DB: [postgres]
+  script: [rake]
+multiple:
+  parallel:
+    matrix:
+      - PROVIDER: aws
+        REGION: [us-east-1, eu-west-1]
+      - PROVIDER: gcp
+  script: [./deploy.sh]
+sharded:
+  parallel: 3
+  script: [rspec]
+"#,
+        );
+        let jobs = &workflow["jobs"];
+        assert_eq!(
+            jobs["single"]["strategy"],
+            yaml(
+                r#"
+fail-fast: false
+matrix:
+  RUBY: ["3.2", "3.3"]
+  DB: [postgres]
+"#
+            )
+        );
+        assert_eq!(jobs["single"]["env"]["RUBY"], "${{ matrix.RUBY }}");
+        assert_eq!(
+            jobs["multiple"]["strategy"]["matrix"],
+            yaml(
+                r#"
+include:
+  - PROVIDER: aws
+    REGION: us-east-1
+  - PROVIDER: aws
+    REGION: eu-west-1
+  - PROVIDER: gcp
+"#
+            )
+        );
+        assert_eq!(jobs["multiple"]["env"]["REGION"], "${{ matrix.REGION }}");
+        assert_eq!(
+            jobs["sharded"]["strategy"]["matrix"],
+            yaml("index: [1, 2, 3]")
+        );
+        assert_eq!(
+            jobs["sharded"]["env"]["CI_NODE_INDEX"],
+            "${{ matrix.index }}"
+        );
+        assert_eq!(jobs["sharded"]["env"]["CI_NODE_TOTAL"], "3");
+    }
+
+    #[test]
+    fn converts_needs() {
+        let (pipeline, notes) = to_pipeline(
+            r#"
+on: push
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - run: make
+  image:
+    runs-on: ubuntu-latest
+    steps:
+      - run: docker build .
+  release:
+    uses: ./.github/workflows/release.yml
+  deploy:
+    needs: [build, image, release]
+    runs-on: ubuntu-latest
+    steps:
+      - run: ./deploy.sh
+"#,
+        );
+        assert_eq!(pipeline["deploy"]["needs"], yaml("[build, image-job]"));
+        assert!(pipeline.get("image-job").is_some());
+        assert!(pipeline.get("release").is_none());
+        assert!(notes.contains("job 'release' calls the reusable workflow"));
+
+        let (workflow, notes) = to_workflow(
+            r#"
+stages: [build, test, deploy]
+compile:
+  stage: build
+  script: [make]
+  artifacts:
+    paths: [bin/app]
+docs:
+  stage: build
+  script: [make docs]
+  artifacts:
+    paths: ["site/*"]
+unit:
+  stage: test
+  script: [make test]
+lint:
+  stage: test
+  needs: []
+  script: [make lint]
+deploy:
+  stage: deploy
+  needs:
+    - job: compile
+      artifacts: false
+    - unit
+  script: [./deploy.sh]
+package:
+  stage: deploy
+  dependencies: [compile]
+  script: [make package]
+"#,
+        );
+        let jobs = &workflow["jobs"];
+        let downloads = |job: &str| -> Vec<Value> {
+            jobs[job]["steps"]
+                .as_sequence()
+                .unwrap()
+                .iter()
+                .filter(|step| step["uses"] == "actions/download-artifact@v4")
+                .map(|step| step["with"].clone())
+                .collect()
+        };
+        assert_eq!(jobs["unit"]["needs"], yaml("[compile, docs]"));
+        assert_eq!(
+            downloads("unit"),
+            [yaml("{name: compile, path: bin/app}"), yaml("{name: docs}")]
+        );
+        assert!(notes.contains("job 'unit' gets the artifacts of 'docs' in the workspace itself"));
+        assert!(jobs["lint"].get("needs").is_none());
+        assert!(downloads("lint").is_empty());
+        assert_eq!(jobs["deploy"]["needs"], yaml("[compile, unit]"));
+        assert!(downloads("deploy").is_empty());
+        assert_eq!(jobs["package"]["needs"], yaml("[unit, lint]"));
+        assert_eq!(
+            downloads("package"),
+            [yaml("{name: compile, path: bin/app}")]
+        );
+    }
+
+    #[test]
+    fn converts_conditions() {
+        let (pipeline, notes) = to_pipeline(
+            r#"
+on: push
+jobs:
+  always:
+    if: always()
+    runs-on: ubuntu-latest
+    steps: [{run: make}]
+  failure:
+    if: ${{ failure() }}
+    runs-on: ubuntu-latest
+    steps: [{run: make}]
+  success:
+    if: success()
+    runs-on: ubuntu-latest
+    steps: [{run: make}]
+  pr:
+    if: github.event_name == 'pull_request'
+    runs-on: ubuntu-latest
+    steps: [{run: make}]
+  tag:
+    if: github.ref == 'refs/tags/v1'
+    runs-on: ubuntu-latest
+    steps: [{run: make}]
+  branch:
+    if: startsWith(github.ref, 'refs/heads/') && github.ref_name != 'main'
+    runs-on: ubuntu-latest
+    steps: [{run: make}]
+  message:
+    if: contains(github.event.head_commit.message, '[deploy]')
+    runs-on: ubuntu-latest
+    steps: [{run: make}]
+"#,
+        );
+        assert_eq!(pipeline["always"]["rules"], yaml("[{when: always}]"));
+        assert_eq!(pipeline["failure"]["rules"], yaml("[{when: on_failure}]"));
+        assert!(pipeline["success"].get("rules").is_none());
+        assert_eq!(
+            pipeline["pr"]["rules"],
+            yaml(r#"[{if: '$CI_PIPELINE_SOURCE == "merge_request_event"'}]"#)
+        );
+        assert_eq!(
+            pipeline["tag"]["rules"],
+            yaml(r#"[{if: '$CI_COMMIT_TAG == "v1"'}]"#)
+        );
+        assert_eq!(
+            pipeline["branch"]["rules"],
+            yaml(r#"[{if: '$CI_COMMIT_BRANCH && $CI_COMMIT_REF_NAME != "main"'}]"#)
+        );
+        assert!(pipeline["message"].get("rules").is_none());
+        assert!(notes.contains(
+            "job 'message' runs `if: contains(github.event.head_commit.message, '[deploy]')`, which couldn't be made a GitLab rule"
+        ));
+
+        let (workflow, notes) = to_workflow(
+            r#"
+release:
+  script: [make release]
+  rules:
+    - if: $CI_COMMIT_BRANCH == "wip"
+      when: never
+    - if: $CI_PIPELINE_SOURCE == "merge_request_event"
+      when: manual
+    - if: $CI_COMMIT_TAG
+nightly:
+  script: [make]
+  only: [schedules]
+  when: always
+cleanup:
+  script: [make clean]
+  when: on_failure
+  except: [main]
+feature:
+  script: [make]
+  rules:
+    - if: $CI_COMMIT_BRANCH =~ /^feature/
+"#,
+        );
+        let jobs = &workflow["jobs"];
+        assert_eq!(
+            jobs["release"]["if"],
+            "!(github.ref_name == 'wip') && ((github.event_name == 'pull_request') || (startsWith(github.ref, 'refs/tags/')))"
+        );
+        assert!(notes.contains("job 'release' has a `when: manual` rule"));
+        assert_eq!(
+            jobs["nightly"]["if"],
+            "always() && (github.event_name == 'schedule')"
+        );
+        assert_eq!(
+            jobs["cleanup"]["if"],
+            "failure() && (!(github.ref_name == 'main'))"
+        );
+        assert!(jobs["feature"].get("if").is_none());
+        assert!(notes.contains(
+            "job 'feature' has the rule `if: $CI_COMMIT_BRANCH =~ /^feature/`, which couldn't be made a GitHub condition"
+        ));
+    }
+
+    #[test]
+    fn converts_artifacts() {
+        let (pipeline, notes) = to_pipeline(
+            r#"
+on: push
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - run: make
+      - uses: actions/upload-artifact@v4
+        if: always()
+        with:
+          name: binaries
+          path: |
+            bin/
+            lib/*.so
+          retention-days: 5
+          if-no-files-found: error
+      - uses: actions/upload-artifact@v4
+        with:
+          name: logs
+          path: logs/
+"#,
+        );
+        assert_eq!(
+            pipeline["build"]["artifacts"],
+            yaml(
+                r#"
+paths: [bin/, lib/*.so, logs/]
+name: binaries
+expire_in: 5 days
+when: always
+"#
+            )
+        );
+        assert!(notes.contains(
+            "step 'actions/upload-artifact@v4' of job 'build' uploads another artifact; GitLab keeps one archive per job"
+        ));
+        assert!(notes.contains(
+            "step 'actions/upload-artifact@v4' of job 'build' sets `if-no-files-found`, which GitLab artifacts don't have"
+        ));
+
+        let (workflow, notes) = to_workflow(
+            r#"
+build:
+  script: [make]
+  artifacts:
+    name: $CI_JOB_NAME-bin
+    paths: [bin/]
+    expire_in: 2 days
+    when: on_failure
+    reports:
+      junit: report.xml
+forever:
+  script: [make]
+  artifacts:
+    paths: [out/]
+    expire_in: never
+"#,
+        );
+        let upload = |job: &str| workflow["jobs"][job]["steps"][2].clone();
+        assert_eq!(
+            upload("build"),
+            yaml(
+                r#"
+uses: actions/upload-artifact@v4
+if: failure()
+with:
+  name: build
+  path: bin/
+  retention-days: "2"
+"#
+            )
+        );
+        assert!(notes.contains(
+            "job 'build''s artifacts are named after the job rather than `$CI_JOB_NAME-bin`"
+        ));
+        assert!(
+            notes.contains("job 'build''s artifacts `reports` has no GitHub Actions equivalent")
+        );
+        assert_eq!(
+            upload("forever")["with"],
+            yaml("{name: forever, path: out/}")
+        );
+        assert!(notes.contains("job 'forever''s artifacts expire `never`, which GitHub can't"));
+    }
+
+    #[test]
+    fn converts_caches() {
+        let (pipeline, _) = to_pipeline(
+            r#"
+on: push
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/cache/restore@v4
+        with:
+          key: deps-${{ github.ref_name }}
+          path: ~/.cargo
+          restore-keys: |
+            deps-
+      - run: cargo build
+      - uses: actions/cache/save@v4
+        with:
+          key: target-${{ github.sha }}
+          path: target
+"#,
+        );
+        assert_eq!(
+            pipeline["build"]["cache"],
+            yaml(
+                r#"
+- key: deps-$CI_COMMIT_REF_NAME
+  paths: [~/.cargo]
+  fallback_keys: [deps-]
+  policy: pull
+- key: target-$CI_COMMIT_SHA
+  paths: [target]
+  policy: push
+"#
+            )
+        );
+
+        let (workflow, notes) = to_workflow(
+            r#"
+restore:
+  script: [make]
+  cache:
+    key: deps
+    paths: [vendor/]
+    policy: pull
+save:
+  script: [make]
+  cache:
+    key: $CI_COMMIT_REF_SLUG
+    paths: [vendor/, node_modules/]
+    policy: push
+    when: always
+"#,
+        );
+        let steps = |job: &str| {
+            workflow["jobs"][job]["steps"]
+                .as_sequence()
+                .unwrap()
+                .clone()
+        };
+        assert_eq!(
+            steps("restore")[1],
+            yaml("{uses: actions/cache/restore@v4, with: {key: deps, path: vendor/}}")
+        );
+        assert_eq!(steps("restore")[2]["name"], "Script");
+        let save = steps("save");
+        assert_eq!(save[1]["name"], "Script");
+        assert_eq!(
+            save[2],
+            yaml(
+                r#"
+uses: actions/cache/save@v4
+with:
+  key: ${{ github.ref_name }}
+  path: "vendor/\nnode_modules/"
+"#
+            )
+        );
+        assert!(notes.contains(
+            "job 'save''s cache is saved `when: always`; GitHub saves caches after successful jobs"
+        ));
+    }
+
+    #[test]
+    fn converts_triggers() {
+        let (pipeline, notes) = to_pipeline(
+            r#"
+on:
+  push:
+    branches: [main, '!legacy']
+    tags: ['v*']
+    paths: [src/**]
+  pull_request:
+    types: [opened]
+    branches-ignore: [wip]
+    paths-ignore: [docs/**]
+  workflow_dispatch:
+    inputs:
+      level:
+        description: Log level
+  release:
+    types: [published]
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - run: make
+"#,
+        );
+        assert_eq!(
+            pipeline["workflow"]["rules"],
+            yaml(
+                r#"
+- if: $CI_PIPELINE_SOURCE == "push" && $CI_COMMIT_BRANCH == "legacy"
+  when: never
+- if: $CI_PIPELINE_SOURCE == "push" && $CI_COMMIT_BRANCH == "main"
+  changes: [src/**]
+- if: $CI_PIPELINE_SOURCE == "push" && $CI_COMMIT_TAG =~ /^v[^\/]*$/
+  changes: [src/**]
+- if: $CI_PIPELINE_SOURCE == "merge_request_event" && $CI_MERGE_REQUEST_TARGET_BRANCH_NAME == "wip"
+  when: never
+- if: $CI_PIPELINE_SOURCE == "merge_request_event"
+- if: $CI_PIPELINE_SOURCE == "web"
+"#
+            )
+        );
+        assert!(notes.contains("the `pull_request` trigger's `types` have no GitLab CI equivalent"));
+        assert!(notes
+            .contains("the `pull_request` trigger's `paths-ignore` has no GitLab CI equivalent"));
+        assert!(notes.contains("the `workflow_dispatch` trigger's inputs are CI/CD variables"));
+        assert!(notes.contains("the `release` trigger has no GitLab CI equivalent"));
+
+        let (workflow, notes) = to_workflow(
+            r#"
+workflow:
+  rules:
+    - if: $CI_PIPELINE_SOURCE == "merge_request_event"
+    - if: $CI_PIPELINE_SOURCE == 'schedule'
+    - if: $CI_PIPELINE_SOURCE == "web"
+      when: never
+build:
+  script: [make]
+"#,
+        );
+        assert_eq!(
+            workflow["on"],
+            yaml(
+                r#"
+pull_request:
+schedule:
+  - cron: 0 0 * * *
+"#
+            )
+        );
+        assert!(notes.contains("`workflow: rules` became the events they mention"));
+        assert!(notes.contains("the `schedule` cron is a placeholder"));
+    }
+
+    #[test]
+    fn notes_what_is_not_converted() {
+        let (pipeline, notes) = to_pipeline(
+            r#"
+on: push
+permissions:
+  contents: read
+concurrency: ci
+jobs:
+  build:
+    runs-on: macos-14
+    outputs:
+      version: ${{ steps.version.outputs.value }}
+    services:
+      db:
+        image: postgres:16
+        credentials:
+          username: ci
+          password: ${{ secrets.DB_PASSWORD }}
+    steps:
+      - uses: actions/setup-node@v4
+      - id: version
+        run: echo "value=1" >> "$GITHUB_OUTPUT"
+      - run: ./test.ps1
+        shell: pwsh
+        continue-on-error: true
+        timeout-minutes: 5
+"#,
+        );
+        assert_eq!(pipeline["build"]["tags"], yaml("[macos-14]"));
+        for note in [
+            "`permissions` has no GitLab CI equivalent",
+            "`concurrency` has no GitLab CI equivalent",
+            "job 'build' runs on macos-14, which needs a GitLab runner tagged for it",
+            "job 'build' has `outputs`",
+            "service 'db' of job 'build' has `credentials`",
+            "step 'actions/setup-node@v4' of job 'build' uses actions/setup-node@v4; use an image that has the tool instead",
+            "step 'version' of job 'build' writes to $GITHUB_OUTPUT, which GitLab doesn't read",
+            "step '#3' of job 'build' runs in pwsh",
+            "step '#3' of job 'build' has `continue-on-error`",
+            "step '#3' of job 'build' has `timeout-minutes`",
+        ] {
+            assert!(notes.contains(note), "no note `{}` in:\n{}", note, notes);
+        }
+
+        let (_, notes) = to_workflow(
+            r#"
+build:
+  image:
+    name: alpine
+    entrypoint: [""]
+  tags: [docker]
+  coverage: '/Total: (\d+)%/'
+  retry: 2
+  timeout: whenever
+  when: delayed
+  only:
+    refs: [main]
+    variables: [$RELEASE]
+  script:
+    - echo $CI_RUNNER_ID
+test:
+  services: [redis:7]
+  script: [make test]
+"#,
+        );
+        for note in [
+            "job 'build''s `coverage` has no GitHub Actions equivalent, so it was left out",
+            "job 'build' ran on runners tagged docker; it now runs on ubuntu-latest",
+            "job 'build''s image `entrypoint` has no GitHub Actions equivalent",
+            "job 'build' is retried on failure",
+            "job 'build''s timeout `whenever` couldn't be read",
+            "job 'build' is `when: delayed`; it now runs without waiting",
+            "job 'build''s `only`/`except` `variables` or `changes` were left out",
+            "$CI_RUNNER_ID has no GitHub Actions equivalent",
+            "job 'test' doesn't run in a container, so its services need `ports`",
+        ] {
+            assert!(notes.contains(note), "no note `{}` in:\n{}", note, notes);
+        }
+    }
+}
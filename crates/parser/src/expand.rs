@@ -0,0 +1,499 @@
+use crate::gitlab::{self, GLOBAL_KEYWORDS};
+use matrix::MatrixConfig;
+use serde_yaml::{Mapping, Value};
+use std::fs;
+use std::path::Path;
+
+/// Default job timeout applied by GitHub Actions (in minutes)
+const DEFAULT_TIMEOUT_MINUTES: u64 = 360;
+
+/// Stages GitLab uses when a pipeline doesn't declare its own
+const DEFAULT_GITLAB_STAGES: &[&str] = &[".pre", "build", "test", "deploy", ".post"];
+
+/// Keys from a GitLab `default:` section that are inherited by every job
+const GITLAB_DEFAULT_KEYS: &[&str] = &[
+    "image",
+    "services",
+    "before_script",
+    "after_script",
+    "cache",
+    "artifacts",
+    "retry",
+    "timeout",
+    "interruptible",
+    "tags",
+    "hooks",
+];
+
+/// Options controlling how much normalization `expand` performs
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExpandOptions {
+    /// Emit one job per matrix combination instead of the `strategy.matrix` block
+    pub expand_matrix: bool,
+}
+
+/// The name GitHub gives a workflow without one: its path in the repository,
+/// like `.github/workflows/ci.yml`, or the path as given outside of one
+fn default_workflow_name(path: &Path) -> String {
+    let components: Vec<_> = path.components().collect();
+    let start = components
+        .windows(2)
+        .position(|pair| pair[0].as_os_str() == ".github" && pair[1].as_os_str() == "workflows")
+        .unwrap_or(0);
+    components[start..]
+        .iter()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Load a GitHub workflow and return it fully expanded: anchors and merge
+/// keys resolved and implicit defaults made explicit.
+pub fn expand_workflow(path: &Path, options: ExpandOptions) -> Result<Value, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read workflow file: {}", e))?;
+    let mut workflow = load_yaml(&content)?;
+
+    let Some(root) = workflow.as_mapping_mut() else {
+        return Err("Workflow file must contain a YAML mapping".to_string());
+    };
+
+    if !root.contains_key("name") {
+        root.insert(
+            Value::from("name"),
+            Value::from(default_workflow_name(path)),
+        );
+    }
+
+    // Some YAML 1.1 loaders turn a bare `on` key into `true`
+    if let Some(on) = root.remove(Value::Bool(true)) {
+        root.insert(Value::from("on"), on);
+    }
+    if let Some(on) = root.get_mut("on") {
+        *on = normalize_triggers(on);
+    }
+
+    if let Some(Value::Mapping(jobs)) = root.get_mut("jobs") {
+        let mut expanded_jobs = Mapping::new();
+        for (job_name, job) in std::mem::take(jobs) {
+            let mut job = job;
+            normalize_github_job(&mut job);
+
+            let name = job_name.as_str().unwrap_or_default().to_string();
+            if options.expand_matrix {
+                for (expanded_name, expanded_job) in expand_job_matrix(&name, &job)? {
+                    expanded_jobs.insert(Value::from(expanded_name), expanded_job);
+                }
+            } else {
+                expanded_jobs.insert(job_name, job);
+            }
+        }
+        *jobs = expanded_jobs;
+    }
+
+    Ok(workflow)
+}
+
+/// Load a GitLab pipeline and return it fully expanded: anchors resolved,
+/// includes and `extends` merged, and `default:` applied to each job.
+pub fn expand_pipeline(path: &Path, options: ExpandOptions) -> Result<Value, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read pipeline file: {}", e))?;
+    let mut pipeline = load_yaml(&content)?;
+
+    if !pipeline.is_mapping() {
+        return Err("Pipeline file must contain a YAML mapping".to_string());
+    }
+
+    let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    gitlab::resolve_includes(&mut pipeline, base_dir).map_err(|e| e.to_string())?;
+    gitlab::resolve_extends(&mut pipeline).map_err(|e| e.to_string())?;
+
+    let Some(root) = pipeline.as_mapping_mut() else {
+        return Ok(pipeline);
+    };
+
+    // Top-level image/scripts are the legacy spelling of `default:`
+    let mut defaults = Mapping::new();
+    for key in [
+        "image",
+        "services",
+        "cache",
+        "before_script",
+        "after_script",
+    ] {
+        if let Some(value) = root.remove(key) {
+            defaults.insert(Value::from(key), value);
+        }
+    }
+    if let Some(Value::Mapping(default)) = root.remove("default") {
+        for (key, value) in default {
+            defaults.insert(key, value);
+        }
+    }
+
+    if !root.contains_key("stages") {
+        let stages = DEFAULT_GITLAB_STAGES
+            .iter()
+            .map(|s| Value::from(*s))
+            .collect();
+        root.insert(Value::from("stages"), Value::Sequence(stages));
+    }
+
+    let mut expanded = Mapping::new();
+    for (key, value) in std::mem::take(root) {
+        let Some(name) = key.as_str() else {
+            continue;
+        };
+
+        if GLOBAL_KEYWORDS.contains(&name) {
+            expanded.insert(key, value);
+            continue;
+        }
+
+        // Hidden jobs only exist to be extended
+        if name.starts_with('.') || !value.is_mapping() {
+            continue;
+        }
+
+        let mut job = value;
+        apply_gitlab_job_defaults(&mut job, &defaults);
+
+        if options.expand_matrix {
+            for (expanded_name, expanded_job) in expand_parallel_matrix(name, &job) {
+                expanded.insert(Value::from(expanded_name), expanded_job);
+            }
+        } else {
+            expanded.insert(key, job);
+        }
+    }
+    *root = expanded;
+
+    Ok(pipeline)
+}
+
+fn load_yaml(content: &str) -> Result<Value, String> {
+    let mut value: Value =
+        serde_yaml::from_str(content).map_err(|e| format!("Failed to parse YAML: {}", e))?;
+    value
+        .apply_merge()
+        .map_err(|e| format!("Failed to resolve YAML merge keys: {}", e))?;
+    Ok(value)
+}
+
+/// Normalize `on:` to its mapping form, e.g. `on: push` -> `on: {push: null}`
+fn normalize_triggers(on: &Value) -> Value {
+    match on {
+        Value::String(event) => {
+            let mut map = Mapping::new();
+            map.insert(Value::from(event.as_str()), Value::Null);
+            Value::Mapping(map)
+        }
+        Value::Sequence(events) => {
+            let mut map = Mapping::new();
+            for event in events {
+                map.insert(event.clone(), Value::Null);
+            }
+            Value::Mapping(map)
+        }
+        other => other.clone(),
+    }
+}
+
+fn normalize_github_job(job: &mut Value) {
+    let Some(job) = job.as_mapping_mut() else {
+        return;
+    };
+
+    if let Some(Value::String(need)) = job.get("needs").cloned() {
+        job.insert(
+            Value::from("needs"),
+            Value::Sequence(vec![Value::from(need)]),
+        );
+    }
+
+    // Reusable workflow calls don't accept the settings below
+    if job.contains_key("uses") {
+        return;
+    }
+
+    insert_default(job, "timeout-minutes", Value::from(DEFAULT_TIMEOUT_MINUTES));
+    insert_default(job, "continue-on-error", Value::Bool(false));
+
+    if let Some(Value::Mapping(strategy)) = job.get_mut("strategy") {
+        insert_default(strategy, "fail-fast", Value::Bool(true));
+    }
+
+    if let Some(Value::Sequence(steps)) = job.get_mut("steps") {
+        for step in steps.iter_mut().filter_map(Value::as_mapping_mut) {
+            insert_default(step, "continue-on-error", Value::Bool(false));
+            if step.contains_key("run") {
+                insert_default(step, "shell", Value::from("bash"));
+            }
+        }
+    }
+}
+
+/// Replace a job's matrix with one concrete job per combination
+fn expand_job_matrix(job_name: &str, job: &Value) -> Result<Vec<(String, Value)>, String> {
+    let matrix_value = job
+        .get("strategy")
+        .and_then(|s| s.get("matrix"))
+        .or_else(|| job.get("matrix"));
+
+    let Some(matrix_value) = matrix_value else {
+        return Ok(vec![(job_name.to_string(), job.clone())]);
+    };
+
+    // Matrices built from expressions can only be resolved at runtime
+    if matrix_value.is_string() {
+        return Ok(vec![(job_name.to_string(), job.clone())]);
+    }
+
+    let config: MatrixConfig = serde_yaml::from_value(matrix_value.clone())
+        .map_err(|e| format!("Invalid matrix in job '{}': {}", job_name, e))?;
+    let combinations = matrix::expand_matrix(&config)
+        .map_err(|e| format!("Failed to expand matrix in job '{}': {}", job_name, e))?;
+
+    let mut jobs = Vec::new();
+    for combination in combinations {
+        // Keep declaration order so the output is stable between runs
+        let mut keys: Vec<&String> = config
+            .parameters
+            .keys()
+            .filter(|k| combination.values.contains_key(*k))
+            .collect();
+        let mut extra: Vec<&String> = combination
+            .values
+            .keys()
+            .filter(|k| !config.parameters.contains_key(*k))
+            .collect();
+        extra.sort();
+        keys.extend(extra);
+
+        let mut matrix_values = Mapping::new();
+        for key in &keys {
+            matrix_values.insert(Value::from(key.as_str()), combination.values[*key].clone());
+        }
+
+        let label = keys
+            .iter()
+            .map(|k| value_label(&combination.values[*k]))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut expanded_job = job.clone();
+        if let Some(map) = expanded_job.as_mapping_mut() {
+            map.remove("matrix");
+            if let Some(Value::Mapping(strategy)) = map.get_mut("strategy") {
+                strategy.insert(Value::from("matrix"), Value::Mapping(matrix_values));
+            } else {
+                let mut strategy = Mapping::new();
+                strategy.insert(Value::from("matrix"), Value::Mapping(matrix_values));
+                map.insert(Value::from("strategy"), Value::Mapping(strategy));
+            }
+        }
+
+        jobs.push((format!("{} ({})", job_name, label), expanded_job));
+    }
+
+    Ok(jobs)
+}
+
+fn apply_gitlab_job_defaults(job: &mut Value, defaults: &Mapping) {
+    let Some(job) = job.as_mapping_mut() else {
+        return;
+    };
+
+    // Jobs can opt out of inheriting defaults with `inherit: default: false`
+    let inherit_defaults = !matches!(
+        job.get("inherit").and_then(|i| i.get("default")),
+        Some(Value::Bool(false))
+    );
+
+    if inherit_defaults {
+        for key in GITLAB_DEFAULT_KEYS {
+            if let Some(value) = defaults.get(*key) {
+                insert_default(job, key, value.clone());
+            }
+        }
+    }
+
+    insert_default(job, "stage", Value::from("test"));
+    insert_default(job, "when", Value::from("on_success"));
+    insert_default(job, "allow_failure", Value::Bool(false));
+}
+
+/// Expand `parallel: matrix:` into one job per combination, named the way
+/// GitLab shows them, e.g. `test: [ruby, 3.2]`
+fn expand_parallel_matrix(job_name: &str, job: &Value) -> Vec<(String, Value)> {
+    let Some(Value::Sequence(entries)) = job.get("parallel").and_then(|p| p.get("matrix")) else {
+        return vec![(job_name.to_string(), job.clone())];
+    };
+
+    let mut jobs = Vec::new();
+    for entry in entries.iter().filter_map(Value::as_mapping) {
+        let mut combinations: Vec<Vec<(String, Value)>> = vec![Vec::new()];
+        for (key, value) in entry {
+            let Some(key) = key.as_str() else {
+                continue;
+            };
+            let values = match value {
+                Value::Sequence(values) => values.clone(),
+                other => vec![other.clone()],
+            };
+            combinations = combinations
+                .into_iter()
+                .flat_map(|combo| {
+                    values.iter().map(move |v| {
+                        let mut combo = combo.clone();
+                        combo.push((key.to_string(), v.clone()));
+                        combo
+                    })
+                })
+                .collect();
+        }
+
+        for combo in combinations {
+            let label = combo
+                .iter()
+                .map(|(_, v)| value_label(v))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            let mut expanded_job = job.clone();
+            if let Some(map) = expanded_job.as_mapping_mut() {
+                map.remove("parallel");
+                let variables = map
+                    .entry(Value::from("variables"))
+                    .or_insert_with(|| Value::Mapping(Mapping::new()));
+                if let Some(variables) = variables.as_mapping_mut() {
+                    for (key, value) in &combo {
+                        variables.insert(Value::from(key.as_str()), value.clone());
+                    }
+                }
+            }
+
+            jobs.push((format!("{}: [{}]", job_name, label), expanded_job));
+        }
+    }
+
+    jobs
+}
+
+fn insert_default(map: &mut Mapping, key: &str, value: Value) {
+    if !map.contains_key(key) {
+        map.insert(Value::from(key), value);
+    }
+}
+
+fn value_label(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        Value::Bool(b) => b.to_string(),
+        other => serde_yaml::to_string(other)
+            .map(|s| s.trim().to_string())
+            .unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn write_yaml(content: &str) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        file
+    }
+
+    #[test]
+    fn expands_anchors_and_defaults() {
+        let file = write_yaml(
+            r#"
+name: CI
+on: push
+env_defaults: &env
+  RUST_LOG: debug
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    env: *env
+    strategy:
+      matrix:
+        os: [ubuntu, macos]
+    steps:
+      - run: cargo build
+"#,
+        );
+
+        let options = ExpandOptions {
+            expand_matrix: true,
+        };
+        let workflow = expand_workflow(file.path(), options).unwrap();
+
+        assert!(workflow["on"].get("push").is_some());
+        let jobs = workflow["jobs"].as_mapping().unwrap();
+        assert_eq!(jobs.len(), 2);
+
+        let job = &workflow["jobs"]["build (ubuntu)"];
+        assert_eq!(job["env"]["RUST_LOG"], Value::from("debug"));
+        assert_eq!(job["timeout-minutes"], Value::from(360));
+        assert_eq!(job["strategy"]["matrix"]["os"], Value::from("ubuntu"));
+        assert_eq!(job["steps"][0]["shell"], Value::from("bash"));
+    }
+
+    #[test]
+    fn names_unnamed_workflows_by_their_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let workflows = dir.path().join(".github").join("workflows");
+        std::fs::create_dir_all(&workflows).unwrap();
+        let path = workflows.join("ci.yml");
+        std::fs::write(&path, "on: push\njobs: {}\n").unwrap();
+
+        let workflow = expand_workflow(&path, ExpandOptions::default()).unwrap();
+        assert_eq!(workflow["name"], Value::from(".github/workflows/ci.yml"));
+        assert_eq!(default_workflow_name(Path::new("ci.yml")), "ci.yml");
+    }
+
+    #[test]
+    fn expands_gitlab_extends_and_defaults() {
+        let file = write_yaml(
+            r#"
+default:
+  image: rust:latest
+.base: &base
+  script:
+    - echo base
+  variables:
+    A: "1"
+test:
+  extends: .base
+  variables:
+    B: "2"
+merged:
+  <<: *base
+  stage: build
+"#,
+        );
+
+        let pipeline = expand_pipeline(file.path(), ExpandOptions::default()).unwrap();
+        let root = pipeline.as_mapping().unwrap();
+
+        assert!(!root.contains_key(".base"));
+        assert!(!root.contains_key("default"));
+
+        let test = &pipeline["test"];
+        assert_eq!(test["image"], Value::from("rust:latest"));
+        assert_eq!(test["variables"]["A"], Value::from("1"));
+        assert_eq!(test["variables"]["B"], Value::from("2"));
+        assert_eq!(test["stage"], Value::from("test"));
+        assert!(test.get("extends").is_none());
+
+        assert_eq!(pipeline["merged"]["script"][0], Value::from("echo base"));
+        assert_eq!(pipeline["merged"]["stage"], Value::from("build"));
+    }
+}
@@ -0,0 +1,253 @@
+// `wrkflw fmt`: workflow files rewritten with two-space indentation, keys in
+// the order GitHub's docs use and single-quoted strings, keeping comments
+use crate::roundtrip::{Document, Entry, Node, Value};
+
+const WORKFLOW_KEYS: &[&str] = &[
+    "name",
+    "run-name",
+    "on",
+    "permissions",
+    "env",
+    "defaults",
+    "concurrency",
+    "jobs",
+];
+
+const JOB_KEYS: &[&str] = &[
+    "name",
+    "needs",
+    "if",
+    "runs-on",
+    "environment",
+    "permissions",
+    "concurrency",
+    "timeout-minutes",
+    "continue-on-error",
+    "strategy",
+    "container",
+    "services",
+    "outputs",
+    "env",
+    "defaults",
+    "uses",
+    "with",
+    "secrets",
+    "steps",
+];
+
+const STEP_KEYS: &[&str] = &[
+    "name",
+    "id",
+    "if",
+    "continue-on-error",
+    "timeout-minutes",
+    "uses",
+    "with",
+    "env",
+    "shell",
+    "working-directory",
+    "run",
+];
+
+/// `content` formatted. `order_keys` puts the keys of the workflow, its
+/// jobs and their steps in order, which only makes sense for GitHub
+/// workflows. Formatting that would change what the file means, e.g.
+/// moving an alias above its anchor, is refused.
+pub fn format_workflow(content: &str, order_keys: bool) -> Result<String, String> {
+    let original: serde_yaml::Value =
+        serde_yaml::from_str(content).map_err(|e| format!("Invalid YAML: {}", e))?;
+    // Files end with a newline once formatted, which a block scalar on the
+    // last line takes in
+    let original = match content.ends_with('\n') {
+        true => original,
+        false => serde_yaml::from_str(&format!("{}\n", content)).unwrap_or(original),
+    };
+
+    let mut document = Document::parse(content)?;
+    if order_keys {
+        order_workflow_keys(&mut document.root);
+    }
+    tidy_node(&mut document.root);
+    tidy_trivia(&mut document.trailer, false);
+    while document.trailer.last().is_some_and(String::is_empty) {
+        document.trailer.pop();
+    }
+
+    let formatted = document.render();
+    match serde_yaml::from_str::<serde_yaml::Value>(&formatted) {
+        Ok(value) if value == original => Ok(formatted),
+        _ => {
+            Err("formatting would change what the file means, so it was left as it is".to_string())
+        }
+    }
+}
+
+fn order_workflow_keys(root: &mut Node) {
+    let Node::Mapping(entries) = root else {
+        return;
+    };
+    order_keys(entries, WORKFLOW_KEYS);
+    for jobs in entries.iter_mut().filter(|entry| key_name(entry) == "jobs") {
+        let Some(Node::Mapping(jobs)) = &mut jobs.value.block else {
+            continue;
+        };
+        for job in jobs {
+            let Some(Node::Mapping(job)) = &mut job.value.block else {
+                continue;
+            };
+            order_keys(job, JOB_KEYS);
+            for steps in job.iter_mut().filter(|entry| key_name(entry) == "steps") {
+                let Some(Node::Sequence(steps)) = &mut steps.value.block else {
+                    continue;
+                };
+                for step in steps {
+                    if let Some(Node::Mapping(step)) = &mut step.value.block {
+                        order_keys(step, STEP_KEYS);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Put the keys in `order` first, in that order, followed by the others
+/// as they were
+fn order_keys(entries: &mut [Entry], order: &[&str]) {
+    let rank = |entry: &Entry| {
+        order
+            .iter()
+            .position(|key| *key == key_name(entry))
+            .unwrap_or(order.len())
+    };
+    if entries.is_empty() || entries.is_sorted_by_key(rank) {
+        return;
+    }
+    // Comments above the first key are about the whole mapping, so they
+    // stay on top
+    let top = std::mem::take(&mut entries[0].leading);
+    entries.sort_by_key(rank);
+    entries[0].leading.splice(0..0, top);
+}
+
+fn key_name(entry: &Entry) -> &str {
+    entry.key.trim_matches(|c| c == '"' || c == '\'')
+}
+
+fn tidy_node(node: &mut Node) {
+    match node {
+        Node::Mapping(entries) => {
+            for (i, entry) in entries.iter_mut().enumerate() {
+                tidy_trivia(&mut entry.leading, i == 0);
+                tidy_value(&mut entry.value);
+            }
+        }
+        Node::Sequence(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                tidy_trivia(&mut item.leading, i == 0);
+                tidy_value(&mut item.value);
+            }
+        }
+        Node::Text(_) => {}
+    }
+}
+
+fn tidy_value(value: &mut Value) {
+    if value.continuation.is_empty() {
+        prefer_single_quotes(&mut value.inline);
+    }
+    if let Some(block) = &mut value.block {
+        tidy_node(block);
+    }
+}
+
+/// Squash runs of blank lines into one, and drop those at the start of a
+/// collection
+fn tidy_trivia(trivia: &mut Vec<String>, first: bool) {
+    let mut previous_blank = first;
+    trivia.retain(|line| {
+        let keep = !(line.is_empty() && previous_blank);
+        previous_blank = line.is_empty();
+        keep
+    });
+}
+
+/// `"text"` as `'text'` when nothing in it needs double quotes
+fn prefer_single_quotes(inline: &mut String) {
+    let Some(text) = inline
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+    else {
+        return;
+    };
+    if !text.contains(['\\', '"', '\'']) {
+        *inline = format!("'{}'", text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_workflows_keeping_comments() {
+        let content = "\
+# Build and test
+
+
+on:
+    push:
+        branches: [ \"main\" ]
+name: CI
+jobs:
+
+    test:
+        steps:
+        # Check out first
+        -   run: cargo test   # all of it
+            name: \"Test\"
+        -   with:
+                node-version: \"20\"
+            uses: actions/setup-node@v4
+            name: \"Say \\\"hi\\\"\"
+        runs-on: ubuntu-latest
+
+
+        needs: build
+";
+        let formatted = format_workflow(content, true).unwrap();
+        assert_eq!(
+            formatted,
+            "\
+# Build and test
+
+name: CI
+on:
+  push:
+    branches: [ \"main\" ]
+jobs:
+  test:
+    needs: build
+    runs-on: ubuntu-latest
+    steps:
+      # Check out first
+      - name: 'Test'
+        run: cargo test # all of it
+      - name: \"Say \\\"hi\\\"\"
+        uses: actions/setup-node@v4
+        with:
+          node-version: '20'
+"
+        );
+        assert_eq!(format_workflow(&formatted, true).unwrap(), formatted);
+
+        // Key order is kept when asked to, and meaning is never changed
+        let pipeline = "script:\n  - make\nimage: alpine\n";
+        assert_eq!(
+            format_workflow("run: |\n  make", false).unwrap(),
+            "run: |\n  make\n"
+        );
+        assert_eq!(format_workflow(pipeline, false).unwrap(), pipeline);
+        let aliased = "jobs:\n  a:\n    steps: &s [x]\n    needs: *s\n";
+        assert!(format_workflow(aliased, true).is_err());
+    }
+}
@@ -1,11 +1,14 @@
 use crate::schema::{SchemaType, SchemaValidator};
-use crate::workflow;
-use models::gitlab::Pipeline;
+use models::github::{self, Container, JobContainer, OrExpression, RunsOn, StringList};
+use models::gitlab::{Job, Parallel, Pipeline, Service};
 use models::ValidationResult;
-use std::collections::HashMap;
+use serde_yaml::{Mapping, Value};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 use thiserror::Error;
+use utils::hash::fnv1a;
 
 #[derive(Error, Debug)]
 pub enum GitlabParserError {
@@ -27,54 +30,548 @@ pub fn parse_pipeline(pipeline_path: &Path) -> Result<Pipeline, GitlabParserErro
     // Read the pipeline file
     let pipeline_content = fs::read_to_string(pipeline_path)?;
 
+    // Expand anchors, includes and `extends` so validation and execution
+    // see every job the way GitLab would run it
+    let mut pipeline_value: Value = serde_yaml::from_str(&pipeline_content)?;
+    pipeline_value.apply_merge()?;
+    let base_dir = pipeline_path.parent().unwrap_or(Path::new("."));
+    resolve_includes(&mut pipeline_value, base_dir)?;
+    resolve_extends(&mut pipeline_value)?;
+    let merged_content = serde_yaml::to_string(&pipeline_value)?;
+
     // Validate against schema
     let validator = SchemaValidator::new().map_err(GitlabParserError::SchemaValidationError)?;
 
     validator
-        .validate_with_specific_schema(&pipeline_content, SchemaType::GitLab)
+        .validate_with_specific_schema(&merged_content, SchemaType::GitLab)
         .map_err(GitlabParserError::SchemaValidationError)?;
 
     // Parse the pipeline YAML
-    let pipeline: Pipeline = serde_yaml::from_str(&pipeline_content)?;
+    let pipeline: Pipeline = serde_yaml::from_value(pipeline_value)?;
 
     // Return the parsed pipeline
     Ok(pipeline)
 }
 
+/// Top-level keywords that configure the pipeline rather than define a job
+pub const GLOBAL_KEYWORDS: &[&str] = &[
+    "default",
+    "include",
+    "stages",
+    "variables",
+    "workflow",
+    "image",
+    "services",
+    "cache",
+    "before_script",
+    "after_script",
+];
+
+/// Maximum nesting depth for `extends`, matching GitLab's own limit
+const MAX_EXTENDS_DEPTH: usize = 11;
+
+/// Deep-merge `overlay` into `base`. Mappings are merged key by key, every
+/// other value in `overlay` replaces the one in `base`.
+pub fn deep_merge(base: &mut Value, overlay: &Value) {
+    match (base, overlay) {
+        (Value::Mapping(base_map), Value::Mapping(overlay_map)) => {
+            for (key, overlay_value) in overlay_map {
+                match base_map.get_mut(key) {
+                    Some(base_value) => deep_merge(base_value, overlay_value),
+                    None => {
+                        base_map.insert(key.clone(), overlay_value.clone());
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay.clone(),
+    }
+}
+
+/// GitLab instance that project includes are fetched from, unless
+/// `CI_SERVER_URL` points somewhere else
+const DEFAULT_GITLAB_URL: &str = "https://gitlab.com";
+
+/// Where GitLab keeps the files behind `include: template:`
+const TEMPLATES_URL: &str =
+    "https://gitlab.com/gitlab-org/gitlab/-/raw/master/lib/gitlab/ci/templates";
+
+/// How long a downloaded include is reused before it is fetched again
+const INCLUDE_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// How long a download of an include may take
+const INCLUDE_FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// Resolve remote includes from the cache only, never downloading them,
+/// as `wrkflw validate --offline` does
+pub fn work_offline() {
+    OFFLINE.store(true, Ordering::Relaxed);
+}
+
+/// Environment variable overriding where downloaded includes are cached
+pub const INCLUDE_CACHE_ENV: &str = "WRKFLW_INCLUDE_CACHE";
+
+/// Directory downloaded includes are cached in
+pub fn include_cache_dir() -> PathBuf {
+    std::env::var_os(INCLUDE_CACHE_ENV)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            dirs::cache_dir()
+                .unwrap_or_else(std::env::temp_dir)
+                .join("wrkflw")
+                .join("includes")
+        })
+}
+
+/// Where an included file came from, which decides what its own `local:`
+/// includes refer to
+#[derive(Debug, Clone)]
+enum IncludeSource {
+    /// A file in the local checkout, rooted at this directory
+    Local(PathBuf),
+    /// A file of another project on the GitLab instance
+    Project { project: String, git_ref: String },
+    /// A remote URL or template, which can't have local includes
+    Remote,
+}
+
+/// Merge `include:` files into the pipeline: local files (globs allowed),
+/// remote URLs, files from other projects and GitLab templates. Downloads
+/// are cached in [`include_cache_dir`]. Entries that can't be resolved
+/// locally, like CI/CD components, are kept under `include:`.
+pub fn resolve_includes(pipeline: &mut Value, base_dir: &Path) -> Result<(), GitlabParserError> {
+    resolve_includes_with_cache(
+        pipeline,
+        base_dir,
+        &include_cache_dir(),
+        OFFLINE.load(Ordering::Relaxed),
+    )
+}
+
+fn resolve_includes_with_cache(
+    pipeline: &mut Value,
+    base_dir: &Path,
+    cache_dir: &Path,
+    offline: bool,
+) -> Result<(), GitlabParserError> {
+    let mut resolver = IncludeResolver {
+        cache_dir,
+        offline,
+        visited: Vec::new(),
+    };
+    resolver.resolve(pipeline, &IncludeSource::Local(base_dir.to_path_buf()))
+}
+
+struct IncludeResolver<'a> {
+    cache_dir: &'a Path,
+    // Only use cached downloads
+    offline: bool,
+    // Files currently being included, to catch cycles
+    visited: Vec<String>,
+}
+
+impl IncludeResolver<'_> {
+    fn resolve(
+        &mut self,
+        pipeline: &mut Value,
+        source: &IncludeSource,
+    ) -> Result<(), GitlabParserError> {
+        let Some(mapping) = pipeline.as_mapping_mut() else {
+            return Ok(());
+        };
+        let Some(include) = mapping.remove("include") else {
+            return Ok(());
+        };
+
+        let entries = match include {
+            Value::Sequence(entries) => entries,
+            other => vec![other],
+        };
+
+        let mut merged = Value::Mapping(Mapping::new());
+        let mut unresolved = Vec::new();
+
+        for entry in entries {
+            let files = self.load_entry(&entry, source)?;
+            if files.is_none() {
+                unresolved.push(entry);
+            }
+
+            for (key, content, nested_source) in files.into_iter().flatten() {
+                if self.visited.contains(&key) {
+                    return Err(GitlabParserError::InvalidStructure(format!(
+                        "Include cycle detected at '{}'",
+                        key
+                    )));
+                }
+
+                let mut included: Value = serde_yaml::from_str(&content)?;
+                included.apply_merge()?;
+
+                self.visited.push(key);
+                self.resolve(&mut included, &nested_source)?;
+                self.visited.pop();
+
+                deep_merge(&mut merged, &included);
+            }
+        }
+
+        // The including file always wins over what it includes
+        let own = Value::Mapping(std::mem::take(mapping));
+        deep_merge(&mut merged, &own);
+
+        if let Value::Mapping(merged_map) = &mut merged {
+            if !unresolved.is_empty() {
+                merged_map.insert(Value::from("include"), Value::Sequence(unresolved));
+            }
+        }
+        *pipeline = merged;
+
+        Ok(())
+    }
+
+    /// Read the files an `include:` entry refers to, as (key, content,
+    /// source) triples. `None` for entries that aren't resolved locally.
+    #[allow(clippy::type_complexity)]
+    fn load_entry(
+        &self,
+        entry: &Value,
+        source: &IncludeSource,
+    ) -> Result<Option<Vec<(String, String, IncludeSource)>>, GitlabParserError> {
+        let field = |name: &str| entry.get(name).and_then(|v| v.as_str());
+
+        let files = match entry {
+            Value::String(url) if url.contains("://") => vec![self.load_remote(url)?],
+            Value::String(path) => self.load_local(path, source)?,
+            Value::Mapping(_) => {
+                if let Some(path) = field("local") {
+                    self.load_local(path, source)?
+                } else if let Some(url) = field("remote") {
+                    vec![self.load_remote(url)?]
+                } else if let Some(project) = field("project") {
+                    let git_ref = field("ref").unwrap_or("HEAD");
+                    let files: Vec<&str> = match entry.get("file") {
+                        Some(Value::String(file)) => vec![file],
+                        Some(Value::Sequence(files)) => {
+                            files.iter().filter_map(|f| f.as_str()).collect()
+                        }
+                        _ => {
+                            return Err(GitlabParserError::InvalidStructure(format!(
+                                "Include of project '{}' needs a 'file'",
+                                project
+                            )))
+                        }
+                    };
+                    files
+                        .into_iter()
+                        .map(|file| self.load_project_file(project, git_ref, file))
+                        .collect::<Result<_, _>>()?
+                } else if let Some(template) = field("template") {
+                    let url = format!("{}/{}", TEMPLATES_URL, template);
+                    vec![(
+                        format!("template:{}", template),
+                        self.fetch(&url, false)?,
+                        IncludeSource::Remote,
+                    )]
+                } else {
+                    return Ok(None);
+                }
+            }
+            _ => return Ok(None),
+        };
+
+        Ok(Some(files))
+    }
+
+    fn load_local(
+        &self,
+        path: &str,
+        source: &IncludeSource,
+    ) -> Result<Vec<(String, String, IncludeSource)>, GitlabParserError> {
+        let base_dir = match source {
+            IncludeSource::Local(base_dir) => base_dir,
+            IncludeSource::Project { project, git_ref } => {
+                return Ok(vec![self.load_project_file(project, git_ref, path)?]);
+            }
+            IncludeSource::Remote => {
+                return Err(GitlabParserError::InvalidStructure(format!(
+                    "Remote includes can't include local file '{}'",
+                    path
+                )))
+            }
+        };
+
+        let include_path = base_dir.join(path.trim_start_matches('/'));
+        let paths = if path.contains('*') {
+            let mut matches: Vec<PathBuf> = glob::glob(&include_path.to_string_lossy())
+                .map_err(|e| {
+                    GitlabParserError::InvalidStructure(format!(
+                        "Invalid include pattern '{}': {}",
+                        path, e
+                    ))
+                })?
+                .flatten()
+                .collect();
+            matches.sort();
+            matches
+        } else {
+            vec![include_path]
+        };
+
+        paths
+            .into_iter()
+            .map(|path| {
+                let content = fs::read_to_string(&path).map_err(|e| {
+                    GitlabParserError::InvalidStructure(format!(
+                        "Failed to read included file '{}': {}",
+                        path.display(),
+                        e
+                    ))
+                })?;
+                Ok((
+                    path.to_string_lossy().to_string(),
+                    content,
+                    IncludeSource::Local(base_dir.clone()),
+                ))
+            })
+            .collect()
+    }
+
+    fn load_remote(&self, url: &str) -> Result<(String, String, IncludeSource), GitlabParserError> {
+        Ok((
+            url.to_string(),
+            self.fetch(url, false)?,
+            IncludeSource::Remote,
+        ))
+    }
+
+    fn load_project_file(
+        &self,
+        project: &str,
+        git_ref: &str,
+        file: &str,
+    ) -> Result<(String, String, IncludeSource), GitlabParserError> {
+        let server = std::env::var("CI_SERVER_URL").unwrap_or_else(|_| DEFAULT_GITLAB_URL.into());
+        let url = format!(
+            "{}/api/v4/projects/{}/repository/files/{}/raw?ref={}",
+            server.trim_end_matches('/'),
+            urlencoding::encode(project),
+            urlencoding::encode(file.trim_start_matches('/')),
+            urlencoding::encode(git_ref)
+        );
+        Ok((
+            format!("project:{}@{}:{}", project, git_ref, file),
+            self.fetch(&url, true)?,
+            IncludeSource::Project {
+                project: project.to_string(),
+                git_ref: git_ref.to_string(),
+            },
+        ))
+    }
+
+    /// Download a URL, reusing a cached copy while it is fresh. A stale copy
+    /// is still used when the download fails or the resolver is offline, so
+    /// pipelines keep working without a network.
+    fn fetch(&self, url: &str, authenticated: bool) -> Result<String, GitlabParserError> {
+        let cached = self.cache_dir.join(format!("{:016x}.yml", fnv1a(url)));
+
+        let age = fs::metadata(&cached)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok());
+        if self.offline || age.is_some_and(|age| age < INCLUDE_CACHE_TTL) {
+            if let Ok(content) = fs::read_to_string(&cached) {
+                return Ok(content);
+            }
+        }
+        if self.offline {
+            return Err(GitlabParserError::InvalidStructure(format!(
+                "Include '{}' hasn't been downloaded yet and can't be while offline",
+                url
+            )));
+        }
+
+        let token = authenticated
+            .then(|| std::env::var("GITLAB_TOKEN").ok())
+            .flatten()
+            .filter(|token| !token.trim().is_empty());
+        match download(url, token) {
+            Ok(content) => {
+                // Failing to cache only costs a download next time
+                let _ =
+                    fs::create_dir_all(self.cache_dir).and_then(|_| fs::write(&cached, &content));
+                Ok(content)
+            }
+            Err(e) => fs::read_to_string(&cached).map_err(|_| {
+                GitlabParserError::InvalidStructure(format!(
+                    "Failed to fetch include '{}': {}",
+                    url, e
+                ))
+            }),
+        }
+    }
+}
+
+/// GET a URL, sending `token` as GitLab's `PRIVATE-TOKEN`. Runs on a
+/// runtime and thread of its own, since parsing may already be running
+/// inside one.
+fn download(url: &str, token: Option<String>) -> Result<String, String> {
+    let url = url.to_string();
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| e.to_string())?;
+        runtime.block_on(async {
+            let client = reqwest::Client::builder()
+                .timeout(INCLUDE_FETCH_TIMEOUT)
+                .build()
+                .map_err(|e| e.to_string())?;
+            let mut request = client.get(&url);
+            if let Some(token) = token {
+                request = request.header("PRIVATE-TOKEN", token.trim());
+            }
+            let response = request
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+                .map_err(|e| e.to_string())?;
+            response.text().await.map_err(|e| e.to_string())
+        })
+    })
+    .join()
+    .unwrap_or_else(|_| Err("download thread panicked".to_string()))
+}
+
+/// Resolve `extends:` on every job by deep-merging the referenced jobs
+/// (in order) underneath the job's own configuration.
+pub fn resolve_extends(pipeline: &mut Value) -> Result<(), GitlabParserError> {
+    let Some(original) = pipeline.as_mapping().cloned() else {
+        return Ok(());
+    };
+
+    let mut resolved = Mapping::new();
+    for (name, job) in &original {
+        let Some(name_str) = name.as_str() else {
+            continue;
+        };
+        if GLOBAL_KEYWORDS.contains(&name_str) || !job.is_mapping() {
+            resolved.insert(name.clone(), job.clone());
+            continue;
+        }
+        let mut chain = Vec::new();
+        resolved.insert(
+            name.clone(),
+            resolve_job_extends(name_str, &original, &mut chain)?,
+        );
+    }
+
+    *pipeline = Value::Mapping(resolved);
+    Ok(())
+}
+
+fn resolve_job_extends(
+    job_name: &str,
+    jobs: &Mapping,
+    chain: &mut Vec<String>,
+) -> Result<Value, GitlabParserError> {
+    if chain.iter().any(|name| name == job_name) {
+        chain.push(job_name.to_string());
+        return Err(GitlabParserError::InvalidStructure(format!(
+            "Circular extends detected: {}",
+            chain.join(" -> ")
+        )));
+    }
+    if chain.len() >= MAX_EXTENDS_DEPTH {
+        return Err(GitlabParserError::InvalidStructure(format!(
+            "Job '{}' exceeds the maximum extends depth of {}",
+            chain.first().map(String::as_str).unwrap_or(job_name),
+            MAX_EXTENDS_DEPTH
+        )));
+    }
+
+    let job = jobs.get(job_name).ok_or_else(|| {
+        GitlabParserError::InvalidStructure(format!(
+            "'{}' extends undefined job '{}'",
+            chain.last().map(String::as_str).unwrap_or(job_name),
+            job_name
+        ))
+    })?;
+
+    let parents: Vec<String> = match job.get("extends") {
+        Some(Value::String(parent)) => vec![parent.clone()],
+        Some(Value::Sequence(parents)) => parents
+            .iter()
+            .filter_map(|p| p.as_str().map(|s| s.to_string()))
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    if parents.is_empty() {
+        return Ok(job.clone());
+    }
+
+    chain.push(job_name.to_string());
+    let mut merged = Value::Mapping(Mapping::new());
+    for parent in &parents {
+        let parent_job = resolve_job_extends(parent, jobs, chain)?;
+        deep_merge(&mut merged, &parent_job);
+    }
+    chain.pop();
+
+    deep_merge(&mut merged, job);
+    if let Value::Mapping(map) = &mut merged {
+        map.remove("extends");
+    }
+
+    Ok(merged)
+}
+
 /// Validate the basic structure of a GitLab CI/CD pipeline
 pub fn validate_pipeline_structure(pipeline: &Pipeline) -> ValidationResult {
     let mut result = ValidationResult::new();
 
     // Check for at least one job
     if pipeline.jobs.is_empty() {
-        result.add_issue("Pipeline must contain at least one job".to_string());
+        result.add_issue_at(
+            "no-jobs",
+            "",
+            "Pipeline must contain at least one job".to_string(),
+        );
     }
 
     // Check for script in jobs
     for (job_name, job) in &pipeline.jobs {
-        // Skip template jobs
-        if let Some(true) = job.template {
+        if !is_runnable_job(job_name, job) {
             continue;
         }
 
         // Check for script or extends
         if job.script.is_none() && job.extends.is_none() {
-            result.add_issue(format!(
-                "Job '{}' must have a script section or extend another job",
-                job_name
-            ));
+            result.add_issue_at(
+                "missing-script",
+                job_name,
+                format!(
+                    "Job '{}' must have a script section or extend another job",
+                    job_name
+                ),
+            );
         }
     }
 
     // Check that referenced stages are defined
-    if let Some(stages) = &pipeline.stages {
+    if pipeline.stages.is_some() {
+        let stages = pipeline_stages(pipeline);
         for (job_name, job) in &pipeline.jobs {
             if let Some(stage) = &job.stage {
                 if !stages.contains(stage) {
-                    result.add_issue(format!(
-                        "Job '{}' references undefined stage '{}'",
-                        job_name, stage
-                    ));
+                    result.add_issue_at(
+                        "undefined-stage",
+                        format!("{}.stage", job_name),
+                        format!("Job '{}' references undefined stage '{}'", job_name, stage),
+                    );
                 }
             }
         }
@@ -83,26 +580,44 @@ pub fn validate_pipeline_structure(pipeline: &Pipeline) -> ValidationResult {
     // Check that job dependencies exist
     for (job_name, job) in &pipeline.jobs {
         if let Some(dependencies) = &job.dependencies {
-            for dependency in dependencies {
+            for (i, dependency) in dependencies.iter().enumerate() {
                 if !pipeline.jobs.contains_key(dependency) {
-                    result.add_issue(format!(
-                        "Job '{}' depends on undefined job '{}'",
-                        job_name, dependency
-                    ));
+                    result.add_issue_at(
+                        "undefined-dependency",
+                        format!("{}.dependencies[{}]", job_name, i),
+                        format!(
+                            "Job '{}' depends on undefined job '{}'",
+                            job_name, dependency
+                        ),
+                    );
                 }
             }
         }
     }
 
+    // Check that needed jobs exist, unless the need is optional
+    for (job_name, job) in &pipeline.jobs {
+        for (i, need) in job.needs.iter().flatten().enumerate() {
+            if !need.is_optional() && !pipeline.jobs.contains_key(need.job()) {
+                result.add_issue_at(
+                    "undefined-need",
+                    format!("{}.needs[{}]", job_name, i),
+                    format!("Job '{}' needs undefined job '{}'", job_name, need.job()),
+                );
+            }
+        }
+    }
+
     // Check that job extensions exist
     for (job_name, job) in &pipeline.jobs {
         if let Some(extends) = &job.extends {
             for extend in extends {
                 if !pipeline.jobs.contains_key(extend) {
-                    result.add_issue(format!(
-                        "Job '{}' extends undefined job '{}'",
-                        job_name, extend
-                    ));
+                    result.add_issue_at(
+                        "undefined-extends",
+                        format!("{}.extends", job_name),
+                        format!("Job '{}' extends undefined job '{}'", job_name, extend),
+                    );
                 }
             }
         }
@@ -111,31 +626,229 @@ pub fn validate_pipeline_structure(pipeline: &Pipeline) -> ValidationResult {
     result
 }
 
+/// Whether a job entry defines a job that runs, rather than a hidden
+/// (`.name`) or template job
+pub fn is_runnable_job(name: &str, job: &Job) -> bool {
+    !name.starts_with('.') && job.template != Some(true)
+}
+
+/// Stage a job runs in, `test` when it doesn't say
+pub fn job_stage(job: &Job) -> &str {
+    job.stage.as_deref().unwrap_or("test")
+}
+
+/// Stages in execution order, including the implicit `.pre` and `.post`
+pub fn pipeline_stages(pipeline: &Pipeline) -> Vec<String> {
+    let mut stages = vec![".pre".to_string()];
+    match &pipeline.stages {
+        Some(defined) => stages.extend(
+            defined
+                .iter()
+                .filter(|s| *s != ".pre" && *s != ".post")
+                .cloned(),
+        ),
+        None => stages.extend(["build", "test", "deploy"].map(String::from)),
+    }
+    stages.push(".post".to_string());
+    stages
+}
+
+/// A job as it runs: the job itself, or one of the copies `parallel:` makes
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobInstance {
+    pub name: String,
+    /// Variables set for this instance only
+    pub variables: Vec<(String, String)>,
+}
+
+/// The instances a job runs as. `parallel: 3` makes `job 1/3` to `job 3/3`,
+/// `parallel: matrix:` one `job: [a, b]` per combination of values, with
+/// the values set as variables.
+pub fn job_instances(name: &str, job: &Job) -> Vec<JobInstance> {
+    let combinations: Vec<Vec<(String, String)>> = match &job.parallel {
+        Some(Parallel::Count(count)) if *count > 1 => vec![Vec::new(); *count],
+        Some(Parallel::Matrix { matrix }) => matrix
+            .iter()
+            .flat_map(|entry| {
+                entry
+                    .iter()
+                    .fold(vec![Vec::new()], |combinations, (key, values)| {
+                        combinations
+                            .iter()
+                            .flat_map(|combination| {
+                                values.values().iter().map(move |value| {
+                                    let mut combination: Vec<(String, String)> =
+                                        combination.clone();
+                                    combination.push((key.clone(), value.clone()));
+                                    combination
+                                })
+                            })
+                            .collect()
+                    })
+            })
+            .collect(),
+        _ => {
+            return vec![JobInstance {
+                name: name.to_string(),
+                variables: Vec::new(),
+            }]
+        }
+    };
+
+    let total = combinations.len();
+    combinations
+        .into_iter()
+        .enumerate()
+        .map(|(idx, mut variables)| {
+            let name = if variables.is_empty() {
+                format!("{} {}/{}", name, idx + 1, total)
+            } else {
+                let values: Vec<&str> = variables.iter().map(|(_, v)| v.as_str()).collect();
+                format!("{}: [{}]", name, values.join(", "))
+            };
+            variables.push(("CI_NODE_INDEX".to_string(), (idx + 1).to_string()));
+            variables.push(("CI_NODE_TOTAL".to_string(), total.to_string()));
+            JobInstance { name, variables }
+        })
+        .collect()
+}
+
+/// Names of the instances of a job, or the name itself for unknown jobs
+pub fn instance_names(pipeline: &Pipeline, name: &str) -> Vec<String> {
+    match pipeline.jobs.get(name) {
+        Some(job) => job_instances(name, job)
+            .into_iter()
+            .map(|i| i.name)
+            .collect(),
+        None => vec![name.to_string()],
+    }
+}
+
+/// Jobs that have to finish before a job starts: its `needs` when it has
+/// them, otherwise every job in an earlier stage
+fn job_order_dependencies(pipeline: &Pipeline, job: &Job) -> Vec<String> {
+    if let Some(needs) = &job.needs {
+        return needs
+            .iter()
+            .filter(|need| pipeline.jobs.contains_key(need.job()) || !need.is_optional())
+            .flat_map(|need| instance_names(pipeline, need.job()))
+            .collect();
+    }
+
+    let stages = pipeline_stages(pipeline);
+    let position = |stage: &str| stages.iter().position(|s| s == stage);
+    let Some(own_stage) = position(job_stage(job)) else {
+        return Vec::new();
+    };
+    let mut earlier: Vec<String> = pipeline
+        .jobs
+        .iter()
+        .filter(|(name, other)| {
+            is_runnable_job(name, other)
+                && position(job_stage(other)).is_some_and(|stage| stage < own_stage)
+        })
+        .flat_map(|(name, _)| instance_names(pipeline, name))
+        .collect();
+    earlier.sort();
+    earlier
+}
+
+/// Hostnames a job reaches a service by: its `alias`es, then the image
+/// without its tag with slashes turned into `__` and into `-`, like
+/// `tutum__wordpress` and `tutum-wordpress` for `tutum/wordpress:latest`
+pub fn service_hostnames(service: &Service) -> Vec<String> {
+    let image = service.name();
+    let image = image.split('@').next().unwrap_or(image);
+    let image = match image.rsplit_once(':') {
+        Some((name, tag)) if !tag.contains('/') => name,
+        _ => image,
+    };
+    let aliases = match service {
+        Service::Detailed {
+            alias: Some(alias), ..
+        } => alias.split(',').map(str::trim).collect(),
+        _ => Vec::new(),
+    };
+    let mut hostnames: Vec<String> = Vec::new();
+    for hostname in aliases
+        .into_iter()
+        .map(str::to_string)
+        .chain([image.replace('/', "__"), image.replace('/', "-")])
+    {
+        if !hostname.is_empty() && !hostnames.contains(&hostname) {
+            hostnames.push(hostname);
+        }
+    }
+    hostnames
+}
+
+fn script_steps(lines: &[String], label: &str, after_script: bool) -> Vec<github::Step> {
+    lines
+        .iter()
+        .enumerate()
+        .map(|(i, cmd)| github::Step {
+            name: Some(format!("{} {}", label, i + 1)),
+            run: Some(cmd.clone()),
+            // after_script runs even when the job failed, and its own
+            // failures don't change the job's status
+            continue_on_error: after_script.then_some(OrExpression::Value(true)),
+            if_condition: after_script.then(|| "always()".to_string()),
+            ..Default::default()
+        })
+        .collect()
+}
+
 /// Convert a GitLab CI/CD pipeline to a format compatible with the workflow executor
-pub fn convert_to_workflow_format(pipeline: &Pipeline) -> workflow::WorkflowDefinition {
+pub fn convert_to_workflow_format(pipeline: &Pipeline) -> github::Workflow {
     // Create a new workflow with required fields
-    let mut workflow = workflow::WorkflowDefinition {
-        name: "Converted GitLab CI Pipeline".to_string(),
-        on: vec!["push".to_string()], // Default trigger
-        on_raw: serde_yaml::Value::String("push".to_string()),
-        jobs: HashMap::new(),
+    let mut workflow = github::Workflow {
+        name: Some("Converted GitLab CI Pipeline".to_string()),
+        run_name: None,
+        on: github::On::Event("push".to_string()), // Default trigger
+        permissions: None,
+        env: Default::default(),
+        defaults: None,
+        concurrency: None,
+        jobs: Default::default(),
     };
+    let defaults = pipeline.default.as_ref();
 
     // Convert each GitLab job to a GitHub Actions job
     for (job_name, gitlab_job) in &pipeline.jobs {
-        // Skip template jobs
-        if let Some(true) = gitlab_job.template {
+        if !is_runnable_job(job_name, gitlab_job) {
             continue;
         }
 
+        // `default:` wins over the deprecated top-level keywords
+        let image = gitlab_job
+            .image
+            .as_ref()
+            .or(defaults.and_then(|d| d.image.as_ref()))
+            .or(pipeline.image.as_ref());
+        let before_script = gitlab_job
+            .before_script
+            .as_ref()
+            .or(defaults.and_then(|d| d.before_script.as_ref()))
+            .or(pipeline.before_script.as_ref());
+        let after_script = gitlab_job
+            .after_script
+            .as_ref()
+            .or(defaults.and_then(|d| d.after_script.as_ref()))
+            .or(pipeline.after_script.as_ref());
+        let services = gitlab_job
+            .services
+            .as_ref()
+            .or(defaults.and_then(|d| d.services.as_ref()))
+            .or(pipeline.services.as_ref());
+
         // Create a new job
-        let mut job = workflow::Job {
-            runs_on: "ubuntu-latest".to_string(), // Default runner
-            needs: None,
-            steps: Vec::new(),
-            env: HashMap::new(),
-            matrix: None,
-            services: HashMap::new(),
+        let mut job = github::Job {
+            runs_on: Some(RunsOn::Label("ubuntu-latest".to_string())), // Default runner
+            needs: Some(StringList::Multiple(job_order_dependencies(
+                pipeline, gitlab_job,
+            ))),
+            container: image.map(|i| JobContainer::Image(i.name().to_string())),
+            ..Default::default()
         };
 
         // Add job-specific environment variables
@@ -151,74 +864,61 @@ pub fn convert_to_workflow_format(pipeline: &Pipeline) -> workflow::WorkflowDefi
             }
         }
 
-        // Convert before_script to steps if it exists
-        if let Some(before_script) = &gitlab_job.before_script {
-            for (i, cmd) in before_script.iter().enumerate() {
-                let step = workflow::Step {
-                    name: Some(format!("Before script {}", i + 1)),
-                    uses: None,
-                    run: Some(cmd.clone()),
-                    with: None,
-                    env: HashMap::new(),
-                    continue_on_error: None,
-                };
-                job.steps.push(step);
-            }
-        }
+        job.env.insert("CI_JOB_NAME".to_string(), job_name.clone());
+        job.env.insert(
+            "CI_JOB_STAGE".to_string(),
+            job_stage(gitlab_job).to_string(),
+        );
 
-        // Convert main script to steps
-        if let Some(script) = &gitlab_job.script {
-            for (i, cmd) in script.iter().enumerate() {
-                let step = workflow::Step {
-                    name: Some(format!("Run script line {}", i + 1)),
-                    uses: None,
-                    run: Some(cmd.clone()),
-                    with: None,
-                    env: HashMap::new(),
-                    continue_on_error: None,
-                };
-                job.steps.push(step);
-            }
+        if let Some(lines) = before_script {
+            job.steps
+                .extend(script_steps(lines, "Before script", false));
         }
-
-        // Convert after_script to steps if it exists
-        if let Some(after_script) = &gitlab_job.after_script {
-            for (i, cmd) in after_script.iter().enumerate() {
-                let step = workflow::Step {
-                    name: Some(format!("After script {}", i + 1)),
-                    uses: None,
-                    run: Some(cmd.clone()),
-                    with: None,
-                    env: HashMap::new(),
-                    continue_on_error: Some(true), // After script should continue even if previous steps fail
-                };
-                job.steps.push(step);
-            }
+        if let Some(lines) = &gitlab_job.script {
+            job.steps
+                .extend(script_steps(lines, "Run script line", false));
+        }
+        if let Some(lines) = after_script {
+            job.steps.extend(script_steps(lines, "After script", true));
         }
 
-        // Add services if they exist
-        if let Some(services) = &gitlab_job.services {
-            for (i, service) in services.iter().enumerate() {
-                let service_name = format!("service-{}", i);
-                let service_image = match service {
-                    models::gitlab::Service::Simple(name) => name.clone(),
-                    models::gitlab::Service::Detailed { name, .. } => name.clone(),
-                };
-
-                let service = workflow::Service {
-                    image: service_image,
-                    ports: None,
-                    env: HashMap::new(),
-                    volumes: None,
-                    options: None,
-                };
-
-                job.services.insert(service_name, service);
+        // Services get the job's variables, and are reached by the
+        // hostnames GitLab gives them
+        for (i, service) in services.into_iter().flatten().enumerate() {
+            let mut hostnames = service_hostnames(service);
+            let key = match hostnames.first() {
+                Some(hostname) if !job.services.contains_key(hostname) => hostnames.remove(0),
+                _ => format!("service-{}", i),
+            };
+            let mut container = Container {
+                image: service.name().to_string(),
+                env: job.env.clone(),
+                aliases: hostnames,
+                ..Default::default()
+            };
+            if let Service::Detailed {
+                command,
+                entrypoint,
+                variables,
+                ..
+            } = service
+            {
+                container.env.extend(variables.clone().unwrap_or_default());
+                container.entrypoint = entrypoint.clone();
+                container.command = command.clone();
             }
+            job.services.insert(key, container);
         }
 
-        // Add the job to the workflow
-        workflow.jobs.insert(job_name.clone(), job);
+        // Add the job to the workflow, once per instance it runs as
+        for instance in job_instances(job_name, gitlab_job) {
+            let mut instance_job = job.clone();
+            instance_job
+                .env
+                .insert("CI_JOB_NAME".to_string(), instance.name.clone());
+            instance_job.env.extend(instance.variables);
+            workflow.jobs.insert(instance.name, instance_job);
+        }
     }
 
     workflow
@@ -227,10 +927,15 @@ pub fn convert_to_workflow_format(pipeline: &Pipeline) -> workflow::WorkflowDefi
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::path::PathBuf;
+
     use tempfile::NamedTempFile;
 
+    fn container_image(job: &github::Job) -> Option<&str> {
+        job.container.as_ref().map(JobContainer::image)
+    }
+
     #[test]
+    #[allow(unused_mut, clippy::needless_borrow)]
     fn test_parse_simple_pipeline() {
         // Create a temporary file with a simple GitLab CI/CD pipeline
         let mut file = NamedTempFile::new().unwrap();
@@ -269,4 +974,342 @@ test_job:
         assert_eq!(test_job.stage.as_ref().unwrap(), "test");
         assert_eq!(test_job.script.as_ref().unwrap().len(), 2);
     }
+
+    #[test]
+    fn parses_pipelines_with_includes_and_extends() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("templates.yml"),
+            ".test:\n  stage: test\n  script: [\"make test\"]\n",
+        )
+        .unwrap();
+        let path = dir.path().join(".gitlab-ci.yml");
+        fs::write(
+            &path,
+            "include: templates.yml\nstages: [test]\nunit:\n  extends: .test\n  variables:\n    SUITE: unit\n",
+        )
+        .unwrap();
+
+        let pipeline = parse_pipeline(&path).unwrap();
+        let unit = &pipeline.jobs["unit"];
+        assert_eq!(unit.stage.as_deref(), Some("test"));
+        assert_eq!(unit.script.as_ref().unwrap(), &["make test"]);
+        assert_eq!(unit.variables.as_ref().unwrap()["SUITE"], "unit");
+    }
+
+    #[test]
+    fn converts_stages_needs_and_defaults() {
+        let pipeline: Pipeline = serde_yaml::from_str(
+            r#"
+stages: [build, test, deploy]
+image: alpine:3.19
+default:
+  before_script: ["echo setup"]
+after_script: ["echo done"]
+.hidden:
+  script: ["echo hidden"]
+build:
+  stage: build
+  script: ["make"]
+unit:
+  script: ["make test"]
+lint:
+  stage: test
+  image: node:20
+  needs: []
+  script: ["npm run lint"]
+deploy:
+  stage: deploy
+  needs: [unit, { job: missing, optional: true }]
+  before_script: []
+  script: ["make deploy"]
+"#,
+        )
+        .unwrap();
+        assert!(validate_pipeline_structure(&pipeline).is_valid);
+
+        let workflow = convert_to_workflow_format(&pipeline);
+        assert!(!workflow.jobs.contains_key(".hidden"));
+        assert!(!workflow.jobs.contains_key("default"));
+
+        // Jobs without needs wait for every earlier stage
+        let unit = &workflow.jobs["unit"];
+        assert_eq!(unit.needs(), ["build"]);
+        assert_eq!(unit.env["CI_JOB_STAGE"], "test");
+        assert_eq!(container_image(unit), Some("alpine:3.19"));
+        let names: Vec<_> = unit.steps.iter().filter_map(|s| s.name.clone()).collect();
+        assert_eq!(
+            names,
+            ["Before script 1", "Run script line 1", "After script 1"]
+        );
+        assert!(unit.steps[2].continues_on_error());
+
+        assert!(workflow.jobs["lint"].needs().is_empty());
+        assert_eq!(container_image(&workflow.jobs["lint"]), Some("node:20"));
+
+        // Optional needs on jobs outside the pipeline are dropped
+        let deploy = &workflow.jobs["deploy"];
+        assert_eq!(deploy.needs(), ["unit"]);
+        assert_eq!(deploy.steps.len(), 2);
+    }
+
+    #[test]
+    fn converts_services_with_gitlab_hostnames() {
+        let pipeline: Pipeline = serde_yaml::from_str(
+            r#"
+variables:
+  POSTGRES_PASSWORD: secret
+test:
+  variables:
+    POSTGRES_DB: app
+  services:
+    - postgres:15
+    - tutum/wordpress:latest
+    - name: redis:7
+      alias: cache, queue
+      command: ["redis-server", "--appendonly", "yes"]
+      variables:
+        POSTGRES_DB: unused
+  script: ["psql -h postgres"]
+"#,
+        )
+        .unwrap();
+        let services = &convert_to_workflow_format(&pipeline).jobs["test"].services;
+        let postgres = &services["postgres"];
+        assert_eq!(postgres.image, "postgres:15");
+        assert!(postgres.aliases.is_empty());
+        assert_eq!(postgres.env["POSTGRES_DB"], "app");
+        assert_eq!(postgres.env["POSTGRES_PASSWORD"], "secret");
+
+        assert_eq!(services["tutum__wordpress"].aliases, ["tutum-wordpress"]);
+
+        let redis = &services["cache"];
+        assert_eq!(redis.aliases, ["queue", "redis"]);
+        assert_eq!(redis.env["POSTGRES_DB"], "unused");
+        assert_eq!(redis.command.as_ref().unwrap()[0], "redis-server");
+    }
+
+    /// Serve `body` to the next `requests` HTTP requests on a local port
+    fn serve(body: &'static str, requests: usize) -> String {
+        use std::io::{Read, Write};
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/remote.yml", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for stream in listener.incoming().take(requests) {
+                let mut stream = stream.unwrap();
+                let mut request = [0; 4096];
+                let _ = stream.read(&mut request);
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+            }
+        });
+        url
+    }
+
+    #[test]
+    fn resolves_local_and_remote_includes() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = dir.path().join("cache");
+        let remote = serve(
+            "variables:\n  FROM_REMOTE: \"yes\"\nlint:\n  script: [\"echo remote\"]\n",
+            1,
+        );
+        fs::create_dir(dir.path().join("ci")).unwrap();
+        fs::write(
+            dir.path().join("ci/build.yml"),
+            "include: ci/common.yml\nbuild:\n  script: [\"make\"]\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("ci/common.yml"),
+            "variables:\n  SHARED: \"1\"\n",
+        )
+        .unwrap();
+
+        let pipeline = format!(
+            "include:\n  - local: ci/build.yml\n  - remote: {}\n  - component: gitlab.com/org/comp@1.0\nvariables:\n  SHARED: \"2\"\n",
+            remote
+        );
+        let mut value: Value = serde_yaml::from_str(&pipeline).unwrap();
+        resolve_includes_with_cache(&mut value, dir.path(), &cache, false).unwrap();
+
+        assert!(value["build"].is_mapping());
+        assert!(value["lint"].is_mapping());
+        assert_eq!(value["variables"]["FROM_REMOTE"], Value::from("yes"));
+        // The including file wins
+        assert_eq!(value["variables"]["SHARED"], Value::from("2"));
+        // Components aren't resolved locally and are kept
+        assert_eq!(value["include"].as_sequence().unwrap().len(), 1);
+
+        // A second resolution is served from the cache, under a key that
+        // stays the same across builds
+        assert!(cache.join(format!("{:016x}.yml", fnv1a(&remote))).is_file());
+        let mut value: Value = serde_yaml::from_str(&pipeline).unwrap();
+        resolve_includes_with_cache(&mut value, dir.path(), &cache, false).unwrap();
+        assert!(value["lint"].is_mapping());
+    }
+
+    #[test]
+    fn offline_includes_come_from_the_cache_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = dir.path().join("cache");
+        let remote = serve("lint:\n  script: [\"echo remote\"]\n", 1);
+        let pipeline = format!("include:\n  - remote: {}\n", remote);
+
+        let mut value: Value = serde_yaml::from_str(&pipeline).unwrap();
+        let err = resolve_includes_with_cache(&mut value, dir.path(), &cache, true).unwrap_err();
+        assert!(err.to_string().contains("offline"), "{}", err);
+
+        let mut value: Value = serde_yaml::from_str(&pipeline).unwrap();
+        resolve_includes_with_cache(&mut value, dir.path(), &cache, false).unwrap();
+
+        // Even a stale copy is used rather than downloading again
+        let cached = cache.join(format!("{:016x}.yml", fnv1a(&remote)));
+        fs::File::options()
+            .write(true)
+            .open(&cached)
+            .unwrap()
+            .set_modified(std::time::SystemTime::UNIX_EPOCH)
+            .unwrap();
+        let mut value: Value = serde_yaml::from_str(&pipeline).unwrap();
+        resolve_includes_with_cache(&mut value, dir.path(), &cache, true).unwrap();
+        assert!(value["lint"].is_mapping());
+    }
+
+    #[test]
+    fn detects_include_cycles() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.yml"), "include: b.yml\n").unwrap();
+        fs::write(dir.path().join("b.yml"), "include: a.yml\n").unwrap();
+
+        let mut value: Value = serde_yaml::from_str("include: a.yml\n").unwrap();
+        let err =
+            resolve_includes_with_cache(&mut value, dir.path(), &dir.path().join("cache"), false)
+                .unwrap_err();
+        assert!(err.to_string().contains("Include cycle"));
+    }
+
+    #[test]
+    fn parses_anchors_and_extends() {
+        let file = NamedTempFile::new().unwrap();
+        let content = r#"
+.defaults: &defaults
+  image: rust:1.75
+  variables:
+    PROFILE: debug
+    LOG: info
+
+.test_template:
+  <<: *defaults
+  stage: test
+  script:
+    - cargo test
+  tags: [docker]
+
+unit:
+  extends: .test_template
+  variables:
+    PROFILE: release
+  tags: [linux]
+"#;
+        fs::write(&file, content).unwrap();
+
+        let pipeline = parse_pipeline(file.path()).unwrap();
+        let unit = &pipeline.jobs["unit"];
+        assert_eq!(unit.stage.as_deref(), Some("test"));
+        assert_eq!(unit.script, Some(vec!["cargo test".to_string()]));
+        assert!(unit.extends.is_none());
+
+        // Hashes are merged key by key, arrays are replaced
+        let variables = unit.variables.as_ref().unwrap();
+        assert_eq!(variables["PROFILE"], "release");
+        assert_eq!(variables["LOG"], "info");
+        assert_eq!(unit.tags, Some(vec!["linux".to_string()]));
+
+        let workflow = convert_to_workflow_format(&pipeline);
+        assert!(workflow.jobs.contains_key("unit"));
+        assert!(!workflow.jobs.contains_key(".test_template"));
+        assert_eq!(container_image(&workflow.jobs["unit"]), Some("rust:1.75"));
+    }
+
+    #[test]
+    fn expands_parallel_jobs() {
+        let file = NamedTempFile::new().unwrap();
+        let content = r#"
+stages: [build, deploy]
+
+build:
+  stage: build
+  parallel:
+    matrix:
+      - PROVIDER: aws
+        STACK: [monitoring, app]
+      - PROVIDER: gcp
+        NODE: [18, 20]
+  variables:
+    PROVIDER: none
+  script: [./build.sh]
+
+shards:
+  stage: build
+  parallel: 2
+  script: [./test.sh]
+
+deploy:
+  stage: deploy
+  needs: [build]
+  script: [./deploy.sh]
+"#;
+        fs::write(&file, content).unwrap();
+
+        let pipeline = parse_pipeline(file.path()).unwrap();
+        let workflow = convert_to_workflow_format(&pipeline);
+
+        let mut names: Vec<&str> = workflow.jobs.keys().map(String::as_str).collect();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                "build: [aws, app]",
+                "build: [aws, monitoring]",
+                "build: [gcp, 18]",
+                "build: [gcp, 20]",
+                "deploy",
+                "shards 1/2",
+                "shards 2/2"
+            ]
+        );
+
+        // Matrix values override the job's own variables
+        let aws_app = &workflow.jobs["build: [aws, app]"].env;
+        assert_eq!(aws_app["PROVIDER"], "aws");
+        assert_eq!(aws_app["STACK"], "app");
+        assert_eq!(aws_app["CI_JOB_NAME"], "build: [aws, app]");
+        assert_eq!(aws_app["CI_NODE_TOTAL"], "4");
+        // Numbers are passed on as text
+        assert_eq!(workflow.jobs["build: [gcp, 20]"].env["NODE"], "20");
+        assert_eq!(workflow.jobs["shards 2/2"].env["CI_NODE_INDEX"], "2");
+
+        // Needing a parallel job waits for all of its instances
+        let mut deploy_needs = workflow.jobs["deploy"].needs().to_vec();
+        deploy_needs.sort();
+        assert_eq!(
+            deploy_needs,
+            [
+                "build: [aws, app]",
+                "build: [aws, monitoring]",
+                "build: [gcp, 18]",
+                "build: [gcp, 20]"
+            ]
+        );
+        assert_eq!(
+            instance_names(&pipeline, "shards"),
+            ["shards 1/2", "shards 2/2"]
+        );
+        assert_eq!(instance_names(&pipeline, "deploy"), ["deploy"]);
+    }
 }
@@ -0,0 +1,383 @@
+// Evaluating GitLab `rules:` and `workflow:rules` against a pipeline's CI
+// variables, to decide which jobs a pipeline gets and how they start
+use models::gitlab::{Job, Pipeline, Rule};
+use regex::Regex;
+use std::collections::HashMap;
+
+/// What a job's rules decided for a pipeline it is part of
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleOutcome {
+    /// `on_success`, `on_failure`, `always`, `manual` or `delayed`
+    pub when: String,
+    /// Variables the matching rule sets
+    pub variables: HashMap<String, String>,
+    pub allow_failure: Option<bool>,
+    pub start_in: Option<String>,
+}
+
+/// The variables of the pipeline, or None when its `workflow:rules` leave
+/// it out: when no rule matches or the matching one says `when: never`
+pub fn pipeline_variables(
+    pipeline: &Pipeline,
+    variables: &HashMap<String, String>,
+) -> Result<Option<HashMap<String, String>>, String> {
+    let Some(workflow) = &pipeline.workflow else {
+        return Ok(Some(HashMap::new()));
+    };
+    match first_match(&workflow.rules, variables)? {
+        Some(rule) if rule.when.as_deref() != Some("never") => {
+            Ok(Some(rule.variables.clone().unwrap_or_default()))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// How a job starts, or None when its rules leave it out of the pipeline.
+/// Jobs without rules keep their own `when`.
+pub fn job_outcome(
+    job: &Job,
+    variables: &HashMap<String, String>,
+) -> Result<Option<RuleOutcome>, String> {
+    let own_when = job.when.clone().unwrap_or_else(|| "on_success".to_string());
+    let Some(rules) = &job.rules else {
+        return Ok(Some(RuleOutcome {
+            when: own_when,
+            variables: HashMap::new(),
+            allow_failure: job.allow_failure,
+            start_in: None,
+        }));
+    };
+    let Some(rule) = first_match(rules, variables)? else {
+        return Ok(None);
+    };
+    let when = rule.when.clone().unwrap_or(own_when);
+    if when == "never" {
+        return Ok(None);
+    }
+    Ok(Some(RuleOutcome {
+        when,
+        variables: rule.variables.clone().unwrap_or_default(),
+        allow_failure: rule.allow_failure.or(job.allow_failure),
+        start_in: rule.start_in.clone(),
+    }))
+}
+
+/// The first rule whose `if:` holds; rules without one always match
+fn first_match<'a>(
+    rules: &'a [Rule],
+    variables: &HashMap<String, String>,
+) -> Result<Option<&'a Rule>, String> {
+    for rule in rules {
+        let matches = match &rule.if_ {
+            Some(condition) => evaluate(condition, variables)?,
+            None => true,
+        };
+        if matches {
+            return Ok(Some(rule));
+        }
+    }
+    Ok(None)
+}
+
+/// Evaluate a `rules:if` expression like `$CI_COMMIT_BRANCH == "main" &&
+/// $CI_PIPELINE_SOURCE != "schedule"`. Unset variables are null, and a
+/// variable on its own holds when it is set and not empty.
+pub fn evaluate(condition: &str, variables: &HashMap<String, String>) -> Result<bool, String> {
+    let tokens = tokenize(condition)?;
+    let mut parser = ExprParser {
+        tokens: &tokens,
+        pos: 0,
+        variables,
+    };
+    let value = parser.or()?;
+    if parser.pos < tokens.len() {
+        return Err(format!(
+            "Unexpected {:?} in `{}`",
+            tokens[parser.pos], condition
+        ));
+    }
+    Ok(value)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Variable(String),
+    Text(String),
+    Pattern(String),
+    Null,
+    Equal,
+    NotEqual,
+    Matches,
+    NotMatches,
+    And,
+    Or,
+    Open,
+    Close,
+}
+
+fn tokenize(condition: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = condition.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        match c {
+            _ if c.is_whitespace() => i += 1,
+            '(' => {
+                tokens.push(Token::Open);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::Close);
+                i += 1;
+            }
+            '$' => {
+                let braced = next == Some('{');
+                let start = if braced { i + 2 } else { i + 1 };
+                let mut end = start;
+                while end < chars.len() && (chars[end].is_alphanumeric() || chars[end] == '_') {
+                    end += 1;
+                }
+                if end == start || (braced && chars.get(end) != Some(&'}')) {
+                    return Err(format!("Invalid variable in `{}`", condition));
+                }
+                tokens.push(Token::Variable(chars[start..end].iter().collect()));
+                i = if braced { end + 1 } else { end };
+            }
+            '"' | '\'' => {
+                let end = (i + 1..chars.len())
+                    .find(|&j| chars[j] == c)
+                    .ok_or_else(|| format!("Unclosed string in `{}`", condition))?;
+                tokens.push(Token::Text(chars[i + 1..end].iter().collect()));
+                i = end + 1;
+            }
+            '/' if matches!(tokens.last(), Some(Token::Matches | Token::NotMatches)) => {
+                let (pattern, end) = read_pattern(&chars, i)
+                    .ok_or_else(|| format!("Unclosed regular expression in `{}`", condition))?;
+                tokens.push(Token::Pattern(pattern));
+                i = end;
+            }
+            '=' | '!' if matches!(next, Some('=') | Some('~')) => {
+                tokens.push(match (c, next) {
+                    ('=', Some('=')) => Token::Equal,
+                    ('=', _) => Token::Matches,
+                    (_, Some('=')) => Token::NotEqual,
+                    _ => Token::NotMatches,
+                });
+                i += 2;
+            }
+            '&' if next == Some('&') => {
+                tokens.push(Token::And);
+                i += 2;
+            }
+            '|' if next == Some('|') => {
+                tokens.push(Token::Or);
+                i += 2;
+            }
+            _ if condition[char_offset(condition, i)..].starts_with("null") => {
+                tokens.push(Token::Null);
+                i += 4;
+            }
+            _ => return Err(format!("Unexpected '{}' in `{}`", c, condition)),
+        }
+    }
+    Ok(tokens)
+}
+
+fn char_offset(text: &str, chars: usize) -> usize {
+    text.char_indices()
+        .nth(chars)
+        .map_or(text.len(), |(i, _)| i)
+}
+
+/// A `/pattern/flags` starting at `start`, as a pattern for the regex
+/// crate, and where it ends
+fn read_pattern(chars: &[char], start: usize) -> Option<(String, usize)> {
+    let mut pattern = String::new();
+    let mut i = start + 1;
+    loop {
+        match chars.get(i)? {
+            '\\' if chars.get(i + 1) == Some(&'/') => {
+                pattern.push('/');
+                i += 2;
+            }
+            '\\' => {
+                pattern.push('\\');
+                pattern.push(*chars.get(i + 1)?);
+                i += 2;
+            }
+            '/' => break,
+            c => {
+                pattern.push(*c);
+                i += 1;
+            }
+        }
+    }
+    i += 1;
+    let mut flags = String::new();
+    while let Some(c) = chars.get(i).filter(|c| c.is_ascii_alphabetic()) {
+        flags.push(*c);
+        i += 1;
+    }
+    if !flags.is_empty() {
+        pattern = format!("(?{}){}", flags, pattern);
+    }
+    Some((pattern, i))
+}
+
+struct ExprParser<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+    variables: &'a HashMap<String, String>,
+}
+
+impl ExprParser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn or(&mut self) -> Result<bool, String> {
+        let mut value = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            // Both sides are parsed, so errors on either side are reported
+            let right = self.and()?;
+            value = value || right;
+        }
+        Ok(value)
+    }
+
+    fn and(&mut self) -> Result<bool, String> {
+        let mut value = self.comparison()?;
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            let right = self.comparison()?;
+            value = value && right;
+        }
+        Ok(value)
+    }
+
+    fn comparison(&mut self) -> Result<bool, String> {
+        if self.peek() == Some(&Token::Open) {
+            self.pos += 1;
+            let value = self.or()?;
+            if self.peek() != Some(&Token::Close) {
+                return Err("Missing ')'".to_string());
+            }
+            self.pos += 1;
+            return Ok(value);
+        }
+        let left = self.operand()?;
+        let operator = match self.peek() {
+            Some(
+                operator @ (Token::Equal | Token::NotEqual | Token::Matches | Token::NotMatches),
+            ) => operator.clone(),
+            // A variable on its own
+            _ => return Ok(left.is_some_and(|value| !value.is_empty())),
+        };
+        self.pos += 1;
+        let literal_pattern = matches!(self.peek(), Some(Token::Pattern(_)));
+        let right = self.operand()?;
+        match operator {
+            Token::Equal => Ok(left == right),
+            Token::NotEqual => Ok(left != right),
+            _ => {
+                let pattern = right.ok_or("A regular expression must follow =~ and !~")?;
+                let pattern = match pattern.strip_prefix('/') {
+                    // A variable holding `/pattern/`
+                    Some(_) if !literal_pattern => {
+                        let chars: Vec<char> = pattern.chars().collect();
+                        read_pattern(&chars, 0)
+                            .map(|(pattern, _)| pattern)
+                            .ok_or_else(|| format!("Invalid regular expression {}", pattern))?
+                    }
+                    _ => pattern,
+                };
+                let regex = Regex::new(&pattern)
+                    .map_err(|e| format!("Invalid regular expression /{}/: {}", pattern, e))?;
+                let matches = left.is_some_and(|value| regex.is_match(&value));
+                Ok(matches == (operator == Token::Matches))
+            }
+        }
+    }
+
+    fn operand(&mut self) -> Result<Option<String>, String> {
+        let token = self.peek().cloned().ok_or("Expression ends too early")?;
+        self.pos += 1;
+        match token {
+            Token::Variable(name) => Ok(self.variables.get(&name).cloned()),
+            Token::Text(text) | Token::Pattern(text) => Ok(Some(text)),
+            Token::Null => Ok(None),
+            other => Err(format!("Unexpected {:?}", other)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evaluates_rules_like_gitlab() {
+        let variables: HashMap<String, String> = [
+            ("CI_COMMIT_BRANCH", "release/1.2"),
+            ("CI_PIPELINE_SOURCE", "push"),
+            ("EMPTY", ""),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        let mut variables = variables;
+        variables.insert("PATTERN".to_string(), "/^release/".to_string());
+        let holds = |condition: &str| evaluate(condition, &variables).unwrap();
+        assert!(holds("$CI_COMMIT_BRANCH =~ $PATTERN"));
+        assert!(holds("$CI_COMMIT_BRANCH"));
+        assert!(!holds("$EMPTY || $CI_MERGE_REQUEST_IID"));
+        assert!(holds("$CI_COMMIT_BRANCH =~ /^release\\/\\d+/"));
+        assert!(holds(
+            "$CI_COMMIT_BRANCH !~ /^MAIN$/ && $CI_COMMIT_TAG == null"
+        ));
+        assert!(holds("$CI_COMMIT_BRANCH =~ /^RELEASE/i"));
+        assert!(holds(
+            "($CI_PIPELINE_SOURCE == 'schedule' || ${CI_PIPELINE_SOURCE} == \"push\") && $EMPTY == \"\""
+        ));
+        assert!(evaluate("$CI_COMMIT_BRANCH ==", &variables).is_err());
+
+        let pipeline: Pipeline = serde_yaml::from_str(
+            r#"
+workflow:
+  rules:
+    - if: $CI_PIPELINE_SOURCE == "schedule"
+      when: never
+    - when: always
+      variables: { DEPLOY: "no" }
+deploy:
+  script: [./deploy]
+  rules:
+    - if: $CI_COMMIT_BRANCH == "main"
+    - if: $CI_COMMIT_BRANCH =~ /^release/
+      when: manual
+      allow_failure: true
+    - when: never
+"#,
+        )
+        .unwrap();
+        let workflow_variables = pipeline_variables(&pipeline, &variables).unwrap().unwrap();
+        assert_eq!(workflow_variables["DEPLOY"], "no");
+        let outcome = job_outcome(&pipeline.jobs["deploy"], &variables)
+            .unwrap()
+            .unwrap();
+        assert_eq!(outcome.when, "manual");
+        assert_eq!(outcome.allow_failure, Some(true));
+
+        let mut scheduled = variables.clone();
+        scheduled.insert("CI_PIPELINE_SOURCE".to_string(), "schedule".to_string());
+        scheduled.insert("CI_COMMIT_BRANCH".to_string(), "feature".to_string());
+        assert_eq!(pipeline_variables(&pipeline, &scheduled).unwrap(), None);
+        assert_eq!(
+            job_outcome(&pipeline.jobs["deploy"], &scheduled).unwrap(),
+            None
+        );
+    }
+}
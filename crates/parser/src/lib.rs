@@ -1,5 +1,14 @@
 // parser crate
 
+pub mod convert;
+pub mod expand;
+pub mod format;
 pub mod gitlab;
+pub mod gitlab_rules;
+pub mod roundtrip;
 pub mod schema;
+pub mod sniff;
+pub mod spans;
+pub mod templates;
+pub mod triggers;
 pub mod workflow;
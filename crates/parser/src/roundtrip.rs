@@ -0,0 +1,598 @@
+// YAML documents taken apart into nodes that keep their comments and blank
+// lines, so files people edit by hand can be rewritten without losing them.
+// Only block collections are taken apart; scalars and flow collections are
+// kept as they're written.
+
+/// Spaces each nested collection is indented by when rendered
+const INDENT: usize = 2;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document {
+    /// `---` and directive lines, with the comments above them
+    pub header: Vec<String>,
+    pub root: Node,
+    /// Comment and blank lines after the last node
+    pub trailer: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    Mapping(Vec<Entry>),
+    Sequence(Vec<Item>),
+    /// A scalar or flow collection on lines of its own
+    Text(Vec<String>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    /// Comment and blank lines above the key, trimmed
+    pub leading: Vec<String>,
+    pub key: String,
+    pub value: Value,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Item {
+    /// Comment and blank lines above the `-`, trimmed
+    pub leading: Vec<String>,
+    pub value: Value,
+}
+
+/// What follows a key or `-`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Value {
+    /// The text on the same line: a scalar, flow collection, block scalar
+    /// header, or the anchor and tag of a collection
+    pub inline: String,
+    /// Comment at the end of the line, from its `#`
+    pub comment: Option<String>,
+    /// Lines the inline text goes on over: block scalar bodies with their
+    /// indentation relative to each other, or trimmed lines of multi-line
+    /// strings and flow collections
+    pub continuation: Vec<String>,
+    /// Collection on the lines below
+    pub block: Option<Node>,
+}
+
+impl Document {
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let lines = content
+            .lines()
+            .enumerate()
+            .map(|(i, raw)| Line {
+                number: i + 1,
+                indent: raw.len() - raw.trim_start_matches(' ').len(),
+                text: raw.trim().to_string(),
+                raw: raw.to_string(),
+            })
+            .collect();
+        let mut reader = Reader { lines, pos: 0 };
+
+        let mut header = Vec::new();
+        while let Some(line) = reader.peek() {
+            if line.text != "---" && !line.text.starts_with('%') {
+                break;
+            }
+            header.extend(reader.take_trivia());
+            header.push(reader.lines[reader.pos].text.clone());
+            reader.pos += 1;
+        }
+
+        let root = match reader.peek() {
+            Some(line) => {
+                let indent = line.indent;
+                reader.parse_node(indent)?
+            }
+            None => Node::Mapping(Vec::new()),
+        };
+        if let Some(line) = reader.peek() {
+            return Err(if line.text == "---" {
+                format!("line {}: only single documents are supported", line.number)
+            } else {
+                format!("line {}: unexpected indentation", line.number)
+            });
+        }
+        let trailer = reader.take_trivia();
+        Ok(Document {
+            header,
+            root,
+            trailer,
+        })
+    }
+
+    /// The document as YAML, with each level of nesting indented by two
+    /// spaces and sequences indented under their keys
+    pub fn render(&self) -> String {
+        let mut out = self.header.clone();
+        render_node(&self.root, 0, &mut out);
+        out.extend(self.trailer.iter().cloned());
+        if out.is_empty() {
+            return String::new();
+        }
+        let mut text = out.join("\n");
+        text.push('\n');
+        text
+    }
+}
+
+struct Line {
+    number: usize,
+    /// Leading spaces
+    indent: usize,
+    /// The line without its leading and trailing whitespace
+    text: String,
+    raw: String,
+}
+
+impl Line {
+    fn is_trivia(&self) -> bool {
+        self.text.is_empty() || self.text.starts_with('#')
+    }
+}
+
+struct Reader {
+    lines: Vec<Line>,
+    pos: usize,
+}
+
+impl Reader {
+    /// The next line with content, past comments and blank lines
+    fn peek(&self) -> Option<&Line> {
+        self.lines[self.pos..].iter().find(|line| !line.is_trivia())
+    }
+
+    /// Consume the comments and blank lines before the next content line
+    fn take_trivia(&mut self) -> Vec<String> {
+        let mut trivia = Vec::new();
+        while let Some(line) = self.lines.get(self.pos).filter(|line| line.is_trivia()) {
+            trivia.push(line.text.clone());
+            self.pos += 1;
+        }
+        trivia
+    }
+
+    /// The collection or scalar starting at the next content line, which is
+    /// indented by `indent`
+    fn parse_node(&mut self, indent: usize) -> Result<Node, String> {
+        let Some(line) = self.peek() else {
+            return Ok(Node::Mapping(Vec::new()));
+        };
+        if is_item(&line.text) {
+            self.parse_sequence(indent)
+        } else if split_key(&line.text).is_some() {
+            self.parse_mapping(indent)
+        } else {
+            Ok(self.parse_text(indent))
+        }
+    }
+
+    fn parse_mapping(&mut self, indent: usize) -> Result<Node, String> {
+        let mut entries = Vec::new();
+        while let Some(line) = self.peek() {
+            if line.indent < indent {
+                break;
+            }
+            if line.indent > indent {
+                return Err(format!("line {}: unexpected indentation", line.number));
+            }
+            let Some((key, rest)) = split_key(&line.text) else {
+                return Err(format!("line {}: expected a key", line.number));
+            };
+            let leading = self.take_trivia();
+            self.pos += 1;
+            let value = self.parse_value(&rest, indent, true)?;
+            entries.push(Entry {
+                leading,
+                key,
+                value,
+            });
+        }
+        Ok(Node::Mapping(entries))
+    }
+
+    fn parse_sequence(&mut self, indent: usize) -> Result<Node, String> {
+        let mut items = Vec::new();
+        while let Some(line) = self.peek() {
+            if line.indent < indent || (line.indent == indent && !is_item(&line.text)) {
+                break;
+            }
+            if line.indent > indent {
+                return Err(format!("line {}: unexpected indentation", line.number));
+            }
+            let leading = self.take_trivia();
+            let line = &self.lines[self.pos];
+            let content = line.text[1..].trim_start().to_string();
+            let column = line.indent + line.text.len() - content.len();
+            let value = if is_item(&content) || split_key(&content).is_some() {
+                // `- key: value` and `- - item` start a collection on the
+                // item's line, which goes on at the column it starts at
+                let number = line.number;
+                self.lines[self.pos] = Line {
+                    number,
+                    indent: column,
+                    text: content.clone(),
+                    raw: format!("{}{}", " ".repeat(column), content),
+                };
+                Value {
+                    block: Some(self.parse_node(column)?),
+                    ..Value::default()
+                }
+            } else {
+                self.pos += 1;
+                self.parse_value(&content, indent, false)?
+            };
+            items.push(Item { leading, value });
+        }
+        Ok(Node::Sequence(items))
+    }
+
+    /// Lines of a scalar or flow collection that starts on a line of its own
+    fn parse_text(&mut self, indent: usize) -> Node {
+        let mut text = Vec::new();
+        while let Some(line) = self.lines.get(self.pos) {
+            if line.is_trivia() || line.indent < indent {
+                break;
+            }
+            text.push(line.text.clone());
+            self.pos += 1;
+        }
+        Node::Text(text)
+    }
+
+    /// The value after a key or `-` indented by `indent`, whose line is
+    /// consumed already
+    fn parse_value(
+        &mut self,
+        rest: &str,
+        indent: usize,
+        in_mapping: bool,
+    ) -> Result<Value, String> {
+        let (end, mut state) = scan(rest, ScanState::default(), true);
+        let mut value = Value {
+            inline: rest[..end].trim_end().to_string(),
+            comment: (end < rest.len()).then(|| rest[end..].to_string()),
+            ..Value::default()
+        };
+
+        if is_block_scalar_header(&value.inline) {
+            // The body is every line indented further, and the blank lines
+            // between them
+            let mut end = self.pos;
+            let mut scan = self.pos;
+            while let Some(line) = self.lines.get(scan) {
+                if !line.text.is_empty() && line.indent <= indent {
+                    break;
+                }
+                scan += 1;
+                if !line.text.is_empty() {
+                    end = scan;
+                }
+            }
+            let body = &self.lines[self.pos..end];
+            let margin = body
+                .iter()
+                .filter(|line| !line.text.is_empty())
+                .map(|line| line.indent)
+                .min()
+                .unwrap_or(0);
+            value.continuation = body
+                .iter()
+                .map(|line| line.raw.get(margin..).unwrap_or_default().to_string())
+                .collect();
+            self.pos = end;
+        } else if value.inline.is_empty() || is_properties(&value.inline) {
+            if let Some(next) = self.peek() {
+                if next.indent > indent {
+                    let indent = next.indent;
+                    value.block = Some(self.parse_node(indent)?);
+                } else if in_mapping && next.indent == indent && is_item(&next.text) {
+                    value.block = Some(self.parse_sequence(indent)?);
+                }
+            }
+        } else if state.is_open() {
+            // A quoted string or flow collection that goes on over the next
+            // lines, until it's closed
+            while state.is_open() {
+                let Some(line) = self.lines.get(self.pos) else {
+                    return Err(format!(
+                        "line {}: unterminated string or collection",
+                        self.lines.len()
+                    ));
+                };
+                state = scan(&line.text, state, false).1;
+                value.continuation.push(line.text.clone());
+                self.pos += 1;
+            }
+        } else {
+            // Plain scalars go on over lines indented further
+            while let Some(line) = self.lines.get(self.pos) {
+                if line.is_trivia() || line.indent <= indent {
+                    break;
+                }
+                value.continuation.push(line.text.clone());
+                self.pos += 1;
+            }
+        }
+        Ok(value)
+    }
+}
+
+fn is_item(text: &str) -> bool {
+    text == "-" || text.starts_with("- ") || text.starts_with("-\t")
+}
+
+/// The key of a `key: value` line and what follows its colon
+fn split_key(text: &str) -> Option<(String, String)> {
+    let start = match text.chars().next()? {
+        quote @ ('"' | '\'') => closing_quote(text, quote)? + 1,
+        '-' if is_item(text) => return None,
+        '#' | '[' | '{' | '&' | '*' | '!' | '|' | '>' | '%' | '@' | '`' | '?' => return None,
+        _ => 0,
+    };
+    let bytes = text.as_bytes();
+    for (i, c) in text[start..].char_indices() {
+        let i = i + start;
+        match c {
+            '#' if i > 0 && bytes[i - 1].is_ascii_whitespace() => return None,
+            ':' if i > 0 && bytes.get(i + 1).is_none_or(|b| b.is_ascii_whitespace()) => {
+                return Some((
+                    text[..i].trim_end().to_string(),
+                    text[i + 1..].trim_start().to_string(),
+                ));
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Byte offset of the quote closing the string `text` starts with
+fn closing_quote(text: &str, quote: char) -> Option<usize> {
+    let mut chars = text.char_indices().skip(1).peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' if quote == '"' => {
+                chars.next();
+            }
+            '\'' if quote == '\'' && chars.peek().is_some_and(|(_, c)| *c == '\'') => {
+                chars.next();
+            }
+            c if c == quote => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// A quoted string or flow collection still open at the end of a line
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct ScanState {
+    quote: Option<char>,
+    depth: usize,
+}
+
+impl ScanState {
+    fn is_open(&self) -> bool {
+        self.quote.is_some() || self.depth > 0
+    }
+}
+
+/// Where the comment of a line of a value starts (or its length when it has
+/// none), and what's left open at its end. `start` is whether the line
+/// starts the value, where quotes and brackets open strings and collections
+/// rather than being part of plain text.
+fn scan(text: &str, mut state: ScanState, start: bool) -> (usize, ScanState) {
+    let mut chars = text.char_indices().peekable();
+    let mut previous = ' ';
+    while let Some((i, c)) = chars.next() {
+        let token_start = (start && i == 0)
+            || (state.depth > 0 && (previous.is_whitespace() || "[{,:".contains(previous)));
+        match state.quote {
+            Some('\'') => {
+                if c == '\'' {
+                    if chars.peek().is_some_and(|(_, c)| *c == '\'') {
+                        chars.next();
+                    } else {
+                        state.quote = None;
+                    }
+                }
+            }
+            Some(_) => match c {
+                '\\' => {
+                    chars.next();
+                }
+                '"' => state.quote = None,
+                _ => {}
+            },
+            None => match c {
+                '#' if previous.is_whitespace() => return (i, state),
+                '\'' | '"' if token_start => state.quote = Some(c),
+                '[' | '{' if token_start => state.depth += 1,
+                ']' | '}' if state.depth > 0 => state.depth -= 1,
+                _ => {}
+            },
+        }
+        previous = c;
+    }
+    (text.len(), state)
+}
+
+/// `|`, `>-` or `&anchor |2+`
+fn is_block_scalar_header(inline: &str) -> bool {
+    let mut tokens: Vec<&str> = inline.split_whitespace().collect();
+    let Some(last) = tokens.pop() else {
+        return false;
+    };
+    (last.starts_with('|') || last.starts_with('>'))
+        && last[1..]
+            .chars()
+            .all(|c| c.is_ascii_digit() || c == '+' || c == '-')
+        && tokens.iter().all(|token| is_property(token))
+}
+
+/// Only the anchor or tag of a collection on the lines below
+fn is_properties(inline: &str) -> bool {
+    !inline.is_empty() && inline.split_whitespace().all(is_property)
+}
+
+fn is_property(token: &str) -> bool {
+    token.starts_with('&') || token.starts_with('!')
+}
+
+fn render_node(node: &Node, indent: usize, out: &mut Vec<String>) {
+    let pad = " ".repeat(indent);
+    match node {
+        Node::Mapping(entries) => {
+            for entry in entries {
+                render_trivia(&entry.leading, &pad, out);
+                let head = format!("{}{}:", pad, entry.key);
+                render_value(head, &entry.value, indent, false, out);
+            }
+        }
+        Node::Sequence(items) => {
+            for item in items {
+                render_trivia(&item.leading, &pad, out);
+                render_value(format!("{}-", pad), &item.value, indent, true, out);
+            }
+        }
+        Node::Text(lines) => out.extend(lines.iter().map(|line| format!("{}{}", pad, line))),
+    }
+}
+
+fn render_trivia(trivia: &[String], pad: &str, out: &mut Vec<String>) {
+    for line in trivia {
+        out.push(if line.is_empty() {
+            String::new()
+        } else {
+            format!("{}{}", pad, line)
+        });
+    }
+}
+
+/// `head` (a key and its colon, or a `-`) followed by `value`
+fn render_value(head: String, value: &Value, indent: usize, item: bool, out: &mut Vec<String>) {
+    let child = indent + INDENT;
+    if let (true, "", None, Some(block @ (Node::Mapping(_) | Node::Sequence(_)))) =
+        (item, value.inline.as_str(), &value.comment, &value.block)
+    {
+        // The collection starts on the `-` line, below the comments above
+        // its first key or item
+        let leading = match block {
+            Node::Mapping(entries) => entries.first().map_or(0, |entry| entry.leading.len()),
+            Node::Sequence(items) => items.first().map_or(0, |item| item.leading.len()),
+            Node::Text(_) => 0,
+        };
+        let start = out.len();
+        render_node(block, child, out);
+        if let Some(first) = out.get_mut(start + leading) {
+            *first = format!("{} {}", head, &first[child..]);
+        } else {
+            out.push(head);
+        }
+        return;
+    }
+
+    let mut line = head;
+    if !value.inline.is_empty() {
+        line.push(' ');
+        line.push_str(&value.inline);
+    }
+    if let Some(comment) = &value.comment {
+        line.push(' ');
+        line.push_str(comment);
+    }
+    out.push(line);
+    let pad = " ".repeat(child);
+    for line in &value.continuation {
+        out.push(if line.is_empty() {
+            String::new()
+        } else {
+            format!("{}{}", pad, line)
+        });
+    }
+    if let Some(block) = &value.block {
+        render_node(block, child, out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_comments_and_reindents() {
+        let content = "\
+# CI
+name:   CI   # the name
+on:
+  push:
+     branches: [ main,
+        'release/*' ]
+jobs:
+    build:
+        runs-on: ubuntu-latest
+
+        # steps below
+        steps:
+        - uses: actions/checkout@v4
+        -   name: Test
+            run: |
+                cargo test
+                  # not a comment
+
+                cargo doc
+        - - nested
+        -
+          env: { A: 1 }
+        - plain text
+          going on
+        - \"quoted # not a comment
+          still quoted\"
+";
+        let document = Document::parse(content).unwrap();
+        let Node::Mapping(root) = &document.root else {
+            panic!("not a mapping");
+        };
+        assert_eq!(root[0].leading, ["# CI"]);
+        assert_eq!(root[0].value.inline, "CI");
+        assert_eq!(root[0].value.comment.as_deref(), Some("# the name"));
+
+        assert_eq!(
+            document.render(),
+            "\
+# CI
+name: CI # the name
+on:
+  push:
+    branches: [ main,
+      'release/*' ]
+jobs:
+  build:
+    runs-on: ubuntu-latest
+
+    # steps below
+    steps:
+      - uses: actions/checkout@v4
+      - name: Test
+        run: |
+          cargo test
+            # not a comment
+
+          cargo doc
+      - - nested
+      - env: { A: 1 }
+      - plain text
+        going on
+      - \"quoted # not a comment
+        still quoted\"
+"
+        );
+        let reparsed = Document::parse(&document.render()).unwrap();
+        assert_eq!(reparsed.render(), document.render());
+
+        assert!(Document::parse("a: 1\n---\nb: 2\n").is_err());
+        assert!(Document::parse("a:\n    b: 1\n  c: 2\n").is_err());
+        assert!(Document::parse("a: 'open\n").is_err());
+        assert_eq!(Document::parse("").unwrap().render(), "");
+    }
+}
@@ -0,0 +1,349 @@
+// Recognizing YAML files that aren't CI workflows at all, and telling
+// GitHub workflows from GitLab pipelines and the CI configs wrkflw only
+// validates
+use serde_yaml::Value;
+use std::fmt;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Platform {
+    GitHub,
+    GitLab,
+    CircleCi,
+    Azure,
+    /// A GitHub Action's metadata file, action.yml
+    Action,
+}
+
+impl Platform {
+    /// Whether wrkflw runs files of this platform, rather than only
+    /// validating them
+    pub fn runs_locally(self) -> bool {
+        matches!(self, Platform::GitHub | Platform::GitLab)
+    }
+}
+
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Platform::GitHub => write!(f, "GitHub workflow"),
+            Platform::GitLab => write!(f, "GitLab pipeline"),
+            Platform::CircleCi => write!(f, "CircleCI config"),
+            Platform::Azure => write!(f, "Azure pipeline"),
+            Platform::Action => write!(f, "GitHub Action"),
+        }
+    }
+}
+
+/// What about a file suggests it's a GitHub workflow or a GitLab pipeline
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Detection {
+    pub github_location: Vec<&'static str>,
+    pub gitlab_location: Vec<&'static str>,
+    pub github_content: Vec<&'static str>,
+    pub gitlab_content: Vec<&'static str>,
+    /// What marks it as a CircleCI or Azure Pipelines config or an action
+    pub other: Vec<(Platform, &'static str)>,
+}
+
+impl Detection {
+    /// Where the file lives decides over what it contains. None when both
+    /// kinds of content match and neither location does, or both do.
+    pub fn platform(&self) -> Option<Platform> {
+        if self.github_location.is_empty() && self.gitlab_location.is_empty() {
+            if let Some((platform, _)) = self.other.first() {
+                return Some(*platform);
+            }
+        }
+        let decide = |github: &[&str], gitlab: &[&str]| match (github.is_empty(), gitlab.is_empty())
+        {
+            (false, true) => Some(Some(Platform::GitHub)),
+            (true, false) => Some(Some(Platform::GitLab)),
+            (false, false) => Some(None),
+            (true, true) => None,
+        };
+        decide(&self.github_location, &self.gitlab_location)
+            .or_else(|| decide(&self.github_content, &self.gitlab_content))
+            // Files nothing points anywhere are read as GitHub workflows
+            .unwrap_or(Some(Platform::GitHub))
+    }
+
+    /// Whether anything about the file points to a CI platform
+    pub fn has_signals(&self) -> bool {
+        !(self.github_signals().is_empty()
+            && self.gitlab_signals().is_empty()
+            && self.other.is_empty())
+    }
+
+    pub fn github_signals(&self) -> Vec<&'static str> {
+        [&self.github_location[..], &self.github_content[..]].concat()
+    }
+
+    pub fn gitlab_signals(&self) -> Vec<&'static str> {
+        [&self.gitlab_location[..], &self.gitlab_content[..]].concat()
+    }
+
+    /// One line listing the signals of both platforms, for debug logs
+    pub fn describe(&self) -> String {
+        let list = |signals: Vec<&str>| {
+            if signals.is_empty() {
+                "none".to_string()
+            } else {
+                signals.join(", ")
+            }
+        };
+        let mut description = format!(
+            "GitHub signals: {}; GitLab signals: {}",
+            list(self.github_signals()),
+            list(self.gitlab_signals())
+        );
+        for (platform, signal) in &self.other {
+            description.push_str(&format!("; {}: {}", platform, signal));
+        }
+        description
+    }
+}
+
+/// Look at a file's name, directory and top-level keys for signs of either
+/// platform
+pub fn detect_platform(path: &Path) -> Detection {
+    let mut detection = Detection::default();
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let parent = path.parent().unwrap_or(Path::new(""));
+    if file_name.ends_with("gitlab-ci.yml") || file_name.ends_with("gitlab-ci.yaml") {
+        detection.gitlab_location.push("named *gitlab-ci.yml");
+    }
+    if parent.ends_with(".gitlab/ci") {
+        detection.gitlab_location.push("in .gitlab/ci");
+    }
+    if parent.ends_with(".github/workflows") {
+        detection.github_location.push("in .github/workflows");
+    }
+    if parent.ends_with(".circleci") {
+        detection.other.push((Platform::CircleCi, "in .circleci"));
+    }
+    if file_name.starts_with("azure-pipelines") {
+        detection
+            .other
+            .push((Platform::Azure, "named azure-pipelines*.yml"));
+    }
+    if parent.ends_with(".azure-pipelines") {
+        detection
+            .other
+            .push((Platform::Azure, "in .azure-pipelines"));
+    }
+    if file_name == "action.yml" || file_name == "action.yaml" {
+        detection.other.push((Platform::Action, "named action.yml"));
+    }
+
+    let document = std::fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_yaml::from_str::<Value>(&content).ok());
+    if let Some(Value::Mapping(map)) = document {
+        let has = |key: &str| map.contains_key(key);
+        let jobs = || map.values().filter(|value| value.is_mapping());
+        if has("on") {
+            detection.github_content.push("top-level on:");
+        }
+        if map
+            .get("jobs")
+            .and_then(Value::as_mapping)
+            .is_some_and(|jobs| jobs.values().any(|job| job.get("runs-on").is_some()))
+        {
+            detection.github_content.push("jobs with runs-on:");
+        }
+        if has("stages") {
+            detection.gitlab_content.push("top-level stages:");
+        }
+        if has("before_script") || has("after_script") {
+            detection
+                .gitlab_content
+                .push("top-level before_script:/after_script:");
+        }
+        if jobs().any(|job| job.get("script").is_some()) {
+            detection.gitlab_content.push("jobs with script:");
+        }
+        if has("version") && (has("workflows") || has("orbs")) {
+            detection
+                .other
+                .push((Platform::CircleCi, "version: with workflows: or orbs:"));
+        }
+        let lists = |key: &str, items: &[&str]| {
+            map.get(key)
+                .and_then(Value::as_sequence)
+                .is_some_and(|list| {
+                    list.iter()
+                        .any(|item| items.iter().any(|key| item.get(key).is_some()))
+                })
+        };
+        if map
+            .get("runs")
+            .is_some_and(|runs| runs.get("using").is_some())
+        {
+            detection.other.push((Platform::Action, "runs.using:"));
+        }
+        if has("trigger") || has("pool") || lists("stages", &["stage"]) {
+            detection
+                .other
+                .push((Platform::Azure, "top-level trigger:, pool: or stage list"));
+        } else if lists("jobs", &["job", "deployment"]) || lists("steps", &["script", "task"]) {
+            detection
+                .other
+                .push((Platform::Azure, "top-level job or step list"));
+        }
+    }
+    detection
+}
+
+/// Describe what kind of file a YAML document is when it's clearly not a
+/// GitHub workflow or GitLab pipeline, e.g. "a docker-compose file"
+pub fn non_workflow_kind(document: &Value) -> Option<String> {
+    if let Value::Sequence(items) = document {
+        let is_playbook = !items.is_empty()
+            && items
+                .iter()
+                .all(|play| play.get("hosts").is_some() || play.get("import_playbook").is_some());
+        return is_playbook.then(|| "an Ansible playbook".to_string());
+    }
+    let Value::Mapping(map) = document else {
+        return None;
+    };
+    let has = |key: &str| map.contains_key(key);
+
+    // Anything with triggers, or GitLab's stages, is left to the validators
+    if has("on") || has("stages") {
+        return None;
+    }
+
+    if let (Some(_), Some(kind)) = (map.get("apiVersion"), map.get("kind")) {
+        let kind = kind.as_str().unwrap_or("resource");
+        return Some(format!("a Kubernetes {} manifest", kind));
+    }
+    if has("openapi") || has("swagger") {
+        return Some("an OpenAPI specification".to_string());
+    }
+    if map
+        .get("runs")
+        .is_some_and(|runs| runs.get("using").is_some())
+    {
+        return Some("a GitHub Action's metadata file (action.yml)".to_string());
+    }
+    if has("updates") && has("version") {
+        return Some("a Dependabot configuration".to_string());
+    }
+    if has("language") && (has("script") || has("install")) {
+        return Some("a Travis CI configuration".to_string());
+    }
+    if let Some(Value::Mapping(services)) = map.get("services") {
+        let compose_service = |service: &Value| {
+            ["image", "build", "ports", "volumes", "depends_on"]
+                .iter()
+                .any(|key| service.get(key).is_some())
+        };
+        if !has("jobs") && services.values().any(compose_service) {
+            return Some("a docker-compose file".to_string());
+        }
+    }
+    None
+}
+
+/// The error shown instead of schema errors for files that aren't workflows
+pub fn non_workflow_message(kind: &str) -> String {
+    format!(
+        "This looks like {}, not a CI workflow. wrkflw runs GitHub Actions workflows (.github/workflows/*.yml) and GitLab pipelines (.gitlab-ci.yml).",
+        kind
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_the_platform_from_location_then_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, content: &str| {
+            let path = dir.path().join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, content).unwrap();
+            path
+        };
+        let github = "on: push\njobs:\n  test:\n    runs-on: ubuntu-latest\n    steps: []\n";
+        let gitlab = "stages: [test]\ntest:\n  stage: test\n  script: make\n";
+        let both = format!("{}stages: [test]\nlint:\n  script: make lint\n", github);
+
+        let detect = |path: &Path| detect_platform(path).platform();
+        assert_eq!(detect(&write("ci.yml", github)), Some(Platform::GitHub));
+        assert_eq!(detect(&write("ci.yml", gitlab)), Some(Platform::GitLab));
+        assert_eq!(detect(&write("ci.yml", &both)), None);
+        assert_eq!(
+            detect(&write("notes.yml", "a: 1\n")),
+            Some(Platform::GitHub)
+        );
+
+        // A conventional location settles mixed content
+        let located = write(".gitlab-ci.yml", &both);
+        assert_eq!(detect(&located), Some(Platform::GitLab));
+        assert_eq!(
+            detect(&write(".github/workflows/ci.yml", &both)),
+            Some(Platform::GitHub)
+        );
+        assert_eq!(
+            detect(&write(".circleci/config.yml", "version: 2.1\njobs: {}\n")),
+            Some(Platform::CircleCi)
+        );
+        assert_eq!(
+            detect(&write(
+                "ci.yml",
+                "trigger: [main]\npool:\n  vmImage: ubuntu-latest\nsteps:\n  - script: make\n"
+            )),
+            Some(Platform::Azure)
+        );
+        let action = "name: Setup\nruns:\n  using: composite\n  steps: []\n";
+        assert_eq!(
+            detect(&write("tools/setup/action.yml", "name: Setup\n")),
+            Some(Platform::Action)
+        );
+        assert_eq!(detect(&write("setup.yml", action)), Some(Platform::Action));
+        assert_eq!(
+            detect_platform(&located).describe(),
+            "GitHub signals: top-level on:, jobs with runs-on:; GitLab signals: named *gitlab-ci.yml, top-level stages:, jobs with script:"
+        );
+    }
+
+    fn kind(yaml: &str) -> Option<String> {
+        non_workflow_kind(&serde_yaml::from_str(yaml).unwrap())
+    }
+
+    #[test]
+    fn recognizes_other_yaml_files() {
+        assert_eq!(
+            kind("services:\n  db:\n    image: postgres:16\n    ports: ['5432:5432']\n").as_deref(),
+            Some("a docker-compose file")
+        );
+        assert_eq!(
+            kind("apiVersion: apps/v1\nkind: Deployment\nmetadata:\n  name: web\n").as_deref(),
+            Some("a Kubernetes Deployment manifest")
+        );
+        assert_eq!(
+            kind("- hosts: all\n  tasks: []\n").as_deref(),
+            Some("an Ansible playbook")
+        );
+        assert_eq!(
+            kind("name: Setup\nruns:\n  using: node20\n  main: index.js\n").as_deref(),
+            Some("a GitHub Action's metadata file (action.yml)")
+        );
+
+        // Workflows, including ones with service containers, aren't flagged
+        assert_eq!(
+            kind("on: push\njobs:\n  test:\n    runs-on: ubuntu-latest\n    services:\n      db:\n        image: postgres\n    steps: []\n"),
+            None
+        );
+        assert_eq!(kind("name: CI\njobs: {}\n"), None);
+        assert_eq!(kind("stages: [build]\nbuild:\n  script: make\n"), None);
+    }
+}
@@ -0,0 +1,177 @@
+// Where the nodes of a YAML document are, by their path, so diagnostics can
+// point at the line and column of the key or item they're about
+use std::collections::HashMap;
+use yaml_rust2::parser::{Event, MarkedEventReceiver, Parser};
+use yaml_rust2::scanner::Marker;
+
+/// 1-based line and column of each node of a document, keyed by paths like
+/// `jobs.build.steps[1].if`. Mapping values are found at their key.
+#[derive(Debug, Default)]
+pub struct SpanIndex {
+    spans: HashMap<String, (usize, usize)>,
+}
+
+impl SpanIndex {
+    /// Index the first document in `content`; YAML that doesn't parse gives
+    /// an empty index, as its syntax error is reported otherwise
+    pub fn new(content: &str) -> Self {
+        let mut builder = Builder::default();
+        if Parser::new_from_str(content)
+            .load(&mut builder, false)
+            .is_err()
+        {
+            return SpanIndex::default();
+        }
+        SpanIndex {
+            spans: builder.spans,
+        }
+    }
+
+    /// Position of the node at `path`, or of its closest ancestor that the
+    /// document has
+    pub fn locate(&self, path: &str) -> Option<(usize, usize)> {
+        let mut path = path;
+        loop {
+            if let Some(span) = self.spans.get(path) {
+                return Some(*span);
+            }
+            path = parent(path)?;
+        }
+    }
+}
+
+fn parent(path: &str) -> Option<&str> {
+    let end = if path.ends_with(']') {
+        path.rfind('[')?
+    } else {
+        path.rfind('.')?
+    };
+    Some(&path[..end])
+}
+
+enum Frame {
+    Mapping {
+        path: String,
+        /// The key whose value comes next, or `None` when a key does
+        key: Option<String>,
+    },
+    Sequence {
+        path: String,
+        index: usize,
+    },
+}
+
+#[derive(Default)]
+struct Builder {
+    stack: Vec<Frame>,
+    spans: HashMap<String, (usize, usize)>,
+}
+
+impl Builder {
+    /// Path of a node starting at `mark`, recording where it is; `None` for
+    /// mapping keys, which are recorded as the position of their value
+    fn node_path(&mut self, event: &Event, mark: Marker) -> Option<String> {
+        let position = (mark.line(), mark.col() + 1);
+        let path = match self.stack.last_mut() {
+            None => String::new(),
+            Some(Frame::Mapping { path, key }) => match key.take() {
+                Some(key) => join(path, &key),
+                None => {
+                    // Keys that aren't scalars get a placeholder name
+                    let name = match event {
+                        Event::Scalar(value, ..) => value.clone(),
+                        _ => "?".to_string(),
+                    };
+                    self.spans.entry(join(path, &name)).or_insert(position);
+                    // Mappings in a list start where their first key does
+                    self.spans.entry(path.clone()).or_insert(position);
+                    *key = Some(name);
+                    return None;
+                }
+            },
+            Some(Frame::Sequence { path, index }) => {
+                let item = format!("{}[{}]", path, index);
+                *index += 1;
+                if !matches!(event, Event::MappingStart(..)) {
+                    self.spans.entry(item.clone()).or_insert(position);
+                }
+                item
+            }
+        };
+        Some(path)
+    }
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+impl MarkedEventReceiver for Builder {
+    fn on_event(&mut self, event: Event, mark: Marker) {
+        match event {
+            Event::Scalar(..) | Event::Alias(_) => {
+                self.node_path(&event, mark);
+            }
+            Event::MappingStart(..) | Event::SequenceStart(..) => {
+                // A key that is itself a collection is indexed under its
+                // placeholder name
+                let path =
+                    self.node_path(&event, mark)
+                        .unwrap_or_else(|| match self.stack.last() {
+                            Some(Frame::Mapping { path, .. }) => join(path, "?"),
+                            _ => String::new(),
+                        });
+                self.stack.push(match event {
+                    Event::MappingStart(..) => Frame::Mapping { path, key: None },
+                    _ => Frame::Sequence { path, index: 0 },
+                });
+            }
+            Event::MappingEnd | Event::SequenceEnd => {
+                self.stack.pop();
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locates_keys_items_and_ancestors() {
+        let index = SpanIndex::new(
+            "\
+name: CI
+on: push
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@v4
+      - name: Test
+        if: success() &&
+        run: cargo test
+    strategy: { matrix: { os: [a, 1] } }
+",
+        );
+        assert_eq!(index.locate("name"), Some((1, 1)));
+        assert_eq!(index.locate("jobs.build"), Some((4, 3)));
+        assert_eq!(index.locate("jobs.build.steps[0]"), Some((7, 9)));
+        assert_eq!(index.locate("jobs.build.steps[1].if"), Some((9, 9)));
+        assert_eq!(
+            index.locate("jobs.build.strategy.matrix.os[1]"),
+            Some((11, 35))
+        );
+        // Nodes that aren't there fall back to the closest one that is
+        assert_eq!(index.locate("jobs.build.needs"), Some((4, 3)));
+        assert_eq!(index.locate("jobs.build.steps[5].uses"), Some((6, 5)));
+        assert_eq!(index.locate("env"), None);
+
+        assert_eq!(SpanIndex::new("jobs: [").locate("jobs"), None);
+    }
+}
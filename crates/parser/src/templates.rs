@@ -0,0 +1,208 @@
+// Starter workflows `wrkflw init` writes: the built-in templates and the
+// ones users keep in their templates directory
+use crate::convert::{workflow_to_pipeline, Conversion};
+use crate::sniff::{self, Platform};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// Overrides where user templates are read from
+pub const TEMPLATES_DIR_ENV: &str = "WRKFLW_TEMPLATES_DIR";
+
+const BUILTIN: &[(&str, &str)] = &[
+    (
+        "docker-publish",
+        include_str!("../templates/docker-publish.yml"),
+    ),
+    ("go", include_str!("../templates/go.yml")),
+    ("node", include_str!("../templates/node.yml")),
+    ("python", include_str!("../templates/python.yml")),
+    ("release", include_str!("../templates/release.yml")),
+    ("rust", include_str!("../templates/rust.yml")),
+];
+
+/// `%{name}` in a template's content, replaced with the option's value
+static PLACEHOLDER: Lazy<Regex> = Lazy::new(|| Regex::new(r"%\{([A-Za-z0-9_-]+)\}").unwrap());
+
+/// A starter workflow or pipeline with the options it asks for
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Template {
+    /// File stem of the template's definition
+    #[serde(skip)]
+    pub name: String,
+    pub description: String,
+    /// Where the result goes, relative to the repository root
+    pub file: PathBuf,
+    #[serde(default)]
+    pub options: Vec<TemplateOption>,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TemplateOption {
+    pub name: String,
+    /// Question asked for the value
+    pub prompt: String,
+    #[serde(default)]
+    pub default: String,
+    /// Comma-separated values, written as quoted items of a YAML flow
+    /// sequence like `"main", "develop"`
+    #[serde(default)]
+    pub list: bool,
+}
+
+impl Template {
+    /// Parse a template definition, checking that its content only uses the
+    /// options it declares
+    pub fn parse(name: &str, definition: &str) -> Result<Self, String> {
+        let mut template: Template = serde_yaml::from_str(definition)
+            .map_err(|e| format!("Invalid template '{}': {}", name, e))?;
+        template.name = name.to_string();
+        for placeholder in PLACEHOLDER.captures_iter(&template.content) {
+            if template.option(&placeholder[1]).is_none() {
+                return Err(format!(
+                    "Template '{}' uses %{{{}}}, which isn't one of its options",
+                    name, &placeholder[1]
+                ));
+            }
+        }
+        Ok(template)
+    }
+
+    pub fn option(&self, name: &str) -> Option<&TemplateOption> {
+        self.options.iter().find(|option| option.name == name)
+    }
+
+    /// What the template generates, going by where it writes it
+    pub fn platform(&self) -> Platform {
+        sniff::detect_platform(&self.file)
+            .platform()
+            .unwrap_or(Platform::GitHub)
+    }
+
+    /// The content with every option's value, or its default when `values`
+    /// has none
+    pub fn render(&self, values: &HashMap<String, String>) -> String {
+        PLACEHOLDER
+            .replace_all(&self.content, |placeholder: &regex::Captures| {
+                let option = self
+                    .option(&placeholder[1])
+                    .expect("placeholders are checked when parsing");
+                let value = values.get(&option.name).unwrap_or(&option.default);
+                option.format(value)
+            })
+            .into_owned()
+    }
+}
+
+impl TemplateOption {
+    fn format(&self, value: &str) -> String {
+        if !self.list {
+            return value.trim().to_string();
+        }
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(|item| serde_json::Value::from(item).to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Where user templates live: `$WRKFLW_TEMPLATES_DIR`, or
+/// `~/.wrkflw/templates`
+pub fn templates_dir() -> Option<PathBuf> {
+    match std::env::var(TEMPLATES_DIR_ENV) {
+        Ok(dir) if !dir.is_empty() => Some(PathBuf::from(dir)),
+        _ => dirs::home_dir().map(|home| home.join(".wrkflw").join("templates")),
+    }
+}
+
+/// The built-in templates and the `*.yml` ones in `dir`, which replace
+/// built-in ones of the same name, sorted by name
+pub fn load_templates(dir: Option<&Path>) -> Result<Vec<Template>, String> {
+    let mut templates = BTreeMap::new();
+    for (name, definition) in BUILTIN {
+        templates.insert(name.to_string(), Template::parse(name, definition)?);
+    }
+    let entries = match dir.map(std::fs::read_dir) {
+        Some(Ok(entries)) => entries,
+        // No templates directory is fine
+        _ => return Ok(templates.into_values().collect()),
+    };
+    for path in entries.flatten().map(|entry| entry.path()) {
+        let is_yaml = path
+            .extension()
+            .is_some_and(|ext| ext == "yml" || ext == "yaml");
+        let name = match path.file_stem() {
+            Some(stem) if is_yaml => stem.to_string_lossy(),
+            _ => continue,
+        };
+        let definition = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        templates.insert(name.to_string(), Template::parse(&name, &definition)?);
+    }
+    Ok(templates.into_values().collect())
+}
+
+/// A rendered GitHub workflow as a GitLab CI pipeline
+pub fn to_pipeline(workflow: &str) -> Result<Conversion, String> {
+    let workflow = serde_yaml::from_str(workflow)
+        .map_err(|e| format!("Failed to parse the generated workflow: {}", e))?;
+    Ok(workflow_to_pipeline(&workflow))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_builtin_and_user_templates() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("rust.yml"),
+            "description: Ours\nfile: .gitlab-ci.yml\noptions:\n  - name: image\n    prompt: Image\n    default: rust:1\ncontent: |\n  test:\n    image: %{image}\n    script: [cargo test]\n",
+        )
+        .unwrap();
+        let templates = load_templates(Some(dir.path())).unwrap();
+        let names: Vec<&str> = templates.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["docker-publish", "go", "node", "python", "release", "rust"]
+        );
+        let ours = templates.iter().find(|t| t.name == "rust").unwrap();
+        assert_eq!(ours.platform(), Platform::GitLab);
+        assert_eq!(
+            ours.render(&HashMap::new()),
+            "test:\n  image: rust:1\n  script: [cargo test]\n"
+        );
+
+        let python = load_templates(None)
+            .unwrap()
+            .into_iter()
+            .find(|t| t.name == "python")
+            .unwrap();
+        let values = HashMap::from([("branches".to_string(), "main, develop".to_string())]);
+        let rendered = python.render(&values);
+        assert!(rendered.contains("branches: [\"main\", \"develop\"]"));
+        assert!(rendered.contains("python-version: [\"3.11\", \"3.12\", \"3.13\"]"));
+        for template in load_templates(None).unwrap() {
+            let workflow: serde_yaml::Value =
+                serde_yaml::from_str(&template.render(&HashMap::new())).unwrap();
+            assert!(workflow.get("jobs").is_some(), "{}", template.name);
+        }
+        assert!(to_pipeline(&rendered).is_ok());
+
+        assert!(Template::parse(
+            "broken",
+            "description: x\nfile: a.yml\ncontent: \"%{missing}\"\n"
+        )
+        .unwrap_err()
+        .contains("%{missing}"));
+    }
+}
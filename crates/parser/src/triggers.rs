@@ -0,0 +1,374 @@
+// Matching events against a workflow's `on:` block
+use models::github::{On, StringList};
+use regex::Regex;
+use serde_json::Value as JsonValue;
+
+/// Activity types a `pull_request` trigger without `types:` listens to
+const DEFAULT_PULL_REQUEST_TYPES: &[&str] = &["opened", "synchronize", "reopened"];
+
+/// An event to check a workflow's triggers against
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TriggerEvent {
+    pub name: String,
+    /// Activity type, e.g. `opened` for `pull_request`; types aren't checked when unset
+    pub action: Option<String>,
+    /// Branch pushed to, or the base branch of a pull request
+    pub branch: Option<String>,
+    /// Tag pushed to
+    pub tag: Option<String>,
+    /// Changed files relative to the repository root; path filters
+    /// aren't checked when unknown
+    pub changed_files: Option<Vec<String>>,
+}
+
+impl TriggerEvent {
+    /// Take the activity type, branch and tag from a webhook payload
+    pub fn from_payload(name: &str, payload: &JsonValue) -> Self {
+        let text = |pointer: &str| {
+            payload
+                .pointer(pointer)
+                .and_then(JsonValue::as_str)
+                .map(str::to_string)
+        };
+        let git_ref = text("/ref").unwrap_or_default();
+
+        let mut event = TriggerEvent {
+            name: name.to_string(),
+            action: text("/action"),
+            ..Default::default()
+        };
+        if name.starts_with("pull_request") {
+            event.branch = text("/pull_request/base/ref");
+        } else if let Some(tag) = git_ref.strip_prefix("refs/tags/") {
+            event.tag = Some(tag.to_string());
+        } else if !git_ref.is_empty() {
+            let branch = git_ref.strip_prefix("refs/heads/").unwrap_or(&git_ref);
+            event.branch = Some(branch.to_string());
+        }
+        event
+    }
+}
+
+/// Whether an event starts a workflow
+#[derive(Debug, Clone, PartialEq)]
+pub enum TriggerDecision {
+    Runs,
+    /// Why the workflow doesn't run
+    Skipped(String),
+}
+
+/// Check `event` against the `on:` block of a workflow, including its
+/// branch, tag, path and activity type filters
+pub fn check_trigger(on: &On, event: &TriggerEvent) -> TriggerDecision {
+    if !on.listens_to(&event.name) {
+        return not_listed(event);
+    }
+    let Some(config) = on.config(&event.name) else {
+        return match event.name.as_str() {
+            name if name.starts_with("pull_request") => check_types(None, event),
+            _ => TriggerDecision::Runs,
+        };
+    };
+    let filter = |list: &Option<StringList>| -> Option<Vec<String>> {
+        list.as_ref().map(|l| l.values().to_vec())
+    };
+
+    if let TriggerDecision::Skipped(reason) = check_types(filter(&config.types), event) {
+        return TriggerDecision::Skipped(reason);
+    }
+
+    let branches = filter(&config.branches);
+    let branches_ignore = filter(&config.branches_ignore);
+    let tags = filter(&config.tags);
+    let tags_ignore = filter(&config.tags_ignore);
+    let has_branch_filter = branches.is_some() || branches_ignore.is_some();
+    let has_tag_filter = tags.is_some() || tags_ignore.is_some();
+
+    if let Some(tag) = &event.tag {
+        // Pushing a tag only checks the tag filters
+        if has_branch_filter && !has_tag_filter {
+            return TriggerDecision::Skipped(format!(
+                "only branch pushes trigger it, not tag '{}'",
+                tag
+            ));
+        }
+        return check_ref(tag, "tag", tags, tags_ignore);
+    }
+
+    if let Some(branch) = &event.branch {
+        if has_tag_filter && !has_branch_filter && event.name == "push" {
+            return TriggerDecision::Skipped(format!(
+                "only tag pushes trigger it, not branch '{}'",
+                branch
+            ));
+        }
+        if let TriggerDecision::Skipped(reason) =
+            check_ref(branch, "branch", branches, branches_ignore)
+        {
+            return TriggerDecision::Skipped(reason);
+        }
+    }
+
+    match &event.changed_files {
+        Some(files) => check_paths(files, filter(&config.paths), filter(&config.paths_ignore)),
+        None => TriggerDecision::Runs,
+    }
+}
+
+fn not_listed(event: &TriggerEvent) -> TriggerDecision {
+    TriggerDecision::Skipped(format!("'{}' is not one of its triggers", event.name))
+}
+
+fn check_types(types: Option<Vec<String>>, event: &TriggerEvent) -> TriggerDecision {
+    let Some(action) = &event.action else {
+        return TriggerDecision::Runs;
+    };
+    let types = match types {
+        Some(types) => types,
+        None if event.name.starts_with("pull_request") => DEFAULT_PULL_REQUEST_TYPES
+            .iter()
+            .map(|t| t.to_string())
+            .collect(),
+        None => return TriggerDecision::Runs,
+    };
+    if types.contains(action) {
+        TriggerDecision::Runs
+    } else {
+        TriggerDecision::Skipped(format!(
+            "activity type '{}' is not one of {}",
+            action,
+            types.join(", ")
+        ))
+    }
+}
+
+fn check_ref(
+    name: &str,
+    kind: &str,
+    include: Option<Vec<String>>,
+    ignore: Option<Vec<String>>,
+) -> TriggerDecision {
+    if let Some(patterns) = include {
+        if !Filter::new(&patterns).matches(name) {
+            return TriggerDecision::Skipped(format!(
+                "{} '{}' doesn't match {}",
+                kind,
+                name,
+                patterns.join(", ")
+            ));
+        }
+    }
+    if let Some(patterns) = ignore {
+        if Filter::new(&patterns).matches(name) {
+            return TriggerDecision::Skipped(format!(
+                "{} '{}' is ignored by {}",
+                kind,
+                name,
+                patterns.join(", ")
+            ));
+        }
+    }
+    TriggerDecision::Runs
+}
+
+fn check_paths(
+    files: &[String],
+    include: Option<Vec<String>>,
+    ignore: Option<Vec<String>>,
+) -> TriggerDecision {
+    if let Some(patterns) = include {
+        let filter = Filter::new(&patterns);
+        if !files.iter().any(|file| filter.matches(file)) {
+            return TriggerDecision::Skipped(format!(
+                "no changed file matches {}",
+                patterns.join(", ")
+            ));
+        }
+    }
+    if let Some(patterns) = ignore {
+        let filter = Filter::new(&patterns);
+        if files.iter().all(|file| filter.matches(file)) {
+            return TriggerDecision::Skipped(format!(
+                "every changed file is ignored by {}",
+                patterns.join(", ")
+            ));
+        }
+    }
+    TriggerDecision::Runs
+}
+
+/// A list of filter patterns, compiled once for matching many values
+pub struct Filter {
+    /// Each pattern's regex, `None` when it doesn't compile, and whether it
+    /// excludes
+    patterns: Vec<(Option<Regex>, bool)>,
+}
+
+impl Filter {
+    pub fn new(patterns: &[String]) -> Self {
+        let patterns = patterns
+            .iter()
+            .map(|pattern| {
+                let (negated, pattern) = match pattern.strip_prefix('!') {
+                    Some(pattern) => (true, pattern),
+                    None => (false, pattern.as_str()),
+                };
+                (Regex::new(&filter_pattern_regex(pattern)).ok(), negated)
+            })
+            .collect();
+        Filter { patterns }
+    }
+
+    /// Whether a value passes. Patterns starting with `!` exclude, and the
+    /// last pattern that matches decides.
+    pub fn matches(&self, value: &str) -> bool {
+        let mut matched = false;
+        for (regex, negated) in &self.patterns {
+            if regex.as_ref().is_some_and(|regex| regex.is_match(value)) {
+                matched = !negated;
+            }
+        }
+        matched
+    }
+}
+
+/// Whether a value passes a list of filter patterns, as [`Filter::matches`]
+pub fn matches_filter(patterns: &[String], value: &str) -> bool {
+    Filter::new(patterns).matches(value)
+}
+
+/// Match a branch, tag or path against a GitHub filter pattern: `*` stops
+/// at `/`, `**` doesn't, `**/` also matches no directory at all, and `?`
+/// and `+` repeat the character before them
+pub fn filter_pattern_matches(pattern: &str, value: &str) -> bool {
+    Regex::new(&filter_pattern_regex(pattern)).is_ok_and(|regex| regex.is_match(value))
+}
+
+/// A GitHub filter pattern as an anchored regular expression
+pub fn filter_pattern_regex(pattern: &str) -> String {
+    let mut regex = String::from("^");
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    regex.push_str("(?:.*/)?");
+                } else {
+                    regex.push_str(".*");
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' | '+' => regex.push(c),
+            '[' => {
+                let mut class = String::from("[");
+                for c in chars.by_ref() {
+                    class.push(c);
+                    if c == ']' {
+                        break;
+                    }
+                }
+                regex.push_str(&class);
+            }
+            '\\' => {
+                if let Some(escaped) = chars.next() {
+                    regex.push_str(&regex::escape(&escaped.to_string()));
+                }
+            }
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn on(yaml: &str) -> On {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    fn push(branch: &str, files: &[&str]) -> TriggerEvent {
+        TriggerEvent {
+            name: "push".to_string(),
+            branch: Some(branch.to_string()),
+            changed_files: Some(files.iter().map(|f| f.to_string()).collect()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn matches_filter_patterns() {
+        assert!(filter_pattern_matches("releases/*", "releases/v1"));
+        assert!(!filter_pattern_matches("releases/*", "releases/v1/hotfix"));
+        assert!(filter_pattern_matches("releases/**", "releases/v1/hotfix"));
+        assert!(filter_pattern_matches("**.js", "src/app/index.js"));
+        assert!(filter_pattern_matches("v[12].[0-9]+.*", "v1.10.3"));
+        assert!(filter_pattern_matches("docs/*.md", "docs/intro.md"));
+        assert!(!filter_pattern_matches("docs/*.md", "docs/api/intro.md"));
+        assert!(filter_pattern_matches("docs/**/*.md", "docs/x.md"));
+        assert!(filter_pattern_matches("docs/**/*.md", "docs/api/v1/x.md"));
+        assert!(!filter_pattern_matches("docs/**/*.md", "docsx.md"));
+        assert!(filter_pattern_matches("**/*.md", "README.md"));
+
+        let patterns = vec!["releases/**".to_string(), "!releases/**-alpha".to_string()];
+        assert!(matches_filter(&patterns, "releases/v2"));
+        assert!(!matches_filter(&patterns, "releases/v2-alpha"));
+    }
+
+    #[test]
+    fn checks_push_filters() {
+        let on = on(r#"
+push:
+  branches: [main, 'releases/**']
+  paths: ['src/**', '!src/**/*.md']
+pull_request:
+  types: [opened]
+"#);
+        assert_eq!(
+            check_trigger(&on, &push("main", &["src/lib.rs"])),
+            TriggerDecision::Runs
+        );
+        assert!(matches!(
+            check_trigger(&on, &push("feature", &["src/lib.rs"])),
+            TriggerDecision::Skipped(reason) if reason.contains("branch 'feature'")
+        ));
+        assert!(matches!(
+            check_trigger(&on, &push("main", &["src/guide/README.md", "Cargo.toml"])),
+            TriggerDecision::Skipped(reason) if reason.starts_with("no changed file")
+        ));
+
+        let tag = TriggerEvent {
+            name: "push".to_string(),
+            tag: Some("v1.0".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            check_trigger(&on, &tag),
+            TriggerDecision::Skipped(_)
+        ));
+
+        let payload = serde_json::json!({
+            "action": "synchronize",
+            "pull_request": {"base": {"ref": "main"}}
+        });
+        let pull_request = TriggerEvent::from_payload("pull_request", &payload);
+        assert_eq!(pull_request.branch.as_deref(), Some("main"));
+        assert!(matches!(
+            check_trigger(&on, &pull_request),
+            TriggerDecision::Skipped(reason) if reason.contains("'synchronize'")
+        ));
+
+        let dispatch = TriggerEvent {
+            name: "workflow_dispatch".to_string(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            check_trigger(&on, &dispatch),
+            TriggerDecision::Skipped(_)
+        ));
+    }
+}
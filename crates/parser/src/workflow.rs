@@ -1,81 +1,18 @@
-use matrix::MatrixConfig;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use models::github::Workflow;
 use std::fs;
 use std::path::Path;
 
 use super::schema::SchemaValidator;
+use super::sniff;
 
-#[derive(Debug, Deserialize, Serialize)]
-pub struct WorkflowDefinition {
-    pub name: String,
-    #[serde(skip, default)] // Skip deserialization of the 'on' field directly
-    pub on: Vec<String>,
-    #[serde(rename = "on")] // Raw access to the 'on' field for custom handling
-    pub on_raw: serde_yaml::Value,
-    pub jobs: HashMap<String, Job>,
-}
-
-#[derive(Debug, Deserialize, Serialize)]
-pub struct Job {
-    #[serde(rename = "runs-on")]
-    pub runs_on: String,
-    #[serde(default)]
-    pub needs: Option<Vec<String>>,
-    pub steps: Vec<Step>,
-    #[serde(default)]
-    pub env: HashMap<String, String>,
-    #[serde(default)]
-    pub matrix: Option<MatrixConfig>,
-    #[serde(default)]
-    pub services: HashMap<String, Service>,
-}
+/// Resolve a step's `uses:` reference like "actions/checkout@v3"
+pub fn resolve_action(action_ref: &str) -> ActionInfo {
+    let repo = action_ref.split('@').next().unwrap_or(action_ref);
 
-#[derive(Debug, Deserialize, Serialize)]
-pub struct Service {
-    pub image: String,
-    #[serde(default)]
-    pub ports: Option<Vec<String>>,
-    #[serde(default)]
-    pub env: HashMap<String, String>,
-    #[serde(default)]
-    pub volumes: Option<Vec<String>>,
-    #[serde(default)]
-    pub options: Option<String>,
-}
-
-#[derive(Debug, Deserialize, Serialize)]
-pub struct Step {
-    #[serde(default)]
-    pub name: Option<String>,
-    #[serde(default)]
-    pub uses: Option<String>,
-    #[serde(default)]
-    pub run: Option<String>,
-    #[serde(default)]
-    pub with: Option<HashMap<String, String>>,
-    #[serde(default)]
-    pub env: HashMap<String, String>,
-    #[serde(default)]
-    pub continue_on_error: Option<bool>,
-}
-
-impl WorkflowDefinition {
-    pub fn resolve_action(&self, action_ref: &str) -> ActionInfo {
-        // Parse GitHub action reference like "actions/checkout@v3"
-        let parts: Vec<&str> = action_ref.split('@').collect();
-
-        let (repo, _) = if parts.len() > 1 {
-            (parts[0], parts[1])
-        } else {
-            (parts[0], "main") // Default to main if no version specified
-        };
-
-        ActionInfo {
-            repository: repo.to_string(),
-            is_docker: repo.starts_with("docker://"),
-            is_local: repo.starts_with("./"),
-        }
+    ActionInfo {
+        repository: repo.to_string(),
+        is_docker: repo.starts_with("docker://"),
+        is_local: repo.starts_with("./"),
     }
 }
 
@@ -86,53 +23,19 @@ pub struct ActionInfo {
     pub is_local: bool,
 }
 
-pub fn parse_workflow(path: &Path) -> Result<WorkflowDefinition, String> {
-    // First validate against schema
-    let validator = SchemaValidator::new()?;
-    validator.validate_workflow(path)?;
-
-    // If validation passes, parse the workflow
+pub fn parse_workflow(path: &Path) -> Result<Workflow, String> {
     let content =
         fs::read_to_string(path).map_err(|e| format!("Failed to read workflow file: {}", e))?;
 
-    // Parse the YAML content
-    let mut workflow: WorkflowDefinition = serde_yaml::from_str(&content)
-        .map_err(|e| format!("Failed to parse workflow structure: {}", e))?;
-
-    // Normalize the trigger events
-    workflow.on = normalize_triggers(&workflow.on_raw)?;
-
-    Ok(workflow)
-}
-
-fn normalize_triggers(on_value: &serde_yaml::Value) -> Result<Vec<String>, String> {
-    let mut triggers = Vec::new();
-
-    match on_value {
-        // Simple string trigger: on: push
-        serde_yaml::Value::String(event) => {
-            triggers.push(event.clone());
-        }
-        // Array of triggers: on: [push, pull_request]
-        serde_yaml::Value::Sequence(events) => {
-            for event in events {
-                if let Some(event_str) = event.as_str() {
-                    triggers.push(event_str.to_string());
-                }
-            }
-        }
-        // Map of triggers with configuration: on: {push: {branches: [main]}}
-        serde_yaml::Value::Mapping(events_map) => {
-            for (event, _) in events_map {
-                if let Some(event_str) = event.as_str() {
-                    triggers.push(event_str.to_string());
-                }
-            }
-        }
-        _ => {
-            return Err("'on' section has invalid format".to_string());
-        }
+    // Files that aren't workflows at all get one clear error instead of schema errors
+    let document: Option<serde_yaml::Value> = serde_yaml::from_str(&content).ok();
+    if let Some(kind) = document.as_ref().and_then(sniff::non_workflow_kind) {
+        return Err(sniff::non_workflow_message(&kind));
     }
 
-    Ok(triggers)
+    // First validate against schema
+    let validator = SchemaValidator::new()?;
+    validator.validate_workflow(path)?;
+
+    serde_yaml::from_str(&content).map_err(|e| format!("Failed to parse workflow structure: {}", e))
 }
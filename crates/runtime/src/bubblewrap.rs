@@ -0,0 +1,156 @@
+// Running emulated steps inside bubblewrap (bwrap) sandboxes on Linux, a
+// lighter stand-in for containers when neither Docker nor Podman is there
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// What jobs miss compared to running in containers
+pub const LIMITATIONS: &str = "Steps use the tools installed on this machine instead of the runner image, container actions, services and job containers don't run, and the host filesystem is read-only apart from the job's directories and package manager caches.";
+
+/// Variables pointing at directories the job's steps write to
+const WRITABLE_DIR_VARS: &[&str] = &[
+    "GITHUB_WORKSPACE",
+    "CI_PROJECT_DIR",
+    "RUNNER_TEMP",
+    "RUNNER_TOOL_CACHE",
+];
+
+/// Variables pointing at files the job's steps write to
+const WRITABLE_FILE_VARS: &[&str] = &[
+    "GITHUB_OUTPUT",
+    "GITHUB_ENV",
+    "GITHUB_PATH",
+    "GITHUB_STEP_SUMMARY",
+];
+
+/// Whether bwrap is installed and allowed to create sandboxes, which needs
+/// unprivileged user namespaces
+pub fn is_available() -> bool {
+    cfg!(target_os = "linux")
+        && Command::new("bwrap")
+            .args(["--ro-bind", "/", "/", "--unshare-all", "true"])
+            .output()
+            .is_ok_and(|output| output.status.success())
+}
+
+/// A command running `program` in a sandbox that sees the host read-only,
+/// except for the directories the step works in
+pub fn command(
+    program: &str,
+    working_dir: &Path,
+    env_vars: &[(&str, &str)],
+    volumes: &[(&Path, &Path)],
+) -> Command {
+    let mut cmd = Command::new("bwrap");
+    cmd.args(args(working_dir, env_vars, volumes)).arg(program);
+    cmd
+}
+
+fn args(
+    working_dir: &Path,
+    env_vars: &[(&str, &str)],
+    volumes: &[(&Path, &Path)],
+) -> Vec<OsString> {
+    let var = |name: &str| {
+        env_vars
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| PathBuf::from(value))
+    };
+
+    let mut writable: Vec<PathBuf> = vec![working_dir.to_path_buf()];
+    writable.extend(volumes.iter().map(|(host, _)| host.to_path_buf()));
+    writable.extend(WRITABLE_DIR_VARS.iter().filter_map(|name| var(name)));
+    writable.extend(
+        WRITABLE_FILE_VARS
+            .iter()
+            .filter_map(|name| var(name)?.parent().map(Path::to_path_buf)),
+    );
+    if let Some(home) = dirs::home_dir() {
+        for cache in [".cache", ".npm", ".cargo/registry", ".cargo/git"] {
+            writable.push(home.join(cache));
+        }
+    }
+
+    // /tmp is private, so whatever else the environment points at in there,
+    // like the job's shim directory, is brought back read-only
+    let tmp = Path::new("/tmp");
+    let mut readable: Vec<PathBuf> = env_vars
+        .iter()
+        .flat_map(|(_, value)| std::env::split_paths(value).collect::<Vec<_>>())
+        .filter(|path| path.starts_with(tmp) && path != tmp)
+        .collect();
+    readable.retain(|path| !writable.iter().any(|dir| path.starts_with(dir)));
+
+    let mut args: Vec<OsString> = [
+        "--ro-bind",
+        "/",
+        "/",
+        "--dev",
+        "/dev",
+        "--proc",
+        "/proc",
+        "--tmpfs",
+        "/tmp",
+        "--unshare-all",
+        "--share-net",
+        "--die-with-parent",
+    ]
+    .map(OsString::from)
+    .to_vec();
+    let mut bind = |flag: &str, paths: Vec<PathBuf>| {
+        let mut seen: Vec<PathBuf> = Vec::new();
+        for path in paths {
+            if path.exists() && !seen.contains(&path) {
+                args.extend([flag.into(), path.clone().into(), path.clone().into()]);
+                seen.push(path);
+            }
+        }
+    };
+    bind("--bind", writable);
+    bind("--ro-bind", readable);
+    args.extend(["--chdir".into(), working_dir.into()]);
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binds_the_job_directories() {
+        let job = tempfile::tempdir().unwrap();
+        let workspace = job.path().join("workspace");
+        let shims = job.path().join("shims");
+        std::fs::create_dir_all(&workspace).unwrap();
+        std::fs::create_dir_all(&shims).unwrap();
+        let output = job.path().join("output_0");
+        let path = format!("{}:/usr/bin", shims.display());
+        let env = [
+            ("GITHUB_OUTPUT", output.to_str().unwrap()),
+            ("PATH", path.as_str()),
+        ];
+
+        let args: Vec<String> = args(&workspace, &env, &[])
+            .into_iter()
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect();
+        let bound = |flag: &str, path: &Path| {
+            args.windows(3)
+                .any(|w| w[0] == flag && w[1] == path.to_string_lossy() && w[2] == w[1])
+        };
+        assert!(args.starts_with(&["--ro-bind".to_string(), "/".into(), "/".into()]));
+        assert!(bound("--bind", &workspace));
+        assert!(bound("--bind", job.path()));
+        // Inside a writable directory already
+        assert!(!bound("--ro-bind", &shims));
+        assert!(!bound("--ro-bind", Path::new("/usr/bin")));
+        assert_eq!(
+            args[args.len() - 2..],
+            [
+                "--chdir".to_string(),
+                workspace.to_string_lossy().to_string()
+            ]
+        );
+    }
+}
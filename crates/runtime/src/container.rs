@@ -1,8 +1,11 @@
 use async_trait::async_trait;
+use std::collections::HashMap;
 use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
 
 #[async_trait]
-pub trait ContainerRuntime {
+pub trait ContainerRuntime: Send + Sync {
     async fn run_container(
         &self,
         image: &str,
@@ -12,22 +15,175 @@ pub trait ContainerRuntime {
         volumes: &[(&Path, &Path)],
     ) -> Result<ContainerOutput, ContainerError>;
 
+    /// Run a container with the image's entrypoint replaced
+    async fn run_container_with_entrypoint(
+        &self,
+        image: &str,
+        entrypoint: &[&str],
+        cmd: &[&str],
+        env_vars: &[(&str, &str)],
+        working_dir: &Path,
+        volumes: &[(&Path, &Path)],
+    ) -> Result<ContainerOutput, ContainerError>;
+
     async fn pull_image(&self, image: &str) -> Result<(), ContainerError>;
 
     async fn build_image(&self, dockerfile: &Path, tag: &str) -> Result<(), ContainerError>;
 
+    /// Build an image unless one tagged `tag` exists already, and remove the
+    /// others whose tags start with `replaces`, like images built from an
+    /// older version of the same Dockerfile
+    async fn build_image_once(
+        &self,
+        dockerfile: &Path,
+        tag: &str,
+        _replaces: &str,
+    ) -> Result<(), ContainerError> {
+        self.build_image(dockerfile, tag).await
+    }
+
     async fn prepare_language_environment(
         &self,
         language: &str,
         version: Option<&str>,
         additional_packages: Option<Vec<String>>,
     ) -> Result<String, ContainerError>;
+
+    /// Create a network that the containers started afterwards join.
+    /// Runtimes without container networking ignore it.
+    async fn create_network(&self, _name: &str) -> Result<(), ContainerError> {
+        Ok(())
+    }
+
+    /// Remove the network made by `create_network`
+    async fn remove_network(&self) -> Result<(), ContainerError> {
+        Ok(())
+    }
+
+    /// Stop tracking the network without removing it, returning its name
+    fn keep_network(&self) -> Option<String> {
+        None
+    }
+
+    /// Create a named volume with these labels, or keep the existing one
+    /// of that name. Runtimes without volumes ignore it.
+    async fn create_volume(
+        &self,
+        _name: &str,
+        _labels: &HashMap<String, String>,
+    ) -> Result<(), ContainerError> {
+        Ok(())
+    }
+
+    /// Start a service container in the background, or return `None` when
+    /// the runtime doesn't run service containers
+    async fn start_service(
+        &self,
+        _service: &BackgroundContainer,
+    ) -> Result<Option<StartedContainer>, ContainerError> {
+        Ok(None)
+    }
+
+    /// Start a job's container, kept running for its steps to be executed
+    /// in, or return `None` when the runtime runs steps without containers
+    async fn start_job_container(
+        &self,
+        _container: &BackgroundContainer,
+    ) -> Result<Option<StartedContainer>, ContainerError> {
+        Ok(None)
+    }
+
+    /// Run a command in a container started by `start_job_container`
+    async fn exec_in_container(
+        &self,
+        _id: &str,
+        _cmd: &[&str],
+        _env_vars: &[(&str, &str)],
+        _working_dir: &Path,
+    ) -> Result<ContainerOutput, ContainerError> {
+        Err(ContainerError::ContainerExecution(
+            "This runtime doesn't run job containers".to_string(),
+        ))
+    }
+
+    /// Open an interactive shell on the terminal in a container started by
+    /// `start_job_container`, and wait until the user leaves it
+    async fn open_shell(
+        &self,
+        _id: &str,
+        _env_vars: &[(&str, &str)],
+        _working_dir: &Path,
+    ) -> Result<(), ContainerError> {
+        Err(ContainerError::ContainerExecution(
+            "This runtime doesn't run job containers".to_string(),
+        ))
+    }
+
+    /// Stop and remove a container started by `start_service` or
+    /// `start_job_container`
+    async fn stop_service(&self, _id: &str) -> Result<(), ContainerError> {
+        Ok(())
+    }
+
+    /// A way to stop what the runtime is running from outside the run using
+    /// it, or `None` when it can't
+    fn stopper(&self) -> Option<Arc<dyn Stopper>> {
+        None
+    }
+}
+
+/// Stops the commands or containers a runtime is running, failing the steps
+/// waiting for them, e.g. when their run is cancelled
+#[async_trait]
+pub trait Stopper: Send + Sync {
+    async fn stop(&self);
+}
+
+/// A container kept running alongside a job's steps: one of its `services:`
+/// or its `container:`, reachable from the job's other containers by its aliases
+#[derive(Debug, Clone, Default)]
+pub struct BackgroundContainer {
+    pub image: String,
+    pub aliases: Vec<String>,
+    pub env: Vec<(String, String)>,
+    /// Published ports like `5432:5432` or `6379`
+    pub ports: Vec<String>,
+    /// Mounts like `/host/path:/container/path`
+    pub volumes: Vec<String>,
+    /// Extra `docker create` options, like `--user 1001 --cpus 2`
+    pub options: Option<String>,
+    /// Username and password for pulling the image from a private registry
+    pub credentials: Option<(String, String)>,
+    /// Overrides of the image's entrypoint and command
+    pub entrypoint: Option<Vec<String>>,
+    pub command: Option<Vec<String>>,
 }
 
 pub struct ContainerOutput {
     pub stdout: String,
     pub stderr: String,
     pub exit_code: i32,
+    pub timings: ContainerTimings,
+}
+
+/// Time a container took to get ready for its command, zero for commands
+/// that run without a new container
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ContainerTimings {
+    pub image_pull: Duration,
+    /// Creating and starting it, and waiting for it to be healthy
+    pub startup: Duration,
+}
+
+/// A container started by `start_service` or `start_job_container`
+#[derive(Debug, Clone, PartialEq)]
+pub struct StartedContainer {
+    pub id: String,
+    pub timings: ContainerTimings,
+    /// The network it joined, when the run has one
+    pub network: Option<String>,
+    /// Host ports its ports are published on, by container port
+    pub ports: HashMap<String, String>,
 }
 
 use std::fmt;
@@ -1,12 +1,15 @@
-use crate::container::{ContainerError, ContainerOutput, ContainerRuntime};
+use crate::bubblewrap;
+use crate::container::{
+    ContainerError, ContainerOutput, ContainerRuntime, ContainerTimings, Stopper,
+};
 use async_trait::async_trait;
 use logging;
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::process::Command;
-use std::sync::Mutex;
+use std::process::{Command, Output, Stdio};
+use std::sync::{Arc, Mutex};
 use tempfile::TempDir;
 use which;
 
@@ -17,6 +20,13 @@ static EMULATION_PROCESSES: Lazy<Mutex<Vec<u32>>> = Lazy::new(|| Mutex::new(Vec:
 pub struct EmulationRuntime {
     #[allow(dead_code)]
     workspace: TempDir,
+    /// Give commands only the step's environment and a few host variables,
+    /// instead of everything wrkflw was started with
+    sandbox: bool,
+    /// Run commands in bubblewrap sandboxes
+    bubblewrap: bool,
+    /// Commands running, for `stopper`
+    processes: Arc<RunningProcesses>,
 }
 
 impl Default for EmulationRuntime {
@@ -36,7 +46,93 @@ impl EmulationRuntime {
             workspaces.push(workspace.path().to_path_buf());
         }
 
-        EmulationRuntime { workspace }
+        EmulationRuntime {
+            workspace,
+            sandbox: false,
+            bubblewrap: false,
+            processes: Arc::default(),
+        }
+    }
+
+    /// A runtime whose commands don't inherit the host environment
+    pub fn sandboxed() -> Self {
+        EmulationRuntime {
+            sandbox: true,
+            ..Self::new()
+        }
+    }
+
+    /// A runtime running every command in a bubblewrap sandbox, see
+    /// [`bubblewrap::is_available`]
+    pub fn bubblewrap(sandbox: bool) -> Self {
+        EmulationRuntime {
+            sandbox,
+            bubblewrap: true,
+            ..Self::new()
+        }
+    }
+
+    fn command(
+        &self,
+        program: &str,
+        working_dir: &Path,
+        env_vars: &[(&str, &str)],
+        volumes: &[(&Path, &Path)],
+    ) -> Command {
+        if self.bubblewrap {
+            bubblewrap::command(program, working_dir, env_vars, volumes)
+        } else {
+            Command::new(program)
+        }
+    }
+
+    /// Run a command to completion like `Command::output`, in a process
+    /// group of its own so that stopping it also stops what it started
+    fn output(&self, cmd: &mut Command) -> std::io::Result<Output> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            cmd.process_group(0);
+        }
+        let child = cmd
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let pid = child.id();
+        track_process(pid);
+        self.processes.track(pid);
+        let output = child.wait_with_output();
+        self.processes.untrack(pid);
+        untrack_process(pid);
+        output
+    }
+
+    /// Set a command's environment: the step's variables on top of the
+    /// host's, or of the few host variables tools need when sandboxed
+    fn apply_env<'a>(
+        &self,
+        cmd: &mut Command,
+        env_vars: impl IntoIterator<Item = (&'a str, String)> + Clone,
+    ) {
+        if self.sandbox {
+            cmd.env_clear();
+            for name in HOST_ENV_VARS {
+                if let Some(value) = std::env::var_os(name) {
+                    cmd.env(name, value);
+                }
+            }
+        } else {
+            // Only the CI variables the run sets count, not the shell's
+            for name in CI_ENV_VARS {
+                if !env_vars.clone().into_iter().any(|(key, _)| key == *name) {
+                    cmd.env_remove(name);
+                }
+            }
+        }
+        for (key, value) in env_vars {
+            cmd.env(key, value);
+        }
     }
 
     #[allow(dead_code)]
@@ -150,7 +246,7 @@ impl ContainerRuntime for EmulationRuntime {
         command: &[&str],
         env_vars: &[(&str, &str)],
         working_dir: &Path,
-        _volumes: &[(&Path, &Path)],
+        volumes: &[(&Path, &Path)],
     ) -> Result<ContainerOutput, ContainerError> {
         // Build command string
         let mut command_str = String::new();
@@ -248,17 +344,15 @@ impl ContainerRuntime for EmulationRuntime {
         {
             logging::info("Executing as shell command");
             // Execute as a shell command
-            let mut cmd = Command::new("sh");
+            let mut cmd = self.command("sh", &actual_working_dir, env_vars, volumes);
             cmd.arg("-c");
             cmd.arg(&command_str);
             cmd.current_dir(&actual_working_dir);
 
             // Add environment variables
-            for (key, value) in env_vars {
-                cmd.env(key, value);
-            }
+            self.apply_env(&mut cmd, env_vars.iter().map(|(k, v)| (*k, v.to_string())));
 
-            match cmd.output() {
+            match self.output(&mut cmd) {
                 Ok(output_result) => {
                     let exit_code = output_result.status.code().unwrap_or(-1);
                     let output = String::from_utf8_lossy(&output_result.stdout).to_string();
@@ -290,6 +384,7 @@ impl ContainerRuntime for EmulationRuntime {
                         stdout: output,
                         stderr: error,
                         exit_code,
+                        timings: ContainerTimings::default(),
                     });
                 }
                 Err(e) => {
@@ -310,28 +405,32 @@ impl ContainerRuntime for EmulationRuntime {
                 ));
             }
 
-            let mut cmd = Command::new(parts[0]);
-
-            // Always use the current directory for cargo/rust commands rather than the temporary directory
-            let current_dir = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+            // Cargo runs in the project directory rather than the temporary one,
+            // unless the job has its own copy of the workspace
+            let current_dir = if self.sandbox {
+                actual_working_dir.clone()
+            } else {
+                std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."))
+            };
             logging::info(&format!(
                 "Using project directory for Rust command: {}",
                 current_dir.display()
             ));
+            let mut cmd = self.command(parts[0], &current_dir, env_vars, volumes);
             cmd.current_dir(&current_dir);
 
             // Add environment variables
-            for (key, value) in env_vars {
+            let env = env_vars.iter().map(|(key, value)| {
                 // Don't use the CI_PROJECT_DIR for CARGO_HOME, use the actual project directory
                 if *key == "CARGO_HOME" && value.contains("${CI_PROJECT_DIR}") {
                     let cargo_home =
                         value.replace("${CI_PROJECT_DIR}", &current_dir.to_string_lossy());
-                    logging::info(&format!("Setting CARGO_HOME to: {}", cargo_home));
-                    cmd.env(key, cargo_home);
+                    (*key, cargo_home)
                 } else {
-                    cmd.env(key, value);
+                    (*key, value.to_string())
                 }
-            }
+            });
+            self.apply_env(&mut cmd, env);
 
             // Add command arguments
             if parts.len() > 1 {
@@ -344,7 +443,7 @@ impl ContainerRuntime for EmulationRuntime {
                 current_dir.display()
             ));
 
-            match cmd.output() {
+            match self.output(&mut cmd) {
                 Ok(output_result) => {
                     let exit_code = output_result.status.code().unwrap_or(-1);
                     let output = String::from_utf8_lossy(&output_result.stdout).to_string();
@@ -377,6 +476,7 @@ impl ContainerRuntime for EmulationRuntime {
                         stdout: output,
                         stderr: error,
                         exit_code,
+                        timings: ContainerTimings::default(),
                     });
                 }
                 Err(e) => {
@@ -389,17 +489,15 @@ impl ContainerRuntime for EmulationRuntime {
         }
 
         // For other commands, use a shell as fallback
-        let mut cmd = Command::new("sh");
+        let mut cmd = self.command("sh", &actual_working_dir, env_vars, volumes);
         cmd.arg("-c");
         cmd.arg(&command_str);
         cmd.current_dir(&actual_working_dir);
 
         // Add environment variables
-        for (key, value) in env_vars {
-            cmd.env(key, value);
-        }
+        self.apply_env(&mut cmd, env_vars.iter().map(|(k, v)| (*k, v.to_string())));
 
-        match cmd.output() {
+        match self.output(&mut cmd) {
             Ok(output_result) => {
                 let exit_code = output_result.status.code().unwrap_or(-1);
                 let output = String::from_utf8_lossy(&output_result.stdout).to_string();
@@ -431,6 +529,7 @@ impl ContainerRuntime for EmulationRuntime {
                     ),
                     stderr: error,
                     exit_code,
+                    timings: ContainerTimings::default(),
                 })
             }
             Err(e) => {
@@ -442,6 +541,21 @@ impl ContainerRuntime for EmulationRuntime {
         }
     }
 
+    async fn run_container_with_entrypoint(
+        &self,
+        image: &str,
+        entrypoint: &[&str],
+        cmd: &[&str],
+        env_vars: &[(&str, &str)],
+        working_dir: &Path,
+        volumes: &[(&Path, &Path)],
+    ) -> Result<ContainerOutput, ContainerError> {
+        // Without an image the entrypoint is simply the start of the command
+        let command: Vec<&str> = entrypoint.iter().chain(cmd).copied().collect();
+        self.run_container(image, &command, env_vars, working_dir, volumes)
+            .await
+    }
+
     async fn pull_image(&self, image: &str) -> Result<(), ContainerError> {
         logging::info(&format!("🔄 Emulation: Pretending to pull image {}", image));
         Ok(())
@@ -487,8 +601,47 @@ impl ContainerRuntime for EmulationRuntime {
         // The actual package installation will be handled during container execution
         Ok(base_image)
     }
+
+    fn stopper(&self) -> Option<Arc<dyn Stopper>> {
+        Some(self.processes.clone())
+    }
 }
 
+/// Variables that tell scripts they run in CI
+const CI_ENV_VARS: &[&str] = &["CI", "GITHUB_ACTIONS", "GITLAB_CI"];
+
+/// Host variables sandboxed commands keep: what shells and locally
+/// installed toolchains need to work
+const HOST_ENV_VARS: &[&str] = &[
+    "PATH",
+    "HOME",
+    "USER",
+    "LOGNAME",
+    "SHELL",
+    "LANG",
+    "LC_ALL",
+    "TERM",
+    "TZ",
+    "TMPDIR",
+    "SSH_AUTH_SOCK",
+    "CARGO_HOME",
+    "RUSTUP_HOME",
+    "GOPATH",
+    "GOROOT",
+    "JAVA_HOME",
+    "NVM_DIR",
+    "PYENV_ROOT",
+    // Windows can't start processes without these
+    "SYSTEMROOT",
+    "COMSPEC",
+    "PATHEXT",
+    "USERPROFILE",
+    "APPDATA",
+    "LOCALAPPDATA",
+    "TEMP",
+    "TMP",
+];
+
 #[allow(dead_code)]
 fn copy_directory_contents(source: &Path, dest: &Path) -> std::io::Result<()> {
     // Create the destination directory if it doesn't exist
@@ -709,25 +862,7 @@ async fn cleanup_processes() {
 
     for pid in processes_to_cleanup {
         logging::info(&format!("Cleaning up emulated process: {}", pid));
-
-        #[cfg(unix)]
-        {
-            // On Unix-like systems, use kill command
-            let _ = Command::new("kill")
-                .arg("-TERM")
-                .arg(pid.to_string())
-                .output();
-        }
-
-        #[cfg(windows)]
-        {
-            // On Windows, use taskkill
-            let _ = Command::new("taskkill")
-                .arg("/F")
-                .arg("/PID")
-                .arg(&pid.to_string())
-                .output();
-        }
+        kill_process_group(pid);
 
         // Remove from tracking
         if let Ok(mut processes) = EMULATION_PROCESSES.lock() {
@@ -767,6 +902,64 @@ async fn cleanup_workspaces() {
     }
 }
 
+/// Processes of one runtime still running
+#[derive(Debug, Default)]
+struct RunningProcesses(Mutex<Vec<u32>>);
+
+impl RunningProcesses {
+    fn track(&self, pid: u32) {
+        if let Ok(mut processes) = self.0.lock() {
+            processes.push(pid);
+        }
+    }
+
+    fn untrack(&self, pid: u32) {
+        if let Ok(mut processes) = self.0.lock() {
+            processes.retain(|p| *p != pid);
+        }
+    }
+}
+
+#[async_trait]
+impl Stopper for RunningProcesses {
+    async fn stop(&self) {
+        let running = self.0.lock().map(|p| p.clone()).unwrap_or_default();
+        for pid in running {
+            logging::info(&format!("Stopping emulated process: {}", pid));
+            kill_process_group(pid);
+        }
+    }
+}
+
+/// Stop a process and those it started, which share its process group, or
+/// just the process when it doesn't lead a group
+fn kill_process_group(pid: u32) {
+    #[cfg(unix)]
+    {
+        let group = Command::new("kill")
+            .arg("-TERM")
+            .arg("--")
+            .arg(format!("-{}", pid))
+            .output();
+        if !matches!(group, Ok(output) if output.status.success()) {
+            let _ = Command::new("kill")
+                .arg("-TERM")
+                .arg(pid.to_string())
+                .output();
+        }
+    }
+
+    #[cfg(windows)]
+    {
+        let _ = Command::new("taskkill")
+            .arg("/F")
+            .arg("/T")
+            .arg("/PID")
+            .arg(&pid.to_string())
+            .output();
+    }
+}
+
 // Add process to tracking
 #[allow(dead_code)]
 pub fn track_process(pid: u32) {
@@ -817,3 +1010,28 @@ pub fn get_tracked_processes() -> Vec<u32> {
         vec![]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    #[cfg(unix)]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn stopping_kills_the_running_command() {
+        let runtime = EmulationRuntime::new();
+        let workspace = tempfile::tempdir().unwrap();
+        let stopper = runtime.stopper().unwrap();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            stopper.stop().await;
+        });
+
+        let started = Instant::now();
+        let result = runtime
+            .run_container("", &["sleep", "30"], &[], workspace.path(), &[])
+            .await;
+        assert!(started.elapsed() < Duration::from_secs(10));
+        assert!(result.is_err());
+    }
+}
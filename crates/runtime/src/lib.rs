@@ -1,4 +1,7 @@
 // runtime crate
 
+pub mod bubblewrap;
 pub mod container;
 pub mod emulation;
+pub mod setup_actions;
+pub mod toolchains;
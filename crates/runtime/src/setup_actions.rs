@@ -0,0 +1,604 @@
+// Setup actions in emulation mode: rather than downloading a toolchain like
+// hosted runners do, find the requested version on this machine and put it
+// on the job's PATH
+use crate::toolchains::{self, dir_with, find_install, Install};
+use indexmap::IndexMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// What a setup action step asks for and where it may put things
+pub struct SetupContext<'a> {
+    /// The step's `uses:`, e.g. `dtolnay/rust-toolchain@1.75.0`
+    pub uses: &'a str,
+    pub with: &'a IndexMap<String, String>,
+    /// Directory version files like `.nvmrc` are read from
+    pub workspace: &'a Path,
+    pub tool_cache: &'a Path,
+    /// Directory at the front of the job's PATH
+    pub shims: &'a Path,
+}
+
+impl SetupContext<'_> {
+    /// A `with:` input, empty when not given
+    pub fn input(&self, name: &str) -> &str {
+        self.with.get(name).map(|value| value.trim()).unwrap_or("")
+    }
+
+    /// The ref after `@` in `uses:`
+    pub fn action_ref(&self) -> &str {
+        self.uses.split_once('@').map_or("", |(_, r)| r)
+    }
+
+    /// The version from the `version` input, or else from the file named by
+    /// the `file` input, like `.nvmrc` or `.tool-versions`
+    fn requested_version(&self, version: &str, file: &str, tool: &str) -> String {
+        let version = self.input(version);
+        if !version.is_empty() || self.input(file).is_empty() {
+            return version.to_string();
+        }
+        let content = fs::read_to_string(self.workspace.join(self.input(file))).unwrap_or_default();
+        let mut lines = content.lines().map(str::trim).filter(|l| !l.is_empty());
+        if self.input(file).ends_with(".tool-versions") {
+            lines
+                .find_map(|line| line.strip_prefix(tool))
+                .unwrap_or("")
+                .trim()
+                .to_string()
+        } else {
+            lines.next().unwrap_or("").to_string()
+        }
+    }
+}
+
+/// What a setup action did, for the step's output and the job's later steps
+#[derive(Debug, Default)]
+pub struct SetupOutcome {
+    /// Variables set for the rest of the job
+    pub env: Vec<(String, String)>,
+    /// Lines describing what was set up
+    pub report: Vec<String>,
+}
+
+impl SetupOutcome {
+    fn note(&mut self, line: impl Into<String>) {
+        self.report.push(line.into());
+    }
+
+    fn warn(&mut self, line: String) {
+        logging::warning(&line);
+        self.report.push(format!("Warning: {}", line));
+    }
+
+    fn export(&mut self, key: &str, value: impl Into<String>) {
+        self.env.push((key.to_string(), value.into()));
+    }
+}
+
+/// The emulation of one setup action
+pub trait SetupAction: Send + Sync {
+    /// The action's repository, like `actions/setup-node`
+    fn action(&self) -> &'static str;
+
+    /// Find or install the requested toolchain and expose it to the job;
+    /// an error fails the step
+    fn setup(&self, ctx: &SetupContext<'_>) -> Result<SetupOutcome, String>;
+}
+
+/// The setup actions emulation mode handles itself
+pub struct SetupActions {
+    handlers: Vec<Box<dyn SetupAction>>,
+}
+
+impl Default for SetupActions {
+    fn default() -> Self {
+        SetupActions {
+            handlers: vec![
+                Box::new(SetupNode),
+                Box::new(SetupPython),
+                Box::new(SetupJava),
+                Box::new(SetupPhp),
+                Box::new(RustToolchain),
+            ],
+        }
+    }
+}
+
+impl fmt::Debug for SetupActions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.handlers.iter().map(|handler| handler.action()))
+            .finish()
+    }
+}
+
+impl SetupActions {
+    /// Add a handler, taking over its action from any registered before
+    pub fn register(&mut self, handler: Box<dyn SetupAction>) {
+        self.handlers.push(handler);
+    }
+
+    /// The handler for a step's `uses:`
+    pub fn find(&self, uses: &str) -> Option<&dyn SetupAction> {
+        let action = uses.split('@').next().unwrap_or(uses);
+        self.handlers
+            .iter()
+            .rev()
+            .find(|handler| handler.action().eq_ignore_ascii_case(action))
+            .map(|handler| handler.as_ref())
+    }
+}
+
+fn describe(version: &str) -> &str {
+    if version.is_empty() {
+        "any version"
+    } else {
+        version
+    }
+}
+
+/// Link an install into the job's shim directory
+fn use_install(
+    ctx: &SetupContext<'_>,
+    name: &str,
+    install: &Install,
+    outcome: &mut SetupOutcome,
+) -> Result<(), String> {
+    toolchains::link_all(&install.bin, ctx.shims)
+        .map_err(|e| format!("Failed to put {} on the job's PATH: {}", name, e))?;
+    outcome.note(format!(
+        "Using {} {} from {}",
+        name,
+        install.version,
+        install.home.display()
+    ));
+    Ok(())
+}
+
+/// Settle for the tool already on PATH, warning when it isn't the version
+/// asked for
+fn use_path(name: &str, command: &str, version: &str, outcome: &mut SetupOutcome) {
+    match toolchains::version_on_path(command) {
+        Some(found) if toolchains::matches_prefix(&found, &toolchains::version_prefix(version)) => {
+            outcome.note(format!("Using {} {} from PATH", name, found))
+        }
+        Some(found) => outcome.warn(format!(
+            "No local {} install matches {}, steps get {} {} from PATH",
+            name,
+            describe(version),
+            name,
+            found
+        )),
+        None => outcome.warn(format!(
+            "No local {} install matches {} and there is none on PATH",
+            name,
+            describe(version)
+        )),
+    }
+}
+
+fn home_dir() -> PathBuf {
+    dirs::home_dir().unwrap_or_default()
+}
+
+fn env_dir(var: &str, default: PathBuf) -> PathBuf {
+    std::env::var_os(var).map(PathBuf::from).unwrap_or(default)
+}
+
+/// `actions/setup-node`, from nvm, fnm, volta or the tool cache
+struct SetupNode;
+
+impl SetupAction for SetupNode {
+    fn action(&self) -> &'static str {
+        "actions/setup-node"
+    }
+
+    fn setup(&self, ctx: &SetupContext<'_>) -> Result<SetupOutcome, String> {
+        let version = ctx.requested_version("node-version", "node-version-file", "nodejs");
+        let home = home_dir();
+        let roots = [
+            env_dir("NVM_DIR", home.join(".nvm")).join("versions/node"),
+            home.join(".local/share/fnm/node-versions"),
+            home.join(".volta/tools/image/node"),
+            ctx.tool_cache.join("node"),
+        ];
+        let found = find_install(&roots, &version, toolchains::installed_version, |install| {
+            dir_with(
+                &[
+                    install.join("bin"),
+                    install.join("installation/bin"),
+                    install.join("x64/bin"),
+                    install.join("x64"),
+                    install.to_path_buf(),
+                ],
+                &["node"],
+            )
+        });
+
+        let mut outcome = SetupOutcome::default();
+        match found {
+            Some(install) => use_install(ctx, "Node.js", &install, &mut outcome)?,
+            None => use_path("Node.js", "node", &version, &mut outcome),
+        }
+        Ok(outcome)
+    }
+}
+
+/// `actions/setup-python`, from pyenv, uv, the tool cache or a
+/// `python3.X` on PATH
+struct SetupPython;
+
+impl SetupAction for SetupPython {
+    fn action(&self) -> &'static str {
+        "actions/setup-python"
+    }
+
+    fn setup(&self, ctx: &SetupContext<'_>) -> Result<SetupOutcome, String> {
+        let version = ctx.requested_version("python-version", "python-version-file", "python");
+        let home = home_dir();
+        let roots = [
+            env_dir("PYENV_ROOT", home.join(".pyenv")).join("versions"),
+            home.join(".local/share/uv/python"),
+            ctx.tool_cache.join("Python"),
+        ];
+        let found = find_install(&roots, &version, toolchains::installed_version, |install| {
+            dir_with(
+                &[
+                    install.join("bin"),
+                    install.join("x64/bin"),
+                    install.join("x64"),
+                    install.to_path_buf(),
+                ],
+                &["python3", "python"],
+            )
+        });
+
+        let mut outcome = SetupOutcome::default();
+        if let Some(install) = found {
+            use_install(ctx, "Python", &install, &mut outcome)?;
+            outcome.export("pythonLocation", install.home.to_string_lossy());
+            return Ok(outcome);
+        }
+
+        // Distributions install minor versions side by side as `python3.11`
+        let prefix = toolchains::version_prefix(&version);
+        if prefix.matches('.').count() == 1 {
+            if let Ok(python) = which::which(format!("python{}", prefix)) {
+                for name in ["python", "python3"] {
+                    toolchains::link(&python, &ctx.shims.join(name))
+                        .map_err(|e| format!("Failed to put Python on the job's PATH: {}", e))?;
+                }
+                outcome.note(format!("Using Python {} from {}", prefix, python.display()));
+                return Ok(outcome);
+            }
+        }
+        use_path("Python", "python3", &version, &mut outcome);
+        Ok(outcome)
+    }
+}
+
+/// `actions/setup-java`, from the system's JVMs, SDKMAN!, IntelliJ's
+/// downloads or the tool cache, whatever their distribution
+struct SetupJava;
+
+impl SetupJava {
+    /// Java versions in directory names, where `1.8.0` means Java 8
+    fn version_of(name: &str) -> Option<String> {
+        let version = toolchains::major_version(name)?;
+        Some(match version.strip_prefix("1.") {
+            Some(rest) if !rest.is_empty() => rest.to_string(),
+            _ => version,
+        })
+    }
+}
+
+impl SetupAction for SetupJava {
+    fn action(&self) -> &'static str {
+        "actions/setup-java"
+    }
+
+    fn setup(&self, ctx: &SetupContext<'_>) -> Result<SetupOutcome, String> {
+        let version = ctx.requested_version("java-version", "java-version-file", "java");
+        let home = home_dir();
+        let mut roots = vec![
+            PathBuf::from("/usr/lib/jvm"),
+            PathBuf::from("/Library/Java/JavaVirtualMachines"),
+            env_dir("SDKMAN_DIR", home.join(".sdkman")).join("candidates/java"),
+            home.join(".jdks"),
+        ];
+        // The tool cache has a directory per distribution, like Java_Temurin-Hotspot_jdk
+        roots.extend(
+            fs::read_dir(ctx.tool_cache)
+                .into_iter()
+                .flatten()
+                .flatten()
+                .filter(|entry| entry.file_name().to_string_lossy().starts_with("Java_"))
+                .map(|entry| entry.path()),
+        );
+        let found = find_install(&roots, &version, SetupJava::version_of, |install| {
+            dir_with(
+                &[
+                    install.join("bin"),
+                    install.join("Contents/Home/bin"),
+                    install.join("x64/bin"),
+                ],
+                &["java"],
+            )
+        });
+
+        let mut outcome = SetupOutcome::default();
+        let distribution = ctx.input("distribution");
+        if !distribution.is_empty() {
+            outcome.note(format!(
+                "Any local JDK is used, whether or not it's a {} build",
+                distribution
+            ));
+        }
+        match found {
+            Some(install) => {
+                use_install(ctx, "Java", &install, &mut outcome)?;
+                let java_home = install.bin.parent().unwrap_or(&install.home);
+                let major = install.version.split('.').next().unwrap_or_default();
+                let arch = match std::env::consts::ARCH {
+                    "aarch64" => "ARM64",
+                    _ => "X64",
+                };
+                outcome.export("JAVA_HOME", java_home.to_string_lossy());
+                outcome.export(
+                    &format!("JAVA_HOME_{}_{}", major, arch),
+                    java_home.to_string_lossy(),
+                );
+            }
+            None => use_path("Java", "java", &version, &mut outcome),
+        }
+        Ok(outcome)
+    }
+}
+
+/// `shivammathur/setup-php`, from a distribution's `php8.2` or Homebrew's
+/// `php@8.2`. Extensions and tools aren't installed.
+struct SetupPhp;
+
+impl SetupAction for SetupPhp {
+    fn action(&self) -> &'static str {
+        "shivammathur/setup-php"
+    }
+
+    fn setup(&self, ctx: &SetupContext<'_>) -> Result<SetupOutcome, String> {
+        let version = ctx.requested_version("php-version", "php-version-file", "php");
+        // `latest` and `nightly` take whatever is installed
+        let prefix = toolchains::version_prefix(&version);
+        let mut outcome = SetupOutcome::default();
+
+        let ignored: Vec<&str> = ["extensions", "ini-values", "coverage", "tools"]
+            .into_iter()
+            .filter(|input| !ctx.input(input).is_empty())
+            .collect();
+        if !ignored.is_empty() {
+            outcome.note(format!(
+                "{} aren't set up in emulation mode, the local PHP is used as it is",
+                ignored.join(", ")
+            ));
+        }
+
+        if prefix.matches('.').count() == 1 {
+            if let Ok(php) = which::which(format!("php{}", prefix)) {
+                for name in ["php", "phpize", "php-config"] {
+                    if let Ok(tool) = which::which(format!("{}{}", name, prefix)) {
+                        toolchains::link(&tool, &ctx.shims.join(name))
+                            .map_err(|e| format!("Failed to put PHP on the job's PATH: {}", e))?;
+                    }
+                }
+                outcome.note(format!("Using PHP {} from {}", prefix, php.display()));
+                return Ok(outcome);
+            }
+        }
+
+        let roots = [
+            PathBuf::from("/opt/homebrew/opt"),
+            PathBuf::from("/usr/local/opt"),
+            PathBuf::from("/home/linuxbrew/.linuxbrew/opt"),
+        ];
+        let found = find_install(
+            &roots,
+            &prefix,
+            |name| {
+                name.strip_prefix("php@")
+                    .and_then(toolchains::major_version)
+            },
+            |install| dir_with(&[install.join("bin")], &["php"]),
+        );
+        match found {
+            Some(install) => use_install(ctx, "PHP", &install, &mut outcome)?,
+            None => use_path("PHP", "php", &prefix, &mut outcome),
+        }
+        Ok(outcome)
+    }
+}
+
+/// `dtolnay/rust-toolchain`, through rustup. The toolchain is installed if
+/// needed and picked with RUSTUP_TOOLCHAIN, leaving rustup's default alone.
+struct RustToolchain;
+
+impl RustToolchain {
+    /// The `toolchain` input, or the action's ref as in `@nightly` or `@1.75.0`
+    fn toolchain(ctx: &SetupContext<'_>) -> String {
+        let from_ref = ctx.action_ref();
+        let commit = from_ref.len() == 40 && from_ref.chars().all(|c| c.is_ascii_hexdigit());
+        match ctx.input("toolchain") {
+            "" if from_ref.is_empty() || from_ref == "master" || commit => "stable".to_string(),
+            "" => from_ref.to_string(),
+            toolchain => toolchain.to_string(),
+        }
+    }
+}
+
+impl SetupAction for RustToolchain {
+    fn action(&self) -> &'static str {
+        "dtolnay/rust-toolchain"
+    }
+
+    fn setup(&self, ctx: &SetupContext<'_>) -> Result<SetupOutcome, String> {
+        let toolchain = RustToolchain::toolchain(ctx);
+        let list = |input: &str| -> Vec<String> {
+            ctx.input(input)
+                .split([',', ' ', '\n'])
+                .filter(|item| !item.is_empty())
+                .map(str::to_string)
+                .collect()
+        };
+        let (components, targets) = (list("components"), list("targets"));
+        let mut outcome = SetupOutcome::default();
+
+        let rustup = |args: &[&str]| {
+            Command::new("rustup")
+                .args(args)
+                .output()
+                .map_err(|e| format!("Failed to run rustup: {}", e))
+        };
+        let Ok(installed) = rustup(&["toolchain", "list"]) else {
+            use_path("Rust", "rustc", &toolchain, &mut outcome);
+            outcome.note("rustup isn't installed, so the toolchain can't be switched");
+            return Ok(outcome);
+        };
+        let installed = String::from_utf8_lossy(&installed.stdout).to_string();
+        let is_installed = installed
+            .lines()
+            .any(|line| line == toolchain || line.starts_with(&format!("{}-", toolchain)));
+
+        let mut steps: Vec<Vec<String>> = Vec::new();
+        if !is_installed {
+            steps.push(
+                [
+                    "toolchain",
+                    "install",
+                    &toolchain,
+                    "--profile",
+                    "minimal",
+                    "--no-self-update",
+                ]
+                .map(String::from)
+                .to_vec(),
+            );
+        }
+        if !components.is_empty() {
+            steps.push(
+                [
+                    vec![
+                        "component".into(),
+                        "add".into(),
+                        "--toolchain".into(),
+                        toolchain.clone(),
+                    ],
+                    components.clone(),
+                ]
+                .concat(),
+            );
+        }
+        if !targets.is_empty() {
+            steps.push(
+                [
+                    vec![
+                        "target".into(),
+                        "add".into(),
+                        "--toolchain".into(),
+                        toolchain.clone(),
+                    ],
+                    targets.clone(),
+                ]
+                .concat(),
+            );
+        }
+        for args in &steps {
+            let args: Vec<&str> = args.iter().map(String::as_str).collect();
+            let output = rustup(&args)?;
+            if !output.status.success() {
+                return Err(format!(
+                    "rustup {} failed: {}",
+                    args.join(" "),
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ));
+            }
+            outcome.note(format!("Ran rustup {}", args.join(" ")));
+        }
+
+        let version = rustup(&["run", &toolchain, "rustc", "--version"])
+            .ok()
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+            .unwrap_or_default();
+        outcome.note(format!("Using Rust toolchain {} ({})", toolchain, version));
+        outcome.export("RUSTUP_TOOLCHAIN", toolchain);
+        // As the action does
+        outcome.export("CARGO_INCREMENTAL", "0");
+        outcome.export("CARGO_TERM_COLOR", "always");
+        Ok(outcome)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_handlers_and_their_versions() {
+        let mut actions = SetupActions::default();
+        assert_eq!(
+            actions.find("actions/setup-node@v4").unwrap().action(),
+            "actions/setup-node"
+        );
+        assert!(actions.find("actions/setup-go@v5").is_none());
+
+        // Handlers registered later take over
+        struct CustomNode;
+        impl SetupAction for CustomNode {
+            fn action(&self) -> &'static str {
+                "actions/setup-node"
+            }
+            fn setup(&self, _: &SetupContext<'_>) -> Result<SetupOutcome, String> {
+                Err("custom".to_string())
+            }
+        }
+        actions.register(Box::new(CustomNode));
+
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join(".tool-versions"),
+            "nodejs 20.11.0\nrust 1.75.0\n",
+        )
+        .unwrap();
+        let mut with = IndexMap::new();
+        with.insert(
+            "node-version-file".to_string(),
+            ".tool-versions".to_string(),
+        );
+        let ctx = SetupContext {
+            uses: "dtolnay/rust-toolchain@1.75.0",
+            with: &with,
+            workspace: dir.path(),
+            tool_cache: dir.path(),
+            shims: dir.path(),
+        };
+        assert_eq!(
+            actions
+                .find("actions/setup-node@v4")
+                .unwrap()
+                .setup(&ctx)
+                .unwrap_err(),
+            "custom"
+        );
+        assert_eq!(
+            ctx.requested_version("node-version", "node-version-file", "nodejs"),
+            "20.11.0"
+        );
+        assert_eq!(RustToolchain::toolchain(&ctx), "1.75.0");
+        assert_eq!(
+            SetupJava::version_of("java-1.8.0-openjdk").as_deref(),
+            Some("8.0")
+        );
+        assert_eq!(
+            SetupJava::version_of("17.0.9-tem").as_deref(),
+            Some("17.0.9")
+        );
+    }
+}
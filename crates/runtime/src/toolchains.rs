@@ -0,0 +1,162 @@
+// Finding locally installed toolchain versions and exposing them to a
+// job's steps through its shim directory
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// An installed version of a tool and the directory holding its executables
+#[derive(Debug, Clone, PartialEq)]
+pub struct Install {
+    pub version: String,
+    pub home: PathBuf,
+    pub bin: PathBuf,
+}
+
+/// The newest install matching `wanted` among the per-version directories
+/// inside `roots`, as version managers and the tool cache lay them out
+pub fn find_install(
+    roots: &[PathBuf],
+    wanted: &str,
+    version_of: fn(&str) -> Option<String>,
+    bin_of: fn(&Path) -> Option<PathBuf>,
+) -> Option<Install> {
+    let wanted = version_prefix(wanted);
+    roots
+        .iter()
+        .flat_map(|dir| fs::read_dir(dir).into_iter().flatten().flatten())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let version = version_of(&name)?;
+            let bin = bin_of(&entry.path())?;
+            matches_prefix(&version, &wanted).then(|| Install {
+                version,
+                home: entry.path(),
+                bin,
+            })
+        })
+        .max_by(|a, b| compare_versions(&a.version, &b.version))
+}
+
+/// The version of the tool on PATH, from `<command> --version`
+pub fn version_on_path(command: &str) -> Option<String> {
+    let output = Command::new(command).arg("--version").output().ok()?;
+    let text = String::from_utf8_lossy(&output.stdout).to_string()
+        + &String::from_utf8_lossy(&output.stderr);
+    text.split_whitespace().find_map(installed_version)
+}
+
+/// Put every executable in `bin` on the PATH through the shim directory
+pub fn link_all(bin: &Path, shims: &Path) -> io::Result<()> {
+    fs::create_dir_all(shims)?;
+    for entry in fs::read_dir(bin)?.flatten() {
+        link(&entry.path(), &shims.join(entry.file_name()))?;
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+pub fn link(target: &Path, shim: &Path) -> io::Result<()> {
+    let _ = fs::remove_file(shim);
+    std::os::unix::fs::symlink(target, shim)
+}
+
+/// Symlinks need extra privileges on Windows, so shims are batch files
+#[cfg(windows)]
+pub fn link(target: &Path, shim: &Path) -> io::Result<()> {
+    let shim = shim.with_extension("cmd");
+    fs::write(shim, format!("@\"{}\" %*\r\n", target.display()))
+}
+
+/// The version prefix a `with:` version like `20.x`, `v18` or `3.11` asks
+/// for; empty for any version
+pub fn version_prefix(version: &str) -> String {
+    let version = version.trim().trim_start_matches(['v', '=', '^', '~']);
+    version
+        .split('.')
+        .take_while(|part| part.chars().all(|c| c.is_ascii_digit()) && !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// The version in a name like `v20.11.0` or `cpython-3.11.9-linux-x86_64-gnu`
+pub fn installed_version(name: &str) -> Option<String> {
+    let version = major_version(name)?;
+    version.contains('.').then_some(version)
+}
+
+/// Like [`installed_version`], but also taking a bare major version as in
+/// `java-17-openjdk-amd64`
+pub fn major_version(name: &str) -> Option<String> {
+    let start = name.find(|c: char| c.is_ascii_digit())?;
+    let version: String = name[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .collect();
+    Some(version.trim_end_matches('.').to_string())
+}
+
+pub fn matches_prefix(version: &str, prefix: &str) -> bool {
+    prefix.is_empty()
+        || version == prefix
+        || version
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with('.'))
+}
+
+fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+    let parts = |v: &str| -> Vec<u64> { v.split('.').filter_map(|p| p.parse().ok()).collect() };
+    parts(a).cmp(&parts(b))
+}
+
+/// The first of `candidates` holding one of `executables`
+pub fn dir_with(candidates: &[PathBuf], executables: &[&str]) -> Option<PathBuf> {
+    candidates
+        .iter()
+        .find(|dir| {
+            executables
+                .iter()
+                .any(|exe| dir.join(exe).is_file() || dir.join(format!("{}.exe", exe)).is_file())
+        })
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_the_newest_matching_install() {
+        assert_eq!(version_prefix("20.x"), "20");
+        assert_eq!(version_prefix("v18.17.0"), "18.17.0");
+        assert_eq!(version_prefix("3.11"), "3.11");
+        assert_eq!(
+            installed_version("cpython-3.11.9-linux-x86_64-gnu").as_deref(),
+            Some("3.11.9")
+        );
+        assert_eq!(installed_version("java-17-openjdk"), None);
+        assert_eq!(major_version("java-17-openjdk").as_deref(), Some("17"));
+        assert!(matches_prefix("20.11.0", "20"));
+        assert!(!matches_prefix("201.0.0", "20"));
+
+        let cache = tempfile::tempdir().unwrap();
+        for version in ["96.1.0", "97.9.0", "97.11.1"] {
+            let bin = cache.path().join("node").join(version).join("x64/bin");
+            fs::create_dir_all(&bin).unwrap();
+            fs::write(bin.join("node"), version).unwrap();
+        }
+        let found = find_install(
+            &[cache.path().join("node")],
+            "97.x",
+            installed_version,
+            |install| dir_with(&[install.join("x64/bin")], &["node"]),
+        )
+        .unwrap();
+        assert_eq!(found.version, "97.11.1");
+
+        let shims = cache.path().join("shims");
+        link_all(&found.bin, &shims).unwrap();
+        #[cfg(unix)]
+        assert_eq!(fs::read_to_string(shims.join("node")).unwrap(), "97.11.1");
+    }
+}
@@ -0,0 +1,116 @@
+// Editing workflows in $VISUAL or $EDITOR from the TUI, which gives the
+// editor the terminal until it exits
+use super::App;
+use chrono::Local;
+use crossterm::{
+    event::{DisableMouseCapture, EnableMouseCapture},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{backend::CrosstermBackend, Terminal};
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+/// Editor used when neither $VISUAL nor $EDITOR is set
+const DEFAULT_EDITOR: &str = "vi";
+
+/// Program and arguments of the editor from `$VISUAL` or `$EDITOR`, which
+/// may hold arguments like `code --wait`
+fn editor_command(visual: Option<String>, editor: Option<String>) -> (String, Vec<String>) {
+    let command = [visual, editor]
+        .into_iter()
+        .flatten()
+        .find(|command| !command.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_EDITOR.to_string());
+    let mut words = command.split_whitespace().map(str::to_string);
+    let program = words.next().unwrap_or_default();
+    (program, words.collect())
+}
+
+/// Open a file in the editor, suspending the TUI while it runs
+fn edit_file(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    path: &Path,
+) -> Result<(), String> {
+    let (program, args) =
+        editor_command(std::env::var("VISUAL").ok(), std::env::var("EDITOR").ok());
+
+    let suspend = |terminal: &mut Terminal<CrosstermBackend<io::Stdout>>| -> io::Result<()> {
+        disable_raw_mode()?;
+        execute!(
+            terminal.backend_mut(),
+            LeaveAlternateScreen,
+            DisableMouseCapture
+        )?;
+        terminal.show_cursor()
+    };
+    suspend(terminal).map_err(|e| format!("Failed to suspend the TUI: {}", e))?;
+
+    let status = Command::new(&program).args(&args).arg(path).status();
+
+    let resume = |terminal: &mut Terminal<CrosstermBackend<io::Stdout>>| -> io::Result<()> {
+        enable_raw_mode()?;
+        execute!(
+            terminal.backend_mut(),
+            EnterAlternateScreen,
+            EnableMouseCapture
+        )?;
+        terminal.clear()
+    };
+    resume(terminal).map_err(|e| format!("Failed to resume the TUI: {}", e))?;
+
+    match status {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(format!("Editor '{}' exited with {}", program, status)),
+        Err(e) => Err(format!("Failed to start editor '{}': {}", program, e)),
+    }
+}
+
+/// Edit the highlighted workflow, then reload the workflow list and
+/// validate it again
+pub(crate) fn edit_selected_workflow(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    app: &mut App,
+) {
+    let Some(path) = app
+        .workflow_list_state
+        .selected()
+        .and_then(|idx| app.workflows.get(idx))
+        .map(|workflow| workflow.path.clone())
+    else {
+        app.set_status_message("No workflow selected to edit".to_string());
+        return;
+    };
+
+    match edit_file(terminal, &path) {
+        Ok(()) => app.reload_after_edit(&path),
+        Err(e) => {
+            let timestamp = Local::now().format("%H:%M:%S").to_string();
+            app.logs.push(format!("[{}] ❌ {}", timestamp, e));
+            logging::error(&e);
+            app.set_status_message(e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_visual_then_editor_then_vi() {
+        let command = |visual: Option<&str>, editor: Option<&str>| {
+            editor_command(visual.map(str::to_string), editor.map(str::to_string))
+        };
+        assert_eq!(
+            command(Some("code --wait"), Some("nano")),
+            ("code".to_string(), vec!["--wait".to_string()])
+        );
+        assert_eq!(
+            command(Some(" "), Some("nano")),
+            ("nano".to_string(), vec![])
+        );
+        assert_eq!(command(None, None), ("vi".to_string(), vec![]));
+    }
+}
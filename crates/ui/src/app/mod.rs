@@ -1,8 +1,11 @@
 // App module for UI state and main TUI entry point
+mod editor;
+mod replay;
 mod state;
 
-use crate::handlers::workflow::start_next_workflow_execution;
-use crate::models::{ExecutionResultMsg, Workflow, WorkflowStatus};
+use crate::handlers::workflow::{revalidate_selected_workflows, start_next_workflow_execution};
+use crate::models::{ExecutionResultMsg, ValidationResultMsg, Workflow, WorkflowStatus};
+use crate::recording::SessionRecorder;
 use crate::utils::load_workflows;
 use crate::views::render_ui;
 use chrono::Local;
@@ -11,13 +14,15 @@ use crossterm::{
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
+use executor::stepping::Breakpoint;
 use executor::RuntimeType;
 use ratatui::{backend::CrosstermBackend, Terminal};
 use std::io::{self, stdout};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc;
 use std::time::{Duration, Instant};
 
+pub use replay::{run_replay_tui, ReplayState};
 pub use state::App;
 
 // Main entry point for the TUI interface
@@ -26,13 +31,12 @@ pub async fn run_wrkflw_tui(
     path: Option<&PathBuf>,
     runtime_type: RuntimeType,
     verbose: bool,
+    show_action_messages: bool,
+    record: Option<&Path>,
+    pauses: (bool, Vec<Breakpoint>),
 ) -> io::Result<()> {
-    // Terminal setup
-    enable_raw_mode()?;
-    let mut stdout = stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+    // Open the recording first so a bad path is reported before the screen is taken over
+    let recorder = record.map(SessionRecorder::create).transpose()?;
 
     // Set up channel for async communication
     let (tx, rx): (
@@ -40,8 +44,27 @@ pub async fn run_wrkflw_tui(
         mpsc::Receiver<ExecutionResultMsg>,
     ) = mpsc::channel();
 
-    // Initialize app state
-    let mut app = App::new(runtime_type.clone(), tx.clone());
+    // Separate channel for background re-validation so it never blocks execution
+    let (validation_tx, validation_rx): (
+        mpsc::Sender<ValidationResultMsg>,
+        mpsc::Receiver<ValidationResultMsg>,
+    ) = mpsc::channel();
+
+    // Initialize app state, also before the screen is taken over
+    let mut app = App::new(runtime_type.clone(), tx.clone()).map_err(io::Error::other)?;
+
+    // Terminal setup
+    enable_raw_mode()?;
+    let mut stdout = stdout();
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+    app.show_action_messages = show_action_messages;
+    app.recorder = recorder;
+    let (single_step, breakpoints) = pauses;
+    if single_step || !breakpoints.is_empty() {
+        app.pause_before_steps(single_step, breakpoints);
+    }
 
     if app.validation_mode {
         app.logs.push("Starting in validation mode".to_string());
@@ -65,6 +88,8 @@ pub async fn run_wrkflw_tui(
                 selected: true,
                 status: WorkflowStatus::NotStarted,
                 execution_details: None,
+                issue_count: None,
+                job_selection: Default::default(),
             }];
 
             // Queue the single workflow for execution
@@ -82,13 +107,22 @@ pub async fn run_wrkflw_tui(
     // Only load directory if we haven't already loaded a single file
     if app.workflows.is_empty() {
         app.workflows = load_workflows(&dir_path);
+        app.workflows_dir = Some(dir_path);
     }
 
     // Run the main event loop
     let tx_clone = tx.clone();
 
     // Run the event loop
-    let result = run_tui_event_loop(&mut terminal, &mut app, &tx_clone, &rx, verbose);
+    let result = run_tui_event_loop(
+        &mut terminal,
+        &mut app,
+        &tx_clone,
+        &rx,
+        &validation_tx,
+        &validation_rx,
+        verbose,
+    );
 
     // Clean up terminal
     disable_raw_mode()?;
@@ -110,10 +144,15 @@ pub async fn run_wrkflw_tui(
             if let Some(path) = path {
                 if path.is_file() {
                     logging::error("Falling back to CLI mode...");
-                    crate::handlers::workflow::execute_workflow_cli(path, runtime_type, verbose)
-                        .await
+                    crate::handlers::workflow::execute_workflow_cli(
+                        path,
+                        runtime_type,
+                        verbose,
+                        show_action_messages,
+                    )
+                    .await
                 } else if path.is_dir() {
-                    crate::handlers::workflow::validate_workflow(path, verbose)
+                    crate::handlers::workflow::validate_workflow(path, verbose).map(|_| ())
                 } else {
                     Err(e)
                 }
@@ -130,6 +169,8 @@ fn run_tui_event_loop(
     app: &mut App,
     tx_clone: &mpsc::Sender<ExecutionResultMsg>,
     rx: &mpsc::Receiver<ExecutionResultMsg>,
+    validation_tx: &mpsc::Sender<ValidationResultMsg>,
+    validation_rx: &mpsc::Receiver<ValidationResultMsg>,
     verbose: bool,
 ) -> io::Result<()> {
     // Max time to wait for events - keep this short to ensure UI responsiveness
@@ -140,18 +181,28 @@ fn run_tui_event_loop(
     let mut last_tick = Instant::now();
 
     loop {
+        app.sync_system_logs();
+
         // Always redraw the UI on each loop iteration to keep it responsive
-        terminal.draw(|f| {
+        let frame = terminal.draw(|f| {
             render_ui(f, app);
         })?;
+        if let Some(recorder) = &mut app.recorder {
+            recorder.record_frame(frame.buffer);
+            recorder.record_logs(&app.logs, &app.system_logs);
+        }
 
         // Update the UI on every tick
         if last_tick.elapsed() >= tick_rate {
             app.tick();
-            app.update_running_workflow_progress();
             last_tick = Instant::now();
         }
 
+        // Apply step events before the final result so none leak into the next run
+        app.update_running_workflow_progress();
+        app.poll_paused_step();
+        app.poll_pending_deployment();
+
         // Non-blocking check for execution results
         if let Ok((workflow_idx, result)) = rx.try_recv() {
             app.process_execution_result(workflow_idx, result);
@@ -161,6 +212,11 @@ fn run_tui_event_loop(
             start_next_workflow_execution(app, tx_clone, verbose);
         }
 
+        // Apply any finished background validations
+        while let Ok((workflow_idx, result)) = validation_rx.try_recv() {
+            app.process_validation_result(workflow_idx, result);
+        }
+
         // Start execution if we have a queued workflow and nothing is currently running
         if app.running && app.current_execution.is_none() && !app.execution_queue.is_empty() {
             start_next_workflow_execution(app, tx_clone, verbose);
@@ -169,20 +225,49 @@ fn run_tui_event_loop(
         // Handle key events with a short timeout
         if event::poll(event_poll_timeout)? {
             if let Event::Key(key) = event::read()? {
+                // The dispatch inputs form takes every key while it's open
+                if app.dispatch_form.is_some() {
+                    app.handle_dispatch_form_input(key.code);
+                    continue;
+                }
+
                 // Handle search input first if we're in search mode and logs tab
                 if app.selected_tab == 2 && app.log_search_active {
                     app.handle_log_search_input(key.code);
                     continue;
                 }
 
+                // Likewise for the search of a step's output in the execution tab
+                if app.selected_tab == 1 && app.step_output.search_active {
+                    app.handle_step_output_search_input(key.code);
+                    continue;
+                }
+
+                // A deployment waiting for approval takes the keys that decide on it
+                if app.pending_deployment.is_some() && app.handle_pending_deployment_key(key.code) {
+                    continue;
+                }
+
+                // A paused run takes the keys that decide on its step
+                if app.paused_step.is_some() && app.handle_paused_step_key(key.code) {
+                    continue;
+                }
+
+                // A key whose action doesn't apply does nothing, rather than
+                // falling through to the arms for other tabs below
+                #[allow(clippy::collapsible_match)]
                 match key.code {
                     KeyCode::Char('q') => {
                         // Exit and clean up
                         break Ok(());
                     }
                     KeyCode::Esc => {
-                        if app.detailed_view {
+                        if app.selected_tab == 1 && !app.step_output.search_query.is_empty() {
+                            app.clear_step_output_search();
+                        } else if app.detailed_view {
                             app.detailed_view = false;
+                        } else if app.selected_tab == 1 && app.split_view.maximized.is_some() {
+                            app.split_view.maximized = None;
                         } else if app.show_help {
                             app.show_help = false;
                         } else {
@@ -192,16 +277,17 @@ fn run_tui_event_loop(
                     }
                     KeyCode::Tab => {
                         // Cycle through tabs
-                        app.switch_tab((app.selected_tab + 1) % 4);
+                        app.switch_tab((app.selected_tab + 1) % 5);
                     }
                     KeyCode::BackTab => {
                         // Cycle through tabs backwards
-                        app.switch_tab((app.selected_tab + 3) % 4);
+                        app.switch_tab((app.selected_tab + 4) % 5);
                     }
                     KeyCode::Char('1') | KeyCode::Char('w') => app.switch_tab(0),
                     KeyCode::Char('2') | KeyCode::Char('x') => app.switch_tab(1),
                     KeyCode::Char('3') | KeyCode::Char('l') => app.switch_tab(2),
-                    KeyCode::Char('4') | KeyCode::Char('h') => app.switch_tab(3),
+                    KeyCode::Char('4') | KeyCode::Char('i') => app.switch_tab(3),
+                    KeyCode::Char('5') | KeyCode::Char('h') => app.switch_tab(4),
                     KeyCode::Up | KeyCode::Char('k') => {
                         if app.selected_tab == 2 {
                             if !app.log_search_matches.is_empty() {
@@ -211,11 +297,13 @@ fn run_tui_event_loop(
                             }
                         } else if app.selected_tab == 0 {
                             app.previous_workflow();
+                        } else if app.selected_tab == 3 {
+                            app.previous_history_run();
                         } else if app.selected_tab == 1 {
                             if app.detailed_view {
                                 app.previous_step();
                             } else {
-                                app.previous_job();
+                                app.previous_tree_row();
                             }
                         }
                     }
@@ -228,11 +316,13 @@ fn run_tui_event_loop(
                             }
                         } else if app.selected_tab == 0 {
                             app.next_workflow();
+                        } else if app.selected_tab == 3 {
+                            app.next_history_run();
                         } else if app.selected_tab == 1 {
                             if app.detailed_view {
                                 app.next_step();
                             } else {
-                                app.next_job();
+                                app.next_tree_row();
                             }
                         }
                     }
@@ -243,14 +333,13 @@ fn run_tui_event_loop(
                     }
                     KeyCode::Enter => {
                         match app.selected_tab {
+                            // In workflows tab, Enter runs the selected workflow, or
+                            // queues it behind the run in progress
                             0 => {
-                                // In workflows tab, Enter runs the selected workflow
-                                if !app.running {
-                                    if let Some(idx) = app.workflow_list_state.selected() {
-                                        app.workflows[idx].selected = true;
-                                        app.queue_selected_for_execution();
-                                        app.start_execution();
-                                    }
+                                if let Some(idx) = app.workflow_list_state.selected() {
+                                    app.workflows[idx].selected = true;
+                                    app.queue_selected_for_execution();
+                                    app.start_execution();
                                 }
                             }
                             1 => {
@@ -285,7 +374,7 @@ fn run_tui_event_loop(
                                     render_ui(f, app);
                                 })?;
                             }
-                        } else if !app.running {
+                        } else {
                             app.queue_selected_for_execution();
                             app.start_execution();
                         }
@@ -301,16 +390,20 @@ fn run_tui_event_loop(
                     KeyCode::Char('e') => {
                         if !app.running {
                             app.toggle_emulation_mode();
+                            revalidate_selected_workflows(app, validation_tx, verbose);
                         }
                     }
                     KeyCode::Char('v') => {
                         if !app.running {
                             app.toggle_validation_mode();
+                            revalidate_selected_workflows(app, validation_tx, verbose);
                         }
                     }
                     KeyCode::Char('n') => {
                         if app.selected_tab == 2 && !app.log_search_query.is_empty() {
                             app.next_search_match();
+                        } else if app.selected_tab == 1 {
+                            app.step_output_search_match(true);
                         } else if app.selected_tab == 0 && !app.running {
                             // Deselect all workflows
                             for workflow in &mut app.workflows {
@@ -345,6 +438,7 @@ fn run_tui_event_loop(
                             ));
                         }
                     }
+                    KeyCode::Char('C') => app.cancel_running_workflow(),
                     KeyCode::Char('?') => {
                         // Toggle help overlay
                         app.show_help = !app.show_help;
@@ -373,12 +467,14 @@ fn run_tui_event_loop(
                                             WorkflowStatus::Success => "Success",
                                             WorkflowStatus::Failed => "Failed",
                                             WorkflowStatus::Skipped => "Skipped",
+                                            WorkflowStatus::Cancelled => "Cancelled",
                                             _ => "current",
                                         };
                                         let needs_reset_hint = workflow.status
                                             == WorkflowStatus::Success
                                             || workflow.status == WorkflowStatus::Failed
-                                            || workflow.status == WorkflowStatus::Skipped;
+                                            || workflow.status == WorkflowStatus::Skipped
+                                            || workflow.status == WorkflowStatus::Cancelled;
 
                                         // Now set the status message (mutable borrow)
                                         app.set_status_message(format!(
@@ -429,6 +525,57 @@ fn run_tui_event_loop(
                             app.switch_tab(0);
                         }
                     }
+                    KeyCode::Char('o') if app.selected_tab == 0 && !app.running => {
+                        editor::edit_selected_workflow(terminal, app);
+                    }
+                    KeyCode::Char('o') if app.selected_tab == 1 && !app.running => {
+                        app.toggle_job_selection();
+                    }
+                    KeyCode::Char('c') if app.selected_tab == 1 && !app.running => {
+                        app.clear_job_selection();
+                    }
+                    KeyCode::Char('g') if app.selected_tab == 1 && !app.detailed_view => {
+                        app.toggle_graph_view();
+                    }
+                    KeyCode::Char('g') if app.selected_tab == 1 => {
+                        app.expand_output_groups = !app.expand_output_groups;
+                    }
+                    KeyCode::Char('<') if app.selected_tab == 1 && !app.detailed_view => {
+                        app.split_view.resize(false);
+                    }
+                    KeyCode::Char('>') if app.selected_tab == 1 && !app.detailed_view => {
+                        app.split_view.resize(true);
+                    }
+                    KeyCode::Char('z') if app.selected_tab == 1 && !app.detailed_view => {
+                        app.split_view.cycle_maximized();
+                    }
+                    KeyCode::Char('N') if app.selected_tab == 1 => {
+                        app.step_output_search_match(false);
+                    }
+                    KeyCode::Char('/') if app.selected_tab == 1 => {
+                        app.start_step_output_search();
+                    }
+                    KeyCode::Char('f') if app.selected_tab == 1 => {
+                        app.toggle_step_output_follow();
+                    }
+                    KeyCode::PageUp if app.selected_tab == 1 => {
+                        app.page_step_output(true);
+                    }
+                    KeyCode::PageDown if app.selected_tab == 1 => {
+                        app.page_step_output(false);
+                    }
+                    KeyCode::Home if app.selected_tab == 1 => {
+                        app.step_output_to_top();
+                    }
+                    KeyCode::End if app.selected_tab == 1 => {
+                        app.step_output.follow = true;
+                    }
+                    KeyCode::Char('d') if app.selected_tab == 3 => {
+                        app.toggle_history_compare();
+                    }
+                    KeyCode::Char('u') if app.selected_tab == 3 => {
+                        app.refresh_history();
+                    }
                     KeyCode::Char('s') => {
                         if app.selected_tab == 2 {
                             app.toggle_log_search();
@@ -0,0 +1,282 @@
+// Replay of recorded TUI sessions
+use crate::recording::{RecordedEntry, Recording};
+use crate::theme::Theme;
+use crate::views::render_replay;
+use crossterm::{
+    event::{self, Event, KeyCode},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use executor::ExecutionEvent;
+use ratatui::{backend::CrosstermBackend, Terminal};
+use std::io::{self, stdout};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Longest pause played back between two screens, so idle stretches of a
+/// session don't have to be sat through
+const MAX_PLAYBACK_GAP: Duration = Duration::from_secs(1);
+
+/// How far PageUp/PageDown move through the session
+const SEEK_STEP_MS: u64 = 10_000;
+
+/// Position of a replay within a recording
+pub struct ReplayState {
+    pub recording: Recording,
+    // Indices of the frame entries in the recording
+    frames: Vec<usize>,
+    pub position: usize,
+    pub playing: bool,
+    pub theme: Theme, // Colors of the timeline around the recorded screens
+    shown_at: Instant,
+}
+
+impl ReplayState {
+    pub fn new(recording: Recording) -> Self {
+        let frames = recording
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| matches!(entry, RecordedEntry::Frame { .. }))
+            .map(|(idx, _)| idx)
+            .collect();
+        ReplayState {
+            recording,
+            frames,
+            position: 0,
+            playing: true,
+            theme: Theme::default(),
+            shown_at: Instant::now(),
+        }
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Rows of the screen being shown
+    pub fn screen(&self) -> &[String] {
+        match self
+            .frames
+            .get(self.position)
+            .map(|&i| &self.recording.entries[i])
+        {
+            Some(RecordedEntry::Frame { lines, .. }) => lines,
+            _ => &[],
+        }
+    }
+
+    /// Offset of the shown screen from the start of the session
+    pub fn at_ms(&self) -> u64 {
+        self.frame_at(self.position)
+    }
+
+    fn frame_at(&self, position: usize) -> u64 {
+        self.frames
+            .get(position)
+            .map(|&i| self.recording.entries[i].at_ms())
+            .unwrap_or(0)
+    }
+
+    fn show(&mut self, position: usize) {
+        self.position = position.min(self.frames.len().saturating_sub(1));
+        self.shown_at = Instant::now();
+    }
+
+    pub fn step(&mut self, delta: isize) {
+        self.show(self.position.saturating_add_signed(delta));
+    }
+
+    /// Jump to the last screen drawn at or before `at_ms`
+    pub fn seek(&mut self, at_ms: u64) {
+        let position = self
+            .frames
+            .iter()
+            .rposition(|&i| self.recording.entries[i].at_ms() <= at_ms)
+            .unwrap_or(0);
+        self.show(position);
+    }
+
+    pub fn toggle_playing(&mut self) {
+        self.playing = !self.playing;
+        self.shown_at = Instant::now();
+    }
+
+    /// Move to the next screen once its time has come
+    pub fn tick(&mut self) {
+        if !self.playing || self.position + 1 >= self.frames.len() {
+            return;
+        }
+        // Frames of a hand-edited recording may go back in time
+        let gap = self
+            .frame_at(self.position + 1)
+            .saturating_sub(self.at_ms());
+        if self.shown_at.elapsed() >= Duration::from_millis(gap).min(MAX_PLAYBACK_GAP) {
+            self.step(1);
+        }
+    }
+
+    /// The last log line or execution event recorded up to the shown screen
+    pub fn latest_activity(&self) -> Option<String> {
+        let at_ms = self.at_ms();
+        self.recording
+            .entries
+            .iter()
+            .rev()
+            .filter(|entry| entry.at_ms() <= at_ms)
+            .filter_map(|entry| match entry {
+                RecordedEntry::Log { line, .. } => Some(line.clone()),
+                RecordedEntry::Event { event, .. } => Some(describe_event(event)),
+                _ => None,
+            })
+            .next()
+    }
+}
+
+fn describe_event(event: &ExecutionEvent) -> String {
+    match event {
+        ExecutionEvent::Planned { total_steps } => format!("Planned {} steps", total_steps),
+        ExecutionEvent::JobStarted { job } => format!("Job started: {}", job),
+        ExecutionEvent::StepStarted { job, step } => format!("Step started: {} / {}", job, step),
+        ExecutionEvent::StepOutput { job, step, output } => {
+            // The last line, as activity is shown on one
+            let last = output.lines().last().unwrap_or_default();
+            format!("Step output: {} / {}: {}", job, step, last)
+        }
+        ExecutionEvent::StepCompleted { job, step, status } => {
+            format!("Step finished: {} / {} ({:?})", job, step, status)
+        }
+        ExecutionEvent::JobCompleted { job, status } => {
+            format!("Job finished: {} ({:?})", job, status)
+        }
+        ExecutionEvent::ImagePull { image, status } => {
+            format!("Pulling {}: {}", image, status)
+        }
+    }
+}
+
+/// Play back a session recorded with `wrkflw tui --record`
+pub fn run_replay_tui(path: &Path) -> io::Result<()> {
+    let recording = Recording::load(path)?;
+    if recording.frames().is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} contains no recorded screens", path.display()),
+        ));
+    }
+    let mut state = ReplayState::new(recording);
+    let project = utils::project::ProjectConfig::load(&std::env::current_dir().unwrap_or_default())
+        .unwrap_or_default();
+    state.theme = Theme::from_project(&project.ui).unwrap_or_else(|e| {
+        logging::warning(&e);
+        Theme::default()
+    });
+
+    enable_raw_mode()?;
+    let mut stdout = stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+
+    let result = run_replay_loop(&mut terminal, &mut state);
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    result
+}
+
+fn run_replay_loop(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    state: &mut ReplayState,
+) -> io::Result<()> {
+    loop {
+        terminal.draw(|f| render_replay(f, state))?;
+        state.tick();
+
+        if !event::poll(Duration::from_millis(50))? {
+            continue;
+        }
+        if let Event::Key(key) = event::read()? {
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Char(' ') => state.toggle_playing(),
+                KeyCode::Left | KeyCode::Char('h') => state.step(-1),
+                KeyCode::Right | KeyCode::Char('l') => state.step(1),
+                KeyCode::PageUp => state.seek(state.at_ms().saturating_sub(SEEK_STEP_MS)),
+                KeyCode::PageDown => state.seek(state.at_ms() + SEEK_STEP_MS),
+                KeyCode::Home => state.seek(0),
+                KeyCode::End => state.seek(u64::MAX),
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use executor::JobStatus;
+
+    fn frame(at_ms: u64, text: &str) -> RecordedEntry {
+        RecordedEntry::Frame {
+            at_ms,
+            lines: vec![text.to_string()],
+        }
+    }
+
+    #[test]
+    fn scrubs_through_recorded_screens() {
+        let recording = Recording {
+            started_at: String::new(),
+            entries: vec![
+                frame(0, "start"),
+                RecordedEntry::Log {
+                    at_ms: 500,
+                    line: "running".to_string(),
+                },
+                frame(1_000, "running"),
+                RecordedEntry::Event {
+                    at_ms: 20_000,
+                    event: ExecutionEvent::JobCompleted {
+                        job: "build".to_string(),
+                        status: JobStatus::Success,
+                    },
+                },
+                frame(20_000, "done"),
+            ],
+        };
+        let mut state = ReplayState::new(recording);
+        assert_eq!(state.frame_count(), 3);
+        assert_eq!(state.screen(), ["start"]);
+        assert_eq!(state.latest_activity(), None);
+
+        state.step(1);
+        assert_eq!(state.screen(), ["running"]);
+        assert_eq!(state.latest_activity().as_deref(), Some("running"));
+
+        state.seek(state.at_ms() + SEEK_STEP_MS);
+        assert_eq!(state.screen(), ["running"]);
+        state.seek(u64::MAX);
+        assert_eq!(state.screen(), ["done"]);
+        assert_eq!(
+            state.latest_activity().as_deref(),
+            Some("Job finished: build (Success)")
+        );
+
+        // Stepping stays within the recording
+        state.step(5);
+        assert_eq!(state.position, 2);
+        state.step(-10);
+        assert_eq!(state.position, 0);
+    }
+
+    #[test]
+    fn plays_frames_recorded_out_of_order() {
+        let mut state = ReplayState::new(Recording {
+            started_at: String::new(),
+            entries: vec![frame(5_000, "later"), frame(1_000, "earlier")],
+        });
+        state.tick();
+        assert_eq!(state.screen(), ["earlier"]);
+    }
+}
@@ -1,36 +1,60 @@
 // App state for the UI
 use crate::models::{
-    ExecutionResultMsg, JobExecution, LogFilterLevel, StepExecution, Workflow, WorkflowExecution,
-    WorkflowStatus,
+    DispatchForm, ExecutionResultMsg, JobExecution, LogFilterLevel, SplitView, StepExecution,
+    StepOutputView, Workflow, WorkflowExecution, WorkflowStatus,
 };
+use crate::recording::SessionRecorder;
+use crate::theme::Theme;
+use crate::utils::load_workflows;
 use chrono::Local;
 use crossterm::event::KeyCode;
-use executor::{JobStatus, RuntimeType, StepStatus};
+use executor::deployments::{Approvals, DeploymentEnvironment, PendingDeployment};
+use executor::graph::JobGraph;
+use executor::platforms::JobPlatforms;
+use executor::resources::JobResources;
+use executor::stepping::{Breakpoint, PausedStep, StepControl, StepDecision};
+use executor::{
+    CancellationToken, ConcurrencyManager, ExecutionEvent, JobStatus, RuntimeType, StepStatus,
+};
 use ratatui::widgets::{ListState, TableState};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc;
 use std::time::{Duration, Instant};
 
 /// Application state
 pub struct App {
     pub workflows: Vec<Workflow>,
+    pub workflows_dir: Option<PathBuf>, // Directory the workflows were loaded from, if not a single file
     pub workflow_list_state: ListState,
     pub selected_tab: usize,
     pub running: bool,
     pub show_help: bool,
     pub runtime_type: RuntimeType,
+    container_runtime: RuntimeType, // Runtime that toggling emulation mode off returns to
     pub validation_mode: bool,
+    pub show_action_messages: bool, // Print messages for actions that are only emulated
     pub execution_queue: Vec<usize>, // Indices of workflows to execute
     pub current_execution: Option<usize>,
-    pub logs: Vec<String>,                    // Overall execution logs
-    pub log_scroll: usize,                    // Scrolling position for logs
-    pub job_list_state: ListState,            // For viewing job details
-    pub detailed_view: bool,                  // Whether we're in detailed view mode
+    pub logs: Vec<String>,                         // Overall execution logs
+    pub log_scroll: usize,                         // Scrolling position for logs
+    pub job_list_state: ListState,                 // For viewing job details
+    pub detailed_view: bool,                       // Whether we're in detailed view mode
+    pub graph_view: bool,                          // Whether jobs are drawn as their needs graph
+    pub job_graph: Option<(usize, JobGraph)>, // Graph of the workflow at an index, built on demand
     pub step_list_state: ListState,           // For selecting steps in detailed view
     pub step_table_state: TableState,         // For the steps table in detailed view
-    pub last_tick: Instant,                   // For UI animations and updates
-    pub tick_rate: Duration,                  // How often to update the UI
+    pub expand_output_groups: bool, // Show the lines of `::group::` sections in step output
+    pub step_output: StepOutputView, // Scrolling and search of the selected step's output
+    pub split_view: SplitView,      // Layout of the job tree and output in the execution tab
+    pub tree_list_state: ListState, // Scrolling of the job tree
+    pub theme: Theme,               // Colors and symbols from .wrkflw.toml
+    pub last_tick: Instant,         // For UI animations and updates
+    pub tick_rate: Duration,        // How often to update the UI
     pub tx: mpsc::Sender<ExecutionResultMsg>, // Channel for async communication
-    pub status_message: Option<String>,       // Temporary status message to display
+    pub progress_tx: mpsc::Sender<ExecutionEvent>, // Handed to the executor for step events
+    pub progress_rx: mpsc::Receiver<ExecutionEvent>,
+    pub status_message: Option<String>, // Temporary status message to display
     pub status_message_time: Option<Instant>, // When the message was set
 
     // Search and filter functionality
@@ -39,10 +63,48 @@ pub struct App {
     pub log_filter_level: Option<LogFilterLevel>, // Current log level filter
     pub log_search_matches: Vec<usize>, // Indices of logs that match the search
     pub log_search_match_idx: usize, // Current match index for navigation
+
+    pub recorder: Option<SessionRecorder>, // Set when the session is being recorded
+
+    pub system_logs: Vec<String>, // Copy of the logging crate's logs, kept up to date by sync_system_logs
+    system_log_revision: usize,   // Revision of the logging crate's logs copied so far
+
+    // Concurrency groups of queued and running workflows
+    pub concurrency: ConcurrencyManager,
+    pub queued_cancellations: HashMap<usize, CancellationToken>, // Per queued workflow index
+    pub running_cancellation: Option<CancellationToken>,         // For the workflow being executed
+
+    pub max_output_chars: usize, // Characters of step output shown before truncating
+    pub ci_env: bool,            // Whether runs set CI=true and GITHUB_ACTIONS=true
+    pub docker_retries: u32,     // Retries of Docker API calls failing for transient reasons
+    pub runner_images: HashMap<String, String>, // Images by runs-on label from .wrkflw.toml
+    pub environments: HashMap<String, DeploymentEnvironment>, // Deployment environments from .wrkflw.toml
+    pub resources: JobResources, // CPU and memory limits of job containers from .wrkflw.toml
+    pub platforms: JobPlatforms, // Platforms of job containers from .wrkflw.toml
+
+    // History tab
+    pub history: Vec<executor::history::RunRecord>, // Recorded runs, most recent first
+    pub history_list_state: ListState,
+    pub history_compare: Option<String>, // Id of the run the selected one is compared with
+    pub dispatch_form: Option<DispatchForm>, // Inputs being filled in before a trigger
+
+    // Pausing runs before steps
+    pub step_control: StepControl, // Where runs pause, handed to each of them
+    pub paused_steps: Option<mpsc::Receiver<PausedStep>>, // Steps runs paused before, in order
+    pub paused_step: Option<PausedStep>, // Step waiting for continue, skip or abort
+
+    // Approving deployments to protected environments
+    pub approvals: Approvals, // Handed to each run
+    pub pending_deployments: mpsc::Receiver<PendingDeployment>, // Deployments waiting, in order
+    pub pending_deployment: Option<PendingDeployment>, // Deployment waiting for approval
 }
 
 impl App {
-    pub fn new(runtime_type: RuntimeType, tx: mpsc::Sender<ExecutionResultMsg>) -> App {
+    /// The TUI's state, or why Podman mode can't be used when it's asked for
+    pub fn new(
+        runtime_type: RuntimeType,
+        tx: mpsc::Sender<ExecutionResultMsg>,
+    ) -> Result<App, String> {
         let mut workflow_list_state = ListState::default();
         workflow_list_state.select(Some(0));
 
@@ -52,9 +114,42 @@ impl App {
         let mut step_list_state = ListState::default();
         step_list_state.select(Some(0));
 
+        let config = utils::config::Config::load().unwrap_or_else(|e| {
+            logging::warning(&e);
+            utils::config::Config::default()
+        });
+        let project =
+            utils::project::ProjectConfig::load(&std::env::current_dir().unwrap_or_default())
+                .unwrap_or_default();
+
         let mut step_table_state = TableState::default();
         step_table_state.select(Some(0));
 
+        let (progress_tx, progress_rx) = mpsc::channel();
+        let (approvals, pending_deployments) = Approvals::new();
+        let environments = executor::deployments::from_project(&project.environments)
+            .unwrap_or_else(|e| {
+                logging::warning(&e);
+                HashMap::new()
+            });
+        let resources = JobResources::from_project(&project.resources).unwrap_or_else(|e| {
+            logging::warning(&e);
+            JobResources::default()
+        });
+        let platforms = JobPlatforms::from_project(&project, None).unwrap_or_else(|e| {
+            logging::warning(&e);
+            JobPlatforms::default()
+        });
+        let theme = Theme::from_project(&project.ui).unwrap_or_else(|e| {
+            logging::warning(&e);
+            Theme::default()
+        });
+
+        let container_runtime = match runtime_type {
+            RuntimeType::Podman => RuntimeType::Podman,
+            _ => RuntimeType::Docker,
+        };
+
         // Check Docker availability if Docker runtime is selected
         let mut initial_logs = Vec::new();
         let runtime_type = match runtime_type {
@@ -95,41 +190,59 @@ impl App {
                 };
 
                 if !is_docker_available {
-                    initial_logs.push(
-                        "Docker is not available or unresponsive. Using emulation mode instead."
-                            .to_string(),
+                    let fallback = executor::fallback_runtime_type();
+                    let message = format!(
+                        "Docker is not available or unresponsive. Using {} mode instead.",
+                        fallback.mode_name()
                     );
-                    logging::warning(
-                        "Docker is not available or unresponsive. Using emulation mode instead.",
-                    );
-                    RuntimeType::Emulation
+                    initial_logs.push(message.clone());
+                    logging::warning(&message);
+                    fallback
                 } else {
                     logging::info("Docker is available, using Docker runtime");
                     RuntimeType::Docker
                 }
             }
+            // Podman is only used when asked for, so it not running is an error
+            RuntimeType::Podman => {
+                executor::podman::socket_path()?;
+                RuntimeType::Podman
+            }
+            RuntimeType::Bubblewrap => RuntimeType::Bubblewrap,
             RuntimeType::Emulation => RuntimeType::Emulation,
         };
 
-        App {
+        Ok(App {
             workflows: Vec::new(),
+            workflows_dir: None,
             workflow_list_state,
             selected_tab: 0,
             running: false,
             show_help: false,
             runtime_type,
+            container_runtime,
             validation_mode: false,
+            show_action_messages: false,
             execution_queue: Vec::new(),
             current_execution: None,
             logs: initial_logs,
             log_scroll: 0,
             job_list_state,
             detailed_view: false,
+            graph_view: false,
+            job_graph: None,
             step_list_state,
             step_table_state,
+            expand_output_groups: false,
+            step_output: StepOutputView::default(),
+            split_view: SplitView::default(),
+            tree_list_state: ListState::default(),
+            theme,
             last_tick: Instant::now(),
             tick_rate: Duration::from_millis(250), // Update 4 times per second
             tx,
+            progress_tx,
+            progress_rx,
             status_message: None,
             status_message_time: None,
 
@@ -139,7 +252,116 @@ impl App {
             log_filter_level: Some(LogFilterLevel::All),
             log_search_matches: Vec::new(),
             log_search_match_idx: 0,
+            recorder: None,
+            system_logs: Vec::new(),
+            system_log_revision: 0,
+            concurrency: ConcurrencyManager::default(),
+            queued_cancellations: HashMap::new(),
+            running_cancellation: None,
+            max_output_chars: config.max_output_chars,
+            ci_env: config.ci_env,
+            docker_retries: config.docker_retries,
+            runner_images: project.images.into_iter().collect(),
+            environments,
+            resources,
+            platforms,
+            history: Vec::new(),
+            history_list_state: ListState::default(),
+            history_compare: None,
+            dispatch_form: None,
+            step_control: StepControl::default(),
+            paused_steps: None,
+            paused_step: None,
+            approvals,
+            pending_deployments,
+            pending_deployment: None,
+        })
+    }
+
+    // Pause runs before every step, or at breakpoints
+    pub fn pause_before_steps(&mut self, single_step: bool, breakpoints: Vec<Breakpoint>) {
+        let (control, paused_steps) = StepControl::new(single_step, breakpoints);
+        self.step_control = control;
+        self.paused_steps = Some(paused_steps);
+    }
+
+    // Take the next step a run paused before, once the previous one is decided
+    pub fn poll_paused_step(&mut self) {
+        if self.paused_step.is_some() {
+            return;
+        }
+        let Some(paused) = self.paused_steps.as_ref().and_then(|rx| rx.try_recv().ok()) else {
+            return;
+        };
+        self.logs.push(format!(
+            "[{}] Paused before step '{}' of job '{}'",
+            Local::now().format("%H:%M:%S"),
+            paused.step,
+            paused.job
+        ));
+        self.paused_step = Some(paused);
+    }
+
+    // Handle a key while a run is paused, returning whether it was used
+    pub fn handle_paused_step_key(&mut self, key: KeyCode) -> bool {
+        let decision = match key {
+            KeyCode::Char('c') | KeyCode::Enter => StepDecision::Run,
+            KeyCode::Char('s') => StepDecision::Skip,
+            KeyCode::Char('a') => StepDecision::Abort,
+            _ => return false,
+        };
+        if let Some(paused) = self.paused_step.take() {
+            self.logs.push(format!(
+                "[{}] {} step '{}' of job '{}'",
+                Local::now().format("%H:%M:%S"),
+                match decision {
+                    StepDecision::Run => "Continuing with",
+                    StepDecision::Skip => "Skipping",
+                    StepDecision::Abort => "Aborting the run at",
+                },
+                paused.step,
+                paused.job
+            ));
+            paused.decide(decision);
+        }
+        true
+    }
+
+    // Take the next deployment waiting for approval, once the previous one is decided
+    pub fn poll_pending_deployment(&mut self) {
+        if self.pending_deployment.is_some() {
+            return;
+        }
+        let Ok(deployment) = self.pending_deployments.try_recv() else {
+            return;
+        };
+        self.logs.push(format!(
+            "[{}] Job '{}' is waiting for approval to deploy to '{}'",
+            Local::now().format("%H:%M:%S"),
+            deployment.job,
+            deployment.environment
+        ));
+        self.pending_deployment = Some(deployment);
+    }
+
+    // Handle a key while a deployment waits, returning whether it was used
+    pub fn handle_pending_deployment_key(&mut self, key: KeyCode) -> bool {
+        let approved = match key {
+            KeyCode::Char('y') => true,
+            KeyCode::Char('n') => false,
+            _ => return false,
+        };
+        if let Some(deployment) = self.pending_deployment.take() {
+            self.logs.push(format!(
+                "[{}] {} deployment of job '{}' to '{}'",
+                Local::now().format("%H:%M:%S"),
+                if approved { "Approved" } else { "Rejected" },
+                deployment.job,
+                deployment.environment
+            ));
+            deployment.decide(approved);
         }
+        true
     }
 
     // Toggle workflow selection
@@ -153,8 +375,11 @@ impl App {
 
     pub fn toggle_emulation_mode(&mut self) {
         self.runtime_type = match self.runtime_type {
-            RuntimeType::Docker => RuntimeType::Emulation,
-            RuntimeType::Emulation => RuntimeType::Docker,
+            RuntimeType::Docker | RuntimeType::Podman => {
+                self.container_runtime = self.runtime_type.clone();
+                RuntimeType::Emulation
+            }
+            RuntimeType::Bubblewrap | RuntimeType::Emulation => self.container_runtime.clone(),
         };
         self.logs
             .push(format!("Switched to {} mode", self.runtime_type_name()));
@@ -173,9 +398,124 @@ impl App {
         logging::info(&format!("Switched to {} mode", mode));
     }
 
+    // Whether a workflow waits in the execution queue or is being executed
+    pub fn is_queued_or_running(&self, workflow_idx: usize) -> bool {
+        self.current_execution == Some(workflow_idx) || self.execution_queue.contains(&workflow_idx)
+    }
+
+    // Update a workflow's status and issue count from a background validation
+    pub fn process_validation_result(
+        &mut self,
+        workflow_idx: usize,
+        result: Result<Vec<String>, String>,
+    ) {
+        if self.is_queued_or_running(workflow_idx) {
+            // A run started since; its result decides the status
+            return;
+        }
+        let Some(workflow) = self.workflows.get_mut(workflow_idx) else {
+            logging::error("Invalid workflow index received in process_validation_result");
+            return;
+        };
+
+        let timestamp = Local::now().format("%H:%M:%S").to_string();
+        match result {
+            Ok(issues) => {
+                workflow.status = if issues.is_empty() {
+                    WorkflowStatus::Success
+                } else {
+                    WorkflowStatus::Failed
+                };
+                workflow.issue_count = Some(issues.len());
+                self.logs.push(format!(
+                    "[{}] Re-validated '{}': {} issue(s)",
+                    timestamp,
+                    workflow.name,
+                    issues.len()
+                ));
+                for issue in issues {
+                    self.logs.push(format!("[{}]   ❌ {}", timestamp, issue));
+                }
+            }
+            Err(e) => {
+                workflow.status = WorkflowStatus::Failed;
+                workflow.issue_count = None;
+                self.logs.push(format!(
+                    "[{}] ❌ Failed to validate '{}': {}",
+                    timestamp, workflow.name, e
+                ));
+                logging::error(&format!("Failed to validate '{}': {}", workflow.name, e));
+            }
+        }
+    }
+
+    // Reload the workflow list after a workflow was edited, keeping the
+    // state of the others, and check the edited one again from scratch
+    pub fn reload_after_edit(&mut self, edited: &Path) {
+        if let Some(dir) = &self.workflows_dir {
+            let mut previous: HashMap<PathBuf, Workflow> = self
+                .workflows
+                .drain(..)
+                .map(|workflow| (workflow.path.clone(), workflow))
+                .collect();
+            self.workflows = load_workflows(dir)
+                .into_iter()
+                .map(|loaded| match previous.remove(&loaded.path) {
+                    Some(kept) if kept.path != edited => kept,
+                    _ => loaded,
+                })
+                .collect();
+        }
+        // Graphs are kept by index
+        self.job_graph = None;
+
+        let timestamp = Local::now().format("%H:%M:%S").to_string();
+        let Some(idx) = self
+            .workflows
+            .iter()
+            .position(|workflow| workflow.path == edited)
+        else {
+            self.workflow_list_state
+                .select(Some(self.workflows.len().saturating_sub(1)));
+            self.set_status_message(format!("{} is no longer a workflow file", edited.display()));
+            return;
+        };
+        self.workflow_list_state.select(Some(idx));
+
+        let workflow = &mut self.workflows[idx];
+        workflow.status = WorkflowStatus::NotStarted;
+        workflow.execution_details = None;
+        workflow.job_selection = Default::default();
+        let message = match evaluator::evaluate_detected_file(edited, false) {
+            Ok(validation) => {
+                workflow.issue_count = Some(validation.issues.len());
+                for issue in &validation.issues {
+                    self.logs.push(format!("[{}]   ❌ {}", timestamp, issue));
+                }
+                format!(
+                    "Reloaded '{}': {} issue(s)",
+                    workflow.name,
+                    validation.issues.len()
+                )
+            }
+            Err(e) => {
+                workflow.issue_count = None;
+                format!(
+                    "Reloaded '{}', which failed to validate: {}",
+                    workflow.name, e
+                )
+            }
+        };
+        self.logs.push(format!("[{}] {}", timestamp, message));
+        logging::info(&message);
+        self.set_status_message(message);
+    }
+
     pub fn runtime_type_name(&self) -> &str {
         match self.runtime_type {
             RuntimeType::Docker => "Docker",
+            RuntimeType::Podman => "Podman",
+            RuntimeType::Bubblewrap => "Bubblewrap",
             RuntimeType::Emulation => "Emulation",
         }
     }
@@ -218,71 +558,60 @@ impl App {
         self.workflow_list_state.select(Some(i));
     }
 
-    // Move cursor up in the job list
-    pub fn previous_job(&mut self) {
-        let current_workflow_idx = self
-            .current_execution
-            .or_else(|| self.workflow_list_state.selected());
-
-        if let Some(workflow_idx) = current_workflow_idx {
-            if workflow_idx >= self.workflows.len() {
-                return;
-            }
-
-            if let Some(execution) = &self.workflows[workflow_idx].execution_details {
-                if execution.jobs.is_empty() {
-                    return;
-                }
+    /// Add the highlighted job to the jobs the workflow's next run is limited
+    /// to, or take it out again
+    pub fn toggle_job_selection(&mut self) {
+        let Some(workflow_idx) = self
+            .workflow_list_state
+            .selected()
+            .filter(|&idx| idx < self.workflows.len())
+        else {
+            return;
+        };
+        let workflow = &mut self.workflows[workflow_idx];
+        let Some(job_name) = workflow.execution_details.as_ref().and_then(|execution| {
+            self.job_list_state
+                .selected()
+                .and_then(|idx| execution.jobs.get(idx))
+                .map(|job| job.job_name().to_string())
+        }) else {
+            return;
+        };
 
-                let i = match self.job_list_state.selected() {
-                    Some(i) => {
-                        if i == 0 {
-                            execution.jobs.len() - 1
-                        } else {
-                            i - 1
-                        }
-                    }
-                    None => 0,
-                };
-                self.job_list_state.select(Some(i));
+        let jobs = &mut workflow.job_selection.jobs;
+        let message = if let Some(pos) = jobs.iter().position(|name| name == &job_name) {
+            jobs.remove(pos);
+            format!("Removed '{}' from the jobs to run", job_name)
+        } else {
+            jobs.push(job_name.clone());
+            format!("Added '{}' to the jobs to run", job_name)
+        };
+        // Jobs left out show as skipped on the graph
+        self.job_graph = None;
+        let summary = if jobs.is_empty() {
+            "all jobs".to_string()
+        } else {
+            jobs.join(", ")
+        };
 
-                // Reset step selection when changing jobs
-                self.step_list_state.select(Some(0));
-            }
-        }
+        let timestamp = Local::now().format("%H:%M:%S").to_string();
+        self.logs.push(format!(
+            "[{}] {}. Next run of '{}': {}",
+            timestamp, message, workflow.name, summary
+        ));
     }
 
-    // Move cursor down in the job list
-    pub fn next_job(&mut self) {
-        let current_workflow_idx = self
-            .current_execution
-            .or_else(|| self.workflow_list_state.selected())
-            .filter(|&idx| idx < self.workflows.len());
-
-        if let Some(workflow_idx) = current_workflow_idx {
-            if workflow_idx >= self.workflows.len() {
-                return;
-            }
-
-            if let Some(execution) = &self.workflows[workflow_idx].execution_details {
-                if execution.jobs.is_empty() {
-                    return;
-                }
-
-                let i = match self.job_list_state.selected() {
-                    Some(i) => {
-                        if i >= execution.jobs.len() - 1 {
-                            0
-                        } else {
-                            i + 1
-                        }
-                    }
-                    None => 0,
-                };
-                self.job_list_state.select(Some(i));
-
-                // Reset step selection when changing jobs
-                self.step_list_state.select(Some(0));
+    /// Run every job of the highlighted workflow again
+    pub fn clear_job_selection(&mut self) {
+        if let Some(idx) = self.workflow_list_state.selected() {
+            if let Some(workflow) = self.workflows.get_mut(idx) {
+                workflow.job_selection = Default::default();
+                self.job_graph = None;
+                let timestamp = Local::now().format("%H:%M:%S").to_string();
+                self.logs.push(format!(
+                    "[{}] Next run of '{}' runs all jobs",
+                    timestamp, workflow.name
+                ));
             }
         }
     }
@@ -316,6 +645,7 @@ impl App {
                         self.step_list_state.select(Some(i));
                         // Update the table state to match
                         self.step_table_state.select(Some(i));
+                        self.reset_step_output();
                     }
                 }
             }
@@ -351,15 +681,126 @@ impl App {
                         self.step_list_state.select(Some(i));
                         // Update the table state to match
                         self.step_table_state.select(Some(i));
+                        self.reset_step_output();
                     }
                 }
             }
         }
     }
 
+    // Move up the job tree of the execution tab, through each job and its steps
+    pub fn previous_tree_row(&mut self) {
+        self.move_in_tree(false);
+    }
+
+    // Move down the job tree of the execution tab
+    pub fn next_tree_row(&mut self) {
+        self.move_in_tree(true);
+    }
+
+    fn move_in_tree(&mut self, forward: bool) {
+        let current_workflow_idx = self
+            .current_execution
+            .or_else(|| self.workflow_list_state.selected())
+            .filter(|&idx| idx < self.workflows.len());
+        let Some(execution) =
+            current_workflow_idx.and_then(|idx| self.workflows[idx].execution_details.as_ref())
+        else {
+            return;
+        };
+
+        // Each job's row, followed by the rows of its steps
+        let rows: Vec<(usize, Option<usize>)> = execution
+            .jobs
+            .iter()
+            .enumerate()
+            .flat_map(|(job_idx, job)| {
+                std::iter::once((job_idx, None))
+                    .chain((0..job.steps.len()).map(move |step_idx| (job_idx, Some(step_idx))))
+            })
+            .collect();
+        if rows.is_empty() {
+            return;
+        }
+
+        let job_idx = self.job_list_state.selected().unwrap_or(0);
+        let step_idx = (!self.split_view.on_job)
+            .then(|| self.step_table_state.selected())
+            .flatten();
+        let i = match rows.iter().position(|&row| row == (job_idx, step_idx)) {
+            Some(i) if forward => (i + 1) % rows.len(),
+            Some(i) => (i + rows.len() - 1) % rows.len(),
+            None => 0,
+        };
+
+        let (job_idx, step_idx) = rows[i];
+        self.job_list_state.select(Some(job_idx));
+        self.split_view.on_job = step_idx.is_none();
+        self.step_list_state.select(Some(step_idx.unwrap_or(0)));
+        self.step_table_state.select(Some(step_idx.unwrap_or(0)));
+        self.reset_step_output();
+    }
+
     // Change the tab
     pub fn switch_tab(&mut self, tab: usize) {
         self.selected_tab = tab;
+        if tab == 3 {
+            self.refresh_history();
+        }
+    }
+
+    // Reload the recorded runs, keeping the selection in range
+    pub fn refresh_history(&mut self) {
+        self.history = executor::history::list(&executor::history::history_root());
+        let selected = match self.history_list_state.selected() {
+            _ if self.history.is_empty() => None,
+            Some(i) => Some(i.min(self.history.len() - 1)),
+            None => Some(0),
+        };
+        self.history_list_state.select(selected);
+        if let Some(id) = &self.history_compare {
+            if !self.history.iter().any(|run| &run.id == id) {
+                self.history_compare = None;
+            }
+        }
+    }
+
+    pub fn next_history_run(&mut self) {
+        if self.history.is_empty() {
+            return;
+        }
+        let i = match self.history_list_state.selected() {
+            Some(i) if i + 1 < self.history.len() => i + 1,
+            _ => 0,
+        };
+        self.history_list_state.select(Some(i));
+    }
+
+    pub fn previous_history_run(&mut self) {
+        if self.history.is_empty() {
+            return;
+        }
+        let i = match self.history_list_state.selected() {
+            Some(0) | None => self.history.len() - 1,
+            Some(i) => i - 1,
+        };
+        self.history_list_state.select(Some(i));
+    }
+
+    // Compare the other runs with the selected one, or stop comparing
+    pub fn toggle_history_compare(&mut self) {
+        let Some(run) = self
+            .history_list_state
+            .selected()
+            .and_then(|i| self.history.get(i))
+        else {
+            return;
+        };
+        if self.history_compare.as_ref() == Some(&run.id) {
+            self.history_compare = None;
+        } else {
+            self.history_compare = Some(run.id.clone());
+        }
     }
 
     // Queue selected workflows for execution
@@ -372,10 +813,102 @@ impl App {
                     "[{}] Added '{}' to execution queue. Press 'Enter' to start.",
                     timestamp, self.workflows[idx].name
                 ));
+                self.join_concurrency_group(idx);
             }
         }
     }
 
+    // Add a queued run to its workflow's concurrency group, which cancels
+    // the runs of the group it replaces
+    fn join_concurrency_group(&mut self, idx: usize) {
+        let cancellation = CancellationToken::default();
+        let config = executor::ExecutionConfig::new(self.runtime_type.clone(), false);
+        let group = parser::workflow::parse_workflow(&self.workflows[idx].path)
+            .ok()
+            .and_then(|workflow| executor::concurrency_group(&workflow, &config).ok())
+            .flatten();
+        if let Some(group) = group {
+            let was_cancelled = self
+                .running_cancellation
+                .as_ref()
+                .is_some_and(CancellationToken::is_cancelled);
+            self.concurrency.enqueue(&group, &cancellation);
+
+            let reason = self
+                .running_cancellation
+                .as_ref()
+                .and_then(CancellationToken::reason);
+            if let (Some(running_idx), Some(reason)) = (self.current_execution, reason) {
+                if !was_cancelled {
+                    let message = format!(
+                        "Stopping workflow '{}': {}",
+                        self.workflows[running_idx].name, reason
+                    );
+                    self.logs
+                        .push(format!("[{}] {}", Local::now().format("%H:%M:%S"), message));
+                    logging::warning(&message);
+                }
+            }
+        }
+        self.queued_cancellations.insert(idx, cancellation);
+
+        // Queued runs replaced by this one never start
+        let cancelled: Vec<(usize, String)> = self
+            .execution_queue
+            .iter()
+            .filter_map(|queued| {
+                let reason = self.queued_cancellations.get(queued)?.reason()?;
+                Some((*queued, reason))
+            })
+            .collect();
+        for (queued, reason) in cancelled {
+            self.execution_queue.retain(|&i| i != queued);
+            self.queued_cancellations.remove(&queued);
+            self.report_cancelled(queued, &reason);
+        }
+    }
+
+    // Mark a workflow whose run was cancelled, unless it's running again
+    fn report_cancelled(&mut self, idx: usize, reason: &str) {
+        if self.current_execution != Some(idx) {
+            self.workflows[idx].status = WorkflowStatus::Cancelled;
+        }
+        let message = format!("Workflow '{}' was {}", self.workflows[idx].name, reason);
+        self.logs
+            .push(format!("[{}] {}", Local::now().format("%H:%M:%S"), message));
+        logging::warning(&message);
+        self.set_status_message(format!("{} {}", self.theme.symbols.cancelled, message));
+    }
+
+    // Cancel the workflow being executed: its steps in flight are stopped and
+    // the jobs it hasn't run are skipped. A pause or approval it waits for is
+    // answered so the run can end.
+    pub fn cancel_running_workflow(&mut self) {
+        let (Some(idx), Some(cancellation)) =
+            (self.current_execution, self.running_cancellation.as_ref())
+        else {
+            self.set_status_message("No workflow is running".to_string());
+            return;
+        };
+        if cancellation.is_cancelled() {
+            return;
+        }
+        cancellation.cancel("cancelled from the TUI");
+
+        let message = format!("Cancelling workflow '{}'", self.workflows[idx].name);
+        self.logs
+            .push(format!("[{}] {}", Local::now().format("%H:%M:%S"), message));
+        logging::warning(&message);
+        self.set_status_message(format!("{} {}", self.theme.symbols.cancelled, message));
+
+        if let Some(paused) = self.paused_step.take() {
+            paused.decide(StepDecision::Abort);
+        }
+        if let Some(deployment) = self.pending_deployment.take() {
+            deployment.decide(false);
+        }
+    }
+
     // Start workflow execution process
     pub fn start_execution(&mut self) {
         // Only start if we have workflows in queue and nothing is currently running
@@ -407,6 +940,15 @@ impl App {
             return;
         }
 
+        // A run stopped by a newer one still sends its results
+        let cancelled = match self.current_execution {
+            Some(idx) if idx == workflow_idx => self
+                .running_cancellation
+                .take()
+                .and_then(|cancellation| cancellation.reason()),
+            _ => None,
+        };
+
         let workflow = &mut self.workflows[workflow_idx];
 
         // Ensure execution details exist
@@ -417,6 +959,8 @@ impl App {
                 end_time: Some(Local::now()),
                 logs: Vec::new(),
                 progress: 1.0,
+                step_progress: executor::Progress::default(),
+                job_events: HashMap::new(),
             });
         }
 
@@ -453,9 +997,17 @@ impl App {
                                         executor::StepStatus::Skipped => StepStatus::Skipped,
                                     },
                                     output: step_result.output.clone(),
+                                    output_file: step_result.output_file.clone(),
+                                    duration: step_result.duration,
+                                    overhead: step_result.overhead,
+                                    annotations: step_result.annotations.clone(),
+                                    running: false,
                                 })
                                 .collect::<Vec<StepExecution>>(),
                             logs: vec![job_result.logs.clone()],
+                            duration: job_result.duration,
+                            summary: job_result.summary.clone(),
+                            running: false,
                         })
                         .collect::<Vec<JobExecution>>();
                 }
@@ -474,14 +1026,27 @@ impl App {
                             name: "Execution Error".to_string(),
                             status: StepStatus::Failure,
                             output: format!("Error: {}\n\nThis error prevented the workflow from executing properly.", e),
+                            output_file: None,
+                            duration: Duration::ZERO,
+                            overhead: executor::Overhead::default(),
+                            annotations: Vec::new(),
+                            running: false,
                         }],
                         logs: vec![format!("Workflow execution error: {}", e)],
+                        duration: Duration::ZERO,
+                        summary: String::new(),
+                        running: false,
                     }];
                 }
             }
         }
 
         match result {
+            Ok(_) if cancelled.is_some() => {
+                let reason = cancelled.unwrap_or_default();
+                self.current_execution = None;
+                self.report_cancelled(workflow_idx, &reason);
+            }
             Ok(_) => {
                 workflow.status = WorkflowStatus::Success;
                 let timestamp = Local::now().format("%H:%M:%S").to_string();
@@ -525,6 +1090,8 @@ impl App {
         let next = self.execution_queue.remove(0);
         self.workflows[next].status = WorkflowStatus::Running;
         self.current_execution = Some(next);
+        self.running_cancellation =
+            Some(self.queued_cancellations.remove(&next).unwrap_or_default());
         self.logs
             .push(format!("Executing workflow: {}", self.workflows[next].name));
         logging::info(&format!(
@@ -539,6 +1106,8 @@ impl App {
             end_time: None,
             logs: vec!["Execution started".to_string()],
             progress: 0.0, // Just started
+            step_progress: executor::Progress::default(),
+            job_events: HashMap::new(),
         });
 
         Some(next)
@@ -563,6 +1132,135 @@ impl App {
             if self.job_list_state.selected().is_none() {
                 self.job_list_state.select(Some(0));
             }
+            self.reset_step_output();
+        }
+    }
+
+    // Show the selected step's output from its first match or the top, or
+    // its end when following it
+    fn reset_step_output(&mut self) {
+        self.step_output.scroll = 0;
+        self.step_output.match_idx = 0;
+        self.step_output.jump = !self.step_output.search_query.is_empty();
+    }
+
+    // Scroll the step output by a page, which stops following it when
+    // scrolling up
+    pub fn page_step_output(&mut self, up: bool) {
+        let view = &mut self.step_output;
+        let page = view.rows.max(1);
+        if up {
+            view.follow = false;
+            view.scroll = view.scroll.saturating_sub(page);
+        } else {
+            view.scroll += page;
+        }
+    }
+
+    // Jump to the top of the step output
+    pub fn step_output_to_top(&mut self) {
+        self.step_output.follow = false;
+        self.step_output.scroll = 0;
+    }
+
+    // Keep the end of the step output in view as it grows, or stop
+    pub fn toggle_step_output_follow(&mut self) {
+        self.step_output.follow = !self.step_output.follow;
+        self.set_status_message(if self.step_output.follow {
+            "Following the step's output".to_string()
+        } else {
+            "Stopped following the step's output".to_string()
+        });
+    }
+
+    // Start typing a search of the step output
+    pub fn start_step_output_search(&mut self) {
+        self.step_output.search_active = true;
+    }
+
+    // Clear the search of the step output
+    pub fn clear_step_output_search(&mut self) {
+        let view = &mut self.step_output;
+        view.search_active = false;
+        view.search_query.clear();
+        view.match_idx = 0;
+        view.match_count = 0;
+    }
+
+    // Handle keys while the search of the step output is typed
+    pub fn handle_step_output_search_input(&mut self, key: KeyCode) {
+        let view = &mut self.step_output;
+        match key {
+            KeyCode::Esc => self.clear_step_output_search(),
+            KeyCode::Enter => view.search_active = false,
+            KeyCode::Backspace => {
+                view.search_query.pop();
+                view.match_idx = 0;
+                view.jump = true;
+            }
+            KeyCode::Char(c) => {
+                view.search_query.push(c);
+                view.match_idx = 0;
+                view.jump = true;
+            }
+            _ => {}
+        }
+    }
+
+    // Show the next or previous match of the step output search
+    pub fn step_output_search_match(&mut self, forward: bool) {
+        let view = &mut self.step_output;
+        if view.match_count == 0 {
+            return;
+        }
+        view.match_idx = if forward {
+            (view.match_idx + 1) % view.match_count
+        } else {
+            (view.match_idx + view.match_count - 1) % view.match_count
+        };
+        view.jump = true;
+        let message = format!(
+            "Search match {}/{} for '{}'",
+            view.match_idx + 1,
+            view.match_count,
+            view.search_query
+        );
+        self.set_status_message(message);
+    }
+
+    // Toggle drawing the jobs as their dependency graph
+    pub fn toggle_graph_view(&mut self) {
+        self.graph_view = !self.graph_view;
+        if self.graph_view {
+            self.load_job_graph();
+        }
+    }
+
+    // Build the graph of the workflow shown in the execution tab, unless it
+    // is the one already built
+    pub fn load_job_graph(&mut self) {
+        let Some(idx) = self
+            .current_execution
+            .or_else(|| self.workflow_list_state.selected())
+            .filter(|&idx| idx < self.workflows.len())
+        else {
+            return;
+        };
+        if matches!(&self.job_graph, Some((graph_idx, _)) if *graph_idx == idx) {
+            return;
+        }
+
+        let config = executor::ExecutionConfig {
+            job_selection: self.workflows[idx].job_selection.clone(),
+            ..executor::ExecutionConfig::new(self.runtime_type.clone(), false)
+        };
+        match executor::graph::graph_file(&self.workflows[idx].path, &config) {
+            Ok(graph) => self.job_graph = Some((idx, graph)),
+            Err(e) => {
+                self.job_graph = None;
+                self.graph_view = false;
+                self.set_status_message(format!("Cannot draw the job graph: {}", e));
+            }
         }
     }
 
@@ -630,7 +1328,7 @@ impl App {
         for log in &self.logs {
             all_logs.push(log.clone());
         }
-        for log in logging::get_logs() {
+        for log in &self.system_logs {
             all_logs.push(log.clone());
         }
 
@@ -714,6 +1412,16 @@ impl App {
         }
     }
 
+    // Copy over the system logs added since the last call
+    pub fn sync_system_logs(&mut self) {
+        let update = logging::get_logs_since(self.system_log_revision);
+        if update.reset {
+            self.system_logs.clear();
+        }
+        self.system_logs.extend(update.lines);
+        self.system_log_revision = update.revision;
+    }
+
     // Scroll logs up
     pub fn scroll_logs_up(&mut self) {
         self.log_scroll = self.log_scroll.saturating_sub(1);
@@ -722,22 +1430,87 @@ impl App {
     // Scroll logs down
     pub fn scroll_logs_down(&mut self) {
         // Get total log count including system logs
-        let total_logs = self.logs.len() + logging::get_logs().len();
+        let total_logs = self.logs.len() + self.system_logs.len();
         if total_logs > 0 {
             self.log_scroll = (self.log_scroll + 1).min(total_logs - 1);
         }
     }
 
-    // Update progress for running workflows
+    // Update progress for the running workflow from executor events
     pub fn update_running_workflow_progress(&mut self) {
-        if let Some(idx) = self.current_execution {
-            if let Some(execution) = &mut self.workflows[idx].execution_details {
-                if execution.end_time.is_none() {
-                    // Gradually increase progress for visual feedback
-                    execution.progress = (execution.progress + 0.01).min(0.95);
+        let events: Vec<ExecutionEvent> = self.progress_rx.try_iter().collect();
+        if let Some(recorder) = &mut self.recorder {
+            for event in &events {
+                recorder.record_event(event);
+            }
+        }
+        let Some(idx) = self.current_execution else {
+            return;
+        };
+        let Some(execution) = &mut self.workflows[idx].execution_details else {
+            return;
+        };
+        if execution.end_time.is_some() {
+            return;
+        }
+
+        for event in events {
+            execution.step_progress.apply(&event);
+            match &event {
+                ExecutionEvent::JobStarted { job } => {
+                    execution.job_events.insert(job.clone(), None);
+                    live_job(&mut execution.jobs, job);
+                    let timestamp = Local::now().format("%H:%M:%S").to_string();
+                    execution
+                        .logs
+                        .push(format!("[{}] Job started: {}", timestamp, job));
                 }
+                ExecutionEvent::StepStarted { job, step } => {
+                    live_job(&mut execution.jobs, job)
+                        .steps
+                        .push(StepExecution::started(step));
+                    let timestamp = Local::now().format("%H:%M:%S").to_string();
+                    execution
+                        .logs
+                        .push(format!("[{}] Step started: {} / {}", timestamp, job, step));
+                }
+                ExecutionEvent::StepOutput { job, step, output } => {
+                    if let Some(live) = live_step(&mut execution.jobs, job, step) {
+                        live.output = output.clone();
+                    }
+                }
+                ExecutionEvent::StepCompleted { job, step, status } => {
+                    match live_step(&mut execution.jobs, job, step) {
+                        Some(live) => {
+                            live.status = status.clone();
+                            live.running = false;
+                        }
+                        // Skipped steps don't start
+                        None => {
+                            let mut skipped = StepExecution::started(step);
+                            skipped.status = status.clone();
+                            skipped.running = false;
+                            live_job(&mut execution.jobs, job).steps.push(skipped);
+                        }
+                    }
+                }
+                ExecutionEvent::JobCompleted { job, status } => {
+                    execution
+                        .job_events
+                        .insert(job.clone(), Some(status.clone()));
+                    let live = live_job(&mut execution.jobs, job);
+                    live.status = status.clone();
+                    live.running = false;
+                    let timestamp = Local::now().format("%H:%M:%S").to_string();
+                    execution.logs.push(format!(
+                        "[{}] Job finished: {} ({:?})",
+                        timestamp, job, status
+                    ));
+                }
+                _ => {}
             }
         }
+        execution.progress = execution.step_progress.fraction();
     }
 
     // Set a temporary status message to be displayed in the UI
@@ -766,75 +1539,124 @@ impl App {
         }
     }
 
-    // Trigger the selected workflow
+    // Trigger the selected workflow, asking for its inputs first if it declares any
     pub fn trigger_selected_workflow(&mut self) {
-        if let Some(selected_idx) = self.workflow_list_state.selected() {
-            if selected_idx < self.workflows.len() {
-                let workflow = &self.workflows[selected_idx];
+        let Some(selected_idx) = self
+            .workflow_list_state
+            .selected()
+            .filter(|&idx| idx < self.workflows.len())
+        else {
+            self.logs
+                .push("No workflow selected to trigger".to_string());
+            logging::warning("No workflow selected to trigger");
+            return;
+        };
 
-                if workflow.name.is_empty() {
-                    let timestamp = Local::now().format("%H:%M:%S").to_string();
-                    self.logs
-                        .push(format!("[{}] Error: Invalid workflow selection", timestamp));
-                    logging::error("Invalid workflow selection in trigger_selected_workflow");
-                    return;
-                }
+        let workflow = &self.workflows[selected_idx];
+        if workflow.name.is_empty() {
+            let timestamp = Local::now().format("%H:%M:%S").to_string();
+            self.logs
+                .push(format!("[{}] Error: Invalid workflow selection", timestamp));
+            logging::error("Invalid workflow selection in trigger_selected_workflow");
+            return;
+        }
 
-                // Set up background task to execute the workflow via GitHub Actions REST API
+        match github::inputs::dispatch_inputs(&workflow.path) {
+            Ok(Some(inputs)) if !inputs.is_empty() => {
+                self.dispatch_form = Some(DispatchForm::new(selected_idx, inputs));
+            }
+            Ok(_) => self.start_trigger(selected_idx, None),
+            Err(e) => {
                 let timestamp = Local::now().format("%H:%M:%S").to_string();
                 self.logs.push(format!(
-                    "[{}] Triggering workflow: {}",
-                    timestamp, workflow.name
+                    "[{}] Could not read the inputs of {}: {}",
+                    timestamp, workflow.name, e
                 ));
-                logging::info(&format!("Triggering workflow: {}", workflow.name));
-
-                // Clone necessary values for the async task
-                let workflow_name = workflow.name.clone();
-                let tx_clone = self.tx.clone();
-
-                // Set this tab as the current execution to ensure it shows in the Execution tab
-                self.current_execution = Some(selected_idx);
-
-                // Switch to execution tab for better user feedback
-                self.selected_tab = 1; // Switch to Execution tab manually to avoid the borrowing issue
-
-                // Create a thread instead of using tokio runtime directly since send() is not async
-                std::thread::spawn(move || {
-                    // Create a runtime for the thread
-                    let rt = match tokio::runtime::Runtime::new() {
-                        Ok(runtime) => runtime,
-                        Err(e) => {
-                            let _ = tx_clone.send((
-                                selected_idx,
-                                Err(format!("Failed to create Tokio runtime: {}", e)),
-                            ));
-                            return;
-                        }
-                    };
-
-                    // Execute the GitHub Actions trigger API call
-                    let result = rt.block_on(async {
-                        crate::handlers::workflow::execute_curl_trigger(&workflow_name, None).await
-                    });
+                logging::warning(&format!("Could not read workflow inputs: {}", e));
+                self.start_trigger(selected_idx, None);
+            }
+        }
+    }
 
-                    // Send the result back to the main thread
-                    if let Err(e) = tx_clone.send((selected_idx, result)) {
-                        logging::error(&format!("Error sending trigger result: {}", e));
+    // Handle a key while the dispatch inputs form is open
+    pub fn handle_dispatch_form_input(&mut self, key: KeyCode) {
+        let Some(form) = &mut self.dispatch_form else {
+            return;
+        };
+        match key {
+            KeyCode::Esc => self.dispatch_form = None,
+            KeyCode::Up | KeyCode::BackTab => form.previous_field(),
+            KeyCode::Down | KeyCode::Tab => form.next_field(),
+            KeyCode::Left => form.cycle(-1),
+            KeyCode::Right => form.cycle(1),
+            KeyCode::Backspace => form.backspace(),
+            KeyCode::Char(c) => form.type_char(c),
+            KeyCode::Enter => {
+                let inputs = form.values();
+                let path = &self.workflows[form.workflow_idx].path;
+                match github::validate_dispatch_inputs(path, &inputs) {
+                    Ok(()) => {
+                        let workflow_idx = form.workflow_idx;
+                        self.dispatch_form = None;
+                        let inputs = (!inputs.is_empty()).then_some(inputs);
+                        self.start_trigger(workflow_idx, inputs);
                     }
-                });
-            } else {
-                let timestamp = Local::now().format("%H:%M:%S").to_string();
-                self.logs
-                    .push(format!("[{}] No workflow selected to trigger", timestamp));
-                logging::warning("No workflow selected to trigger");
+                    Err(e) => form.error = Some(e.to_string()),
+                }
             }
-        } else {
-            self.logs
-                .push("No workflow selected to trigger".to_string());
-            logging::warning("No workflow selected to trigger");
+            _ => {}
         }
     }
 
+    // Trigger a workflow on GitHub in the background
+    fn start_trigger(&mut self, selected_idx: usize, inputs: Option<HashMap<String, String>>) {
+        let workflow = &self.workflows[selected_idx];
+
+        // Set up background task to execute the workflow via GitHub Actions REST API
+        let timestamp = Local::now().format("%H:%M:%S").to_string();
+        self.logs.push(format!(
+            "[{}] Triggering workflow: {}",
+            timestamp, workflow.name
+        ));
+        logging::info(&format!("Triggering workflow: {}", workflow.name));
+
+        // Clone necessary values for the async task
+        let workflow_name = workflow.name.clone();
+        let tx_clone = self.tx.clone();
+
+        // Set this tab as the current execution to ensure it shows in the Execution tab
+        self.current_execution = Some(selected_idx);
+
+        // Switch to execution tab for better user feedback
+        self.selected_tab = 1; // Switch to Execution tab manually to avoid the borrowing issue
+
+        // Create a thread instead of using tokio runtime directly since send() is not async
+        std::thread::spawn(move || {
+            // Create a runtime for the thread
+            let rt = match tokio::runtime::Runtime::new() {
+                Ok(runtime) => runtime,
+                Err(e) => {
+                    let _ = tx_clone.send((
+                        selected_idx,
+                        Err(format!("Failed to create Tokio runtime: {}", e)),
+                    ));
+                    return;
+                }
+            };
+
+            // Execute the GitHub Actions trigger API call
+            let result = rt.block_on(async {
+                crate::handlers::workflow::execute_remote_trigger(&workflow_name, None, inputs)
+                    .await
+            });
+
+            // Send the result back to the main thread
+            if let Err(e) = tx_clone.send((selected_idx, result)) {
+                logging::error(&format!("Error sending trigger result: {}", e));
+            }
+        });
+    }
+
     // Reset a workflow's status to NotStarted
     pub fn reset_workflow_status(&mut self) {
         // Log whether a selection exists
@@ -864,6 +1686,7 @@ impl App {
                     WorkflowStatus::Success => "Success",
                     WorkflowStatus::Failed => "Failed",
                     WorkflowStatus::Skipped => "Skipped",
+                    WorkflowStatus::Cancelled => "Cancelled",
                     WorkflowStatus::NotStarted => "NotStarted",
                     WorkflowStatus::Running => "Running",
                 };
@@ -875,6 +1698,7 @@ impl App {
                 workflow.status = WorkflowStatus::NotStarted;
                 // Clear execution details to reset all state
                 workflow.execution_details = None;
+                workflow.issue_count = None;
 
                 let timestamp = Local::now().format("%H:%M:%S").to_string();
                 self.logs.push(format!(
@@ -887,8 +1711,115 @@ impl App {
                 ));
 
                 // Set a success status message
-                self.set_status_message(format!("✅ Workflow '{}' has been reset!", workflow_name));
+                self.set_status_message(format!(
+                    "{} Workflow '{}' has been reset!",
+                    self.theme.symbols.success, workflow_name
+                ));
             }
         }
     }
 }
+
+// The job of a running workflow, added when its first event arrives
+fn live_job<'a>(jobs: &'a mut Vec<JobExecution>, name: &str) -> &'a mut JobExecution {
+    let idx = match jobs.iter().position(|job| job.name == name) {
+        Some(idx) => idx,
+        None => {
+            jobs.push(JobExecution {
+                name: name.to_string(),
+                status: JobStatus::Success,
+                steps: Vec::new(),
+                logs: Vec::new(),
+                duration: Duration::ZERO,
+                summary: String::new(),
+                running: true,
+            });
+            jobs.len() - 1
+        }
+    };
+    &mut jobs[idx]
+}
+
+// The running step of a job of a running workflow
+fn live_step<'a>(
+    jobs: &'a mut [JobExecution],
+    job: &str,
+    step: &str,
+) -> Option<&'a mut StepExecution> {
+    jobs.iter_mut()
+        .find(|live| live.name == job)?
+        .steps
+        .iter_mut()
+        .rev()
+        .find(|live| live.running && live.name == step)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::workflow::revalidate_selected_workflows;
+
+    fn app_with(path: PathBuf) -> App {
+        let (tx, _rx) = mpsc::channel();
+        let mut app = App::new(RuntimeType::Emulation, tx).unwrap();
+        app.workflows = vec![Workflow {
+            name: "pipeline".to_string(),
+            path,
+            selected: false,
+            status: WorkflowStatus::NotStarted,
+            execution_details: None,
+            issue_count: None,
+            job_selection: Default::default(),
+        }];
+        app.workflow_list_state.select(Some(0));
+        app
+    }
+
+    #[test]
+    fn revalidates_gitlab_pipelines_as_pipelines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".gitlab-ci.yml");
+        std::fs::write(
+            &path,
+            "stages: [test]\ntest:\n  stage: test\n  script:\n    - cargo test\n",
+        )
+        .unwrap();
+        let mut app = app_with(path);
+
+        let (validation_tx, validation_rx) = mpsc::channel();
+        revalidate_selected_workflows(&mut app, &validation_tx, false);
+        let (idx, result) = validation_rx.recv().unwrap();
+        app.process_validation_result(idx, result);
+
+        assert_eq!(app.workflows[0].status, WorkflowStatus::Success);
+        assert_eq!(app.workflows[0].issue_count, Some(0));
+    }
+
+    #[test]
+    fn reloads_edited_gitlab_pipelines_as_pipelines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".gitlab-ci.yml");
+        std::fs::write(&path, "build:\n  script:\n    - make\n").unwrap();
+        let mut app = app_with(path.clone());
+
+        app.reload_after_edit(&path);
+        assert_eq!(app.workflows[0].status, WorkflowStatus::NotStarted);
+        assert_eq!(app.workflows[0].issue_count, Some(0));
+    }
+
+    #[test]
+    fn keeps_the_status_of_running_workflows() {
+        let mut app = app_with(PathBuf::from("ci.yml"));
+        app.workflows[0].status = WorkflowStatus::Running;
+        app.current_execution = Some(0);
+
+        app.process_validation_result(0, Ok(vec!["Workflow is missing 'jobs'".to_string()]));
+        assert_eq!(app.workflows[0].status, WorkflowStatus::Running);
+        assert_eq!(app.workflows[0].issue_count, None);
+
+        app.current_execution = None;
+        app.execution_queue.push(0);
+        app.process_validation_result(0, Err("unreadable".to_string()));
+        assert_eq!(app.workflows[0].status, WorkflowStatus::Running);
+    }
+}
@@ -1,16 +1,26 @@
 // Workflow handlers
 use crate::app::App;
-use crate::models::{ExecutionResultMsg, WorkflowExecution, WorkflowStatus};
+use crate::models::{ExecutionResultMsg, ValidationResultMsg, WorkflowExecution, WorkflowStatus};
 use chrono::Local;
 use evaluator::evaluate_workflow_file;
 use executor::{self, JobStatus, RuntimeType, StepStatus};
+use std::collections::HashMap;
 use std::io;
 use std::path::{Path, PathBuf};
 use std::sync::mpsc;
 use std::thread;
 
+/// How many workflows `validate_workflow` found valid and invalid, and how
+/// many of them have warnings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ValidationCounts {
+    pub valid: usize,
+    pub invalid: usize,
+    pub with_warnings: usize,
+}
+
 // Validate a workflow or directory containing workflows
-pub fn validate_workflow(path: &Path, verbose: bool) -> io::Result<()> {
+pub fn validate_workflow(path: &Path, verbose: bool) -> io::Result<ValidationCounts> {
     let mut workflows = Vec::new();
 
     if path.is_dir() {
@@ -33,38 +43,66 @@ pub fn validate_workflow(path: &Path, verbose: bool) -> io::Result<()> {
         ));
     }
 
-    let mut valid_count = 0;
-    let mut invalid_count = 0;
+    let mut counts = ValidationCounts::default();
 
     println!("Validating {} workflow file(s)...", workflows.len());
 
     for workflow_path in workflows {
-        match evaluate_workflow_file(&workflow_path, verbose) {
+        let mut output = String::new();
+        match write_workflow_validation(&workflow_path, verbose, &mut output) {
             Ok(result) => {
                 if result.is_valid {
-                    println!("✅ Valid: {}", workflow_path.display());
-                    valid_count += 1;
+                    counts.valid += 1;
                 } else {
-                    println!("❌ Invalid: {}", workflow_path.display());
-                    for (i, issue) in result.issues.iter().enumerate() {
-                        println!("   {}. {}", i + 1, issue);
-                    }
-                    invalid_count += 1;
+                    counts.invalid += 1;
+                }
+                if !result.warnings.is_empty() {
+                    counts.with_warnings += 1;
                 }
             }
-            Err(e) => {
-                println!("❌ Error processing {}: {}", workflow_path.display(), e);
-                invalid_count += 1;
-            }
+            Err(_) => counts.invalid += 1,
         }
+        print!("{}", output);
     }
 
     println!(
         "\nSummary: {} valid, {} invalid",
-        valid_count, invalid_count
+        counts.valid, counts.invalid
     );
 
-    Ok(())
+    Ok(counts)
+}
+
+/// Validate one workflow, adding whether it's valid and its issues and
+/// warnings to `output`, and return the result or the error that kept it
+/// from being validated
+pub fn write_workflow_validation(
+    path: &Path,
+    verbose: bool,
+    output: &mut String,
+) -> Result<models::ValidationResult, String> {
+    let result = evaluate_workflow_file(path, verbose);
+    match &result {
+        Ok(result) => {
+            if result.is_valid {
+                output.push_str(&format!("✅ Valid: {}\n", path.display()));
+            } else {
+                output.push_str(&format!("❌ Invalid: {}\n", path.display()));
+            }
+            // Issues first, then warnings, each with the line it's about
+            let content = std::fs::read_to_string(path).unwrap_or_default();
+            let mut diagnostics: Vec<_> = result.diagnostics.iter().collect();
+            diagnostics.sort_by_key(|d| d.severity != models::DiagnosticSeverity::Error);
+            for diagnostic in diagnostics {
+                let rendered = evaluator::diagnostics::render(path, &content, diagnostic);
+                for line in rendered.lines() {
+                    output.push_str(&format!("   {}\n", line));
+                }
+            }
+        }
+        Err(e) => output.push_str(&format!("❌ Error processing {}: {}\n", path.display(), e)),
+    }
+    result
 }
 
 // Execute a workflow through the CLI
@@ -72,6 +110,7 @@ pub async fn execute_workflow_cli(
     path: &Path,
     runtime_type: RuntimeType,
     verbose: bool,
+    show_action_messages: bool,
 ) -> io::Result<()> {
     if !path.exists() {
         return Err(io::Error::new(
@@ -93,12 +132,15 @@ pub async fn execute_workflow_cli(
                     "Workflow validation failed",
                 ));
             }
+            for warning in &result.warnings {
+                println!("⚠️  {}", warning);
+            }
         }
         Err(e) => {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                format!("Error validating workflow: {}", e),
-            ));
+            return Err(io::Error::other(format!(
+                "Error validating workflow: {}",
+                e
+            )));
         }
     }
 
@@ -106,13 +148,24 @@ pub async fn execute_workflow_cli(
     let runtime_type = match runtime_type {
         RuntimeType::Docker => {
             if !executor::docker::is_available() {
-                println!("⚠️ Docker is not available. Using emulation mode instead.");
-                logging::warning("Docker is not available. Using emulation mode instead.");
-                RuntimeType::Emulation
+                let fallback = executor::fallback_runtime_type();
+                let message = format!(
+                    "Docker is not available. Using {} mode instead.",
+                    fallback.mode_name()
+                );
+                println!("⚠️ {}", message);
+                logging::warning(&message);
+                fallback
             } else {
                 RuntimeType::Docker
             }
         }
+        // Podman is only used when asked for, so it not running is an error
+        RuntimeType::Podman => {
+            executor::podman::socket_path().map_err(io::Error::other)?;
+            RuntimeType::Podman
+        }
+        RuntimeType::Bubblewrap => RuntimeType::Bubblewrap,
         RuntimeType::Emulation => RuntimeType::Emulation,
     };
 
@@ -127,12 +180,19 @@ pub async fn execute_workflow_cli(
         verbose
     ));
 
-    match executor::execute_workflow(path, runtime_type, verbose).await {
+    let config = executor::ExecutionConfig {
+        show_action_messages,
+        progress: executor::ProgressReporter::default().with_observer(executor::LogObserver),
+        history: true,
+        log_file: true,
+        ..executor::ExecutionConfig::new(runtime_type, verbose)
+    };
+    match executor::execute_workflow_with_config(path, &config).await {
         Ok(result) => {
             println!("\nWorkflow execution results:");
 
-            // Track if the workflow had any failures
-            let mut any_job_failed = false;
+            // Failed jobs that were allowed to fail don't fail the workflow
+            let any_job_failed = result.failure_details.is_some();
 
             for job in &result.jobs {
                 match job.status {
@@ -141,7 +201,6 @@ pub async fn execute_workflow_cli(
                     }
                     JobStatus::Failure => {
                         println!("\n❌ Job failed: {}", job.name);
-                        any_job_failed = true;
                     }
                     JobStatus::Skipped => {
                         println!("\n⏭️ Job skipped: {}", job.name);
@@ -159,9 +218,7 @@ pub async fn execute_workflow_cli(
                             println!("  ✅ {}", step.name);
 
                             // Check if this is a GitHub action output that should be hidden
-                            let should_hide = std::env::var("WRKFLW_HIDE_ACTION_MESSAGES")
-                                .map(|val| val == "true")
-                                .unwrap_or(false)
+                            let should_hide = !show_action_messages
                                 && step.output.contains("Would execute GitHub action:");
 
                             // Only show output if not hidden and it's short
@@ -262,26 +319,18 @@ pub async fn execute_workflow_cli(
         Err(e) => {
             println!("❌ Failed to execute workflow: {}", e);
             logging::error(&format!("Failed to execute workflow: {}", e));
-            Err(io::Error::new(io::ErrorKind::Other, e))
+            Err(io::Error::other(e))
         }
     }
 }
 
-// Helper function to execute workflow trigger using curl
-pub async fn execute_curl_trigger(
+// Helper function to trigger a workflow on GitHub
+pub async fn execute_remote_trigger(
     workflow_name: &str,
     branch: Option<&str>,
+    inputs: Option<HashMap<String, String>>,
 ) -> Result<(Vec<executor::JobResult>, ()), String> {
-    // Get GitHub token
-    let token = std::env::var("GITHUB_TOKEN").map_err(|_| {
-        "GitHub token not found. Please set GITHUB_TOKEN environment variable".to_string()
-    })?;
-
-    // Debug log to check if GITHUB_TOKEN is set
-    match std::env::var("GITHUB_TOKEN") {
-        Ok(token) => logging::info(&format!("GITHUB_TOKEN is set: {}", &token[..5])), // Log first 5 characters for security
-        Err(_) => logging::error("GITHUB_TOKEN is not set"),
-    }
+    let client = github::GithubClient::from_env().map_err(|e| e.to_string())?;
 
     // Get repository information
     let repo_info =
@@ -302,43 +351,15 @@ pub async fn execute_curl_trigger(
 
     logging::info(&format!("Using workflow name: {}", workflow_name));
 
-    // Construct JSON payload
-    let payload = serde_json::json!({
-        "ref": branch_ref
-    });
-
-    // Construct API URL
-    let url = format!(
-        "https://api.github.com/repos/{}/{}/actions/workflows/{}.yml/dispatches",
-        repo_info.owner, repo_info.repo, workflow_name
-    );
-
-    logging::info(&format!("Triggering workflow at URL: {}", url));
-
-    // Create a reqwest client
-    let client = reqwest::Client::new();
-
-    // Send the request using reqwest
-    let response = client
-        .post(&url)
-        .header("Authorization", format!("Bearer {}", token.trim()))
-        .header("Accept", "application/vnd.github.v3+json")
-        .header("Content-Type", "application/json")
-        .header("User-Agent", "wrkflw-cli")
-        .json(&payload)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to send request: {}", e))?;
-
-    if !response.status().is_success() {
-        let status = response.status().as_u16();
-        let error_message = response
-            .text()
-            .await
-            .unwrap_or_else(|_| format!("Unknown error (HTTP {})", status));
-
-        return Err(format!("API error: {} - {}", status, error_message));
-    }
+    github::dispatch_workflow(
+        &client,
+        &repo_info,
+        &format!("{}.yml", workflow_name),
+        branch_ref,
+        inputs.as_ref(),
+    )
+    .await
+    .map_err(|e| e.to_string())?;
 
     // Success message with URL to view the workflow
     let success_msg = format!(
@@ -354,13 +375,76 @@ pub async fn execute_curl_trigger(
             name: "Remote Trigger".to_string(),
             status: executor::StepStatus::Success,
             output: success_msg,
+            output_file: None,
+            exit_code: None,
+            duration: std::time::Duration::ZERO,
+            overhead: executor::Overhead::default(),
+            annotations: Vec::new(),
         }],
         logs: "Workflow triggered remotely on GitHub".to_string(),
+        outputs: HashMap::new(),
+        duration: std::time::Duration::ZERO,
+        overhead: executor::Overhead::default(),
+        summary: String::new(),
     };
 
     Ok((vec![job_result], ()))
 }
 
+// Re-validate the selected workflows (or the highlighted one if none are
+// selected) on a background thread after a mode toggle
+pub fn revalidate_selected_workflows(
+    app: &mut App,
+    validation_tx: &mpsc::Sender<ValidationResultMsg>,
+    verbose: bool,
+) {
+    let mut targets: Vec<(usize, PathBuf)> = app
+        .workflows
+        .iter()
+        .enumerate()
+        .filter(|(_, workflow)| workflow.selected)
+        .map(|(idx, workflow)| (idx, workflow.path.clone()))
+        .collect();
+
+    if targets.is_empty() {
+        if let Some(idx) = app.workflow_list_state.selected() {
+            if let Some(workflow) = app.workflows.get(idx) {
+                targets.push((idx, workflow.path.clone()));
+            }
+        }
+    }
+    // Their status is the run's until it's done
+    targets.retain(|(idx, _)| !app.is_queued_or_running(*idx));
+
+    if targets.is_empty() {
+        return;
+    }
+
+    for (idx, _) in &targets {
+        app.workflows[*idx].status = WorkflowStatus::Running;
+    }
+
+    let timestamp = Local::now().format("%H:%M:%S").to_string();
+    app.logs.push(format!(
+        "[{}] Re-validating {} workflow(s) in the background",
+        timestamp,
+        targets.len()
+    ));
+
+    let tx = validation_tx.clone();
+    thread::spawn(move || {
+        for (idx, path) in targets {
+            let result = evaluator::evaluate_detected_file(&path, verbose)
+                .map(|validation| validation.issues)
+                .map_err(|e| e.to_string());
+            if tx.send((idx, result)).is_err() {
+                // The TUI has exited
+                break;
+            }
+        }
+    });
+}
+
 // Extract common workflow execution logic to avoid duplication
 pub fn start_next_workflow_execution(
     app: &mut App,
@@ -370,6 +454,8 @@ pub fn start_next_workflow_execution(
     if let Some(next_idx) = app.get_next_workflow_to_execute() {
         app.current_execution = Some(next_idx);
         let tx_clone_inner = tx_clone.clone();
+        let progress_tx = app.progress_tx.clone();
+        let show_action_messages = app.show_action_messages;
         let workflow_path = app.workflows[next_idx].path.clone();
 
         // Log whether verbose mode is enabled
@@ -403,18 +489,51 @@ pub fn start_next_workflow_execution(
                     };
 
                 if !is_docker_available {
-                    app.logs
-                        .push("Docker is not available. Using emulation mode instead.".to_string());
-                    logging::warning("Docker is not available. Using emulation mode instead.");
-                    RuntimeType::Emulation
+                    let fallback = executor::fallback_runtime_type();
+                    let message = format!(
+                        "Docker is not available. Using {} mode instead.",
+                        fallback.mode_name()
+                    );
+                    app.logs.push(message.clone());
+                    logging::warning(&message);
+                    fallback
                 } else {
                     RuntimeType::Docker
                 }
             }
+            RuntimeType::Podman => match executor::podman::socket_path() {
+                Ok(_) => RuntimeType::Podman,
+                // Fails the workflow, like any other error starting it
+                Err(e) => {
+                    app.workflows[next_idx].status = WorkflowStatus::Running;
+                    let _ = tx_clone.send((next_idx, Err(e)));
+                    return;
+                }
+            },
+            RuntimeType::Bubblewrap => RuntimeType::Bubblewrap,
             RuntimeType::Emulation => RuntimeType::Emulation,
         };
 
         let validation_mode = app.validation_mode;
+        let config = executor::ExecutionConfig {
+            show_action_messages,
+            progress: executor::ProgressReporter::new(progress_tx)
+                .with_observer(executor::LogObserver),
+            job_selection: app.workflows[next_idx].job_selection.clone(),
+            concurrency: app.concurrency.clone(),
+            cancellation: app.running_cancellation.clone().unwrap_or_default(),
+            ci_env: app.ci_env,
+            docker_retry: executor::retry::RetryPolicy::with_retries(app.docker_retries),
+            runner_images: app.runner_images.clone(),
+            environments: app.environments.clone(),
+            resources: app.resources.clone(),
+            platforms: app.platforms.clone(),
+            approvals: app.approvals.clone(),
+            step_control: app.step_control.clone(),
+            history: true,
+            log_file: true,
+            ..executor::ExecutionConfig::new(runtime_type, verbose)
+        };
 
         // Update workflow status and add execution details
         app.workflows[next_idx].status = WorkflowStatus::Running;
@@ -427,6 +546,8 @@ pub fn start_next_workflow_execution(
                 end_time: None,
                 logs: Vec::new(),
                 progress: 0.0,
+                step_progress: executor::Progress::default(),
+                job_events: HashMap::new(),
             });
         }
 
@@ -445,7 +566,7 @@ pub fn start_next_workflow_execution(
             let result = rt.block_on(async {
                 if validation_mode {
                     // Perform validation instead of execution
-                    match evaluate_workflow_file(&workflow_path, verbose) {
+                    match evaluator::evaluate_detected_file(&workflow_path, verbose) {
                         Ok(validation_result) => {
                             // Create execution result based on validation
                             let status = if validation_result.is_valid {
@@ -465,7 +586,18 @@ pub fn start_next_workflow_execution(
                                     } else {
                                         executor::StepStatus::Failure
                                     },
-                                    output: validation_result.issues.join("\n"),
+                                    output: validation_result
+                                        .issues
+                                        .iter()
+                                        .chain(&validation_result.warnings)
+                                        .cloned()
+                                        .collect::<Vec<_>>()
+                                        .join("\n"),
+                                    output_file: None,
+                                    exit_code: None,
+                                    duration: std::time::Duration::ZERO,
+                                    overhead: executor::Overhead::default(),
+                                    annotations: Vec::new(),
                                 }],
                                 logs: format!(
                                     "Validation result: {}",
@@ -475,6 +607,10 @@ pub fn start_next_workflow_execution(
                                         "FAILED"
                                     }
                                 ),
+                                outputs: HashMap::new(),
+                                duration: std::time::Duration::ZERO,
+                                overhead: executor::Overhead::default(),
+                                summary: String::new(),
                             }];
 
                             Ok((jobs, ()))
@@ -485,7 +621,7 @@ pub fn start_next_workflow_execution(
                     // Use safe FD redirection for execution
                     let execution_result = utils::fd::with_stderr_to_null(|| {
                         futures::executor::block_on(async {
-                            executor::execute_workflow(&workflow_path, runtime_type, verbose).await
+                            executor::execute_workflow_with_config(&workflow_path, &config).await
                         })
                     })
                     .map_err(|e| format!("Failed to redirect stderr during execution: {}", e))?;
@@ -3,8 +3,10 @@
 // This crate is organized into several modules:
 // - app: Contains the main App state and TUI entry point
 // - models: Contains the data structures for the UI
+// - recording: Records TUI sessions and loads them for replay
 // - components: Contains reusable UI elements
 // - handlers: Contains workflow handling logic
+// - theme: Contains the colors and status symbols of the views
 // - utils: Contains utility functions
 // - views: Contains UI rendering code
 
@@ -13,10 +15,12 @@ pub mod app;
 pub mod components;
 pub mod handlers;
 pub mod models;
+pub mod recording;
+pub mod theme;
 pub mod utils;
 pub mod views;
 
 // Re-export main entry points
-pub use app::run_wrkflw_tui;
+pub use app::{run_replay_tui, run_wrkflw_tui};
 pub use handlers::workflow::execute_workflow_cli;
-pub use handlers::workflow::validate_workflow;
+pub use handlers::workflow::{validate_workflow, write_workflow_validation, ValidationCounts};
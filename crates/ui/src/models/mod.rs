@@ -1,11 +1,18 @@
 // UI Models for wrkflw
 use chrono::Local;
+use executor::dependency::JobSelection;
 use executor::{JobStatus, StepStatus};
+use github::inputs::{DispatchInput, InputKind};
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::time::Duration;
 
 /// Type alias for the complex execution result type
 pub type ExecutionResultMsg = (usize, Result<(Vec<executor::JobResult>, ()), String>);
 
+/// Background validation result: workflow index and the issues found
+pub type ValidationResultMsg = (usize, Result<Vec<String>, String>);
+
 /// Represents an individual workflow file
 pub struct Workflow {
     pub name: String,
@@ -13,6 +20,8 @@ pub struct Workflow {
     pub selected: bool,
     pub status: WorkflowStatus,
     pub execution_details: Option<WorkflowExecution>,
+    pub issue_count: Option<usize>, // Set by the last background validation
+    pub job_selection: JobSelection, // Jobs picked in the execution tab
 }
 
 /// Status of a workflow
@@ -23,6 +32,8 @@ pub enum WorkflowStatus {
     Success,
     Failed,
     Skipped,
+    /// Stopped or dropped from the queue by a newer run of its concurrency group
+    Cancelled,
 }
 
 /// Detailed execution information
@@ -31,7 +42,9 @@ pub struct WorkflowExecution {
     pub start_time: chrono::DateTime<Local>,
     pub end_time: Option<chrono::DateTime<Local>>,
     pub logs: Vec<String>,
-    pub progress: f64, // 0.0 - 1.0 for progress bar
+    pub progress: f64,                     // 0.0 - 1.0 for progress bar
+    pub step_progress: executor::Progress, // Steps completed out of the planned total
+    pub job_events: HashMap<String, Option<JobStatus>>, // Jobs started so far, None while running
 }
 
 /// Job execution details
@@ -40,6 +53,19 @@ pub struct JobExecution {
     pub status: JobStatus,
     pub steps: Vec<StepExecution>,
     pub logs: Vec<String>,
+    pub duration: Duration,
+    /// Markdown the job's steps wrote to `$GITHUB_STEP_SUMMARY`
+    pub summary: String,
+    pub running: bool, // Built from the run's events, until its results arrive
+}
+
+impl JobExecution {
+    /// Name of the workflow job, without the matrix values of a combination
+    pub fn job_name(&self) -> &str {
+        self.name
+            .split_once(" (")
+            .map_or(self.name.as_str(), |(name, _)| name)
+    }
 }
 
 /// Step execution details
@@ -47,6 +73,106 @@ pub struct StepExecution {
     pub name: String,
     pub status: StepStatus,
     pub output: String,
+    pub output_file: Option<std::path::PathBuf>, // Full output, when the step ran
+    pub duration: Duration,
+    pub overhead: executor::Overhead, // Part of the duration spent on images and containers
+    pub annotations: Vec<executor::actions::Annotation>, // Errors, warnings and notices it printed
+    pub running: bool,                // Started and not completed yet
+}
+
+impl StepExecution {
+    /// A step that just started, filled in by the run's events
+    pub fn started(name: &str) -> Self {
+        StepExecution {
+            name: name.to_string(),
+            status: StepStatus::Success,
+            output: String::new(),
+            output_file: None,
+            duration: Duration::ZERO,
+            overhead: executor::Overhead::default(),
+            annotations: Vec::new(),
+            running: true,
+        }
+    }
+}
+
+/// Scrolling and search of the step output in the execution tab and the
+/// job detail view
+pub struct StepOutputView {
+    pub scroll: usize,        // First row shown
+    pub follow: bool,         // Keep the last rows in view as output arrives
+    pub rows: usize,          // Rows the output had room for when last drawn
+    pub search_query: String, // Text searched for in the output
+    pub search_active: bool,  // Whether the query is being typed
+    pub match_idx: usize,     // Match shown, counted from the top
+    pub match_count: usize,   // Matches found when last drawn
+    pub jump: bool,           // Scroll to the match shown when next drawn
+    pub steps_seen: usize,    // Steps of the job when last drawn, to follow new ones
+}
+
+impl Default for StepOutputView {
+    fn default() -> Self {
+        StepOutputView {
+            scroll: 0,
+            follow: true,
+            rows: 0,
+            search_query: String::new(),
+            search_active: false,
+            match_idx: 0,
+            match_count: 0,
+            jump: false,
+            steps_seen: 0,
+        }
+    }
+}
+
+/// Layout of the execution tab, with the job tree beside the output of the
+/// selected step
+pub struct SplitView {
+    pub tree_width: u16,              // Percentage of the width taken by the tree
+    pub maximized: Option<SplitPane>, // Pane taking the whole tab, if any
+    pub on_job: bool,                 // A job's own row is selected rather than one of its steps
+}
+
+impl SplitView {
+    pub const MIN_TREE_WIDTH: u16 = 20;
+    pub const MAX_TREE_WIDTH: u16 = 80;
+
+    /// Widen the tree, or narrow it, by a step, showing both panes again
+    pub fn resize(&mut self, wider: bool) {
+        self.maximized = None;
+        self.tree_width = if wider {
+            (self.tree_width + 5).min(Self::MAX_TREE_WIDTH)
+        } else {
+            self.tree_width.saturating_sub(5).max(Self::MIN_TREE_WIDTH)
+        };
+    }
+
+    /// Maximize the tree, then the output, then show both again
+    pub fn cycle_maximized(&mut self) {
+        self.maximized = match self.maximized {
+            None => Some(SplitPane::Tree),
+            Some(SplitPane::Tree) => Some(SplitPane::Output),
+            Some(SplitPane::Output) => None,
+        };
+    }
+}
+
+impl Default for SplitView {
+    fn default() -> Self {
+        SplitView {
+            tree_width: 40,
+            maximized: None,
+            on_job: true,
+        }
+    }
+}
+
+/// A pane of the execution tab's split view
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SplitPane {
+    Tree,
+    Output,
 }
 
 /// Log filter levels
@@ -97,3 +223,101 @@ impl LogFilterLevel {
         }
     }
 }
+
+/// Values for the `workflow_dispatch` inputs of a workflow about to be
+/// triggered, starting from their defaults
+pub struct DispatchForm {
+    pub workflow_idx: usize,
+    pub inputs: Vec<DispatchInput>,
+    pub values: Vec<String>,
+    pub selected: usize,
+    pub error: Option<String>, // Why the last submit was refused
+}
+
+impl DispatchForm {
+    pub fn new(workflow_idx: usize, inputs: Vec<DispatchInput>) -> Self {
+        let values = inputs
+            .iter()
+            .map(|input| match (&input.default, &input.kind) {
+                (Some(default), _) => default.clone(),
+                (None, InputKind::Boolean) => "false".to_string(),
+                (None, InputKind::Choice(options)) if input.required => {
+                    options.first().cloned().unwrap_or_default()
+                }
+                (None, _) => String::new(),
+            })
+            .collect();
+        DispatchForm {
+            workflow_idx,
+            inputs,
+            values,
+            selected: 0,
+            error: None,
+        }
+    }
+
+    pub fn next_field(&mut self) {
+        self.selected = (self.selected + 1) % self.inputs.len().max(1);
+    }
+
+    pub fn previous_field(&mut self) {
+        let len = self.inputs.len().max(1);
+        self.selected = (self.selected + len - 1) % len;
+    }
+
+    /// Toggle a boolean or move a choice `step` options along; other
+    /// fields are typed into
+    pub fn cycle(&mut self, step: isize) {
+        let Some(input) = self.inputs.get(self.selected) else {
+            return;
+        };
+        let value = &mut self.values[self.selected];
+        match &input.kind {
+            InputKind::Boolean => {
+                *value = if value == "true" { "false" } else { "true" }.to_string();
+            }
+            InputKind::Choice(options) if !options.is_empty() => {
+                let len = options.len() as isize;
+                let current = options.iter().position(|o| o == value);
+                let next = match current {
+                    Some(i) => (i as isize + step).rem_euclid(len),
+                    None if step < 0 => len - 1,
+                    None => 0,
+                };
+                *value = options[next as usize].clone();
+            }
+            _ => {}
+        }
+    }
+
+    pub fn type_char(&mut self, c: char) {
+        if let Some(input) = self.inputs.get(self.selected) {
+            match input.kind {
+                InputKind::Boolean | InputKind::Choice(_) => {
+                    if c == ' ' {
+                        self.cycle(1);
+                    }
+                }
+                _ => self.values[self.selected].push(c),
+            }
+        }
+    }
+
+    pub fn backspace(&mut self) {
+        if let Some(input) = self.inputs.get(self.selected) {
+            if !matches!(input.kind, InputKind::Boolean | InputKind::Choice(_)) {
+                self.values[self.selected].pop();
+            }
+        }
+    }
+
+    /// The inputs to send: empty fields are left for GitHub's defaults
+    pub fn values(&self) -> HashMap<String, String> {
+        self.inputs
+            .iter()
+            .zip(&self.values)
+            .filter(|(_, value)| !value.is_empty())
+            .map(|(input, value)| (input.name.clone(), value.clone()))
+            .collect()
+    }
+}
@@ -0,0 +1,338 @@
+// Recording of TUI sessions, and loading them back for replay
+use executor::ExecutionEvent;
+use ratatui::buffer::Buffer;
+use ratatui::text::Span;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Version of the recording format, bumped on incompatible changes
+pub const RECORDING_VERSION: u32 = 1;
+
+/// Screens closer together than this are collapsed into the later one
+const FRAME_INTERVAL: Duration = Duration::from_millis(250);
+
+/// One line of a recording file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RecordedEntry {
+    Session {
+        version: u32,
+        started_at: String,
+    },
+    /// The screen as it was drawn, one string per row
+    Frame {
+        at_ms: u64,
+        lines: Vec<String>,
+    },
+    Log {
+        at_ms: u64,
+        line: String,
+    },
+    Event {
+        at_ms: u64,
+        event: ExecutionEvent,
+    },
+}
+
+impl RecordedEntry {
+    pub fn at_ms(&self) -> u64 {
+        match self {
+            RecordedEntry::Session { .. } => 0,
+            RecordedEntry::Frame { at_ms, .. }
+            | RecordedEntry::Log { at_ms, .. }
+            | RecordedEntry::Event { at_ms, .. } => *at_ms,
+        }
+    }
+}
+
+/// Writes the screens, logs and execution events of a TUI session as JSON lines
+pub struct SessionRecorder {
+    writer: BufWriter<File>,
+    started: Instant,
+    last_frame: Vec<String>,
+    last_frame_at: Option<Instant>,
+    // A screen held back by the frame interval, written if nothing replaces it
+    pending_frame: Option<(u64, Vec<String>)>,
+    app_logs_seen: usize,
+    system_logs_seen: usize,
+    failed: bool,
+}
+
+impl SessionRecorder {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        let mut recorder = SessionRecorder {
+            writer: BufWriter::new(File::create(path)?),
+            started: Instant::now(),
+            last_frame: Vec::new(),
+            last_frame_at: None,
+            pending_frame: None,
+            app_logs_seen: 0,
+            system_logs_seen: 0,
+            failed: false,
+        };
+        recorder.write(&RecordedEntry::Session {
+            version: RECORDING_VERSION,
+            started_at: chrono::Local::now().to_rfc3339(),
+        });
+        Ok(recorder)
+    }
+
+    fn elapsed_ms(&self) -> u64 {
+        self.started.elapsed().as_millis() as u64
+    }
+
+    fn write(&mut self, entry: &RecordedEntry) {
+        if self.failed {
+            return;
+        }
+        let result = serde_json::to_string(entry)
+            .map_err(io::Error::other)
+            .and_then(|line| writeln!(self.writer, "{}", line));
+        if let Err(e) = result {
+            // Keep the session going, just stop recording it
+            self.failed = true;
+            logging::error(&format!("Stopped recording the session: {}", e));
+        }
+    }
+
+    /// Record the screen that was just drawn, if it changed
+    pub fn record_frame(&mut self, buffer: &Buffer) {
+        let lines = buffer_lines(buffer);
+        if lines == self.last_frame {
+            return;
+        }
+        let at_ms = self.elapsed_ms();
+        self.last_frame = lines.clone();
+
+        if self
+            .last_frame_at
+            .is_some_and(|at| at.elapsed() < FRAME_INTERVAL)
+        {
+            self.pending_frame = Some((at_ms, lines));
+            return;
+        }
+        // The last screen of a burst, at the time it was drawn
+        if let Some((at_ms, lines)) = self.pending_frame.take() {
+            self.write(&RecordedEntry::Frame { at_ms, lines });
+        }
+        self.last_frame_at = Some(Instant::now());
+        self.write(&RecordedEntry::Frame { at_ms, lines });
+    }
+
+    /// Record log lines added since the last call. The app's own logs and the
+    /// shared logging store are tracked separately.
+    pub fn record_logs(&mut self, app_logs: &[String], system_logs: &[String]) {
+        let at_ms = self.elapsed_ms();
+        let mut lines = unseen(app_logs, &mut self.app_logs_seen).to_vec();
+        lines.extend_from_slice(unseen(system_logs, &mut self.system_logs_seen));
+        for line in lines {
+            self.write(&RecordedEntry::Log { at_ms, line });
+        }
+    }
+
+    pub fn record_event(&mut self, event: &ExecutionEvent) {
+        let at_ms = self.elapsed_ms();
+        self.write(&RecordedEntry::Event {
+            at_ms,
+            event: event.clone(),
+        });
+    }
+
+    /// Write out anything held back and flush the file
+    fn finish(&mut self) {
+        if let Some((at_ms, lines)) = self.pending_frame.take() {
+            self.write(&RecordedEntry::Frame { at_ms, lines });
+        }
+        if !self.failed {
+            if let Err(e) = self.writer.flush() {
+                logging::error(&format!("Failed to save the session recording: {}", e));
+            }
+        }
+    }
+}
+
+impl Drop for SessionRecorder {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+/// Lines added since `seen` lines were last looked at
+fn unseen<'l>(logs: &'l [String], seen: &mut usize) -> &'l [String] {
+    // The logs were cleared, start over
+    if logs.len() < *seen {
+        *seen = 0;
+    }
+    let new_lines = &logs[*seen..];
+    *seen = logs.len();
+    new_lines
+}
+
+/// Text of each row of a drawn buffer. Cells covered by a wide character
+/// are left out so the rows render the same way again.
+fn buffer_lines(buffer: &Buffer) -> Vec<String> {
+    let area = buffer.area;
+    (area.top()..area.bottom())
+        .map(|y| {
+            let mut line = String::new();
+            let mut hidden = 0;
+            for x in area.left()..area.right() {
+                if hidden > 0 {
+                    hidden -= 1;
+                    continue;
+                }
+                let symbol = &buffer.get(x, y).symbol;
+                line.push_str(symbol);
+                hidden = Span::raw(symbol.as_str()).width().saturating_sub(1);
+            }
+            line
+        })
+        .collect()
+}
+
+/// A recording loaded from disk
+#[derive(Debug, Default)]
+pub struct Recording {
+    pub started_at: String,
+    pub entries: Vec<RecordedEntry>,
+}
+
+impl Recording {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut recording = Recording::default();
+
+        for (idx, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry: RecordedEntry = serde_json::from_str(&line).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid recording at line {}: {}", idx + 1, e),
+                )
+            })?;
+            if let RecordedEntry::Session {
+                version,
+                started_at,
+            } = &entry
+            {
+                if *version > RECORDING_VERSION {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "Recording format version {} is newer than this wrkflw supports",
+                            version
+                        ),
+                    ));
+                }
+                recording.started_at = started_at.clone();
+            }
+            recording.entries.push(entry);
+        }
+
+        Ok(recording)
+    }
+
+    /// Recorded screens with their offset from the start of the session
+    pub fn frames(&self) -> Vec<(u64, &[String])> {
+        self.entries
+            .iter()
+            .filter_map(|entry| match entry {
+                RecordedEntry::Frame { at_ms, lines } => Some((*at_ms, lines.as_slice())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn duration_ms(&self) -> u64 {
+        self.entries
+            .iter()
+            .map(RecordedEntry::at_ms)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use executor::StepStatus;
+    use ratatui::layout::Rect;
+    use ratatui::style::Style;
+
+    #[test]
+    fn records_and_loads_a_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.jsonl");
+
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 6, 2));
+        buffer.set_string(0, 0, "✅ ok", Style::default());
+        {
+            let mut recorder = SessionRecorder::create(&path).unwrap();
+            recorder.record_frame(&buffer);
+            // Unchanged screens aren't recorded again
+            recorder.record_frame(&buffer);
+            recorder.record_logs(&["started".to_string()], &[]);
+            recorder.record_logs(&["started".to_string()], &["info".to_string()]);
+            recorder.record_event(&ExecutionEvent::StepCompleted {
+                job: "build".to_string(),
+                step: "Run tests".to_string(),
+                status: StepStatus::Success,
+            });
+        }
+
+        let recording = Recording::load(&path).unwrap();
+        assert!(!recording.started_at.is_empty());
+        let frames = recording.frames();
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].1, ["✅ ok ", "      "]);
+
+        let logs: Vec<_> = recording
+            .entries
+            .iter()
+            .filter_map(|e| match e {
+                RecordedEntry::Log { line, .. } => Some(line.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(logs, ["started", "info"]);
+        assert!(matches!(
+            recording.entries.last(),
+            Some(RecordedEntry::Event {
+                event: ExecutionEvent::StepCompleted { .. },
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn keeps_the_last_screen_of_a_burst() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.jsonl");
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 1, 1));
+        let mut recorder = SessionRecorder::create(&path).unwrap();
+        for text in ["a", "b", "c"] {
+            buffer.set_string(0, 0, text, Style::default());
+            recorder.record_frame(&buffer);
+        }
+        // The UI sat idle, then drew again
+        recorder.last_frame_at = Some(Instant::now() - FRAME_INTERVAL);
+        buffer.set_string(0, 0, "d", Style::default());
+        recorder.record_frame(&buffer);
+        recorder.writer.flush().unwrap();
+
+        let recording = Recording::load(&path).unwrap();
+        let screens: Vec<_> = recording
+            .frames()
+            .iter()
+            .map(|(_, lines)| lines.concat())
+            .collect();
+        assert_eq!(screens, ["a", "c", "d"]);
+        drop(recorder);
+    }
+}
@@ -0,0 +1,262 @@
+// Colors and status symbols of the TUI, picked in the `[ui]` section of
+// .wrkflw.toml
+use ratatui::style::{Color, Modifier, Style};
+use std::str::FromStr;
+use utils::project::{PaletteConfig, UiConfig};
+
+/// Markers of statuses, as emoji or as ASCII for terminals and fonts that
+/// draw emoji poorly
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Symbols {
+    pub success: &'static str,
+    pub failure: &'static str,
+    pub running: &'static str,
+    pub skipped: &'static str,
+    pub cancelled: &'static str,
+    pub pending: &'static str,
+    pub warning: &'static str,
+    pub notice: &'static str,
+    pub approval: &'static str,
+    pub paused: &'static str,
+}
+
+impl Symbols {
+    pub const EMOJI: Symbols = Symbols {
+        success: "✅",
+        failure: "❌",
+        running: "⏳",
+        skipped: "⏭",
+        cancelled: "⏹",
+        pending: "○",
+        warning: "⚠️",
+        notice: "ℹ️",
+        approval: "🔒",
+        paused: "⏸",
+    };
+
+    pub const ASCII: Symbols = Symbols {
+        success: "[ok]",
+        failure: "[x]",
+        running: "[~]",
+        skipped: "[-]",
+        cancelled: "[/]",
+        pending: "[ ]",
+        warning: "[!]",
+        notice: "[i]",
+        approval: "[?]",
+        paused: "[||]",
+    };
+}
+
+/// Colors of every view by what they show, and the status symbols
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    pub accent: Color,     // Titles, headers and keys
+    pub label: Color,      // Labels in front of values
+    pub text: Color,       // Values and names
+    pub muted: Color,      // Hints, durations and other secondary text
+    pub selection: Color,  // Background of the selected row and the status bar
+    pub background: Color, // Background of the whole screen
+    pub success: Color,
+    pub failure: Color,
+    pub running: Color,
+    pub skipped: Color,
+    pub cancelled: Color,
+    pub warning: Color,
+    pub info: Color, // Notices, folded groups and other highlights
+    pub symbols: Symbols,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            accent: Color::Yellow,
+            label: Color::Blue,
+            text: Color::White,
+            muted: Color::DarkGray,
+            selection: Color::DarkGray,
+            background: Color::Reset,
+            success: Color::Green,
+            failure: Color::Red,
+            running: Color::Cyan,
+            skipped: Color::Gray,
+            cancelled: Color::Magenta,
+            warning: Color::Yellow,
+            info: Color::Cyan,
+            symbols: Symbols::EMOJI,
+        }
+    }
+}
+
+impl Theme {
+    pub const BUILT_IN: [&'static str; 4] = ["default", "dark", "light", "high-contrast"];
+
+    /// One of the themes wrkflw comes with
+    pub fn built_in(name: &str) -> Option<Self> {
+        let theme = match name {
+            "default" => Theme::default(),
+            "dark" => Theme {
+                accent: Color::Rgb(229, 192, 123),
+                label: Color::Rgb(97, 175, 239),
+                text: Color::Rgb(220, 223, 228),
+                muted: Color::Rgb(92, 99, 112),
+                selection: Color::Rgb(62, 68, 81),
+                background: Color::Rgb(40, 44, 52),
+                success: Color::Rgb(152, 195, 121),
+                failure: Color::Rgb(224, 108, 117),
+                running: Color::Rgb(86, 182, 194),
+                skipped: Color::Rgb(171, 178, 191),
+                cancelled: Color::Rgb(198, 120, 221),
+                warning: Color::Rgb(209, 154, 102),
+                info: Color::Rgb(86, 182, 194),
+                symbols: Symbols::EMOJI,
+            },
+            "light" => Theme {
+                accent: Color::Rgb(152, 104, 1),
+                label: Color::Rgb(64, 120, 242),
+                text: Color::Rgb(56, 58, 66),
+                muted: Color::Rgb(160, 161, 167),
+                selection: Color::Rgb(220, 220, 222),
+                background: Color::Rgb(250, 250, 250),
+                success: Color::Rgb(80, 161, 79),
+                failure: Color::Rgb(228, 86, 73),
+                running: Color::Rgb(1, 132, 188),
+                skipped: Color::Rgb(105, 108, 119),
+                cancelled: Color::Rgb(166, 38, 164),
+                warning: Color::Rgb(193, 132, 1),
+                info: Color::Rgb(1, 132, 188),
+                symbols: Symbols::EMOJI,
+            },
+            "high-contrast" => Theme {
+                accent: Color::LightYellow,
+                label: Color::LightCyan,
+                text: Color::White,
+                muted: Color::Gray,
+                selection: Color::Blue,
+                background: Color::Black,
+                success: Color::LightGreen,
+                failure: Color::LightRed,
+                running: Color::LightCyan,
+                skipped: Color::White,
+                cancelled: Color::LightMagenta,
+                warning: Color::LightYellow,
+                info: Color::LightCyan,
+                symbols: Symbols::EMOJI,
+            },
+            _ => return None,
+        };
+        Some(theme)
+    }
+
+    /// The theme a project picked, built in or from its own palettes, with
+    /// ASCII symbols when it turned emoji off
+    pub fn from_project(config: &UiConfig) -> Result<Self, String> {
+        let name = config.theme.as_deref().unwrap_or("default");
+        let mut theme = match config.themes.get(name) {
+            Some(palette) => {
+                let base = palette.base.as_deref().unwrap_or("default");
+                let mut theme = Theme::built_in(base)
+                    .ok_or_else(|| format!("Unknown base theme '{}' of theme '{}'", base, name))?;
+                theme
+                    .apply(palette)
+                    .map_err(|e| format!("Invalid theme '{}': {}", name, e))?;
+                theme
+            }
+            None => Theme::built_in(name).ok_or_else(|| {
+                format!(
+                    "Unknown theme '{}', expected one of {} or a theme in [ui.themes]",
+                    name,
+                    Theme::BUILT_IN.join(", ")
+                )
+            })?,
+        };
+        if !config.emoji {
+            theme.symbols = Symbols::ASCII;
+        }
+        Ok(theme)
+    }
+
+    // Take the colors a palette sets
+    fn apply(&mut self, palette: &PaletteConfig) -> Result<(), String> {
+        let colors = [
+            ("accent", &palette.accent, &mut self.accent),
+            ("label", &palette.label, &mut self.label),
+            ("text", &palette.text, &mut self.text),
+            ("muted", &palette.muted, &mut self.muted),
+            ("selection", &palette.selection, &mut self.selection),
+            ("background", &palette.background, &mut self.background),
+            ("success", &palette.success, &mut self.success),
+            ("failure", &palette.failure, &mut self.failure),
+            ("running", &palette.running, &mut self.running),
+            ("skipped", &palette.skipped, &mut self.skipped),
+            ("cancelled", &palette.cancelled, &mut self.cancelled),
+            ("warning", &palette.warning, &mut self.warning),
+            ("info", &palette.info, &mut self.info),
+        ];
+        for (role, value, color) in colors {
+            if let Some(value) = value {
+                *color = Color::from_str(value)
+                    .map_err(|_| format!("'{}' is not a color for {}", value, role))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Highlight of the selected row of a list or table
+    pub fn highlight(&self) -> Style {
+        Style::default()
+            .bg(self.selection)
+            .add_modifier(Modifier::BOLD)
+    }
+
+    /// Text drawn on a bar of one of the theme's colors
+    pub fn on_color(&self) -> Color {
+        match self.background {
+            Color::Reset => Color::Black,
+            color => color,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn picks_built_in_and_project_themes() {
+        assert_eq!(
+            Theme::from_project(&UiConfig::default()).unwrap(),
+            Theme::default()
+        );
+
+        let mut config = UiConfig {
+            theme: Some("high-contrast".to_string()),
+            emoji: false,
+            themes: BTreeMap::new(),
+        };
+        let theme = Theme::from_project(&config).unwrap();
+        assert_eq!(theme.background, Color::Black);
+        assert_eq!(theme.symbols, Symbols::ASCII);
+
+        config.theme = Some("mine".to_string());
+        assert!(Theme::from_project(&config).is_err());
+
+        config.themes.insert(
+            "mine".to_string(),
+            PaletteConfig {
+                base: Some("light".to_string()),
+                accent: Some("#ff8800".to_string()),
+                failure: Some("208".to_string()),
+                ..Default::default()
+            },
+        );
+        let theme = Theme::from_project(&config).unwrap();
+        assert_eq!(theme.accent, Color::Rgb(255, 136, 0));
+        assert_eq!(theme.failure, Color::Indexed(208));
+        assert_eq!(theme.text, Theme::built_in("light").unwrap().text);
+
+        config.themes.get_mut("mine").unwrap().success = Some("greenish".to_string());
+        assert!(Theme::from_project(&config).is_err());
+    }
+}
@@ -27,6 +27,8 @@ pub fn load_workflows(dir_path: &Path) -> Vec<Workflow> {
                     selected: false,
                     status: WorkflowStatus::NotStarted,
                     execution_details: None,
+                    issue_count: None,
+                    job_selection: Default::default(),
                 });
             }
         }
@@ -43,6 +45,8 @@ pub fn load_workflows(dir_path: &Path) -> Vec<Workflow> {
                 selected: false,
                 status: WorkflowStatus::NotStarted,
                 execution_details: None,
+                issue_count: None,
+                job_selection: Default::default(),
             });
         }
     }
@@ -0,0 +1,298 @@
+// Step output with ANSI colors: parsing SGR escape codes into styled spans,
+// wrapping long lines and highlighting search matches
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+};
+use unicode_width::UnicodeWidthChar;
+
+/// Columns between tab stops
+const TAB_WIDTH: usize = 8;
+
+/// Turn a line of output into styled spans. `style` is the style in effect
+/// where the line starts, and is left as it is where the line ends, since
+/// colors carry over to the next line. Escape codes other than colors are
+/// dropped.
+pub fn parse_line(text: &str, style: &mut Style) -> Line<'static> {
+    let mut spans: Vec<Span<'static>> = Vec::new();
+    let mut current = String::new();
+    let mut column = 0;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' => match chars.next() {
+                Some('[') => {
+                    let mut params = String::new();
+                    let mut fin = None;
+                    for c in chars.by_ref() {
+                        if ('\x40'..='\x7e').contains(&c) {
+                            fin = Some(c);
+                            break;
+                        }
+                        params.push(c);
+                    }
+                    if fin == Some('m') {
+                        let next = apply_sgr(&params, *style);
+                        if next != *style && !current.is_empty() {
+                            spans.push(Span::styled(std::mem::take(&mut current), *style));
+                        }
+                        *style = next;
+                    }
+                }
+                // Titles and hyperlinks end with BEL or ESC \
+                Some(']') => {
+                    while let Some(c) = chars.next() {
+                        if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            },
+            // Progress bars redraw their line; keep what was drawn last
+            '\r' => {
+                spans.clear();
+                current.clear();
+                column = 0;
+            }
+            '\t' => {
+                let spaces = TAB_WIDTH - column % TAB_WIDTH;
+                current.push_str(&" ".repeat(spaces));
+                column += spaces;
+            }
+            c if c.is_control() => {}
+            c => {
+                current.push(c);
+                column += c.width().unwrap_or(0);
+            }
+        }
+    }
+    if !current.is_empty() {
+        spans.push(Span::styled(current, *style));
+    }
+    Line::from(spans)
+}
+
+/// The style after a `ESC [ ... m` sequence with these parameters
+fn apply_sgr(params: &str, style: Style) -> Style {
+    let codes: Vec<u16> = params
+        .split([';', ':'])
+        .map(|code| code.parse().unwrap_or(0))
+        .collect();
+    let mut style = style;
+    let mut codes = codes.into_iter();
+    while let Some(code) = codes.next() {
+        style = match code {
+            0 => Style::default(),
+            1 => style.add_modifier(Modifier::BOLD),
+            2 => style.add_modifier(Modifier::DIM),
+            3 => style.add_modifier(Modifier::ITALIC),
+            4 => style.add_modifier(Modifier::UNDERLINED),
+            5 => style.add_modifier(Modifier::SLOW_BLINK),
+            7 => style.add_modifier(Modifier::REVERSED),
+            9 => style.add_modifier(Modifier::CROSSED_OUT),
+            22 => style.remove_modifier(Modifier::BOLD | Modifier::DIM),
+            23 => style.remove_modifier(Modifier::ITALIC),
+            24 => style.remove_modifier(Modifier::UNDERLINED),
+            25 => style.remove_modifier(Modifier::SLOW_BLINK),
+            27 => style.remove_modifier(Modifier::REVERSED),
+            29 => style.remove_modifier(Modifier::CROSSED_OUT),
+            30..=37 => style.fg(basic_color(code - 30)),
+            90..=97 => style.fg(bright_color(code - 90)),
+            40..=47 => style.bg(basic_color(code - 40)),
+            100..=107 => style.bg(bright_color(code - 100)),
+            39 => Style { fg: None, ..style },
+            49 => Style { bg: None, ..style },
+            38 | 48 => {
+                let color = match codes.next() {
+                    Some(5) => codes.next().map(|n| Color::Indexed(n as u8)),
+                    Some(2) => match (codes.next(), codes.next(), codes.next()) {
+                        (Some(r), Some(g), Some(b)) => Some(Color::Rgb(r as u8, g as u8, b as u8)),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                match (code, color) {
+                    (38, Some(color)) => style.fg(color),
+                    (48, Some(color)) => style.bg(color),
+                    _ => style,
+                }
+            }
+            _ => style,
+        };
+    }
+    style
+}
+
+fn basic_color(n: u16) -> Color {
+    match n {
+        0 => Color::Black,
+        1 => Color::Red,
+        2 => Color::Green,
+        3 => Color::Yellow,
+        4 => Color::Blue,
+        5 => Color::Magenta,
+        6 => Color::Cyan,
+        _ => Color::Gray,
+    }
+}
+
+fn bright_color(n: u16) -> Color {
+    match n {
+        0 => Color::DarkGray,
+        1 => Color::LightRed,
+        2 => Color::LightGreen,
+        3 => Color::LightYellow,
+        4 => Color::LightBlue,
+        5 => Color::LightMagenta,
+        6 => Color::LightCyan,
+        _ => Color::White,
+    }
+}
+
+/// The text of a line without its styles
+pub fn plain_text(line: &Line) -> String {
+    line.spans
+        .iter()
+        .map(|span| span.content.as_ref())
+        .collect()
+}
+
+/// Mark where `query` appears in the line, ignoring ASCII case. Returns
+/// whether it appears at all.
+pub fn highlight(line: &mut Line<'static>, query: &str, mark: Style) -> bool {
+    if query.is_empty() {
+        return false;
+    }
+    let text = plain_text(line).to_ascii_lowercase();
+    let query = query.to_ascii_lowercase();
+    let ranges: Vec<(usize, usize)> = text
+        .match_indices(&query)
+        .map(|(start, found)| (start, start + found.len()))
+        .collect();
+    if ranges.is_empty() {
+        return false;
+    }
+
+    let mut spans = Vec::new();
+    let mut offset = 0;
+    for span in line.spans.drain(..) {
+        let content = span.content.as_ref();
+        let end = offset + content.len();
+        let mut at = offset;
+        for &(start, stop) in &ranges {
+            let (start, stop) = (start.max(offset), stop.min(end));
+            if start >= stop {
+                continue;
+            }
+            if start > at {
+                spans.push(Span::styled(
+                    content[at - offset..start - offset].to_string(),
+                    span.style,
+                ));
+            }
+            spans.push(Span::styled(
+                content[start - offset..stop - offset].to_string(),
+                span.style.patch(mark),
+            ));
+            at = stop;
+        }
+        if at < end {
+            spans.push(Span::styled(content[at - offset..].to_string(), span.style));
+        }
+        offset = end;
+    }
+    line.spans = spans;
+    true
+}
+
+/// Break a line into rows at most `width` columns wide
+pub fn wrap(line: Line<'static>, width: usize) -> Vec<Line<'static>> {
+    let width = width.max(1);
+    let mut rows = Vec::new();
+    let mut row: Vec<Span<'static>> = Vec::new();
+    let mut column = 0;
+    for span in line.spans {
+        let mut piece = String::new();
+        for c in span.content.chars() {
+            let c_width = c.width().unwrap_or(0);
+            if column + c_width > width && column > 0 {
+                if !piece.is_empty() {
+                    row.push(Span::styled(std::mem::take(&mut piece), span.style));
+                }
+                rows.push(Line::from(std::mem::take(&mut row)));
+                column = 0;
+            }
+            piece.push(c);
+            column += c_width;
+        }
+        if !piece.is_empty() {
+            row.push(Span::styled(piece, span.style));
+        }
+    }
+    rows.push(Line::from(row));
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_colors_and_wraps_and_highlights() {
+        let mut style = Style::default();
+        let line = parse_line(
+            "\x1b[1;32mok\x1b[0m \x1b]0;title\x07\x1b[38;5;208mwarn\x1b[K \x1b[48;2;1;2;3mx",
+            &mut style,
+        );
+        let spans: Vec<(&str, Style)> = line
+            .spans
+            .iter()
+            .map(|span| (span.content.as_ref(), span.style))
+            .collect();
+        assert_eq!(
+            spans,
+            [
+                (
+                    "ok",
+                    Style::default()
+                        .fg(Color::Green)
+                        .add_modifier(Modifier::BOLD)
+                ),
+                (" ", Style::default()),
+                ("warn ", Style::default().fg(Color::Indexed(208))),
+                (
+                    "x",
+                    Style::default()
+                        .fg(Color::Indexed(208))
+                        .bg(Color::Rgb(1, 2, 3))
+                ),
+            ]
+        );
+        // The color carries over to the next line until it's reset
+        assert_eq!(style.bg, Some(Color::Rgb(1, 2, 3)));
+        let next = parse_line("\x1b[49mdone\r\x1b[39mDone", &mut style);
+        assert_eq!(plain_text(&next), "Done");
+        assert_eq!(next.spans[0].style, Style::default());
+
+        let rows = wrap(line.clone(), 4);
+        let rows: Vec<String> = rows.iter().map(plain_text).collect();
+        assert_eq!(rows, ["ok w", "arn ", "x"]);
+
+        let mark = Style::default().bg(Color::Yellow);
+        let mut marked = line;
+        assert!(highlight(&mut marked, "K W", mark));
+        assert_eq!(
+            marked
+                .spans
+                .iter()
+                .map(|span| span.content.as_ref())
+                .collect::<Vec<_>>(),
+            ["o", "k", " ", "w", "arn ", "x"]
+        );
+        assert_eq!(marked.spans[1].style.bg, Some(Color::Yellow));
+        assert_eq!(marked.spans[3].style.fg, Some(Color::Indexed(208)));
+        assert!(!highlight(&mut marked, "missing", mark));
+    }
+}
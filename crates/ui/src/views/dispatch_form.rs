@@ -0,0 +1,94 @@
+// Dispatch inputs form rendering
+use crate::models::DispatchForm;
+use crate::theme::Theme;
+use github::inputs::InputKind;
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+use std::io;
+
+// Render the form for a workflow's dispatch inputs over the rest of the UI
+pub fn render_dispatch_form(
+    f: &mut Frame<CrosstermBackend<io::Stdout>>,
+    form: &DispatchForm,
+    workflow_name: &str,
+    theme: &Theme,
+) {
+    let size = f.size();
+    let mut lines = Vec::new();
+
+    for (i, (input, value)) in form.inputs.iter().zip(&form.values).enumerate() {
+        let selected = i == form.selected;
+        let name_style = if selected {
+            Style::default()
+                .fg(theme.accent)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(theme.text)
+        };
+        let shown = match &input.kind {
+            InputKind::Boolean if value == "true" => "[x]".to_string(),
+            InputKind::Boolean => "[ ]".to_string(),
+            InputKind::Choice(_) => format!("‹ {} ›", value),
+            _ if selected => format!("{}▏", value),
+            _ => value.clone(),
+        };
+        lines.push(Line::from(vec![
+            Span::raw(if selected { "› " } else { "  " }),
+            Span::styled(input.name.clone(), name_style),
+            Span::styled(
+                if input.needs_value() { " *" } else { "" },
+                Style::default().fg(theme.failure),
+            ),
+            Span::raw(": "),
+            Span::styled(shown, Style::default().fg(theme.info)),
+        ]));
+        if let Some(description) = &input.description {
+            lines.push(Line::from(Span::styled(
+                format!("    {}", description),
+                Style::default().fg(theme.muted),
+            )));
+        }
+    }
+
+    lines.push(Line::from(""));
+    if let Some(error) = &form.error {
+        lines.push(Line::from(Span::styled(
+            error.clone(),
+            Style::default().fg(theme.failure),
+        )));
+    }
+    lines.push(Line::from(Span::styled(
+        "↑/↓ field · ←/→/Space change · Enter trigger · Esc cancel",
+        Style::default().fg(theme.muted),
+    )));
+
+    let width = size.width.min(70);
+    let height = (lines.len() as u16 + 2).min(size.height);
+    let area = Rect {
+        x: (size.width - width) / 2,
+        y: (size.height - height) / 2,
+        width,
+        height,
+    };
+
+    let form_widget = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .title(Span::styled(
+                    format!(" Trigger {} ", workflow_name),
+                    Style::default().fg(theme.accent),
+                )),
+        )
+        .wrap(Wrap { trim: false });
+
+    f.render_widget(Clear, area);
+    f.render_widget(form_widget, area);
+}
@@ -1,10 +1,11 @@
 // Execution tab rendering
 use crate::app::App;
-use crate::models::WorkflowStatus;
+use crate::models::{SplitPane, WorkflowStatus};
+use crate::views::{job_detail, job_graph};
 use ratatui::{
     backend::CrosstermBackend,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, BorderType, Borders, Gauge, List, ListItem, Paragraph},
     Frame,
@@ -17,14 +18,42 @@ pub fn render_execution_tab(
     app: &mut App,
     area: Rect,
 ) {
+    let theme = app.theme;
     // Get the workflow index either from current_execution or selected workflow
     let current_workflow_idx = app
         .current_execution
         .or_else(|| app.workflow_list_state.selected())
         .filter(|&idx| idx < app.workflows.len());
 
+    if app.graph_view {
+        app.load_job_graph();
+    }
+
     if let Some(idx) = current_workflow_idx {
+        // A maximized pane of the split view takes the whole tab
+        let has_jobs = app.workflows[idx]
+            .execution_details
+            .as_ref()
+            .is_some_and(|execution| !execution.jobs.is_empty());
+        if let (Some(pane), true, false) = (app.split_view.maximized, has_jobs, app.graph_view) {
+            let area = Layout::default()
+                .constraints([Constraint::Min(0)].as_ref())
+                .margin(1)
+                .split(area)[0];
+            match pane {
+                SplitPane::Tree => render_job_tree(f, app, idx, area),
+                SplitPane::Output => render_selected_output(f, app, idx, area),
+            }
+            return;
+        }
+
         let workflow = &app.workflows[idx];
+        let mut split_area = None;
+        let job_graph = app
+            .job_graph
+            .as_ref()
+            .filter(|_| app.graph_view)
+            .map(|(_, graph)| graph);
 
         // Split the area into sections
         let chunks = Layout::default()
@@ -47,28 +76,28 @@ pub fn render_execution_tab(
             WorkflowStatus::Success => "Success",
             WorkflowStatus::Failed => "Failed",
             WorkflowStatus::Skipped => "Skipped",
+            WorkflowStatus::Cancelled => "Cancelled",
         };
 
         let status_style = match workflow.status {
-            WorkflowStatus::NotStarted => Style::default().fg(Color::Gray),
-            WorkflowStatus::Running => Style::default().fg(Color::Cyan),
-            WorkflowStatus::Success => Style::default().fg(Color::Green),
-            WorkflowStatus::Failed => Style::default().fg(Color::Red),
-            WorkflowStatus::Skipped => Style::default().fg(Color::Yellow),
+            WorkflowStatus::NotStarted => Style::default().fg(theme.skipped),
+            WorkflowStatus::Running => Style::default().fg(theme.running),
+            WorkflowStatus::Success => Style::default().fg(theme.success),
+            WorkflowStatus::Failed => Style::default().fg(theme.failure),
+            WorkflowStatus::Skipped => Style::default().fg(theme.skipped),
+            WorkflowStatus::Cancelled => Style::default().fg(theme.cancelled),
         };
 
         let mut workflow_info = vec![
             Line::from(vec![
-                Span::styled("Workflow: ", Style::default().fg(Color::Blue)),
+                Span::styled("Workflow: ", Style::default().fg(theme.label)),
                 Span::styled(
                     workflow.name.clone(),
-                    Style::default()
-                        .fg(Color::White)
-                        .add_modifier(Modifier::BOLD),
+                    Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
                 ),
             ]),
             Line::from(vec![
-                Span::styled("Status: ", Style::default().fg(Color::Blue)),
+                Span::styled("Status: ", Style::default().fg(theme.label)),
                 Span::styled(status_text, status_style),
             ]),
         ];
@@ -80,13 +109,19 @@ pub fn render_execution_tab(
 
             // Add progress bar
             let gauge_color = match workflow.status {
-                WorkflowStatus::Running => Color::Cyan,
-                WorkflowStatus::Success => Color::Green,
-                WorkflowStatus::Failed => Color::Red,
-                _ => Color::Gray,
+                WorkflowStatus::Running => theme.running,
+                WorkflowStatus::Success => theme.success,
+                WorkflowStatus::Failed => theme.failure,
+                _ => theme.skipped,
             };
 
             let progress_text = match workflow.status {
+                WorkflowStatus::Running if execution.step_progress.total_steps > 0 => format!(
+                    "{:.0}% ({}/{} steps)",
+                    progress * 100.0,
+                    execution.step_progress.completed_steps,
+                    execution.step_progress.total_steps
+                ),
                 WorkflowStatus::Running => format!("{:.0}%", progress * 100.0),
                 WorkflowStatus::Success => "Completed".to_string(),
                 WorkflowStatus::Failed => "Failed".to_string(),
@@ -99,12 +134,12 @@ pub fn render_execution_tab(
             // Add the gauge widget to the paragraph data
             workflow_info.push(Line::from(vec![Span::styled(
                 format!("Progress: {}", progress_text),
-                Style::default().fg(Color::Blue),
+                Style::default().fg(theme.label),
             )]));
 
             let gauge = Gauge::default()
                 .block(Block::default())
-                .gauge_style(Style::default().fg(gauge_color).bg(Color::Black))
+                .gauge_style(Style::default().fg(gauge_color).bg(theme.on_color()))
                 .percent((progress * 100.0) as u16);
 
             // Render gauge separately after the paragraph
@@ -114,7 +149,7 @@ pub fn render_execution_tab(
                     .border_type(BorderType::Rounded)
                     .title(Span::styled(
                         " Workflow Information ",
-                        Style::default().fg(Color::Yellow),
+                        Style::default().fg(theme.accent),
                     )),
             );
 
@@ -129,104 +164,54 @@ pub fn render_execution_tab(
             f.render_widget(gauge, gauge_area);
 
             // Jobs list section
-            if execution.jobs.is_empty() {
+            if let Some(graph) = job_graph {
+                job_graph::render_job_graph(f, graph, Some(execution), &theme, chunks[1]);
+            } else if execution.jobs.is_empty() {
                 let placeholder = Paragraph::new("No jobs have started execution yet...")
                     .block(
                         Block::default()
                             .borders(Borders::ALL)
                             .border_type(BorderType::Rounded)
-                            .title(Span::styled(" Jobs ", Style::default().fg(Color::Yellow))),
+                            .title(Span::styled(" Jobs ", Style::default().fg(theme.accent))),
                     )
                     .alignment(Alignment::Center);
                 f.render_widget(placeholder, chunks[1]);
             } else {
-                let job_items: Vec<ListItem> = execution
-                    .jobs
-                    .iter()
-                    .map(|job| {
-                        let status_symbol = match job.status {
-                            executor::JobStatus::Success => "✅",
-                            executor::JobStatus::Failure => "❌",
-                            executor::JobStatus::Skipped => "⏭",
-                        };
-
-                        let status_style = match job.status {
-                            executor::JobStatus::Success => Style::default().fg(Color::Green),
-                            executor::JobStatus::Failure => Style::default().fg(Color::Red),
-                            executor::JobStatus::Skipped => Style::default().fg(Color::Gray),
-                        };
-
-                        // Count completed and total steps
-                        let total_steps = job.steps.len();
-                        let completed_steps = job
-                            .steps
-                            .iter()
-                            .filter(|s| {
-                                s.status == executor::StepStatus::Success
-                                    || s.status == executor::StepStatus::Failure
-                            })
-                            .count();
-
-                        let steps_info = format!("[{}/{}]", completed_steps, total_steps);
-
-                        ListItem::new(Line::from(vec![
-                            Span::styled(status_symbol, status_style),
-                            Span::raw(" "),
-                            Span::styled(&job.name, Style::default().fg(Color::White)),
-                            Span::raw(" "),
-                            Span::styled(steps_info, Style::default().fg(Color::DarkGray)),
-                        ]))
-                    })
-                    .collect();
-
-                let jobs_list = List::new(job_items)
-                    .block(
-                        Block::default()
-                            .borders(Borders::ALL)
-                            .border_type(BorderType::Rounded)
-                            .title(Span::styled(" Jobs ", Style::default().fg(Color::Yellow))),
-                    )
-                    .highlight_style(
-                        Style::default()
-                            .bg(Color::DarkGray)
-                            .add_modifier(Modifier::BOLD),
-                    )
-                    .highlight_symbol("» ");
-
-                f.render_stateful_widget(jobs_list, chunks[1], &mut app.job_list_state);
+                // Drawn once the workflow is no longer borrowed
+                split_area = Some(chunks[1]);
             }
 
             // Execution info section
             let mut execution_info = Vec::new();
 
             execution_info.push(Line::from(vec![
-                Span::styled("Started: ", Style::default().fg(Color::Blue)),
+                Span::styled("Started: ", Style::default().fg(theme.label)),
                 Span::styled(
                     execution.start_time.format("%Y-%m-%d %H:%M:%S").to_string(),
-                    Style::default().fg(Color::White),
+                    Style::default().fg(theme.text),
                 ),
             ]));
 
             if let Some(end_time) = execution.end_time {
                 execution_info.push(Line::from(vec![
-                    Span::styled("Finished: ", Style::default().fg(Color::Blue)),
+                    Span::styled("Finished: ", Style::default().fg(theme.label)),
                     Span::styled(
                         end_time.format("%Y-%m-%d %H:%M:%S").to_string(),
-                        Style::default().fg(Color::White),
+                        Style::default().fg(theme.text),
                     ),
                 ]));
 
                 // Calculate duration
                 let duration = end_time.signed_duration_since(execution.start_time);
                 execution_info.push(Line::from(vec![
-                    Span::styled("Duration: ", Style::default().fg(Color::Blue)),
+                    Span::styled("Duration: ", Style::default().fg(theme.label)),
                     Span::styled(
                         format!(
                             "{}m {}s",
                             duration.num_minutes(),
                             duration.num_seconds() % 60
                         ),
-                        Style::default().fg(Color::White),
+                        Style::default().fg(theme.text),
                     ),
                 ]));
             } else {
@@ -234,14 +219,14 @@ pub fn render_execution_tab(
                 let current_time = chrono::Local::now();
                 let running_time = current_time.signed_duration_since(execution.start_time);
                 execution_info.push(Line::from(vec![
-                    Span::styled("Running for: ", Style::default().fg(Color::Blue)),
+                    Span::styled("Running for: ", Style::default().fg(theme.label)),
                     Span::styled(
                         format!(
                             "{}m {}s",
                             running_time.num_minutes(),
                             running_time.num_seconds() % 60
                         ),
-                        Style::default().fg(Color::White),
+                        Style::default().fg(theme.text),
                     ),
                 ]));
             }
@@ -249,9 +234,17 @@ pub fn render_execution_tab(
             // Add hint for Enter key to see details
             execution_info.push(Line::from(""));
             execution_info.push(Line::from(vec![
-                Span::styled("Press ", Style::default().fg(Color::DarkGray)),
-                Span::styled("Enter", Style::default().fg(Color::Yellow)),
-                Span::styled(" to view job details", Style::default().fg(Color::DarkGray)),
+                Span::styled("Press ", Style::default().fg(theme.muted)),
+                Span::styled("Enter", Style::default().fg(theme.accent)),
+                Span::styled(" to view job details, ", Style::default().fg(theme.muted)),
+                Span::styled("g", Style::default().fg(theme.accent)),
+                Span::styled(" for the job graph, ", Style::default().fg(theme.muted)),
+                Span::styled("<", Style::default().fg(theme.accent)),
+                Span::styled("/", Style::default().fg(theme.muted)),
+                Span::styled(">", Style::default().fg(theme.accent)),
+                Span::styled(" to resize, ", Style::default().fg(theme.muted)),
+                Span::styled("z", Style::default().fg(theme.accent)),
+                Span::styled(" to maximize", Style::default().fg(theme.muted)),
             ]));
 
             let info_widget = Paragraph::new(execution_info).block(
@@ -260,7 +253,7 @@ pub fn render_execution_tab(
                     .border_type(BorderType::Rounded)
                     .title(Span::styled(
                         " Execution Information ",
-                        Style::default().fg(Color::Yellow),
+                        Style::default().fg(theme.accent),
                     )),
             );
 
@@ -273,41 +266,45 @@ pub fn render_execution_tab(
                     .border_type(BorderType::Rounded)
                     .title(Span::styled(
                         " Workflow Information ",
-                        Style::default().fg(Color::Yellow),
+                        Style::default().fg(theme.accent),
                     )),
             );
 
             f.render_widget(workflow_info_widget, chunks[0]);
 
-            // No execution details to display
-            let placeholder = Paragraph::new(vec![
-                Line::from(""),
-                Line::from(vec![Span::styled(
-                    "No execution data available.",
-                    Style::default()
-                        .fg(Color::Yellow)
-                        .add_modifier(Modifier::BOLD),
-                )]),
-                Line::from(""),
-                Line::from("Press 'Enter' to run this workflow."),
-                Line::from(""),
-            ])
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .border_type(BorderType::Rounded)
-                    .title(Span::styled(" Jobs ", Style::default().fg(Color::Yellow))),
-            )
-            .alignment(Alignment::Center);
+            // The graph of a workflow that hasn't run, or no execution details
+            if let Some(graph) = job_graph {
+                job_graph::render_job_graph(f, graph, None, &theme, chunks[1]);
+            } else {
+                let placeholder = Paragraph::new(vec![
+                    Line::from(""),
+                    Line::from(vec![Span::styled(
+                        "No execution data available.",
+                        Style::default()
+                            .fg(theme.accent)
+                            .add_modifier(Modifier::BOLD),
+                    )]),
+                    Line::from(""),
+                    Line::from("Press 'Enter' to run this workflow."),
+                    Line::from(""),
+                ])
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_type(BorderType::Rounded)
+                        .title(Span::styled(" Jobs ", Style::default().fg(theme.accent))),
+                )
+                .alignment(Alignment::Center);
 
-            f.render_widget(placeholder, chunks[1]);
+                f.render_widget(placeholder, chunks[1]);
+            }
 
             // Execution information
             let info_widget = Paragraph::new(vec![
                 Line::from(""),
                 Line::from(vec![Span::styled(
                     "No execution has been started.",
-                    Style::default().fg(Color::Yellow),
+                    Style::default().fg(theme.accent),
                 )]),
                 Line::from(""),
                 Line::from("Press 'Enter' in the Workflows tab to run,"),
@@ -319,13 +316,28 @@ pub fn render_execution_tab(
                     .border_type(BorderType::Rounded)
                     .title(Span::styled(
                         " Execution Information ",
-                        Style::default().fg(Color::Yellow),
+                        Style::default().fg(theme.accent),
                     )),
             )
             .alignment(Alignment::Center);
 
             f.render_widget(info_widget, chunks[2]);
         }
+
+        if let Some(area) = split_area {
+            let panes = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(
+                    [
+                        Constraint::Percentage(app.split_view.tree_width),
+                        Constraint::Percentage(100 - app.split_view.tree_width),
+                    ]
+                    .as_ref(),
+                )
+                .split(area);
+            render_job_tree(f, app, idx, panes[0]);
+            render_selected_output(f, app, idx, panes[1]);
+        }
     } else {
         // No workflow execution to display
         let placeholder = Paragraph::new(vec![
@@ -333,7 +345,7 @@ pub fn render_execution_tab(
             Line::from(vec![Span::styled(
                 "No workflow execution data available.",
                 Style::default()
-                    .fg(Color::Yellow)
+                    .fg(theme.accent)
                     .add_modifier(Modifier::BOLD),
             )]),
             Line::from(""),
@@ -349,7 +361,7 @@ pub fn render_execution_tab(
                 .border_type(BorderType::Rounded)
                 .title(Span::styled(
                     " Execution ",
-                    Style::default().fg(Color::Yellow),
+                    Style::default().fg(theme.accent),
                 )),
         )
         .alignment(Alignment::Center);
@@ -357,3 +369,177 @@ pub fn render_execution_tab(
         f.render_widget(placeholder, area);
     }
 }
+
+// Each job of the run with its steps below it, the selected row highlighted
+fn render_job_tree(
+    f: &mut Frame<CrosstermBackend<io::Stdout>>,
+    app: &mut App,
+    workflow_idx: usize,
+    area: Rect,
+) {
+    let theme = app.theme;
+    let workflow = &app.workflows[workflow_idx];
+    let Some(execution) = &workflow.execution_details else {
+        return;
+    };
+    let selected_job = app.job_list_state.selected().unwrap_or(0);
+    let selected_step = app.step_table_state.selected().unwrap_or(0);
+
+    let mut items = Vec::new();
+    let mut selected_row = None;
+    for (job_idx, job) in execution.jobs.iter().enumerate() {
+        if job_idx == selected_job && app.split_view.on_job {
+            selected_row = Some(items.len());
+        }
+
+        // Jobs picked with 'o' for the next run
+        let picked = if workflow
+            .job_selection
+            .jobs
+            .iter()
+            .any(|name| name == job.job_name())
+        {
+            "● "
+        } else {
+            "  "
+        };
+
+        let (status_symbol, status_style) = match job.status {
+            _ if job.running => (theme.symbols.running, Style::default().fg(theme.running)),
+            executor::JobStatus::Success => {
+                (theme.symbols.success, Style::default().fg(theme.success))
+            }
+            executor::JobStatus::Failure => {
+                (theme.symbols.failure, Style::default().fg(theme.failure))
+            }
+            executor::JobStatus::Skipped => {
+                (theme.symbols.skipped, Style::default().fg(theme.skipped))
+            }
+        };
+
+        // Count completed and total steps
+        let total_steps = job.steps.len();
+        let completed_steps = job
+            .steps
+            .iter()
+            .filter(|s| {
+                !s.running
+                    && (s.status == executor::StepStatus::Success
+                        || s.status == executor::StepStatus::Failure)
+            })
+            .count();
+
+        let steps_info = format!("[{}/{}]", completed_steps, total_steps);
+
+        items.push(ListItem::new(Line::from(vec![
+            Span::styled(picked, Style::default().fg(theme.info)),
+            Span::styled(status_symbol, status_style),
+            Span::raw(" "),
+            Span::styled(&job.name, Style::default().fg(theme.text)),
+            Span::raw(" "),
+            Span::styled(steps_info, Style::default().fg(theme.muted)),
+        ])));
+
+        for (step_idx, step) in job.steps.iter().enumerate() {
+            if job_idx == selected_job && !app.split_view.on_job && step_idx == selected_step {
+                selected_row = Some(items.len());
+            }
+            let (status_symbol, status_style) = match step.status {
+                _ if step.running => (theme.symbols.running, Style::default().fg(theme.running)),
+                executor::StepStatus::Success => {
+                    (theme.symbols.success, Style::default().fg(theme.success))
+                }
+                executor::StepStatus::Failure => {
+                    (theme.symbols.failure, Style::default().fg(theme.failure))
+                }
+                executor::StepStatus::Skipped => {
+                    (theme.symbols.skipped, Style::default().fg(theme.skipped))
+                }
+            };
+            items.push(ListItem::new(Line::from(vec![
+                Span::raw("     "),
+                Span::styled(status_symbol, status_style),
+                Span::raw(" "),
+                Span::raw(step.name.clone()),
+                Span::raw(" "),
+                Span::styled(
+                    format!("{:.1}s", step.duration.as_secs_f64()),
+                    Style::default().fg(theme.muted),
+                ),
+            ])));
+        }
+    }
+
+    let tree = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .title(Span::styled(" Jobs ", Style::default().fg(theme.accent))),
+        )
+        .highlight_style(theme.highlight())
+        .highlight_symbol("» ");
+
+    app.tree_list_state.select(selected_row);
+    f.render_stateful_widget(tree, area, &mut app.tree_list_state);
+}
+
+// Output of the selected step, or of the step a selected job is at, so that
+// the output of a running job can be watched from its row
+fn render_selected_output(
+    f: &mut Frame<CrosstermBackend<io::Stdout>>,
+    app: &mut App,
+    workflow_idx: usize,
+    area: Rect,
+) {
+    let theme = app.theme;
+    let Some(job) = app.workflows[workflow_idx]
+        .execution_details
+        .as_ref()
+        .and_then(|execution| {
+            execution
+                .jobs
+                .get(app.job_list_state.selected().unwrap_or(0))
+        })
+    else {
+        return;
+    };
+
+    let step = if app.split_view.on_job {
+        job.steps
+            .iter()
+            .rev()
+            .find(|step| step.running)
+            .or_else(|| job.steps.last())
+    } else {
+        app.step_table_state
+            .selected()
+            .and_then(|idx| job.steps.get(idx))
+    };
+
+    match step {
+        Some(step) => job_detail::render_step_output(
+            f,
+            step,
+            &mut app.step_output,
+            app.expand_output_groups,
+            app.max_output_chars,
+            &theme,
+            area,
+        ),
+        None => {
+            let placeholder = Paragraph::new("Waiting for the job's first step...")
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_type(BorderType::Rounded)
+                        .title(Span::styled(
+                            " Step Output ",
+                            Style::default().fg(theme.accent),
+                        )),
+                )
+                .alignment(Alignment::Center);
+            f.render_widget(placeholder, area);
+        }
+    }
+}
@@ -1,8 +1,9 @@
 // Help overlay rendering
+use crate::theme::Theme;
 use ratatui::{
     backend::CrosstermBackend,
     layout::Rect,
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, BorderType, Borders, Paragraph, Wrap},
     Frame,
@@ -10,20 +11,18 @@ use ratatui::{
 use std::io;
 
 // Render the help tab
-pub fn render_help_tab(f: &mut Frame<CrosstermBackend<io::Stdout>>, area: Rect) {
+pub fn render_help_tab(f: &mut Frame<CrosstermBackend<io::Stdout>>, theme: &Theme, area: Rect) {
     let help_text = vec![
         Line::from(Span::styled(
             "Keyboard Controls",
-            Style::default()
-                .fg(Color::Cyan)
-                .add_modifier(Modifier::BOLD),
+            Style::default().fg(theme.info).add_modifier(Modifier::BOLD),
         )),
         Line::from(""),
         Line::from(vec![
             Span::styled(
                 "Tab",
                 Style::default()
-                    .fg(Color::Yellow)
+                    .fg(theme.accent)
                     .add_modifier(Modifier::BOLD),
             ),
             Span::raw(" - Switch between tabs"),
@@ -36,7 +35,7 @@ pub fn render_help_tab(f: &mut Frame<CrosstermBackend<io::Stdout>>, area: Rect)
             Block::default()
                 .borders(Borders::ALL)
                 .border_type(BorderType::Rounded)
-                .title(Span::styled(" Help ", Style::default().fg(Color::Yellow))),
+                .title(Span::styled(" Help ", Style::default().fg(theme.accent))),
         )
         .wrap(Wrap { trim: true });
 
@@ -44,7 +43,7 @@ pub fn render_help_tab(f: &mut Frame<CrosstermBackend<io::Stdout>>, area: Rect)
 }
 
 // Render a help overlay
-pub fn render_help_overlay(f: &mut Frame<CrosstermBackend<io::Stdout>>) {
+pub fn render_help_overlay(f: &mut Frame<CrosstermBackend<io::Stdout>>, theme: &Theme) {
     let size = f.size();
 
     // Create a slightly smaller centered modal
@@ -61,9 +60,9 @@ pub fn render_help_overlay(f: &mut Frame<CrosstermBackend<io::Stdout>>) {
     };
 
     // Create a clear background
-    let clear = Block::default().style(Style::default().bg(Color::Black));
+    let clear = Block::default().style(Style::default().bg(theme.on_color()));
     f.render_widget(clear, size);
 
     // Render the help content
-    render_help_tab(f, help_area);
+    render_help_tab(f, theme, help_area);
 }
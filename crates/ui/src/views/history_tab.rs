@@ -0,0 +1,183 @@
+// History tab rendering
+use crate::app::App;
+use crate::theme::Theme;
+use executor::history::{self, RunRecord};
+use executor::{JobStatus, StepStatus};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Cell, Paragraph, Row, Table, TableState, Wrap},
+    Frame,
+};
+use std::io;
+
+// Render the list of recorded runs next to the selected run's results, or
+// its differences from the run being compared with
+pub fn render_history_tab(f: &mut Frame<CrosstermBackend<io::Stdout>>, app: &mut App, area: Rect) {
+    let theme = app.theme;
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(45), Constraint::Percentage(55)].as_ref())
+        .split(area);
+
+    let header_cells = ["", "", "Started", "Workflow", "Runtime"]
+        .iter()
+        .map(|h| Cell::from(*h).style(Style::default().fg(theme.accent)));
+    let header = Row::new(header_cells)
+        .style(Style::default().add_modifier(Modifier::BOLD))
+        .height(1);
+
+    let rows = app.history.iter().map(|run| {
+        let compared = app.history_compare.as_ref() == Some(&run.id);
+        let (status_symbol, status_style) = if run.cancelled.is_some() {
+            (
+                theme.symbols.cancelled,
+                Style::default().fg(theme.cancelled),
+            )
+        } else if run.success {
+            (theme.symbols.success, Style::default().fg(theme.success))
+        } else {
+            (theme.symbols.failure, Style::default().fg(theme.failure))
+        };
+        let workflow = run
+            .workflow
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        Row::new(vec![
+            Cell::from(if compared { "◆" } else { " " }).style(Style::default().fg(theme.info)),
+            Cell::from(status_symbol).style(status_style),
+            Cell::from(started(run)),
+            Cell::from(workflow),
+            Cell::from(run.runtime.clone()).style(Style::default().fg(theme.muted)),
+        ])
+    });
+
+    let runs_table = Table::new(rows)
+        .header(header)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .title(Span::styled(" Runs ", Style::default().fg(theme.accent))),
+        )
+        .highlight_style(theme.highlight())
+        .highlight_symbol("» ")
+        .widths(&[
+            Constraint::Length(1),
+            Constraint::Length(4),
+            Constraint::Length(19),
+            Constraint::Percentage(60),
+            Constraint::Percentage(40),
+        ]);
+
+    let mut table_state = TableState::default();
+    table_state.select(app.history_list_state.selected());
+    f.render_stateful_widget(runs_table, chunks[0], &mut table_state);
+
+    let selected = app
+        .history_list_state
+        .selected()
+        .and_then(|i| app.history.get(i));
+    let compared = app
+        .history_compare
+        .as_ref()
+        .and_then(|id| app.history.iter().find(|run| &run.id == id));
+
+    let (title, lines) = match (selected, compared) {
+        (None, _) => (
+            " Run ".to_string(),
+            vec![Line::from(Span::styled(
+                "No runs recorded yet. Runs started here or with `wrkflw run` show up in this tab.",
+                Style::default().fg(theme.muted),
+            ))],
+        ),
+        (Some(run), Some(base)) if base.id != run.id => (
+            format!(" {} → {} ", base.id, run.id),
+            diff_lines(base, run, &theme),
+        ),
+        (Some(run), _) => (format!(" Run {} ", run.id), run_lines(run, &theme)),
+    };
+
+    let details = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .title(Span::styled(title, Style::default().fg(theme.accent))),
+        )
+        .wrap(Wrap { trim: false });
+    f.render_widget(details, chunks[1]);
+}
+
+fn started(run: &RunRecord) -> String {
+    chrono::DateTime::parse_from_rfc3339(&run.started_at)
+        .map(|time| {
+            time.with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+        })
+        .unwrap_or_default()
+}
+
+fn run_lines(run: &RunRecord, theme: &Theme) -> Vec<Line<'static>> {
+    let mut lines = vec![
+        Line::from(format!("Workflow: {}", run.workflow.display())),
+        Line::from(format!("Started:  {}", started(run))),
+        Line::from(format!("Runtime:  {}", run.runtime)),
+    ];
+    for job in &run.jobs {
+        let style = match job.status {
+            JobStatus::Success => Style::default().fg(theme.success),
+            JobStatus::Failure => Style::default().fg(theme.failure),
+            JobStatus::Skipped => Style::default().fg(theme.skipped),
+        };
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![
+            Span::styled(
+                job.name.clone(),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                format!(
+                    "  {} {:.1}s",
+                    history::job_status(&job.status),
+                    job.duration.as_secs_f64()
+                ),
+                style,
+            ),
+        ]));
+        for step in &job.steps {
+            let (symbol, style) = match step.status {
+                StepStatus::Success => (theme.symbols.success, Style::default().fg(theme.success)),
+                StepStatus::Failure => (theme.symbols.failure, Style::default().fg(theme.failure)),
+                StepStatus::Skipped => (theme.symbols.skipped, Style::default().fg(theme.skipped)),
+            };
+            let exit_code = match step.exit_code {
+                Some(code) if code != 0 => format!(" (exit code {})", code),
+                _ => String::new(),
+            };
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {} ", symbol), style),
+                Span::raw(format!("{}{}", step.name, exit_code)),
+            ]));
+        }
+    }
+    lines
+}
+
+fn diff_lines(base: &RunRecord, run: &RunRecord, theme: &Theme) -> Vec<Line<'static>> {
+    let changes = history::diff(base, run);
+    if changes.is_empty() {
+        return vec![Line::from(Span::styled(
+            "No differences in job and step results or job outputs",
+            Style::default().fg(theme.muted),
+        ))];
+    }
+    changes
+        .iter()
+        .map(|change| Line::from(change.to_string()))
+        .collect()
+}
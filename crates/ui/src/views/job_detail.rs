@@ -1,11 +1,16 @@
 // Job detail view rendering
 use crate::app::App;
+use crate::models::{StepExecution, StepOutputView};
+use crate::theme::Theme;
+use crate::views::ansi;
+use executor::actions::{Annotation, AnnotationLevel};
+use executor::summary::{self, SummaryStyle};
 use ratatui::{
     backend::CrosstermBackend,
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, BorderType, Borders, Paragraph, Row, Table},
+    widgets::{Block, BorderType, Borders, Paragraph, Row, Table, Wrap},
     Frame,
 };
 use std::io;
@@ -16,6 +21,7 @@ pub fn render_job_detail_view(
     app: &mut App,
     area: Rect,
 ) {
+    let theme = app.theme;
     // Get the workflow index either from current_execution or selected workflow
     let current_workflow_idx = app
         .current_execution
@@ -35,9 +41,9 @@ pub fn render_job_detail_view(
                         .direction(Direction::Vertical)
                         .constraints(
                             [
-                                Constraint::Length(3), // Job title
-                                Constraint::Min(5),    // Steps table
-                                Constraint::Length(8), // Step details
+                                Constraint::Length(3),      // Job title
+                                Constraint::Percentage(35), // Steps table
+                                Constraint::Min(8),         // Step output
                             ]
                             .as_ref(),
                         )
@@ -45,36 +51,40 @@ pub fn render_job_detail_view(
                         .split(area);
 
                     // Job title section
-                    let status_text = match job.status {
-                        executor::JobStatus::Success => "Success",
-                        executor::JobStatus::Failure => "Failed",
-                        executor::JobStatus::Skipped => "Skipped",
-                    };
-
-                    let status_style = match job.status {
-                        executor::JobStatus::Success => Style::default().fg(Color::Green),
-                        executor::JobStatus::Failure => Style::default().fg(Color::Red),
-                        executor::JobStatus::Skipped => Style::default().fg(Color::Yellow),
+                    let (status_text, status_style) = match job.status {
+                        _ if job.running => ("Running", Style::default().fg(theme.running)),
+                        executor::JobStatus::Success => {
+                            ("Success", Style::default().fg(theme.success))
+                        }
+                        executor::JobStatus::Failure => {
+                            ("Failed", Style::default().fg(theme.failure))
+                        }
+                        executor::JobStatus::Skipped => {
+                            ("Skipped", Style::default().fg(theme.skipped))
+                        }
                     };
 
                     let job_title = Paragraph::new(vec![
                         Line::from(vec![
-                            Span::styled("Job: ", Style::default().fg(Color::Blue)),
+                            Span::styled("Job: ", Style::default().fg(theme.label)),
                             Span::styled(
                                 job.name.clone(),
-                                Style::default()
-                                    .fg(Color::White)
-                                    .add_modifier(Modifier::BOLD),
+                                Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
                             ),
                             Span::raw(" ("),
                             Span::styled(status_text, status_style),
                             Span::raw(")"),
                         ]),
                         Line::from(vec![
-                            Span::styled("Steps: ", Style::default().fg(Color::Blue)),
+                            Span::styled("Steps: ", Style::default().fg(theme.label)),
                             Span::styled(
                                 format!("{}", job.steps.len()),
-                                Style::default().fg(Color::White),
+                                Style::default().fg(theme.text),
+                            ),
+                            Span::styled("   Duration: ", Style::default().fg(theme.label)),
+                            Span::styled(
+                                format!("{:.1}s", job.duration.as_secs_f64()),
+                                Style::default().fg(theme.text),
                             ),
                         ]),
                     ])
@@ -84,15 +94,15 @@ pub fn render_job_detail_view(
                             .border_type(BorderType::Rounded)
                             .title(Span::styled(
                                 " Job Details ",
-                                Style::default().fg(Color::Yellow),
+                                Style::default().fg(theme.accent),
                             )),
                     );
 
                     f.render_widget(job_title, chunks[0]);
 
                     // Steps section
-                    let header_cells = ["Status", "Step Name"].iter().map(|h| {
-                        ratatui::widgets::Cell::from(*h).style(Style::default().fg(Color::Yellow))
+                    let header_cells = ["Status", "Step Name", "Duration"].iter().map(|h| {
+                        ratatui::widgets::Cell::from(*h).style(Style::default().fg(theme.accent))
                     });
 
                     let header = Row::new(header_cells)
@@ -100,21 +110,29 @@ pub fn render_job_detail_view(
                         .height(1);
 
                     let rows = job.steps.iter().map(|step| {
-                        let status_symbol = match step.status {
-                            executor::StepStatus::Success => "✅",
-                            executor::StepStatus::Failure => "❌",
-                            executor::StepStatus::Skipped => "⏭",
-                        };
-
-                        let status_style = match step.status {
-                            executor::StepStatus::Success => Style::default().fg(Color::Green),
-                            executor::StepStatus::Failure => Style::default().fg(Color::Red),
-                            executor::StepStatus::Skipped => Style::default().fg(Color::Gray),
+                        let (status_symbol, status_style) = match step.status {
+                            _ if step.running => {
+                                (theme.symbols.running, Style::default().fg(theme.running))
+                            }
+                            executor::StepStatus::Success => {
+                                (theme.symbols.success, Style::default().fg(theme.success))
+                            }
+                            executor::StepStatus::Failure => {
+                                (theme.symbols.failure, Style::default().fg(theme.failure))
+                            }
+                            executor::StepStatus::Skipped => {
+                                (theme.symbols.skipped, Style::default().fg(theme.skipped))
+                            }
                         };
 
                         Row::new(vec![
                             ratatui::widgets::Cell::from(status_symbol).style(status_style),
                             ratatui::widgets::Cell::from(step.name.clone()),
+                            ratatui::widgets::Cell::from(format!(
+                                "{:.1}s",
+                                step.duration.as_secs_f64()
+                            ))
+                            .style(Style::default().fg(theme.muted)),
                         ])
                     });
 
@@ -124,74 +142,56 @@ pub fn render_job_detail_view(
                             Block::default()
                                 .borders(Borders::ALL)
                                 .border_type(BorderType::Rounded)
-                                .title(Span::styled(" Steps ", Style::default().fg(Color::Yellow))),
-                        )
-                        .highlight_style(
-                            Style::default()
-                                .bg(Color::DarkGray)
-                                .add_modifier(Modifier::BOLD),
+                                .title(Span::styled(" Steps ", Style::default().fg(theme.accent))),
                         )
+                        .highlight_style(theme.highlight())
                         .highlight_symbol("» ")
                         .widths(&[
                             Constraint::Length(8),      // Status icon column
-                            Constraint::Percentage(92), // Name column
+                            Constraint::Percentage(80), // Name column
+                            Constraint::Length(10),     // Duration column
                         ]);
 
+                    // Jobs that wrote a step summary show it next to their steps
+                    let steps_area = if job.summary.trim().is_empty() {
+                        chunks[1]
+                    } else {
+                        let columns = Layout::default()
+                            .direction(Direction::Horizontal)
+                            .constraints(
+                                [Constraint::Percentage(55), Constraint::Percentage(45)].as_ref(),
+                            )
+                            .split(chunks[1]);
+                        render_summary(f, &job.summary, &theme, columns[1]);
+                        columns[0]
+                    };
+
+                    // While following a running job, show each step as it starts
+                    if app.step_output.follow
+                        && job.running
+                        && job.steps.len() > app.step_output.steps_seen
+                    {
+                        let newest = job.steps.len() - 1;
+                        app.step_list_state.select(Some(newest));
+                        app.step_table_state.select(Some(newest));
+                    }
+                    app.step_output.steps_seen = job.steps.len();
+
                     // We need to use the table state from the app
-                    f.render_stateful_widget(steps_table, chunks[1], &mut app.step_table_state);
+                    f.render_stateful_widget(steps_table, steps_area, &mut app.step_table_state);
 
                     // Step detail section
                     if let Some(step_idx) = app.step_table_state.selected() {
                         if step_idx < job.steps.len() {
-                            let step = &job.steps[step_idx];
-
-                            // Show step output with proper styling
-                            let status_text = match step.status {
-                                executor::StepStatus::Success => "Success",
-                                executor::StepStatus::Failure => "Failed",
-                                executor::StepStatus::Skipped => "Skipped",
-                            };
-
-                            let status_style = match step.status {
-                                executor::StepStatus::Success => Style::default().fg(Color::Green),
-                                executor::StepStatus::Failure => Style::default().fg(Color::Red),
-                                executor::StepStatus::Skipped => Style::default().fg(Color::Yellow),
-                            };
-
-                            let mut output_text = step.output.clone();
-                            // Truncate if too long
-                            if output_text.len() > 1000 {
-                                output_text = format!("{}... [truncated]", &output_text[..1000]);
-                            }
-
-                            let step_detail = Paragraph::new(vec![
-                                Line::from(vec![
-                                    Span::styled("Step: ", Style::default().fg(Color::Blue)),
-                                    Span::styled(
-                                        step.name.clone(),
-                                        Style::default()
-                                            .fg(Color::White)
-                                            .add_modifier(Modifier::BOLD),
-                                    ),
-                                    Span::raw(" ("),
-                                    Span::styled(status_text, status_style),
-                                    Span::raw(")"),
-                                ]),
-                                Line::from(""),
-                                Line::from(output_text),
-                            ])
-                            .block(
-                                Block::default()
-                                    .borders(Borders::ALL)
-                                    .border_type(BorderType::Rounded)
-                                    .title(Span::styled(
-                                        " Step Output ",
-                                        Style::default().fg(Color::Yellow),
-                                    )),
-                            )
-                            .wrap(ratatui::widgets::Wrap { trim: false });
-
-                            f.render_widget(step_detail, chunks[2]);
+                            render_step_output(
+                                f,
+                                &job.steps[step_idx],
+                                &mut app.step_output,
+                                app.expand_output_groups,
+                                app.max_output_chars,
+                                &theme,
+                                chunks[2],
+                            );
                         }
                     }
                 }
@@ -199,3 +199,228 @@ pub fn render_job_detail_view(
         }
     }
 }
+
+// The output of a step with its colors, wrapped to the pane, with search
+// matches marked and scrolled to its end while following it
+pub fn render_step_output(
+    f: &mut Frame<CrosstermBackend<io::Stdout>>,
+    step: &StepExecution,
+    view: &mut StepOutputView,
+    expand_groups: bool,
+    max_output_chars: usize,
+    theme: &Theme,
+    area: Rect,
+) {
+    let (status_text, status_style) = match step.status {
+        _ if step.running => ("Running", Style::default().fg(theme.running)),
+        executor::StepStatus::Success => ("Success", Style::default().fg(theme.success)),
+        executor::StepStatus::Failure => ("Failed", Style::default().fg(theme.failure)),
+        executor::StepStatus::Skipped => ("Skipped", Style::default().fg(theme.skipped)),
+    };
+
+    let mut output_text = step.output.clone();
+    // Truncate if too long; the full output stays in its file
+    if let Some((cut, _)) = output_text.char_indices().nth(max_output_chars) {
+        output_text.truncate(cut);
+        match &step.output_file {
+            Some(file) => {
+                output_text.push_str(&format!("... [truncated, full output: {}]", file.display()))
+            }
+            None => output_text.push_str("... [truncated]"),
+        }
+    }
+
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("Step: ", Style::default().fg(theme.label)),
+            Span::styled(
+                step.name.clone(),
+                Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(" ("),
+            Span::styled(status_text, status_style),
+            Span::raw(")"),
+        ]),
+        Line::from(step_timing(step, theme)),
+    ];
+    lines.extend(
+        step.annotations
+            .iter()
+            .map(|annotation| annotation_line(annotation, theme)),
+    );
+
+    // Wrap to the pane ourselves, so that scrolling knows how many rows
+    // the output takes
+    let width = area.width.saturating_sub(2) as usize;
+    let mut rows: Vec<Line<'static>> = lines
+        .into_iter()
+        .flat_map(|line| ansi::wrap(line, width))
+        .collect();
+    let mark = Style::default().fg(theme.on_color()).bg(theme.warning);
+    let mut match_rows = Vec::new();
+    for mut line in output_lines(&output_text, expand_groups, theme) {
+        if ansi::highlight(&mut line, &view.search_query, mark) {
+            match_rows.push(rows.len());
+        }
+        rows.extend(ansi::wrap(line, width));
+    }
+
+    view.rows = area.height.saturating_sub(2) as usize;
+    view.match_count = match_rows.len();
+    if view.match_idx >= match_rows.len() {
+        view.match_idx = 0;
+    }
+    if std::mem::take(&mut view.jump) {
+        if let Some(&row) = match_rows.get(view.match_idx) {
+            view.follow = false;
+            view.scroll = row;
+        }
+    }
+    let last_page = rows.len().saturating_sub(view.rows);
+    if view.follow {
+        view.scroll = last_page;
+    }
+    view.scroll = view.scroll.min(last_page);
+
+    let mut title = vec![Span::styled(
+        " Step Output ",
+        Style::default().fg(theme.accent),
+    )];
+    if view.follow {
+        title.push(Span::styled(
+            "[following] ",
+            Style::default().fg(theme.info),
+        ));
+    }
+    if view.search_active {
+        title.push(Span::styled(
+            format!("/{}_ ", view.search_query),
+            Style::default().fg(theme.text),
+        ));
+    } else if !view.search_query.is_empty() {
+        let found = match view.match_count {
+            0 => "no matches".to_string(),
+            count => format!("{}/{}", view.match_idx + 1, count),
+        };
+        title.push(Span::styled(
+            format!("/{} ({}) ", view.search_query, found),
+            Style::default().fg(theme.text),
+        ));
+    }
+
+    let shown: Vec<Line<'static>> = rows.into_iter().skip(view.scroll).collect();
+    let step_detail = Paragraph::new(shown).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .title(Line::from(title)),
+    );
+    f.render_widget(step_detail, area);
+}
+
+// Duration of a step, with the time it spent on images and containers
+fn step_timing(step: &StepExecution, theme: &Theme) -> Span<'static> {
+    let mut timing = format!("Took {:.1}s", step.duration.as_secs_f64());
+    if !step.overhead.total().is_zero() {
+        timing.push_str(&format!(
+            ", {:.1}s of it pulling images and {:.1}s starting containers",
+            step.overhead.image_pull.as_secs_f64(),
+            step.overhead.container_startup.as_secs_f64()
+        ));
+    }
+    Span::styled(timing, Style::default().fg(theme.muted))
+}
+
+// An error, warning or notice the step printed
+fn annotation_line(annotation: &Annotation, theme: &Theme) -> Line<'static> {
+    let (symbol, color) = match annotation.level {
+        AnnotationLevel::Error => (theme.symbols.failure, theme.failure),
+        AnnotationLevel::Warning => (theme.symbols.warning, theme.warning),
+        AnnotationLevel::Notice => (theme.symbols.notice, theme.info),
+    };
+    Line::from(Span::styled(
+        format!("{} {}", symbol, annotation),
+        Style::default().fg(color),
+    ))
+}
+
+// Step output line by line, with each `::group::` section folded into its
+// title unless groups are expanded
+fn output_lines(output: &str, expand_groups: bool, theme: &Theme) -> Vec<Line<'static>> {
+    let group_style = Style::default().fg(theme.info).add_modifier(Modifier::BOLD);
+    let mut lines = Vec::new();
+    // Title, where its line is and how many lines it holds
+    let mut group: Option<(String, usize, usize)> = None;
+    let close = |lines: &mut Vec<Line<'static>>, group: Option<(String, usize, usize)>| {
+        if let Some((title, at, count)) = group {
+            lines[at] = if expand_groups {
+                Line::from(Span::styled(format!("▾ {}", title), group_style))
+            } else {
+                Line::from(Span::styled(
+                    format!("▸ {} ({} lines)", title, count),
+                    group_style,
+                ))
+            };
+        }
+    };
+
+    // Colors carry over from line to line
+    let mut style = Style::default();
+    for raw in output.lines() {
+        let line = ansi::parse_line(raw, &mut style);
+        let text = ansi::plain_text(&line);
+        if let Some(title) = text.trim_start().strip_prefix("::group::") {
+            close(&mut lines, group.take());
+            group = Some((title.to_string(), lines.len(), 0));
+            lines.push(Line::from(""));
+        } else if text.trim() == "::endgroup::" {
+            close(&mut lines, group.take());
+        } else if let Some((_, _, count)) = &mut group {
+            *count += 1;
+            if expand_groups {
+                let mut line = line;
+                line.spans.insert(0, Span::raw("  "));
+                lines.push(line);
+            }
+        } else {
+            lines.push(line);
+        }
+    }
+    close(&mut lines, group);
+    lines
+}
+
+// The Markdown the job's steps wrote to $GITHUB_STEP_SUMMARY
+fn render_summary(
+    f: &mut Frame<CrosstermBackend<io::Stdout>>,
+    markdown: &str,
+    theme: &Theme,
+    area: Rect,
+) {
+    let lines: Vec<Line> = summary::render(markdown)
+        .into_iter()
+        .map(|line| {
+            let style = match line.style {
+                SummaryStyle::Heading => {
+                    Style::default().fg(theme.info).add_modifier(Modifier::BOLD)
+                }
+                SummaryStyle::Quote => Style::default()
+                    .fg(theme.muted)
+                    .add_modifier(Modifier::ITALIC),
+                SummaryStyle::Code => Style::default().fg(theme.success),
+                SummaryStyle::Text => Style::default(),
+            };
+            Line::from(Span::styled(line.text, style))
+        })
+        .collect();
+
+    let summary = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .title(Span::styled(" Summary ", Style::default().fg(theme.accent))),
+        )
+        .wrap(Wrap { trim: false });
+    f.render_widget(summary, area);
+}
@@ -0,0 +1,277 @@
+// Job dependency graph in the execution tab, colored by job status
+use crate::models::WorkflowExecution;
+use crate::theme::Theme;
+use executor::graph::{GraphJob, JobGraph};
+use executor::JobStatus;
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Paragraph, Wrap},
+    Frame,
+};
+use std::collections::HashMap;
+use std::io;
+
+/// Where a job on the graph is in the run
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum GraphJobState {
+    Pending,
+    Running,
+    Success,
+    Failure,
+    Skipped,
+}
+
+impl GraphJobState {
+    fn symbol(self) -> &'static str {
+        match self {
+            GraphJobState::Pending => "○",
+            GraphJobState::Skipped => "⊘",
+            _ => "●",
+        }
+    }
+
+    fn color(self, theme: &Theme) -> Color {
+        match self {
+            GraphJobState::Pending => theme.skipped,
+            GraphJobState::Running => theme.running,
+            GraphJobState::Success => theme.success,
+            GraphJobState::Failure => theme.failure,
+            GraphJobState::Skipped => theme.muted,
+        }
+    }
+}
+
+/// The graph job a job event is about: the job itself, or the job a matrix
+/// combination or `parallel:` instance like "test (linux)" or "test 1/3"
+/// belongs to
+fn event_job<'a>(graph: &'a JobGraph, event_name: &str) -> Option<&'a GraphJob> {
+    graph
+        .jobs
+        .iter()
+        .filter(|job| {
+            event_name == job.name
+                || event_name
+                    .strip_prefix(job.name.as_str())
+                    .is_some_and(|rest| rest.starts_with(' ') || rest.starts_with(':'))
+        })
+        .max_by_key(|job| job.name.len())
+}
+
+/// States of the graph's jobs by name. A matrix job runs until all of its
+/// instances finished and failed if any of them did.
+fn job_states(
+    graph: &JobGraph,
+    execution: Option<&WorkflowExecution>,
+) -> HashMap<String, GraphJobState> {
+    let mut statuses: HashMap<&str, Vec<Option<JobStatus>>> = HashMap::new();
+    if let Some(execution) = execution {
+        // The results of a finished run have the last word over its events
+        let mut events = execution.job_events.clone();
+        for job in execution.jobs.iter().filter(|job| !job.running) {
+            events.insert(job.name.clone(), Some(job.status.clone()));
+        }
+        for (name, status) in events {
+            if let Some(job) = event_job(graph, &name) {
+                statuses.entry(job.name.as_str()).or_default().push(status);
+            }
+        }
+    }
+    let finished_run = execution.is_some_and(|execution| execution.end_time.is_some());
+
+    graph
+        .jobs
+        .iter()
+        .map(|job| {
+            let seen = statuses
+                .get(job.name.as_str())
+                .map_or(&[][..], Vec::as_slice);
+            let state = if seen.is_empty() {
+                match job.skip_reason {
+                    Some(_) => GraphJobState::Skipped,
+                    None => GraphJobState::Pending,
+                }
+            } else if seen.contains(&Some(JobStatus::Failure)) {
+                GraphJobState::Failure
+            } else if seen.contains(&None) || (!finished_run && seen.len() < job.instances) {
+                GraphJobState::Running
+            } else if seen.iter().all(|s| *s == Some(JobStatus::Skipped)) {
+                GraphJobState::Skipped
+            } else {
+                GraphJobState::Success
+            };
+            (job.name.clone(), state)
+        })
+        .collect()
+}
+
+// Render the graph as one column per level, jobs flowing left to right
+pub fn render_job_graph(
+    f: &mut Frame<CrosstermBackend<io::Stdout>>,
+    graph: &JobGraph,
+    execution: Option<&WorkflowExecution>,
+    theme: &Theme,
+    area: Rect,
+) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .title(Span::styled(
+            " Job Graph ",
+            Style::default().fg(theme.accent),
+        ));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let levels = graph.levels();
+    if levels.is_empty() || inner.height < 2 {
+        return;
+    }
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)].as_ref())
+        .split(inner);
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(
+            levels
+                .iter()
+                .map(|_| Constraint::Ratio(1, levels.len() as u32))
+                .collect::<Vec<_>>(),
+        )
+        .split(rows[0]);
+
+    let states = job_states(graph, execution);
+    for (idx, (level, column)) in levels.iter().zip(columns.iter()).enumerate() {
+        let arrow = if idx == 0 { "" } else { "→ " };
+        let mut lines = vec![Line::from(Span::styled(
+            format!("{}Level {}", arrow, idx + 1),
+            Style::default()
+                .fg(theme.label)
+                .add_modifier(Modifier::BOLD),
+        ))];
+        for job in level {
+            let state = states
+                .get(&job.name)
+                .copied()
+                .unwrap_or(GraphJobState::Pending);
+            let mut name_style = Style::default().fg(state.color(theme));
+            if state == GraphJobState::Running {
+                name_style = name_style.add_modifier(Modifier::BOLD);
+            }
+            if job.skip_reason.is_some() && state == GraphJobState::Skipped {
+                name_style = name_style.add_modifier(Modifier::CROSSED_OUT);
+            }
+
+            let mut spans = vec![
+                Span::styled(format!("{} ", state.symbol()), name_style),
+                Span::styled(job.name.clone(), name_style),
+            ];
+            if job.instances > 1 {
+                spans.push(Span::styled(
+                    format!(" ×{}", job.instances),
+                    Style::default().fg(theme.info),
+                ));
+            }
+            if let Some(stage) = &job.stage {
+                spans.push(Span::styled(
+                    format!(" [{}]", stage),
+                    Style::default().fg(theme.muted),
+                ));
+            }
+            lines.push(Line::from(spans));
+            if !job.needs.is_empty() {
+                lines.push(Line::from(Span::styled(
+                    format!("  ← {}", job.needs.join(", ")),
+                    Style::default().fg(theme.muted),
+                )));
+            }
+        }
+        f.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), *column);
+    }
+
+    let legend = [
+        GraphJobState::Pending,
+        GraphJobState::Running,
+        GraphJobState::Success,
+        GraphJobState::Failure,
+        GraphJobState::Skipped,
+    ]
+    .iter()
+    .flat_map(|state| {
+        [
+            Span::styled(
+                format!("{} ", state.symbol()),
+                Style::default().fg(state.color(theme)),
+            ),
+            Span::styled(
+                format!("{:?}   ", state).to_lowercase(),
+                Style::default().fg(theme.muted),
+            ),
+        ]
+    })
+    .collect::<Vec<_>>();
+    f.render_widget(Paragraph::new(Line::from(legend)), rows[1]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Local;
+
+    fn job(name: &str, instances: usize, skip_reason: Option<&str>) -> GraphJob {
+        GraphJob {
+            name: name.to_string(),
+            stage: None,
+            instances,
+            needs: Vec::new(),
+            skip_reason: skip_reason.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn colors_jobs_from_their_events() {
+        let graph = JobGraph {
+            name: "CI".to_string(),
+            jobs: vec![
+                job("build", 2, None),
+                job("build-docs", 1, None),
+                job("test", 1, None),
+                job("deploy", 1, Some("'if' condition is false")),
+            ],
+        };
+        let mut execution = WorkflowExecution {
+            jobs: Vec::new(),
+            start_time: Local::now(),
+            end_time: None,
+            logs: Vec::new(),
+            progress: 0.0,
+            step_progress: executor::Progress::default(),
+            job_events: HashMap::from([
+                ("build (linux)".to_string(), Some(JobStatus::Success)),
+                ("build-docs".to_string(), None),
+            ]),
+        };
+
+        let states = job_states(&graph, Some(&execution));
+        assert_eq!(
+            states["build"],
+            GraphJobState::Running,
+            "one combination left"
+        );
+        assert_eq!(states["build-docs"], GraphJobState::Running);
+        assert_eq!(states["test"], GraphJobState::Pending);
+        assert_eq!(states["deploy"], GraphJobState::Skipped);
+
+        execution.job_events.extend([
+            ("build (macos)".to_string(), Some(JobStatus::Failure)),
+            ("build-docs".to_string(), Some(JobStatus::Success)),
+        ]);
+        let states = job_states(&graph, Some(&execution));
+        assert_eq!(states["build"], GraphJobState::Failure);
+        assert_eq!(states["build-docs"], GraphJobState::Success);
+    }
+}
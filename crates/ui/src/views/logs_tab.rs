@@ -3,7 +3,7 @@ use crate::app::App;
 use ratatui::{
     backend::CrosstermBackend,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, BorderType, Borders, Cell, Paragraph, Row, Table, TableState},
     Frame,
@@ -12,6 +12,7 @@ use std::io;
 
 // Render the logs tab
 pub fn render_logs_tab(f: &mut Frame<CrosstermBackend<io::Stdout>>, app: &App, area: Rect) {
+    let theme = app.theme;
     // Split the area into header, search bar (optionally shown), and log content
     let chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -44,30 +45,30 @@ pub fn render_logs_tab(f: &mut Frame<CrosstermBackend<io::Stdout>>, app: &App, a
         Line::from(vec![Span::styled(
             "Execution and System Logs",
             Style::default()
-                .fg(Color::Yellow)
+                .fg(theme.accent)
                 .add_modifier(Modifier::BOLD),
         )]),
         Line::from(vec![
-            Span::styled("↑/↓", Style::default().fg(Color::Cyan)),
+            Span::styled("↑/↓", Style::default().fg(theme.info)),
             Span::raw(" or "),
-            Span::styled("j/k", Style::default().fg(Color::Cyan)),
+            Span::styled("j/k", Style::default().fg(theme.info)),
             Span::raw(": Navigate logs/matches   "),
-            Span::styled("s", Style::default().fg(Color::Cyan)),
+            Span::styled("s", Style::default().fg(theme.info)),
             Span::raw(": Search   "),
-            Span::styled("f", Style::default().fg(Color::Cyan)),
+            Span::styled("f", Style::default().fg(theme.info)),
             Span::raw(": Filter   "),
-            Span::styled("Tab", Style::default().fg(Color::Cyan)),
+            Span::styled("Tab", Style::default().fg(theme.info)),
             Span::raw(": Switch tabs"),
         ]),
     ];
 
     if show_search_bar {
         header_text.push(Line::from(vec![
-            Span::styled("Enter", Style::default().fg(Color::Cyan)),
+            Span::styled("Enter", Style::default().fg(theme.info)),
             Span::raw(": Apply search   "),
-            Span::styled("Esc", Style::default().fg(Color::Cyan)),
+            Span::styled("Esc", Style::default().fg(theme.info)),
             Span::raw(": Clear search   "),
-            Span::styled("c", Style::default().fg(Color::Cyan)),
+            Span::styled("c", Style::default().fg(theme.info)),
             Span::raw(": Clear all filters"),
         ]));
     }
@@ -113,16 +114,16 @@ pub fn render_logs_tab(f: &mut Frame<CrosstermBackend<io::Stdout>>, app: &App, a
             Span::styled(
                 filter_text,
                 Style::default().fg(match &app.log_filter_level {
-                    Some(crate::models::LogFilterLevel::Error) => Color::Red,
-                    Some(crate::models::LogFilterLevel::Warning) => Color::Yellow,
-                    Some(crate::models::LogFilterLevel::Info) => Color::Cyan,
-                    Some(crate::models::LogFilterLevel::Success) => Color::Green,
-                    Some(crate::models::LogFilterLevel::Trigger) => Color::Magenta,
-                    Some(crate::models::LogFilterLevel::All) | None => Color::Gray,
+                    Some(crate::models::LogFilterLevel::Error) => theme.failure,
+                    Some(crate::models::LogFilterLevel::Warning) => theme.warning,
+                    Some(crate::models::LogFilterLevel::Info) => theme.info,
+                    Some(crate::models::LogFilterLevel::Success) => theme.success,
+                    Some(crate::models::LogFilterLevel::Trigger) => theme.label,
+                    Some(crate::models::LogFilterLevel::All) | None => theme.text,
                 }),
             ),
             Span::raw("   "),
-            Span::styled(match_info, Style::default().fg(Color::Magenta)),
+            Span::styled(match_info, Style::default().fg(theme.info)),
         ]);
 
         let search_block = Paragraph::new(search_info)
@@ -132,7 +133,7 @@ pub fn render_logs_tab(f: &mut Frame<CrosstermBackend<io::Stdout>>, app: &App, a
                     .border_type(BorderType::Rounded)
                     .title(Span::styled(
                         " Search & Filter ",
-                        Style::default().fg(Color::Yellow),
+                        Style::default().fg(theme.accent),
                     )),
             )
             .alignment(Alignment::Left);
@@ -151,7 +152,7 @@ pub fn render_logs_tab(f: &mut Frame<CrosstermBackend<io::Stdout>>, app: &App, a
     }
 
     // Process system logs
-    for log in logging::get_logs() {
+    for log in &app.system_logs {
         all_logs.push(log.clone());
     }
 
@@ -183,7 +184,7 @@ pub fn render_logs_tab(f: &mut Frame<CrosstermBackend<io::Stdout>>, app: &App, a
     // Create a table for logs for better organization
     let header_cells = ["Time", "Type", "Message"]
         .iter()
-        .map(|h| Cell::from(*h).style(Style::default().fg(Color::Yellow)));
+        .map(|h| Cell::from(*h).style(Style::default().fg(theme.accent)));
 
     let header = Row::new(header_cells)
         .style(Style::default().add_modifier(Modifier::BOLD))
@@ -207,14 +208,18 @@ pub fn render_logs_tab(f: &mut Frame<CrosstermBackend<io::Stdout>>, app: &App, a
         let (log_type, log_style, _) =
             if log_line.contains("Error") || log_line.contains("error") || log_line.contains("❌")
             {
-                ("ERROR", Style::default().fg(Color::Red), log_line.as_str())
+                (
+                    "ERROR",
+                    Style::default().fg(theme.failure),
+                    log_line.as_str(),
+                )
             } else if log_line.contains("Warning")
                 || log_line.contains("warning")
                 || log_line.contains("⚠️")
             {
                 (
                     "WARN",
-                    Style::default().fg(Color::Yellow),
+                    Style::default().fg(theme.warning),
                     log_line.as_str(),
                 )
             } else if log_line.contains("Success")
@@ -223,22 +228,18 @@ pub fn render_logs_tab(f: &mut Frame<CrosstermBackend<io::Stdout>>, app: &App, a
             {
                 (
                     "SUCCESS",
-                    Style::default().fg(Color::Green),
+                    Style::default().fg(theme.success),
                     log_line.as_str(),
                 )
             } else if log_line.contains("Running")
                 || log_line.contains("running")
                 || log_line.contains("⟳")
             {
-                ("INFO", Style::default().fg(Color::Cyan), log_line.as_str())
+                ("INFO", Style::default().fg(theme.info), log_line.as_str())
             } else if log_line.contains("Triggering") || log_line.contains("triggered") {
-                (
-                    "TRIG",
-                    Style::default().fg(Color::Magenta),
-                    log_line.as_str(),
-                )
+                ("TRIG", Style::default().fg(theme.label), log_line.as_str())
             } else {
-                ("INFO", Style::default().fg(Color::Gray), log_line.as_str())
+                ("INFO", Style::default().fg(theme.text), log_line.as_str())
             };
 
         // Extract content after timestamp
@@ -269,7 +270,7 @@ pub fn render_logs_tab(f: &mut Frame<CrosstermBackend<io::Stdout>>, app: &App, a
                     let match_end = real_idx + app.log_search_query.len();
                     content_spans.push(Span::styled(
                         content[real_idx..match_end].to_string(),
-                        Style::default().bg(Color::Yellow).fg(Color::Black),
+                        Style::default().bg(theme.warning).fg(theme.on_color()),
                     ));
 
                     last_idx = match_end;
@@ -311,10 +312,10 @@ pub fn render_logs_tab(f: &mut Frame<CrosstermBackend<io::Stdout>>, app: &App, a
                         },
                         filtered_logs.len()
                     ),
-                    Style::default().fg(Color::Yellow),
+                    Style::default().fg(theme.accent),
                 )),
         )
-        .highlight_style(Style::default().bg(Color::DarkGray))
+        .highlight_style(Style::default().bg(theme.selection))
         .widths(&[
             Constraint::Length(10),     // Timestamp column
             Constraint::Length(7),      // Log type column
@@ -1,8 +1,13 @@
 // UI Views module
+mod ansi;
+mod dispatch_form;
 mod execution_tab;
 mod help_overlay;
+mod history_tab;
 mod job_detail;
+mod job_graph;
 mod logs_tab;
+mod replay;
 mod status_bar;
 mod title_bar;
 mod workflows_tab;
@@ -11,16 +16,26 @@ use crate::app::App;
 use ratatui::{backend::CrosstermBackend, Frame};
 use std::io;
 
+pub use replay::render_replay;
+
 // Main render function for the UI
 pub fn render_ui(f: &mut Frame<CrosstermBackend<io::Stdout>>, app: &mut App) {
     // Check if help should be shown as an overlay
     if app.show_help {
-        help_overlay::render_help_overlay(f);
+        help_overlay::render_help_overlay(f, &app.theme);
         return;
     }
 
     let size = f.size();
 
+    // Paint the theme's background and text color under every view
+    let background = ratatui::widgets::Block::default().style(
+        ratatui::style::Style::default()
+            .bg(app.theme.background)
+            .fg(app.theme.text),
+    );
+    f.render_widget(background, size);
+
     // Create main layout
     let main_chunks = ratatui::layout::Layout::default()
         .direction(ratatui::layout::Direction::Vertical)
@@ -48,10 +63,16 @@ pub fn render_ui(f: &mut Frame<CrosstermBackend<io::Stdout>>, app: &mut App) {
             }
         }
         2 => logs_tab::render_logs_tab(f, app, main_chunks[1]),
-        3 => help_overlay::render_help_tab(f, main_chunks[1]),
+        3 => history_tab::render_history_tab(f, app, main_chunks[1]),
+        4 => help_overlay::render_help_tab(f, &app.theme, main_chunks[1]),
         _ => {}
     }
 
     // Render status bar
     status_bar::render_status_bar(f, app, main_chunks[2]);
+
+    if let Some(form) = &app.dispatch_form {
+        let workflow_name = &app.workflows[form.workflow_idx].name;
+        dispatch_form::render_dispatch_form(f, form, workflow_name, &app.theme);
+    }
 }
@@ -0,0 +1,75 @@
+// Replay view for recorded sessions
+use crate::app::ReplayState;
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, LineGauge, Paragraph},
+    Frame,
+};
+use std::io;
+
+fn format_offset(ms: u64) -> String {
+    format!("{:02}:{:02}.{}", ms / 60_000, ms / 1000 % 60, ms / 100 % 10)
+}
+
+// Render the recorded screen with a timeline underneath
+pub fn render_replay(f: &mut Frame<CrosstermBackend<io::Stdout>>, state: &ReplayState) {
+    let theme = state.theme;
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(4)].as_ref())
+        .split(f.size());
+
+    let screen: Vec<Line> = state
+        .screen()
+        .iter()
+        .map(|row| Line::from(row.as_str()))
+        .collect();
+    f.render_widget(Paragraph::new(screen), chunks[0]);
+
+    let block = Block::default().borders(Borders::ALL).title(format!(
+        " Replay of session started {} ",
+        state.recording.started_at
+    ));
+    let inner = block.inner(chunks[1]);
+    f.render_widget(block, chunks[1]);
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Length(1)].as_ref())
+        .split(inner);
+
+    let duration = state.recording.duration_ms();
+    let ratio = if duration == 0 {
+        1.0
+    } else {
+        state.at_ms() as f64 / duration as f64
+    };
+    let gauge = LineGauge::default()
+        .gauge_style(Style::default().fg(theme.info))
+        .label(format!(
+            "{} {} / {}",
+            if state.playing { "▶" } else { "⏸" },
+            format_offset(state.at_ms()),
+            format_offset(duration)
+        ))
+        .ratio(ratio.clamp(0.0, 1.0));
+    f.render_widget(gauge, rows[0]);
+
+    let mut status = vec![
+        Span::styled(
+            format!(" Screen {}/{} ", state.position + 1, state.frame_count()),
+            Style::default().add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(
+            "Space: play/pause  ←/→: screen  PgUp/PgDn: 10s  Home/End  q: quit",
+            Style::default().fg(theme.muted),
+        ),
+    ];
+    if let Some(activity) = state.latest_activity() {
+        status.push(Span::raw("  "));
+        status.push(Span::styled(activity, Style::default().fg(theme.accent)));
+    }
+    f.render_widget(Paragraph::new(Line::from(status)), rows[1]);
+}
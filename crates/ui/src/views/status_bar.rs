@@ -4,7 +4,7 @@ use executor::RuntimeType;
 use ratatui::{
     backend::CrosstermBackend,
     layout::{Alignment, Rect},
-    style::{Color, Style},
+    style::Style,
     text::{Line, Span},
     widgets::Paragraph,
     Frame,
@@ -13,16 +13,68 @@ use std::io;
 
 // Render the status bar
 pub fn render_status_bar(f: &mut Frame<CrosstermBackend<io::Stdout>>, app: &App, area: Rect) {
+    let theme = app.theme;
+    // A run waiting for a decision says so above anything else
+    if let Some(deployment) = &app.pending_deployment {
+        let approval_bar = Paragraph::new(Line::from(vec![
+            Span::styled(
+                format!(
+                    " {} Job '{}' deploys to '{}' ",
+                    theme.symbols.approval, deployment.job, deployment.environment
+                ),
+                Style::default()
+                    .bg(theme.warning)
+                    .fg(theme.on_color())
+                    .add_modifier(ratatui::style::Modifier::BOLD),
+            ),
+            Span::styled(
+                "  [y] Approve   [n] Reject ",
+                Style::default().fg(theme.text),
+            ),
+        ]))
+        .style(Style::default().bg(theme.selection))
+        .alignment(Alignment::Left);
+        f.render_widget(approval_bar, area);
+        return;
+    }
+
+    if let Some(paused) = &app.paused_step {
+        let paused_bar = Paragraph::new(Line::from(vec![
+            Span::styled(
+                format!(
+                    " {} Paused before step '{}' of job '{}' ",
+                    theme.symbols.paused, paused.step, paused.job
+                ),
+                Style::default()
+                    .bg(theme.warning)
+                    .fg(theme.on_color())
+                    .add_modifier(ratatui::style::Modifier::BOLD),
+            ),
+            Span::styled(
+                "  [c/Enter] Continue   [s] Skip step   [a] Abort run ",
+                Style::default().fg(theme.text),
+            ),
+        ]))
+        .style(Style::default().bg(theme.selection))
+        .alignment(Alignment::Left);
+        f.render_widget(paused_bar, area);
+        return;
+    }
+
     // If we have a status message, show it instead of the normal status bar
     if let Some(message) = &app.status_message {
-        // Determine if this is a success message (starts with ✅)
-        let is_success = message.starts_with("✅");
+        // Determine if this is a success message (starts with the success symbol)
+        let is_success = message.starts_with(theme.symbols.success);
 
         let status_message = Paragraph::new(Line::from(vec![Span::styled(
             format!(" {} ", message),
             Style::default()
-                .bg(if is_success { Color::Green } else { Color::Red })
-                .fg(Color::White)
+                .bg(if is_success {
+                    theme.success
+                } else {
+                    theme.failure
+                })
+                .fg(theme.text)
                 .add_modifier(ratatui::style::Modifier::BOLD),
         )]))
         .alignment(Alignment::Center);
@@ -39,10 +91,12 @@ pub fn render_status_bar(f: &mut Frame<CrosstermBackend<io::Stdout>>, app: &App,
         format!(" {} ", app.runtime_type_name()),
         Style::default()
             .bg(match app.runtime_type {
-                RuntimeType::Docker => Color::Blue,
-                RuntimeType::Emulation => Color::Magenta,
+                RuntimeType::Docker => theme.label,
+                RuntimeType::Podman => theme.info,
+                RuntimeType::Bubblewrap => theme.success,
+                RuntimeType::Emulation => theme.cancelled,
             })
-            .fg(Color::White),
+            .fg(theme.text),
     ));
 
     // Add Docker status if relevant
@@ -66,11 +120,11 @@ pub fn render_status_bar(f: &mut Frame<CrosstermBackend<io::Stdout>>, app: &App,
             },
             Style::default()
                 .bg(if is_docker_available {
-                    Color::Green
+                    theme.success
                 } else {
-                    Color::Red
+                    theme.failure
                 })
-                .fg(Color::White),
+                .fg(theme.text),
         ));
     }
 
@@ -87,11 +141,11 @@ pub fn render_status_bar(f: &mut Frame<CrosstermBackend<io::Stdout>>, app: &App,
         ),
         Style::default()
             .bg(if app.validation_mode {
-                Color::Yellow
+                theme.warning
             } else {
-                Color::Green
+                theme.success
             })
-            .fg(Color::Black),
+            .fg(theme.on_color()),
     ));
 
     // Add context-specific help based on current tab
@@ -102,9 +156,9 @@ pub fn render_status_bar(f: &mut Frame<CrosstermBackend<io::Stdout>>, app: &App,
                 if idx < app.workflows.len() {
                     let workflow = &app.workflows[idx];
                     match workflow.status {
-                        crate::models::WorkflowStatus::NotStarted => "[Space] Toggle selection   [Enter] Run selected   [r] Run all selected   [t] Trigger Workflow  [Shift+R] Reset workflow",
-                        crate::models::WorkflowStatus::Running => "[Space] Toggle selection   [Enter] Run selected   [r] Run all selected   (Workflow running...)",
-                        crate::models::WorkflowStatus::Success | crate::models::WorkflowStatus::Failed | crate::models::WorkflowStatus::Skipped => "[Space] Toggle selection   [Enter] Run selected   [r] Run all selected   [Shift+R] Reset workflow",
+                        crate::models::WorkflowStatus::NotStarted => "[Space] Toggle selection   [Enter] Run selected   [r] Run all selected   [o] Edit   [t] Trigger Workflow  [Shift+R] Reset workflow",
+                        crate::models::WorkflowStatus::Running => "[Space] Toggle selection   [Enter] Run selected   [r] Queue another run   [Shift+C] Cancel run   (Workflow running...)",
+                        crate::models::WorkflowStatus::Success | crate::models::WorkflowStatus::Failed | crate::models::WorkflowStatus::Skipped | crate::models::WorkflowStatus::Cancelled => "[Space] Toggle selection   [Enter] Run selected   [r] Run all selected   [o] Edit   [Shift+R] Reset workflow",
                     }
                 } else {
                     "[Space] Toggle selection   [Enter] Run selected   [r] Run all selected"
@@ -115,14 +169,14 @@ pub fn render_status_bar(f: &mut Frame<CrosstermBackend<io::Stdout>>, app: &App,
         }
         1 => {
             if app.detailed_view {
-                "[Esc] Back to jobs   [↑/↓] Navigate steps"
+                "[Esc] Back to jobs   [↑/↓] Navigate steps   [PgUp/PgDn] Scroll output   [f] Follow   [/] Search   [g] Expand/collapse groups"
             } else {
-                "[Enter] View details   [↑/↓] Navigate jobs"
+                "[Enter] View details   [↑/↓] Navigate jobs and steps   [</>] Resize   [z] Maximize   [f] Follow   [/] Search   [g] Job graph   [o] Only run job   [c] Run all jobs"
             }
         }
         2 => {
             // For logs tab, show scrolling instructions
-            let log_count = app.logs.len() + logging::get_logs().len();
+            let log_count = app.logs.len() + app.system_logs.len();
             if log_count > 0 {
                 // Convert to a static string for consistent return type
                 let scroll_text = format!(
@@ -135,31 +189,32 @@ pub fn render_status_bar(f: &mut Frame<CrosstermBackend<io::Stdout>>, app: &App,
                 "[No logs to display]"
             }
         }
-        3 => "[?] Toggle help overlay",
+        3 => {
+            if app.history_compare.is_some() {
+                "[↑/↓] Navigate runs   [d] Stop comparing   [u] Reload"
+            } else {
+                "[↑/↓] Navigate runs   [d] Compare with selected run   [u] Reload"
+            }
+        }
+        4 => "[?] Toggle help overlay",
         _ => "",
     };
     status_items.push(Span::styled(
         format!(" {} ", help_text),
-        Style::default().fg(Color::White),
+        Style::default().fg(theme.text),
     ));
 
     // Show keybindings for common actions
     status_items.push(Span::raw(" "));
     status_items.push(Span::styled(
         " [Tab] Switch tabs ",
-        Style::default().fg(Color::White),
-    ));
-    status_items.push(Span::styled(
-        " [?] Help ",
-        Style::default().fg(Color::White),
-    ));
-    status_items.push(Span::styled(
-        " [q] Quit ",
-        Style::default().fg(Color::White),
+        Style::default().fg(theme.text),
     ));
+    status_items.push(Span::styled(" [?] Help ", Style::default().fg(theme.text)));
+    status_items.push(Span::styled(" [q] Quit ", Style::default().fg(theme.text)));
 
     let status_bar = Paragraph::new(Line::from(status_items))
-        .style(Style::default().bg(Color::DarkGray))
+        .style(Style::default().bg(theme.selection))
         .alignment(Alignment::Left);
 
     f.render_widget(status_bar, area);
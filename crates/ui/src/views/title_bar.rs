@@ -3,7 +3,7 @@ use crate::app::App;
 use ratatui::{
     backend::CrosstermBackend,
     layout::{Alignment, Rect},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, BorderType, Borders, Tabs},
     Frame,
@@ -12,26 +12,28 @@ use std::io;
 
 // Render the title bar with tabs
 pub fn render_title_bar(f: &mut Frame<CrosstermBackend<io::Stdout>>, app: &App, area: Rect) {
-    let titles = ["Workflows", "Execution", "Logs", "Help"];
+    let theme = app.theme;
+    let titles = ["Workflows", "Execution", "Logs", "History", "Help"];
     let tabs = Tabs::new(
         titles
             .iter()
             .enumerate()
             .map(|(i, t)| {
-                if i == 1 {
-                    // Special case for "Execution"
+                if i == 1 || i == 3 {
+                    // Special case for "Execution" and "History", whose
+                    // second letters are their keys
                     let e_part = &t[0..1]; // "E"
                     let x_part = &t[1..2]; // "x"
                     let rest = &t[2..]; // "ecution"
                     Line::from(vec![
-                        Span::styled(e_part, Style::default().fg(Color::White)),
+                        Span::styled(e_part, Style::default().fg(theme.text)),
                         Span::styled(
                             x_part,
                             Style::default()
-                                .fg(Color::Yellow)
+                                .fg(theme.accent)
                                 .add_modifier(Modifier::UNDERLINED),
                         ),
-                        Span::styled(rest, Style::default().fg(Color::White)),
+                        Span::styled(rest, Style::default().fg(theme.text)),
                     ])
                 } else {
                     // Original styling for other tabs
@@ -40,10 +42,10 @@ pub fn render_title_bar(f: &mut Frame<CrosstermBackend<io::Stdout>>, app: &App,
                         Span::styled(
                             first,
                             Style::default()
-                                .fg(Color::Yellow)
+                                .fg(theme.accent)
                                 .add_modifier(Modifier::UNDERLINED),
                         ),
-                        Span::styled(rest, Style::default().fg(Color::White)),
+                        Span::styled(rest, Style::default().fg(theme.text)),
                     ])
                 }
             })
@@ -55,16 +57,14 @@ pub fn render_title_bar(f: &mut Frame<CrosstermBackend<io::Stdout>>, app: &App,
             .border_type(BorderType::Rounded)
             .title(Span::styled(
                 " wrkflw ",
-                Style::default()
-                    .fg(Color::Cyan)
-                    .add_modifier(Modifier::BOLD),
+                Style::default().fg(theme.info).add_modifier(Modifier::BOLD),
             ))
             .title_alignment(Alignment::Center),
     )
     .highlight_style(
         Style::default()
-            .bg(Color::DarkGray)
-            .fg(Color::Yellow)
+            .bg(theme.selection)
+            .fg(theme.accent)
             .add_modifier(Modifier::BOLD),
     )
     .select(app.selected_tab)
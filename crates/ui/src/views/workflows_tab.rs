@@ -4,7 +4,7 @@ use crate::models::WorkflowStatus;
 use ratatui::{
     backend::CrosstermBackend,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, BorderType, Borders, Cell, Paragraph, Row, Table, TableState},
     Frame,
@@ -17,6 +17,7 @@ pub fn render_workflows_tab(
     app: &mut App,
     area: Rect,
 ) {
+    let theme = app.theme;
     // Create a more structured layout for the workflow tab
     let chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -35,15 +36,15 @@ pub fn render_workflows_tab(
         Line::from(vec![Span::styled(
             "Available Workflows",
             Style::default()
-                .fg(Color::Yellow)
+                .fg(theme.accent)
                 .add_modifier(Modifier::BOLD),
         )]),
         Line::from(vec![
-            Span::styled("Space", Style::default().fg(Color::Cyan)),
+            Span::styled("Space", Style::default().fg(theme.info)),
             Span::raw(": Toggle selection   "),
-            Span::styled("Enter", Style::default().fg(Color::Cyan)),
+            Span::styled("Enter", Style::default().fg(theme.info)),
             Span::raw(": Run   "),
-            Span::styled("t", Style::default().fg(Color::Cyan)),
+            Span::styled("t", Style::default().fg(theme.info)),
             Span::raw(": Trigger remotely"),
         ]),
     ];
@@ -59,15 +60,13 @@ pub fn render_workflows_tab(
     f.render_widget(header, chunks[0]);
 
     // Create a table for workflows instead of a list for better organization
-    let selected_style = Style::default()
-        .bg(Color::DarkGray)
-        .add_modifier(Modifier::BOLD);
+    let selected_style = theme.highlight();
 
     // Normal style definition removed as it was unused
 
-    let header_cells = ["", "Status", "Workflow Name", "Path"]
+    let header_cells = ["", "Status", "Issues", "Workflow Name", "Path"]
         .iter()
-        .map(|h| Cell::from(*h).style(Style::default().fg(Color::Yellow)));
+        .map(|h| Cell::from(*h).style(Style::default().fg(theme.accent)));
 
     let header = Row::new(header_cells)
         .style(Style::default().add_modifier(Modifier::BOLD))
@@ -78,11 +77,23 @@ pub fn render_workflows_tab(
         let checkbox = if workflow.selected { "✓" } else { " " };
 
         let (status_symbol, status_style) = match workflow.status {
-            WorkflowStatus::NotStarted => ("○", Style::default().fg(Color::Gray)),
-            WorkflowStatus::Running => ("⟳", Style::default().fg(Color::Cyan)),
-            WorkflowStatus::Success => ("✅", Style::default().fg(Color::Green)),
-            WorkflowStatus::Failed => ("❌", Style::default().fg(Color::Red)),
-            WorkflowStatus::Skipped => ("⏭", Style::default().fg(Color::Yellow)),
+            WorkflowStatus::NotStarted => {
+                (theme.symbols.pending, Style::default().fg(theme.skipped))
+            }
+            WorkflowStatus::Running => (theme.symbols.running, Style::default().fg(theme.running)),
+            WorkflowStatus::Success => (theme.symbols.success, Style::default().fg(theme.success)),
+            WorkflowStatus::Failed => (theme.symbols.failure, Style::default().fg(theme.failure)),
+            WorkflowStatus::Skipped => (theme.symbols.skipped, Style::default().fg(theme.skipped)),
+            WorkflowStatus::Cancelled => (
+                theme.symbols.cancelled,
+                Style::default().fg(theme.cancelled),
+            ),
+        };
+
+        let (issues, issues_style) = match workflow.issue_count {
+            Some(0) => ("0".to_string(), Style::default().fg(theme.success)),
+            Some(count) => (count.to_string(), Style::default().fg(theme.failure)),
+            None => ("-".to_string(), Style::default().fg(theme.muted)),
         };
 
         let path_display = workflow.path.to_string_lossy();
@@ -93,10 +104,11 @@ pub fn render_workflows_tab(
         };
 
         Row::new(vec![
-            Cell::from(checkbox).style(Style::default().fg(Color::Green)),
+            Cell::from(checkbox).style(Style::default().fg(theme.success)),
             Cell::from(status_symbol).style(status_style),
+            Cell::from(issues).style(issues_style),
             Cell::from(workflow.name.clone()),
-            Cell::from(path_shortened).style(Style::default().fg(Color::DarkGray)),
+            Cell::from(path_shortened).style(Style::default().fg(theme.muted)),
         ])
     });
 
@@ -108,7 +120,7 @@ pub fn render_workflows_tab(
                 .border_type(BorderType::Rounded)
                 .title(Span::styled(
                     " Workflows ",
-                    Style::default().fg(Color::Yellow),
+                    Style::default().fg(theme.accent),
                 )),
         )
         .highlight_style(selected_style)
@@ -116,6 +128,7 @@ pub fn render_workflows_tab(
         .widths(&[
             Constraint::Length(3),      // Checkbox column
             Constraint::Length(4),      // Status icon column
+            Constraint::Length(6),      // Validation issue count column
             Constraint::Percentage(45), // Name column
             Constraint::Percentage(45), // Path column
         ]);
@@ -0,0 +1,109 @@
+// User settings read from ~/.wrkflw/config.yml
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::PathBuf;
+
+/// Environment variable pointing at a config file to use instead
+pub const CONFIG_ENV: &str = "WRKFLW_CONFIG";
+
+/// Defaults for command-line flags, set once instead of on every run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    /// Lines of a failed step's output that `wrkflw run` prints
+    pub max_output_lines: usize,
+    /// Characters of a step's output the TUI shows
+    pub max_output_chars: usize,
+    /// Set `CI` and `GITHUB_ACTIONS`/`GITLAB_CI` in jobs, as CI runners do
+    pub ci_env: bool,
+    /// Times a Docker or Podman API call failing for a transient reason is
+    /// retried
+    pub docker_retries: u32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            max_output_lines: 5,
+            max_output_chars: 1000,
+            ci_env: true,
+            docker_retries: 3,
+        }
+    }
+}
+
+impl Config {
+    /// Read the config file, falling back to the defaults when there is none
+    pub fn load() -> Result<Self, String> {
+        let Some(path) = config_path() else {
+            return Ok(Config::default());
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(content) => Self::parse(&content)
+                .map_err(|e| format!("Invalid config file {}: {}", path.display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+        }
+    }
+
+    pub fn parse(content: &str) -> Result<Self, serde_yaml::Error> {
+        // An empty file is an empty mapping, not an error
+        if content.trim().is_empty() {
+            return Ok(Config::default());
+        }
+        serde_yaml::from_str(content)
+    }
+}
+
+/// A JSON Schema of the config file, which editors use to complete keys
+/// and point out typos and wrong values
+pub fn schema() -> Value {
+    let mut schema = schemars::schema_for!(Config);
+    schema.insert("title".into(), "wrkflw config".into());
+    schema.into()
+}
+
+fn config_path() -> Option<PathBuf> {
+    match std::env::var(CONFIG_ENV) {
+        Ok(path) if !path.is_empty() => Some(PathBuf::from(path)),
+        _ => dirs::home_dir().map(|home| home.join(".wrkflw").join("config.yml")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_partial_config() {
+        let config = Config::parse("max-output-lines: 40\n").unwrap();
+        assert_eq!(config.max_output_lines, 40);
+        assert_eq!(config.max_output_chars, 1000);
+        assert!(config.ci_env);
+        assert!(!Config::parse("ci-env: false\n").unwrap().ci_env);
+        assert_eq!(
+            Config::parse("docker-retries: 0\n").unwrap().docker_retries,
+            0
+        );
+        assert_eq!(Config::parse("").unwrap(), Config::default());
+        assert!(Config::parse("max-output-lines: many\n").is_err());
+        assert!(Config::parse("max-ouput-lines: 40\n").is_err());
+    }
+
+    #[test]
+    fn schema_covers_every_key() {
+        let schema = schema();
+        let properties = schema["properties"].as_object().unwrap();
+        let defaults = serde_json::to_value(Config::default()).unwrap();
+        let defaults = defaults.as_object().unwrap();
+        assert_eq!(
+            properties.keys().collect::<Vec<_>>(),
+            defaults.keys().collect::<Vec<_>>()
+        );
+        for (key, default) in defaults {
+            assert_eq!(&properties[key]["default"], default, "{}", key);
+        }
+        assert_eq!(schema["additionalProperties"], false);
+    }
+}
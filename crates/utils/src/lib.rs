@@ -2,6 +2,10 @@
 
 use std::path::Path;
 
+pub mod config;
+pub mod hash;
+pub mod project;
+
 pub fn is_workflow_file(path: &Path) -> bool {
     // First, check for GitLab CI files by name
     if let Some(file_name) = path.file_name() {
@@ -35,6 +39,7 @@ pub fn is_workflow_file(path: &Path) -> bool {
 }
 
 /// Module for safely handling file descriptor redirection
+#[cfg(unix)]
 pub mod fd {
     use nix::fcntl::{open, OFlag};
     use nix::sys::stat::Mode;
@@ -58,7 +63,7 @@ pub mod fd {
             // Duplicate the current stderr fd
             let stderr_backup = match dup(STDERR_FILENO) {
                 Ok(fd) => fd,
-                Err(e) => return Err(io::Error::new(io::ErrorKind::Other, e)),
+                Err(e) => return Err(io::Error::other(e)),
             };
 
             // Open /dev/null
@@ -66,7 +71,7 @@ pub mod fd {
                 Ok(fd) => fd,
                 Err(e) => {
                     let _ = close(stderr_backup); // Clean up on error
-                    return Err(io::Error::new(io::ErrorKind::Other, e));
+                    return Err(io::Error::other(e));
                 }
             };
 
@@ -74,7 +79,7 @@ pub mod fd {
             if let Err(e) = dup2(null_fd, STDERR_FILENO) {
                 let _ = close(stderr_backup); // Clean up on error
                 let _ = close(null_fd);
-                return Err(io::Error::new(io::ErrorKind::Other, e));
+                return Err(io::Error::other(e));
             }
 
             Ok(RedirectedStderr {
@@ -110,6 +115,30 @@ pub mod fd {
     }
 }
 
+/// Windows has no file descriptors to swap, and Rust writes to the console
+/// handle directly, so stderr is left alone there
+#[cfg(windows)]
+pub mod fd {
+    use std::io::Result;
+
+    /// Stands in for the Unix redirection; stderr stays as it is
+    pub struct RedirectedStderr;
+
+    impl RedirectedStderr {
+        pub fn to_null() -> Result<Self> {
+            Ok(RedirectedStderr)
+        }
+    }
+
+    /// Run a function; stderr isn't redirected on Windows
+    pub fn with_stderr_to_null<F, T>(f: F) -> Result<T>
+    where
+        F: FnOnce() -> T,
+    {
+        Ok(f())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,293 @@
+// Project settings read from a .wrkflw.toml at the root of a repository
+use models::RuleSeverity;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+pub const PROJECT_CONFIG_FILE: &str = ".wrkflw.toml";
+
+/// Settings shared by everyone working on a project, checked in next to its
+/// workflows
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, JsonSchema)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ProjectConfig {
+    /// Severity of validation rules by code, e.g. `unpinned-action =
+    /// "error"`; rules not listed keep their own
+    pub rules: BTreeMap<String, RuleSeverity>,
+    /// Runtime used when no `--runtime` or `--emulate` is given
+    pub runtime: Option<String>,
+    /// File of KEY=VALUE lines the `secrets` context is read from, relative
+    /// to the config file
+    pub secrets_file: Option<PathBuf>,
+    /// Images that jobs run in by `runs-on` label, instead of wrkflw's own
+    pub images: BTreeMap<String, String>,
+    /// Keep tool caches in volumes between runs, as with `--tool-cache`
+    pub tool_cache: bool,
+    /// Run jobs in the project directory instead of a copy of it, as with
+    /// `--bind-workspace`
+    pub bind_workspace: bool,
+    /// Deployment environments by name, for jobs with `environment:`
+    pub environments: BTreeMap<String, EnvironmentConfig>,
+    /// CPU and memory limits of job containers
+    pub resources: ResourcesConfig,
+    /// Platform of job images and containers, like `linux/arm64`, as with
+    /// `--platform`
+    pub platform: Option<String>,
+    /// Platforms of particular jobs by ID, over `platform`
+    pub platforms: BTreeMap<String, String>,
+    /// Colors and symbols of the TUI
+    pub ui: UiConfig,
+}
+
+/// Look of the TUI
+#[derive(Debug, Clone, PartialEq, Deserialize, JsonSchema)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct UiConfig {
+    /// `default`, `dark`, `light`, `high-contrast` or one of `themes`
+    pub theme: Option<String>,
+    /// Show emoji for statuses, or ASCII markers like `[ok]` when false
+    pub emoji: bool,
+    /// Palettes defined by the project, by name
+    pub themes: BTreeMap<String, PaletteConfig>,
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        UiConfig {
+            theme: None,
+            emoji: true,
+            themes: BTreeMap::new(),
+        }
+    }
+}
+
+/// Colors of a theme, like `"cyan"`, `"#ff8800"` or a 256-color index such
+/// as `"208"`; the ones left out come from `base`
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, JsonSchema)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct PaletteConfig {
+    /// Built-in theme the palette starts from (default `default`)
+    pub base: Option<String>,
+    /// Titles, headers and keys
+    pub accent: Option<String>,
+    /// Labels in front of values
+    pub label: Option<String>,
+    pub text: Option<String>,
+    /// Hints, durations and other secondary text
+    pub muted: Option<String>,
+    /// Background of the selected row and the status bar
+    pub selection: Option<String>,
+    pub background: Option<String>,
+    pub success: Option<String>,
+    pub failure: Option<String>,
+    pub running: Option<String>,
+    pub skipped: Option<String>,
+    pub cancelled: Option<String>,
+    pub warning: Option<String>,
+    /// Notices, folded groups and other highlights
+    pub info: Option<String>,
+}
+
+/// CPUs and memory like `7g` that a job's containers may use
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, JsonSchema)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ResourceLimitsConfig {
+    pub cpus: Option<f64>,
+    pub memory: Option<String>,
+}
+
+/// Limits of every job's containers, and of particular jobs by ID
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, JsonSchema)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ResourcesConfig {
+    #[serde(flatten)]
+    pub limits: ResourceLimitsConfig,
+    pub jobs: BTreeMap<String, ResourceLimitsConfig>,
+}
+
+/// What a deployment environment gives the jobs that use it
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, JsonSchema)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct EnvironmentConfig {
+    /// Values of the `vars` context
+    pub variables: BTreeMap<String, String>,
+    /// File of KEY=VALUE lines with secrets that win over the project's,
+    /// relative to the config file
+    pub secrets_file: Option<PathBuf>,
+    /// Ask before running a job that deploys to the environment, as its
+    /// required reviewers would
+    pub require_approval: bool,
+}
+
+impl ProjectConfig {
+    /// Read the `.wrkflw.toml` in `dir` or the closest directory above it,
+    /// falling back to the defaults when there is none
+    pub fn load(dir: &Path) -> Result<Self, String> {
+        let Some(path) = dir
+            .ancestors()
+            .map(|dir| dir.join(PROJECT_CONFIG_FILE))
+            .find(|path| path.is_file())
+        else {
+            return Ok(ProjectConfig::default());
+        };
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let mut config = Self::parse(&content)
+            .map_err(|e| format!("Invalid config file {}: {}", path.display(), e))?;
+        if let Some(root) = path.parent() {
+            let secrets_files = std::iter::once(&mut config.secrets_file).chain(
+                config
+                    .environments
+                    .values_mut()
+                    .map(|environment| &mut environment.secrets_file),
+            );
+            for file in secrets_files.flatten() {
+                *file = root.join(&*file);
+            }
+        }
+        Ok(config)
+    }
+
+    pub fn parse(content: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(content)
+    }
+}
+
+/// A JSON Schema of `.wrkflw.toml`, which editors use to complete keys and
+/// point out typos and wrong values
+pub fn schema() -> Value {
+    let mut schema = schemars::schema_for!(ProjectConfig);
+    schema.insert("title".into(), "wrkflw project config".into());
+    schema.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loads_the_closest_project_config() {
+        let config = ProjectConfig::parse(
+            r#"
+runtime = "podman"
+secrets-file = ".secrets"
+tool-cache = true
+bind-workspace = true
+platform = "linux/amd64"
+
+[platforms]
+build-arm = "linux/arm64"
+
+[rules]
+unpinned-action = "error"
+missing-name = "ignore"
+
+[images]
+ubuntu-latest = "catthehacker/ubuntu:act-latest"
+
+[resources]
+cpus = 4
+memory = "16g"
+
+[resources.jobs.build]
+memory = "7g"
+
+[environments.production]
+variables = { REGION = "eu-west-1" }
+secrets-file = ".secrets.production"
+require-approval = true
+
+[ui]
+theme = "solarized"
+emoji = false
+
+[ui.themes.solarized]
+base = "dark"
+accent = "light-yellow"
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.runtime.as_deref(), Some("podman"));
+        assert!(config.tool_cache);
+        assert!(config.bind_workspace);
+        assert_eq!(config.rules["unpinned-action"], RuleSeverity::Error);
+        assert_eq!(config.rules["missing-name"], RuleSeverity::Ignore);
+        assert_eq!(
+            config.images["ubuntu-latest"],
+            "catthehacker/ubuntu:act-latest"
+        );
+        assert_eq!(config.platforms["build-arm"], "linux/arm64");
+        assert_eq!(config.resources.limits.cpus, Some(4.0));
+        assert_eq!(config.resources.jobs["build"].memory.as_deref(), Some("7g"));
+        let production = &config.environments["production"];
+        assert_eq!(production.variables["REGION"], "eu-west-1");
+        assert!(production.require_approval);
+        assert_eq!(config.ui.theme.as_deref(), Some("solarized"));
+        assert!(!config.ui.emoji);
+        assert_eq!(
+            config.ui.themes["solarized"].accent.as_deref(),
+            Some("light-yellow")
+        );
+        assert!(ProjectConfig::default().ui.emoji);
+        assert!(ProjectConfig::parse("[ui.themes.mine]\nhighlight = \"red\"\n").is_err());
+        assert_eq!(ProjectConfig::parse("").unwrap(), ProjectConfig::default());
+        assert!(ProjectConfig::parse("[environments.staging]\nreviewers = 1\n").is_err());
+        assert!(ProjectConfig::parse("[resources]\ncpu = 2\n").is_err());
+        assert!(ProjectConfig::parse("[rules]\nmissing-name = \"fatal\"\n").is_err());
+        assert!(ProjectConfig::parse("runtimes = \"docker\"\n").is_err());
+
+        let root = tempfile::tempdir().unwrap();
+        let nested = root.path().join(".github").join("workflows");
+        std::fs::create_dir_all(&nested).unwrap();
+        assert_eq!(
+            ProjectConfig::load(&nested).unwrap(),
+            ProjectConfig::default()
+        );
+        std::fs::write(
+            root.path().join(PROJECT_CONFIG_FILE),
+            "secrets-file = \".secrets\"\n[environments.staging]\nsecrets-file = \"staging.env\"\n",
+        )
+        .unwrap();
+        let loaded = ProjectConfig::load(&nested).unwrap();
+        assert_eq!(loaded.secrets_file, Some(root.path().join(".secrets")));
+        assert_eq!(
+            loaded.environments["staging"].secrets_file,
+            Some(root.path().join("staging.env"))
+        );
+    }
+
+    #[test]
+    fn schema_covers_every_key() {
+        let schema = schema();
+        let properties = schema["properties"].as_object().unwrap();
+        assert_eq!(
+            properties.keys().map(String::as_str).collect::<Vec<_>>(),
+            [
+                "bind-workspace",
+                "environments",
+                "images",
+                "platform",
+                "platforms",
+                "resources",
+                "rules",
+                "runtime",
+                "secrets-file",
+                "tool-cache",
+                "ui",
+            ]
+        );
+        assert_eq!(schema["additionalProperties"], false);
+        let severity = schema["$defs"]["RuleSeverity"].to_string();
+        for value in ["error", "warning", "ignore"] {
+            assert!(severity.contains(&format!("\"{}\"", value)), "{}", severity);
+        }
+        let resources = &schema["$defs"]["ResourcesConfig"]["properties"];
+        assert!(resources["cpus"].is_object() && resources["jobs"].is_object());
+        let palette = &schema["$defs"]["PaletteConfig"];
+        assert_eq!(palette["additionalProperties"], false);
+        assert!(palette["properties"]["accent"].is_object());
+        assert!(schema["$defs"]["UiConfig"]["properties"]["themes"].is_object());
+    }
+}
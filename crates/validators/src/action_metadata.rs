@@ -0,0 +1,396 @@
+// Structural checks of a GitHub Action's metadata file (action.yml): its
+// inputs and outputs, how it runs, and the steps of composite actions
+use crate::shells::BUILT_IN_SHELLS;
+use models::ValidationResult;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_yaml::{Mapping, Value};
+
+const TOP_LEVEL_KEYS: &[&str] = &[
+    "name",
+    "author",
+    "description",
+    "inputs",
+    "outputs",
+    "runs",
+    "branding",
+];
+
+const INPUT_KEYS: &[&str] = &["description", "required", "default", "deprecationMessage"];
+
+const NODE_KEYS: &[&str] = &["using", "main", "pre", "pre-if", "post", "post-if"];
+
+const DOCKER_KEYS: &[&str] = &[
+    "using",
+    "image",
+    "env",
+    "entrypoint",
+    "pre-entrypoint",
+    "post-entrypoint",
+    "args",
+    "pre-if",
+    "post-if",
+];
+
+const COMPOSITE_STEP_KEYS: &[&str] = &[
+    "id",
+    "if",
+    "name",
+    "uses",
+    "run",
+    "shell",
+    "with",
+    "env",
+    "working-directory",
+    "continue-on-error",
+];
+
+/// Node.js versions GitHub no longer runs actions on, running them on a
+/// newer one instead
+const DEPRECATED_NODE_VERSIONS: &[&str] = &["node12", "node16"];
+
+const NODE_VERSIONS: &[&str] = &["node20", "node24"];
+
+/// Ids of inputs and outputs, as expressions can reference them
+static ID: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[A-Za-z_][A-Za-z0-9_-]*$").unwrap());
+
+/// Validate an action.yml file
+pub fn validate_action_metadata(action: &Value) -> ValidationResult {
+    let mut result = ValidationResult::new();
+    let Some(action) = action.as_mapping() else {
+        result.add_issue_at(
+            "not-a-mapping",
+            "",
+            "An action's metadata must be a mapping".to_string(),
+        );
+        return result;
+    };
+
+    warn_unknown_keys(action, TOP_LEVEL_KEYS, "", "The action", &mut result);
+    if action.get("name").and_then(Value::as_str).is_none() {
+        result.add_issue_at(
+            "missing-action-key",
+            "name",
+            "The action is missing a 'name'".to_string(),
+        );
+    }
+    if action.get("description").and_then(Value::as_str).is_none() {
+        result.add_warning_at(
+            "missing-action-description",
+            "description",
+            "The action has no 'description', which the Marketplace requires".to_string(),
+        );
+    }
+
+    let Some(runs) = action.get("runs") else {
+        result.add_issue_at(
+            "missing-action-key",
+            "runs",
+            "The action is missing 'runs', saying how it runs".to_string(),
+        );
+        return result;
+    };
+    let composite = validate_runs(runs, &mut result);
+    validate_inputs(action.get("inputs"), &mut result);
+    validate_outputs(action.get("outputs"), composite, &mut result);
+    result
+}
+
+/// Check `runs:`, returning whether the action is a composite one
+fn validate_runs(runs: &Value, result: &mut ValidationResult) -> bool {
+    let Some(runs) = runs.as_mapping() else {
+        result.add_issue_at(
+            "invalid-action-runs",
+            "runs",
+            "'runs' must be a mapping with 'using'".to_string(),
+        );
+        return false;
+    };
+    let Some(using) = runs.get("using").and_then(Value::as_str) else {
+        result.add_issue_at(
+            "missing-action-key",
+            "runs",
+            "'runs' is missing 'using': node20, docker or composite".to_string(),
+        );
+        return false;
+    };
+
+    let required = |key: &str, result: &mut ValidationResult| {
+        if runs.get(key).and_then(Value::as_str).is_none() {
+            result.add_issue_at(
+                "missing-action-key",
+                "runs",
+                format!("A '{}' action needs 'runs.{}'", using, key),
+            );
+        }
+    };
+    match using {
+        "docker" => {
+            warn_unknown_keys(runs, DOCKER_KEYS, "runs.", "'runs'", result);
+            required("image", result);
+        }
+        "composite" => {
+            warn_unknown_keys(runs, &["using", "steps"], "runs.", "'runs'", result);
+            match runs.get("steps") {
+                Some(Value::Sequence(steps)) => validate_composite_steps(steps, result),
+                _ => result.add_issue_at(
+                    "missing-action-key",
+                    "runs",
+                    "A composite action needs a list of 'runs.steps'".to_string(),
+                ),
+            }
+            return true;
+        }
+        node if NODE_VERSIONS.contains(&node) || DEPRECATED_NODE_VERSIONS.contains(&node) => {
+            if DEPRECATED_NODE_VERSIONS.contains(&node) {
+                result.add_warning_at(
+                    "deprecated-action-runtime",
+                    "runs.using",
+                    format!(
+                        "GitHub no longer runs actions on {}; use {}",
+                        node, NODE_VERSIONS[0]
+                    ),
+                );
+            }
+            warn_unknown_keys(runs, NODE_KEYS, "runs.", "'runs'", result);
+            required("main", result);
+        }
+        other => result.add_issue_at(
+            "unknown-action-runtime",
+            "runs.using",
+            format!(
+                "Unknown 'runs.using' value '{}'; use {}, docker or composite",
+                other,
+                NODE_VERSIONS.join(", ")
+            ),
+        ),
+    }
+    false
+}
+
+fn validate_composite_steps(steps: &[Value], result: &mut ValidationResult) {
+    for (i, step) in steps.iter().enumerate() {
+        let path = format!("runs.steps[{}]", i);
+        let Some(step) = step.as_mapping() else {
+            result.add_issue_at(
+                "invalid-composite-step",
+                &path,
+                format!("Step {} must be a mapping", i + 1),
+            );
+            continue;
+        };
+        warn_unknown_keys(
+            step,
+            COMPOSITE_STEP_KEYS,
+            &format!("{}.", path),
+            &format!("Step {}", i + 1),
+            result,
+        );
+        match (step.get("run"), step.get("uses")) {
+            (Some(_), Some(_)) => result.add_issue_at(
+                "invalid-composite-step",
+                &path,
+                format!("Step {} has both 'run' and 'uses'; use one", i + 1),
+            ),
+            (None, None) => result.add_issue_at(
+                "invalid-composite-step",
+                &path,
+                format!("Step {} needs 'run' or 'uses'", i + 1),
+            ),
+            (Some(_), None) => match step.get("shell").and_then(Value::as_str) {
+                None => result.add_issue_at(
+                    "missing-shell",
+                    &path,
+                    format!(
+                        "Step {} runs a script without a 'shell', which composite actions require",
+                        i + 1
+                    ),
+                ),
+                Some(shell) if !BUILT_IN_SHELLS.contains(&shell.trim()) && !shell.contains("{0}") => {
+                    result.add_issue_at(
+                        "unknown-shell",
+                        format!("{}.shell", path),
+                        format!(
+                            "Unknown shell '{}'; use one of {}, or a command with {{0}} where the script's path goes",
+                            shell,
+                            BUILT_IN_SHELLS.join(", ")
+                        ),
+                    )
+                }
+                Some(_) => {}
+            },
+            (None, Some(_)) => {}
+        }
+    }
+}
+
+fn validate_inputs(inputs: Option<&Value>, result: &mut ValidationResult) {
+    let Some(inputs) = inputs else {
+        return;
+    };
+    let Some(inputs) = inputs.as_mapping() else {
+        result.add_issue_at(
+            "invalid-action-input",
+            "inputs",
+            "'inputs' must be a mapping of input ids to their settings".to_string(),
+        );
+        return;
+    };
+    for (id, input) in inputs {
+        let id = id.as_str().unwrap_or_default();
+        let path = format!("inputs.{}", id);
+        check_id(id, &path, "Input", result);
+        match input {
+            Value::Null => {}
+            Value::Mapping(input) => {
+                warn_unknown_keys(
+                    input,
+                    INPUT_KEYS,
+                    &format!("{}.", path),
+                    &format!("Input '{}'", id),
+                    result,
+                );
+                if input
+                    .get("required")
+                    .is_some_and(|required| !required.is_bool())
+                {
+                    result.add_issue_at(
+                        "invalid-action-input",
+                        format!("{}.required", path),
+                        format!("Input '{}': 'required' must be true or false", id),
+                    );
+                }
+            }
+            _ => result.add_issue_at(
+                "invalid-action-input",
+                &path,
+                format!("Input '{}' must be a mapping", id),
+            ),
+        }
+    }
+}
+
+fn validate_outputs(outputs: Option<&Value>, composite: bool, result: &mut ValidationResult) {
+    let Some(outputs) = outputs else {
+        return;
+    };
+    let Some(outputs) = outputs.as_mapping() else {
+        result.add_issue_at(
+            "invalid-action-output",
+            "outputs",
+            "'outputs' must be a mapping of output ids to their settings".to_string(),
+        );
+        return;
+    };
+    for (id, output) in outputs {
+        let id = id.as_str().unwrap_or_default();
+        let path = format!("outputs.{}", id);
+        check_id(id, &path, "Output", result);
+        // Other actions set their outputs from their code, composite
+        // ones from a step's outputs
+        if composite && output.get("value").is_none() {
+            result.add_issue_at(
+                "invalid-action-output",
+                &path,
+                format!(
+                    "Output '{}' needs a 'value', like ${{{{ steps.<id>.outputs.<name> }}}}, in a composite action",
+                    id
+                ),
+            );
+        }
+    }
+}
+
+fn check_id(id: &str, path: &str, kind: &str, result: &mut ValidationResult) {
+    if !ID.is_match(id) {
+        result.add_issue_at(
+            &format!("invalid-action-{}", kind.to_lowercase()),
+            path,
+            format!(
+                "{} id '{}' must start with a letter or '_' and contain only letters, digits, '-' and '_'",
+                kind, id
+            ),
+        );
+    }
+}
+
+fn warn_unknown_keys(
+    map: &Mapping,
+    known: &[&str],
+    path: &str,
+    context: &str,
+    result: &mut ValidationResult,
+) {
+    for key in map.keys().filter_map(Value::as_str) {
+        if !known.contains(&key) {
+            result.add_warning_at(
+                "unknown-action-key",
+                format!("{}{}", path, key),
+                format!("{} has an unknown key '{}'", context, key),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codes(yaml: &str) -> Vec<String> {
+        let action: Value = serde_yaml::from_str(yaml).unwrap();
+        validate_action_metadata(&action)
+            .diagnostics
+            .into_iter()
+            .map(|d| format!("{}@{}", d.code, d.path))
+            .collect()
+    }
+
+    #[test]
+    fn accepts_actions_of_every_kind() {
+        for runs in [
+            "using: node20\n  main: dist/index.js\n  post: dist/cleanup.js",
+            "using: docker\n  image: Dockerfile\n  args: ['${{ inputs.who }}']",
+            "using: composite\n  steps:\n    - run: echo hi\n      shell: bash\n    - uses: actions/checkout@v4",
+        ] {
+            let yaml = format!(
+                "name: Greet\ndescription: Says hello\ninputs:\n  who:\n    required: true\n    default: world\nruns:\n  {}\n",
+                runs
+            );
+            assert_eq!(codes(&yaml), Vec::<String>::new(), "{}", runs);
+        }
+    }
+
+    #[test]
+    fn reports_what_github_would_reject() {
+        assert_eq!(
+            codes(
+                "description: x\ninputs:\n  2fast:\n    required: yes please\n    typo: 1\noutputs:\n  result:\n    description: r\nruns:\n  using: composite\n  steps:\n    - run: make\n    - run: make\n      shell: zsh\n    - name: nothing\n"
+            ),
+            [
+                "missing-action-key@name",
+                "missing-shell@runs.steps[0]",
+                "unknown-shell@runs.steps[1].shell",
+                "invalid-composite-step@runs.steps[2]",
+                "invalid-action-input@inputs.2fast",
+                "unknown-action-key@inputs.2fast.typo",
+                "invalid-action-input@inputs.2fast.required",
+                "invalid-action-output@outputs.result",
+            ]
+        );
+        assert_eq!(
+            codes("name: a\ndescription: b\nruns:\n  using: node16\n  mian: index.js\n"),
+            [
+                "deprecated-action-runtime@runs.using",
+                "unknown-action-key@runs.mian",
+                "missing-action-key@runs",
+            ]
+        );
+        assert_eq!(
+            codes("name: a\nruns:\n  using: node8\n"),
+            [
+                "missing-action-description@description",
+                "unknown-action-runtime@runs.using",
+            ]
+        );
+    }
+}
@@ -1,22 +1,33 @@
+use github::action_refs::{ActionRef, RefKind, RefResolution};
 use models::ValidationResult;
+use std::path::Path;
 
+/// Check the format of a step's `uses:`, and that a local action exists
+/// in the repository at `root`
 pub fn validate_action_reference(
     action_ref: &str,
     job_name: &str,
     step_idx: usize,
+    root: &Path,
     result: &mut ValidationResult,
 ) {
+    let path = format!("jobs.{}.steps[{}].uses", job_name, step_idx);
+
     // Check if it's a local action (starts with ./)
     let is_local_action = action_ref.starts_with("./");
 
     // For non-local actions, enforce standard format
     if !is_local_action && !action_ref.contains('/') && !action_ref.contains('.') {
-        result.add_issue(format!(
-            "Job '{}', step {}: Invalid action reference format '{}'",
-            job_name,
-            step_idx + 1,
-            action_ref
-        ));
+        result.add_issue_at(
+            "invalid-action-ref",
+            &path,
+            format!(
+                "Job '{}', step {}: Invalid action reference format '{}'",
+                job_name,
+                step_idx + 1,
+                action_ref
+            ),
+        );
         return;
     }
 
@@ -24,35 +35,145 @@ pub fn validate_action_reference(
     if !is_local_action && action_ref.contains('@') {
         let parts: Vec<&str> = action_ref.split('@').collect();
         if parts.len() != 2 || parts[1].is_empty() {
-            result.add_issue(format!(
-                "Job '{}', step {}: Action '{}' has invalid version/ref format",
-                job_name,
-                step_idx + 1,
-                action_ref
-            ));
+            result.add_issue_at(
+                "invalid-action-ref",
+                &path,
+                format!(
+                    "Job '{}', step {}: Action '{}' has invalid version/ref format",
+                    job_name,
+                    step_idx + 1,
+                    action_ref
+                ),
+            );
         }
     } else if !is_local_action {
         // Missing version tag is not recommended for non-local actions
-        result.add_issue(format!(
-            "Job '{}', step {}: Action '{}' is missing version tag (@v2, @main, etc.)",
-            job_name,
-            step_idx + 1,
-            action_ref
-        ));
+        result.add_issue_at(
+            "missing-action-version",
+            &path,
+            format!(
+                "Job '{}', step {}: Action '{}' is missing version tag (@v2, @main, etc.)",
+                job_name,
+                step_idx + 1,
+                action_ref
+            ),
+        );
     }
 
-    // For local actions, verify the path exists
-    if is_local_action {
-        let action_path = std::path::Path::new(action_ref);
-        if !action_path.exists() {
-            // We can't reliably check this during validation since the working directory
-            // might not be the repository root, but we'll add a warning
-            result.add_issue(format!(
-                "Job '{}', step {}: Local action path '{}' may not exist at runtime",
+    // Local actions are relative to the repository root, as on GitHub
+    if is_local_action && !root.join(action_ref).exists() {
+        result.add_issue_at(
+            "missing-local-action",
+            &path,
+            format!(
+                "Job '{}', step {}: Local action path '{}' doesn't exist in the repository",
                 job_name,
                 step_idx + 1,
                 action_ref
-            ));
+            ),
+        );
+    }
+}
+
+/// Check an action reference against what GitHub has for it, as `lookup`
+/// finds it: the repository and ref should exist, and the ref should be a
+/// commit SHA rather than a tag or branch that can be moved. References
+/// `lookup` has no answer for are left alone.
+pub fn validate_action_reference_online(
+    action_ref: &str,
+    job_name: &str,
+    step_idx: usize,
+    lookup: &dyn Fn(&ActionRef) -> Option<RefResolution>,
+    result: &mut ValidationResult,
+) {
+    let Some(action) = ActionRef::parse(action_ref) else {
+        return;
+    };
+    let Some(resolution) = lookup(&action) else {
+        return;
+    };
+    let path = format!("jobs.{}.steps[{}].uses", job_name, step_idx);
+    let location = format!("Job '{}', step {}", job_name, step_idx + 1);
+
+    match resolution {
+        RefResolution::MissingRepository => result.add_warning_at(
+            "unknown-action",
+            path,
+            format!(
+                "{}: Action repository '{}/{}' was not found on GitHub (or is private)",
+                location, action.owner, action.repo
+            ),
+        ),
+        RefResolution::UnknownRef => result.add_issue_at(
+            "unknown-action-ref",
+            path,
+            format!(
+                "{}: '{}' is not a tag, branch or commit of '{}/{}'",
+                location, action.git_ref, action.owner, action.repo
+            ),
+        ),
+        RefResolution::Resolved { kind, sha } if kind != RefKind::Commit => {
+            let kind = if kind == RefKind::Tag {
+                "tag"
+            } else {
+                "branch"
+            };
+            result.add_warning_at(
+                "unpinned-action",
+                path,
+                format!(
+                    "{}: Action '{}' uses {} '{}', which can be moved; pin it to the commit SHA {}",
+                    location, action_ref, kind, action.git_ref, sha
+                ),
+            );
         }
+        RefResolution::Resolved { .. } => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_references_against_lookups() {
+        let lookup = |action: &ActionRef| match action.key().as_str() {
+            "actions/checkout@v4" => Some(RefResolution::Resolved {
+                kind: RefKind::Tag,
+                sha: "8ade135a".to_string(),
+            }),
+            "actions/checkout@8ade135a41bc03ea155e62e844d188df1ea18608" => {
+                Some(RefResolution::Resolved {
+                    kind: RefKind::Commit,
+                    sha: "8ade135a41bc03ea155e62e844d188df1ea18608".to_string(),
+                })
+            }
+            "actions/checkout@v99" => Some(RefResolution::UnknownRef),
+            "nobody/nothing@main" => Some(RefResolution::MissingRepository),
+            _ => None,
+        };
+        let check = |uses: &str| {
+            let mut result = ValidationResult::new();
+            validate_action_reference_online(uses, "build", 0, &lookup, &mut result);
+            result
+        };
+
+        let tagged = check("actions/checkout@v4");
+        assert!(tagged.is_valid);
+        assert_eq!(tagged.diagnostics[0].code, "unpinned-action");
+        assert_eq!(tagged.diagnostics[0].path, "jobs.build.steps[0].uses");
+        assert!(tagged.warnings[0]
+            .ends_with("uses tag 'v4', which can be moved; pin it to the commit SHA 8ade135a"));
+
+        assert!(
+            check("actions/checkout@8ade135a41bc03ea155e62e844d188df1ea18608")
+                .diagnostics
+                .is_empty()
+        );
+        assert!(!check("actions/checkout@v99").is_valid);
+        let missing = check("nobody/nothing@main");
+        assert!(missing.is_valid);
+        assert_eq!(missing.diagnostics[0].code, "unknown-action");
+        assert!(check("someone/unknown@v1").diagnostics.is_empty());
     }
 }
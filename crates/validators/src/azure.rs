@@ -0,0 +1,319 @@
+// Structural checks of Azure Pipelines YAML (azure-pipelines.yml): how its
+// stages, jobs and steps are laid out, dependsOn references and task syntax
+use models::ValidationResult;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_yaml::Value;
+use std::collections::HashSet;
+
+/// Keys saying what a step does; each step has exactly one
+const STEP_KINDS: &[&str] = &[
+    "script",
+    "bash",
+    "pwsh",
+    "powershell",
+    "task",
+    "checkout",
+    "download",
+    "downloadBuild",
+    "getPackage",
+    "publish",
+    "reviewApp",
+    "template",
+];
+
+/// A task with its major version, like `NodeTool@0`
+static TASK_REFERENCE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[A-Za-z0-9_.-]+@\d+$").unwrap());
+
+/// Context of messages about the pipeline's own stages or jobs
+const PIPELINE: &str = "The pipeline: ";
+
+/// Validate an Azure Pipelines YAML file
+pub fn validate_azure_pipeline(pipeline: &Value) -> ValidationResult {
+    let mut result = ValidationResult::new();
+    let Some(pipeline) = pipeline.as_mapping() else {
+        result.add_issue_at(
+            "not-a-mapping",
+            "",
+            "An Azure pipeline must be a mapping".to_string(),
+        );
+        return result;
+    };
+
+    let layout: Vec<&str> = ["stages", "jobs", "steps"]
+        .into_iter()
+        .filter(|key| pipeline.contains_key(*key))
+        .collect();
+    match layout.as_slice() {
+        // Pipelines extending a template get their layout from it
+        [] if pipeline.contains_key("extends") => {}
+        [] => result.add_issue_at(
+            "invalid-layout",
+            "",
+            "An Azure pipeline needs 'stages', 'jobs' or 'steps', or 'extends' a template"
+                .to_string(),
+        ),
+        ["stages"] => validate_stages(pipeline.get("stages"), &mut result),
+        ["jobs"] => validate_jobs(pipeline.get("jobs"), "jobs", PIPELINE, &mut result),
+        ["steps"] => validate_steps(pipeline.get("steps"), "steps", "The pipeline", &mut result),
+        _ => result.add_issue_at(
+            "invalid-layout",
+            layout[1],
+            format!(
+                "An Azure pipeline has only one of 'stages', 'jobs' and 'steps', not {}",
+                layout.join(" and ")
+            ),
+        ),
+    }
+    result
+}
+
+/// The items of a list, leaving out `${{ if ... }}` blocks whose contents
+/// depend on template expressions
+fn items(list: &Value) -> impl Iterator<Item = (usize, &Value)> {
+    list.as_sequence()
+        .into_iter()
+        .flatten()
+        .enumerate()
+        .filter(|(_, item)| {
+            !item.as_mapping().is_some_and(|map| {
+                map.keys()
+                    .any(|key| key.as_str().is_some_and(|key| key.starts_with("${{")))
+            })
+        })
+}
+
+/// Names in a `dependsOn`, which may be one name or a list
+fn depends_on(item: &Value) -> Vec<&str> {
+    match item.get("dependsOn") {
+        Some(Value::String(name)) => vec![name.as_str()],
+        Some(Value::Sequence(names)) => names.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Check that the names of a list of stages or jobs are unique and that
+/// their `dependsOn` name one of them
+fn check_names(
+    named: &[(usize, &str, &Value)],
+    path: &str,
+    context: &str,
+    kind: &str,
+    result: &mut ValidationResult,
+) {
+    let mut seen = HashSet::new();
+    for (i, name, _) in named {
+        if !seen.insert(*name) {
+            result.add_issue_at(
+                &format!("duplicate-{}", kind),
+                format!("{}[{}]", path, i),
+                format!("{}{} '{}' is defined more than once", context, kind, name),
+            );
+        }
+    }
+    for (i, name, item) in named {
+        for dependency in depends_on(item) {
+            if !seen.contains(dependency) {
+                result.add_issue_at(
+                    "unknown-depends-on",
+                    format!("{}[{}].dependsOn", path, i),
+                    format!(
+                        "{}{} '{}' depends on '{}', which isn't a {} here",
+                        context, kind, name, dependency, kind
+                    ),
+                );
+            }
+        }
+    }
+}
+
+fn validate_stages(stages: Option<&Value>, result: &mut ValidationResult) {
+    let mut named = Vec::new();
+    for (i, stage) in stages.into_iter().flat_map(items) {
+        if stage.get("template").is_some() {
+            continue;
+        }
+        let Some(name) = stage.get("stage").and_then(Value::as_str) else {
+            result.add_issue_at(
+                "missing-stage-name",
+                format!("stages[{}]", i),
+                format!("Stage {} needs a 'stage' name or a 'template'", i + 1),
+            );
+            continue;
+        };
+        named.push((i, name, stage));
+        validate_jobs(
+            stage.get("jobs"),
+            &format!("stages[{}].jobs", i),
+            &format!("Stage '{}': ", name),
+            result,
+        );
+    }
+    check_names(&named, "stages", PIPELINE, "stage", result);
+}
+
+/// `context` is prefixed to messages, e.g. `Stage 'build': `
+fn validate_jobs(jobs: Option<&Value>, path: &str, context: &str, result: &mut ValidationResult) {
+    let Some(jobs) = jobs.filter(|jobs| jobs.is_sequence()) else {
+        result.add_issue_at(
+            "missing-jobs",
+            path,
+            format!("{}'jobs' must be a list", context),
+        );
+        return;
+    };
+    let mut named = Vec::new();
+    for (i, job) in items(jobs) {
+        let job_path = format!("{}[{}]", path, i);
+        if job.get("template").is_some() {
+            continue;
+        }
+        if let Some(name) = job.get("deployment").and_then(Value::as_str) {
+            named.push((i, name, job));
+            if job.get("strategy").is_none() {
+                result.add_issue_at(
+                    "missing-strategy",
+                    &job_path,
+                    format!("{}deployment job '{}' needs a 'strategy'", context, name),
+                );
+            }
+            continue;
+        }
+        let Some(name) = job.get("job").and_then(Value::as_str) else {
+            result.add_issue_at(
+                "missing-job-name",
+                &job_path,
+                format!(
+                    "{}job {} needs a 'job' or 'deployment' name or a 'template'",
+                    context,
+                    i + 1
+                ),
+            );
+            continue;
+        };
+        named.push((i, name, job));
+        validate_steps(
+            job.get("steps"),
+            &format!("{}.steps", job_path),
+            &format!("{}job '{}'", context, name),
+            result,
+        );
+    }
+    check_names(&named, path, context, "job", result);
+}
+
+fn validate_steps(steps: Option<&Value>, path: &str, context: &str, result: &mut ValidationResult) {
+    let Some(steps) = steps.filter(|steps| steps.is_sequence()) else {
+        result.add_issue_at(
+            "missing-steps",
+            path,
+            format!("{} needs a list of 'steps'", context),
+        );
+        return;
+    };
+    for (i, step) in items(steps) {
+        let step_path = format!("{}[{}]", path, i);
+        let kinds: Vec<&str> = STEP_KINDS
+            .iter()
+            .copied()
+            .filter(|kind| step.get(kind).is_some())
+            .collect();
+        match kinds.as_slice() {
+            [] => result.add_issue_at(
+                "empty-step",
+                &step_path,
+                format!(
+                    "{}: step {} needs one of {}",
+                    context,
+                    i + 1,
+                    STEP_KINDS.join(", ")
+                ),
+            ),
+            [_] => {}
+            _ => result.add_issue_at(
+                "multiple-step-kinds",
+                &step_path,
+                format!(
+                    "{}: step {} has {}, but a step does only one thing",
+                    context,
+                    i + 1,
+                    kinds.join(" and ")
+                ),
+            ),
+        }
+        if let Some(task) = step.get("task").and_then(Value::as_str) {
+            if !TASK_REFERENCE.is_match(task) {
+                result.add_issue_at(
+                    "invalid-task-ref",
+                    format!("{}.task", step_path),
+                    format!(
+                        "{}: step {} runs task '{}', expected a name and major version like NodeTool@0",
+                        context,
+                        i + 1,
+                        task
+                    ),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_layout_references_and_tasks() {
+        let pipeline: Value = serde_yaml::from_str(
+            r#"
+trigger: [main]
+stages:
+  - stage: build
+    jobs:
+      - job: compile
+        steps:
+          - task: NodeTool@0
+          - script: npm ci
+            bash: npm ci
+          - ${{ if eq(variables.debug, true) }}:
+            - script: env
+      - job: compile
+        dependsOn: lint
+        steps:
+          - task: Npm
+          - displayName: nothing
+  - stage: deploy
+    dependsOn: [build, test]
+    jobs:
+      - deployment: web
+      - template: jobs/smoke.yml
+"#,
+        )
+        .unwrap();
+        let result = validate_azure_pipeline(&pipeline);
+        let codes: Vec<&str> = result
+            .diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.code.as_str())
+            .collect();
+        assert_eq!(
+            codes,
+            [
+                "multiple-step-kinds",
+                "invalid-task-ref",
+                "empty-step",
+                "duplicate-job",
+                "unknown-depends-on",
+                "missing-strategy",
+                "unknown-depends-on",
+            ]
+        );
+        assert_eq!(
+            result.issues[4],
+            "Stage 'build': job 'compile' depends on 'lint', which isn't a job here"
+        );
+
+        let both: Value = serde_yaml::from_str("jobs: []\nsteps: []\n").unwrap();
+        assert!(!validate_azure_pipeline(&both).is_valid);
+    }
+}
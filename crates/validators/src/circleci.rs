@@ -0,0 +1,434 @@
+// Structural checks of CircleCI configs (.circleci/config.yml): the version,
+// the executors, commands and orbs jobs refer to, and workflow job references
+use models::ValidationResult;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_yaml::{Mapping, Value};
+use std::collections::HashSet;
+
+/// Steps CircleCI provides itself
+const BUILTIN_STEPS: &[&str] = &[
+    "run",
+    "checkout",
+    "setup_remote_docker",
+    "save_cache",
+    "restore_cache",
+    "store_artifacts",
+    "store_test_results",
+    "persist_to_workspace",
+    "attach_workspace",
+    "add_ssh_keys",
+    "when",
+    "unless",
+];
+
+/// Keys giving a job the environment it runs in
+const EXECUTOR_KEYS: &[&str] = &["docker", "machine", "macos", "executor"];
+
+/// Keys only 2.1 configs may use
+const VERSION_2_1_KEYS: &[&str] = &["orbs", "commands", "executors", "parameters"];
+
+/// An orb like `circleci/node@5.1.0`
+static ORB_REFERENCE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^[a-z0-9][a-z0-9_-]*/[a-z0-9][a-z0-9_-]*@(\d+(\.\d+){0,2}|volatile|dev:\S+)$")
+        .unwrap()
+});
+
+/// The names a config defines, which its jobs and workflows refer to
+struct Definitions {
+    orbs: HashSet<String>,
+    commands: HashSet<String>,
+    executors: HashSet<String>,
+    jobs: HashSet<String>,
+}
+
+impl Definitions {
+    /// Whether `name` is defined in `own`, or is one of an imported orb's
+    /// like `node/test`
+    fn defines(&self, own: &HashSet<String>, name: &str) -> bool {
+        own.contains(name)
+            || name
+                .split_once('/')
+                .is_some_and(|(orb, _)| self.orbs.contains(orb))
+    }
+}
+
+/// Validate a CircleCI config
+pub fn validate_circleci_config(config: &Value) -> ValidationResult {
+    let mut result = ValidationResult::new();
+    let Some(config) = config.as_mapping() else {
+        result.add_issue_at(
+            "not-a-mapping",
+            "",
+            "A CircleCI config must be a mapping".to_string(),
+        );
+        return result;
+    };
+
+    let version = match config.get("version") {
+        Some(Value::String(version)) => version.clone(),
+        Some(Value::Number(version)) => version.to_string(),
+        _ => String::new(),
+    };
+    if !["2", "2.0", "2.1"].contains(&version.as_str()) {
+        result.add_issue_at(
+            "invalid-version",
+            "version",
+            "'version' must be 2, 2.0 or 2.1".to_string(),
+        );
+    }
+    if version != "2.1" {
+        for key in VERSION_2_1_KEYS
+            .iter()
+            .filter(|key| config.contains_key(**key))
+        {
+            result.add_issue_at(
+                "requires-version-2-1",
+                *key,
+                format!("'{}' needs 'version: 2.1'", key),
+            );
+        }
+    }
+
+    let names = |key: &str| -> HashSet<String> {
+        config
+            .get(key)
+            .and_then(Value::as_mapping)
+            .map(|map| {
+                map.keys()
+                    .filter_map(Value::as_str)
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let definitions = Definitions {
+        orbs: names("orbs"),
+        commands: names("commands"),
+        executors: names("executors"),
+        jobs: names("jobs"),
+    };
+
+    for (name, orb) in entries(config.get("orbs")) {
+        // Inline orbs are mappings
+        if let Value::String(reference) = orb {
+            if !ORB_REFERENCE.is_match(reference) {
+                result.add_issue_at(
+                    "invalid-orb-ref",
+                    format!("orbs.{}", name),
+                    format!(
+                        "Orb '{}' is '{}', expected one like circleci/node@5.1.0",
+                        name, reference
+                    ),
+                );
+            }
+        }
+    }
+    for (name, command) in entries(config.get("commands")) {
+        let context = format!("Command '{}'", name);
+        validate_steps(
+            command.get("steps"),
+            &format!("commands.{}.steps", name),
+            &context,
+            &definitions,
+            &mut result,
+        );
+    }
+
+    let jobs = entries(config.get("jobs"));
+    if jobs.is_empty() {
+        result.add_issue_at(
+            "empty-jobs",
+            "jobs",
+            "A CircleCI config needs at least one job".to_string(),
+        );
+    }
+    for (name, job) in jobs {
+        validate_job(&name, job, &definitions, &mut result);
+    }
+
+    for (name, workflow) in entries(config.get("workflows")) {
+        // 2.0 configs give the workflows' version next to them
+        if name != "version" {
+            validate_workflow(&name, workflow, &definitions, &mut result);
+        }
+    }
+    result
+}
+
+/// The string keys of a mapping and their values, empty for anything else
+fn entries(value: Option<&Value>) -> Vec<(String, &Value)> {
+    value
+        .and_then(Value::as_mapping)
+        .map(|map| {
+            map.iter()
+                .filter_map(|(key, value)| Some((key.as_str()?.to_string(), value)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The name an entry like `checkout` or `{ run: ... }` of a step or
+/// workflow job list refers to, and its settings
+fn single_key(item: &Value) -> Option<(&str, Option<&Value>)> {
+    match item {
+        Value::String(name) => Some((name, None)),
+        Value::Mapping(map) if map.len() == 1 => {
+            let (key, value) = map.iter().next()?;
+            Some((key.as_str()?, Some(value)))
+        }
+        _ => None,
+    }
+}
+
+fn validate_job(name: &str, job: &Value, definitions: &Definitions, result: &mut ValidationResult) {
+    let path = format!("jobs.{}", name);
+    let Some(job) = job.as_mapping() else {
+        result.add_issue_at(
+            "invalid-job",
+            &path,
+            format!("Job '{}' must be a mapping", name),
+        );
+        return;
+    };
+    if !EXECUTOR_KEYS.iter().any(|key| job.contains_key(*key)) {
+        result.add_issue_at(
+            "missing-executor",
+            &path,
+            format!(
+                "Job '{}' needs one of {} to say where it runs",
+                name,
+                EXECUTOR_KEYS.join(", ")
+            ),
+        );
+    }
+    let executor = match job.get("executor") {
+        Some(Value::String(executor)) => Some(executor.as_str()),
+        Some(Value::Mapping(executor)) => executor.get("name").and_then(Value::as_str),
+        _ => None,
+    };
+    if let Some(executor) = executor {
+        if !definitions.defines(&definitions.executors, executor) {
+            result.add_issue_at(
+                "unknown-executor",
+                format!("{}.executor", path),
+                format!(
+                    "Job '{}' uses executor '{}', which isn't defined in 'executors' or an imported orb",
+                    name, executor
+                ),
+            );
+        }
+    }
+    validate_steps(
+        job.get("steps"),
+        &format!("{}.steps", path),
+        &format!("Job '{}'", name),
+        definitions,
+        result,
+    );
+}
+
+/// Check that every step is a built-in step, a command or an orb's command
+fn validate_steps(
+    steps: Option<&Value>,
+    path: &str,
+    context: &str,
+    definitions: &Definitions,
+    result: &mut ValidationResult,
+) {
+    let Some(steps) = steps.and_then(Value::as_sequence) else {
+        result.add_issue_at(
+            "missing-steps",
+            path,
+            format!("{} needs a list of 'steps'", context),
+        );
+        return;
+    };
+    for (i, step) in steps.iter().enumerate() {
+        let step_path = format!("{}[{}]", path, i);
+        let Some((name, settings)) = single_key(step) else {
+            result.add_issue_at(
+                "invalid-step",
+                &step_path,
+                format!(
+                    "{}: step {} must be a name or a mapping with a single key",
+                    context,
+                    i + 1
+                ),
+            );
+            continue;
+        };
+        if !BUILTIN_STEPS.contains(&name) && !definitions.defines(&definitions.commands, name) {
+            result.add_issue_at(
+                "unknown-step",
+                &step_path,
+                format!(
+                    "{}: step {} uses '{}', which isn't a built-in step, a command or an orb's command",
+                    context,
+                    i + 1,
+                    name
+                ),
+            );
+        }
+        if name == "when" || name == "unless" {
+            validate_steps(
+                settings.and_then(|settings| settings.get("steps")),
+                &format!("{}.{}.steps", step_path, name),
+                context,
+                definitions,
+                result,
+            );
+        }
+    }
+}
+
+fn validate_workflow(
+    name: &str,
+    workflow: &Value,
+    definitions: &Definitions,
+    result: &mut ValidationResult,
+) {
+    let path = format!("workflows.{}.jobs", name);
+    let Some(jobs) = workflow.get("jobs").and_then(Value::as_sequence) else {
+        result.add_issue_at(
+            "missing-jobs",
+            &path,
+            format!("Workflow '{}' needs a list of 'jobs'", name),
+        );
+        return;
+    };
+
+    // Jobs may be listed under another `name:`, which `requires` then uses
+    let mut listed = Vec::new();
+    for (i, item) in jobs.iter().enumerate() {
+        let Some((job, settings)) = single_key(item) else {
+            result.add_issue_at(
+                "invalid-workflow-job",
+                format!("{}[{}]", path, i),
+                format!(
+                    "Workflow '{}': job {} must be a name or a mapping with a single key",
+                    name,
+                    i + 1
+                ),
+            );
+            continue;
+        };
+        let settings = settings.and_then(Value::as_mapping);
+        let listed_as = settings
+            .and_then(|settings| settings.get("name"))
+            .and_then(Value::as_str)
+            .unwrap_or(job);
+        listed.push((i, job, listed_as, settings));
+    }
+    let listed_names: HashSet<&str> = listed.iter().map(|(_, _, name, _)| *name).collect();
+
+    for (i, job, _, settings) in &listed {
+        let job_path = format!("{}[{}]", path, i);
+        if !definitions.defines(&definitions.jobs, job) {
+            result.add_issue_at(
+                "unknown-job",
+                &job_path,
+                format!(
+                    "Workflow '{}' runs job '{}', which isn't defined in 'jobs' or an imported orb",
+                    name, job
+                ),
+            );
+        }
+        for required in requires(*settings) {
+            // Matrix jobs get names from their parameters
+            if !listed_names.contains(required) && !required.contains("<<") {
+                result.add_issue_at(
+                    "unknown-requires",
+                    format!("{}.{}.requires", job_path, job),
+                    format!(
+                        "Workflow '{}': job '{}' requires '{}', which the workflow doesn't run",
+                        name, job, required
+                    ),
+                );
+            }
+        }
+    }
+}
+
+/// Jobs a workflow job waits for, given by name or as `{ name: status }`
+fn requires(settings: Option<&Mapping>) -> Vec<&str> {
+    settings
+        .and_then(|settings| settings.get("requires"))
+        .and_then(Value::as_sequence)
+        .map(|list| {
+            list.iter()
+                .filter_map(|item| single_key(item).map(|(name, _)| name))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_references_of_jobs_and_workflows() {
+        let config: Value = serde_yaml::from_str(
+            r#"
+version: 2.1
+orbs:
+  node: circleci/node@5.1.0
+  broken: node@latest
+executors:
+  default:
+    docker: [{ image: cimg/base:stable }]
+commands:
+  install:
+    steps: [checkout, { run: npm ci }]
+jobs:
+  test:
+    executor: default
+    steps:
+      - install
+      - node/install-packages
+      - when:
+          condition: true
+          steps: [deploy-things]
+  lint:
+    executor: missing
+    steps: [checkout]
+  bare:
+    steps: [checkout]
+workflows:
+  main:
+    jobs:
+      - test
+      - lint: { name: lint-all, requires: [test] }
+      - node/test: { requires: [lint] }
+      - package
+"#,
+        )
+        .unwrap();
+        let result = validate_circleci_config(&config);
+        let codes: Vec<&str> = result
+            .diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.code.as_str())
+            .collect();
+        assert_eq!(
+            codes,
+            [
+                "invalid-orb-ref",
+                "unknown-step",
+                "unknown-executor",
+                "missing-executor",
+                "unknown-requires",
+                "unknown-job",
+            ]
+        );
+        assert!(result.issues[1].contains("'deploy-things'"));
+
+        let old: Value = serde_yaml::from_str(
+            "version: 2\norbs: {}\njobs:\n  a:\n    docker: []\n    steps: []\n",
+        )
+        .unwrap();
+        let result = validate_circleci_config(&old);
+        assert_eq!(result.issues, ["'orbs' needs 'version: 2.1'"]);
+    }
+}
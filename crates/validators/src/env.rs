@@ -0,0 +1,68 @@
+use models::github::{is_default_variable, Workflow};
+use models::ValidationResult;
+
+/// Warn about `env:` variables named like the `GITHUB_*` and `RUNNER_*`
+/// variables runners set themselves, which keep the runner's values
+pub fn validate_env_names(workflow: &Workflow, result: &mut ValidationResult) {
+    let mut envs = vec![("env".to_string(), &workflow.env)];
+    for (job_name, job) in &workflow.jobs {
+        envs.push((format!("jobs.{}.env", job_name), &job.env));
+        for (i, step) in job.steps.iter().enumerate() {
+            envs.push((format!("jobs.{}.steps[{}].env", job_name, i), &step.env));
+        }
+    }
+
+    for (path, env) in envs {
+        for name in env.keys().filter(|name| is_default_variable(name)) {
+            result.add_warning_at(
+                "reserved-env-name",
+                format!("{}.{}", path, name),
+                format!(
+                    "'{}' is set by the runner and can't be overridden in 'env:'; use another name",
+                    name
+                ),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn warns_about_runner_variables() {
+        let workflow: Workflow = serde_yaml::from_str(
+            r#"
+on: push
+env:
+  GITHUB_SHA: abc
+  GITHUB_TOKEN: ${{ secrets.GITHUB_TOKEN }}
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    env:
+      runner_temp: /tmp/build
+    steps:
+      - run: make
+        env:
+          GITHUB_WORKSPACE_DIR: ok
+          GITHUB_REF: refs/heads/main
+"#,
+        )
+        .unwrap();
+
+        let mut result = ValidationResult::new();
+        validate_env_names(&workflow, &mut result);
+        let paths: Vec<String> = result.diagnostics.into_iter().map(|d| d.path).collect();
+        assert_eq!(
+            paths,
+            [
+                "env.GITHUB_SHA",
+                "jobs.build.env.runner_temp",
+                "jobs.build.steps[0].env.GITHUB_REF",
+            ]
+        );
+        assert!(result.is_valid);
+    }
+}
@@ -0,0 +1,246 @@
+use expressions::{CompareOp, Expr, ExpressionError, KNOWN_CONTEXTS};
+use models::ValidationResult;
+use serde_json::Value as JsonValue;
+use serde_yaml::Value;
+
+/// Check every `${{ }}` expression in a workflow's env, jobs and steps for
+/// syntax errors, unknown contexts and comparisons of strings with booleans.
+/// The syntax of job and step `if:` conditions is checked with the jobs, so
+/// only their comparisons are looked at here.
+pub fn validate_expressions(workflow: &Value, result: &mut ValidationResult) {
+    let Some(workflow) = workflow.as_mapping() else {
+        return;
+    };
+    for (key, value) in workflow {
+        // Triggers don't take expressions
+        if let Some(key @ ("env" | "jobs" | "defaults")) = key.as_str() {
+            lint_value(value, key, result);
+        }
+    }
+}
+
+fn lint_value(value: &Value, path: &str, result: &mut ValidationResult) {
+    match value {
+        Value::String(text) => {
+            let is_condition = path.ends_with(".if");
+            let mut sources = expressions::embedded_expressions(text);
+            // Conditions may leave out the `${{ }}`
+            if is_condition && sources.is_empty() {
+                sources.push(expressions::strip_delimiters(text));
+            }
+            for source in sources {
+                lint_expression(source, path, !is_condition, result);
+            }
+        }
+        Value::Sequence(items) => {
+            for (i, item) in items.iter().enumerate() {
+                lint_value(item, &format!("{}[{}]", path, i), result);
+            }
+        }
+        Value::Mapping(map) => {
+            for (key, item) in map {
+                if let Some(key) = key.as_str() {
+                    lint_value(item, &format!("{}.{}", path, key), result);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn lint_expression(source: &str, path: &str, check_syntax: bool, result: &mut ValidationResult) {
+    let location = describe(path);
+    if check_syntax {
+        match expressions::validate(source) {
+            Err(ExpressionError::UnknownContext(name)) => {
+                let suggestion = closest_context(&name)
+                    .map(|context| format!("; did you mean '{}'?", context))
+                    .unwrap_or_default();
+                result.add_issue_at(
+                    "unknown-context",
+                    path,
+                    format!(
+                        "{}: Unknown context '{}' in '{}'{}",
+                        location, name, source, suggestion
+                    ),
+                );
+                return;
+            }
+            Err(e) => {
+                result.add_issue_at(
+                    "invalid-expression",
+                    path,
+                    format!("{}: Invalid expression '{}': {}", location, source, e),
+                );
+                return;
+            }
+            Ok(()) => {}
+        }
+    }
+
+    let Ok(expr) = expressions::parse(source) else {
+        return;
+    };
+    expr.walk(&mut |node| {
+        let Expr::Compare(left, CompareOp::Eq | CompareOp::Ne, right) = node else {
+            return;
+        };
+        let (boolean, other) = match (left.as_ref(), right.as_ref()) {
+            (Expr::Literal(JsonValue::Bool(b)), other)
+            | (other, Expr::Literal(JsonValue::Bool(b))) => (*b, other),
+            _ => return,
+        };
+        let Some(string) = string_operand(other) else {
+            return;
+        };
+        result.add_warning_at(
+            "expression-type-mismatch",
+            path,
+            format!(
+                "{}: '{}' compares {}, a string, to the boolean {}; strings other than numbers are never equal to booleans, so compare to '{}' instead",
+                location, source, string, boolean, boolean
+            ),
+        );
+    });
+}
+
+/// What an operand is when it's always a string: a string literal, or a
+/// context value that only holds strings
+fn string_operand(expr: &Expr) -> Option<String> {
+    if let Expr::Literal(JsonValue::String(text)) = expr {
+        return Some(format!("'{}'", text));
+    }
+    let segments = property_path(expr)?;
+    let names: Vec<String> = segments.iter().map(|s| s.to_ascii_lowercase()).collect();
+    let is_string = match names.as_slice() {
+        [context, _, ..] if ["env", "vars", "secrets", "runner"].contains(&context.as_str()) => {
+            true
+        }
+        // Inputs of workflow_dispatch events are strings, unlike `inputs.*`
+        [github, event, inputs, _]
+            if github == "github" && event == "event" && inputs == "inputs" =>
+        {
+            true
+        }
+        [context, _, outputs, _] => {
+            (context == "steps" || context == "needs") && outputs == "outputs"
+        }
+        _ => false,
+    };
+    is_string.then(|| segments.join("."))
+}
+
+/// `a.b['c']` as `[a, b, c]`, for references without computed parts
+pub(crate) fn property_path(expr: &Expr) -> Option<Vec<String>> {
+    match expr {
+        Expr::Context(name) => Some(vec![name.clone()]),
+        Expr::Property(base, name) => {
+            let mut path = property_path(base)?;
+            path.push(name.clone());
+            Some(path)
+        }
+        Expr::Index(base, index) => match index.as_ref() {
+            Expr::Literal(JsonValue::String(name)) => {
+                let mut path = property_path(base)?;
+                path.push(name.clone());
+                Some(path)
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The known context a misspelled one was most likely meant to be
+fn closest_context(name: &str) -> Option<&'static str> {
+    let name = name.to_ascii_lowercase();
+    KNOWN_CONTEXTS
+        .iter()
+        .map(|context| (edit_distance(&name, context), *context))
+        .filter(|(distance, _)| *distance <= 2)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, context)| context)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// `Job 'build', step 2` for a path inside the second step of job `build`
+fn describe(path: &str) -> String {
+    let Some(rest) = path.strip_prefix("jobs.") else {
+        return "Workflow".to_string();
+    };
+    let job = rest.split('.').next().unwrap_or(rest);
+    let step = rest
+        .split_once(".steps[")
+        .and_then(|(_, after)| after.split(']').next())
+        .and_then(|index| index.parse::<usize>().ok());
+    match step {
+        Some(index) => format!("Job '{}', step {}", job, index + 1),
+        None => format!("Job '{}'", job),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lints_expressions_throughout_the_workflow() {
+        let workflow: Value = serde_yaml::from_str(
+            r#"
+on:
+  push:
+    branches: ["${{ not checked }}"]
+env:
+  TOKEN: ${{ secret.TOKEN }}
+jobs:
+  build:
+    if: github.event.inputs.deploy == true
+    runs-on: ubuntu-latest
+    steps:
+      - run: echo "${{ github.sha }} ${{ matrix.os == }}"
+      - uses: actions/cache@v4
+        with:
+          key: ${{ env.CACHE == false }}
+      - if: ${{ inputs.deploy == true && 'yes' != true }}
+        run: echo ${{ steps.build.outputs.ok == true }}
+"#,
+        )
+        .unwrap();
+        let mut result = ValidationResult::new();
+        validate_expressions(&workflow, &mut result);
+
+        let found: Vec<(&str, &str)> = result
+            .diagnostics
+            .iter()
+            .map(|d| (d.code.as_str(), d.path.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("unknown-context", "env.TOKEN"),
+                ("expression-type-mismatch", "jobs.build.if"),
+                ("invalid-expression", "jobs.build.steps[0].run"),
+                ("expression-type-mismatch", "jobs.build.steps[1].with.key"),
+                ("expression-type-mismatch", "jobs.build.steps[2].if"),
+                ("expression-type-mismatch", "jobs.build.steps[2].run"),
+            ]
+        );
+        assert!(result.issues[0].ends_with("did you mean 'secrets'?"));
+        assert!(result.issues[0].starts_with("Workflow: Unknown context 'secret'"));
+        assert!(result.issues[1].starts_with("Job 'build', step 1: Invalid expression"));
+        assert!(result.warnings[2].contains("compares 'yes', a string, to the boolean true"));
+    }
+}
@@ -1,6 +1,52 @@
-use models::gitlab::{Job, Pipeline};
+use models::gitlab::{Job, Parallel, Pipeline};
 use models::ValidationResult;
-use std::collections::HashMap;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// `$NAME` and `${NAME}` references; `$$` is an escaped dollar sign
+static VARIABLE_REFERENCE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\$(\$|\{([A-Za-z_][A-Za-z0-9_]*)\}|[A-Za-z_][A-Za-z0-9_]*)").unwrap()
+});
+
+/// Variables a script sets itself, e.g. `NAME=...`, `export NAME`,
+/// `for NAME in` or `read NAME`
+static SHELL_ASSIGNMENT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?:^|[\s;&|(])(?:(?:export|local|readonly|declare(?:\s+-\w+)*)\s+)?([A-Za-z_][A-Za-z0-9_]*)=|\b(?:export|for|read(?:\s+-\w+)*)\s+([A-Za-z_][A-Za-z0-9_]*)",
+    )
+    .unwrap()
+});
+
+/// Prefixes of the variables GitLab and its runners predefine
+const PREDEFINED_PREFIXES: &[&str] = &["CI_", "GITLAB_", "RUNNER_", "FF_"];
+
+/// Predefined variables without one of the prefixes above, and variables
+/// every shell has
+const PREDEFINED_VARIABLES: &[&str] = &[
+    "CI",
+    "CHAT_CHANNEL",
+    "CHAT_INPUT",
+    "CHAT_USER_ID",
+    "TRIGGER_PAYLOAD",
+    "KUBECONFIG",
+    "HOME",
+    "PATH",
+    "PWD",
+    "OLDPWD",
+    "USER",
+    "SHELL",
+    "HOSTNAME",
+    "TERM",
+    "LANG",
+    "TMPDIR",
+    "RANDOM",
+    "SECONDS",
+    "LINENO",
+    "IFS",
+    "UID",
+    "REPLY",
+];
 
 /// Validate a GitLab CI/CD pipeline
 pub fn validate_gitlab_pipeline(pipeline: &Pipeline) -> ValidationResult {
@@ -8,7 +54,11 @@ pub fn validate_gitlab_pipeline(pipeline: &Pipeline) -> ValidationResult {
 
     // Basic structure validation
     if pipeline.jobs.is_empty() {
-        result.add_issue("Pipeline must contain at least one job".to_string());
+        result.add_issue_at(
+            "no-jobs",
+            "",
+            "Pipeline must contain at least one job".to_string(),
+        );
     }
 
     // Validate jobs
@@ -28,6 +78,9 @@ pub fn validate_gitlab_pipeline(pipeline: &Pipeline) -> ValidationResult {
     // Validate artifacts
     validate_artifacts(&pipeline.jobs, &mut result);
 
+    // Check variable references
+    validate_variable_references(pipeline, &mut result);
+
     result
 }
 
@@ -41,10 +94,14 @@ fn validate_jobs(jobs: &HashMap<String, Job>, result: &mut ValidationResult) {
 
         // Check for script or extends
         if job.script.is_none() && job.extends.is_none() {
-            result.add_issue(format!(
-                "Job '{}' must have a script section or extend another job",
-                job_name
-            ));
+            result.add_issue_at(
+                "missing-script",
+                job_name,
+                format!(
+                    "Job '{}' must have a script section or extend another job",
+                    job_name
+                ),
+            );
         }
 
         // Check when value if present
@@ -54,10 +111,14 @@ fn validate_jobs(jobs: &HashMap<String, Job>, result: &mut ValidationResult) {
                     // Valid when value
                 }
                 _ => {
-                    result.add_issue(format!(
-                        "Job '{}' has invalid 'when' value: '{}'. Valid values are: on_success, on_failure, always, manual, never",
-                        job_name, when
-                    ));
+                    result.add_issue_at(
+                        "invalid-when",
+                        format!("{}.when", job_name),
+                        format!(
+                            "Job '{}' has invalid 'when' value: '{}'. Valid values are: on_success, on_failure, always, manual, never",
+                            job_name, when
+                        ),
+                    );
                 }
             }
         }
@@ -67,18 +128,26 @@ fn validate_jobs(jobs: &HashMap<String, Job>, result: &mut ValidationResult) {
             match retry {
                 models::gitlab::Retry::MaxAttempts(attempts) => {
                     if *attempts > 10 {
-                        result.add_issue(format!(
-                            "Job '{}' has excessive retry count: {}. Consider reducing to avoid resource waste",
-                            job_name, attempts
-                        ));
+                        result.add_issue_at(
+                            "excessive-retry",
+                            format!("{}.retry", job_name),
+                            format!(
+                                "Job '{}' has excessive retry count: {}. Consider reducing to avoid resource waste",
+                                job_name, attempts
+                            ),
+                        );
                     }
                 }
                 models::gitlab::Retry::Detailed { max, when: _ } => {
                     if *max > 10 {
-                        result.add_issue(format!(
-                            "Job '{}' has excessive retry count: {}. Consider reducing to avoid resource waste",
-                            job_name, max
-                        ));
+                        result.add_issue_at(
+                            "excessive-retry",
+                            format!("{}.retry", job_name),
+                            format!(
+                                "Job '{}' has excessive retry count: {}. Consider reducing to avoid resource waste",
+                                job_name, max
+                            ),
+                        );
                     }
                 }
             }
@@ -92,18 +161,22 @@ fn validate_stages(stages: &[String], jobs: &HashMap<String, Job>, result: &mut
     for (job_name, job) in jobs {
         if let Some(stage) = &job.stage {
             if !stages.contains(stage) {
-                result.add_issue(format!(
-                    "Job '{}' references undefined stage '{}'. Available stages are: {}",
-                    job_name,
-                    stage,
-                    stages.join(", ")
-                ));
+                result.add_issue_at(
+                    "undefined-stage",
+                    format!("{}.stage", job_name),
+                    format!(
+                        "Job '{}' references undefined stage '{}'. Available stages are: {}",
+                        job_name,
+                        stage,
+                        stages.join(", ")
+                    ),
+                );
             }
         }
     }
 
     // Check for unused stages
-    for stage in stages {
+    for (i, stage) in stages.iter().enumerate() {
         let used = jobs.values().any(|job| {
             if let Some(job_stage) = &job.stage {
                 job_stage == stage
@@ -113,10 +186,11 @@ fn validate_stages(stages: &[String], jobs: &HashMap<String, Job>, result: &mut
         });
 
         if !used {
-            result.add_issue(format!(
-                "Stage '{}' is defined but not used by any job",
-                stage
-            ));
+            result.add_issue_at(
+                "unused-stage",
+                format!("stages[{}]", i),
+                format!("Stage '{}' is defined but not used by any job", stage),
+            );
         }
     }
 }
@@ -125,14 +199,23 @@ fn validate_stages(stages: &[String], jobs: &HashMap<String, Job>, result: &mut
 fn validate_dependencies(jobs: &HashMap<String, Job>, result: &mut ValidationResult) {
     for (job_name, job) in jobs {
         if let Some(dependencies) = &job.dependencies {
-            for dependency in dependencies {
+            for (i, dependency) in dependencies.iter().enumerate() {
+                let path = format!("{}.dependencies[{}]", job_name, i);
                 if !jobs.contains_key(dependency) {
-                    result.add_issue(format!(
-                        "Job '{}' depends on undefined job '{}'",
-                        job_name, dependency
-                    ));
+                    result.add_issue_at(
+                        "undefined-dependency",
+                        path,
+                        format!(
+                            "Job '{}' depends on undefined job '{}'",
+                            job_name, dependency
+                        ),
+                    );
                 } else if job_name == dependency {
-                    result.add_issue(format!("Job '{}' cannot depend on itself", job_name));
+                    result.add_issue_at(
+                        "self-dependency",
+                        path,
+                        format!("Job '{}' cannot depend on itself", job_name),
+                    );
                 }
             }
         }
@@ -147,10 +230,11 @@ fn validate_extends(jobs: &HashMap<String, Job>, result: &mut ValidationResult)
             // Check that all extended jobs exist
             for extend in extends {
                 if !jobs.contains_key(extend) {
-                    result.add_issue(format!(
-                        "Job '{}' extends undefined job '{}'",
-                        job_name, extend
-                    ));
+                    result.add_issue_at(
+                        "undefined-extends",
+                        format!("{}.extends", job_name),
+                        format!("Job '{}' extends undefined job '{}'", job_name, extend),
+                    );
                     continue;
                 }
 
@@ -184,7 +268,11 @@ fn check_circular_extends(
                         .collect::<Vec<_>>()
                         .join(" -> ");
 
-                    result.add_issue(format!("Circular extends detected: {}", cycle));
+                    result.add_issue_at(
+                        "circular-extends",
+                        format!("{}.extends", job_name),
+                        format!("Circular extends detected: {}", cycle),
+                    );
                     return;
                 }
 
@@ -203,16 +291,21 @@ fn validate_artifacts(jobs: &HashMap<String, Job>, result: &mut ValidationResult
             // Check that paths are specified
             if let Some(paths) = &artifacts.paths {
                 if paths.is_empty() {
-                    result.add_issue(format!(
-                        "Job '{}' has artifacts section with empty paths",
-                        job_name
-                    ));
+                    result.add_issue_at(
+                        "empty-artifact-paths",
+                        format!("{}.artifacts.paths", job_name),
+                        format!("Job '{}' has artifacts section with empty paths", job_name),
+                    );
                 }
             } else {
-                result.add_issue(format!(
-                    "Job '{}' has artifacts section without specifying paths",
-                    job_name
-                ));
+                result.add_issue_at(
+                    "missing-artifact-paths",
+                    format!("{}.artifacts", job_name),
+                    format!(
+                        "Job '{}' has artifacts section without specifying paths",
+                        job_name
+                    ),
+                );
             }
 
             // Check for valid 'when' value if present
@@ -222,13 +315,205 @@ fn validate_artifacts(jobs: &HashMap<String, Job>, result: &mut ValidationResult
                         // Valid when value
                     }
                     _ => {
-                        result.add_issue(format!(
-                            "Job '{}' has artifacts with invalid 'when' value: '{}'. Valid values are: on_success, on_failure, always",
-                            job_name, when
-                        ));
+                        result.add_issue_at(
+                            "invalid-artifact-when",
+                            format!("{}.artifacts.when", job_name),
+                            format!(
+                                "Job '{}' has artifacts with invalid 'when' value: '{}'. Valid values are: on_success, on_failure, always",
+                                job_name, when
+                            ),
+                        );
                     }
                 }
             }
         }
     }
 }
+
+/// Warn about `$VARIABLE` references in scripts, rules and images that no
+/// `variables:` block, predefined variable or the script itself sets
+fn validate_variable_references(pipeline: &Pipeline, result: &mut ValidationResult) {
+    let global: HashSet<&str> = pipeline
+        .variables
+        .iter()
+        .flatten()
+        .map(|(name, _)| name.as_str())
+        .collect();
+    let defaults = pipeline.default.as_ref();
+
+    let mut job_names: Vec<&String> = pipeline.jobs.keys().collect();
+    job_names.sort();
+    for job_name in job_names {
+        let job = &pipeline.jobs[job_name];
+        if job_name.starts_with('.') || job.template == Some(true) {
+            continue;
+        }
+
+        let mut defined = global.clone();
+        defined.extend(
+            job.variables
+                .iter()
+                .flatten()
+                .map(|(name, _)| name.as_str()),
+        );
+        for rule in job.rules.iter().flatten() {
+            defined.extend(
+                rule.variables
+                    .iter()
+                    .flatten()
+                    .map(|(name, _)| name.as_str()),
+            );
+        }
+        if let Some(Parallel::Matrix { matrix }) = &job.parallel {
+            defined.extend(
+                matrix
+                    .iter()
+                    .flat_map(|entry| entry.keys().map(String::as_str)),
+            );
+        }
+
+        let before_script = job
+            .before_script
+            .as_ref()
+            .or(defaults.and_then(|d| d.before_script.as_ref()))
+            .or(pipeline.before_script.as_ref());
+        let after_script = job
+            .after_script
+            .as_ref()
+            .or(defaults.and_then(|d| d.after_script.as_ref()))
+            .or(pipeline.after_script.as_ref());
+        let scripts: Vec<&String> = before_script
+            .into_iter()
+            .chain(&job.script)
+            .chain(after_script)
+            .flatten()
+            .collect();
+        for line in &scripts {
+            for captures in SHELL_ASSIGNMENT.captures_iter(line) {
+                if let Some(name) = captures.get(1).or_else(|| captures.get(2)) {
+                    defined.insert(name.as_str());
+                }
+            }
+        }
+
+        let image = job
+            .image
+            .as_ref()
+            .or(defaults.and_then(|d| d.image.as_ref()))
+            .or(pipeline.image.as_ref());
+        let mut sources: Vec<(&str, &str)> = scripts
+            .iter()
+            .map(|line| ("script", line.as_str()))
+            .collect();
+        sources.extend(
+            job.rules
+                .iter()
+                .flatten()
+                .filter_map(|rule| rule.if_.as_deref())
+                .map(|condition| ("rules", condition)),
+        );
+        sources.extend(image.map(|image| ("image", image.name())));
+
+        // One warning per variable and place
+        let mut undefined: BTreeSet<(&str, &str)> = BTreeSet::new();
+        for (place, text) in sources {
+            for captures in VARIABLE_REFERENCE.captures_iter(text) {
+                let Some(name) = captures.get(2).or_else(|| captures.get(1)) else {
+                    continue;
+                };
+                let name = name.as_str();
+                if name != "$" && !defined.contains(name) && !is_predefined(name) {
+                    undefined.insert((place, name));
+                }
+            }
+        }
+        for (place, name) in undefined {
+            result.add_warning_at(
+                "undefined-variable",
+                format!("{}.{}", job_name, place),
+                format!(
+                    "Job '{}' {} references undefined variable '${}', which will be empty unless it is set as a CI/CD variable of the project",
+                    job_name, place, name
+                ),
+            );
+        }
+    }
+}
+
+fn is_predefined(name: &str) -> bool {
+    PREDEFINED_VARIABLES.contains(&name)
+        || PREDEFINED_PREFIXES
+            .iter()
+            .any(|prefix| name.starts_with(prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn warns_about_undefined_variables() {
+        let pipeline: Pipeline = serde_yaml::from_str(
+            r#"
+variables:
+  REGISTRY: registry.example.com
+  RETRIES: 3
+  DEPLOY_ENV:
+    value: staging
+    description: Where to deploy
+image: $REGISTRY/builder:$BUILDER_TAG
+build:
+  script:
+    - export OUT=dist
+    - for file in src/*; do echo "$file"; done
+    - make OUT=$OUT RETRIES=${RETRIES} TARGET=${TARGET}
+    - echo "$$NOT_A_REFERENCE $CI_COMMIT_SHA $HOME"
+  rules:
+    - if: $CI_COMMIT_BRANCH == "main" && $RELEASE == "true"
+      variables:
+        TARGET: release
+deploy:
+  image: alpine
+  parallel:
+    matrix:
+      - REGION: [eu, us]
+  script:
+    - ./deploy.sh $REGION $DEPLOY_ENV $API_TOKEN
+"#,
+        )
+        .unwrap();
+        assert_eq!(pipeline.variables.as_ref().unwrap()["RETRIES"], "3",);
+
+        let mut result = ValidationResult::new();
+        validate_variable_references(&pipeline, &mut result);
+        let mut warnings: Vec<String> = result
+            .warnings
+            .iter()
+            .map(|w| w.split(", which").next().unwrap().to_string())
+            .collect();
+        warnings.sort();
+        assert_eq!(
+            warnings,
+            [
+                "Job 'build' image references undefined variable '$BUILDER_TAG'",
+                "Job 'build' rules references undefined variable '$RELEASE'",
+                "Job 'deploy' script references undefined variable '$API_TOKEN'",
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_variable_values() {
+        let error = serde_yaml::from_str::<Pipeline>(
+            "variables:\n  LIST: [a, b]\nbuild:\n  script: [make]\n",
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("variable 'LIST'"));
+
+        let error = serde_yaml::from_str::<Pipeline>(
+            "variables:\n  MODE:\n    default: fast\nbuild:\n  script: [make]\n",
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("unknown key 'default'"));
+    }
+}
@@ -0,0 +1,208 @@
+use models::ValidationResult;
+use serde_yaml::Value;
+use std::path::{Path, PathBuf};
+
+/// Warn about `hashFiles()` calls whose patterns match no file under
+/// `repo_root`. They hash to an empty string, so cache keys built from them
+/// never change and the cache silently stops tracking the files.
+pub fn validate_hash_files(workflow: &Value, repo_root: &Path, result: &mut ValidationResult) {
+    let Some(Value::Mapping(jobs)) = workflow.get("jobs") else {
+        return;
+    };
+
+    for (job_name, job) in jobs {
+        let Some(job_name) = job_name.as_str() else {
+            continue;
+        };
+
+        let mut job_calls = Vec::new();
+        for (key, value) in job.as_mapping().into_iter().flatten() {
+            if key.as_str() != Some("steps") {
+                collect_calls(value, key.as_str() == Some("if"), &mut job_calls);
+            }
+        }
+        for patterns in job_calls {
+            check_patterns(
+                &patterns,
+                &format!("Job '{}'", job_name),
+                &format!("jobs.{}", job_name),
+                false,
+                repo_root,
+                result,
+            );
+        }
+
+        let Some(Value::Sequence(steps)) = job.get("steps") else {
+            continue;
+        };
+        for (i, step) in steps.iter().enumerate() {
+            let Some(step_map) = step.as_mapping() else {
+                continue;
+            };
+            let is_cache = step
+                .get("uses")
+                .and_then(|uses| uses.as_str())
+                .is_some_and(|uses| uses.starts_with("actions/cache"));
+
+            let mut step_calls = Vec::new();
+            for (key, value) in step_map {
+                collect_calls(value, key.as_str() == Some("if"), &mut step_calls);
+            }
+            let location = format!("Job '{}', step {}", job_name, i + 1);
+            let path = format!("jobs.{}.steps[{}]", job_name, i);
+            for patterns in step_calls {
+                check_patterns(&patterns, &location, &path, is_cache, repo_root, result);
+            }
+        }
+    }
+}
+
+/// Gather the literal patterns of every `hashFiles()` call in a value.
+/// `if:` conditions may leave out the `${{ }}`.
+fn collect_calls(value: &Value, is_condition: bool, calls: &mut Vec<Vec<String>>) {
+    match value {
+        Value::String(text) => {
+            let mut sources = expressions::embedded_expressions(text);
+            if is_condition && sources.is_empty() {
+                sources.push(text);
+            }
+            for source in sources {
+                let Ok(expr) = expressions::parse(source) else {
+                    continue;
+                };
+                expr.walk(&mut |node| {
+                    let expressions::Expr::Call(name, args) = node else {
+                        return;
+                    };
+                    if !name.eq_ignore_ascii_case("hashFiles") {
+                        return;
+                    }
+                    // Patterns built at runtime can't be checked ahead of time
+                    let patterns: Option<Vec<String>> = args
+                        .iter()
+                        .map(|arg| match arg {
+                            expressions::Expr::Literal(serde_json::Value::String(p)) => {
+                                Some(p.clone())
+                            }
+                            _ => None,
+                        })
+                        .collect();
+                    calls.extend(patterns);
+                });
+            }
+        }
+        Value::Sequence(items) => {
+            for item in items {
+                collect_calls(item, false, calls);
+            }
+        }
+        Value::Mapping(map) => {
+            for item in map.values() {
+                collect_calls(item, false, calls);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn check_patterns(
+    patterns: &[String],
+    location: &str,
+    path: &str,
+    is_cache: bool,
+    repo_root: &Path,
+    result: &mut ValidationResult,
+) {
+    if !matched_files(patterns, repo_root).is_empty() {
+        return;
+    }
+
+    let quoted: Vec<String> = patterns.iter().map(|p| format!("'{}'", p)).collect();
+    result.add_warning_at(
+        "hash-files-no-match",
+        path,
+        format!(
+            "{}: hashFiles({}) matches no files, so it hashes to an empty string{}",
+            location,
+            quoted.join(", "),
+            if is_cache {
+                " and the cache key never changes"
+            } else {
+                ""
+            }
+        ),
+    );
+}
+
+/// Files matched by a list of `hashFiles()` patterns. Patterns starting
+/// with `!` exclude files matched by the others.
+fn matched_files(patterns: &[String], repo_root: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut excludes = Vec::new();
+
+    for pattern in patterns {
+        if let Some(exclude) = pattern.strip_prefix('!') {
+            excludes.extend(glob::Pattern::new(exclude.trim_start_matches("./")));
+            continue;
+        }
+
+        let full = repo_root.join(pattern.trim_start_matches("./"));
+        let Ok(paths) = glob::glob(&full.to_string_lossy()) else {
+            continue;
+        };
+        files.extend(paths.flatten().filter(|path| path.is_file()));
+    }
+
+    files.retain(|path| {
+        let relative = path.strip_prefix(repo_root).unwrap_or(path);
+        !excludes
+            .iter()
+            .any(|exclude| exclude.matches_path(relative))
+    });
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn warnings_for(workflow: &str, repo_root: &Path) -> Vec<String> {
+        let workflow: Value = serde_yaml::from_str(workflow).unwrap();
+        let mut result = ValidationResult::new();
+        validate_hash_files(&workflow, repo_root, &mut result);
+        assert!(result.is_valid);
+        result.warnings
+    }
+
+    #[test]
+    fn warns_about_patterns_matching_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("crates/app")).unwrap();
+        fs::write(dir.path().join("crates/app/Cargo.lock"), "").unwrap();
+
+        let warnings = warnings_for(
+            r#"
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/cache@v4
+        with:
+          path: target
+          key: ${{ runner.os }}-${{ hashFiles('**/Cargo.lock') }}
+          restore-keys: ${{ runner.os }}-${{ hashFiles('**/package-lock.json') }}
+      - if: hashFiles('**/Cargo.lock', '!crates/**') != ''
+        run: cargo build
+      - run: echo "${{ hashFiles(format('{0}.txt', matrix.name)) }}"
+"#,
+            dir.path(),
+        );
+
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].starts_with("Job 'build', step 1: hashFiles('**/package-lock.json')"));
+        assert!(warnings[0].ends_with("and the cache key never changes"));
+        assert!(warnings[1]
+            .starts_with("Job 'build', step 2: hashFiles('**/Cargo.lock', '!crates/**')"));
+    }
+}
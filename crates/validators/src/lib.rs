@@ -1,15 +1,37 @@
 // validators crate
 
+mod action_metadata;
 mod actions;
+mod azure;
+mod circleci;
+mod env;
+mod expression_lint;
 mod gitlab;
+mod hash_files;
 mod jobs;
 mod matrix;
+mod needs;
+mod runner_labels;
+mod security;
+mod shells;
 mod steps;
 mod triggers;
+mod working_directories;
 
-pub use actions::validate_action_reference;
+pub use action_metadata::validate_action_metadata;
+pub use actions::{validate_action_reference, validate_action_reference_online};
+pub use azure::validate_azure_pipeline;
+pub use circleci::validate_circleci_config;
+pub use env::validate_env_names;
+pub use expression_lint::validate_expressions;
 pub use gitlab::validate_gitlab_pipeline;
+pub use hash_files::validate_hash_files;
 pub use jobs::validate_jobs;
 pub use matrix::validate_matrix;
+pub use needs::validate_needs_references;
+pub use runner_labels::validate_runner_labels;
+pub use security::{audit_workflow, SecurityFinding, SecurityReport, Severity};
+pub use shells::validate_shells;
 pub use steps::validate_steps;
 pub use triggers::validate_triggers;
+pub use working_directories::validate_working_directories;
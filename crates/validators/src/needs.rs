@@ -0,0 +1,170 @@
+use crate::expression_lint::property_path;
+use expressions::Expr;
+use models::github::Workflow;
+use models::ValidationResult;
+use serde_yaml::Value;
+use std::collections::BTreeSet;
+
+/// Report `needs.<job>` references in a job's expressions to jobs it
+/// doesn't need, which are always empty, and `needs.<job>.outputs.<name>`
+/// references to outputs the needed job doesn't define
+pub fn validate_needs_references(workflow: &Workflow, result: &mut ValidationResult) {
+    for (job_name, job) in &workflow.jobs {
+        let Ok(value) = serde_yaml::to_value(job) else {
+            continue;
+        };
+        let mut references = Vec::new();
+        collect_references(&value, &format!("jobs.{}", job_name), &mut references);
+
+        let needs = job.needs();
+        let mut reported = BTreeSet::new();
+        for (path, reference) in references {
+            let Some(need) = needs
+                .iter()
+                .find(|need| need.eq_ignore_ascii_case(&reference[1]))
+            else {
+                if reported.insert((path.clone(), reference[1].clone())) {
+                    result.add_issue_at(
+                        "undefined-needs-job",
+                        &path,
+                        format!(
+                            "Job '{}': 'needs.{}' refers to a job that isn't in its 'needs', so it is always empty",
+                            job_name, reference[1]
+                        ),
+                    );
+                }
+                continue;
+            };
+
+            let Some(needed) = workflow.jobs.get(need) else {
+                continue;
+            };
+            // Reusable workflows declare their outputs in the called file
+            let Some(output) = reference
+                .get(3)
+                .filter(|_| needed.uses.is_none() && reference[2].eq_ignore_ascii_case("outputs"))
+            else {
+                continue;
+            };
+            if !needed
+                .outputs
+                .keys()
+                .any(|name| name.eq_ignore_ascii_case(output))
+                && reported.insert((path.clone(), format!("{}.{}", need, output)))
+            {
+                result.add_issue_at(
+                    "undefined-job-output",
+                    &path,
+                    format!(
+                        "Job '{}': 'needs.{}.outputs.{}' refers to an output job '{}' doesn't define",
+                        job_name, need, output, need
+                    ),
+                );
+            }
+        }
+    }
+}
+
+/// The `needs.*` references of every expression under `value`, each as
+/// its path segments with where it was found
+fn collect_references(value: &Value, path: &str, references: &mut Vec<(String, Vec<String>)>) {
+    match value {
+        Value::String(text) => {
+            let mut sources = expressions::embedded_expressions(text);
+            // Conditions may leave out the `${{ }}`
+            if path.ends_with(".if") && sources.is_empty() {
+                sources.push(expressions::strip_delimiters(text));
+            }
+            for source in sources {
+                let Ok(expr) = expressions::parse(source) else {
+                    continue;
+                };
+                let mut found: Vec<Vec<String>> = Vec::new();
+                expr.walk(&mut |node| {
+                    if !matches!(node, Expr::Property(..) | Expr::Index(..)) {
+                        return;
+                    }
+                    let Some(segments) = property_path(node) else {
+                        return;
+                    };
+                    if segments.len() < 2 || !segments[0].eq_ignore_ascii_case("needs") {
+                        return;
+                    }
+                    // Keep only the longest reference, not each of its prefixes
+                    if !found.iter().any(|longer| longer.starts_with(&segments)) {
+                        found.push(segments);
+                    }
+                });
+                references.extend(
+                    found
+                        .into_iter()
+                        .map(|segments| (path.to_string(), segments)),
+                );
+            }
+        }
+        Value::Sequence(items) => {
+            for (i, item) in items.iter().enumerate() {
+                collect_references(item, &format!("{}[{}]", path, i), references);
+            }
+        }
+        Value::Mapping(map) => {
+            for (key, item) in map {
+                if let Some(key) = key.as_str() {
+                    collect_references(item, &format!("{}.{}", path, key), references);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_references_to_jobs_and_outputs_that_dont_exist() {
+        let workflow: Workflow = serde_yaml::from_str(
+            r#"
+on: push
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    outputs:
+      version: ${{ steps.v.outputs.version }}
+    steps:
+      - run: echo
+  lint:
+    runs-on: ubuntu-latest
+    steps:
+      - run: echo
+  deploy:
+    needs: build
+    if: needs.build.result == 'success' && needs.lint.result == 'success'
+    runs-on: ubuntu-latest
+    env:
+      VERSION: ${{ needs.build.outputs.version }}
+    steps:
+      - run: echo ${{ needs.build.outputs.versoin }} ${{ needs.build.outputs['version'] }}
+"#,
+        )
+        .unwrap();
+
+        let mut result = ValidationResult::new();
+        validate_needs_references(&workflow, &mut result);
+        let found: Vec<(&str, &str)> = result
+            .diagnostics
+            .iter()
+            .map(|d| (d.code.as_str(), d.path.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("undefined-needs-job", "jobs.deploy.if"),
+                ("undefined-job-output", "jobs.deploy.steps[0].run"),
+            ]
+        );
+        assert!(result.issues[0].starts_with("Job 'deploy': 'needs.lint' refers to a job"));
+        assert!(result.issues[1].contains("'needs.build.outputs.versoin'"));
+    }
+}
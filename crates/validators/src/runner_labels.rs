@@ -0,0 +1,128 @@
+use models::ValidationResult;
+use serde_yaml::Value;
+use std::collections::BTreeSet;
+
+/// Labels of GitHub-hosted runners
+const HOSTED_LABELS: &[&str] = &[
+    "ubuntu-latest",
+    "ubuntu-24.04",
+    "ubuntu-22.04",
+    "ubuntu-20.04",
+    "ubuntu-24.04-arm",
+    "ubuntu-22.04-arm",
+    "windows-latest",
+    "windows-2025",
+    "windows-2022",
+    "windows-2019",
+    "windows-11-arm",
+    "macos-latest",
+    "macos-15",
+    "macos-14",
+    "macos-13",
+    "macos-latest-large",
+    "macos-15-large",
+    "macos-14-large",
+    "macos-13-large",
+    "macos-latest-xlarge",
+    "macos-15-xlarge",
+    "macos-14-xlarge",
+    "macos-13-xlarge",
+];
+
+/// Labels every self-hosted runner gets
+const SELF_HOSTED_LABELS: &[&str] = &[
+    "self-hosted",
+    "linux",
+    "windows",
+    "macos",
+    "x64",
+    "arm",
+    "arm64",
+];
+
+/// Warn about `runs-on` labels that are neither GitHub-hosted nor default
+/// self-hosted ones, unless they are in `mapped`, the labels a project gives
+/// an image or the host to run on. Labels are compared ignoring case.
+pub fn validate_runner_labels(
+    workflow: &Value,
+    mapped: &BTreeSet<String>,
+    result: &mut ValidationResult,
+) {
+    let Some(Value::Mapping(jobs)) = workflow.get("jobs") else {
+        return;
+    };
+    let known = |label: &str| {
+        HOSTED_LABELS
+            .iter()
+            .chain(SELF_HOSTED_LABELS)
+            .any(|known| known.eq_ignore_ascii_case(label))
+            || mapped.iter().any(|known| known.eq_ignore_ascii_case(label))
+    };
+
+    for (job_name, job) in jobs {
+        let Some(job_name) = job_name.as_str() else {
+            continue;
+        };
+        // A `group:` form picks runners by group, with optional labels
+        let labels = match job.get("runs-on") {
+            Some(Value::Mapping(runs_on)) => runs_on.get("labels"),
+            runs_on => runs_on,
+        };
+        let labels: Vec<&str> = match labels {
+            Some(Value::String(label)) => vec![label.as_str()],
+            Some(Value::Sequence(labels)) => labels.iter().filter_map(Value::as_str).collect(),
+            _ => continue,
+        };
+        for label in labels {
+            let label = label.trim();
+            if label.contains("${{") || known(label) {
+                continue;
+            }
+            result.add_warning_at(
+                "unknown-runner-label",
+                format!("jobs.{}.runs-on", job_name),
+                format!(
+                    "Job '{}' runs on unknown label '{}'. Map it to an image or to emulation under [images] in .wrkflw.toml to pick where it runs locally",
+                    job_name, label
+                ),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn warns_about_unmapped_custom_labels() {
+        let workflow: Value = serde_yaml::from_str(
+            r#"
+jobs:
+  build:
+    runs-on: [self-hosted, Linux, x64, gpu]
+  test:
+    runs-on: ${{ matrix.os }}
+  deploy:
+    runs-on:
+      group: deployers
+      labels: deploy-box
+  docs:
+    runs-on: macos-14
+"#,
+        )
+        .unwrap();
+
+        let mut result = ValidationResult::new();
+        validate_runner_labels(&workflow, &BTreeSet::new(), &mut result);
+        assert!(result.is_valid);
+        assert_eq!(result.warnings.len(), 2);
+        assert!(result.warnings[0].starts_with("Job 'build' runs on unknown label 'gpu'"));
+        assert!(result.warnings[1].starts_with("Job 'deploy' runs on unknown label 'deploy-box'"));
+
+        let mapped = BTreeSet::from(["GPU".to_string(), "deploy-box".to_string()]);
+        let mut result = ValidationResult::new();
+        validate_runner_labels(&workflow, &mapped, &mut result);
+        assert!(result.warnings.is_empty());
+    }
+}
@@ -0,0 +1,550 @@
+use serde::Serialize;
+use serde_yaml::Value;
+
+/// Expression contexts an outside contributor controls. Interpolating them
+/// into a script lets them inject commands.
+const UNTRUSTED_CONTEXTS: &[&str] = &[
+    "github.head_ref",
+    "github.event.issue.title",
+    "github.event.issue.body",
+    "github.event.pull_request.title",
+    "github.event.pull_request.body",
+    "github.event.pull_request.head.ref",
+    "github.event.pull_request.head.label",
+    "github.event.pull_request.head.repo.default_branch",
+    "github.event.comment.body",
+    "github.event.review.body",
+    "github.event.review_comment.body",
+    "github.event.discussion.title",
+    "github.event.discussion.body",
+    "github.event.pages",
+    "github.event.commits",
+    "github.event.head_commit.message",
+    "github.event.head_commit.author.email",
+    "github.event.head_commit.author.name",
+    "github.event.workflow_run.head_branch",
+    "github.event.workflow_run.head_commit.message",
+];
+
+/// Fields of any event payload object that the person who opened an issue,
+/// pull request, comment or commit writes, e.g. `github.event.issue.title`
+const UNTRUSTED_FIELDS: &[&str] = &[".title", ".body", ".message", ".head_ref", ".label"];
+
+/// Owners whose actions are maintained by GitHub itself
+const GITHUB_OWNED: &[&str] = &["actions/", "github/"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        }
+    }
+
+    /// Points a finding of this severity takes off the score
+    fn penalty(self) -> u32 {
+        match self {
+            Severity::Low => 1,
+            Severity::Medium => 4,
+            Severity::High => 10,
+            Severity::Critical => 25,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SecurityFinding {
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub file: String,
+    /// Where in the file, e.g. `Job 'build', step 2`
+    pub location: String,
+    pub message: String,
+    pub remediation: &'static str,
+}
+
+/// Security findings of one or more workflows with a score out of 100
+#[derive(Debug, Clone, Serialize)]
+pub struct SecurityReport {
+    pub score: u32,
+    pub files: Vec<String>,
+    pub findings: Vec<SecurityFinding>,
+}
+
+impl SecurityReport {
+    pub fn new(files: Vec<String>, mut findings: Vec<SecurityFinding>) -> Self {
+        findings.sort_by(|a, b| {
+            b.severity
+                .cmp(&a.severity)
+                .then_with(|| a.file.cmp(&b.file))
+        });
+        let penalty: u32 = findings.iter().map(|f| f.severity.penalty()).sum();
+        SecurityReport {
+            score: 100u32.saturating_sub(penalty),
+            files,
+            findings,
+        }
+    }
+
+    pub fn count(&self, severity: Severity) -> usize {
+        self.findings
+            .iter()
+            .filter(|f| f.severity == severity)
+            .count()
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut md = String::from("# Workflow security report\n\n");
+        md.push_str(&format!("**Score: {}/100**\n\n", self.score));
+        md.push_str(&format!(
+            "Audited {} file(s): {} critical, {} high, {} medium, {} low finding(s).\n",
+            self.files.len(),
+            self.count(Severity::Critical),
+            self.count(Severity::High),
+            self.count(Severity::Medium),
+            self.count(Severity::Low)
+        ));
+
+        if self.findings.is_empty() {
+            md.push_str("\nNo findings.\n");
+            return md;
+        }
+
+        md.push_str("\n| Severity | Rule | File | Location | Finding | Remediation |\n");
+        md.push_str("|---|---|---|---|---|---|\n");
+        for finding in &self.findings {
+            md.push_str(&format!(
+                "| {} | `{}` | {} | {} | {} | {} |\n",
+                finding.severity.as_str(),
+                finding.rule,
+                finding.file,
+                finding.location,
+                finding.message.replace('|', "\\|"),
+                finding.remediation
+            ));
+        }
+        md
+    }
+}
+
+/// Check a GitHub Actions workflow for unpinned actions, missing or broad
+/// permissions, `pull_request_target` misuse, exposed secrets and script
+/// injection
+pub fn audit_workflow(workflow: &Value, file: &str) -> Vec<SecurityFinding> {
+    let mut findings = Vec::new();
+    let mut add = |rule, severity, location: &str, message: String, remediation| {
+        findings.push(SecurityFinding {
+            rule,
+            severity,
+            file: file.to_string(),
+            location: location.to_string(),
+            message,
+            remediation,
+        });
+    };
+
+    let on_pull_request_target = match workflow.get("on") {
+        Some(Value::String(event)) => event == "pull_request_target",
+        Some(Value::Sequence(events)) => events.iter().any(|e| e == "pull_request_target"),
+        Some(Value::Mapping(events)) => events.contains_key("pull_request_target"),
+        _ => false,
+    };
+
+    let top_permissions = workflow.get("permissions");
+    if let Some(level) = write_all(top_permissions) {
+        add(
+            "excessive-permissions",
+            Severity::High,
+            "Workflow",
+            format!("Workflow grants `permissions: {}`", level),
+            "List only the scopes the jobs need, e.g. `permissions: contents: read`",
+        );
+    } else if on_pull_request_target {
+        check_fork_write_scopes(top_permissions, "Workflow", &mut add);
+    }
+
+    let Some(Value::Mapping(jobs)) = workflow.get("jobs") else {
+        return findings;
+    };
+
+    let mut jobs_without_permissions = Vec::new();
+    let mut runs_untrusted_code = false;
+    for (job_name, job) in jobs {
+        let job_name = job_name.as_str().unwrap_or_default();
+        let job_location = format!("Job '{}'", job_name);
+
+        match job.get("permissions") {
+            None => jobs_without_permissions.push(job_name),
+            permissions => {
+                if let Some(level) = write_all(permissions) {
+                    add(
+                        "excessive-permissions",
+                        Severity::High,
+                        &job_location,
+                        format!("Job grants `permissions: {}`", level),
+                        "List only the scopes the job needs",
+                    );
+                } else if on_pull_request_target {
+                    check_fork_write_scopes(permissions, &job_location, &mut add);
+                }
+            }
+        }
+
+        if let Some(Value::String(uses)) = job.get("uses") {
+            check_pinned(uses, &job_location, &mut add);
+            if on_pull_request_target && job.get("secrets").is_some() {
+                add(
+                    "secrets-to-forks",
+                    Severity::High,
+                    &job_location,
+                    "Passes secrets to a reusable workflow that runs for pull requests from forks".to_string(),
+                    "Pass only the secrets the called workflow needs, and make sure it doesn't run the pull request's code",
+                );
+            }
+        }
+
+        let Some(Value::Sequence(steps)) = job.get("steps") else {
+            continue;
+        };
+        let mut checked_out_head = false;
+        for (idx, step) in steps.iter().enumerate() {
+            let location = format!("Job '{}', step {}", job_name, idx + 1);
+            let uses = step.get("uses").and_then(Value::as_str);
+
+            if let Some(uses) = uses {
+                check_pinned(uses, &location, &mut add);
+            }
+
+            let checks_out_head = uses.is_some_and(|u| u.starts_with("actions/checkout"))
+                && step
+                    .get("with")
+                    .and_then(|with| with.get("ref"))
+                    .and_then(Value::as_str)
+                    .is_some_and(|r| {
+                        r.contains("github.event.pull_request.head")
+                            || r.contains("github.head_ref")
+                    });
+            if on_pull_request_target && checked_out_head && uses_secrets(step) {
+                add(
+                    "secrets-to-forks",
+                    Severity::Critical,
+                    &location,
+                    "Gives secrets to a step after the pull request's code was checked out, so a fork can read them".to_string(),
+                    "Don't use secrets in jobs that check out code from forks; move those steps to a separate job",
+                );
+            }
+            if on_pull_request_target && checks_out_head {
+                runs_untrusted_code = true;
+                checked_out_head = true;
+                add(
+                    "pull-request-target",
+                    Severity::Critical,
+                    &location,
+                    "Checks out the pull request's code in a `pull_request_target` workflow, which runs it with secrets and a write token".to_string(),
+                    "Use `pull_request` for building untrusted code, or split privileged steps into a `workflow_run` workflow",
+                );
+            }
+
+            // Inline scripts, including the script input of github-script
+            let script = step.get("run").and_then(Value::as_str).or_else(|| {
+                uses.filter(|u| u.starts_with("actions/github-script"))
+                    .and(step.get("with").and_then(|w| w.get("script")))
+                    .and_then(Value::as_str)
+            });
+            let Some(script) = script else {
+                continue;
+            };
+
+            for expression in expressions::embedded_expressions(script) {
+                if let Some(context) = untrusted_context(expression) {
+                    add(
+                        "script-injection",
+                        Severity::High,
+                        &location,
+                        format!("Script interpolates untrusted `{}`", context),
+                        "Pass the value through an `env:` variable and quote it in the script",
+                    );
+                }
+                if expression.contains("toJSON(secrets)") {
+                    add(
+                        "secrets-exposure",
+                        Severity::Critical,
+                        &location,
+                        "Script receives every secret through `toJSON(secrets)`".to_string(),
+                        "Pass only the secrets the step needs, through `env:`",
+                    );
+                } else if expression.contains("secrets.") {
+                    let echoed = script.lines().any(|line| {
+                        let line = line.trim_start();
+                        (line.starts_with("echo") || line.starts_with("printf"))
+                            && line.contains(expression)
+                    });
+                    if echoed {
+                        add(
+                            "secrets-exposure",
+                            Severity::High,
+                            &location,
+                            format!("Script prints `{}`", expression),
+                            "Don't print secrets; logs can be read by anyone with access to the run",
+                        );
+                    } else {
+                        add(
+                            "secrets-exposure",
+                            Severity::Medium,
+                            &location,
+                            format!("Script interpolates `{}` into its source", expression),
+                            "Pass secrets through `env:` so they never become part of the script",
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    if top_permissions.is_none() && !jobs_without_permissions.is_empty() {
+        jobs_without_permissions.sort();
+        add(
+            "missing-permissions",
+            Severity::Medium,
+            "Workflow",
+            format!(
+                "No `permissions` set for job(s) {}, so they get the repository's default token scopes",
+                jobs_without_permissions.join(", ")
+            ),
+            "Add a top-level `permissions:` block, e.g. `permissions: contents: read`",
+        );
+    }
+
+    if on_pull_request_target && !runs_untrusted_code {
+        add(
+            "pull-request-target",
+            Severity::Low,
+            "Workflow",
+            "Runs on `pull_request_target`, with secrets and a write token for pull requests from forks".to_string(),
+            "Make sure no step runs code from the pull request, or use `pull_request` instead",
+        );
+    }
+
+    findings
+}
+
+/// The untrusted value an expression interpolates: one of the known ones, or
+/// a title, body or message field of any event payload object
+fn untrusted_context(expression: &str) -> Option<String> {
+    if let Some(context) = UNTRUSTED_CONTEXTS
+        .iter()
+        .find(|context| expression.contains(*context))
+    {
+        return Some(context.to_string());
+    }
+    expression
+        .split(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-' | '*')))
+        .find(|reference| {
+            reference.starts_with("github.event.")
+                && UNTRUSTED_FIELDS
+                    .iter()
+                    .any(|field| reference.ends_with(field))
+        })
+        .map(str::to_string)
+}
+
+/// Whether a step's inputs, environment or script reference secrets other
+/// than the workflow's own token
+fn uses_secrets(step: &Value) -> bool {
+    let text = serde_yaml::to_string(step).unwrap_or_default();
+    expressions::embedded_expressions(&text)
+        .iter()
+        .any(|expression| {
+            expression.contains("secrets.") && !expression.contains("secrets.GITHUB_TOKEN")
+                || expression.contains("toJSON(secrets)")
+        })
+}
+
+/// `write` scopes in a `pull_request_target` workflow, whose token pull
+/// requests from forks get
+fn check_fork_write_scopes(
+    permissions: Option<&Value>,
+    location: &str,
+    add: &mut impl FnMut(&'static str, Severity, &str, String, &'static str),
+) {
+    let Some(Value::Mapping(scopes)) = permissions else {
+        return;
+    };
+    let mut writable: Vec<&str> = scopes
+        .iter()
+        .filter(|(_, level)| level.as_str() == Some("write"))
+        .filter_map(|(scope, _)| scope.as_str())
+        .collect();
+    if writable.is_empty() {
+        return;
+    }
+    writable.sort_unstable();
+    add(
+        "excessive-permissions",
+        Severity::Medium,
+        location,
+        format!(
+            "Grants write access to {} in a workflow that runs for pull requests from forks",
+            writable.join(", ")
+        ),
+        "Keep the token of `pull_request_target` workflows read-only, and do writes in a separate `workflow_run` workflow",
+    );
+}
+
+fn write_all(permissions: Option<&Value>) -> Option<&str> {
+    permissions
+        .and_then(Value::as_str)
+        .filter(|level| *level == "write-all")
+}
+
+fn check_pinned(
+    uses: &str,
+    location: &str,
+    add: &mut impl FnMut(&'static str, Severity, &str, String, &'static str),
+) {
+    if uses.starts_with("./") {
+        return;
+    }
+
+    if let Some(image) = uses.strip_prefix("docker://") {
+        if !image.contains("@sha256:") {
+            add(
+                "unpinned-action",
+                Severity::Medium,
+                location,
+                format!("Docker image `{}` isn't pinned to a digest", image),
+                "Pin the image by digest, e.g. `docker://image@sha256:...`",
+            );
+        }
+        return;
+    }
+
+    let pinned = uses.rsplit_once('@').is_some_and(|(_, git_ref)| {
+        git_ref.len() == 40 && git_ref.chars().all(|c| c.is_ascii_hexdigit())
+    });
+    if pinned {
+        return;
+    }
+
+    let github_owned = GITHUB_OWNED.iter().any(|owner| uses.starts_with(owner));
+    add(
+        "unpinned-action",
+        if github_owned {
+            Severity::Low
+        } else {
+            Severity::Medium
+        },
+        location,
+        format!("`{}` isn't pinned to a commit SHA", uses),
+        "Pin the action to a full commit SHA and note the version in a comment",
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scores_security_findings() {
+        let workflow: Value = serde_yaml::from_str(
+            r#"
+on: pull_request_target
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@v4
+        with:
+          ref: ${{ github.event.pull_request.head.sha }}
+      - uses: some/action@11bd71901bbe5b1630ceea73d27597364c9af683
+      - run: |
+          echo "${{ github.event.pull_request.title }}"
+          echo ${{ secrets.TOKEN }}
+  release:
+    runs-on: ubuntu-latest
+    permissions: write-all
+    steps:
+      - uses: third/party@main
+"#,
+        )
+        .unwrap();
+
+        let findings = audit_workflow(&workflow, "ci.yml");
+        let rules: Vec<(&str, Severity)> = findings.iter().map(|f| (f.rule, f.severity)).collect();
+        assert_eq!(
+            rules,
+            [
+                ("unpinned-action", Severity::Low),
+                ("pull-request-target", Severity::Critical),
+                ("secrets-to-forks", Severity::Critical),
+                ("script-injection", Severity::High),
+                ("secrets-exposure", Severity::High),
+                ("excessive-permissions", Severity::High),
+                ("unpinned-action", Severity::Medium),
+                ("missing-permissions", Severity::Medium),
+            ]
+        );
+        assert!(findings[7].message.contains("build"));
+        assert!(!findings[7].message.contains("release"));
+
+        let report = SecurityReport::new(vec!["ci.yml".to_string()], findings);
+        assert_eq!(report.score, 100 - (1 + 25 + 25 + 10 + 10 + 10 + 4 + 4));
+        assert_eq!(report.findings[0].severity, Severity::Critical);
+        assert!(report.to_markdown().contains("**Score: 11/100**"));
+    }
+
+    #[test]
+    fn flags_what_forks_can_reach() {
+        let workflow: Value = serde_yaml::from_str(
+            r#"
+on:
+  pull_request_target:
+permissions:
+  contents: read
+  pull-requests: write
+jobs:
+  label:
+    uses: org/repo/.github/workflows/label.yml@11bd71901bbe5b1630ceea73d27597364c9af683
+    secrets: inherit
+  greet:
+    runs-on: ubuntu-latest
+    steps:
+      - run: echo "${{ github.event.discussion_comment.title }}"
+      - run: echo hi
+        env:
+          TOKEN: ${{ secrets.GITHUB_TOKEN }}
+"#,
+        )
+        .unwrap();
+
+        let findings = audit_workflow(&workflow, "ci.yml");
+        let rules: Vec<(&str, &str)> = findings
+            .iter()
+            .map(|f| (f.rule, f.location.as_str()))
+            .collect();
+        assert_eq!(
+            rules,
+            [
+                ("excessive-permissions", "Workflow"),
+                ("secrets-to-forks", "Job 'label'"),
+                ("script-injection", "Job 'greet', step 1"),
+                ("pull-request-target", "Workflow"),
+            ]
+        );
+        assert!(findings[0].message.contains("pull-requests"));
+        assert!(findings[2]
+            .message
+            .contains("github.event.discussion_comment.title"));
+    }
+}
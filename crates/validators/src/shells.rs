@@ -0,0 +1,82 @@
+use models::github::{Defaults, Workflow};
+use models::ValidationResult;
+
+/// Shells GitHub runners know by name
+pub(crate) const BUILT_IN_SHELLS: &[&str] = &["bash", "sh", "pwsh", "python", "cmd", "powershell"];
+
+/// Report `shell:` settings of `run` steps and `defaults.run` that are
+/// neither a built-in shell nor a custom command taking the script as `{0}`
+pub fn validate_shells(workflow: &Workflow, result: &mut ValidationResult) {
+    let defaults_shell =
+        |defaults: &Option<Defaults>| defaults.as_ref()?.run.as_ref()?.shell.clone();
+
+    let mut shells = Vec::new();
+    if let Some(shell) = defaults_shell(&workflow.defaults) {
+        shells.push(("defaults.run.shell".to_string(), shell));
+    }
+    for (job_name, job) in &workflow.jobs {
+        if let Some(shell) = defaults_shell(&job.defaults) {
+            shells.push((format!("jobs.{}.defaults.run.shell", job_name), shell));
+        }
+        for (i, step) in job.steps.iter().enumerate() {
+            if let Some(shell) = &step.shell {
+                shells.push((
+                    format!("jobs.{}.steps[{}].shell", job_name, i),
+                    shell.clone(),
+                ));
+            }
+        }
+    }
+
+    for (path, shell) in shells {
+        let shell = shell.trim();
+        if BUILT_IN_SHELLS.contains(&shell) || shell.contains("{0}") {
+            continue;
+        }
+        result.add_issue_at(
+            "unknown-shell",
+            &path,
+            format!(
+                "Unknown shell '{}'; use one of {}, or a command with {{0}} where the script's path goes",
+                shell,
+                BUILT_IN_SHELLS.join(", ")
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_unknown_shells() {
+        let workflow: Workflow = serde_yaml::from_str(
+            r#"
+on: push
+defaults:
+  run:
+    shell: zsh
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    defaults:
+      run:
+        shell: bash
+    steps:
+      - run: echo hi
+        shell: perl {0}
+      - run: print('hi')
+        shell: python3
+"#,
+        )
+        .unwrap();
+
+        let mut result = ValidationResult::new();
+        validate_shells(&workflow, &mut result);
+        assert!(!result.is_valid);
+        assert_eq!(result.issues.len(), 2);
+        assert!(result.issues[0].starts_with("Unknown shell 'zsh'"));
+        assert!(result.issues[1].starts_with("Unknown shell 'python3'"));
+    }
+}
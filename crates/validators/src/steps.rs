@@ -1,42 +1,56 @@
 use crate::validate_action_reference;
+use models::github::Step;
 use models::ValidationResult;
-use serde_yaml::Value;
+use std::path::Path;
 
-pub fn validate_steps(steps: &[Value], job_name: &str, result: &mut ValidationResult) {
+pub fn validate_steps(steps: &[Step], job_name: &str, root: &Path, result: &mut ValidationResult) {
     for (i, step) in steps.iter().enumerate() {
-        if let Some(step_map) = step.as_mapping() {
-            if !step_map.contains_key(Value::String("name".to_string()))
-                && !step_map.contains_key(Value::String("uses".to_string()))
-                && !step_map.contains_key(Value::String("run".to_string()))
-            {
-                result.add_issue(format!(
+        let path = format!("jobs.{}.steps[{}]", job_name, i);
+        if step.name.is_none() && step.uses.is_none() && step.run.is_none() {
+            result.add_issue_at(
+                "empty-step",
+                &path,
+                format!(
                     "Job '{}', step {}: Missing 'name', 'uses', or 'run' field",
                     job_name,
                     i + 1
-                ));
-            }
+                ),
+            );
+        }
 
-            // Check for both 'uses' and 'run' in the same step
-            if step_map.contains_key(Value::String("uses".to_string()))
-                && step_map.contains_key(Value::String("run".to_string()))
-            {
-                result.add_issue(format!(
+        // Check for both 'uses' and 'run' in the same step
+        if step.uses.is_some() && step.run.is_some() {
+            result.add_issue_at(
+                "uses-and-run",
+                &path,
+                format!(
                     "Job '{}', step {}: Contains both 'uses' and 'run' (should only use one)",
                     job_name,
                     i + 1
-                ));
-            }
+                ),
+            );
+        }
+
+        // Validate action reference if 'uses' is present
+        if let Some(uses) = &step.uses {
+            validate_action_reference(uses, job_name, i, root, result);
+        }
 
-            // Validate action reference if 'uses' is present
-            if let Some(Value::String(uses)) = step_map.get(Value::String("uses".to_string())) {
-                validate_action_reference(uses, job_name, i, result);
+        // Validate the 'if' expression so typos surface before running
+        if let Some(condition) = &step.if_condition {
+            if let Err(e) = expressions::validate(condition) {
+                result.add_issue_at(
+                    "invalid-expression",
+                    format!("{}.if", path),
+                    format!(
+                        "Job '{}', step {}: Invalid 'if' expression '{}': {}",
+                        job_name,
+                        i + 1,
+                        condition,
+                        e
+                    ),
+                );
             }
-        } else {
-            result.add_issue(format!(
-                "Job '{}', step {}: Not a valid mapping",
-                job_name,
-                i + 1
-            ));
         }
     }
 }
@@ -0,0 +1,95 @@
+use models::github::{Defaults, Workflow};
+use models::ValidationResult;
+use std::path::{Component, Path};
+
+/// Report `working-directory` settings of `run` steps and `defaults.run`
+/// that lead out of the workspace, like `../other-repo`
+pub fn validate_working_directories(workflow: &Workflow, result: &mut ValidationResult) {
+    let defaults_dir =
+        |defaults: &Option<Defaults>| defaults.as_ref()?.run.as_ref()?.working_directory.clone();
+
+    let mut dirs = Vec::new();
+    if let Some(dir) = defaults_dir(&workflow.defaults) {
+        dirs.push(("defaults.run.working-directory".to_string(), dir));
+    }
+    for (job_name, job) in &workflow.jobs {
+        if let Some(dir) = defaults_dir(&job.defaults) {
+            dirs.push((
+                format!("jobs.{}.defaults.run.working-directory", job_name),
+                dir,
+            ));
+        }
+        for (i, step) in job.steps.iter().enumerate() {
+            if let Some(dir) = &step.working_directory {
+                dirs.push((
+                    format!("jobs.{}.steps[{}].working-directory", job_name, i),
+                    dir.clone(),
+                ));
+            }
+        }
+    }
+
+    for (path, dir) in dirs {
+        if dir.contains("${{") || !leaves_workspace(&dir) {
+            continue;
+        }
+        result.add_issue_at(
+            "working-directory-outside-workspace",
+            &path,
+            format!(
+                "Working directory '{}' is outside the workspace; relative directories are resolved against GITHUB_WORKSPACE",
+                dir
+            ),
+        );
+    }
+}
+
+/// Whether a relative directory goes up further than it went down
+fn leaves_workspace(dir: &str) -> bool {
+    let mut depth = 0usize;
+    for component in Path::new(dir).components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::ParentDir if depth == 0 => return true,
+            Component::ParentDir => depth -= 1,
+            Component::RootDir | Component::Prefix(_) => return false,
+            Component::CurDir => {}
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_directories_outside_the_workspace() {
+        let workflow: Workflow = serde_yaml::from_str(
+            r#"
+on: push
+defaults:
+  run:
+    working-directory: ./packages/app
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    defaults:
+      run:
+        working-directory: packages/../..
+    steps:
+      - run: make
+        working-directory: /opt/build
+      - run: make
+        working-directory: packages/app/../../../other
+"#,
+        )
+        .unwrap();
+
+        let mut result = ValidationResult::new();
+        validate_working_directories(&workflow, &mut result);
+        assert_eq!(result.issues.len(), 2);
+        assert!(result.issues[0].starts_with("Working directory 'packages/../..'"));
+        assert!(result.issues[1].starts_with("Working directory 'packages/app/../../../other'"));
+    }
+}
@@ -0,0 +1,89 @@
+// `wrkflw actions`: fetching the actions of workflows ahead of running them
+use clap::Subcommand;
+use clap_complete::engine::ArgValueCompleter;
+use std::path::PathBuf;
+
+#[derive(Debug, Subcommand)]
+pub enum ActionsCommand {
+    /// Fetch the actions workflows use into the cache, so running them
+    /// needs no network
+    Pull {
+        /// Path to a workflow file or directory (defaults to .github/workflows)
+        #[arg(add = ArgValueCompleter::new(crate::completions::workflow_files))]
+        path: Option<PathBuf>,
+
+        /// Fetch tags and branches again, in case they moved
+        #[arg(long)]
+        update: bool,
+    },
+}
+
+/// Handle `wrkflw actions` subcommands
+pub fn handle(command: &ActionsCommand) -> Result<(), String> {
+    use executor::actions::{self, ActionRef};
+
+    let ActionsCommand::Pull { path, update } = command;
+    let path = path
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(".github/workflows"));
+    if !path.exists() {
+        return Err(format!("Path does not exist: {}", path.display()));
+    }
+    let mut files = if path.is_dir() {
+        crate::workflow_files(&path)?
+    } else {
+        vec![path]
+    };
+    files.retain(|file| !crate::is_gitlab_pipeline(file));
+    files.sort();
+
+    let mut uses: Vec<String> = Vec::new();
+    for file in &files {
+        let workflow = parser::workflow::parse_workflow(file)
+            .map_err(|e| format!("Failed to parse {}: {}", file.display(), e))?;
+        for step in workflow.jobs.values().flat_map(|job| &job.steps) {
+            if let Some(action) = &step.uses {
+                if ActionRef::parse(action).is_some()
+                    && !actions::is_built_in(action)
+                    && !uses.contains(action)
+                {
+                    uses.push(action.clone());
+                }
+            }
+        }
+    }
+    uses.sort();
+    if uses.is_empty() {
+        println!("No actions from GitHub repositories are used");
+        return Ok(());
+    }
+
+    let (base_url, root) = (actions::actions_base_url(), actions::actions_root());
+    let mut failed = 0;
+    for action in &uses {
+        let action_ref = ActionRef::parse(action).expect("parsed above");
+        let cached = actions::is_fetched(&action_ref, &root);
+        let fetched = if *update {
+            actions::update_action(&action_ref, &base_url, &root)
+        } else {
+            actions::fetch_action(&action_ref, &base_url, &root)
+        };
+        match fetched {
+            Ok(_) if cached && !*update => println!("✅ {} (cached)", action),
+            Ok(_) => println!("✅ {}", action),
+            Err(e) => {
+                println!("❌ {}: {}", action, e);
+                failed += 1;
+            }
+        }
+    }
+    println!("Actions are cached in {}", root.display());
+    match failed {
+        0 => Ok(()),
+        failed => Err(format!(
+            "{} of {} actions couldn't be fetched",
+            failed,
+            uses.len()
+        )),
+    }
+}
@@ -0,0 +1,82 @@
+// `wrkflw artifacts`: the artifacts runs uploaded to the local store
+use clap::Subcommand;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Subcommand)]
+pub enum ArtifactsCommand {
+    /// List artifacts stored by a run (defaults to the most recent run)
+    List {
+        /// Run id to inspect
+        #[arg(long)]
+        run: Option<String>,
+
+        /// List all runs that have stored artifacts
+        #[arg(long)]
+        all_runs: bool,
+    },
+
+    /// Extract an artifact from a run into a directory
+    Extract {
+        /// Name of the artifact to extract
+        name: String,
+
+        /// Directory to extract into
+        #[arg(short, long, default_value = ".")]
+        output: PathBuf,
+
+        /// Run id to extract from (defaults to the most recent run)
+        #[arg(long)]
+        run: Option<String>,
+    },
+}
+
+/// Handle `wrkflw artifacts` subcommands
+pub fn handle(command: &ArtifactsCommand, root: &Path) -> Result<(), String> {
+    use executor::artifacts::{self, ArtifactStore};
+
+    match command {
+        ArtifactsCommand::List { run: _, all_runs } if *all_runs => {
+            let runs = artifacts::list_runs(root)?;
+            if runs.is_empty() {
+                println!("No artifact runs found in {}", root.display());
+                return Ok(());
+            }
+
+            println!("Artifact runs in {}:", root.display());
+            for run in runs {
+                println!("  - {} {} {}", run.run_id, run.started_at, run.workflow);
+            }
+        }
+        ArtifactsCommand::List { run, .. } => {
+            let store = ArtifactStore::open_run(root, run.as_deref())?;
+            let artifacts = store.list()?;
+
+            println!("Artifacts for run {}:", store.run_id());
+            if artifacts.is_empty() {
+                println!("  No artifacts");
+            }
+            for artifact in artifacts {
+                println!(
+                    "  - {} ({} file(s), {})",
+                    artifact.name,
+                    artifact.files,
+                    super::format_size(artifact.size)
+                );
+            }
+        }
+        ArtifactsCommand::Extract { name, output, run } => {
+            let store = ArtifactStore::open_run(root, run.as_deref())?;
+            let extracted = store.download(Some(name), None, false, output)?;
+            for artifact in extracted {
+                println!(
+                    "✅ Extracted '{}' ({} file(s)) to {}",
+                    artifact.name,
+                    artifact.files,
+                    output.display()
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,66 @@
+// `wrkflw cache`: the Docker or Podman volumes tool caches are kept in
+use bollard::Docker;
+use clap::Subcommand;
+
+#[derive(Debug, Subcommand)]
+pub enum CacheCommand {
+    /// List the cache volumes with the workflow and job they belong to
+    #[command(visible_alias = "list")]
+    Ls,
+
+    /// Remove cache volumes, all of them unless narrowed down
+    Clear {
+        /// Only the volumes of this workflow, by its name
+        #[arg(long)]
+        workflow: Option<String>,
+
+        /// Only the volumes of this job
+        #[arg(long)]
+        job: Option<String>,
+    },
+}
+
+/// Handle `wrkflw cache` subcommands
+pub async fn handle(
+    command: &CacheCommand,
+    runtime_type: &executor::RuntimeType,
+) -> Result<(), String> {
+    use executor::tool_cache;
+
+    let docker = match runtime_type {
+        executor::RuntimeType::Podman => executor::podman::connect().await?,
+        executor::RuntimeType::Docker => Docker::connect_with_local_defaults()
+            .map_err(|e| format!("Failed to connect to Docker: {}", e))?,
+        _ => return Err("Tool caches are kept in Docker or Podman volumes".to_string()),
+    };
+    match command {
+        CacheCommand::Ls => {
+            let volumes = tool_cache::list_volumes(&docker).await?;
+            if volumes.is_empty() {
+                println!("No cache volumes");
+                return Ok(());
+            }
+            for volume in volumes {
+                let size = volume
+                    .size
+                    .map(super::format_size)
+                    .unwrap_or_else(|| "?".to_string());
+                println!(
+                    "{:<24} {:<20} {:<16} {:>10}  {}",
+                    volume.workflow, volume.job, volume.tool, size, volume.name
+                );
+            }
+        }
+        CacheCommand::Clear { workflow, job } => {
+            let removed =
+                tool_cache::remove_volumes(&docker, workflow.as_deref(), job.as_deref()).await?;
+            if removed.is_empty() {
+                println!("No cache volumes to remove");
+            }
+            for name in removed {
+                println!("Removed {}", name);
+            }
+        }
+    }
+    Ok(())
+}
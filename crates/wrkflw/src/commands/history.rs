@@ -0,0 +1,143 @@
+// `wrkflw history`: listing, showing and comparing recorded runs
+use clap::Subcommand;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Subcommand)]
+pub enum HistoryCommand {
+    /// List recorded runs, most recent first
+    List {
+        /// Only runs of this workflow or pipeline file
+        #[arg(long)]
+        workflow: Option<PathBuf>,
+
+        /// Most runs to list
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+    },
+
+    /// Show the job and step results of a run (defaults to the most recent run)
+    Show {
+        /// Run id, or the start of one
+        id: Option<String>,
+    },
+
+    /// Show which jobs, steps and job outputs changed between two runs
+    Diff {
+        /// Run id to compare from
+        a: String,
+
+        /// Run id to compare to
+        b: String,
+    },
+}
+
+/// Handle `wrkflw history` subcommands
+pub fn handle(command: &HistoryCommand, root: &Path) -> Result<(), String> {
+    use executor::history;
+
+    match command {
+        HistoryCommand::List { workflow, limit } => {
+            let runs: Vec<_> = history::list(root)
+                .into_iter()
+                .filter(|run| workflow.as_ref().is_none_or(|path| &run.workflow == path))
+                .take(*limit)
+                .collect();
+            if runs.is_empty() {
+                println!("No runs recorded in {}", root.display());
+                return Ok(());
+            }
+            for run in runs {
+                let status = match (&run.cancelled, run.success) {
+                    (Some(_), _) => "⏹",
+                    (None, true) => "✅",
+                    (None, false) => "❌",
+                };
+                println!(
+                    "{} {}  {}  {}  ({})",
+                    status,
+                    run.id,
+                    history_time(&run.started_at),
+                    run.workflow.display(),
+                    run.runtime
+                );
+            }
+        }
+        HistoryCommand::Show { id } => {
+            let run = match id {
+                Some(id) => history::load(root, id)?,
+                None => history::list(root)
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| format!("No runs recorded in {}", root.display()))?,
+            };
+            println!("Run {}", run.id);
+            println!("Workflow: {}", run.workflow.display());
+            println!("Runtime:  {}", run.runtime);
+            println!("Started:  {}", history_time(&run.started_at));
+            println!("Finished: {}", history_time(&run.finished_at));
+            match &run.cancelled {
+                Some(reason) => println!("Result:   {}", reason),
+                None => println!(
+                    "Result:   {}",
+                    if run.success { "success" } else { "failure" }
+                ),
+            }
+            for job in &run.jobs {
+                println!(
+                    "\n{} ({}, {:.1}s)",
+                    job.name,
+                    history::job_status(&job.status),
+                    job.duration.as_secs_f64()
+                );
+                for step in &job.steps {
+                    let exit_code = match step.exit_code {
+                        Some(code) => format!(", exit code {}", code),
+                        None => String::new(),
+                    };
+                    println!(
+                        "  {} ({}{}, {:.1}s)",
+                        step.name,
+                        history::step_status(&step.status),
+                        exit_code,
+                        step.duration.as_secs_f64()
+                    );
+                }
+                let mut outputs: Vec<_> = job.outputs.iter().collect();
+                outputs.sort();
+                for (name, value) in outputs {
+                    println!("  outputs.{} = {}", name, value);
+                }
+            }
+        }
+        HistoryCommand::Diff { a, b } => {
+            let (a, b) = (history::load(root, a)?, history::load(root, b)?);
+            if a.workflow != b.workflow {
+                println!(
+                    "Note: comparing runs of different files, {} and {}",
+                    a.workflow.display(),
+                    b.workflow.display()
+                );
+            }
+            let changes = history::diff(&a, &b);
+            if changes.is_empty() {
+                println!("No differences between {} and {}", a.id, b.id);
+            }
+            for change in changes {
+                println!("{}", change);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// A recorded RFC 3339 time in the local time zone, without the fraction
+fn history_time(time: &str) -> String {
+    chrono::DateTime::parse_from_rfc3339(time)
+        .map(|time| {
+            time.with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+        })
+        .unwrap_or_else(|_| time.to_string())
+}
@@ -0,0 +1,68 @@
+// `wrkflw list`: the workflows and pipelines found in the repository
+use std::path::PathBuf;
+
+/// List the workflows and pipelines of the repository
+pub fn handle(verbose: bool) {
+    // Check for GitHub workflows
+    let github_path = PathBuf::from(".github/workflows");
+    if github_path.exists() && github_path.is_dir() {
+        println!("GitHub Workflows:");
+
+        let entries = std::fs::read_dir(&github_path)
+            .expect("Failed to read directory")
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                entry.path().is_file()
+                    && entry
+                        .path()
+                        .extension()
+                        .is_some_and(|ext| ext == "yml" || ext == "yaml")
+            })
+            .collect::<Vec<_>>();
+
+        if entries.is_empty() {
+            println!("  No workflow files found in .github/workflows");
+        } else {
+            for entry in entries {
+                println!("  - {}", entry.path().display());
+            }
+        }
+    } else {
+        println!("GitHub Workflows: No .github/workflows directory found");
+    }
+
+    // Check for GitLab CI pipeline
+    let gitlab_path = PathBuf::from(".gitlab-ci.yml");
+    if gitlab_path.exists() && gitlab_path.is_file() {
+        println!("GitLab CI Pipeline:");
+        println!("  - {}", gitlab_path.display());
+    } else {
+        println!("GitLab CI Pipeline: No .gitlab-ci.yml file found");
+    }
+
+    // Check for other GitLab CI pipeline files
+    if verbose {
+        println!("Searching for other GitLab CI pipeline files...");
+
+        let entries = walkdir::WalkDir::new(".")
+            .follow_links(true)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                entry.path().is_file()
+                    && entry
+                        .file_name()
+                        .to_string_lossy()
+                        .ends_with("gitlab-ci.yml")
+                    && entry.path() != gitlab_path
+            })
+            .collect::<Vec<_>>();
+
+        if !entries.is_empty() {
+            println!("Additional GitLab CI Pipeline files:");
+            for entry in entries {
+                println!("  - {}", entry.path().display());
+            }
+        }
+    }
+}
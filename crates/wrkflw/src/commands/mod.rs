@@ -0,0 +1,25 @@
+// Handlers of the subcommands that list or manage what wrkflw finds and
+// keeps around, rather than validating or running workflows
+pub mod actions;
+pub mod artifacts;
+pub mod cache;
+pub mod history;
+pub mod list;
+
+/// Format a byte count for display, e.g. "1.5 MB"
+pub(crate) fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
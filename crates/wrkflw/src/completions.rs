@@ -0,0 +1,151 @@
+// Shell completions: the script `wrkflw completions` prints calls wrkflw
+// back with the command line being completed, so workflow and job names
+// come from the repository at the time of completing
+use clap::ValueEnum;
+use clap_complete::engine::{CompletionCandidate, PathCompleter, ValueCompleter};
+use clap_complete::env::{Bash, EnvCompleter, Fish, Powershell, Zsh};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+/// Environment variable the completion script sets when calling wrkflw
+pub const ENV_VAR: &str = "COMPLETE";
+
+const WORKFLOWS_DIR: &str = ".github/workflows";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+}
+
+/// Write the script that registers wrkflw's completions with `shell`
+pub fn write_script(shell: Shell, output: &mut dyn std::io::Write) -> std::io::Result<()> {
+    let completer: &dyn EnvCompleter = match shell {
+        Shell::Bash => &Bash,
+        Shell::Zsh => &Zsh,
+        Shell::Fish => &Fish,
+        Shell::Powershell => &Powershell,
+    };
+    // Found on PATH when completing, so the script keeps working when
+    // wrkflw is upgraded or moved
+    completer.write_registration(ENV_VAR, "wrkflw", "wrkflw", "wrkflw", output)
+}
+
+/// Workflow files in .github/workflows starting with `current`, then any
+/// path, for workflows kept elsewhere
+pub fn workflow_files(current: &OsStr) -> Vec<CompletionCandidate> {
+    let prefix = current.to_string_lossy();
+    let mut candidates: Vec<CompletionCandidate> = workflows_in(Path::new(WORKFLOWS_DIR))
+        .into_iter()
+        .map(|path| path.display().to_string())
+        .filter(|path| path.starts_with(prefix.as_ref()))
+        .map(CompletionCandidate::new)
+        .collect();
+    for candidate in PathCompleter::any().complete(current) {
+        if !candidates
+            .iter()
+            .any(|c| c.get_value() == candidate.get_value())
+        {
+            candidates.push(candidate);
+        }
+    }
+    candidates
+}
+
+/// Names of the workflows in .github/workflows starting with `current`, as
+/// `trigger` takes them
+pub fn workflow_names(current: &OsStr) -> Vec<CompletionCandidate> {
+    let prefix = current.to_string_lossy();
+    workflows_in(Path::new(WORKFLOWS_DIR))
+        .iter()
+        .filter_map(|path| path.file_stem())
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .filter(|name| name.starts_with(prefix.as_ref()))
+        .map(CompletionCandidate::new)
+        .collect()
+}
+
+/// Jobs starting with `current` of the workflow file on the command line,
+/// or of every workflow in .github/workflows when none is there yet
+pub fn job_names(current: &OsStr) -> Vec<CompletionCandidate> {
+    let prefix = current.to_string_lossy();
+    let named = std::env::args_os()
+        .map(PathBuf::from)
+        .find(|path| path.is_file() && is_workflow_file(path));
+    let files = match named {
+        Some(path) => vec![path],
+        None => workflows_in(Path::new(WORKFLOWS_DIR)),
+    };
+    let mut candidates: Vec<CompletionCandidate> = Vec::new();
+    for (job, name) in files.iter().flat_map(|path| jobs_of(path)) {
+        if job.starts_with(prefix.as_ref())
+            && !candidates.iter().any(|c| c.get_value() == job.as_str())
+        {
+            candidates.push(CompletionCandidate::new(job).help(name.map(Into::into)));
+        }
+    }
+    candidates
+}
+
+/// The YAML files in `dir`, sorted
+fn workflows_in(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && is_workflow_file(path))
+        .collect();
+    files.sort();
+    files
+}
+
+fn is_workflow_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext == "yml" || ext == "yaml")
+}
+
+/// The ids of a workflow's jobs with their `name:`, read without validating
+/// the workflow, which may be half written
+fn jobs_of(path: &Path) -> Vec<(String, Option<String>)> {
+    let Some(jobs) = std::fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_yaml::from_str::<serde_yaml::Value>(&content).ok())
+        .and_then(|workflow| workflow.get("jobs").cloned())
+        .and_then(|jobs| jobs.as_mapping().cloned())
+    else {
+        return Vec::new();
+    };
+    jobs.iter()
+        .filter_map(|(id, job)| {
+            let name = job.get("name").and_then(|name| name.as_str());
+            Some((id.as_str()?.to_string(), name.map(str::to_string)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_job_ids_and_names_of_a_half_written_workflow() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ci.yml");
+        std::fs::write(
+            &path,
+            "on: push\njobs:\n  build:\n    name: Build it\n    runs-on: ubuntu-latest\n  test:\n    steps:\n",
+        )
+        .unwrap();
+        assert_eq!(
+            jobs_of(&path),
+            [
+                ("build".to_string(), Some("Build it".to_string())),
+                ("test".to_string(), None)
+            ]
+        );
+        assert!(jobs_of(&dir.path().join("missing.yml")).is_empty());
+    }
+}
@@ -1,3 +1,7 @@
+pub mod runner;
+
+pub use runner::{RunReport, Runner, RuntimeType};
+
 pub use evaluator;
 pub use executor;
 pub use github;
@@ -1,15 +1,27 @@
+mod commands;
+mod completions;
+
 use bollard::Docker;
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::engine::ArgValueCompleter;
+use commands::actions::ActionsCommand;
+use commands::artifacts::ArtifactsCommand;
+use commands::cache::CacheCommand;
+use commands::history::HistoryCommand;
+use parser::sniff::{self, Platform};
+use rayon::prelude::*;
 use std::collections::HashMap;
+use std::io::{BufRead, IsTerminal, Write};
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 #[derive(Debug, Parser)]
 #[command(
     name = "wrkflw",
     about = "GitHub & GitLab CI/CD validator and executor",
     version,
-    long_about = "A CI/CD validator and executor that runs workflows locally.\n\nExamples:\n  wrkflw validate                             # Validate all workflows in .github/workflows\n  wrkflw run .github/workflows/build.yml      # Run a specific workflow\n  wrkflw run .gitlab-ci.yml                   # Run a GitLab CI pipeline\n  wrkflw --verbose run .github/workflows/build.yml  # Run with more output\n  wrkflw --debug run .github/workflows/build.yml    # Run with detailed debug information\n  wrkflw run --emulate .github/workflows/build.yml  # Use emulation mode instead of Docker"
+    long_about = "A CI/CD validator and executor that runs workflows locally.\n\nExamples:\n  wrkflw validate                             # Validate all workflows in .github/workflows\n  wrkflw run .github/workflows/build.yml      # Run a specific workflow\n  wrkflw run .gitlab-ci.yml                   # Run a GitLab CI pipeline\n  wrkflw --verbose run .github/workflows/build.yml  # Run with more output\n  wrkflw --debug run .github/workflows/build.yml    # Run with detailed debug information\n  wrkflw run --emulate .github/workflows/build.yml  # Use emulation mode instead of Docker\n  wrkflw expand .gitlab-ci.yml --format json  # Show the fully expanded pipeline"
 )]
 struct Wrkflw {
     #[command(subcommand)]
@@ -22,36 +34,284 @@ struct Wrkflw {
     /// Run in debug mode with extensive execution details
     #[arg(short, long, global = true)]
     debug: bool,
+
+    /// Print log lines as text, or as JSON objects with the workflow, job
+    /// and step they belong to
+    #[arg(long, value_enum, global = true, default_value_t = LogFormatChoice::Text)]
+    log_format: LogFormatChoice,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum LogFormatChoice {
+    Text,
+    Json,
 }
 
+// Parsed once, so the size of `Run` doesn't matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Subcommand)]
 enum Commands {
     /// Validate workflow or pipeline files
     Validate {
-        /// Path to workflow/pipeline file or directory (defaults to .github/workflows)
+        /// Path to workflow/pipeline file or directory (defaults to .github/workflows
+        /// and the .gitlab-ci.yml, .circleci/config.yml and azure-pipelines.yml there are)
+        #[arg(add = ArgValueCompleter::new(completions::workflow_files))]
         path: Option<PathBuf>,
 
         /// Explicitly validate as GitLab CI/CD pipeline
-        #[arg(long)]
+        #[arg(long, conflicts_with = "github")]
         gitlab: bool,
+
+        /// Explicitly validate as GitHub workflow
+        #[arg(long)]
+        github: bool,
+
+        /// Warn about actions that need a newer Node.js or tools like buildx
+        /// than Docker or emulation mode provide
+        #[arg(long)]
+        version_check: bool,
+
+        /// Validate every file again instead of reusing results cached in
+        /// .wrkflw/cache for files that haven't changed
+        #[arg(long)]
+        no_cache: bool,
+
+        /// Don't look actions up on GitHub to check that they exist and
+        /// whether their refs are pinned to a commit, nor download GitLab
+        /// includes that aren't cached yet
+        #[arg(long)]
+        offline: bool,
+
+        /// Print results as text, JSON, or a SARIF log for code scanning
+        #[arg(long, value_enum, default_value_t = ValidateFormat::Text)]
+        format: ValidateFormat,
+
+        /// Exit with 2 when a file has errors, or with `warning` also when
+        /// one only has warnings
+        #[arg(long, value_enum, default_value_t = FailOn::Error)]
+        fail_on: FailOn,
     },
 
     /// Execute workflow or pipeline files locally
     Run {
         /// Path to workflow/pipeline file to execute
+        #[arg(add = ArgValueCompleter::new(completions::workflow_files))]
         path: PathBuf,
 
         /// Use emulation mode instead of Docker
-        #[arg(short, long)]
+        #[arg(short, long, conflicts_with = "runtime")]
         emulate: bool,
 
+        /// Where jobs run [default: docker, or runtime in .wrkflw.toml]
+        #[arg(long, value_enum)]
+        runtime: Option<RuntimeChoice>,
+
         /// Show 'Would execute GitHub action' messages in emulation mode
         #[arg(long, default_value_t = false)]
         show_action_messages: bool,
 
         /// Explicitly run as GitLab CI/CD pipeline
-        #[arg(long)]
+        #[arg(long, conflicts_with = "github")]
         gitlab: bool,
+
+        /// Explicitly run as GitHub workflow
+        #[arg(long)]
+        github: bool,
+
+        /// Run jobs with this runs-on label in this image, ahead of [images]
+        /// in .wrkflw.toml and the built-in images (can be repeated)
+        #[arg(long = "runner-image", value_name = "LABEL=IMAGE", value_parser = parse_runner_image)]
+        runner_images: Vec<(String, String)>,
+
+        /// Warn about artifacts larger than this many MiB
+        #[arg(long, value_name = "MIB", value_parser = parse_mib)]
+        artifact_size_warning: Option<u64>,
+
+        /// Warn about caches larger than this many MiB
+        #[arg(long, value_name = "MIB", value_parser = parse_mib)]
+        cache_size_warning: Option<u64>,
+
+        /// Only run this job (can be repeated)
+        #[arg(long = "job", value_name = "NAME", add = ArgValueCompleter::new(completions::job_names))]
+        jobs: Vec<String>,
+
+        /// Don't run this job (can be repeated)
+        #[arg(long = "skip-job", value_name = "NAME", add = ArgValueCompleter::new(completions::job_names))]
+        skip_jobs: Vec<String>,
+
+        /// Also run the jobs that the --job jobs need
+        #[arg(long, requires = "jobs")]
+        with_needs: bool,
+
+        /// Run as if triggered by this event, e.g. pull_request, or for GitLab
+        /// pipelines the CI_PIPELINE_SOURCE, e.g. merge_request_event
+        #[arg(long, value_name = "NAME")]
+        event: Option<String>,
+
+        /// JSON webhook payload of the --event, available as github.event. For
+        /// GitLab pipelines, a JSON object of CI variables like CI_COMMIT_TAG
+        #[arg(long, value_name = "FILE", requires = "event")]
+        event_file: Option<PathBuf>,
+
+        /// Warn before running about actions the runtime can't support
+        #[arg(long)]
+        version_check: bool,
+
+        /// Leave the run's Docker network in place to debug connections
+        /// between containers
+        #[arg(long)]
+        keep_network: bool,
+
+        /// What step containers may reach: anything, only each other and the
+        /// job's services (offline), or also the --allow-host hosts through
+        /// a proxy (allow-list)
+        #[arg(long, value_enum, default_value_t = NetworkChoice::Open)]
+        network: NetworkChoice,
+
+        /// Host step containers may reach with --network allow-list, like
+        /// github.com or *.crates.io (can be repeated)
+        #[arg(long = "allow-host", value_name = "HOST")]
+        allowed_hosts: Vec<String>,
+
+        /// CPUs each job's containers may use, like 2 or 1.5 [default:
+        /// cpus under [resources] in .wrkflw.toml, or no limit]
+        #[arg(long, value_name = "CPUS")]
+        cpus: Option<f64>,
+
+        /// Memory each job's containers may use, like 512m or 7g [default:
+        /// memory under [resources] in .wrkflw.toml, or no limit]
+        #[arg(long, value_name = "SIZE")]
+        memory: Option<String>,
+
+        /// Platform to pull images for and run job containers as, like
+        /// linux/arm64, emulated with QEMU on other hosts [default: platform
+        /// in .wrkflw.toml, or the host's]
+        #[arg(long = "platform", value_name = "PLATFORM")]
+        container_platform: Option<String>,
+
+        /// Lines of a failed step's output to print [default: 5, or
+        /// max-output-lines in ~/.wrkflw/config.yml]
+        #[arg(long, value_name = "LINES")]
+        max_output_lines: Option<usize>,
+
+        /// Don't set CI=true and GITHUB_ACTIONS=true (GITLAB_CI=true for
+        /// pipelines) in jobs [default: set, unless ci-env: false in
+        /// ~/.wrkflw/config.yml]
+        #[arg(long)]
+        no_ci_env: bool,
+
+        /// Run emulated steps in the project directory with wrkflw's whole
+        /// environment, instead of a per-job copy of the workspace
+        #[arg(long)]
+        no_sandbox: bool,
+
+        /// Don't record the run in the history
+        #[arg(long)]
+        no_history: bool,
+
+        /// Keep the cargo, npm, pip and Go module caches of container jobs
+        /// in named volumes between runs [default: off, or tool-cache in
+        /// .wrkflw.toml]
+        #[arg(long)]
+        tool_cache: bool,
+
+        /// Run jobs in the project directory itself instead of a copy of
+        /// it, so steps change your files [default: off, or bind-workspace
+        /// in .wrkflw.toml]
+        #[arg(long)]
+        bind_workspace: bool,
+
+        /// Print the jobs and steps that would run, the images they'd pull
+        /// and the actions that need the network, without running anything
+        #[arg(long, conflicts_with = "reports")]
+        dry_run: bool,
+
+        /// Print where the run's time went afterwards: the slowest steps,
+        /// image pulls, container startup and script time
+        #[arg(long, conflicts_with = "dry_run")]
+        profile: bool,
+
+        /// When a run step fails in Docker or Podman mode, open a shell in
+        /// its container, in its working directory with its environment,
+        /// before going on
+        #[arg(long, visible_alias = "debug-shell", conflicts_with = "dry_run")]
+        interactive: bool,
+
+        /// Ask before each step whether to run it, skip it or abort the run
+        #[arg(long = "step", conflicts_with = "dry_run")]
+        single_step: bool,
+
+        /// Pause before this step, given as JOB:STEP with the step's name or
+        /// number (can be repeated)
+        #[arg(long = "break-at", value_name = "JOB:STEP", conflicts_with = "dry_run")]
+        breakpoints: Vec<executor::stepping::Breakpoint>,
+
+        /// Times a Docker or Podman API call that failed for a transient
+        /// reason, like a pull cut off by the registry, is retried [default:
+        /// 3, or docker-retries in ~/.wrkflw/config.yml]
+        #[arg(long, value_name = "TIMES")]
+        docker_retries: Option<u32>,
+
+        /// Value of github.token and secrets.GITHUB_TOKEN [default:
+        /// GITHUB_TOKEN in the secrets file, or a made-up token]
+        #[arg(long, value_name = "TOKEN")]
+        github_token: Option<String>,
+
+        /// Set a value of the github context and its GITHUB_* variable, like
+        /// run_number=42 or ref=refs/tags/v1.0.0, over the detected one (can
+        /// be repeated)
+        #[arg(long = "github-context", value_name = "KEY=VALUE", value_parser = parse_github_context)]
+        github_context: Vec<(String, String)>,
+
+        /// Print the results as text, or as JSON with every job and step, or
+        /// stream job and step events as JSON lines ending with the results
+        #[arg(long, value_enum, default_value_t = RunFormat::Text)]
+        format: RunFormat,
+
+        /// Also write the results to a file, as junit:PATH (JUnit XML) or
+        /// json:PATH (can be repeated)
+        #[arg(long = "report", value_name = "FORMAT:PATH", value_parser = parse_report)]
+        reports: Vec<(RunReport, PathBuf)>,
+
+        /// Run unattended for CI: never ask anything, validate the workflow
+        /// first, and print plain timestamped lines with each step's output
+        /// in a ::group:: GitHub Actions folds
+        #[arg(
+            long,
+            conflicts_with_all = ["format", "dry_run", "interactive", "single_step", "breakpoints"]
+        )]
+        ci: bool,
+    },
+
+    /// Open a shell in a job's container, prepared like a run prepares it
+    /// with the workspace, services and environment, without running steps
+    Exec {
+        /// Job to open the shell for
+        #[arg(add = ArgValueCompleter::new(completions::job_names))]
+        job: String,
+
+        /// Workflow file with the job [default: the one in
+        /// .github/workflows that has it]
+        #[arg(add = ArgValueCompleter::new(completions::workflow_files))]
+        path: Option<PathBuf>,
+
+        /// Container engine to use [default: docker, or runtime in
+        /// .wrkflw.toml]
+        #[arg(long, value_enum)]
+        runtime: Option<RuntimeChoice>,
+
+        /// Run jobs with this runs-on label in this image, ahead of [images]
+        /// in .wrkflw.toml and the built-in images (can be repeated)
+        #[arg(long = "runner-image", value_name = "LABEL=IMAGE", value_parser = parse_runner_image)]
+        runner_images: Vec<(String, String)>,
+
+        /// Prepare the job as if triggered by this event, e.g. pull_request
+        #[arg(long, value_name = "NAME")]
+        event: Option<String>,
+
+        /// JSON webhook payload of the --event, available as github.event
+        #[arg(long, value_name = "FILE", requires = "event")]
+        event_file: Option<PathBuf>,
     },
 
     /// Open TUI interface to manage workflows
@@ -60,18 +320,41 @@ enum Commands {
         path: Option<PathBuf>,
 
         /// Use emulation mode instead of Docker
-        #[arg(short, long)]
+        #[arg(short, long, conflicts_with = "runtime")]
         emulate: bool,
 
+        /// Where jobs run [default: docker, or runtime in .wrkflw.toml]
+        #[arg(long, value_enum)]
+        runtime: Option<RuntimeChoice>,
+
         /// Show 'Would execute GitHub action' messages in emulation mode
         #[arg(long, default_value_t = false)]
         show_action_messages: bool,
+
+        /// Record the session to a file that `wrkflw replay` can play back
+        #[arg(long, value_name = "FILE")]
+        record: Option<PathBuf>,
+
+        /// Pause runs before each step, to continue, skip it or abort
+        #[arg(long = "step")]
+        single_step: bool,
+
+        /// Pause runs before this step, given as JOB:STEP with the step's
+        /// name or number (can be repeated)
+        #[arg(long = "break-at", value_name = "JOB:STEP")]
+        breakpoints: Vec<executor::stepping::Breakpoint>,
     },
 
-    /// Trigger a GitHub workflow remotely
+    /// Trigger GitHub workflows remotely
     Trigger {
-        /// Name of the workflow file (without .yml extension)
-        workflow: String,
+        /// Name of the workflow file (without .yml extension), or a glob
+        /// matching workflow file names or names, like "deploy-*"
+        #[arg(required_unless_present = "all", add = ArgValueCompleter::new(completions::workflow_names))]
+        workflow: Option<String>,
+
+        /// Trigger every workflow with a workflow_dispatch trigger
+        #[arg(long, conflicts_with = "workflow")]
+        all: bool,
 
         /// Branch to run the workflow on
         #[arg(short, long)]
@@ -80,6 +363,48 @@ enum Commands {
         /// Key-value inputs for the workflow in format key=value
         #[arg(short, long, value_parser = parse_key_val)]
         input: Option<Vec<(String, String)>>,
+
+        /// JSON or YAML file with workflow inputs; --input values take precedence
+        #[arg(long)]
+        inputs_file: Option<PathBuf>,
+
+        /// Ask for every input the workflow declares that wasn't given, not
+        /// just the missing required ones
+        #[arg(short, long)]
+        prompt: bool,
+
+        /// Wait until the triggered runs finish, watching all of them at
+        /// once; a single run is followed job by job, like `watch-run`
+        #[arg(long)]
+        watch: bool,
+    },
+
+    /// Follow a workflow run on GitHub until it finishes, printing its job
+    /// and step statuses and the end of each job's log. Exits non-zero
+    /// unless the run succeeds.
+    WatchRun {
+        /// Run id or URL [default: the latest run on the branch]
+        run: Option<String>,
+
+        /// Watch the latest run of this workflow, e.g. ci or ci.yml
+        #[arg(short, long, conflicts_with = "run")]
+        workflow: Option<String>,
+
+        /// Branch to look for the latest run on [default: current branch]
+        #[arg(short, long, conflicts_with = "run")]
+        branch: Option<String>,
+
+        /// Seconds between polls
+        #[arg(long, default_value_t = 5)]
+        interval: u64,
+
+        /// Lines of each finished job's log to show
+        #[arg(long, default_value_t = 10)]
+        tail: usize,
+
+        /// Save the full log of each job in this directory
+        #[arg(long, value_name = "DIR")]
+        logs_dir: Option<PathBuf>,
     },
 
     /// Trigger a GitLab pipeline remotely
@@ -95,284 +420,1424 @@ enum Commands {
 
     /// List available workflows and pipelines
     List,
-}
 
-// Parser function for key-value pairs
-fn parse_key_val(s: &str) -> Result<(String, String), String> {
-    let pos = s
-        .find('=')
-        .ok_or_else(|| format!("invalid KEY=value: no `=` found in `{}`", s))?;
+    /// Play back a TUI session recorded with `wrkflw tui --record`
+    Replay {
+        /// Recording file
+        file: PathBuf,
+    },
 
-    Ok((s[..pos].to_string(), s[pos + 1..].to_string()))
-}
+    /// Inspect artifacts uploaded during local runs
+    Artifacts {
+        #[command(subcommand)]
+        command: ArtifactsCommand,
 
-// Make this function public for testing? Or move to a utils/cleanup mod?
-// Or call executor::cleanup and runtime::cleanup directly?
-// Let's try calling them directly for now.
-async fn cleanup_on_exit() {
-    // Clean up Docker resources if available, but don't let it block indefinitely
-    match tokio::time::timeout(std::time::Duration::from_secs(3), async {
-        match Docker::connect_with_local_defaults() {
-            Ok(docker) => {
-                // Assuming cleanup_resources exists in executor crate
-                executor::cleanup_resources(&docker).await;
-            }
-            Err(_) => {
-                // Docker not available
-                logging::info("Docker not available, skipping Docker cleanup");
-            }
-        }
-    })
-    .await
-    {
-        Ok(_) => logging::debug("Docker cleanup completed successfully"),
-        Err(_) => {
-            logging::warning("Docker cleanup timed out after 3 seconds, continuing with shutdown")
-        }
-    }
+        /// Artifact storage directory (defaults to $WRKFLW_ARTIFACTS_DIR or the user cache dir)
+        #[arg(long, global = true)]
+        dir: Option<PathBuf>,
+    },
 
-    // Always clean up emulation resources
-    match tokio::time::timeout(
-        std::time::Duration::from_secs(2),
-        // Assuming cleanup_resources exists in runtime::emulation module
-        runtime::emulation::cleanup_resources(),
-    )
-    .await
-    {
-        Ok(_) => logging::debug("Emulation cleanup completed successfully"),
-        Err(_) => logging::warning("Emulation cleanup timed out, continuing with shutdown"),
-    }
+    /// Browse the results of previous runs
+    History {
+        #[command(subcommand)]
+        command: HistoryCommand,
 
-    logging::info("Resource cleanup completed");
-}
+        /// History directory (defaults to $WRKFLW_HISTORY_DIR or the user data dir)
+        #[arg(long, global = true)]
+        dir: Option<PathBuf>,
+    },
 
-async fn handle_signals() {
-    // Set up a hard exit timer in case cleanup takes too long
-    // This ensures the app always exits even if Docker operations are stuck
-    let hard_exit_time = std::time::Duration::from_secs(10);
+    /// Manage the cache of actions fetched for runs
+    Actions {
+        #[command(subcommand)]
+        command: ActionsCommand,
+    },
 
-    // Wait for Ctrl+C
-    match tokio::signal::ctrl_c().await {
-        Ok(_) => {
-            println!("Received Ctrl+C, shutting down and cleaning up...");
-        }
-        Err(e) => {
-            // Log the error but continue with cleanup
-            eprintln!("Warning: Failed to properly listen for ctrl+c event: {}", e);
-            println!("Shutting down and cleaning up...");
-        }
-    }
+    /// Manage the volumes that keep tool caches between runs
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommand,
 
-    // Set up a watchdog thread that will force exit if cleanup takes too long
-    // This is important because Docker operations can sometimes hang indefinitely
-    let _ = std::thread::spawn(move || {
-        std::thread::sleep(hard_exit_time);
-        eprintln!(
-            "Cleanup taking too long (over {} seconds), forcing exit...",
-            hard_exit_time.as_secs()
-        );
-        logging::error("Forced exit due to cleanup timeout");
-        std::process::exit(1);
-    });
+        /// Runtime whose volumes to manage [default: docker, or runtime in
+        /// .wrkflw.toml]
+        #[arg(long, value_enum, global = true)]
+        runtime: Option<RuntimeChoice>,
+    },
 
-    // Clean up containers
-    cleanup_on_exit().await;
+    /// Inspect wrkflw's own config file
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
 
-    // Exit with success status - the force exit thread will be terminated automatically
-    std::process::exit(0);
-}
+    /// Score workflows on security checks and report findings with remediations
+    #[command(visible_alias = "audit")]
+    AuditSecurity {
+        /// Path to a workflow file or directory (defaults to .github/workflows)
+        path: Option<PathBuf>,
 
-/// Determines if a file is a GitLab CI/CD pipeline based on its name and content
-fn is_gitlab_pipeline(path: &Path) -> bool {
-    // First check the file name
-    if let Some(file_name) = path.file_name() {
-        if let Some(file_name_str) = file_name.to_str() {
-            if file_name_str == ".gitlab-ci.yml" || file_name_str.ends_with("gitlab-ci.yml") {
-                return true;
-            }
-        }
-    }
+        /// Report format
+        #[arg(long, value_enum, default_value_t = ReportFormat::Md)]
+        format: ReportFormat,
+    },
 
-    // Check if file is in .gitlab/ci directory
-    if let Some(parent) = path.parent() {
-        if let Some(parent_str) = parent.to_str() {
-            if parent_str.ends_with(".gitlab/ci")
-                && path
-                    .extension()
-                    .is_some_and(|ext| ext == "yml" || ext == "yaml")
-            {
-                return true;
-            }
-        }
-    }
+    /// Report which workflows and jobs an event would run, without running them
+    Triggers {
+        /// Path to a workflow file or directory (defaults to .github/workflows)
+        path: Option<PathBuf>,
 
-    // If file exists, check the content
-    if path.exists() {
-        if let Ok(content) = std::fs::read_to_string(path) {
-            // GitLab CI/CD pipelines typically have stages, before_script, after_script at the top level
-            if content.contains("stages:")
-                || content.contains("before_script:")
-                || content.contains("after_script:")
-            {
-                // Check for GitHub Actions specific keys that would indicate it's not GitLab
-                if !content.contains("on:")
-                    && !content.contains("runs-on:")
-                    && !content.contains("uses:")
-                {
-                    return true;
-                }
-            }
-        }
-    }
+        /// Event to check, e.g. push or pull_request
+        #[arg(long, default_value = "push")]
+        event: String,
 
-    false
-}
+        /// JSON webhook payload of the event
+        #[arg(long, value_name = "FILE")]
+        event_file: Option<PathBuf>,
 
-#[tokio::main]
-async fn main() {
-    let cli = Wrkflw::parse();
-    let verbose = cli.verbose;
-    let debug = cli.debug;
+        /// Branch pushed to, or the base branch of a pull request (defaults to the current branch)
+        #[arg(long, conflicts_with = "tag")]
+        branch: Option<String>,
 
-    // Set log level based on command line flags
-    if debug {
-        logging::set_log_level(logging::LogLevel::Debug);
-        logging::debug("Debug mode enabled - showing detailed logs");
-    } else if verbose {
-        logging::set_log_level(logging::LogLevel::Info);
-        logging::info("Verbose mode enabled");
-    } else {
-        logging::set_log_level(logging::LogLevel::Warning);
-    }
+        /// Tag pushed to
+        #[arg(long)]
+        tag: Option<String>,
 
-    // Setup a Ctrl+C handler that runs in the background
-    tokio::spawn(handle_signals());
+        /// Activity type, e.g. opened for pull_request
+        #[arg(long = "type", value_name = "TYPE")]
+        action: Option<String>,
 
-    match &cli.command {
-        Some(Commands::Validate { path, gitlab }) => {
-            // Determine the path to validate
-            let validate_path = path
-                .clone()
-                .unwrap_or_else(|| PathBuf::from(".github/workflows"));
+        /// Changed files, comma separated (defaults to `git diff` against --diff-base)
+        #[arg(long, value_delimiter = ',')]
+        files: Option<Vec<String>>,
 
-            // Check if the path exists
-            if !validate_path.exists() {
-                eprintln!("Error: Path does not exist: {}", validate_path.display());
-                std::process::exit(1);
-            }
-
-            // Determine if we're validating a GitLab pipeline based on the --gitlab flag or file detection
-            let force_gitlab = *gitlab;
-
-            if validate_path.is_dir() {
-                // Validate all workflow files in the directory
-                let entries = std::fs::read_dir(&validate_path)
-                    .expect("Failed to read directory")
-                    .filter_map(|entry| entry.ok())
-                    .filter(|entry| {
-                        entry.path().is_file()
-                            && entry
-                                .path()
-                                .extension()
-                                .is_some_and(|ext| ext == "yml" || ext == "yaml")
-                    })
-                    .collect::<Vec<_>>();
+        /// Revision to diff against for changed files (defaults to the upstream branch, or HEAD~1)
+        #[arg(long, value_name = "REV", conflicts_with = "files")]
+        diff_base: Option<String>,
+    },
 
-                println!("Validating {} workflow file(s)...", entries.len());
+    /// Print a workflow or pipeline with anchors, includes, extends and defaults expanded
+    Expand {
+        /// Path to workflow/pipeline file to expand
+        path: PathBuf,
 
-                for entry in entries {
-                    let path = entry.path();
-                    let is_gitlab = force_gitlab || is_gitlab_pipeline(&path);
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Yaml)]
+        format: OutputFormat,
 
-                    if is_gitlab {
-                        validate_gitlab_pipeline(&path, verbose);
-                    } else {
-                        validate_github_workflow(&path, verbose);
-                    }
-                }
-            } else {
-                // Validate a single workflow file
-                let is_gitlab = force_gitlab || is_gitlab_pipeline(&validate_path);
+        /// Expand matrix jobs into one job per combination
+        #[arg(long)]
+        expand_matrix: bool,
 
-                if is_gitlab {
-                    validate_gitlab_pipeline(&validate_path, verbose);
-                } else {
-                    validate_github_workflow(&validate_path, verbose);
-                }
-            }
-        }
-        Some(Commands::Run {
-            path,
-            emulate,
-            show_action_messages: _,
-            gitlab,
-        }) => {
-            // Determine the runtime type
-            let runtime_type = if *emulate {
-                executor::RuntimeType::Emulation
-            } else {
-                executor::RuntimeType::Docker
-            };
+        /// Explicitly treat the file as a GitLab CI/CD pipeline
+        #[arg(long, conflicts_with = "github")]
+        gitlab: bool,
 
-            // Check if we're explicitly or implicitly running a GitLab pipeline
-            let is_gitlab = *gitlab || is_gitlab_pipeline(path);
-            let workflow_type = if is_gitlab {
-                "GitLab CI pipeline"
-            } else {
-                "GitHub workflow"
-            };
+        /// Explicitly treat the file as a GitHub workflow
+        #[arg(long)]
+        github: bool,
+    },
 
-            logging::info(&format!("Running {} at: {}", workflow_type, path.display()));
+    /// Print the jobs' `needs:` graph, with GitLab stages, matrix fan-out and
+    /// the jobs an event would skip
+    Graph {
+        /// Path to workflow/pipeline file
+        path: PathBuf,
 
-            // Execute the workflow
-            let result = executor::execute_workflow(path, runtime_type, verbose)
-                .await
-                .unwrap_or_else(|e| {
-                    eprintln!("Error executing workflow: {}", e);
-                    std::process::exit(1);
-                });
+        /// Output format
+        #[arg(long, value_enum, default_value_t = GraphFormat::Ascii)]
+        format: GraphFormat,
 
-            // Print execution summary
-            if result.failure_details.is_some() {
-                eprintln!("❌ Workflow execution failed:");
-                if let Some(details) = result.failure_details {
-                    if verbose {
-                        // Show full error details in verbose mode
-                        eprintln!("{}", details);
-                    } else {
-                        // Show simplified error info in non-verbose mode
-                        let simplified_error = details
-                            .lines()
-                            .filter(|line| line.contains("❌") || line.trim().starts_with("Error:"))
-                            .take(5) // Limit to the first 5 error lines
-                            .collect::<Vec<&str>>()
-                            .join("\n");
+        /// Event to mark skipped jobs for, e.g. push or pull_request
+        #[arg(long)]
+        event: Option<String>,
 
-                        eprintln!("{}", simplified_error);
+        /// JSON webhook payload of the event
+        #[arg(long, value_name = "FILE", requires = "event")]
+        event_file: Option<PathBuf>,
 
-                        if details.lines().count() > 5 {
-                            eprintln!("\nUse --verbose flag to see full error details");
-                        }
-                    }
-                }
-                std::process::exit(1);
-            } else {
-                println!("✅ Workflow execution completed successfully!");
+        /// Explicitly treat the file as a GitLab CI/CD pipeline
+        #[arg(long, conflicts_with = "github")]
+        gitlab: bool,
 
-                // Print a summary of executed jobs
-                if true {
-                    // Always show job summary
-                    println!("\nJob summary:");
-                    for job in result.jobs {
-                        println!(
-                            "  {} {} ({})",
-                            match job.status {
-                                executor::JobStatus::Success => "✅",
-                                executor::JobStatus::Failure => "❌",
-                                executor::JobStatus::Skipped => "⏭️",
-                            },
-                            job.name,
-                            match job.status {
+        /// Explicitly treat the file as a GitHub workflow
+        #[arg(long)]
+        github: bool,
+    },
+
+    /// Rewrite workflows with consistent indentation, key order and quoting,
+    /// keeping their comments
+    Fmt {
+        /// Path to a workflow file or directory (defaults to .github/workflows)
+        path: Option<PathBuf>,
+
+        /// Don't write anything; list the files that need formatting and
+        /// exit with status 1 if there are any
+        #[arg(long)]
+        check: bool,
+    },
+
+    /// List the actions workflows use with the latest version of each, looked
+    /// up on GitHub
+    Outdated {
+        /// Path to a workflow file or directory (defaults to .github/workflows)
+        path: Option<PathBuf>,
+
+        /// Rewrite tag and branch refs to the commit they point at, keeping
+        /// the ref as a comment
+        #[arg(long)]
+        pin_shas: bool,
+    },
+
+    /// Convert a GitHub workflow to a GitLab CI pipeline or back, noting
+    /// what couldn't be converted
+    Convert {
+        /// Workflow or pipeline file to convert
+        path: PathBuf,
+
+        /// What to convert it to
+        #[arg(long, value_enum)]
+        to: ConvertTarget,
+
+        /// File to write the result to [default: stdout]
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+
+    /// Write a test file expecting the job and step statuses and job outputs
+    /// of a run, from its `run --format json` or `--report json:PATH` results
+    ScaffoldTests {
+        /// JSON results of the run
+        results: PathBuf,
+
+        /// Test file to write [default: <workflow name>.test.yml]
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+
+        /// Overwrite the test file if it exists
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Write a starter workflow from a template (rust, node, python, go,
+    /// docker-publish, release, or one in ~/.wrkflw/templates), asking for
+    /// its options, and validate it
+    Init {
+        /// Template to use; lists the templates when left out
+        template: Option<String>,
+
+        /// Value of a template option instead of asking for it, like
+        /// branches=main,develop (can be repeated)
+        #[arg(long = "set", value_name = "OPTION=VALUE")]
+        values: Vec<String>,
+
+        /// Take the defaults of the options that aren't set instead of
+        /// asking for them
+        #[arg(short, long)]
+        yes: bool,
+
+        /// Write a GitLab CI pipeline, converted from the template's workflow
+        #[arg(long)]
+        gitlab: bool,
+
+        /// File to write [default: the template's, like
+        /// .github/workflows/rust.yml, or .gitlab-ci.yml with --gitlab]
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+
+        /// Overwrite the file if it exists
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Print a script completing wrkflw's commands, options, workflow files
+    /// and job names in your shell, e.g. `source <(wrkflw completions bash)`
+    Completions {
+        #[arg(value_enum)]
+        shell: completions::Shell,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum ConfigCommand {
+    /// Print a JSON Schema of .wrkflw.toml, for editor completion and
+    /// validation
+    Schema {
+        /// Of ~/.wrkflw/config.yml instead
+        #[arg(long)]
+        user: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ValidateFormat {
+    Text,
+    Json,
+    Sarif,
+}
+
+/// The findings that make `validate` fail
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum FailOn {
+    Error,
+    Warning,
+}
+
+impl FailOn {
+    fn fails(self, findings: Findings) -> bool {
+        match findings.severity() {
+            Some(models::DiagnosticSeverity::Error) => true,
+            Some(models::DiagnosticSeverity::Warning) => self == FailOn::Warning,
+            None => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum RunFormat {
+    Text,
+    Json,
+    JsonLines,
+}
+
+/// Files `run --report` writes the results to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RunReport {
+    Junit,
+    Json,
+}
+
+fn parse_runner_image(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((label, image)) if !label.trim().is_empty() && !image.trim().is_empty() => {
+            Ok((label.trim().to_string(), image.trim().to_string()))
+        }
+        _ => Err(format!(
+            "expected LABEL=IMAGE, like ubuntu-latest=catthehacker/ubuntu:act-22.04, got `{}`",
+            s
+        )),
+    }
+}
+
+/// A size in MiB, as bytes
+fn parse_mib(s: &str) -> Result<u64, String> {
+    let mib: u64 = s
+        .parse()
+        .map_err(|_| format!("expected a number of MiB, got `{}`", s))?;
+    mib.checked_mul(1024 * 1024)
+        .ok_or_else(|| format!("{} MiB is too large", mib))
+}
+
+fn parse_github_context(s: &str) -> Result<(String, String), String> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected KEY=VALUE, like run_number=42, got `{}`", s))?;
+    executor::environment::github_context_variable(key)?;
+    Ok((key.to_string(), value.to_string()))
+}
+
+fn parse_report(s: &str) -> Result<(RunReport, PathBuf), String> {
+    let (format, path) = s
+        .split_once(':')
+        .ok_or_else(|| format!("expected FORMAT:PATH, like junit:results.xml, got `{}`", s))?;
+    if path.is_empty() {
+        return Err(format!("no file given for the {} report", format));
+    }
+    let report = match format {
+        "junit" => RunReport::Junit,
+        "json" => RunReport::Json,
+        _ => {
+            return Err(format!(
+                "unknown report format `{}`, use junit or json",
+                format
+            ))
+        }
+    };
+    Ok((report, PathBuf::from(path)))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum NetworkChoice {
+    Open,
+    Offline,
+    AllowList,
+}
+
+/// The project's limits of job containers, with `cpus` and `memory` over
+/// its default ones
+fn job_resources(
+    project: &utils::project::ProjectConfig,
+    cpus: Option<f64>,
+    memory: Option<&str>,
+) -> Result<executor::resources::JobResources, String> {
+    let mut resources = executor::resources::JobResources::from_project(&project.resources)?;
+    let limits = executor::resources::ResourceLimits::parse(cpus, memory)?;
+    resources.default = limits.or(resources.default);
+    Ok(resources)
+}
+
+/// The project's platforms of job containers, with `platform` over its
+/// default one
+fn job_platforms(
+    project: &utils::project::ProjectConfig,
+    platform: Option<&str>,
+) -> Result<executor::platforms::JobPlatforms, String> {
+    executor::platforms::JobPlatforms::from_project(project, platform)
+}
+
+impl NetworkChoice {
+    fn policy(self, allowed_hosts: &[String]) -> Result<executor::docker::NetworkPolicy, String> {
+        use executor::docker::NetworkPolicy;
+        match (self, allowed_hosts.is_empty()) {
+            (NetworkChoice::AllowList, true) => Err(
+                "--network allow-list needs the hosts to allow, given with --allow-host"
+                    .to_string(),
+            ),
+            (NetworkChoice::AllowList, false) => {
+                Ok(NetworkPolicy::AllowList(allowed_hosts.to_vec()))
+            }
+            (_, false) => Err("--allow-host only applies with --network allow-list".to_string()),
+            (NetworkChoice::Open, true) => Ok(NetworkPolicy::Open),
+            (NetworkChoice::Offline, true) => Ok(NetworkPolicy::Offline),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum RuntimeChoice {
+    Docker,
+    Podman,
+    Bubblewrap,
+    Emulation,
+}
+
+impl RuntimeChoice {
+    /// The runtime given by `--emulate` or `--runtime`, or else the
+    /// project's, or else Docker
+    fn resolve(
+        choice: Option<Self>,
+        emulate: bool,
+        project: &utils::project::ProjectConfig,
+    ) -> executor::RuntimeType {
+        let choice = choice.or_else(|| {
+            let name = project.runtime.as_deref()?;
+            RuntimeChoice::from_str(name, true)
+                .map_err(|_| {
+                    logging::warning(&format!(
+                        "Unknown runtime '{}' in .wrkflw.toml, using docker",
+                        name
+                    ))
+                })
+                .ok()
+        });
+        choice
+            .unwrap_or(RuntimeChoice::Docker)
+            .runtime_type(emulate)
+    }
+
+    /// The runtime to use, where `--emulate` is the same as `--runtime emulation`
+    fn runtime_type(self, emulate: bool) -> executor::RuntimeType {
+        match self {
+            _ if emulate => executor::RuntimeType::Emulation,
+            RuntimeChoice::Docker => executor::RuntimeType::Docker,
+            RuntimeChoice::Podman => executor::RuntimeType::Podman,
+            RuntimeChoice::Bubblewrap => executor::RuntimeType::Bubblewrap,
+            RuntimeChoice::Emulation => executor::RuntimeType::Emulation,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Yaml,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum GraphFormat {
+    Ascii,
+    Dot,
+    Mermaid,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ReportFormat {
+    Md,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ConvertTarget {
+    Github,
+    Gitlab,
+}
+
+// Parser function for key-value pairs
+fn parse_key_val(s: &str) -> Result<(String, String), String> {
+    let pos = s
+        .find('=')
+        .ok_or_else(|| format!("invalid KEY=value: no `=` found in `{}`", s))?;
+
+    Ok((s[..pos].to_string(), s[pos + 1..].to_string()))
+}
+
+/// Merge inputs from --inputs-file and --input flags, checking them against
+/// the inputs the workflow declares when the workflow file is available
+/// locally, after asking on the terminal for the ones left out
+fn resolve_trigger_inputs(
+    workflow: &str,
+    input: Option<&[(String, String)]>,
+    inputs_file: &Option<PathBuf>,
+    prompt: bool,
+) -> Result<Option<HashMap<String, String>>, github::GithubError> {
+    let mut inputs = match inputs_file {
+        Some(path) => github::load_inputs_file(path)?,
+        None => HashMap::new(),
+    };
+    inputs.extend(input.unwrap_or_default().iter().cloned());
+
+    match github::find_workflow_file(workflow) {
+        Some(path) => {
+            ask_dispatch_inputs(&path, &mut inputs, prompt)?;
+            github::validate_dispatch_inputs(&path, &inputs)?
+        }
+        None if !inputs.is_empty() => eprintln!(
+            "Warning: workflow file for '{}' not found locally, skipping input validation",
+            workflow
+        ),
+        None => {}
+    }
+
+    Ok((!inputs.is_empty()).then_some(inputs))
+}
+
+/// Ask on the terminal for the declared inputs missing from `inputs`: the
+/// required ones without a default, or with `all` every one. Does nothing
+/// when not run interactively.
+fn ask_dispatch_inputs(
+    workflow_path: &Path,
+    inputs: &mut HashMap<String, String>,
+    all: bool,
+) -> Result<(), github::GithubError> {
+    if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+        return Ok(());
+    }
+    let Some(declared) = github::inputs::dispatch_inputs(workflow_path)? else {
+        return Ok(());
+    };
+    let mut stdin = std::io::stdin().lock();
+    for input in &declared {
+        if !(all || input.needs_value()) || inputs.contains_key(&input.name) {
+            continue;
+        }
+        match ask_dispatch_input(&mut stdin, input) {
+            Some(value) if value.is_empty() => {}
+            Some(value) => {
+                inputs.insert(input.name.clone(), value);
+            }
+            None => break,
+        }
+    }
+    Ok(())
+}
+
+/// Ask for one input until the answer fits its type. An empty answer takes
+/// the default, or leaves out an optional input. None at end of input.
+fn ask_dispatch_input(
+    stdin: &mut impl BufRead,
+    input: &github::inputs::DispatchInput,
+) -> Option<String> {
+    use github::inputs::InputKind;
+
+    match &input.description {
+        Some(description) => eprintln!("{}: {}", input.name, description),
+        None => eprintln!("{}", input.name),
+    }
+    if let InputKind::Choice(options) = &input.kind {
+        for (i, option) in options.iter().enumerate() {
+            eprintln!("  [{}] {}", i + 1, option);
+        }
+    }
+    let hint = match &input.kind {
+        InputKind::Boolean => " (y/n)",
+        InputKind::Number => " (number)",
+        InputKind::Choice(_) => " (number or value)",
+        InputKind::String | InputKind::Environment => "",
+    };
+    let default = match &input.default {
+        Some(default) => format!(" [{}]", default),
+        None if !input.required => " [none]".to_string(),
+        None => String::new(),
+    };
+
+    loop {
+        eprint!("{}{}{}: ", input.name, hint, default);
+        let _ = std::io::stderr().flush();
+        let mut answer = String::new();
+        if stdin.read_line(&mut answer).ok()? == 0 {
+            eprintln!();
+            return None;
+        }
+        let answer = answer.trim();
+        if answer.is_empty() {
+            match &input.default {
+                Some(default) => return Some(default.clone()),
+                None if !input.required => return Some(String::new()),
+                None => continue,
+            }
+        }
+        let value = match &input.kind {
+            InputKind::Boolean => match answer.to_lowercase().as_str() {
+                "y" | "yes" | "true" => "true".to_string(),
+                "n" | "no" | "false" => "false".to_string(),
+                _ => answer.to_string(),
+            },
+            InputKind::Choice(options) => answer
+                .parse::<usize>()
+                .ok()
+                .and_then(|n| options.get(n.checked_sub(1)?))
+                .cloned()
+                .unwrap_or_else(|| answer.to_string()),
+            _ => answer.to_string(),
+        };
+        match input.check(&value) {
+            Ok(()) => return Some(value),
+            Err(e) => eprintln!("  {}", e),
+        }
+    }
+}
+
+/// How dispatching one workflow of a batch went
+enum DispatchOutcome {
+    /// Sent; holds when, to find the run it started
+    Dispatched(String),
+    Skipped(String),
+    Failed(String),
+}
+
+/// Dispatch every workflow matching `pattern`, or all of them, with shared
+/// inputs and print a table of the results. With `watch`, wait for all the
+/// runs together. Returns whether every dispatch and watched run succeeded.
+async fn trigger_batch(
+    pattern: Option<&str>,
+    branch: Option<&str>,
+    input: Option<&[(String, String)]>,
+    inputs_file: &Option<PathBuf>,
+    prompt: bool,
+    watch: bool,
+) -> Result<bool, String> {
+    let targets = github::find_dispatch_targets(Path::new(".github/workflows"), pattern)
+        .map_err(|e| e.to_string())?;
+    if targets.is_empty() {
+        return Err(format!(
+            "no workflow in .github/workflows matches '{}'",
+            pattern.unwrap_or("*")
+        ));
+    }
+
+    let client = github::GithubClient::from_env().map_err(|e| e.to_string())?;
+    let repo_info = github::get_repo_info().map_err(|e| e.to_string())?;
+    let branch = branch.unwrap_or(&repo_info.default_branch).to_string();
+
+    let mut shared_inputs = match inputs_file {
+        Some(path) => github::load_inputs_file(path).map_err(|e| e.to_string())?,
+        None => HashMap::new(),
+    };
+    shared_inputs.extend(input.unwrap_or_default().iter().cloned());
+    // Answers only make sense for one workflow at a time
+    if let [target] = targets.as_slice() {
+        ask_dispatch_inputs(&target.path, &mut shared_inputs, prompt).map_err(|e| e.to_string())?;
+    }
+
+    println!(
+        "Dispatching {} workflow(s) in {}/{} on {}",
+        targets.len(),
+        repo_info.owner,
+        repo_info.repo,
+        branch
+    );
+
+    let mut outcomes = Vec::new();
+    for target in &targets {
+        let outcome = if !target.dispatchable {
+            DispatchOutcome::Skipped("no workflow_dispatch trigger".to_string())
+        } else {
+            match dispatch_target(&client, &repo_info, target, &branch, &shared_inputs).await {
+                Ok(dispatched_at) => DispatchOutcome::Dispatched(dispatched_at),
+                Err(e) => DispatchOutcome::Failed(e.to_string()),
+            }
+        };
+        outcomes.push(outcome);
+    }
+
+    let width = targets.iter().map(|t| t.file_name.len()).max().unwrap_or(0);
+    println!();
+    for (target, outcome) in targets.iter().zip(&outcomes) {
+        let result = match outcome {
+            DispatchOutcome::Dispatched(_) => "✅ dispatched".to_string(),
+            DispatchOutcome::Skipped(reason) => format!("⏭️  skipped: {}", reason),
+            DispatchOutcome::Failed(error) => format!("❌ {}", error),
+        };
+        println!("  {:<width$}  {}", target.file_name, result, width = width);
+    }
+    let mut succeeded = !outcomes
+        .iter()
+        .any(|outcome| matches!(outcome, DispatchOutcome::Failed(_)));
+
+    if !watch {
+        return Ok(succeeded);
+    }
+
+    println!("\nWatching the dispatched runs...");
+    let dispatched: Vec<_> = targets
+        .iter()
+        .zip(&outcomes)
+        .filter_map(|(target, outcome)| match outcome {
+            DispatchOutcome::Dispatched(dispatched_at) => Some((target, dispatched_at)),
+            _ => None,
+        })
+        .collect();
+    // Job and step output of several runs would interleave, so only a
+    // single run is followed in detail
+    let follow = dispatched.len() == 1;
+    let watches = dispatched.into_iter().map(|(target, dispatched_at)| {
+        let (client, repo_info, branch) = (&client, &repo_info, &branch);
+        async move {
+            let run = if follow {
+                match github::find_dispatched_run(
+                    client,
+                    repo_info,
+                    &target.file_name,
+                    branch,
+                    dispatched_at,
+                )
+                .await
+                {
+                    Ok(run) => {
+                        let options = github::runs::WatchOptions::default();
+                        github::runs::watch_run(client, repo_info, run, &options).await
+                    }
+                    Err(e) => Err(e),
+                }
+            } else {
+                github::watch_dispatched_run(
+                    client,
+                    repo_info,
+                    &target.file_name,
+                    branch,
+                    dispatched_at,
+                    std::time::Duration::from_secs(5),
+                )
+                .await
+            };
+            match &run {
+                Ok(run) => println!(
+                    "  {} finished: {} {}",
+                    target.file_name,
+                    run.conclusion.as_deref().unwrap_or("unknown"),
+                    run.html_url
+                ),
+                Err(e) => println!("  {} could not be watched: {}", target.file_name, e),
+            }
+            (target, run)
+        }
+    });
+    let runs = futures::future::join_all(watches).await;
+
+    println!();
+    for (target, run) in &runs {
+        let result = match run {
+            Ok(run) if run.conclusion.as_deref() == Some("success") => "✅ success".to_string(),
+            Ok(run) => format!("❌ {}", run.conclusion.as_deref().unwrap_or("unknown")),
+            Err(e) => format!("❓ {}", e),
+        };
+        println!("  {:<width$}  {}", target.file_name, result, width = width);
+    }
+    succeeded &= runs
+        .iter()
+        .all(|(_, run)| matches!(run, Ok(run) if run.conclusion.as_deref() == Some("success")));
+    Ok(succeeded)
+}
+
+/// Settings of `wrkflw watch-run`
+struct WatchRunArgs<'a> {
+    run: Option<&'a str>,
+    workflow: Option<&'a str>,
+    branch: Option<&'a str>,
+    options: github::runs::WatchOptions,
+}
+
+/// Find the run to watch, by id or URL or as the latest one on a branch,
+/// and follow it. Returns the run's exit status.
+async fn watch_run_command(args: WatchRunArgs<'_>) -> Result<i32, String> {
+    let client = github::GithubClient::from_env().map_err(|e| e.to_string())?;
+    let run_ref = match args.run {
+        Some(run) => Some(
+            github::runs::RunRef::parse(run)
+                .ok_or_else(|| format!("'{}' is neither a run id nor a run URL", run))?,
+        ),
+        None => None,
+    };
+
+    let repo_info = match run_ref.as_ref().and_then(|r| r.repository.clone()) {
+        Some((owner, repo)) => github::RepoInfo {
+            owner,
+            repo,
+            default_branch: String::new(),
+        },
+        None => github::get_repo_info().map_err(|e| e.to_string())?,
+    };
+
+    let run = match run_ref {
+        Some(run_ref) => github::runs::fetch_run(&client, &repo_info, run_ref.id).await,
+        None => {
+            let workflow_file =
+                args.workflow
+                    .map(|workflow| match Path::new(workflow).extension() {
+                        Some(_) => workflow.to_string(),
+                        None => github::find_workflow_file(workflow)
+                            .and_then(|path| Some(path.file_name()?.to_string_lossy().into_owned()))
+                            .unwrap_or_else(|| format!("{}.yml", workflow)),
+                    });
+            let branch = args.branch.unwrap_or(&repo_info.default_branch);
+            github::runs::latest_run(&client, &repo_info, workflow_file.as_deref(), branch).await
+        }
+    }
+    .map_err(|e| e.to_string())?;
+
+    let run = github::runs::watch_run(&client, &repo_info, run, &args.options)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(github::runs::conclusion_exit_code(
+        run.conclusion.as_deref(),
+    ))
+}
+
+/// Send one workflow of a batch the shared inputs it declares. Returns the
+/// time to look for its run from.
+async fn dispatch_target(
+    client: &github::GithubClient,
+    repo_info: &github::RepoInfo,
+    target: &github::DispatchTarget,
+    branch: &str,
+    shared_inputs: &HashMap<String, String>,
+) -> Result<String, github::GithubError> {
+    let inputs = github::declared_inputs(&target.path, shared_inputs)?;
+    github::validate_dispatch_inputs(&target.path, &inputs)?;
+
+    // Leave room for clock skew between this machine and GitHub
+    let dispatched_at = (chrono::Utc::now() - chrono::Duration::seconds(10))
+        .format("%Y-%m-%dT%H:%M:%SZ")
+        .to_string();
+    let inputs = (!inputs.is_empty()).then_some(&inputs);
+    github::dispatch_workflow(client, repo_info, &target.file_name, branch, inputs).await?;
+    Ok(dispatched_at)
+}
+
+/// User settings, or the defaults when the config file can't be read
+fn load_config() -> utils::config::Config {
+    utils::config::Config::load().unwrap_or_else(|e| {
+        logging::warning(&e);
+        utils::config::Config::default()
+    })
+}
+
+/// Settings of the project in the current directory, or the defaults when
+/// it has no .wrkflw.toml or it can't be read
+fn load_project_config() -> utils::project::ProjectConfig {
+    let dir = std::env::current_dir().unwrap_or_default();
+    utils::project::ProjectConfig::load(&dir).unwrap_or_else(|e| {
+        logging::warning(&e);
+        utils::project::ProjectConfig::default()
+    })
+}
+
+/// Ask on the terminal before deploying to protected environments. Without
+/// one, jobs deploying to them fail.
+fn terminal_approvals() -> executor::deployments::Approvals {
+    if !std::io::stdin().is_terminal() {
+        return Default::default();
+    }
+    let (approvals, pending) = executor::deployments::Approvals::new();
+    std::thread::spawn(move || {
+        for deployment in pending {
+            eprint!(
+                "🔒 Job '{}' deploys to '{}'. Approve [y/N]? ",
+                deployment.job, deployment.environment
+            );
+            let _ = std::io::stderr().flush();
+            let mut answer = String::new();
+            let approved = std::io::stdin().lock().read_line(&mut answer).is_ok()
+                && matches!(answer.trim().to_lowercase().as_str(), "y" | "yes");
+            deployment.decide(approved);
+        }
+    });
+    approvals
+}
+
+// Make this function public for testing? Or move to a utils/cleanup mod?
+// Or call executor::cleanup and runtime::cleanup directly?
+// Let's try calling them directly for now.
+async fn cleanup_on_exit() {
+    // Clean up Docker resources if available, but don't let it block indefinitely
+    match tokio::time::timeout(std::time::Duration::from_secs(3), async {
+        // Resources belong to the client of the runtime that made them, which
+        // may be Podman's
+        let client = executor::docker::cleanup_client()
+            .map(Ok)
+            .unwrap_or_else(Docker::connect_with_local_defaults);
+        match client {
+            Ok(docker) => {
+                // Assuming cleanup_resources exists in executor crate
+                executor::cleanup_resources(&docker).await;
+            }
+            Err(_) => {
+                // Docker not available
+                logging::info("Docker not available, skipping Docker cleanup");
+            }
+        }
+    })
+    .await
+    {
+        Ok(_) => logging::debug("Docker cleanup completed successfully"),
+        Err(_) => {
+            logging::warning("Docker cleanup timed out after 3 seconds, continuing with shutdown")
+        }
+    }
+
+    // Always clean up emulation resources
+    match tokio::time::timeout(
+        std::time::Duration::from_secs(2),
+        // Assuming cleanup_resources exists in runtime::emulation module
+        runtime::emulation::cleanup_resources(),
+    )
+    .await
+    {
+        Ok(_) => logging::debug("Emulation cleanup completed successfully"),
+        Err(_) => logging::warning("Emulation cleanup timed out, continuing with shutdown"),
+    }
+
+    logging::info("Resource cleanup completed");
+}
+
+async fn handle_signals() {
+    // Set up a hard exit timer in case cleanup takes too long
+    // This ensures the app always exits even if Docker operations are stuck
+    let hard_exit_time = std::time::Duration::from_secs(10);
+
+    // Wait for Ctrl+C
+    match tokio::signal::ctrl_c().await {
+        Ok(_) => {
+            println!("Received Ctrl+C, shutting down and cleaning up...");
+        }
+        Err(e) => {
+            // Log the error but continue with cleanup
+            eprintln!("Warning: Failed to properly listen for ctrl+c event: {}", e);
+            println!("Shutting down and cleaning up...");
+        }
+    }
+
+    // Set up a watchdog thread that will force exit if cleanup takes too long
+    // This is important because Docker operations can sometimes hang indefinitely
+    let _ = std::thread::spawn(move || {
+        std::thread::sleep(hard_exit_time);
+        eprintln!(
+            "Cleanup taking too long (over {} seconds), forcing exit...",
+            hard_exit_time.as_secs()
+        );
+        logging::error("Forced exit due to cleanup timeout");
+        std::process::exit(EXIT_CANCELLED);
+    });
+
+    // Clean up containers
+    cleanup_on_exit().await;
+
+    // The force exit thread will be terminated automatically
+    std::process::exit(EXIT_CANCELLED);
+}
+
+/// Whether a file is clearly a GitLab CI/CD pipeline, for commands that
+/// only handle GitHub workflows
+fn is_gitlab_pipeline(path: &Path) -> bool {
+    sniff::detect_platform(path).platform() == Some(Platform::GitLab)
+}
+
+/// What to treat a file as: what --github or --gitlab says, what it's
+/// detected as, or what the user picks when it looks like both
+fn resolve_platform(path: &Path, github: bool, gitlab: bool) -> Result<Platform, String> {
+    if github {
+        return Ok(Platform::GitHub);
+    }
+    if gitlab {
+        return Ok(Platform::GitLab);
+    }
+
+    let detection = sniff::detect_platform(path);
+    logging::debug(&format!("{}: {}", path.display(), detection.describe()));
+    if let Some(platform) = detection.platform() {
+        return Ok(platform);
+    }
+
+    let question = format!(
+        "{} looks like both a GitHub workflow ({}) and a GitLab pipeline ({}).",
+        path.display(),
+        detection.github_signals().join(", "),
+        detection.gitlab_signals().join(", ")
+    );
+    if std::io::stdin().is_terminal() && std::io::stderr().is_terminal() {
+        if let Some(platform) = ask_platform(&question) {
+            return Ok(platform);
+        }
+    }
+    Err(format!(
+        "{} Pass --github or --gitlab to say which.",
+        question
+    ))
+}
+
+/// Ask on the terminal until the answer names a platform, None at end of input
+fn ask_platform(question: &str) -> Option<Platform> {
+    let mut stdin = std::io::stdin().lock();
+    loop {
+        eprint!(
+            "{} Treat it as [1] GitHub workflow or [2] GitLab pipeline? ",
+            question
+        );
+        let _ = std::io::stderr().flush();
+        let mut answer = String::new();
+        if stdin.read_line(&mut answer).ok()? == 0 {
+            eprintln!();
+            return None;
+        }
+        match answer.trim().to_lowercase().as_str() {
+            "1" | "github" => return Some(Platform::GitHub),
+            "2" | "gitlab" => return Some(Platform::GitLab),
+            _ => {}
+        }
+    }
+}
+
+/// Ask on the terminal for a template option's value, None for its default
+fn ask_template_option(option: &parser::templates::TemplateOption) -> Option<String> {
+    if option.default.is_empty() {
+        eprint!("{}: ", option.prompt);
+    } else {
+        eprint!("{} [{}]: ", option.prompt, option.default);
+    }
+    let _ = std::io::stderr().flush();
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer).ok()?;
+    let answer = answer.trim();
+    (!answer.is_empty()).then(|| answer.to_string())
+}
+
+#[tokio::main]
+async fn main() {
+    // Answer the completion script, which calls wrkflw with COMPLETE set
+    clap_complete::CompleteEnv::with_factory(Wrkflw::command)
+        .var(completions::ENV_VAR)
+        .complete();
+
+    let cli = Wrkflw::parse();
+    let verbose = cli.verbose;
+    let debug = cli.debug;
+
+    if cli.log_format == LogFormatChoice::Json {
+        logging::set_log_format(logging::LogFormat::Json);
+    }
+
+    // Set log level based on command line flags
+    if debug {
+        logging::set_log_level(logging::LogLevel::Debug);
+        logging::debug("Debug mode enabled - showing detailed logs");
+    } else if verbose {
+        logging::set_log_level(logging::LogLevel::Info);
+        logging::info("Verbose mode enabled");
+    } else {
+        logging::set_log_level(logging::LogLevel::Warning);
+    }
+
+    // Setup a Ctrl+C handler that runs in the background
+    tokio::spawn(handle_signals());
+
+    match &cli.command {
+        Some(Commands::Validate {
+            path,
+            gitlab,
+            github,
+            version_check,
+            no_cache,
+            offline,
+            format,
+            fail_on,
+        }) => {
+            if *no_cache {
+                evaluator::cache::disable();
+            }
+            let project = load_project_config();
+            evaluator::rules::configure(project.rules);
+            evaluator::rules::configure_runner_labels(project.images.into_keys().collect());
+            if *offline {
+                parser::gitlab::work_offline();
+            } else {
+                evaluator::online::enable();
+            }
+
+            // Without a path, the workflows and every other CI config of
+            // the repository are validated
+            let files = validate_targets(path.as_deref()).unwrap_or_else(|e| exit_with_error(e));
+
+            // Platforms are told one file at a time, as an ambiguous file
+            // asks which it is, then the files are validated in parallel
+            let files: Vec<(PathBuf, Platform)> = files
+                .into_iter()
+                .map(|file| {
+                    let platform = resolve_platform(&file, *github, *gitlab)?;
+                    Ok((file, platform))
+                })
+                .collect::<Result<_, String>>()
+                .unwrap_or_else(|e| exit_with_error(e));
+            let failed = if *format != ValidateFormat::Text {
+                // Keep stdout to the report
+                logging::use_stderr();
+                let reports: Vec<_> = files
+                    .par_iter()
+                    .map(|(path, platform)| file_report(path, *platform, *version_check))
+                    .collect();
+                let failed = reports.iter().any(|report| {
+                    fail_on.fails(Findings {
+                        errors: report.issues.len().max(usize::from(!report.valid)),
+                        warnings: report.warnings.len(),
+                    })
+                });
+                let output = match format {
+                    ValidateFormat::Sarif => {
+                        serde_json::to_string_pretty(&evaluator::report::sarif(&reports))
+                    }
+                    _ => serde_json::to_string_pretty(&reports),
+                };
+                println!("{}", output.expect("validation reports serialize"));
+                failed
+            } else {
+                if path.as_ref().is_none_or(|path| path.is_dir()) {
+                    println!("Validating {} workflow file(s)...", files.len());
+                }
+                let results: Vec<(String, Findings)> = files
+                    .par_iter()
+                    .map(|(file, platform)| {
+                        let mut output = String::new();
+                        let findings =
+                            validate_file(file, *platform, verbose, *version_check, &mut output);
+                        (output, findings)
+                    })
+                    .collect();
+                for (output, _) in &results {
+                    print!("{}", output);
+                }
+                let findings: Vec<Findings> =
+                    results.into_iter().map(|(_, findings)| findings).collect();
+                if files.len() > 1 {
+                    print_validation_summary(&files, &findings);
+                }
+                findings.into_iter().any(|findings| fail_on.fails(findings))
+            };
+            if failed {
+                std::process::exit(EXIT_INVALID_WORKFLOW);
+            }
+        }
+        Some(Commands::Run {
+            path,
+            emulate,
+            runtime,
+            show_action_messages,
+            gitlab,
+            github,
+            runner_images,
+            artifact_size_warning,
+            cache_size_warning,
+            jobs,
+            skip_jobs,
+            with_needs,
+            event,
+            event_file,
+            version_check,
+            keep_network,
+            network,
+            allowed_hosts,
+            cpus,
+            memory,
+            container_platform,
+            max_output_lines,
+            no_ci_env,
+            no_sandbox,
+            no_history,
+            tool_cache,
+            bind_workspace,
+            dry_run,
+            profile,
+            interactive,
+            single_step,
+            breakpoints,
+            docker_retries,
+            github_token,
+            github_context,
+            format,
+            reports,
+            ci,
+        }) => {
+            if *ci && cli.log_format == LogFormatChoice::Text {
+                logging::set_log_format(logging::LogFormat::Plain);
+            }
+            if *format != RunFormat::Text {
+                // Keep stdout to the results
+                logging::use_stderr();
+            }
+            if *interactive && !std::io::stdin().is_terminal() {
+                exit_with_error("--interactive needs a terminal to open shells in");
+            }
+            let pausing = *single_step || !breakpoints.is_empty();
+            if pausing && !std::io::stdin().is_terminal() {
+                exit_with_error("--step and --break-at need a terminal to ask on");
+            }
+            let user_config = load_config();
+            let project = load_project_config();
+            let runtime_type = RuntimeChoice::resolve(*runtime, *emulate, &project);
+            let secrets = match &project.secrets_file {
+                Some(file) => executor::environment::read_secrets_file(file)
+                    .unwrap_or_else(|e| exit_with_error(e)),
+                None => HashMap::new(),
+            };
+
+            // Check if we're explicitly or implicitly running a GitLab pipeline
+            let platform =
+                resolve_platform(path, *github, *gitlab).unwrap_or_else(|e| exit_with_error(e));
+
+            logging::info(&format!("Running {} at: {}", platform, path.display()));
+            if *ci && platform == Platform::GitHub {
+                check_workflow_for_ci(path);
+            }
+
+            // Execute the workflow
+            let mut size_limits = executor::artifacts::SizeLimits::default();
+            if let Some(bytes) = artifact_size_warning {
+                size_limits.artifact_bytes = *bytes;
+            }
+            if let Some(bytes) = cache_size_warning {
+                size_limits.cache_bytes = *bytes;
+            }
+            let event = event.as_deref().map(|name| {
+                executor::environment::SimulatedEvent::new(name, event_file.as_deref())
+                    .unwrap_or_else(|e| exit_with_error(e))
+            });
+            let ci_output = ci.then(|| Arc::new(executor::CiObserver::new(std::io::stdout())));
+            let mut progress = match &ci_output {
+                Some(ci_output) => {
+                    executor::ProgressReporter::default().with_observer(ci_output.clone())
+                }
+                None => executor::ProgressReporter::default().with_observer(executor::LogObserver),
+            };
+            if *format == RunFormat::JsonLines {
+                progress =
+                    progress.with_observer(executor::JsonLinesObserver::new(std::io::stdout()));
+            }
+            let mut config = executor::ExecutionConfig {
+                show_action_messages: *show_action_messages,
+                progress,
+                size_limits,
+                job_selection: executor::dependency::JobSelection {
+                    jobs: jobs.clone(),
+                    skip: skip_jobs.clone(),
+                    with_needs: *with_needs,
+                },
+                event,
+                version_check: *version_check,
+                keep_network: *keep_network,
+                network_policy: network
+                    .policy(allowed_hosts)
+                    .unwrap_or_else(|e| exit_with_error(e)),
+                resources: job_resources(&project, *cpus, memory.as_deref())
+                    .unwrap_or_else(|e| exit_with_error(e)),
+                platforms: job_platforms(&project, container_platform.as_deref())
+                    .unwrap_or_else(|e| exit_with_error(e)),
+                ci_env: !*no_ci_env && user_config.ci_env,
+                sandbox: !*no_sandbox,
+                history: !*no_history,
+                log_file: true,
+                tool_cache: *tool_cache || project.tool_cache,
+                bind_workspace: *bind_workspace || project.bind_workspace,
+                debug_shell: *interactive,
+                step_control: if pausing {
+                    let (control, paused_steps) =
+                        executor::stepping::StepControl::new(*single_step, breakpoints.clone());
+                    std::thread::spawn(move || answer_paused_steps(paused_steps));
+                    control
+                } else {
+                    Default::default()
+                },
+                platform: Some(platform),
+                docker_retry: executor::retry::RetryPolicy::with_retries(
+                    docker_retries.unwrap_or(user_config.docker_retries),
+                ),
+                secrets,
+                environments: executor::deployments::from_project(&project.environments)
+                    .unwrap_or_else(|e| exit_with_error(e)),
+                approvals: if *ci {
+                    Default::default()
+                } else {
+                    terminal_approvals()
+                },
+                runner_images: project
+                    .images
+                    .into_iter()
+                    .chain(runner_images.iter().cloned())
+                    .collect(),
+                ..executor::ExecutionConfig::new(runtime_type, verbose)
+            };
+            if let Some(token) = github_token {
+                config
+                    .secrets
+                    .insert("GITHUB_TOKEN".to_string(), token.clone());
+            }
+            config.github_context.extend(github_context.iter().cloned());
+            if *dry_run {
+                let plans = executor::plan_workflow_file(path, &config).unwrap_or_else(|e| {
+                    eprintln!("Error planning workflow: {}", e);
+                    std::process::exit(EXIT_ERROR);
+                });
+                match format {
+                    RunFormat::Json | RunFormat::JsonLines => println!(
+                        "{}",
+                        serde_json::to_string_pretty(&plans).expect("plans serialize")
+                    ),
+                    RunFormat::Text => print_plan(path, &config.runtime_type, &plans),
+                }
+                return;
+            }
+            let result = executor::execute_workflow_with_config(path, &config)
+                .await
+                .unwrap_or_else(|e| {
+                    eprintln!("Error executing workflow: {}", e);
+                    std::process::exit(execution_exit_code(&e))
+                });
+            let max_output_lines = max_output_lines.unwrap_or(user_config.max_output_lines);
+
+            let success = result.failure_details.is_none();
+            let profile = profile.then(|| executor::profile::profile(&result.jobs));
+            let summary = executor::summary::run_summary(&result.jobs);
+            let summary = (!summary.is_empty()).then(|| summary.to_string());
+            let results = || {
+                let mut output = serde_json::json!({
+                    "file": path,
+                    "platform": platform,
+                    "success": success,
+                    "jobs": result.jobs,
+                    "failure_details": result.failure_details,
+                });
+                if let Some(profile) = &profile {
+                    output["profile"] = serde_json::json!(profile);
+                }
+                output
+            };
+            let json = || serde_json::to_string_pretty(&results()).expect("run results serialize");
+            for (report, report_path) in reports {
+                let content = match report {
+                    RunReport::Junit => {
+                        executor::junit::render(&path.display().to_string(), &result)
+                    }
+                    RunReport::Json => json(),
+                };
+                match std::fs::write(report_path, content) {
+                    Ok(()) => logging::info(&format!("Wrote report to {}", report_path.display())),
+                    Err(e) => {
+                        eprintln!("Error writing {}: {}", report_path.display(), e);
+                        std::process::exit(EXIT_ERROR);
+                    }
+                }
+            }
+
+            if let Some(ci_output) = ci_output {
+                ci_output.run_completed(&result);
+                if !success {
+                    std::process::exit(EXIT_JOB_FAILURE);
+                }
+                return;
+            }
+
+            if *format != RunFormat::Text {
+                if *format == RunFormat::JsonLines {
+                    let mut output = results();
+                    output["type"] = "run_completed".into();
+                    println!("{}", output);
+                } else {
+                    println!("{}", json());
+                }
+                if !success {
+                    std::process::exit(EXIT_JOB_FAILURE);
+                }
+                return;
+            }
+
+            // Print execution summary
+            if result.failure_details.is_some() {
+                eprintln!("❌ Workflow execution failed:");
+                if let Some(details) = result.failure_details {
+                    if verbose {
+                        // Show full error details in verbose mode
+                        eprintln!("{}", details);
+                    } else {
+                        // Show simplified error info in non-verbose mode
+                        let simplified_error = details
+                            .lines()
+                            .filter(|line| line.contains("❌") || line.trim().starts_with("Error:"))
+                            .take(max_output_lines)
+                            .collect::<Vec<&str>>()
+                            .join("\n");
+
+                        eprintln!("{}", simplified_error);
+
+                        if details.lines().count() > max_output_lines {
+                            let output_files: Vec<_> = result
+                                .jobs
+                                .iter()
+                                .flat_map(|job| &job.steps)
+                                .filter(|step| step.status == executor::StepStatus::Failure)
+                                .filter_map(|step| step.output_file.as_ref())
+                                .collect();
+                            if output_files.is_empty() {
+                                eprintln!("\nUse --verbose flag to see full error details");
+                            } else {
+                                eprintln!("\nFull output of the failed steps:");
+                                for file in output_files {
+                                    eprintln!("  {}", file.display());
+                                }
+                            }
+                        }
+                    }
+                }
+                print_annotations(&result.jobs);
+                if let Some(summary) = &summary {
+                    print!("\n{}", summary);
+                }
+                if let Some(profile) = &profile {
+                    println!("\n{}", profile);
+                }
+                std::process::exit(EXIT_JOB_FAILURE);
+            } else {
+                println!("✅ Workflow execution completed successfully!");
+
+                // Print a summary of executed jobs
+                if true {
+                    // Always show job summary
+                    println!("\nJob summary:");
+                    for job in &result.jobs {
+                        println!(
+                            "  {} {} ({})",
+                            match job.status {
+                                executor::JobStatus::Success => "✅",
+                                executor::JobStatus::Failure => "❌",
+                                executor::JobStatus::Skipped => "⏭️",
+                            },
+                            job.name,
+                            match job.status {
                                 executor::JobStatus::Success => "success",
                                 executor::JobStatus::Failure => "failure",
                                 executor::JobStatus::Skipped => "skipped",
@@ -381,7 +1846,7 @@ async fn main() {
 
                         // Always show steps, not just in debug mode
                         println!("  Steps:");
-                        for step in job.steps {
+                        for step in &job.steps {
                             let step_status = match step.status {
                                 executor::StepStatus::Success => "✅",
                                 executor::StepStatus::Failure => "❌",
@@ -402,194 +1867,1512 @@ async fn main() {
                                             || line.trim().starts_with("Exit code:")
                                             || line.contains("failed")
                                     })
-                                    .take(3) // Limit to 3 most relevant error lines
+                                    .take(max_output_lines)
                                     .collect::<Vec<&str>>();
 
-                                if !error_lines.is_empty() {
-                                    println!("      Error details:");
-                                    for line in error_lines {
-                                        println!("      {}", line.trim());
-                                    }
+                                if !error_lines.is_empty() {
+                                    println!("      Error details:");
+                                    for line in &error_lines {
+                                        println!("      {}", line.trim());
+                                    }
+
+                                    if step.output.lines().count() > error_lines.len() {
+                                        match &step.output_file {
+                                            Some(file) => {
+                                                println!("      (Full output: {})", file.display())
+                                            }
+                                            None => {
+                                                println!("      (Use --verbose for full output)")
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                print_annotations(&result.jobs);
+                if let Some(summary) = &summary {
+                    print!("\n{}", summary);
+                }
+                if let Some(profile) = &profile {
+                    println!("\n{}", profile);
+                }
+            }
+
+            // Cleanup is handled automatically via the signal handler
+        }
+        Some(Commands::TriggerGitlab { branch, variable }) => {
+            // Convert optional Vec<(String, String)> to Option<HashMap<String, String>>
+            let variables = variable
+                .as_ref()
+                .map(|v| v.iter().cloned().collect::<HashMap<String, String>>());
+
+            // Trigger the pipeline
+            if let Err(e) = gitlab::trigger_pipeline(branch.as_deref(), variables).await {
+                eprintln!("Error triggering GitLab pipeline: {}", e);
+                std::process::exit(EXIT_ERROR);
+            }
+        }
+        Some(Commands::Tui {
+            path,
+            emulate,
+            runtime,
+            show_action_messages,
+            record,
+            single_step,
+            breakpoints,
+        }) => {
+            let project = load_project_config();
+            evaluator::rules::configure(project.rules.clone());
+            evaluator::rules::configure_runner_labels(project.images.keys().cloned().collect());
+            let runtime_type = RuntimeChoice::resolve(*runtime, *emulate, &project);
+
+            // Call the TUI implementation from the ui crate
+            if let Err(e) = ui::run_wrkflw_tui(
+                path.as_ref(),
+                runtime_type,
+                verbose,
+                *show_action_messages,
+                record.as_deref(),
+                (*single_step, breakpoints.clone()),
+            )
+            .await
+            {
+                eprintln!("Error running TUI: {}", e);
+                std::process::exit(EXIT_ERROR);
+            }
+        }
+        Some(Commands::Trigger {
+            workflow,
+            all,
+            branch,
+            input,
+            inputs_file,
+            prompt,
+            watch,
+        }) => {
+            // Patterns, --all and --watch dispatch through the batch path
+            let is_pattern = workflow
+                .as_deref()
+                .is_some_and(|w| w.contains(['*', '?', '[']));
+            if *all || *watch || is_pattern {
+                let pattern = workflow.as_deref().filter(|_| !*all);
+                match trigger_batch(
+                    pattern,
+                    branch.as_deref(),
+                    input.as_deref(),
+                    inputs_file,
+                    *prompt,
+                    *watch,
+                )
+                .await
+                {
+                    Ok(true) => {}
+                    Ok(false) => std::process::exit(EXIT_JOB_FAILURE),
+                    Err(e) => exit_with_error(e),
+                }
+                return;
+            }
+
+            // Fail before asking for any inputs
+            if std::env::var("GITHUB_TOKEN").is_err() {
+                eprintln!(
+                    "Error triggering GitHub workflow: {}",
+                    github::GithubError::TokenNotFound
+                );
+                std::process::exit(EXIT_ERROR);
+            }
+            let workflow = workflow.as_deref().unwrap_or_default();
+            let inputs =
+                match resolve_trigger_inputs(workflow, input.as_deref(), inputs_file, *prompt) {
+                    Ok(inputs) => inputs,
+                    Err(e) => exit_with_error(e),
+                };
+
+            // Trigger the workflow
+            if let Err(e) = github::trigger_workflow(workflow, branch.as_deref(), inputs).await {
+                eprintln!("Error triggering GitHub workflow: {}", e);
+                std::process::exit(EXIT_ERROR);
+            }
+        }
+        Some(Commands::WatchRun {
+            run,
+            workflow,
+            branch,
+            interval,
+            tail,
+            logs_dir,
+        }) => {
+            let args = WatchRunArgs {
+                run: run.as_deref(),
+                workflow: workflow.as_deref(),
+                branch: branch.as_deref(),
+                options: github::runs::WatchOptions {
+                    interval: std::time::Duration::from_secs((*interval).max(1)),
+                    tail: *tail,
+                    logs_dir: logs_dir.clone(),
+                },
+            };
+            match watch_run_command(args).await {
+                Ok(code) => std::process::exit(code),
+                Err(e) => exit_with_error(e),
+            }
+        }
+        Some(Commands::List) => {
+            commands::list::handle(verbose);
+        }
+        Some(Commands::Replay { file }) => {
+            if let Err(e) = ui::run_replay_tui(file) {
+                eprintln!("Error replaying {}: {}", file.display(), e);
+                std::process::exit(EXIT_ERROR);
+            }
+        }
+        Some(Commands::Artifacts { command, dir }) => {
+            let root = dir
+                .clone()
+                .unwrap_or_else(executor::artifacts::artifacts_root);
+            if let Err(e) = commands::artifacts::handle(command, &root) {
+                exit_with_error(e);
+            }
+        }
+        Some(Commands::Actions { command }) => {
+            if let Err(e) = commands::actions::handle(command) {
+                exit_with_error(e);
+            }
+        }
+        Some(Commands::History { command, dir }) => {
+            let root = dir.clone().unwrap_or_else(executor::history::history_root);
+            if let Err(e) = commands::history::handle(command, &root) {
+                exit_with_error(e);
+            }
+        }
+        Some(Commands::Cache { command, runtime }) => {
+            let project = load_project_config();
+            let runtime_type = RuntimeChoice::resolve(*runtime, false, &project);
+            if let Err(e) = commands::cache::handle(command, &runtime_type).await {
+                exit_with_error(e);
+            }
+        }
+        Some(Commands::Config { command }) => match command {
+            ConfigCommand::Schema { user } => {
+                let schema = if *user {
+                    utils::config::schema()
+                } else {
+                    utils::project::schema()
+                };
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&schema).expect("schema serializes")
+                );
+            }
+        },
+        Some(Commands::Expand {
+            path,
+            format,
+            expand_matrix,
+            gitlab,
+            github,
+        }) => {
+            if !path.exists() {
+                eprintln!("Error: Path does not exist: {}", path.display());
+                std::process::exit(EXIT_ERROR);
+            }
+
+            let options = parser::expand::ExpandOptions {
+                expand_matrix: *expand_matrix,
+            };
+            let platform =
+                resolve_platform(path, *github, *gitlab).unwrap_or_else(|e| exit_with_error(e));
+            let expanded = match platform {
+                Platform::GitLab => parser::expand::expand_pipeline(path, options),
+                Platform::GitHub => parser::expand::expand_workflow(path, options),
+                other => Err(format!(
+                    "wrkflw can validate {}s but not expand them",
+                    other
+                )),
+            };
+
+            let expanded = expanded.unwrap_or_else(|e| {
+                eprintln!("Error expanding {}: {}", path.display(), e);
+                std::process::exit(EXIT_ERROR);
+            });
+
+            let output = match format {
+                OutputFormat::Yaml => serde_yaml::to_string(&expanded).map_err(|e| e.to_string()),
+                OutputFormat::Json => {
+                    serde_json::to_string_pretty(&expanded).map_err(|e| e.to_string())
+                }
+            };
+
+            match output {
+                Ok(output) => println!("{}", output.trim_end()),
+                Err(e) => {
+                    eprintln!("Error serializing expanded output: {}", e);
+                    std::process::exit(EXIT_ERROR);
+                }
+            }
+        }
+        Some(Commands::Exec {
+            job,
+            path,
+            runtime,
+            runner_images,
+            event,
+            event_file,
+        }) => {
+            if !std::io::stdin().is_terminal() {
+                eprintln!("Error: wrkflw exec needs a terminal to open the shell in");
+                std::process::exit(EXIT_ERROR);
+            }
+            let path = match path {
+                Some(path) => path.clone(),
+                None => workflow_with_job(Path::new(".github/workflows"), job)
+                    .unwrap_or_else(|e| exit_with_error(e)),
+            };
+            let user_config = load_config();
+            let project = load_project_config();
+            let runtime_type = RuntimeChoice::resolve(*runtime, false, &project);
+            let secrets = match &project.secrets_file {
+                Some(file) => executor::environment::read_secrets_file(file)
+                    .unwrap_or_else(|e| exit_with_error(e)),
+                None => HashMap::new(),
+            };
+            let event = event.as_deref().map(|name| {
+                executor::environment::SimulatedEvent::new(name, event_file.as_deref())
+                    .unwrap_or_else(|e| exit_with_error(e))
+            });
+            let config = executor::ExecutionConfig {
+                event,
+                ci_env: user_config.ci_env,
+                tool_cache: project.tool_cache,
+                bind_workspace: project.bind_workspace,
+                resources: job_resources(&project, None, None)
+                    .unwrap_or_else(|e| exit_with_error(e)),
+                platforms: job_platforms(&project, None).unwrap_or_else(|e| exit_with_error(e)),
+                docker_retry: executor::retry::RetryPolicy::with_retries(
+                    user_config.docker_retries,
+                ),
+                secrets,
+                environments: executor::deployments::from_project(&project.environments)
+                    .unwrap_or_else(|e| exit_with_error(e)),
+                approvals: terminal_approvals(),
+                runner_images: project
+                    .images
+                    .into_iter()
+                    .chain(runner_images.iter().cloned())
+                    .collect(),
+                ..executor::ExecutionConfig::new(runtime_type, verbose)
+            };
+            if let Err(e) = executor::open_job_shell(&path, job, &config).await {
+                eprintln!("Error opening a shell for job '{}': {}", job, e);
+                std::process::exit(EXIT_ERROR);
+            }
+        }
+        Some(Commands::Graph {
+            path,
+            format,
+            event,
+            event_file,
+            gitlab,
+            github,
+        }) => {
+            if !path.exists() {
+                eprintln!("Error: Path does not exist: {}", path.display());
+                std::process::exit(EXIT_ERROR);
+            }
+
+            let event = event.as_ref().map(|event| {
+                executor::environment::SimulatedEvent::new(event, event_file.as_deref())
+                    .unwrap_or_else(|e| exit_with_error(e))
+            });
+            let platform =
+                resolve_platform(path, *github, *gitlab).unwrap_or_else(|e| exit_with_error(e));
+            let config = executor::ExecutionConfig {
+                event,
+                platform: Some(platform),
+                ..executor::ExecutionConfig::new(executor::RuntimeType::Emulation, false)
+            };
+            let graph = executor::graph::graph_file(path, &config).unwrap_or_else(|e| {
+                eprintln!("Error graphing {}: {}", path.display(), e);
+                std::process::exit(EXIT_ERROR);
+            });
+
+            let output = match format {
+                GraphFormat::Ascii => graph.to_text(),
+                GraphFormat::Dot => graph.to_dot(),
+                GraphFormat::Mermaid => graph.to_mermaid(),
+            };
+            println!("{}", output.trim_end());
+        }
+        Some(Commands::ScaffoldTests {
+            results,
+            output,
+            force,
+        }) => {
+            let expectations = std::fs::read_to_string(results)
+                .map_err(|e| format!("Error reading {}: {}", results.display(), e))
+                .and_then(|json| executor::expectations::RunExpectations::from_results_json(&json))
+                .unwrap_or_else(|e| {
+                    eprintln!("{}", e);
+                    std::process::exit(EXIT_ERROR);
+                });
+            // ci.yml gets ci.test.yml, outside the workflows directory so it
+            // isn't taken for a workflow
+            let output = output.clone().unwrap_or_else(|| {
+                let stem = expectations
+                    .workflow
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string())
+                    .unwrap_or_else(|| "workflow".to_string());
+                PathBuf::from(format!("{}.test.yml", stem))
+            });
+            if output.exists() && !*force {
+                eprintln!(
+                    "Error: {} already exists, use --force to overwrite it",
+                    output.display()
+                );
+                std::process::exit(EXIT_ERROR);
+            }
+
+            let written = expectations
+                .to_yaml()
+                .and_then(|yaml| std::fs::write(&output, yaml).map_err(|e| e.to_string()));
+            match written {
+                Ok(()) => println!("Wrote {}", output.display()),
+                Err(e) => {
+                    eprintln!("Error writing {}: {}", output.display(), e);
+                    std::process::exit(EXIT_ERROR);
+                }
+            }
+        }
+        Some(Commands::Triggers {
+            path,
+            event,
+            event_file,
+            branch,
+            tag,
+            action,
+            files,
+            diff_base,
+        }) => {
+            let path = path
+                .clone()
+                .unwrap_or_else(|| PathBuf::from(".github/workflows"));
+            let simulated =
+                executor::environment::SimulatedEvent::new(event, event_file.as_deref())
+                    .unwrap_or_else(|e| exit_with_error(e));
+
+            let mut trigger =
+                parser::triggers::TriggerEvent::from_payload(event, &simulated.payload);
+            if action.is_some() {
+                trigger.action = action.clone();
+            }
+            if let Some(tag) = tag {
+                trigger.tag = Some(tag.clone());
+                trigger.branch = None;
+            } else if let Some(branch) = branch {
+                trigger.branch = Some(branch.clone());
+            } else if trigger.branch.is_none() && trigger.tag.is_none() {
+                trigger.branch = current_branch();
+            }
+            trigger.changed_files = files
+                .clone()
+                .or_else(|| changed_files(diff_base.as_deref()));
 
-                                    if step.output.lines().count() > 3 {
-                                        println!("      (Use --verbose for full output)");
-                                    }
-                                }
-                            }
+            if let Err(e) = explain_triggers(&path, &trigger, simulated, verbose) {
+                eprintln!("Error checking triggers of {}: {}", path.display(), e);
+                std::process::exit(EXIT_ERROR);
+            }
+        }
+        Some(Commands::Fmt { path, check }) => {
+            let path = path
+                .clone()
+                .unwrap_or_else(|| PathBuf::from(".github/workflows"));
+            if !path.exists() {
+                eprintln!("Error: Path does not exist: {}", path.display());
+                std::process::exit(EXIT_ERROR);
+            }
+            let files = if path.is_dir() {
+                workflow_files(&path).unwrap_or_else(|e| exit_with_error(e))
+            } else {
+                vec![path]
+            };
+
+            let mut unformatted = 0;
+            let mut failed = false;
+            for file in &files {
+                let formatted = std::fs::read_to_string(file)
+                    .map_err(|e| e.to_string())
+                    .and_then(|content| {
+                        // GitLab jobs are ordered by their keys' meaning, not
+                        // by convention, so only workflows get theirs sorted
+                        let order_keys =
+                            sniff::detect_platform(file).platform() == Some(Platform::GitHub);
+                        let formatted = parser::format::format_workflow(&content, order_keys)?;
+                        Ok((formatted != content).then_some(formatted))
+                    });
+                match formatted {
+                    Ok(None) => {}
+                    Ok(Some(_)) if *check => {
+                        println!("Would reformat {}", file.display());
+                        unformatted += 1;
+                    }
+                    Ok(Some(formatted)) => match std::fs::write(file, formatted) {
+                        Ok(()) => {
+                            println!("Formatted {}", file.display());
+                            unformatted += 1;
+                        }
+                        Err(e) => {
+                            eprintln!("Error writing {}: {}", file.display(), e);
+                            failed = true;
                         }
+                    },
+                    Err(e) => {
+                        eprintln!("Error formatting {}: {}", file.display(), e);
+                        failed = true;
                     }
                 }
             }
 
-            // Cleanup is handled automatically via the signal handler
+            if *check {
+                println!("{} of {} file(s) need formatting", unformatted, files.len());
+            } else {
+                println!("Formatted {} of {} file(s)", unformatted, files.len());
+            }
+            if failed {
+                std::process::exit(EXIT_ERROR);
+            }
+            if *check && unformatted > 0 {
+                std::process::exit(EXIT_CHECK_FAILED);
+            }
         }
-        Some(Commands::TriggerGitlab { branch, variable }) => {
-            // Convert optional Vec<(String, String)> to Option<HashMap<String, String>>
-            let variables = variable
-                .as_ref()
-                .map(|v| v.iter().cloned().collect::<HashMap<String, String>>());
+        Some(Commands::Init {
+            template,
+            values,
+            yes,
+            gitlab,
+            output,
+            force,
+        }) => {
+            let templates_dir = parser::templates::templates_dir();
+            let templates = parser::templates::load_templates(templates_dir.as_deref())
+                .unwrap_or_else(|e| exit_with_error(e));
+            let Some(name) = template else {
+                println!("Templates:");
+                for template in &templates {
+                    println!("  {:<16} {}", template.name, template.description);
+                }
+                if let Some(dir) = &templates_dir {
+                    println!("\nAdd your own as YAML files in {}", dir.display());
+                }
+                return;
+            };
+            let Some(template) = templates.iter().find(|template| &template.name == name) else {
+                let names: Vec<&str> = templates.iter().map(|t| t.name.as_str()).collect();
+                eprintln!(
+                    "Error: No template named '{}', pick one of {}",
+                    name,
+                    names.join(", ")
+                );
+                std::process::exit(EXIT_ERROR);
+            };
 
-            // Trigger the pipeline
-            if let Err(e) = gitlab::trigger_pipeline(branch.as_deref(), variables).await {
-                eprintln!("Error triggering GitLab pipeline: {}", e);
-                std::process::exit(1);
+            let mut chosen = HashMap::new();
+            for value in values {
+                let Some((option, value)) = value.split_once('=') else {
+                    eprintln!("Error: --set takes OPTION=VALUE, got '{}'", value);
+                    std::process::exit(EXIT_ERROR);
+                };
+                if template.option(option).is_none() {
+                    eprintln!(
+                        "Error: Template '{}' has no option '{}'",
+                        template.name, option
+                    );
+                    std::process::exit(EXIT_ERROR);
+                }
+                chosen.insert(option.to_string(), value.to_string());
+            }
+            if !*yes && std::io::stdin().is_terminal() {
+                for option in &template.options {
+                    if !chosen.contains_key(&option.name) {
+                        if let Some(value) = ask_template_option(option) {
+                            chosen.insert(option.name.clone(), value);
+                        }
+                    }
+                }
+            }
+            let mut content = template.render(&chosen);
+
+            let to_gitlab = *gitlab && template.platform() == Platform::GitHub;
+            if to_gitlab {
+                let conversion = parser::templates::to_pipeline(&content).and_then(|conversion| {
+                    for note in &conversion.notes {
+                        eprintln!("⚠️  {}", note);
+                    }
+                    conversion.to_yaml()
+                });
+                content = conversion.unwrap_or_else(|e| {
+                    eprintln!("Error converting the workflow: {}", e);
+                    std::process::exit(EXIT_ERROR);
+                });
+            }
+            let output = match output {
+                Some(output) => output.clone(),
+                None if to_gitlab => PathBuf::from(".gitlab-ci.yml"),
+                None => template.file.clone(),
+            };
+            if output.exists() && !*force {
+                eprintln!(
+                    "Error: {} already exists, use --force to overwrite it",
+                    output.display()
+                );
+                std::process::exit(EXIT_ERROR);
+            }
+            let written = match output.parent() {
+                Some(dir) if !dir.as_os_str().is_empty() => std::fs::create_dir_all(dir),
+                _ => Ok(()),
             }
+            .and_then(|()| std::fs::write(&output, content));
+            if let Err(e) = written {
+                eprintln!("Error writing {}: {}", output.display(), e);
+                std::process::exit(EXIT_ERROR);
+            }
+            println!("Wrote {}", output.display());
+
+            let platform = if to_gitlab {
+                Platform::GitLab
+            } else {
+                template.platform()
+            };
+            let mut report = String::new();
+            validate_file(&output, platform, verbose, false, &mut report);
+            print!("{}", report);
         }
-        Some(Commands::Tui {
-            path,
-            emulate,
-            show_action_messages: _,
-        }) => {
-            // Set runtime type based on the emulate flag
-            let runtime_type = if *emulate {
-                executor::RuntimeType::Emulation
+        Some(Commands::Outdated { path, pin_shas }) => {
+            let path = path
+                .clone()
+                .unwrap_or_else(|| PathBuf::from(".github/workflows"));
+            if !path.exists() {
+                eprintln!("Error: Path does not exist: {}", path.display());
+                std::process::exit(EXIT_ERROR);
+            }
+            let mut files = if path.is_dir() {
+                workflow_files(&path).unwrap_or_else(|e| exit_with_error(e))
             } else {
-                executor::RuntimeType::Docker
+                vec![path]
             };
-
-            // Call the TUI implementation from the ui crate
-            if let Err(e) = ui::run_wrkflw_tui(path.as_ref(), runtime_type, verbose).await {
-                eprintln!("Error running TUI: {}", e);
-                std::process::exit(1);
+            files.sort();
+            if let Err(e) = outdated_actions(&files, *pin_shas).await {
+                exit_with_error(e);
             }
         }
-        Some(Commands::Trigger {
-            workflow,
-            branch,
-            input,
-        }) => {
-            // Convert optional Vec<(String, String)> to Option<HashMap<String, String>>
-            let inputs = input
-                .as_ref()
-                .map(|i| i.iter().cloned().collect::<HashMap<String, String>>());
+        Some(Commands::Convert { path, to, output }) => {
+            let conversion = match to {
+                ConvertTarget::Gitlab => parser::convert::convert_workflow(path),
+                ConvertTarget::Github => parser::convert::convert_pipeline(path),
+            };
+            let yaml = conversion.and_then(|conversion| {
+                for note in &conversion.notes {
+                    eprintln!("⚠️  {}", note);
+                }
+                conversion.to_yaml()
+            });
+            let yaml = match yaml {
+                Ok(yaml) => yaml,
+                Err(e) => {
+                    eprintln!("Error converting {}: {}", path.display(), e);
+                    std::process::exit(EXIT_ERROR);
+                }
+            };
+            match output {
+                Some(output) => match std::fs::write(output, yaml) {
+                    Ok(()) => println!("Wrote {}", output.display()),
+                    Err(e) => {
+                        eprintln!("Error writing {}: {}", output.display(), e);
+                        std::process::exit(EXIT_ERROR);
+                    }
+                },
+                None => print!("{}", yaml),
+            }
+        }
+        Some(Commands::AuditSecurity { path, format }) => {
+            let path = path
+                .clone()
+                .unwrap_or_else(|| PathBuf::from(".github/workflows"));
 
-            // Trigger the workflow
-            if let Err(e) = github::trigger_workflow(workflow, branch.as_deref(), inputs).await {
-                eprintln!("Error triggering GitHub workflow: {}", e);
-                std::process::exit(1);
+            match audit_security(&path) {
+                Ok(report) => match format {
+                    ReportFormat::Md => print!("{}", report.to_markdown()),
+                    ReportFormat::Json => match serde_json::to_string_pretty(&report) {
+                        Ok(json) => println!("{}", json),
+                        Err(e) => {
+                            eprintln!("Error serializing report: {}", e);
+                            std::process::exit(EXIT_ERROR);
+                        }
+                    },
+                },
+                Err(e) => {
+                    eprintln!("Error auditing {}: {}", path.display(), e);
+                    std::process::exit(EXIT_ERROR);
+                }
             }
         }
-        Some(Commands::List) => {
-            list_workflows_and_pipelines(verbose);
+        Some(Commands::Completions { shell }) => {
+            if let Err(e) = completions::write_script(*shell, &mut std::io::stdout()) {
+                exit_with_error(e);
+            }
         }
         None => {
             // Launch TUI by default when no command is provided
             let runtime_type = executor::RuntimeType::Docker;
 
             // Call the TUI implementation from the ui crate with default path
-            if let Err(e) = ui::run_wrkflw_tui(None, runtime_type, verbose).await {
+            if let Err(e) =
+                ui::run_wrkflw_tui(None, runtime_type, verbose, false, None, Default::default())
+                    .await
+            {
                 eprintln!("Error running TUI: {}", e);
-                std::process::exit(1);
+                std::process::exit(EXIT_ERROR);
             }
         }
     }
 }
 
-/// Validate a GitHub workflow file
-fn validate_github_workflow(path: &Path, verbose: bool) {
-    print!("Validating GitHub workflow file: {}... ", path.display());
+/// Exit codes telling why a command failed
+const EXIT_JOB_FAILURE: i32 = 1;
+/// A check failing, like `fmt --check` finding files to format
+const EXIT_CHECK_FAILED: i32 = 1;
+const EXIT_INVALID_WORKFLOW: i32 = 2;
+/// Anything else going wrong, like Docker being unreachable
+const EXIT_ERROR: i32 = 3;
+/// Stopped with Ctrl+C, as shells report a process killed by SIGINT
+const EXIT_CANCELLED: i32 = 130;
+
+/// Print an error of a command that isn't about a workflow or its jobs,
+/// and exit with `EXIT_ERROR`
+fn exit_with_error(error: impl std::fmt::Display) -> ! {
+    eprintln!("Error: {}", error);
+    std::process::exit(EXIT_ERROR);
+}
 
-    // Use the ui crate's validate_workflow function
-    match ui::validate_workflow(path, verbose) {
-        Ok(_) => {
-            // The detailed validation output is already printed by the function
+/// Exit code of `run` for an error that kept the workflow from running
+fn execution_exit_code(error: &executor::ExecutionError) -> i32 {
+    match error {
+        executor::ExecutionError::Parse(_) => EXIT_INVALID_WORKFLOW,
+        _ => EXIT_ERROR,
+    }
+}
+
+/// The files `validate` checks: `path`, the validatable files in it when
+/// it's a directory, or the repository's workflows and CI configs
+fn validate_targets(path: Option<&Path>) -> Result<Vec<PathBuf>, String> {
+    let files = match path {
+        Some(path) if !path.exists() => {
+            return Err(format!("Path does not exist: {}", path.display()))
+        }
+        Some(path) if path.is_dir() => validation_files(path),
+        Some(path) => vec![path.to_path_buf()],
+        None => repository_ci_files(),
+    };
+    if files.is_empty() && path.is_none() {
+        return Err(format!(
+            "No workflows or CI configs found in .github/workflows, {}",
+            CI_CONFIG_FILES.join(", ")
+        ));
+    }
+    Ok(files)
+}
+
+/// Exit before running a GitHub workflow that doesn't validate, listing
+/// its issues
+fn check_workflow_for_ci(path: &Path) {
+    match evaluator::evaluate_workflow_file(path, false) {
+        Ok(result) if result.is_valid => {}
+        Ok(result) => {
+            for issue in result.issues {
+                logging::error(&format!("Invalid workflow: {}", issue));
+            }
+            std::process::exit(EXIT_INVALID_WORKFLOW);
         }
         Err(e) => {
-            eprintln!("Error validating workflow: {}", e);
+            logging::error(&format!("Invalid workflow: {}", e));
+            std::process::exit(EXIT_INVALID_WORKFLOW);
         }
     }
 }
 
-/// Validate a GitLab CI/CD pipeline file
-fn validate_gitlab_pipeline(path: &Path, verbose: bool) {
-    print!("Validating GitLab CI pipeline file: {}... ", path.display());
+/// Validate a GitHub workflow file
+fn validate_github_workflow(
+    path: &Path,
+    verbose: bool,
+    version_check: bool,
+    output: &mut String,
+) -> Findings {
+    output.push_str(&format!(
+        "Validating GitHub workflow file: {}... ",
+        path.display()
+    ));
+
+    let mut findings = match ui::write_workflow_validation(path, verbose, output) {
+        Ok(result) => Findings::of(&result),
+        Err(_) => Findings::ERROR,
+    };
+
+    if version_check {
+        for warning in version_warnings(path) {
+            output.push_str(&format!("⚠️  {}\n", warning.message));
+            findings.warnings += 1;
+        }
+    }
+    findings
+}
+
+/// Actions in a workflow that Docker or emulation mode can't run as they
+/// are; nothing for invalid workflows, which are reported otherwise
+fn version_warnings(path: &Path) -> Vec<models::Diagnostic> {
+    let Ok(workflow) = parser::workflow::parse_workflow(path) else {
+        return Vec::new();
+    };
+    [
+        executor::RuntimeType::Docker,
+        executor::RuntimeType::Emulation,
+    ]
+    .iter()
+    .flat_map(|runtime_type| {
+        let runner = executor::requirements::RunnerCapabilities::for_runtime(runtime_type);
+        executor::requirements::check_workflow(&workflow, &runner)
+    })
+    .collect()
+}
+
+/// Print the actions `files` use next to their latest versions, and with
+/// `pin` point their tags and branches at commits
+async fn outdated_actions(files: &[PathBuf], pin: bool) -> Result<(), String> {
+    use github::action_refs::RefResolution;
+    use github::outdated::{self, ActionUse};
+    use std::collections::BTreeMap;
+
+    let mut contents = Vec::new();
+    for file in files {
+        let content = std::fs::read_to_string(file)
+            .map_err(|e| format!("Failed to read {}: {}", file.display(), e))?;
+        let uses = outdated::find_action_uses(&content);
+        contents.push((file, content, uses));
+    }
+
+    let token = std::env::var("GITHUB_TOKEN")
+        .ok()
+        .filter(|token| !token.is_empty());
+    let client = github::GithubClient::new(token.as_deref()).map_err(|e| e.to_string())?;
+    let looked_up = |e: github::GithubError| {
+        let rate_limited = matches!(
+            e,
+            github::GithubError::RateLimited(_) | github::GithubError::ApiError { status: 403, .. }
+        );
+        format!(
+            "Could not look actions up on GitHub: {}{}",
+            e,
+            if rate_limited && token.is_none() {
+                " (set GITHUB_TOKEN to raise the rate limit)"
+            } else {
+                ""
+            }
+        )
+    };
+
+    // One row per action and version, listing the files using it
+    let mut rows: BTreeMap<(String, String), (&ActionUse, Vec<String>)> = BTreeMap::new();
+    for (file, _, uses) in &contents {
+        for action in uses {
+            let (_, used_in) = rows
+                .entry((action.name.clone(), action.current_version().to_string()))
+                .or_insert_with(|| (action, Vec::new()));
+            let file = file.display().to_string();
+            if !used_in.contains(&file) {
+                used_in.push(file);
+            }
+        }
+    }
+    let mut latest: HashMap<(String, String), Option<String>> = HashMap::new();
+    for (action, _) in rows.values() {
+        let repo = (action.action.owner.clone(), action.action.repo.clone());
+        if let std::collections::hash_map::Entry::Vacant(entry) = latest.entry(repo) {
+            let (owner, repo) = entry.key();
+            let version = outdated::latest_version(&client, owner, repo)
+                .await
+                .map_err(looked_up)?;
+            entry.insert(version);
+        }
+    }
 
-    // Parse and validate the pipeline file
-    match parser::gitlab::parse_pipeline(path) {
-        Ok(pipeline) => {
-            println!("✅ Valid syntax");
+    if rows.is_empty() {
+        println!("No actions from GitHub repositories are used");
+    } else {
+        let table: Vec<[String; 5]> = rows
+            .iter()
+            .map(|((name, current), (action, used_in))| {
+                let latest = latest
+                    .get(&(action.action.owner.clone(), action.action.repo.clone()))
+                    .cloned()
+                    .flatten();
+                let status = match latest
+                    .as_deref()
+                    .and_then(|latest| outdated::is_outdated(current, latest))
+                {
+                    Some(true) => "outdated",
+                    Some(false) => "up to date",
+                    None => "unknown",
+                };
+                [
+                    name.clone(),
+                    current.clone(),
+                    latest.unwrap_or_else(|| "-".to_string()),
+                    status.to_string(),
+                    used_in.join(", "),
+                ]
+            })
+            .collect();
+        let header = ["Action", "Current", "Latest", "Status", "Used in"];
+        let widths: Vec<usize> = (0..header.len())
+            .map(|i| {
+                table
+                    .iter()
+                    .map(|row| row[i].chars().count())
+                    .chain([header[i].len()])
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        for row in std::iter::once(header.map(String::from)).chain(table.iter().cloned()) {
+            let line: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect();
+            println!("{}", line.join("  ").trim_end());
+        }
+        let outdated = table.iter().filter(|row| row[3] == "outdated").count();
+        println!("\n{} of {} action versions outdated", outdated, table.len());
+    }
 
-            // Additional structural validation
-            let validation_result = validators::validate_gitlab_pipeline(&pipeline);
+    if !pin {
+        return Ok(());
+    }
+    let mut shas = HashMap::new();
+    for (action, _) in rows.values() {
+        let action = &action.action;
+        if action.is_pinned() || shas.contains_key(&action.key()) {
+            continue;
+        }
+        match github::action_refs::resolve(&client, action)
+            .await
+            .map_err(looked_up)?
+        {
+            RefResolution::Resolved { sha, .. } => {
+                shas.insert(action.key(), sha);
+            }
+            RefResolution::UnknownRef => {
+                eprintln!(
+                    "⚠️  {} has no such tag or branch, not pinning it",
+                    action.key()
+                )
+            }
+            RefResolution::MissingRepository => eprintln!(
+                "⚠️  {}/{} wasn't found, not pinning it",
+                action.owner, action.repo
+            ),
+        }
+    }
+    for (file, content, uses) in &contents {
+        let pinned = outdated::pin_shas(content, &shas);
+        if pinned == *content {
+            continue;
+        }
+        std::fs::write(file, pinned)
+            .map_err(|e| format!("Failed to write {}: {}", file.display(), e))?;
+        let count = uses
+            .iter()
+            .filter(|action| shas.contains_key(&action.action.key()))
+            .count();
+        println!("Pinned {} action(s) in {}", count, file.display());
+    }
+    Ok(())
+}
 
-            if !validation_result.is_valid {
-                println!("⚠️  Validation issues:");
-                for issue in validation_result.issues {
-                    println!("   - {}", issue);
+/// Ask on the terminal what to do with each step a run paused before, until
+/// the run is over
+/// List the errors, warnings and notices the run's steps printed
+fn print_annotations(jobs: &[executor::JobResult]) {
+    use executor::actions::AnnotationLevel;
+
+    let mut printed_header = false;
+    for job in jobs {
+        for step in &job.steps {
+            for annotation in &step.annotations {
+                if !printed_header {
+                    println!("\nAnnotations:");
+                    printed_header = true;
                 }
-            } else if verbose {
-                println!("✅ All validation checks passed");
+                let symbol = match annotation.level {
+                    AnnotationLevel::Error => "❌",
+                    AnnotationLevel::Warning => "⚠️",
+                    AnnotationLevel::Notice => "ℹ️",
+                };
+                println!("  {} {} › {}: {}", symbol, job.name, step.name, annotation);
             }
         }
-        Err(e) => {
-            println!("❌ Invalid");
-            eprintln!("Validation failed: {}", e);
+    }
+}
+
+fn answer_paused_steps(paused_steps: std::sync::mpsc::Receiver<executor::stepping::PausedStep>) {
+    use executor::stepping::StepDecision;
+    for paused in paused_steps {
+        let decision = loop {
+            eprint!(
+                "⏸  Paused before step '{}' of job '{}'. Continue [c/Enter], skip the step [s] or abort the run [a]? ",
+                paused.step, paused.job
+            );
+            let _ = std::io::stderr().flush();
+            let mut answer = String::new();
+            match std::io::stdin().lock().read_line(&mut answer) {
+                // Without anyone to ask, the run goes on
+                Ok(0) | Err(_) => break StepDecision::Run,
+                Ok(_) => {}
+            }
+            match answer.trim().to_lowercase().as_str() {
+                "" | "c" | "continue" => break StepDecision::Run,
+                "s" | "skip" => break StepDecision::Skip,
+                "a" | "abort" => break StepDecision::Abort,
+                _ => eprintln!("Answer c, s or a"),
+            }
+        };
+        paused.decide(decision);
+    }
+}
+
+/// The GitHub workflow in `dir` that has a job named `job`, refusing to
+/// guess between several
+fn workflow_with_job(dir: &Path, job: &str) -> Result<PathBuf, String> {
+    if !dir.is_dir() {
+        return Err(format!(
+            "{} doesn't exist; name the workflow file with the job",
+            dir.display()
+        ));
+    }
+    let mut matches: Vec<PathBuf> = workflow_files(dir)?
+        .into_iter()
+        .filter(|path| {
+            parser::workflow::parse_workflow(path)
+                .is_ok_and(|workflow| workflow.jobs.contains_key(job))
+        })
+        .collect();
+    matches.sort();
+    match matches.len() {
+        0 => Err(format!(
+            "No workflow in {} has a job '{}'",
+            dir.display(),
+            job
+        )),
+        1 => Ok(matches.remove(0)),
+        _ => Err(format!(
+            "Several workflows have a job '{}' ({}); name the file to use",
+            job,
+            matches
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// CI configs of other platforms `validate` looks for at the repository root
+const CI_CONFIG_FILES: &[&str] = &[
+    ".gitlab-ci.yml",
+    ".circleci/config.yml",
+    "azure-pipelines.yml",
+];
+
+/// The workflows in .github/workflows and the CI configs of other platforms
+/// the repository has
+fn repository_ci_files() -> Vec<PathBuf> {
+    let workflows = Path::new(".github/workflows");
+    let mut files = if workflows.is_dir() {
+        workflow_files(workflows).unwrap_or_else(|e| exit_with_error(e))
+    } else {
+        Vec::new()
+    };
+    files.extend(
+        CI_CONFIG_FILES
+            .iter()
+            .map(PathBuf::from)
+            .filter(|path| path.is_file()),
+    );
+    files
+}
+
+/// The YAML files directly inside `dir`
+fn workflow_files(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    Ok(std::fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|ext| ext == "yml" || ext == "yaml")
+        })
+        .collect())
+}
+
+/// The workflow file at `path`, or the YAML files directly inside it when
+/// it's a directory, sorted
+fn workflows_at(path: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut workflows = if path.is_dir() {
+        workflow_files(path)?
+    } else if path.is_file() {
+        vec![path.to_path_buf()]
+    } else {
+        return Err("Path does not exist".to_string());
+    };
+    workflows.sort();
+    Ok(workflows)
+}
+
+/// Directories `validate` doesn't look into: version control, build output
+/// and dependencies, which hold YAML files of their own
+const SKIPPED_DIRS: &[&str] = &[".git", ".wrkflw", "node_modules", "target"];
+
+/// The YAML files directly in `dir`, and those in its subdirectories that
+/// look like CI configs, such as `.gitlab/ci/*.yml`, sorted
+fn validation_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_entry(|entry| {
+            entry.depth() == 0
+                || !entry.file_type().is_dir()
+                || !SKIPPED_DIRS.contains(&entry.file_name().to_string_lossy().as_ref())
+        })
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            entry.file_type().is_file()
+                && entry
+                    .path()
+                    .extension()
+                    .is_some_and(|ext| ext == "yml" || ext == "yaml")
+                && (entry.depth() == 1 || sniff::detect_platform(entry.path()).has_signals())
+        })
+        .map(walkdir::DirEntry::into_path)
+        .collect();
+    files.sort();
+    files
+}
+
+/// Validate a file for `validate --format json` or `--format sarif`
+fn file_report(
+    path: &Path,
+    platform: Platform,
+    version_check: bool,
+) -> evaluator::report::FileReport {
+    let result = evaluator::evaluate_file(path, platform, false);
+    let extra_warnings = if version_check && platform == Platform::GitHub {
+        version_warnings(path)
+    } else {
+        Vec::new()
+    };
+    evaluator::report::FileReport::new(path, platform, result, extra_warnings)
+}
+
+/// Validate a file as what it is, writing what's wrong with it to `output`
+fn validate_file(
+    path: &Path,
+    platform: Platform,
+    verbose: bool,
+    version_check: bool,
+    output: &mut String,
+) -> Findings {
+    match platform {
+        Platform::GitHub => validate_github_workflow(path, verbose, version_check, output),
+        Platform::GitLab => validate_gitlab_pipeline(path, verbose, output),
+        Platform::CircleCi | Platform::Azure | Platform::Action => {
+            output.push_str(&format!("Validating {}: {}... ", platform, path.display()));
+            match evaluator::evaluate_file(path, platform, verbose) {
+                Ok(result) => {
+                    output.push_str("✅ Valid syntax\n");
+                    write_validation_result(&result, verbose, output);
+                    Findings::of(&result)
+                }
+                Err(e) => {
+                    output.push_str(&format!("❌ Invalid\nValidation failed: {}\n", e));
+                    Findings::ERROR
+                }
+            }
         }
     }
 }
 
-/// List available workflows and pipelines in the repository
-fn list_workflows_and_pipelines(verbose: bool) {
-    // Check for GitHub workflows
-    let github_path = PathBuf::from(".github/workflows");
-    if github_path.exists() && github_path.is_dir() {
-        println!("GitHub Workflows:");
+/// How many errors and warnings validating a file found
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Findings {
+    errors: usize,
+    warnings: usize,
+}
 
-        let entries = std::fs::read_dir(&github_path)
-            .expect("Failed to read directory")
-            .filter_map(|entry| entry.ok())
-            .filter(|entry| {
-                entry.path().is_file()
-                    && entry
-                        .path()
-                        .extension()
-                        .is_some_and(|ext| ext == "yml" || ext == "yaml")
-            })
-            .collect::<Vec<_>>();
+impl Findings {
+    /// A file that couldn't be parsed or validated at all
+    const ERROR: Findings = Findings {
+        errors: 1,
+        warnings: 0,
+    };
+
+    fn of(result: &models::ValidationResult) -> Self {
+        Findings {
+            errors: result.issues.len().max(usize::from(!result.is_valid)),
+            warnings: result.warnings.len(),
+        }
+    }
 
-        if entries.is_empty() {
-            println!("  No workflow files found in .github/workflows");
+    fn severity(self) -> Option<models::DiagnosticSeverity> {
+        if self.errors > 0 {
+            Some(models::DiagnosticSeverity::Error)
+        } else if self.warnings > 0 {
+            Some(models::DiagnosticSeverity::Warning)
         } else {
-            for entry in entries {
-                println!("  - {}", entry.path().display());
-            }
+            None
         }
-    } else {
-        println!("GitHub Workflows: No .github/workflows directory found");
     }
+}
 
-    // Check for GitLab CI pipeline
-    let gitlab_path = PathBuf::from(".gitlab-ci.yml");
-    if gitlab_path.exists() && gitlab_path.is_file() {
-        println!("GitLab CI Pipeline:");
-        println!("  - {}", gitlab_path.display());
-    } else {
-        println!("GitLab CI Pipeline: No .gitlab-ci.yml file found");
+/// A line per file with what validating it found, then the totals
+fn print_validation_summary(files: &[(PathBuf, Platform)], findings: &[Findings]) {
+    let plural = |count: usize, noun: &str| {
+        format!("{} {}{}", count, noun, if count == 1 { "" } else { "s" })
+    };
+    let width = files
+        .iter()
+        .map(|(file, _)| file.display().to_string().chars().count())
+        .max()
+        .unwrap_or(0);
+    println!("\nSummary:");
+    for ((file, _), findings) in files.iter().zip(findings) {
+        let result = match (findings.errors, findings.warnings) {
+            (0, 0) => "✅ valid".to_string(),
+            (0, warnings) => format!("⚠️  {}", plural(warnings, "warning")),
+            (errors, 0) => format!("❌ {}", plural(errors, "error")),
+            (errors, warnings) => format!(
+                "❌ {}, {}",
+                plural(errors, "error"),
+                plural(warnings, "warning")
+            ),
+        };
+        println!(
+            "  {:<width$}  {}",
+            file.display().to_string(),
+            result,
+            width = width
+        );
+    }
+    let invalid = findings.iter().filter(|f| f.errors > 0).count();
+    let with_warnings = findings
+        .iter()
+        .filter(|f| f.errors == 0 && f.warnings > 0)
+        .count();
+    println!(
+        "{} valid ({} with warnings), {} invalid",
+        findings.len() - invalid,
+        with_warnings,
+        invalid
+    );
+}
+
+/// Write the issues and warnings of a file whose syntax is valid
+fn write_validation_result(result: &models::ValidationResult, verbose: bool, output: &mut String) {
+    if !result.is_valid {
+        output.push_str("⚠️  Validation issues:\n");
+        for issue in &result.issues {
+            output.push_str(&format!("   - {}\n", issue));
+        }
+    } else if verbose {
+        output.push_str("✅ All validation checks passed\n");
+    }
+    for warning in &result.warnings {
+        output.push_str(&format!("   ⚠️  {}\n", warning));
+    }
+}
+
+/// Validate a GitLab CI/CD pipeline file
+fn validate_gitlab_pipeline(path: &Path, verbose: bool, output: &mut String) -> Findings {
+    output.push_str(&format!(
+        "Validating GitLab CI pipeline file: {}... ",
+        path.display()
+    ));
+
+    match evaluator::evaluate_file(path, Platform::GitLab, verbose) {
+        Ok(validation_result) => {
+            output.push_str("✅ Valid syntax\n");
+            write_validation_result(&validation_result, verbose, output);
+            Findings::of(&validation_result)
+        }
+        Err(e) => {
+            output.push_str(&format!("❌ Invalid\nValidation failed: {}\n", e));
+            Findings::ERROR
+        }
+    }
+}
+
+/// Print which workflows `trigger` starts and which of their jobs would run
+fn explain_triggers(
+    path: &Path,
+    trigger: &parser::triggers::TriggerEvent,
+    mut event: executor::environment::SimulatedEvent,
+    verbose: bool,
+) -> Result<(), String> {
+    let mut workflows = workflows_at(path)?;
+    if path.is_dir() {
+        workflows.retain(|p| !is_gitlab_pipeline(p));
     }
 
-    // Check for other GitLab CI pipeline files
+    // Job conditions see the same ref as the trigger filters
+    if let Some(payload) = event.payload.as_object_mut() {
+        if let Some(tag) = &trigger.tag {
+            payload
+                .entry("ref")
+                .or_insert_with(|| format!("refs/tags/{}", tag).into());
+        } else if let Some(branch) = &trigger.branch {
+            if !trigger.name.starts_with("pull_request") {
+                payload
+                    .entry("ref")
+                    .or_insert_with(|| format!("refs/heads/{}", branch).into());
+            }
+        }
+        if let Some(action) = &trigger.action {
+            payload.insert("action".to_string(), action.clone().into());
+        }
+    }
+
+    let target = match (&trigger.tag, &trigger.branch) {
+        (Some(tag), _) => format!(" to tag '{}'", tag),
+        (None, Some(branch)) => format!(" on branch '{}'", branch),
+        _ => String::new(),
+    };
+    let files = match &trigger.changed_files {
+        Some(files) => format!(" with {} changed file(s)", files.len()),
+        None => " (changed files unknown, path filters not checked)".to_string(),
+    };
+    println!("Event: {}{}{}", trigger.name, target, files);
     if verbose {
-        println!("Searching for other GitLab CI pipeline files...");
-
-        let entries = walkdir::WalkDir::new(".")
-            .follow_links(true)
-            .into_iter()
-            .filter_map(|entry| entry.ok())
-            .filter(|entry| {
-                entry.path().is_file()
-                    && entry
-                        .file_name()
-                        .to_string_lossy()
-                        .ends_with("gitlab-ci.yml")
-                    && entry.path() != gitlab_path
-            })
-            .collect::<Vec<_>>();
+        for file in trigger.changed_files.iter().flatten() {
+            println!("  {}", file);
+        }
+    }
+
+    let config = executor::ExecutionConfig {
+        event: Some(event),
+        ..executor::ExecutionConfig::new(executor::RuntimeType::Emulation, verbose)
+    };
+    for workflow_path in workflows {
+        let workflow = parser::workflow::parse_workflow(&workflow_path)
+            .map_err(|e| format!("{}: {}", workflow_path.display(), e))?;
+        println!();
+        match parser::triggers::check_trigger(&workflow.on, trigger) {
+            parser::triggers::TriggerDecision::Skipped(reason) => {
+                println!("⏭  {}: not triggered, {}", workflow_path.display(), reason);
+            }
+            parser::triggers::TriggerDecision::Runs => {
+                println!("✅ {}: triggered", workflow_path.display());
+                let jobs = executor::preview_jobs(&workflow, &config)
+                    .map_err(|e| format!("{}: {}", workflow_path.display(), e))?;
+                for job in jobs {
+                    match job.skip_reason {
+                        None => println!("   ✅ {}", job.name),
+                        Some(reason) => println!("   ⏭  {}: {}", job.name, reason),
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
 
-        if !entries.is_empty() {
-            println!("Additional GitLab CI Pipeline files:");
-            for entry in entries {
-                println!("  - {}", entry.path().display());
+/// Print a dry run's plan: jobs by batch with their steps, then what the
+/// run would download
+fn print_plan(path: &Path, runtime_type: &executor::RuntimeType, plans: &[executor::JobPlan]) {
+    println!(
+        "Plan for {} ({} mode), nothing was run:",
+        path.display(),
+        runtime_type.mode_name()
+    );
+    let mut batch = 0;
+    for job in plans {
+        if job.batch != batch {
+            batch = job.batch;
+            println!("\nBatch {}:", batch);
+        }
+        match &job.skip_reason {
+            Some(reason) => {
+                println!("  ⏭  {}: {}", job.name, reason);
+                continue;
+            }
+            None => println!("  ✅ {}", job.name),
+        }
+        for step in &job.steps {
+            let uses = match (&step.uses, &step.run) {
+                (Some(uses), _) => format!(" ({})", uses),
+                (None, Some(run)) => format!(" (run: {})", run),
+                (None, None) => String::new(),
+            };
+            match &step.skip_reason {
+                None => println!("     ▶ {}{}", step.name, uses),
+                Some(reason) => println!("     ⏭  {}{}: {}", step.name, uses, reason),
             }
         }
     }
+
+    let mut images: Vec<&String> = plans.iter().flat_map(|job| &job.images).collect();
+    images.sort();
+    images.dedup();
+    if !images.is_empty() {
+        println!("\nImages:");
+        for image in images {
+            println!("  {}", image);
+        }
+    }
+    let network: Vec<String> = plans
+        .iter()
+        .flat_map(|job| &job.steps)
+        .filter_map(|step| step.network.clone())
+        .collect::<std::collections::BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    if !network.is_empty() {
+        println!("\nNetwork access:");
+        for reason in network {
+            println!("  {}", reason);
+        }
+    }
+}
+
+fn current_branch() -> Option<String> {
+    let output = std::process::Command::new("git")
+        .args(["symbolic-ref", "--short", "HEAD"])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Files changed since `base`, or since the upstream branch (or the last
+/// commit) when not given, including uncommitted changes
+fn changed_files(base: Option<&str>) -> Option<Vec<String>> {
+    let git = |args: &[&str]| {
+        let output = std::process::Command::new("git").args(args).output().ok()?;
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).to_string())
+    };
+    let base = match base {
+        Some(base) => base.to_string(),
+        None => git(&["rev-parse", "--verify", "--quiet", "@{upstream}"])
+            .map(|rev| rev.trim().to_string())
+            .unwrap_or_else(|| "HEAD~1".to_string()),
+    };
+    let diff = git(&["diff", "--name-only", &base])?;
+    Some(diff.lines().map(str::to_string).collect())
+}
+
+/// Run the security checks over a workflow file or every workflow in a directory
+fn audit_security(path: &Path) -> Result<validators::SecurityReport, String> {
+    let workflows = workflows_at(path)?;
+
+    let mut files = Vec::new();
+    let mut findings = Vec::new();
+    for workflow_path in workflows {
+        // The checks are about GitHub Actions
+        if is_gitlab_pipeline(&workflow_path) {
+            eprintln!("Skipping GitLab pipeline {}", workflow_path.display());
+            continue;
+        }
+
+        let content = std::fs::read_to_string(&workflow_path)
+            .map_err(|e| format!("Failed to read {}: {}", workflow_path.display(), e))?;
+        let workflow: serde_yaml::Value = serde_yaml::from_str(&content)
+            .map_err(|e| format!("Invalid YAML in {}: {}", workflow_path.display(), e))?;
+
+        let file = workflow_path.display().to_string();
+        findings.extend(validators::audit_workflow(&workflow, &file));
+        files.push(file);
+    }
+
+    Ok(validators::SecurityReport::new(files, findings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fails_on_errors_and_optionally_warnings() {
+        let findings = |errors, warnings| Findings { errors, warnings };
+        assert!(FailOn::Error.fails(findings(1, 0)));
+        assert!(FailOn::Warning.fails(findings(1, 2)));
+        assert!(!FailOn::Error.fails(findings(0, 2)));
+        assert!(FailOn::Warning.fails(findings(0, 2)));
+        assert!(!FailOn::Error.fails(findings(0, 0)));
+        assert!(!FailOn::Warning.fails(findings(0, 0)));
+        assert!(FailOn::Error.fails(Findings::ERROR));
+    }
+
+    #[test]
+    fn maps_errors_to_exit_codes() {
+        use executor::ExecutionError;
+        assert_eq!(
+            execution_exit_code(&ExecutionError::Parse("bad yaml".into())),
+            EXIT_INVALID_WORKFLOW
+        );
+        assert_eq!(
+            execution_exit_code(&ExecutionError::Runtime("no docker".into())),
+            EXIT_ERROR
+        );
+        assert_eq!(
+            execution_exit_code(&ExecutionError::Execution("failed".into())),
+            EXIT_ERROR
+        );
+        assert_eq!(
+            [
+                EXIT_JOB_FAILURE,
+                EXIT_INVALID_WORKFLOW,
+                EXIT_ERROR,
+                EXIT_CANCELLED
+            ],
+            [1, 2, 3, 130]
+        );
+    }
+
+    #[test]
+    fn reports_command_errors_instead_of_exiting() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing.yml");
+        let error = validate_targets(Some(&missing)).unwrap_err();
+        assert!(error.contains("Path does not exist"), "{}", error);
+        let workflow = dir.path().join("ci.yml");
+        std::fs::write(&workflow, "on: push\njobs: {}\n").unwrap();
+        assert_eq!(validate_targets(Some(&workflow)).unwrap(), [workflow]);
+
+        let project = utils::project::ProjectConfig::default();
+        assert!(job_resources(&project, None, Some("lots")).is_err());
+        assert!(job_resources(&project, Some(2.0), Some("7g")).is_ok());
+        assert!(job_platforms(&project, Some("linux/arm64")).is_ok());
+        assert!(job_platforms(&project, Some("arm64")).is_err());
+        assert_eq!(
+            resolve_platform(&missing, false, true).unwrap(),
+            Platform::GitLab
+        );
+        assert_eq!(parse_mib("512"), Ok(512 * 1024 * 1024));
+        assert!(parse_mib("-1").is_err());
+        assert!(parse_mib(&u64::MAX.to_string()).is_err());
+    }
 }
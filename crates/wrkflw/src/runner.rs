@@ -0,0 +1,319 @@
+// Running workflows and pipelines from other programs, without the CLI or
+// the TUI
+use executor::environment::SimulatedEvent;
+use executor::{ExecutionConfig, ExecutionEvent, ExecutionObserver, JobResult, JobStatus};
+use parser::sniff::Platform;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+pub use executor::engine::ExecutionError;
+pub use executor::RuntimeType;
+
+/// A closure given to `Runner::on_progress`
+struct Callback<F>(F);
+
+impl<F: Fn(&ExecutionEvent) + Send + Sync> ExecutionObserver for Callback<F> {
+    fn on_event(&self, event: &ExecutionEvent) {
+        (self.0)(event)
+    }
+}
+
+/// Runs workflow files with settings given builder-style:
+///
+/// ```no_run
+/// # async fn example() -> Result<(), wrkflw_lib::runner::ExecutionError> {
+/// use wrkflw_lib::{RuntimeType, Runner};
+///
+/// let report = Runner::new(RuntimeType::Emulation)
+///     .secret("NPM_TOKEN", "test-token")
+///     .env("RUST_LOG", "debug")
+///     .event("pull_request", serde_json::json!({ "number": 1 }))
+///     .on_progress(|event| println!("{:?}", event))
+///     .run(".github/workflows/ci.yml")
+///     .await?;
+/// assert!(report.success());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Runner {
+    config: ExecutionConfig,
+    event_file: Option<(String, PathBuf)>,
+}
+
+impl Runner {
+    pub fn new(runtime_type: RuntimeType) -> Self {
+        Self::from_config(ExecutionConfig::new(runtime_type, false))
+    }
+
+    /// A runner with every execution setting, for the ones the builder
+    /// methods don't cover
+    pub fn from_config(config: ExecutionConfig) -> Self {
+        Runner {
+            config,
+            event_file: None,
+        }
+    }
+
+    pub fn runtime(mut self, runtime_type: RuntimeType) -> Self {
+        self.config.runtime_type = runtime_type;
+        self
+    }
+
+    pub fn verbose(mut self, verbose: bool) -> Self {
+        self.config.verbose = verbose;
+        self
+    }
+
+    /// Add a value of the `secrets` context
+    pub fn secret(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.config.secrets.insert(name.into(), value.into());
+        self
+    }
+
+    pub fn secrets(mut self, secrets: impl IntoIterator<Item = (String, String)>) -> Self {
+        self.config.secrets.extend(secrets);
+        self
+    }
+
+    /// Set a variable in every job, over the ones wrkflw sets
+    pub fn env(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.config.env.insert(name.into(), value.into());
+        self
+    }
+
+    pub fn envs(mut self, env: impl IntoIterator<Item = (String, String)>) -> Self {
+        self.config.env.extend(env);
+        self
+    }
+
+    /// Set a value of the `github` context, like `run_number`, as
+    /// `--github-context` does. Unknown keys fail the run.
+    pub fn github_context(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.config.github_context.insert(key.into(), value.into());
+        self
+    }
+
+    /// Run as `name` with a webhook payload, like `--event` and
+    /// `--event-file`
+    pub fn event(mut self, name: impl Into<String>, payload: serde_json::Value) -> Self {
+        self.config.event = Some(SimulatedEvent {
+            name: name.into(),
+            payload,
+        });
+        self.event_file = None;
+        self
+    }
+
+    /// Run as `name` with the payload in a JSON file, read when the run
+    /// starts
+    pub fn event_file(mut self, name: impl Into<String>, path: impl Into<PathBuf>) -> Self {
+        self.event_file = Some((name.into(), path.into()));
+        self
+    }
+
+    /// Run only these jobs; the others are reported as skipped
+    pub fn jobs(mut self, jobs: impl IntoIterator<Item = String>) -> Self {
+        self.config.job_selection.jobs = jobs.into_iter().collect();
+        self
+    }
+
+    /// Run files as this kind of workflow instead of detecting it
+    pub fn platform(mut self, platform: Platform) -> Self {
+        self.config.platform = Some(platform);
+        self
+    }
+
+    /// Record runs in the history store, as `wrkflw run` does
+    pub fn history(mut self, history: bool) -> Self {
+        self.config.history = history;
+        self
+    }
+
+    /// Log "Would execute GitHub action" for actions that are only emulated
+    pub fn show_action_messages(mut self, show: bool) -> Self {
+        self.config.show_action_messages = show;
+        self
+    }
+
+    /// Call `sink` with the runs' log lines instead of printing them
+    pub fn log_sink(
+        mut self,
+        sink: impl Fn(logging::LogLevel, &str) + Send + Sync + 'static,
+    ) -> Self {
+        self.config.log_sink = Some(logging::LogSink::new(sink));
+        self
+    }
+
+    /// Call `callback` with each job and step event, as the run goes
+    pub fn on_progress(self, callback: impl Fn(&ExecutionEvent) + Send + Sync + 'static) -> Self {
+        self.observer(Callback(callback))
+    }
+
+    /// Pass every event to `observer`, along with the ones added before
+    pub fn observer(mut self, observer: impl ExecutionObserver + 'static) -> Self {
+        self.config.progress = self.config.progress.with_observer(observer);
+        self
+    }
+
+    /// Settings runs get, after the builder methods
+    pub fn config(&self) -> &ExecutionConfig {
+        &self.config
+    }
+
+    /// Run a GitHub workflow or GitLab pipeline. Failing jobs are part of
+    /// the report; errors are for files that can't be run at all.
+    pub async fn run(&self, path: impl AsRef<Path>) -> Result<RunReport, ExecutionError> {
+        let path = path.as_ref();
+        let mut config = self.config.clone();
+        if let Some((name, file)) = &self.event_file {
+            config.event =
+                Some(SimulatedEvent::new(name, Some(file)).map_err(ExecutionError::Execution)?);
+        }
+
+        let result = executor::execute_workflow_with_config(path, &config).await?;
+        Ok(RunReport {
+            file: path.to_path_buf(),
+            jobs: result.jobs,
+            failure_details: result.failure_details,
+        })
+    }
+}
+
+/// Results of a run, job by job
+#[derive(Debug, Clone, Serialize)]
+pub struct RunReport {
+    pub file: PathBuf,
+    pub jobs: Vec<JobResult>,
+    /// What failed, when something did
+    pub failure_details: Option<String>,
+}
+
+impl RunReport {
+    pub fn success(&self) -> bool {
+        self.failure_details.is_none()
+    }
+
+    pub fn job(&self, name: &str) -> Option<&JobResult> {
+        self.jobs.iter().find(|job| job.name == name)
+    }
+
+    /// Outputs of the jobs that set any, by job
+    pub fn outputs(&self) -> HashMap<&str, &HashMap<String, String>> {
+        self.jobs
+            .iter()
+            .filter(|job| job.status != JobStatus::Skipped && !job.outputs.is_empty())
+            .map(|job| (job.name.as_str(), &job.outputs))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[tokio::test]
+    async fn runs_a_workflow_with_env_secrets_and_progress() {
+        let dir = tempfile::tempdir().unwrap();
+        let workflow = dir.path().join("ci.yml");
+        std::fs::write(
+            &workflow,
+            r#"
+on: push
+jobs:
+  check:
+    runs-on: ubuntu-latest
+    outputs:
+      greeting: ${{ steps.greet.outputs.greeting }}
+    steps:
+      - id: greet
+        run: |
+          test "$GREETING" = hello
+          test "${{ secrets.TOKEN }}" = s3cret
+          test "${{ github.event_name }}" = workflow_dispatch
+          echo "greeting=$GREETING" >> "$GITHUB_OUTPUT"
+  skipped:
+    runs-on: ubuntu-latest
+    steps:
+      - run: exit 1
+"#,
+        )
+        .unwrap();
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let seen = events.clone();
+        let runner = Runner::new(RuntimeType::Emulation)
+            .env("GREETING", "hello")
+            .secret("TOKEN", "s3cret")
+            .event("workflow_dispatch", serde_json::json!({}))
+            .jobs(["check".to_string()])
+            .on_progress(move |event| seen.lock().unwrap().push(event.clone()));
+        let report = runner.run(&workflow).await.unwrap();
+
+        assert!(report.success(), "{:?}", report.failure_details);
+        assert_eq!(report.job("skipped").unwrap().status, JobStatus::Skipped);
+        assert_eq!(report.outputs()["check"]["greeting"], "hello");
+        let events = events.lock().unwrap().clone();
+        assert!(matches!(events[0], ExecutionEvent::Planned { .. }));
+        assert!(events.iter().any(
+            |event| matches!(event, ExecutionEvent::StepStarted { job, .. } if job == "check")
+        ));
+        assert!(events.iter().any(|event| matches!(
+            event,
+            ExecutionEvent::JobCompleted { job, status: JobStatus::Success } if job == "check"
+        )));
+
+        let missing = runner
+            .clone()
+            .event_file("push", dir.path().join("missing.json"))
+            .run(&workflow)
+            .await;
+        assert!(missing.unwrap_err().to_string().contains("missing.json"));
+    }
+
+    #[tokio::test]
+    async fn logs_action_messages_to_the_sink_when_asked() {
+        let dir = tempfile::tempdir().unwrap();
+        let workflow = dir.path().join("ci.yml");
+        std::fs::write(
+            &workflow,
+            r#"
+on: push
+jobs:
+  check:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: some-owner/some-action@v1
+"#,
+        )
+        .unwrap();
+
+        let workflow = &workflow;
+        let logged = |show: bool| async move {
+            let lines = Arc::new(Mutex::new(Vec::new()));
+            let seen = lines.clone();
+            Runner::new(RuntimeType::Emulation)
+                .show_action_messages(show)
+                .log_sink(move |_, line| seen.lock().unwrap().push(line.to_string()))
+                .run(workflow)
+                .await
+                .unwrap();
+            let lines = lines.lock().unwrap().clone();
+            lines
+        };
+
+        // Not the emulated step's own `echo 'Would execute ...'`
+        let action_message = |line: &String| {
+            line.trim_start()
+                .starts_with("⚙️ Would execute GitHub action")
+        };
+        let shown = logged(true).await;
+        assert!(shown.iter().any(|line| line.contains("Executing workflow")));
+        assert!(shown.iter().any(action_message));
+        let hidden = logged(false).await;
+        assert!(!hidden.is_empty());
+        assert!(!hidden.iter().any(action_message));
+    }
+}